@@ -0,0 +1,35 @@
+//! 启用 `grpc` feature 时生成 gRPC 控制面的服务代码
+//!
+//! 服务用 tonic-build 的 manual 接口在 Rust 中定义，消息类型手写在 `src/grpc.rs`，构建不依赖 protoc。
+//! `proto/ark.proto` 是对外的等价定义（供其他语言生成客户端），修改接口时两边同步。
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    grpc::compile();
+}
+
+#[cfg(feature = "grpc")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, MethodBuilder, Service};
+
+    fn method(name: &str, route_name: &str, input: &str, output: &str) -> MethodBuilder {
+        Method::builder()
+            .name(name)
+            .route_name(route_name)
+            .input_type(format!("crate::grpc::pb::{}", input))
+            .output_type(format!("crate::grpc::pb::{}", output))
+            .codec_path("tonic_prost::ProstCodec")
+    }
+
+    pub fn compile() {
+        let service = Service::builder()
+            .name("ArkControl")
+            .package("ark.v1")
+            .method(method("list_processes", "ListProcesses", "ListProcessesRequest", "ListProcessesResponse").build())
+            .method(method("why_process", "WhyProcess", "WhyProcessRequest", "WhyProcessResponse").build())
+            .method(method("subscribe", "Subscribe", "SubscribeRequest", "StreamItem").server_streaming().build())
+            .method(method("execute_action", "ExecuteAction", "ExecuteActionRequest", "ExecuteActionResponse").build())
+            .build();
+        Builder::new().build_client(false).compile(&[service]);
+    }
+}
@@ -0,0 +1,84 @@
+//! 破窗审批校验
+//!
+//! 高危操作（zap、节点隔离、大范围集群修复）需要第二位运维通过 Hub 签发的短时审批 token。
+//! 破坏性动作无论来自本地请求还是 Hub 下发，daemon 都强制校验（没有 token 一律拒绝）；
+//! 集群修复超过 `--approval-threshold` 时 Hub 同样要求审批。审批信息写入审计日志。
+
+use serde::{Deserialize, Serialize};
+
+/// Hub 返回的审批记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Approval {
+    pub token: String,
+    pub action: String,
+    pub target: String,
+    pub requested_by: String,
+    pub approved_by: String,
+    pub reason: Option<String>,
+    pub issued_at: u64,
+    pub expires_at: u64,
+}
+
+impl Approval {
+    /// 写入审计日志 details 的摘要（不记录完整 token）
+    pub fn audit_summary(&self) -> String {
+        format!(
+            "approval={}…; requested_by={}; approved_by={}; expires_at={}",
+            &self.token[..self.token.len().min(8)],
+            self.requested_by,
+            self.approved_by,
+            self.expires_at
+        )
+    }
+}
+
+/// 向 Hub 校验审批 token 是否适用于指定操作和目标（指定发起人时须是为其签发的 token）
+pub async fn verify(
+    hub_url: &str,
+    token: &str,
+    action: &str,
+    target: &str,
+    requested_by: Option<&str>,
+) -> Result<Approval, String> {
+    let url = format!("{}/api/v1/approvals/verify", hub_url.trim_end_matches('/'));
+    let response = crate::hub_auth::authorize(reqwest::Client::new().post(&url))
+        .json(&serde_json::json!({
+            "token": token,
+            "action": action,
+            "target": target,
+            "requested_by": requested_by,
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("连接 Hub 校验审批失败: {}", e))?;
+
+    if !response.status().is_success() {
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        return Err(format!(
+            "审批校验未通过: {}",
+            body["error"].as_str().unwrap_or("未知错误")
+        ));
+    }
+
+    response
+        .json::<Approval>()
+        .await
+        .map_err(|e| format!("解析审批记录失败: {}", e))
+}
+
+/// 高危操作执行前的审批检查（失败即拒绝）
+///
+/// 没有 token、daemon 未配置 Hub 地址或 Hub 校验未通过时均返回错误
+pub async fn check(
+    hub_url: Option<&str>,
+    token: Option<&str>,
+    action: &str,
+    target: &str,
+    requested_by: Option<&str>,
+) -> Result<Approval, String> {
+    let token = token.ok_or_else(|| {
+        format!("{} {} 是高危操作，需要第二位运维通过 Hub 签发的审批 token（--approval）", action, target)
+    })?;
+    let hub_url = hub_url.ok_or_else(|| "校验审批 token 需要 daemon 配置 Hub 地址（ark run --hub-api）".to_string())?;
+    verify(hub_url, token, action, target, requested_by).await
+}
@@ -1,17 +1,33 @@
 //! 审计日志模块
 //! 
 //! 记录所有 ark fix 执行的系统级动作，满足企业合规要求
+//!
+//! 日志是防篡改的哈希链：每条记录带有序号（跨轮转连续）和上一条记录整行 JSON 的 SHA-256，
+//! 设置 `ARK_AUDIT_KEY` 时再附上站点密钥的 HMAC-SHA256。`ark audit verify <file>` 据此检测记录被修改、
+//! 删除和文件被截断；校验结果中的最后一条哈希留存到外部后，还能发现尾部被整段截掉。
+//!
+//! `ark audit search <file>` 按时间、进程、job、动作和结果查询，同目录下已轮转的文件一并查询。
 
+use ark_core::digest::{hmac_sha256, sha256_hex, to_hex};
 use serde::{Serialize, Deserialize};
 use std::fs::{File, OpenOptions};
 use std::io::{Write, BufWriter};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, Mutex, RwLock};
 use chrono::{DateTime, Utc};
 
+/// 审计日志 HMAC 密钥的环境变量（未设置时只有哈希链）
+pub const AUDIT_KEY_ENV: &str = "ARK_AUDIT_KEY";
+
+/// 已写入记录的广播容量（转发到 Hub 的订阅者落后超过该数量时丢弃最旧的记录）
+const MIRROR_CAPACITY: usize = 1024;
+
+/// 哈希链起点：日志中第一条记录的 prev_hash
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
 /// 审计日志条目
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditLogEntry {
     pub timestamp: String,
     pub user: String,
@@ -20,6 +36,68 @@ pub struct AuditLogEntry {
     pub target_job_id: Option<String>,
     pub result: String,
     pub details: String,
+    /// 请求来源：ipc（CLI 的 fix / zap / rollback 及 gRPC）或 hub
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Hub 下发命令的 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command_id: Option<String>,
+    /// 序号（从 1 开始，轮转后继续递增）；旧格式的记录没有哈希链字段
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    /// 上一条记录整行 JSON 的 SHA-256
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_hash: Option<String>,
+    /// 不含本字段的整行 JSON 的 HMAC-SHA256（配置了 `ARK_AUDIT_KEY` 时）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hmac: Option<String>,
+}
+
+impl AuditLogEntry {
+    /// 记录的 HMAC：对去掉 hmac 字段后的 JSON 计算
+    fn compute_hmac(&self, key: &[u8]) -> Result<String, serde_json::Error> {
+        let unsigned = AuditLogEntry { hmac: None, ..self.clone() };
+        Ok(to_hex(&hmac_sha256(key, serde_json::to_string(&unsigned)?.as_bytes())))
+    }
+}
+
+/// 发起操作的一方
+#[derive(Debug, Clone)]
+pub struct AuditOrigin {
+    /// 请求来源（ipc / hub）
+    pub source: &'static str,
+    /// 发起操作的用户：IPC 调用方的用户名（无法解析时为 uid），Hub 命令的 requested_by
+    pub user: String,
+    /// Hub 下发命令的 ID
+    pub command_id: Option<String>,
+}
+
+/// 从环境变量读取审计日志 HMAC 密钥
+pub fn audit_key_from_env() -> Option<Vec<u8>> {
+    std::env::var(AUDIT_KEY_ENV)
+        .ok()
+        .filter(|k| !k.is_empty())
+        .map(String::into_bytes)
+}
+
+/// 哈希链末端：最后一条记录的序号和整行哈希
+struct ChainHead {
+    seq: u64,
+    hash: String,
+}
+
+impl ChainHead {
+    /// 从日志文件的最后一条记录恢复（文件为空时从起点开始）
+    fn load(path: &Path) -> Result<Self, std::io::Error> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(match content.lines().rev().find(|line| !line.trim().is_empty()) {
+            Some(line) => Self {
+                seq: serde_json::from_str::<AuditLogEntry>(line).ok().and_then(|e| e.seq).unwrap_or(0),
+                hash: sha256_hex(line.as_bytes()),
+            },
+            None => Self { seq: 0, hash: GENESIS_HASH.to_string() },
+        })
+    }
 }
 
 /// 审计日志记录器
@@ -28,6 +106,12 @@ pub struct AuditLogger {
     log_path: PathBuf,
     max_size: u64, // 最大文件大小（字节）
     current_size: Arc<RwLock<u64>>,
+    /// 哈希链末端（写入期间持有，保证记录按链的顺序落盘）
+    chain: Mutex<ChainHead>,
+    /// HMAC 密钥
+    key: Option<Vec<u8>>,
+    /// 已落盘的记录（供 Hub 转发器镜像到 Hub）
+    mirror: broadcast::Sender<AuditLogEntry>,
 }
 
 impl AuditLogger {
@@ -47,18 +131,41 @@ impl AuditLogger {
             .open(&log_path)?;
         
         let current_size = file.metadata()?.len();
+        let chain = ChainHead::load(&log_path)?;
         
         Ok(Self {
             log_file: Arc::new(RwLock::new(BufWriter::new(file))),
             log_path,
             max_size,
             current_size: Arc::new(RwLock::new(current_size)),
+            chain: Mutex::new(chain),
+            key: None,
+            mirror: broadcast::channel(MIRROR_CAPACITY).0,
         })
     }
     
+    /// 设置 HMAC 密钥（之后写入的记录带 hmac 字段）
+    pub fn with_key(mut self, key: Option<Vec<u8>>) -> Self {
+        self.key = key;
+        self
+    }
+    
+    /// 订阅之后写入的记录（与落盘内容一致，含哈希链字段）
+    pub fn subscribe(&self) -> broadcast::Receiver<AuditLogEntry> {
+        self.mirror.subscribe()
+    }
+    
     /// 记录审计日志
-    pub async fn log(&self, entry: AuditLogEntry) -> Result<(), std::io::Error> {
-        // 序列化为 JSON
+    pub async fn log(&self, mut entry: AuditLogEntry) -> Result<(), std::io::Error> {
+        let mut chain = self.chain.lock().await;
+        
+        // 接到哈希链末端后序列化为 JSON
+        entry.seq = Some(chain.seq + 1);
+        entry.prev_hash = Some(chain.hash.clone());
+        entry.hmac = match self.key {
+            Some(ref key) => Some(entry.compute_hmac(key)?),
+            None => None,
+        };
         let json = serde_json::to_string(&entry)?;
         let line = format!("{}\n", json);
         let line_bytes = line.as_bytes().len() as u64;
@@ -78,10 +185,34 @@ impl AuditLogger {
         }
         
         *current_size += line_bytes;
+        chain.seq += 1;
+        chain.hash = sha256_hex(json.as_bytes());
+        // 没有订阅者时发送失败，忽略
+        let _ = self.mirror.send(entry);
         
         Ok(())
     }
     
+    /// 记录一次运维操作（执行、拒绝或失败）：daemon 中执行系统动作的路径（IPC 的 fix / zap / rollback、
+    /// Hub 下发的命令）和图运维操作都经过这里。写入失败只打印错误：动作可能已经生效，不能因此报告失败
+    pub async fn record(
+        &self,
+        origin: &AuditOrigin,
+        action: &str,
+        target_pid: u32,
+        target_job_id: Option<&str>,
+        result: &str,
+        details: &str,
+    ) {
+        let mut entry = create_audit_entry(action, target_pid, target_job_id, result, details);
+        entry.user = origin.user.clone();
+        entry.source = Some(origin.source.to_string());
+        entry.command_id = origin.command_id.clone();
+        if let Err(e) = self.log(entry).await {
+            eprintln!("[audit] 记录审计日志失败: {}", e);
+        }
+    }
+    
     /// 轮转日志文件
     async fn rotate_log(&self) -> Result<(), std::io::Error> {
         // 关闭当前文件
@@ -110,6 +241,20 @@ impl AuditLogger {
         Ok(())
     }
     
+    /// 读取当前日志文件中最近的 limit 条记录（不含已轮转的文件，无法解析的行跳过）
+    pub fn recent(&self, limit: usize) -> Result<Vec<AuditLogEntry>, String> {
+        let content = std::fs::read_to_string(&self.log_path)
+            .map_err(|e| format!("读取审计日志 {} 失败: {}", self.log_path.display(), e))?;
+        let mut entries: Vec<AuditLogEntry> = content
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .take(limit)
+            .collect();
+        entries.reverse();
+        Ok(entries)
+    }
+
     /// 获取当前用户（从环境变量或系统）
     fn get_current_user() -> String {
         std::env::var("USER")
@@ -134,5 +279,225 @@ pub fn create_audit_entry(
         target_job_id: target_job_id.map(|s| s.to_string()),
         result: result.to_string(),
         details: details.to_string(),
+        source: None,
+        command_id: None,
+        seq: None,
+        prev_hash: None,
+        hmac: None,
+    }
+}
+
+/// `ark audit search` 的过滤条件（未设置的条件不过滤）
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub pid: Option<u32>,
+    pub job_id: Option<String>,
+    pub action: Option<String>,
+    pub result: Option<String>,
+}
+
+impl AuditFilter {
+    fn matches(&self, entry: &AuditLogEntry) -> bool {
+        // 设置了时间范围时，时间戳无法解析的记录不匹配
+        let in_range = (self.since.is_none() && self.until.is_none())
+            || DateTime::parse_from_rfc3339(&entry.timestamp).is_ok_and(|ts| {
+                self.since.is_none_or(|since| ts >= since) && self.until.is_none_or(|until| ts <= until)
+            });
+        in_range
+            && self.pid.is_none_or(|pid| entry.target_pid == pid)
+            && self.job_id.as_ref().is_none_or(|job| entry.target_job_id.as_ref() == Some(job))
+            && self.action.as_ref().is_none_or(|action| &entry.action == action)
+            && self.result.as_ref().is_none_or(|result| &entry.result == result)
+    }
+}
+
+/// 解析时间参数：RFC3339 时间（如 2026-10-16T08:00:00+08:00），或距现在的相对时长（如 30s、15m、2h、7d）
+pub fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    let (amount, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len()));
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("无效的时间: {}（应为 RFC3339 时间或 30m、2h、7d 等相对时长）", value))?;
+    let duration = match unit {
+        "s" => chrono::Duration::seconds(amount),
+        "m" => chrono::Duration::minutes(amount),
+        "h" => chrono::Duration::hours(amount),
+        "d" => chrono::Duration::days(amount),
+        _ => return Err(format!("无效的时间单位: {}（支持 s、m、h、d）", value)),
+    };
+    Ok(Utc::now() - duration)
+}
+
+/// 审计日志及其已轮转的文件，按时间从旧到新排列
+///
+/// 轮转的文件与日志同目录，文件名为 `<文件名>.<YYYYmmdd_HHMMSS>.log`（见 `rotate_log`）
+pub fn log_files(path: &Path) -> Result<Vec<PathBuf>, String> {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let prefix = format!("{}.", path.file_stem().and_then(|s| s.to_str()).unwrap_or_default());
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| format!("读取目录 {} 失败: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|file| {
+            file.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(prefix.as_str())?.strip_suffix(".log"))
+                .is_some_and(|ts| chrono::NaiveDateTime::parse_from_str(ts, "%Y%m%d_%H%M%S").is_ok())
+        })
+        .collect();
+    // 时间戳定长，按文件名排序即按轮转时间排序
+    files.sort();
+    if path.exists() {
+        files.push(path.to_path_buf());
+    }
+    if files.is_empty() {
+        return Err(format!("审计日志 {} 不存在", path.display()));
+    }
+    Ok(files)
+}
+
+/// 在审计日志及其已轮转的文件中查询，返回匹配的最近 limit 条记录（按时间顺序，无法解析的行跳过）
+pub fn search(path: &Path, filter: &AuditFilter, limit: usize) -> Result<Vec<AuditLogEntry>, String> {
+    let mut entries = Vec::new();
+    // 从最新的文件往前读，取够 limit 条即停止
+    for file in log_files(path)?.iter().rev() {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| format!("读取审计日志 {} 失败: {}", file.display(), e))?;
+        entries.extend(
+            content
+                .lines()
+                .rev()
+                .filter_map(|line| serde_json::from_str::<AuditLogEntry>(line).ok())
+                .filter(|entry| filter.matches(entry))
+                .take(limit - entries.len()),
+        );
+        if entries.len() >= limit {
+            break;
+        }
+    }
+    entries.reverse();
+    Ok(entries)
+}
+
+/// `ark audit verify` 的结果
+#[derive(Debug, Serialize)]
+pub struct VerifyReport {
+    pub file: String,
+    pub entries: usize,
+    /// 哈希链之前的旧格式记录数（无法校验）
+    pub unchained: usize,
+    pub first_seq: Option<u64>,
+    pub last_seq: Option<u64>,
+    /// 最后一条记录的 SHA-256：留存到外部，下次校验时比对可发现尾部截断
+    pub last_hash: Option<String>,
+    /// 是否校验了 HMAC（设置了 `ARK_AUDIT_KEY`）
+    pub hmac_checked: bool,
+    /// 不影响结论的提示（如文件从轮转前的记录接续）
+    pub warnings: Vec<String>,
+    /// 完整性问题：记录被修改、删除，或文件被截断
+    pub problems: Vec<String>,
+}
+
+/// 校验审计日志文件的哈希链（给定密钥时同时校验每条记录的 HMAC）
+pub fn verify_file(path: &Path, key: Option<&[u8]>) -> Result<VerifyReport, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取审计日志 {} 失败: {}", path.display(), e))?;
+    let mut report = VerifyReport {
+        file: path.display().to_string(),
+        entries: 0,
+        unchained: 0,
+        first_seq: None,
+        last_seq: None,
+        last_hash: None,
+        hmac_checked: key.is_some(),
+        warnings: Vec::new(),
+        problems: Vec::new(),
+    };
+    // 上一行的哈希；文件中第一行之前为空
+    let mut prev_hash: Option<String> = None;
+
+    for (idx, line) in content.lines().enumerate() {
+        let line_no = idx + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        report.entries += 1;
+        let hash = sha256_hex(line.as_bytes());
+        let entry = match serde_json::from_str::<AuditLogEntry>(line) {
+            Ok(entry) => entry,
+            Err(e) => {
+                report.problems.push(format!("第 {} 行无法解析: {}", line_no, e));
+                prev_hash = Some(hash);
+                continue;
+            }
+        };
+
+        let (Some(seq), Some(entry_prev)) = (entry.seq, entry.prev_hash.as_deref()) else {
+            if report.first_seq.is_some() {
+                report.problems.push(format!("第 {} 行缺少哈希链字段（在哈希链中间插入了记录）", line_no));
+            } else {
+                report.unchained += 1;
+            }
+            prev_hash = Some(hash);
+            continue;
+        };
+
+        match (&prev_hash, report.last_seq) {
+            // 上一条也在链上：哈希和序号都必须接续
+            (Some(expected), Some(last_seq)) => {
+                if entry_prev != expected.as_str() {
+                    report.problems.push(format!(
+                        "第 {} 行（序号 {}）的 prev_hash 与上一行不符：上一行被修改，或两者之间的记录被删除",
+                        line_no, seq
+                    ));
+                }
+                if seq != last_seq + 1 {
+                    report.problems.push(format!("第 {} 行：序号从 {} 跳到 {}", line_no, last_seq, seq));
+                }
+            }
+            // 哈希链从旧格式记录之后开始
+            (Some(expected), None) => {
+                if entry_prev != expected.as_str() {
+                    report.problems.push(format!("第 {} 行（序号 {}）的 prev_hash 与上一行不符", line_no, seq));
+                }
+            }
+            // 文件的第一条记录
+            (None, _) => {
+                if entry_prev == GENESIS_HASH {
+                    if seq != 1 {
+                        report.problems.push(format!("第 {} 行：链起点的序号为 {}，应为 1", line_no, seq));
+                    }
+                } else {
+                    report.warnings.push(format!(
+                        "文件从序号 {} 开始：之前的记录在轮转前的文件中，或文件头部被截断",
+                        seq
+                    ));
+                }
+            }
+        }
+
+        if let Some(key) = key {
+            match entry.hmac {
+                None => report.problems.push(format!("第 {} 行（序号 {}）缺少 HMAC", line_no, seq)),
+                Some(ref hmac) => {
+                    if entry.compute_hmac(key).ok().as_ref() != Some(hmac) {
+                        report.problems.push(format!("第 {} 行（序号 {}）HMAC 不匹配：记录被修改", line_no, seq));
+                    }
+                }
+            }
+        }
+
+        report.first_seq.get_or_insert(seq);
+        report.last_seq = Some(seq);
+        prev_hash = Some(hash);
+    }
+
+    if !content.is_empty() && !content.ends_with('\n') {
+        report.problems.push("最后一行不完整：文件被截断或写入中断".to_string());
     }
+    report.last_hash = prev_hash;
+    Ok(report)
 }
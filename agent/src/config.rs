@@ -0,0 +1,112 @@
+//! daemon 动态配置（`ark run --config`）：日志级别、Hub 推送过滤、外部执行器、动作冷却和执行时限
+//!
+//! 这些配置不影响状态图和连接，收到 SIGHUP 时重新读取即可生效，不需要重启 daemon。
+//!
+//! ```yaml
+//! log_level: info            # error / warn / info / debug（debug 输出每条事件）
+//! forward:
+//!   include: [transport.bw]  # 总是推送到 Hub（跳过边缘折叠）
+//!   exclude: [compute.util]  # 不推送到 Hub
+//! actuators:                 # 外部执行器插件，规则中以 `type: plugin, name: ...` 调用
+//!   - name: npu-reset
+//!     command: /opt/vendor/bin/npu-reset-ark
+//! action_cooldown:           # 同一进程 / job 在窗口内不重复执行同类动作
+//!   window_seconds: 60
+//! action_timeout_seconds: 120  # 单个修复动作的执行时限，超时取消（0 表示不限制）
+//! ```
+
+use crate::exec::{ActuatorSpec, CooldownConfig};
+use ark_core::event::EventType;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::time::Duration;
+
+/// 日志级别
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+}
+
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+
+const DEFAULT_ACTION_TIMEOUT_SECONDS: u64 = 120;
+
+static ACTION_TIMEOUT_SECONDS: AtomicU64 = AtomicU64::new(DEFAULT_ACTION_TIMEOUT_SECONDS);
+
+/// 当前日志级别下是否输出该级别的日志
+pub fn log_enabled(level: LogLevel) -> bool {
+    level as u8 <= LOG_LEVEL.load(Ordering::Relaxed)
+}
+
+/// 单个修复动作的执行时限（None 时不限制）
+pub fn action_timeout() -> Option<Duration> {
+    match ACTION_TIMEOUT_SECONDS.load(Ordering::Relaxed) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+/// Hub 推送过滤，在边缘折叠逻辑之前生效
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ForwardFilter {
+    pub include: Vec<EventType>,
+    pub exclude: Vec<EventType>,
+}
+
+impl ForwardFilter {
+    /// Some(true) 总是推送，Some(false) 不推送，None 交给边缘折叠判断；同时出现时 exclude 优先
+    pub fn decide(&self, event_type: &EventType) -> Option<bool> {
+        if self.exclude.contains(event_type) {
+            Some(false)
+        } else if self.include.contains(event_type) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+/// 动态配置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    pub log_level: LogLevel,
+    pub forward: ForwardFilter,
+    pub actuators: Vec<ActuatorSpec>,
+    pub action_cooldown: CooldownConfig,
+    pub action_timeout_seconds: u64,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            log_level: LogLevel::default(),
+            forward: ForwardFilter::default(),
+            actuators: Vec::new(),
+            action_cooldown: CooldownConfig::default(),
+            action_timeout_seconds: DEFAULT_ACTION_TIMEOUT_SECONDS,
+        }
+    }
+}
+
+impl DaemonConfig {
+    /// 读取配置文件
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("读取配置 {} 失败: {}", path.display(), e))?;
+        serde_yaml::from_str(&content).map_err(|e| format!("解析配置 {} 失败: {}", path.display(), e))
+    }
+
+    /// 使全局配置（日志级别、动作执行时限）生效
+    pub fn apply(&self) {
+        LOG_LEVEL.store(self.log_level as u8, Ordering::Relaxed);
+        ACTION_TIMEOUT_SECONDS.store(self.action_timeout_seconds, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,103 @@
+//! 进程托管：后台化、pidfile、日志重定向、信号处理和 systemd 就绪通知
+//!
+//! `--daemonize` 必须在 tokio 运行时创建之前 fork，因此由同步的 `main` 在进入运行时前调用 `start`。
+//! 由 systemd 托管时不需要后台化：使用 `Type=notify`，IPC 服务就绪后发送 `READY=1`。
+
+use daemonize::{Daemonize, Stdio};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+/// pidfile：daemon 退出时删除
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            eprintln!("[ark] 警告：删除 pidfile {} 失败: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// 按参数后台化并写入 pidfile
+///
+/// 后台化时 stdout / stderr 重定向到 `log_file`（未指定时丢弃），工作目录保持不变，
+/// 相对路径的探针脚本和规则目录仍然有效。前台运行时只写入 pidfile。
+pub fn start(daemonize: bool, pid_file: Option<PathBuf>, log_file: Option<&Path>) -> Result<Option<PidFile>, String> {
+    if let Some(parent) = pid_file.as_ref().and_then(|p| p.parent()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建 pidfile 目录失败: {}", e))?;
+    }
+
+    if !daemonize {
+        if log_file.is_some() {
+            return Err("--log-file 只能与 --daemonize 一起使用".to_string());
+        }
+        if let Some(path) = &pid_file {
+            std::fs::write(path, format!("{}\n", std::process::id()))
+                .map_err(|e| format!("写入 pidfile {} 失败: {}", path.display(), e))?;
+        }
+        return Ok(pid_file.map(|path| PidFile { path }));
+    }
+
+    let cwd = std::env::current_dir().map_err(|e| format!("获取当前目录失败: {}", e))?;
+    let (stdout, stderr) = match log_file {
+        Some(path) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| format!("创建日志目录失败: {}", e))?;
+            }
+            let open = || {
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| format!("打开日志文件 {} 失败: {}", path.display(), e))
+            };
+            (Stdio::from(open()?), Stdio::from(open()?))
+        }
+        None => (Stdio::devnull(), Stdio::devnull()),
+    };
+
+    let mut daemon = Daemonize::new().working_directory(cwd).umask(0o027).stdout(stdout).stderr(stderr);
+    if let Some(path) = &pid_file {
+        daemon = daemon.pid_file(path);
+    }
+    // 父进程在 fork 后直接退出，只有子进程从这里返回
+    daemon.start().map_err(|e| format!("后台化失败: {}", e))?;
+    Ok(pid_file.map(|path| PidFile { path }))
+}
+
+/// 通知 systemd 服务已就绪（未由 systemd 以 Type=notify 启动时无操作）
+pub fn notify_ready() {
+    if let Err(e) = sd_notify::notify(false, &[sd_notify::NotifyState::Ready]) {
+        eprintln!("[ark] 警告：发送 systemd 就绪通知失败: {}", e);
+    }
+}
+
+/// 通知 systemd 正在重新加载配置（完成后再次调用 `notify_ready`）
+pub fn notify_reloading() {
+    let _ = sd_notify::notify(false, &[sd_notify::NotifyState::Reloading]);
+}
+
+/// 通知 systemd 服务正在停止
+pub fn notify_stopping() {
+    let _ = sd_notify::notify(false, &[sd_notify::NotifyState::Stopping]);
+}
+
+/// 等待退出信号：Ctrl+C（SIGINT）或 SIGTERM（systemctl stop / kill）
+pub async fn shutdown_signal() -> Result<(), String> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = signal(SignalKind::terminate()).map_err(|e| format!("注册 SIGTERM 处理失败: {}", e))?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result.map_err(|e| format!("等待 Ctrl+C 失败: {}", e)),
+        _ = sigterm.recv() => Ok(()),
+    }
+}
+
+/// 注册 SIGHUP（`systemctl reload` / `kill -HUP`），daemon 收到后重新加载配置
+pub fn hangup_signal() -> Result<tokio::signal::unix::Signal, String> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    signal(SignalKind::hangup()).map_err(|e| format!("注册 SIGHUP 处理失败: {}", e))
+}
@@ -1,12 +1,15 @@
-use crate::ipc::IpcClient;
-use ark_core::rules::RuleEngine;
+use crate::history::{self, HistoryKind, HistoryRecord, HistoryStore};
+use crate::ipc::{IpcClient, ProcessFilter, DEFAULT_GRAPH_DEPTH};
+use crate::scene::SceneIdentifier;
+use ark_core::digest::sha256_hex;
+use ark_core::rules::{MatchStatus, Rule, RuleEngine, RuleExplanation, RuleMatch};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// 诊断结果
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Diagnosis {
     pub pid: u32,
     pub causes: Vec<String>,
@@ -38,8 +41,45 @@ impl LlmProvider {
             LlmProvider::Local => "http://localhost:11434/api/generate", // Ollama 默认端口
         }
     }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LlmProvider::OpenAI => "openai",
+            LlmProvider::Claude => "claude",
+            LlmProvider::Local => "local",
+        }
+    }
+
+    /// 调用的模型（均使用成本较低的模型）
+    pub fn model(&self) -> &'static str {
+        match self {
+            LlmProvider::OpenAI => "gpt-4o-mini",
+            LlmProvider::Claude => "claude-3-haiku-20240307",
+            LlmProvider::Local => "llama2",
+        }
+    }
 }
 
+/// 大模型的回答及 token 用量（提供商未返回时为 None）
+struct LlmResponse {
+    text: String,
+    input_tokens: Option<u64>,
+    output_tokens: Option<u64>,
+}
+
+impl LlmResponse {
+    fn new(text: Option<&str>, input_tokens: &serde_json::Value, output_tokens: &serde_json::Value) -> Result<Self, String> {
+        Ok(Self {
+            text: text.ok_or_else(|| "响应格式错误".to_string())?.to_string(),
+            input_tokens: input_tokens.as_u64(),
+            output_tokens: output_tokens.as_u64(),
+        })
+    }
+}
+
+/// 历史记录中回答摘要的最大字符数
+const RESPONSE_SUMMARY_CHARS: usize = 200;
+
 /// 大模型客户端
 pub struct LlmClient {
     provider: LlmProvider,
@@ -80,6 +120,9 @@ impl LlmClient {
     }
 
     /// 调用大模型获取诊断建议
+    ///
+    /// 每次调用（包括失败的）都记入本地历史（`ark history --kind llm-call`），供追溯运维依据的 AI 建议。
+    /// prompt 含进程和资源信息，只记录其 SHA-256 和长度
     pub async fn diagnose(
         &self,
         pid: u32,
@@ -88,18 +131,50 @@ impl LlmClient {
     ) -> Result<Diagnosis, String> {
         let prompt = build_diagnosis_prompt(pid, causes, processes);
 
+        let started = std::time::Instant::now();
         let response = match self.provider {
-            LlmProvider::OpenAI => self.call_openai(&prompt).await?,
-            LlmProvider::Claude => self.call_claude(&prompt).await?,
-            LlmProvider::Local => self.call_local(&prompt).await?,
+            LlmProvider::OpenAI => self.call_openai(&prompt).await,
+            LlmProvider::Claude => self.call_claude(&prompt).await,
+            LlmProvider::Local => self.call_local(&prompt).await,
         };
+        self.record_call(pid, &prompt, &response, started.elapsed());
+
+        parse_diagnosis_response(response?.text)
+    }
 
-        parse_diagnosis_response(response)
+    /// 把一次调用记入本地历史
+    fn record_call(&self, pid: u32, prompt: &str, response: &Result<LlmResponse, String>, latency: std::time::Duration) {
+        let latency_ms = latency.as_millis() as u64;
+        let mut detail = json!({
+            "provider": self.provider.name(),
+            "model": self.provider.model(),
+            "prompt_sha256": sha256_hex(prompt.as_bytes()),
+            "prompt_chars": prompt.chars().count(),
+            "latency_ms": latency_ms,
+        });
+        let summary = match response {
+            Ok(response) => {
+                detail["input_tokens"] = json!(response.input_tokens);
+                detail["output_tokens"] = json!(response.output_tokens);
+                detail["response_summary"] =
+                    json!(response.text.trim().chars().take(RESPONSE_SUMMARY_CHARS).collect::<String>());
+                format!("{} / {}: {} ms", self.provider.name(), self.provider.model(), latency_ms)
+            }
+            Err(e) => {
+                detail["error"] = json!(e);
+                format!("{} / {}: 调用失败", self.provider.name(), self.provider.model())
+            }
+        };
+        HistoryStore::new(history::default_history_path()).record(
+            HistoryRecord::new(HistoryKind::LlmCall, summary)
+                .with_target(Some(pid), None)
+                .with_detail(detail),
+        );
     }
 
-    async fn call_openai(&self, prompt: &str) -> Result<String, String> {
+    async fn call_openai(&self, prompt: &str) -> Result<LlmResponse, String> {
         let body = json!({
-            "model": "gpt-4o-mini", // 使用成本更低的模型
+            "model": self.provider.model(),
             "messages": [
                 {
                     "role": "system",
@@ -134,15 +209,16 @@ impl LlmClient {
             .await
             .map_err(|e| format!("解析响应失败: {}", e))?;
 
-        json["choices"][0]["message"]["content"]
-            .as_str()
-            .ok_or_else(|| "响应格式错误".to_string())
-            .map(|s| s.to_string())
+        LlmResponse::new(
+            json["choices"][0]["message"]["content"].as_str(),
+            &json["usage"]["prompt_tokens"],
+            &json["usage"]["completion_tokens"],
+        )
     }
 
-    async fn call_claude(&self, prompt: &str) -> Result<String, String> {
+    async fn call_claude(&self, prompt: &str) -> Result<LlmResponse, String> {
         let body = json!({
-            "model": "claude-3-haiku-20240307", // 使用成本更低的模型
+            "model": self.provider.model(),
             "max_tokens": 500,
             "messages": [
                 {
@@ -173,16 +249,17 @@ impl LlmClient {
             .await
             .map_err(|e| format!("解析响应失败: {}", e))?;
 
-        json["content"][0]["text"]
-            .as_str()
-            .ok_or_else(|| "响应格式错误".to_string())
-            .map(|s| s.to_string())
+        LlmResponse::new(
+            json["content"][0]["text"].as_str(),
+            &json["usage"]["input_tokens"],
+            &json["usage"]["output_tokens"],
+        )
     }
 
-    async fn call_local(&self, prompt: &str) -> Result<String, String> {
+    async fn call_local(&self, prompt: &str) -> Result<LlmResponse, String> {
         // 本地模型（如 Ollama）的调用
         let body = json!({
-            "model": "llama2", // 默认模型，可通过环境变量配置
+            "model": self.provider.model(),
             "prompt": format!("你是一位资深的 SRE。请用简洁、专业的中文回答。\n\n{}", prompt),
             "stream": false
         });
@@ -205,10 +282,11 @@ impl LlmClient {
             .await
             .map_err(|e| format!("解析响应失败: {}", e))?;
 
-        json["response"]
-            .as_str()
-            .ok_or_else(|| "响应格式错误".to_string())
-            .map(|s| s.to_string())
+        LlmResponse::new(
+            json["response"].as_str(),
+            &json["prompt_eval_count"],
+            &json["eval_count"],
+        )
     }
 }
 
@@ -282,6 +360,103 @@ fn parse_diagnosis_response(response: String) -> Result<Diagnosis, String> {
     })
 }
 
+/// 规则命中时直接给出规则中的解决方案（附带命中依据）
+fn rule_diagnosis(pid: u32, causes: Vec<String>, rule: &Rule, explanation: Option<&RuleExplanation>) -> Diagnosis {
+    let mut recommendation = String::new();
+    recommendation.push_str(&format!("【规则匹配: {}】\n\n", rule.name));
+    recommendation.push_str(&format!("根因: {}\n\n", rule.root_cause_pattern.primary));
+    if let Some(explanation) = explanation {
+        recommendation.push_str(&format!("命中依据:\n{}\n", explanation));
+    }
+    recommendation.push_str("解决方案:\n");
+
+    for step in &rule.solution_steps {
+        recommendation.push_str(&format!("{}. {}\n", step.step, step.action));
+        if let Some(cmd) = &step.command {
+            recommendation.push_str(&format!("   命令: {}\n", cmd));
+        }
+        if step.manual {
+            recommendation.push_str("   [需要手动执行]\n");
+        }
+    }
+
+    Diagnosis {
+        pid,
+        causes,
+        recommendation,
+        confidence: 0.9, // 规则匹配置信度较高
+    }
+}
+
+/// 由 daemon 的规则命中结果生成诊断：优先本次触发的规则；
+/// 只有冷却中的规则命中时说明已推荐过，而不是回退到本地规则或大模型重复给出推荐
+fn daemon_rule_diagnosis(pid: u32, causes: &[String], matches: &[RuleMatch]) -> Option<Diagnosis> {
+    if let Some(m) = matches.iter().find(|m| m.fired()) {
+        return Some(rule_diagnosis(pid, causes.to_vec(), &m.rule, m.explanation.as_ref()));
+    }
+    matches.iter().find_map(|m| match m.status {
+        MatchStatus::CoolingDown { remaining_secs } => Some(Diagnosis {
+            pid,
+            causes: causes.to_vec(),
+            recommendation: format!(
+                "【规则匹配: {}】\n\n该规则已对 {} 给出过推荐，冷却中（剩余 {} 秒），不重复推荐",
+                m.rule.name,
+                if m.entities.is_empty() { "当前状态".to_string() } else { m.entities.join(", ") },
+                remaining_secs
+            ),
+            confidence: 0.9,
+        }),
+        _ => None,
+    })
+}
+
+/// 本地规则匹配时从 daemon 取的最近事件条数
+const LOCAL_RULE_EVENTS: usize = 1000;
+
+/// 用本地规则目录匹配 daemon 的完整状态图和最近事件
+async fn local_rule_diagnosis(client: &IpcClient, pid: u32, causes: &[String], rules_path: &Path) -> Option<Diagnosis> {
+    let rule_engine = RuleEngine::load_from_dir(rules_path).ok()?;
+    let graph = client.get_graph(None, DEFAULT_GRAPH_DEPTH).await.ok()?;
+    let events = client.recent_events(LOCAL_RULE_EVENTS).await.ok()?;
+    let rule = rule_engine.match_first(&graph, &events).await?;
+    let explanation = RuleEngine::explain(rule, &events, &graph).await;
+    Some(rule_diagnosis(pid, causes.to_vec(), rule, Some(&explanation)))
+}
+
+/// 用进程在状态图中的邻域运行场景分析器，识别到场景时直接给出分析结论，不再调用大模型
+async fn scene_diagnosis(client: &IpcClient, pid: u32, causes: &[String]) -> Option<Diagnosis> {
+    let graph = client.get_graph(Some(pid), DEFAULT_GRAPH_DEPTH).await.ok()?;
+    let identifier = SceneIdentifier::new();
+    let scene = identifier.identify_scene(&graph, pid).await?;
+    let analysis = identifier.analyze_scene(scene, &graph, pid).await?;
+
+    let mut recommendation = format!("【场景分析: {}】\n\n", analysis.scene.as_str());
+    if !analysis.root_causes.is_empty() {
+        recommendation.push_str("根因:\n");
+        for cause in &analysis.root_causes {
+            recommendation.push_str(&format!("- {}\n", cause));
+        }
+        recommendation.push('\n');
+    }
+    recommendation.push_str("建议:\n");
+    for (idx, item) in analysis.recommendations.iter().enumerate() {
+        recommendation.push_str(&format!("{}. {}\n", idx + 1, item));
+    }
+    if !analysis.recommended_actions.is_empty() {
+        recommendation.push_str("\n推荐动作（可用 ark fix 执行）:\n");
+        for action in &analysis.recommended_actions {
+            recommendation.push_str(&format!("- {}\n", action));
+        }
+    }
+
+    Some(Diagnosis {
+        pid,
+        causes: causes.to_vec(),
+        recommendation,
+        confidence: analysis.confidence,
+    })
+}
+
 /// 执行诊断
 #[cfg(unix)]
 pub async fn run_diagnosis(
@@ -301,48 +476,27 @@ pub async fn run_diagnosis(
     let causes = client.why_process(pid).await?;
 
     // 获取进程列表（用于上下文）
-    let processes = client.list_processes().await?;
+    let processes = client.list_processes(&ProcessFilter::default()).await?;
+
+    // 优先使用 daemon 持有的热加载规则集（可匹配完整状态图）
+    if let Ok((_, matches)) = client.match_rules().await {
+        if let Some(diagnosis) = daemon_rule_diagnosis(pid, &causes, &matches) {
+            return Ok(diagnosis);
+        }
+    }
 
-    // 尝试加载规则引擎并匹配规则
+    // daemon 未配置规则目录时，回退到本地加载规则
     if let Some(rules_path) = rules_dir {
-        if let Ok(rule_engine) = RuleEngine::load_from_dir(&rules_path) {
-            // 从图中提取信息进行规则匹配
-            // 注意：这里我们需要获取图状态，但当前 IPC 接口不直接提供
-            // 为了简化，我们基于根因分析结果来匹配规则
-            // 未来可以扩展 IPC 接口以获取更详细的图状态
-            
-            // 创建虚拟事件列表（从根因和进程信息中提取）
-            let virtual_events = extract_virtual_events_from_causes(&causes, &processes);
-            
-            // 尝试匹配规则（需要图状态，这里先跳过图条件匹配）
-            // 简化版本：只匹配事件条件
-            if let Some(rule) = rule_engine.match_first_simple(&virtual_events).await {
-                // 规则匹配成功，返回规则中的解决方案
-                let mut recommendation = String::new();
-                recommendation.push_str(&format!("【规则匹配: {}】\n\n", rule.name));
-                recommendation.push_str(&format!("根因: {}\n\n", rule.root_cause_pattern.primary));
-                recommendation.push_str("解决方案:\n");
-                
-                for step in &rule.solution_steps {
-                    recommendation.push_str(&format!("{}. {}\n", step.step, step.action));
-                    if let Some(cmd) = &step.command {
-                        recommendation.push_str(&format!("   命令: {}\n", cmd));
-                    }
-                    if step.manual {
-                        recommendation.push_str("   [需要手动执行]\n");
-                    }
-                }
-                
-                return Ok(Diagnosis {
-                    pid,
-                    causes,
-                    recommendation,
-                    confidence: 0.9, // 规则匹配置信度较高
-                });
-            }
+        if let Some(diagnosis) = local_rule_diagnosis(&client, pid, &causes, &rules_path).await {
+            return Ok(diagnosis);
         }
     }
 
+    // 规则未匹配时先运行场景分析器
+    if let Some(diagnosis) = scene_diagnosis(&client, pid, &causes).await {
+        return Ok(diagnosis);
+    }
+
     // 规则未匹配，调用大模型
     let llm_client = if let Some(provider_str) = llm_provider {
         let provider = LlmProvider::from_str(&provider_str);
@@ -390,48 +544,27 @@ pub async fn run_diagnosis(
     let causes = client.why_process(pid).await?;
 
     // 获取进程列表（用于上下文）
-    let processes = client.list_processes().await?;
+    let processes = client.list_processes(&ProcessFilter::default()).await?;
 
-    // 尝试加载规则引擎并匹配规则
+    // 优先使用 daemon 持有的热加载规则集（可匹配完整状态图）
+    if let Ok((_, matches)) = client.match_rules().await {
+        if let Some(diagnosis) = daemon_rule_diagnosis(pid, &causes, &matches) {
+            return Ok(diagnosis);
+        }
+    }
+
+    // daemon 未配置规则目录时，回退到本地加载规则
     if let Some(rules_path) = rules_dir {
-        if let Ok(rule_engine) = RuleEngine::load_from_dir(&rules_path) {
-            // 从图中提取信息进行规则匹配
-            // 注意：这里我们需要获取图状态，但当前 IPC 接口不直接提供
-            // 为了简化，我们基于根因分析结果来匹配规则
-            // 未来可以扩展 IPC 接口以获取更详细的图状态
-            
-            // 创建虚拟事件列表（从根因和进程信息中提取）
-            let virtual_events = extract_virtual_events_from_causes(&causes, &processes);
-            
-            // 尝试匹配规则（需要图状态，这里先跳过图条件匹配）
-            // 简化版本：只匹配事件条件
-            if let Some(rule) = rule_engine.match_first_simple(&virtual_events).await {
-                // 规则匹配成功，返回规则中的解决方案
-                let mut recommendation = String::new();
-                recommendation.push_str(&format!("【规则匹配: {}】\n\n", rule.name));
-                recommendation.push_str(&format!("根因: {}\n\n", rule.root_cause_pattern.primary));
-                recommendation.push_str("解决方案:\n");
-                
-                for step in &rule.solution_steps {
-                    recommendation.push_str(&format!("{}. {}\n", step.step, step.action));
-                    if let Some(cmd) = &step.command {
-                        recommendation.push_str(&format!("   命令: {}\n", cmd));
-                    }
-                    if step.manual {
-                        recommendation.push_str("   [需要手动执行]\n");
-                    }
-                }
-                
-                return Ok(Diagnosis {
-                    pid,
-                    causes,
-                    recommendation,
-                    confidence: 0.9, // 规则匹配置信度较高
-                });
-            }
+        if let Some(diagnosis) = local_rule_diagnosis(&client, pid, &causes, &rules_path).await {
+            return Ok(diagnosis);
         }
     }
 
+    // 规则未匹配时先运行场景分析器
+    if let Some(diagnosis) = scene_diagnosis(&client, pid, &causes).await {
+        return Ok(diagnosis);
+    }
+
     // 规则未匹配，调用大模型
     let llm_client = if let Some(provider_str) = llm_provider {
         let provider = LlmProvider::from_str(&provider_str);
@@ -460,59 +593,3 @@ pub async fn run_diagnosis(
 
     Ok(diagnosis)
 }
-
-/// 从根因和进程信息中提取虚拟事件（用于规则匹配）
-fn extract_virtual_events_from_causes(
-    causes: &[String],
-    processes: &[serde_json::Value],
-) -> Vec<crate::event::Event> {
-    use crate::event::{Event, EventType};
-    use std::time::{SystemTime, UNIX_EPOCH};
-    
-    let mut events = Vec::new();
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()  // 如果系统时间异常，使用默认值 0
-        .as_millis() as u64;
-
-    // 从根因中提取错误事件
-    for cause in causes {
-        if cause.contains("error.hw") || cause.contains("GPU") || cause.contains("OOM") {
-            events.push(Event {
-                ts: now,
-                event_type: EventType::ErrorHw,
-                entity_id: "gpu-*".to_string(),
-                job_id: None,
-                pid: None,
-                value: cause.clone(),
-            });
-        } else if cause.contains("network") || cause.contains("网络") {
-            events.push(Event {
-                ts: now,
-                event_type: EventType::ErrorNet,
-                entity_id: "network-*".to_string(),
-                job_id: None,
-                pid: None,
-                value: cause.clone(),
-            });
-        }
-    }
-
-    // 从进程信息中提取事件
-    for proc in processes {
-        if let Some(state) = proc["state"].as_str() {
-            if state == "blocked" || state == "waiting" {
-                events.push(Event {
-                    ts: now,
-                    event_type: EventType::ProcessState,
-                    entity_id: format!("pid-{}", proc["pid"].as_u64().unwrap_or(0)),
-                    job_id: None,
-                    pid: proc["pid"].as_u64().map(|p| p as u32),
-                    value: state.to_string(),
-                });
-            }
-        }
-    }
-
-    events
-}
@@ -1,5 +1,9 @@
-/// 执行动作类型
-#[derive(Debug, Clone, PartialEq, Eq)]
+use ark_core::rules::{EscalationStep, RuleAction};
+use serde::{Deserialize, Serialize};
+
+/// 执行动作类型（序列化格式与规则文件中的 `RuleAction` 相同，`execute_action` RPC 使用）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum ActionType {
     /// 发送信号（如 SIGUSR1 触发 Checkpoint）
     Signal { signal: i32 },
@@ -11,18 +15,32 @@ pub enum ActionType {
     },
     /// 重启网络接口
     NetworkRestart { interface: String },
+    /// 网络限速（tc HTB，限制接口出向带宽，代替会中断 RDMA 流量的接口重启）
+    NetworkThrottle { interface: String, rate_mbps: u64 },
     /// 优雅降级（先发信号，等待后 kill）
     GracefulShutdown { 
         signal: i32, 
         wait_seconds: u64,
         force_kill: bool,
     },
+    /// 逐级处置：按顺序发送信号并等待，进程退出后不再升级
+    Escalate { steps: Vec<EscalationStep> },
     /// 清理进程（kill -9）
     KillProcess,
+    /// 清理整个进程树（`ark zap`，按进程组终止）
+    KillProcessTree,
     /// 隔离节点（标记为不可调度）
     IsolateNode { reason: String },
     /// 检查 Checkpoint 文件
     CheckCheckpoint { checkpoint_dir: String },
+    /// 重置 GPU（XID 等导致设备卡死时）：先清空该 GPU 上目标作业的进程，再执行设备重置
+    GpuReset { gpu_id: u32 },
+    /// 外部执行器插件（daemon 动态配置中注册，见 `exec::registry`）
+    Plugin {
+        name: String,
+        #[serde(default)]
+        params: serde_json::Value,
+    },
     /// 其他自定义动作
     Custom { command: String, args: Vec<String> },
 }
@@ -32,6 +50,13 @@ impl ActionType {
     pub fn from_recommendation(text: &str) -> Option<Self> {
         let text_lower = text.to_lowercase();
         
+        // GPU 重置（必须能从文本中解析出 GPU 编号，且要先于"清理"等终止进程的关键字匹配）
+        if text_lower.contains("gpu-reset") || text_lower.contains("gpu reset") || text_lower.contains("重置 gpu") {
+            if let Some(gpu_id) = parse_gpu_id(&text_lower) {
+                return Some(ActionType::GpuReset { gpu_id });
+            }
+        }
+        
         // 信号相关
         if text_lower.contains("sigusr1") || text_lower.contains("checkpoint dump") || 
            text_lower.contains("触发 checkpoint") || text_lower.contains("保存 checkpoint") {
@@ -42,6 +67,11 @@ impl ActionType {
             return Some(ActionType::Signal { signal: 10 });
         }
         
+        // 逐级处置（要先于"kill"等关键字匹配）
+        if text_lower.contains("逐级") || text_lower.contains("escalat") {
+            return Some(ActionType::Escalate { steps: default_escalation() });
+        }
+        
         // Kill/Zap 相关
         if text_lower.contains("ark zap") || text_lower.contains("kill") || 
            text_lower.contains("终止进程") || text_lower.contains("清理") {
@@ -122,27 +152,221 @@ impl ActionType {
             ActionType::NetworkRestart { interface } => {
                 format!("重启网络接口: {}", interface)
             }
+            ActionType::NetworkThrottle { interface, rate_mbps } => {
+                format!("网络限速: {} 出向 {} Mbit/s", interface, rate_mbps)
+            }
             ActionType::GracefulShutdown { signal, wait_seconds, force_kill } => {
                 format!("优雅降级: 发送信号 {}，等待 {} 秒{}", 
                        signal, wait_seconds, 
                        if *force_kill { "，然后强制终止" } else { "" })
             }
+            ActionType::Escalate { steps } => format!("逐级处置: {}", escalation_ladder(steps)),
             ActionType::KillProcess => "强制终止进程".to_string(),
+            ActionType::KillProcessTree => "强制终止进程树".to_string(),
             ActionType::IsolateNode { reason } => {
                 format!("隔离节点: {}", reason)
             }
             ActionType::CheckCheckpoint { checkpoint_dir } => {
                 format!("检查 Checkpoint: {}", checkpoint_dir)
             }
+            ActionType::GpuReset { gpu_id } => format!("重置 GPU {}", gpu_id),
+            ActionType::Plugin { name, .. } => format!("外部执行器: {}", name),
             ActionType::Custom { command, args } => {
                 format!("执行命令: {} {}", command, args.join(" "))
             }
         }
     }
+
+    /// 执行前应满足的条件（修复计划中展示给运维确认）
+    pub fn preconditions(&self, pid: u32) -> Vec<String> {
+        let process_alive = format!("进程 {} 仍在运行", pid);
+        match self {
+            ActionType::Signal { signal } => vec![
+                process_alive,
+                format!("进程处理 {}（未注册处理函数时默认行为可能是终止进程）", signal_name(*signal)),
+            ],
+            ActionType::CgroupThrottle { .. } => vec![
+                process_alive,
+                "cgroup 挂载于 /sys/fs/cgroup，daemon 有写权限".to_string(),
+            ],
+            ActionType::NetworkRestart { interface } => vec![
+                format!("网络接口 {} 存在", interface),
+                format!("{} 不是当前管理连接使用的接口（重启期间连接中断）", interface),
+            ],
+            ActionType::NetworkThrottle { interface, .. } => vec![
+                format!("网络接口 {} 存在", interface),
+                "tc 可用，daemon 有 CAP_NET_ADMIN 权限".to_string(),
+                format!("{} 上没有需要保留的 root qdisc（将被替换，撤销时删除）", interface),
+            ],
+            ActionType::GracefulShutdown { signal, .. } => vec![
+                process_alive,
+                format!("进程收到 {} 后会自行保存状态并退出", signal_name(*signal)),
+            ],
+            ActionType::Escalate { steps } => vec![
+                process_alive,
+                format!("进程收到 {} 后会自行保存状态并退出", escalation_ladder(steps)),
+            ],
+            ActionType::KillProcess | ActionType::KillProcessTree => vec![
+                process_alive,
+                "已保存 Checkpoint 或可以接受丢失未保存的状态".to_string(),
+            ],
+            ActionType::IsolateNode { .. } => vec!["节点上的其他任务可以迁移".to_string()],
+            ActionType::CheckCheckpoint { checkpoint_dir } => vec![format!("目录 {} 存在", checkpoint_dir)],
+            ActionType::GpuReset { gpu_id } => vec![
+                format!("GPU {} 上只有进程 {} 所在进程组的进程（其他作业在用时拒绝执行）", gpu_id, pid),
+                "nvidia-smi 可用，daemon 以 root 运行".to_string(),
+                "已保存 Checkpoint 或可以接受丢失未保存的状态".to_string(),
+            ],
+            ActionType::Plugin { name, .. } => vec![format!("daemon 注册了外部执行器 {}", name)],
+            ActionType::Custom { command, .. } => vec![format!("命令 {} 在 daemon 的 PATH 中", command)],
+        }
+    }
+
+    /// 执行后的预期效果
+    pub fn expected_effect(&self, pid: u32) -> String {
+        match self {
+            ActionType::Signal { signal } => {
+                format!("进程 {} 收到 {}（通常触发 Checkpoint dump）", pid, signal_name(*signal))
+            }
+            ActionType::CgroupThrottle { .. } => format!("进程 {} 被移入 ark 专用 cgroup 并限制资源", pid),
+            ActionType::NetworkRestart { interface } => format!("网络接口 {} 断开约 1 秒后恢复", interface),
+            ActionType::NetworkThrottle { interface, rate_mbps } => {
+                format!("网络接口 {} 出向带宽限制在 {} Mbit/s，连接不中断", interface, rate_mbps)
+            }
+            ActionType::GracefulShutdown { wait_seconds, force_kill, .. } => {
+                if *force_kill {
+                    format!("进程 {} 有 {} 秒退出，超时后被强制终止", pid, wait_seconds)
+                } else {
+                    format!("进程 {} 有 {} 秒自行退出，不会强制终止", pid, wait_seconds)
+                }
+            }
+            ActionType::Escalate { steps } => format!(
+                "进程 {} 退出后停止升级，最多等待 {} 秒",
+                pid,
+                steps.iter().map(|step| step.wait_seconds).sum::<u64>()
+            ),
+            ActionType::KillProcess => format!("进程 {} 立即终止，未保存的状态丢失", pid),
+            ActionType::KillProcessTree => format!("进程 {} 所在进程组的全部进程立即终止", pid),
+            ActionType::IsolateNode { .. } => "节点被标记为隔离，不再调度新任务".to_string(),
+            ActionType::CheckCheckpoint { .. } => "只读检查，报告最新的 Checkpoint 文件".to_string(),
+            ActionType::GpuReset { gpu_id } => {
+                format!("GPU {} 上的进程（含进程 {}）被终止，设备重置后恢复可用", gpu_id, pid)
+            }
+            ActionType::Plugin { name, .. } => format!("由外部执行器 {} 处理进程 {}，效果取决于插件本身", name, pid),
+            ActionType::Custom { .. } => "执行自定义命令，效果取决于命令本身".to_string(),
+        }
+    }
+
+    /// 动作类型名（与序列化的 type 字段一致，用作指标标签）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ActionType::Signal { .. } => "signal",
+            ActionType::CgroupThrottle { .. } => "cgroup_throttle",
+            ActionType::NetworkRestart { .. } => "network_restart",
+            ActionType::NetworkThrottle { .. } => "network_throttle",
+            ActionType::GracefulShutdown { .. } => "graceful_shutdown",
+            ActionType::Escalate { .. } => "escalate",
+            ActionType::KillProcess => "kill_process",
+            ActionType::KillProcessTree => "kill_process_tree",
+            ActionType::IsolateNode { .. } => "isolate_node",
+            ActionType::CheckCheckpoint { .. } => "check_checkpoint",
+            ActionType::GpuReset { .. } => "gpu_reset",
+            ActionType::Plugin { .. } => "plugin",
+            ActionType::Custom { .. } => "custom",
+        }
+    }
+
+    /// 是否为高危（不可逆）动作：终止进程（SIGTERM / SIGKILL，含逐级处置和优雅降级）、重启网络接口、隔离节点、
+    /// 重置 GPU，以及效果未知的外部执行器和自定义命令。这类动作需要破窗审批 token
+    pub fn is_destructive(&self) -> bool {
+        match self {
+            ActionType::KillProcess
+            | ActionType::KillProcessTree
+            | ActionType::NetworkRestart { .. }
+            | ActionType::IsolateNode { .. }
+            | ActionType::GpuReset { .. }
+            | ActionType::Plugin { .. }
+            | ActionType::Custom { .. } => true,
+            ActionType::Signal { signal } => is_terminating_signal(*signal),
+            ActionType::GracefulShutdown { signal, force_kill, .. } => *force_kill || is_terminating_signal(*signal),
+            ActionType::Escalate { steps } => steps.iter().any(|step| is_terminating_signal(step.signal)),
+            ActionType::CgroupThrottle { .. } | ActionType::NetworkThrottle { .. } | ActionType::CheckCheckpoint { .. } => false,
+        }
+    }
+}
+
+/// 终止进程的信号（SIGTERM、SIGKILL）
+fn is_terminating_signal(signal: i32) -> bool {
+    matches!(signal, 9 | 15)
+}
+
+/// 规则文件中声明的动作直接映射为执行动作（无需从文本推断）
+impl From<&RuleAction> for ActionType {
+    fn from(action: &RuleAction) -> Self {
+        match action.clone() {
+            RuleAction::Signal { signal } => ActionType::Signal { signal },
+            RuleAction::CgroupThrottle { cpu_quota, memory_limit, io_limit } => {
+                ActionType::CgroupThrottle { cpu_quota, memory_limit, io_limit }
+            }
+            RuleAction::NetworkRestart { interface } => ActionType::NetworkRestart { interface },
+            RuleAction::NetworkThrottle { interface, rate_mbps } => ActionType::NetworkThrottle { interface, rate_mbps },
+            RuleAction::GracefulShutdown { signal, wait_seconds, force_kill } => {
+                ActionType::GracefulShutdown { signal, wait_seconds, force_kill }
+            }
+            RuleAction::Escalate { steps } => ActionType::Escalate { steps },
+            RuleAction::KillProcess => ActionType::KillProcess,
+            RuleAction::IsolateNode { reason } => ActionType::IsolateNode { reason },
+            RuleAction::CheckCheckpoint { checkpoint_dir } => ActionType::CheckCheckpoint { checkpoint_dir },
+            RuleAction::GpuReset { gpu_id } => ActionType::GpuReset { gpu_id },
+            RuleAction::Plugin { name, params } => ActionType::Plugin { name, params },
+            RuleAction::Custom { command, args } => ActionType::Custom { command, args },
+        }
+    }
+}
+
+/// 从文本中解析 GPU 编号（"gpu 3" / "gpu-3" / "gpu3"，或 nvidia-smi 的 "-i 3"）
+fn parse_gpu_id(text_lower: &str) -> Option<u32> {
+    let number_after = |marker: &str| {
+        text_lower.rmatch_indices(marker).find_map(|(idx, _)| {
+            let digits: String = text_lower[idx + marker.len()..]
+                .trim_start_matches([' ', '-', ':', '#'])
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+            digits.parse().ok()
+        })
+    };
+    number_after("gpu").or_else(|| number_after("-i "))
+}
+
+/// 文本推断时使用的默认逐级处置：SIGUSR1 保存 Checkpoint → SIGTERM → SIGKILL
+fn default_escalation() -> Vec<EscalationStep> {
+    [(10, 30), (15, 10), (9, 0)]
+        .into_iter()
+        .map(|(signal, wait_seconds)| EscalationStep { signal, wait_seconds })
+        .collect()
+}
+
+/// 逐级处置的各级（如 "SIGUSR1 等待 30 秒 → SIGTERM 等待 10 秒 → SIGKILL"）
+fn escalation_ladder(steps: &[EscalationStep]) -> String {
+    steps
+        .iter()
+        .map(|step| {
+            let signal = match signal_name(step.signal) {
+                "UNKNOWN" => format!("信号 {}", step.signal),
+                name => name.to_string(),
+            };
+            match step.wait_seconds {
+                0 => signal,
+                wait => format!("{} 等待 {} 秒", signal, wait),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" → ")
 }
 
 /// 获取信号名称
-fn signal_name(sig: i32) -> &'static str {
+pub(crate) fn signal_name(sig: i32) -> &'static str {
     match sig {
         1 => "SIGHUP",
         2 => "SIGINT",
@@ -154,3 +378,42 @@ fn signal_name(sig: i32) -> &'static str {
         _ => "UNKNOWN",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_destructive() {
+        let destructive = [
+            ActionType::Signal { signal: 9 },
+            ActionType::Signal { signal: 15 },
+            ActionType::NetworkRestart { interface: "eth0".to_string() },
+            ActionType::GracefulShutdown { signal: 10, wait_seconds: 10, force_kill: true },
+            ActionType::GracefulShutdown { signal: 15, wait_seconds: 10, force_kill: false },
+            ActionType::Escalate { steps: default_escalation() },
+            ActionType::Escalate { steps: vec![EscalationStep { signal: 15, wait_seconds: 0 }] },
+            ActionType::KillProcess,
+            ActionType::KillProcessTree,
+            ActionType::IsolateNode { reason: "xid 79".to_string() },
+            ActionType::GpuReset { gpu_id: 0 },
+            ActionType::Plugin { name: "drain".to_string(), params: serde_json::Value::Null },
+            ActionType::Custom { command: "true".to_string(), args: Vec::new() },
+        ];
+        for action in &destructive {
+            assert!(action.is_destructive(), "{:?} 应为高危动作", action);
+        }
+
+        let safe = [
+            ActionType::Signal { signal: 10 },
+            ActionType::CgroupThrottle { cpu_quota: Some(50_000), memory_limit: None, io_limit: None },
+            ActionType::NetworkThrottle { interface: "eth0".to_string(), rate_mbps: 1000 },
+            ActionType::GracefulShutdown { signal: 10, wait_seconds: 10, force_kill: false },
+            ActionType::Escalate { steps: vec![EscalationStep { signal: 10, wait_seconds: 30 }] },
+            ActionType::CheckCheckpoint { checkpoint_dir: "/ckpt".to_string() },
+        ];
+        for action in &safe {
+            assert!(!action.is_destructive(), "{:?} 不应为高危动作", action);
+        }
+    }
+}
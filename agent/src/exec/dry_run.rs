@@ -0,0 +1,192 @@
+use crate::exec::action::ActionType;
+use crate::exec::executor::{cgroup_path, tc_throttle_args, tc_unthrottle_args, Executor};
+use crate::exec::registry::request_json;
+use crate::exec::rollback::RollbackAction;
+use async_trait::async_trait;
+
+/// 预演执行器（`ark fix --dry-run`）
+///
+/// 与 `ActionExecutor` 相同的接口，不修改系统，只按 `ActionExecutor` 的执行方式列出将要运行的命令、
+/// 发送的信号和写入的 cgroup 文件（含解析出的进程组和 cgroup 路径），每行一步
+pub struct DryRunExecutor;
+
+impl DryRunExecutor {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 动作对应的执行步骤
+    async fn steps(&self, action: &ActionType, pid: u32) -> Result<Vec<String>, String> {
+        let steps = match action {
+            ActionType::Signal { signal } => vec![signal_step(*signal, pid)?],
+            ActionType::CgroupThrottle { cpu_quota, memory_limit, io_limit } => {
+                if cfg!(windows) {
+                    let mut steps = vec![format!("CreateJobObject ark-throttle-{}", pid)];
+                    if let Some(quota) = cpu_quota {
+                        steps.push(format!("CPU 硬上限: {}%", quota / 1000));
+                    }
+                    if let Some(limit) = memory_limit {
+                        steps.push(format!("进程内存上限: {}MB", limit / 1024 / 1024));
+                    }
+                    steps.push(format!("AssignProcessToJobObject: {}", pid));
+                    return Ok(steps);
+                }
+                let path = cgroup_path(pid);
+                let mut steps = vec![format!("mkdir -p {}", path)];
+                if let Some(quota) = cpu_quota {
+                    steps.push(format!("写入 {}/cpu.cfs_quota_us: {}", path, quota));
+                }
+                if let Some(limit) = memory_limit {
+                    steps.push(format!("写入 {}/memory.limit_in_bytes: {}", path, limit));
+                }
+                if io_limit.is_some() {
+                    steps.push("io_limit 暂不支持，不会写入".to_string());
+                }
+                steps.push(format!("写入 {}/tasks: {}", path, pid));
+                steps
+            }
+            ActionType::NetworkRestart { interface } => {
+                if cfg!(windows) {
+                    return Err("Windows 网络接口重启需要管理员权限".to_string());
+                }
+                vec![
+                    format!("ip link set down {}", interface),
+                    "等待 1 秒".to_string(),
+                    format!("ip link set up {}", interface),
+                ]
+            }
+            ActionType::NetworkThrottle { interface, rate_mbps } => {
+                if cfg!(windows) {
+                    return Err("Windows 不支持 tc 网络限速".to_string());
+                }
+                tc_throttle_args(interface, *rate_mbps).iter().map(|args| format!("tc {}", args.join(" "))).collect()
+            }
+            ActionType::GracefulShutdown { signal, wait_seconds, force_kill } => {
+                let mut steps = match signal_step(*signal, pid) {
+                    Ok(step) => vec![step],
+                    // 与实际执行一致：发信号失败但需要强制终止时继续
+                    Err(e) if *force_kill => vec![format!("{}（将继续强制终止）", e)],
+                    Err(e) => return Err(e),
+                };
+                steps.push(format!("等待 {} 秒", wait_seconds));
+                if *force_kill {
+                    steps.push(kill_step(pid));
+                }
+                steps
+            }
+            ActionType::Escalate { steps } => {
+                let mut planned = Vec::new();
+                for step in steps {
+                    planned.push(signal_step(step.signal, pid)?);
+                    planned.push(format!("等待最多 {} 秒，进程退出后停止", step.wait_seconds));
+                }
+                planned
+            }
+            ActionType::KillProcess => vec![kill_step(pid)],
+            ActionType::KillProcessTree => vec![kill_tree_step(pid).await],
+            ActionType::IsolateNode { reason } => vec![format!("标记节点隔离（不执行系统命令）: {}", reason)],
+            ActionType::CheckCheckpoint { checkpoint_dir } => {
+                vec![format!("读取目录 {}（只读）", checkpoint_dir)]
+            }
+            ActionType::GpuReset { gpu_id } => gpu_reset_steps(*gpu_id, pid).await?,
+            ActionType::Plugin { name, params } => {
+                vec![format!("调用外部执行器 {}，stdin: {}", name, request_json(name, pid, params))]
+            }
+            ActionType::Custom { command, args } => {
+                vec![std::iter::once(command.as_str()).chain(args.iter().map(String::as_str)).collect::<Vec<_>>().join(" ")]
+            }
+        };
+        Ok(steps)
+    }
+}
+
+impl Default for DryRunExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Executor for DryRunExecutor {
+    async fn execute(&self, action: &ActionType, pid: u32) -> Result<String, String> {
+        Ok(self.steps(action, pid).await?.join("\n"))
+    }
+
+    async fn rollback(&self, rollback: &RollbackAction) -> Result<String, String> {
+        let steps = match rollback {
+            RollbackAction::CgroupRestore { pid, .. } if cfg!(windows) => {
+                vec![format!("清除 Job Object ark-throttle-{} 的 CPU / 内存限制", pid)]
+            }
+            RollbackAction::CgroupRestore { pid, cgroup_path, original_cgroup } => {
+                let mut steps = Vec::new();
+                if let Some(original) = original_cgroup {
+                    steps.push(format!("写入 /sys/fs/cgroup{}/cgroup.procs: {}", original, pid));
+                }
+                steps.push(format!("rmdir {}", cgroup_path));
+                steps
+            }
+            RollbackAction::NetworkUp { interface } => vec![format!("ip link set up {}", interface)],
+            RollbackAction::NetworkUnthrottle { interface } => vec![format!("tc {}", tc_unthrottle_args(interface).join(" "))],
+            RollbackAction::Unisolate { reason } => vec![format!("取消节点隔离（不执行系统命令）: {}", reason)],
+        };
+        Ok(steps.join("\n"))
+    }
+}
+
+fn signal_step(signal: i32, pid: u32) -> Result<String, String> {
+    if cfg!(windows) {
+        return match signal {
+            9 => Ok(format!("TerminateProcess {}", pid)),
+            2 => Ok(format!("向进程 {} 的控制台发送 CTRL_C_EVENT", pid)),
+            1 | 3 | 15 => Ok(format!("向进程 {} 的控制台发送 CTRL_BREAK_EVENT（没有控制台时向其窗口发送 WM_CLOSE）", pid)),
+            _ => Err(format!("Windows 上没有与信号 {} 对应的机制", signal)),
+        };
+    }
+    Ok(format!("kill -{} {}", signal, pid))
+}
+
+fn kill_step(pid: u32) -> String {
+    if cfg!(windows) {
+        format!("taskkill /F /PID {}", pid)
+    } else {
+        format!("kill -9 {}", pid)
+    }
+}
+
+/// 进程树按进程组终止：能解析出进程组时给出实际的 pgid，否则与实际执行一样退回到只终止主进程
+async fn kill_tree_step(pid: u32) -> String {
+    #[cfg(unix)]
+    {
+        match crate::exec::SystemActuator::new().get_process_group(pid).await {
+            Ok(pgid) => format!("kill -9 -{}（进程 {} 所在的进程组）", pgid, pid),
+            Err(e) => format!("kill -9 {}（无法解析进程组: {}）", pid, e),
+        }
+    }
+    #[cfg(windows)]
+    {
+        format!("taskkill /F /T /PID {}", pid)
+    }
+}
+
+/// GPU 重置：与实际执行一样先做安全检查（只读查询），列出要清空的进程
+async fn gpu_reset_steps(gpu_id: u32, pid: u32) -> Result<Vec<String>, String> {
+    #[cfg(unix)]
+    {
+        use crate::exec::executor::{gpu_drain_targets, GPU_DRAIN_SECONDS};
+
+        let drain = gpu_drain_targets(gpu_id, pid).await?;
+        let mut steps = vec![format!("nvidia-smi --query-compute-apps=pid -i {}（GPU 上只有进程 {} 所在进程组的进程）", gpu_id, pid)];
+        if !drain.is_empty() {
+            let pids: Vec<String> = drain.iter().map(|p| p.to_string()).collect();
+            steps.push(format!("kill -15 {}", pids.join(" ")));
+            steps.push(format!("等待最多 {} 秒，仍在运行的进程 kill -9", GPU_DRAIN_SECONDS));
+        }
+        steps.push(format!("nvidia-smi --gpu-reset -i {}", gpu_id));
+        Ok(steps)
+    }
+    #[cfg(windows)]
+    {
+        let _ = (gpu_id, pid);
+        Err("Windows 暂不支持 GPU 重置".to_string())
+    }
+}
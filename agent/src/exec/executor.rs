@@ -1,20 +1,186 @@
-use crate::exec::action::ActionType;
+use crate::exec::action::{signal_name, ActionType};
+use crate::exec::limiter::ActionLimiter;
+use crate::exec::preflight;
+use crate::exec::registry::ActuatorRegistry;
+use crate::exec::rollback::RollbackAction;
+use crate::exec::SystemActuator;
+use crate::plugin::Actuator;
+use ark_core::event::{Event, EventType};
+use ark_core::rules::EscalationStep;
+use async_trait::async_trait;
 use std::process::Stdio;
+use std::sync::Arc;
 use tokio::process::Command;
+use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration};
 
+/// 动作执行接口：`ActionExecutor` 实际执行，`DryRunExecutor` 只给出将要执行的操作
+#[async_trait]
+pub trait Executor: Send + Sync {
+    /// 对目标进程执行动作，返回执行结果描述
+    async fn execute(&self, action: &ActionType, pid: u32) -> Result<String, String>;
+
+    /// 撤销一个已执行的可回滚动作
+    async fn rollback(&self, rollback: &RollbackAction) -> Result<String, String>;
+}
+
+/// 进程限流使用的 cgroup 目录
+pub(crate) fn cgroup_path(pid: u32) -> String {
+    format!("/sys/fs/cgroup/ark/ark-{}", pid)
+}
+
+/// GPU 重置前等待进程退出的时间（超时后强制终止）
+#[cfg(unix)]
+pub(crate) const GPU_DRAIN_SECONDS: u64 = 10;
+
+/// GPU 上的计算进程（`nvidia-smi --query-compute-apps=pid -i <gpu_id>`）
+#[cfg(unix)]
+pub(crate) async fn gpu_processes(gpu_id: u32) -> Result<Vec<u32>, String> {
+    let output = Command::new("nvidia-smi")
+        .arg("--query-compute-apps=pid")
+        .arg("--format=csv,noheader,nounits")
+        .arg("-i")
+        .arg(gpu_id.to_string())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|e| format!("执行 nvidia-smi 失败: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("查询 GPU {} 进程失败: {}", gpu_id, stderr.trim()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().parse().ok())
+        .collect())
+}
+
+/// GPU 重置的安全检查：返回需要清空的进程（目标进程及其进程组内的进程）
+///
+/// GPU 上有不属于目标进程组的进程时视为其他作业在使用，拒绝重置
+#[cfg(unix)]
+pub(crate) async fn gpu_drain_targets(gpu_id: u32, pid: u32) -> Result<Vec<u32>, String> {
+    let actuator = SystemActuator::new();
+    let target_group = actuator.get_process_group(pid).await.ok();
+    let mut drain = Vec::new();
+    let mut others = Vec::new();
+    for gpu_pid in gpu_processes(gpu_id).await? {
+        let same_job = gpu_pid == pid
+            || (target_group.is_some() && actuator.get_process_group(gpu_pid).await.ok() == target_group);
+        if same_job {
+            drain.push(gpu_pid);
+        } else {
+            others.push(gpu_pid.to_string());
+        }
+    }
+    if !others.is_empty() {
+        return Err(format!(
+            "GPU {} 上还有其他作业的进程 ({})，拒绝重置",
+            gpu_id,
+            others.join(", ")
+        ));
+    }
+    Ok(drain)
+}
+
+/// 网络限速的 tc 参数：root qdisc 替换为 HTB（handle 1:，默认 class 1:10），class 1:10 限速
+pub(crate) fn tc_throttle_args(interface: &str, rate_mbps: u64) -> Vec<Vec<String>> {
+    let rate = format!("{}mbit", rate_mbps);
+    [
+        vec!["qdisc", "replace", "dev", interface, "root", "handle", "1:", "htb", "default", "10"],
+        vec!["class", "replace", "dev", interface, "parent", "1:", "classid", "1:10", "htb", "rate", &rate, "ceil", &rate],
+    ]
+    .into_iter()
+    .map(|args| args.into_iter().map(str::to_string).collect())
+    .collect()
+}
+
+/// 撤销网络限速的 tc 参数：删除 root qdisc，恢复内核默认 qdisc
+pub(crate) fn tc_unthrottle_args(interface: &str) -> Vec<String> {
+    ["qdisc", "del", "dev", interface, "root"].into_iter().map(str::to_string).collect()
+}
+
+#[cfg(windows)]
+use crate::exec::windows::process_exited;
+
+/// 进程是否已退出（/proc 中不存在或已成为僵尸进程）
+#[cfg(unix)]
+fn process_exited(pid: u32) -> bool {
+    match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        // 格式：pid (comm) state ...，comm 中可能有空格，取最后一个 ')' 之后的字段
+        Ok(stat) => stat.rsplit_once(')').and_then(|(_, rest)| rest.split_whitespace().next()) == Some("Z"),
+        Err(_) => true,
+    }
+}
+
 /// 动作执行器
 /// 
 /// 负责执行各种类型的动作
-pub struct ActionExecutor;
+pub struct ActionExecutor {
+    /// `plugin` 动作使用的外部执行器
+    actuators: Arc<ActuatorRegistry>,
+    /// 动作冷却（未设置时不限制）
+    limiter: Option<Arc<ActionLimiter>>,
+    /// 目标进程所属 job（动作冷却和 action.exec 事件使用）
+    job: Option<String>,
+    /// 执行结果以 action.exec 事件发回的事件总线
+    events: Option<mpsc::Sender<Event>>,
+}
 
 impl ActionExecutor {
     pub fn new() -> Self {
-        Self
+        Self::with_actuators(Arc::new(ActuatorRegistry::default()))
     }
-    
-    /// 执行动作
-    pub async fn execute(&self, action: &ActionType, pid: u32) -> Result<String, String> {
+
+    pub fn with_actuators(actuators: Arc<ActuatorRegistry>) -> Self {
+        Self {
+            actuators,
+            limiter: None,
+            job: None,
+            events: None,
+        }
+    }
+
+    /// 执行前检查动作冷却：同一进程或 job 在窗口内重复执行同类动作时拒绝
+    pub fn with_limiter(mut self, limiter: Arc<ActionLimiter>) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// 设置目标进程所属 job
+    pub fn with_job(mut self, job: Option<String>) -> Self {
+        self.job = job;
+        self
+    }
+
+    /// 每次执行（含回滚）后向事件总线发送 action.exec 事件，使干预进入状态图、指标并推送到 Hub
+    pub fn with_events(mut self, events: mpsc::Sender<Event>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 发送 action.exec 事件：entity_id 为动作类型，value 为 "ok: ..." 或 "failed: ..."
+    ///
+    /// 与规则命中事件一样用 try_send，总线已满时丢弃事件而不阻塞修复
+    fn publish(&self, kind: &str, pid: Option<u32>, result: &Result<String, String>) {
+        let Some(ref events) = self.events else {
+            return;
+        };
+        let value = match result {
+            Ok(msg) => format!("ok: {}", msg),
+            Err(e) => format!("failed: {}", e),
+        };
+        let event = Event::new(EventType::ActionExec, kind.to_string(), value, self.job.clone(), pid);
+        if let Err(e) = events.try_send(event) {
+            eprintln!("[ark] 发送 action.exec 事件失败: {}", e);
+        }
+    }
+
+    /// 按动作类型执行（不检查冷却、不发送事件）
+    async fn perform(&self, action: &ActionType, pid: u32) -> Result<String, String> {
         match action {
             ActionType::Signal { signal } => {
                 self.send_signal(*signal, pid).await
@@ -25,23 +191,84 @@ impl ActionExecutor {
             ActionType::NetworkRestart { interface } => {
                 self.restart_network_interface(interface).await
             }
+            ActionType::NetworkThrottle { interface, rate_mbps } => {
+                self.throttle_network(interface, *rate_mbps).await
+            }
             ActionType::GracefulShutdown { signal, wait_seconds, force_kill } => {
                 self.graceful_shutdown(*signal, *wait_seconds, *force_kill, pid).await
             }
+            ActionType::Escalate { steps } => {
+                self.escalate(steps, pid).await
+            }
             ActionType::KillProcess => {
                 self.kill_process(pid).await
             }
+            ActionType::KillProcessTree => {
+                SystemActuator::new()
+                    .execute(pid, "zap")
+                    .await
+                    .map(|_| format!("成功终止进程树 {}", pid))
+            }
             ActionType::IsolateNode { reason } => {
                 self.isolate_node(reason).await
             }
             ActionType::CheckCheckpoint { checkpoint_dir } => {
                 self.check_checkpoint(checkpoint_dir).await
             }
+            ActionType::GpuReset { gpu_id } => {
+                self.reset_gpu(*gpu_id, pid).await
+            }
+            ActionType::Plugin { name, params } => {
+                self.actuators.invoke(name, pid, params).await
+            }
             ActionType::Custom { command, args } => {
                 self.execute_custom_command(command, args).await
             }
         }
     }
+}
+
+#[async_trait]
+impl Executor for ActionExecutor {
+    /// 执行动作
+    async fn execute(&self, action: &ActionType, pid: u32) -> Result<String, String> {
+        // 权限不足时不计入冷却：补齐权限后可以立即重试
+        preflight::check(action, pid)?;
+        if let Some(ref limiter) = self.limiter {
+            limiter.acquire(action, pid, self.job.as_deref())?;
+        }
+        let result = self.perform(action, pid).await;
+        self.publish(action.kind(), Some(pid), &result);
+        result
+    }
+
+    async fn rollback(&self, rollback: &RollbackAction) -> Result<String, String> {
+        let (result, pid) = match rollback {
+            RollbackAction::CgroupRestore { pid, cgroup_path, original_cgroup } => (
+                self.restore_cgroup(*pid, cgroup_path, original_cgroup.as_deref()).await,
+                Some(*pid),
+            ),
+            RollbackAction::NetworkUp { interface } => (
+                self.set_link(interface, "up")
+                    .await
+                    .map(|_| format!("已启用网络接口: {}", interface))
+                    .map_err(|e| format!("启动接口失败: {}", e)),
+                None,
+            ),
+            RollbackAction::NetworkUnthrottle { interface } => (
+                self.run_tc(&tc_unthrottle_args(interface))
+                    .await
+                    .map(|_| format!("已撤销网络限速: {}", interface)),
+                None,
+            ),
+            RollbackAction::Unisolate { reason } => (Ok(format!("已取消节点隔离: {}", reason)), None),
+        };
+        self.publish("rollback", pid, &result);
+        result
+    }
+}
+
+impl ActionExecutor {
     
     /// 发送信号
     async fn send_signal(&self, signal: i32, pid: u32) -> Result<String, String> {
@@ -66,11 +293,11 @@ impl ActionExecutor {
         
         #[cfg(windows)]
         {
-            Err("Windows 不支持信号发送".to_string())
+            crate::exec::windows::send_signal(signal, pid)
         }
     }
     
-    /// 应用 Cgroup 限流
+    /// 应用 Cgroup 限流（Windows 上使用 Job Object，见 `exec::windows`）
     async fn apply_cgroup_throttle(
         &self,
         pid: u32,
@@ -81,8 +308,7 @@ impl ActionExecutor {
         #[cfg(unix)]
         {
             // 创建临时 cgroup
-            let cgroup_name = format!("ark-{}", pid);
-            let cgroup_path = format!("/sys/fs/cgroup/ark/{}", cgroup_name);
+            let cgroup_path = cgroup_path(pid);
             
             // 创建 cgroup 目录
             let _ = Command::new("mkdir")
@@ -124,60 +350,138 @@ impl ActionExecutor {
         
         #[cfg(windows)]
         {
-            Err("Windows 不支持 Cgroup".to_string())
+            let _ = io_limit;
+            let applied = crate::exec::windows::apply_job_limits(pid, cpu_quota, memory_limit)?;
+            Ok(format!("Job Object 限流应用成功: {}", applied.join(", ")))
         }
     }
     
     /// 重启网络接口
     async fn restart_network_interface(&self, interface: &str) -> Result<String, String> {
+        // 先 down
+        self.set_link(interface, "down")
+            .await
+            .map_err(|e| format!("关闭接口失败: {}", e))?;
+        
+        // 等待 1 秒
+        sleep(Duration::from_secs(1)).await;
+        
+        // 再 up
+        self.set_link(interface, "up")
+            .await
+            .map_err(|e| format!("启动接口失败: {}", e))?;
+        
+        Ok(format!("成功重启网络接口: {}", interface))
+    }
+    
+    /// 网络限速：把接口的 root qdisc 替换为 HTB，默认流量进入限速 class
+    async fn throttle_network(&self, interface: &str, rate_mbps: u64) -> Result<String, String> {
+        for args in tc_throttle_args(interface, rate_mbps) {
+            self.run_tc(&args).await?;
+        }
+        Ok(format!("已将网络接口 {} 出向带宽限制为 {} Mbit/s", interface, rate_mbps))
+    }
+    
+    /// 执行 tc 命令
+    async fn run_tc(&self, args: &[String]) -> Result<(), String> {
         #[cfg(unix)]
         {
-            // 先 down
-            let down_output = Command::new("ip")
-                .arg("link")
-                .arg("set")
-                .arg("down")
-                .arg(interface)
+            let output = Command::new("tc")
+                .args(args)
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
                 .output()
                 .await
-                .map_err(|e| format!("执行 ip link set down 失败: {}", e))?;
+                .map_err(|e| format!("执行 tc 失败: {}", e))?;
             
-            if !down_output.status.success() {
-                let stderr = String::from_utf8_lossy(&down_output.stderr);
-                return Err(format!("关闭接口失败: {}", stderr));
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(format!("tc {} 失败: {}", args.join(" "), stderr.trim()));
             }
             
-            // 等待 1 秒
-            sleep(Duration::from_secs(1)).await;
-            
-            // 再 up
-            let up_output = Command::new("ip")
+            Ok(())
+        }
+        
+        #[cfg(windows)]
+        {
+            let _ = args;
+            Err("Windows 不支持 tc 网络限速".to_string())
+        }
+    }
+    
+    /// 设置网络接口状态（`ip link set <up|down> <interface>`）
+    async fn set_link(&self, interface: &str, state: &str) -> Result<(), String> {
+        #[cfg(unix)]
+        {
+            let output = Command::new("ip")
                 .arg("link")
                 .arg("set")
-                .arg("up")
+                .arg(state)
                 .arg(interface)
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
                 .output()
                 .await
-                .map_err(|e| format!("执行 ip link set up 失败: {}", e))?;
+                .map_err(|e| format!("执行 ip link set {} 失败: {}", state, e))?;
             
-            if !up_output.status.success() {
-                let stderr = String::from_utf8_lossy(&up_output.stderr);
-                return Err(format!("启动接口失败: {}", stderr));
+            if !output.status.success() {
+                return Err(String::from_utf8_lossy(&output.stderr).to_string());
             }
             
-            Ok(format!("成功重启网络接口: {}", interface))
+            Ok(())
         }
         
         #[cfg(windows)]
         {
+            let _ = (interface, state);
             Err("Windows 网络接口重启需要管理员权限".to_string())
         }
     }
     
+    /// 撤销 Cgroup 限流：进程移回原 cgroup（v2 写 cgroup.procs，v1 写 tasks），再删除 ark 创建的 cgroup
+    async fn restore_cgroup(
+        &self,
+        pid: u32,
+        cgroup_path: &str,
+        original_cgroup: Option<&str>,
+    ) -> Result<String, String> {
+        #[cfg(unix)]
+        {
+            let mut results = Vec::new();
+            
+            if let Some(original) = original_cgroup {
+                let dir = format!("/sys/fs/cgroup{}", original);
+                let procs_file = ["cgroup.procs", "tasks"]
+                    .iter()
+                    .map(|name| format!("{}/{}", dir, name))
+                    .find(|file| std::path::Path::new(file).exists())
+                    .ok_or_else(|| format!("原 cgroup {} 不存在", dir))?;
+                match tokio::fs::write(&procs_file, pid.to_string()).await {
+                    Ok(()) => results.push(format!("进程 {} 已移回 {}", pid, original)),
+                    // 进程已退出（ESRCH），无需移回
+                    Err(e) if e.raw_os_error() == Some(3) => {
+                        results.push(format!("进程 {} 已不存在", pid));
+                    }
+                    Err(e) => return Err(format!("将进程移回原 cgroup 失败: {}", e)),
+                }
+            }
+            
+            match tokio::fs::remove_dir(cgroup_path).await {
+                Ok(()) => results.push(format!("已删除 {}", cgroup_path)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(format!("删除 cgroup {} 失败: {}", cgroup_path, e)),
+            }
+            
+            Ok(format!("Cgroup 限流已撤销: {}", results.join(", ")))
+        }
+        
+        #[cfg(windows)]
+        {
+            let _ = (cgroup_path, original_cgroup);
+            crate::exec::windows::clear_job_limits(pid)
+        }
+    }
+    
     /// 优雅降级
     async fn graceful_shutdown(
         &self,
@@ -211,6 +515,38 @@ impl ActionExecutor {
         }
     }
     
+    /// 逐级处置：每级发送信号后等待进程退出，退出后立即停止，返回生效的那一级
+    async fn escalate(&self, steps: &[EscalationStep], pid: u32) -> Result<String, String> {
+        for (idx, step) in steps.iter().enumerate() {
+            let name = signal_name(step.signal);
+            if process_exited(pid) {
+                return Ok(format!("进程 {} 在第 {} 级（{}）之前已退出", pid, idx + 1, name));
+            }
+            if let Err(e) = self.send_signal(step.signal, pid).await {
+                // 检查之后、发信号之前退出
+                if process_exited(pid) {
+                    return Ok(format!("进程 {} 在第 {} 级（{}）之前已退出", pid, idx + 1, name));
+                }
+                return Err(format!("第 {} 级（{}）{}", idx + 1, name, e));
+            }
+            // 每 200ms 检查一次，至少检查一次（wait_seconds 为 0 时给信号处理留出时间）
+            let polls = (step.wait_seconds * 5).max(1);
+            for _ in 0..polls {
+                sleep(Duration::from_millis(200)).await;
+                if process_exited(pid) {
+                    return Ok(format!(
+                        "进程 {} 在第 {}/{} 级（{}）后退出",
+                        pid,
+                        idx + 1,
+                        steps.len(),
+                        name
+                    ));
+                }
+            }
+        }
+        Err(format!("逐级处置 {} 级后进程 {} 仍在运行", steps.len(), pid))
+    }
+    
     /// 终止进程
     async fn kill_process(&self, pid: u32) -> Result<String, String> {
         #[cfg(unix)]
@@ -264,6 +600,61 @@ impl ActionExecutor {
         Ok(format!("节点已隔离: {}", reason))
     }
     
+    /// 重置 GPU：安全检查 → SIGTERM 清空设备上的进程（超时后 SIGKILL）→ nvidia-smi --gpu-reset
+    async fn reset_gpu(&self, gpu_id: u32, pid: u32) -> Result<String, String> {
+        #[cfg(unix)]
+        {
+            let drain = gpu_drain_targets(gpu_id, pid).await?;
+            
+            // 清空设备：先让进程自行退出，超时后强制终止
+            for target in &drain {
+                let _ = self.send_signal(15, *target).await;
+            }
+            let mut remaining = gpu_processes(gpu_id).await?;
+            for _ in 0..GPU_DRAIN_SECONDS {
+                if remaining.is_empty() {
+                    break;
+                }
+                sleep(Duration::from_secs(1)).await;
+                remaining = gpu_processes(gpu_id).await?;
+            }
+            for target in remaining.iter().filter(|p| drain.contains(p)) {
+                self.kill_process(*target).await?;
+            }
+            sleep(Duration::from_secs(1)).await;
+            let remaining = gpu_processes(gpu_id).await?;
+            if !remaining.is_empty() {
+                let pids: Vec<String> = remaining.iter().map(|p| p.to_string()).collect();
+                return Err(format!("GPU {} 上仍有进程 ({})，未执行重置", gpu_id, pids.join(", ")));
+            }
+            
+            // 设备重置（nvidia-smi 通过 NVML 的 nvmlDeviceResetGpu 完成）
+            let output = Command::new("nvidia-smi")
+                .arg("--gpu-reset")
+                .arg("-i")
+                .arg(gpu_id.to_string())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .output()
+                .await
+                .map_err(|e| format!("执行 nvidia-smi --gpu-reset 失败: {}", e))?;
+            
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                return Err(format!("GPU {} 重置失败: {}{}", gpu_id, stdout.trim(), stderr.trim()));
+            }
+            
+            Ok(format!("GPU {} 已重置（清空 {} 个进程）", gpu_id, drain.len()))
+        }
+        
+        #[cfg(windows)]
+        {
+            let _ = (gpu_id, pid);
+            Err("Windows 暂不支持 GPU 重置".to_string())
+        }
+    }
+    
     /// 检查 Checkpoint
     async fn check_checkpoint(&self, checkpoint_dir: &str) -> Result<String, String> {
         use tokio::fs;
@@ -313,6 +704,8 @@ impl ActionExecutor {
             .args(args)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
+            // 超过执行时限被取消时终止命令
+            .kill_on_drop(true)
             .output()
             .await
             .map_err(|e| format!("执行命令失败: {}", e))?;
@@ -1,33 +1,100 @@
 use crate::exec::action::ActionType;
-use crate::exec::executor::ActionExecutor;
+use crate::exec::dry_run::DryRunExecutor;
+use crate::exec::executor::{ActionExecutor, Executor};
+use crate::exec::limiter::RATE_LIMITED;
+use crate::exec::preflight::MISSING_PRIVILEGE;
+use crate::exec::policy::{ActionPolicy, PolicyLevel, PolicySubject};
+use crate::exec::rollback::{RollbackAction, RollbackOutcome};
 use crate::scene::AnalysisResult;
+use ark_core::rules::Rule;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// ark fix 执行引擎
 /// 
 /// 这是 OODA 循环中的 Act 层，负责执行诊断结果中的 recommended_actions
 pub struct FixEngine {
-    executor: ActionExecutor,
+    executor: Box<dyn Executor>,
+    /// 动作策略和作用对象：计划中标注每步的级别，执行时拒绝包含禁止动作的计划
+    policy: ActionPolicy,
+    subject: PolicySubject,
+    /// 单个动作（含回滚操作）的执行时限，超时后取消该动作（None 时不限制）
+    step_timeout: Option<Duration>,
 }
 
 impl FixEngine {
     pub fn new() -> Self {
+        Self::with_executor(Box::new(ActionExecutor::new()))
+    }
+
+    /// 使用配置好的执行器（daemon 使用：外部执行器插件、动作冷却）
+    pub fn with_action_executor(executor: ActionExecutor) -> Self {
+        Self::with_executor(Box::new(executor))
+    }
+
+    /// 预演模式：计划照常生成和“执行”，但只列出将要进行的操作，不修改系统
+    pub fn dry_run() -> Self {
+        Self::with_executor(Box::new(DryRunExecutor::new()))
+    }
+
+    fn with_executor(executor: Box<dyn Executor>) -> Self {
         Self {
-            executor: ActionExecutor::new(),
+            executor,
+            policy: ActionPolicy::default(),
+            subject: PolicySubject::default(),
+            step_timeout: None,
+        }
+    }
+
+    /// 按动作策略规划和执行（未设置时所有动作都是 auto）
+    pub fn with_policy(mut self, policy: ActionPolicy, subject: PolicySubject) -> Self {
+        self.policy = policy;
+        self.subject = subject;
+        self
+    }
+
+    /// 设置单个动作的执行时限：超时的动作被取消（其中启动的子进程随之终止），计为超时失败
+    pub fn with_step_timeout(mut self, step_timeout: Option<Duration>) -> Self {
+        self.step_timeout = step_timeout;
+        self
+    }
+
+    /// 从 recommended_actions 文本推断执行计划（按动作类型排优先级）
+    pub fn plan_from_analysis(&self, result: &AnalysisResult, pid: u32) -> FixPlan {
+        self.apply_policy(FixPlan::new(pid, None, self.parse_recommendations(&result.recommended_actions)))
+    }
+
+    /// 使用规则声明的动作作为执行计划，保持规则中的声明顺序
+    pub fn plan_from_rule(&self, rule: &Rule, pid: u32) -> FixPlan {
+        let actions = rule
+            .actions
+            .iter()
+            .enumerate()
+            .map(|(idx, action)| (ActionType::from(action), idx.min(u8::MAX as usize) as u8))
+            .collect();
+        self.apply_policy(FixPlan::new(pid, Some(rule.name.clone()), actions))
+    }
+
+    /// 为计划中的每一步标注策略级别
+    fn apply_policy(&self, mut plan: FixPlan) -> FixPlan {
+        for step in &mut plan.steps {
+            step.policy = self.policy.decide(&step.action, &self.subject);
         }
+        plan
     }
 
-    /// 从 AnalysisResult 解析并执行推荐动作
-    pub async fn fix_from_analysis(
+    /// 按优先级执行计划中的动作
+    ///
+    /// 某个动作失败时不再执行后续动作，已执行（含失败动作本身可能已部分生效）的可回滚动作按相反顺序自动回滚
+    pub async fn execute_plan(
         &self,
-        result: &AnalysisResult,
+        actions: Vec<(ActionType, u8)>,
         pid: u32,
     ) -> Result<FixResult, String> {
         let mut executed_actions = Vec::new();
         let mut failed_actions = Vec::new();
-        
-        // 解析 recommended_actions
-        let actions = self.parse_recommendations(&result.recommended_actions);
+        let mut rollback = Vec::new();
         
         if actions.is_empty() {
             return Ok(FixResult {
@@ -35,45 +102,148 @@ impl FixEngine {
                 message: "没有可执行的动作".to_string(),
                 executed_actions,
                 failed_actions,
+                rollback,
+                rolled_back: Vec::new(),
+                fix_id: None,
             });
         }
         
+        // 策略禁止的动作：整个计划都不执行
+        let denied: Vec<FailedAction> = actions
+            .iter()
+            .filter(|(action, _)| self.policy.decide(action, &self.subject) == PolicyLevel::Deny)
+            .map(|(action, priority)| FailedAction {
+                action: action.description(),
+                error: format!("动作策略禁止执行 {}", action.kind()),
+                kind: FailureKind::Denied,
+                priority: *priority,
+            })
+            .collect();
+        if !denied.is_empty() {
+            return Ok(FixResult {
+                success: false,
+                message: format!("{} 个动作被动作策略禁止，未执行任何动作", denied.len()),
+                executed_actions,
+                failed_actions: denied,
+                rollback,
+                rolled_back: Vec::new(),
+                fix_id: None,
+            });
+        }
+        
+        let total = actions.len();
+        
         // 按优先级执行动作
         for (action, priority) in actions {
-            match self.executor.execute(&action, pid).await {
+            let undo = RollbackAction::capture(&action, pid).await;
+            match self.execute_step(&action, pid).await {
                 Ok(msg) => {
                     executed_actions.push(ExecutedAction {
                         action: action.description(),
                         result: msg,
                         priority,
                     });
+                    rollback.extend(undo);
                 }
-                Err(e) => {
+                Err((kind, e)) => {
                     failed_actions.push(FailedAction {
                         action: action.description(),
                         error: e,
+                        kind,
                         priority,
                     });
+                    rollback.extend(undo);
+                    break;
                 }
             }
         }
         
         let success = failed_actions.is_empty();
-        let message = if success {
-            format!("成功执行 {} 个动作", executed_actions.len())
-        } else {
-            format!("执行完成：{} 成功，{} 失败", 
-                   executed_actions.len(), failed_actions.len())
-        };
+        if success {
+            return Ok(FixResult {
+                success,
+                message: format!("成功执行 {} 个动作", executed_actions.len()),
+                executed_actions,
+                failed_actions,
+                rollback,
+                rolled_back: Vec::new(),
+                fix_id: None,
+            });
+        }
+        
+        let skipped = total - executed_actions.len() - failed_actions.len();
+        let rolled_back = self.rollback(&rollback).await;
+        let failure = if failed_actions.iter().any(|a| a.kind == FailureKind::Timeout) { "超时" } else { "失败" };
+        let mut message = format!("执行中断：{} 成功，1 {}，{} 未执行",
+                                  executed_actions.len(), failure, skipped);
+        if !rolled_back.is_empty() {
+            let failed = rolled_back.iter().filter(|r| !r.success).count();
+            message.push_str(&format!("；已自动回滚 {} 个动作", rolled_back.len() - failed));
+            if failed > 0 {
+                message.push_str(&format!("，{} 个回滚失败", failed));
+            }
+        }
         
         Ok(FixResult {
             success,
             message,
             executed_actions,
             failed_actions,
+            rollback: Vec::new(),
+            rolled_back,
+            fix_id: None,
         })
     }
     
+    /// 按相反顺序执行撤销操作，单个失败不影响其余
+    pub async fn rollback(&self, actions: &[RollbackAction]) -> Vec<RollbackOutcome> {
+        let mut outcomes = Vec::new();
+        for action in actions.iter().rev() {
+            let result = match self.step_timeout {
+                Some(limit) => tokio::time::timeout(limit, self.executor.rollback(action))
+                    .await
+                    .unwrap_or_else(|_| Err(format!("回滚超时（{} 秒），已取消", limit.as_secs()))),
+                None => self.executor.rollback(action).await,
+            };
+            let (success, detail) = match result {
+                Ok(msg) => (true, msg),
+                Err(e) => (false, e),
+            };
+            outcomes.push(RollbackOutcome {
+                action: action.description(),
+                success,
+                detail,
+            });
+        }
+        outcomes
+    }
+    
+    /// 在执行时限内执行单个动作，失败时给出失败类型
+    async fn execute_step(&self, action: &ActionType, pid: u32) -> Result<String, (FailureKind, String)> {
+        let result = match self.step_timeout {
+            Some(limit) => match tokio::time::timeout(limit, self.executor.execute(action, pid)).await {
+                Ok(result) => result,
+                Err(_) => {
+                    return Err((
+                        FailureKind::Timeout,
+                        format!("执行超时（{} 秒），已取消，动作可能已部分生效", limit.as_secs()),
+                    ))
+                }
+            },
+            None => self.executor.execute(action, pid).await,
+        };
+        result.map_err(|e| {
+            let kind = if e.starts_with(RATE_LIMITED) {
+                FailureKind::RateLimited
+            } else if e.starts_with(MISSING_PRIVILEGE) {
+                FailureKind::MissingPrivilege
+            } else {
+                FailureKind::Error
+            };
+            (kind, e)
+        })
+    }
+
     /// 解析 recommended_actions 文本为 ActionType 列表
     fn parse_recommendations(&self, recommendations: &[String]) -> Vec<(ActionType, u8)> {
         let mut actions = Vec::new();
@@ -83,12 +253,14 @@ impl FixEngine {
                 // 根据动作类型设置优先级
                 let priority = match &action {
                     ActionType::Signal { .. } => 1, // 最高优先级：先发信号
-                    ActionType::GracefulShutdown { .. } => 2,
+                    ActionType::GracefulShutdown { .. } | ActionType::Escalate { .. } => 2,
                     ActionType::CgroupThrottle { .. } => 3,
                     ActionType::CheckCheckpoint { .. } => 4,
-                    ActionType::NetworkRestart { .. } => 5,
+                    ActionType::NetworkRestart { .. } | ActionType::NetworkThrottle { .. } => 5,
                     ActionType::IsolateNode { .. } => 6,
-                    ActionType::KillProcess => 10, // 最低优先级：最后才 kill
+                    ActionType::GpuReset { .. } => 9,
+                    ActionType::Plugin { .. } => 8,
+                    ActionType::KillProcess | ActionType::KillProcessTree => 10, // 最低优先级：最后才 kill
                     ActionType::Custom { .. } => 7,
                 };
                 actions.push((action, priority));
@@ -107,17 +279,93 @@ impl Default for FixEngine {
     }
 }
 
+/// 修复计划：按执行顺序排列的步骤及其前置条件和预期效果
+///
+/// CLI 据此展示计划并让用户整体或逐步确认，只把确认的步骤交给 daemon 执行；
+/// `-o json` 和集群修复报告中原样输出
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixPlan {
+    pub pid: u32,
+    /// 提供动作的规则（未命中规则时为 None，动作从场景分析推断）
+    pub rule: Option<String>,
+    pub steps: Vec<FixStep>,
+}
+
+/// 修复计划中的一步
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixStep {
+    /// 执行顺序（从 1 开始）
+    pub order: usize,
+    pub action: ActionType,
+    pub description: String,
+    pub preconditions: Vec<String>,
+    pub expected_effect: String,
+    /// 高危动作（强制审批模式下需要审批 token）
+    pub destructive: bool,
+    pub priority: u8,
+    /// 动作策略级别（auto 可随 --yes 自动执行，confirm 必须交互确认，deny 不会执行）
+    pub policy: PolicyLevel,
+}
+
+impl FixPlan {
+    /// 由已排好序的 (动作, 优先级) 构造计划
+    pub fn new(pid: u32, rule: Option<String>, actions: Vec<(ActionType, u8)>) -> Self {
+        let steps = actions
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (action, priority))| FixStep {
+                order: idx + 1,
+                description: action.description(),
+                preconditions: action.preconditions(pid),
+                expected_effect: action.expected_effect(pid),
+                destructive: action.is_destructive(),
+                action,
+                priority,
+                policy: PolicyLevel::Auto,
+            })
+            .collect();
+        Self { pid, rule, steps }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// 策略级别不高于 level 的步骤序号
+    pub fn steps_within(&self, level: PolicyLevel) -> Vec<usize> {
+        self.steps.iter().filter(|step| step.policy <= level).map(|step| step.order).collect()
+    }
+
+    /// 选中步骤（按 order）的 (动作, 优先级)，保持计划顺序，供 `execute_plan` 使用
+    pub fn selected_actions(&self, orders: &[usize]) -> Vec<(ActionType, u8)> {
+        self.steps
+            .iter()
+            .filter(|step| orders.contains(&step.order))
+            .map(|step| (step.action.clone(), step.priority))
+            .collect()
+    }
+}
+
 /// 执行结果
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FixResult {
     pub success: bool,
     pub message: String,
     pub executed_actions: Vec<ExecutedAction>,
     pub failed_actions: Vec<FailedAction>,
+    /// 已生效的可回滚动作的撤销方式（执行顺序），`ark fix --rollback` 按相反顺序执行
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rollback: Vec<RollbackAction>,
+    /// 执行中途失败时自动回滚的结果
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rolled_back: Vec<RollbackOutcome>,
+    /// daemon 记录到修复日志时分配的 ID（仅当存在可回滚动作）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix_id: Option<String>,
 }
 
 /// 已执行的动作
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutedAction {
     pub action: String,
     pub result: String,
@@ -125,9 +373,28 @@ pub struct ExecutedAction {
 }
 
 /// 失败的动作
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FailedAction {
     pub action: String,
     pub error: String,
+    #[serde(default)]
+    pub kind: FailureKind,
     pub priority: u8,
 }
+
+/// 动作失败的类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// 执行出错
+    #[default]
+    Error,
+    /// 超过执行时限被取消
+    Timeout,
+    /// 动作策略禁止
+    Denied,
+    /// 动作冷却中被拒绝
+    RateLimited,
+    /// daemon 缺少执行所需的权限（执行前预检）
+    MissingPrivilege,
+}
@@ -0,0 +1,97 @@
+//! 动作冷却：同一进程 / job 在窗口内不重复执行同一类动作
+//!
+//! 规则反复命中或 Hub 重复下发命令时，避免对同一目标连续发信号或 kill。
+//! 窗口和受限的动作类型在动态配置（`ark run --config`）中设置，SIGHUP 时重新加载：
+//!
+//! ```yaml
+//! action_cooldown:
+//!   window_seconds: 60     # 0 表示关闭
+//!   actions: [signal, graceful_shutdown, escalate, kill_process, kill_process_tree, gpu_reset]
+//! ```
+
+use crate::exec::action::ActionType;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// 冷却被拒绝时错误信息的前缀（审计日志中据此区分冷却拒绝和执行失败）
+pub const RATE_LIMITED: &str = "rate_limited";
+
+/// 动作冷却配置
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct CooldownConfig {
+    /// 同一目标同一类动作的最短间隔（秒）
+    pub window_seconds: u64,
+    /// 受冷却限制的动作类型（`ActionType::kind`）
+    pub actions: Vec<String>,
+}
+
+impl Default for CooldownConfig {
+    fn default() -> Self {
+        Self {
+            window_seconds: 60,
+            actions: ["signal", "graceful_shutdown", "escalate", "kill_process", "kill_process_tree", "gpu_reset"]
+                .iter()
+                .map(|kind| kind.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// 最近执行过的动作，按（目标, 动作类型）记录
+pub struct ActionLimiter {
+    config: RwLock<CooldownConfig>,
+    recent: Mutex<HashMap<(String, &'static str), Instant>>,
+}
+
+impl ActionLimiter {
+    pub fn new(config: CooldownConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            recent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 替换配置（已有的执行记录保留）
+    pub fn update(&self, config: CooldownConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    /// 执行前检查并登记：进程或其所属 job 在窗口内执行过同类动作时拒绝
+    pub fn acquire(&self, action: &ActionType, pid: u32, job: Option<&str>) -> Result<(), String> {
+        let kind = action.kind();
+        let window = {
+            let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+            if config.window_seconds == 0 || !config.actions.iter().any(|a| a == kind) {
+                return Ok(());
+            }
+            Duration::from_secs(config.window_seconds)
+        };
+
+        let targets: Vec<String> = std::iter::once(format!("进程 {}", pid))
+            .chain(job.map(|job| format!("job {}", job)))
+            .collect();
+        let now = Instant::now();
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        recent.retain(|_, at| now.duration_since(*at) < window);
+        for target in &targets {
+            if let Some(at) = recent.get(&(target.clone(), kind)) {
+                let elapsed = now.duration_since(*at);
+                return Err(format!(
+                    "{}: {} 在 {} 秒前已执行 {}，{} 秒内不再重复执行",
+                    RATE_LIMITED,
+                    target,
+                    elapsed.as_secs(),
+                    kind,
+                    (window - elapsed).as_secs().max(1)
+                ));
+            }
+        }
+        for target in targets {
+            recent.insert((target, kind), now);
+        }
+        Ok(())
+    }
+}
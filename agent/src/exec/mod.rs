@@ -1,10 +1,23 @@
 mod action;
+mod dry_run;
 mod executor;
 mod fix_engine;
+mod limiter;
+mod policy;
+mod preflight;
+mod registry;
+mod rollback;
+#[cfg(windows)]
+mod windows;
 
 pub use action::ActionType;
-pub use executor::ActionExecutor;
-pub use fix_engine::{FixEngine, FixResult};
+pub use executor::{ActionExecutor, Executor};
+pub use fix_engine::{FailureKind, FixEngine, FixPlan, FixResult};
+pub use limiter::{ActionLimiter, CooldownConfig, RATE_LIMITED};
+pub use policy::{caller_user, ActionPolicy, PolicyLevel, PolicySubject};
+pub use preflight::MISSING_PRIVILEGE;
+pub use registry::{ActuatorRegistry, ActuatorSpec};
+pub use rollback::{default_journal_path, FixJournal, RollbackOutcome};
 
 use async_trait::async_trait;
 use crate::plugin::Actuator;
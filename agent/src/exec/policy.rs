@@ -0,0 +1,140 @@
+//! 动作策略（`/etc/ark/policy.yaml`，可用环境变量 `ARK_POLICY` 覆盖）
+//!
+//! 按动作类型（`ActionType::kind`）规定哪些动作可以自动执行、哪些必须人工确认、哪些禁止执行，
+//! 并可按 job / 用户设置例外：
+//!
+//! ```yaml
+//! default: confirm              # 未列出的动作（缺省为 confirm）
+//! actions:
+//!   signal: auto
+//!   check_checkpoint: auto
+//!   kill_process: deny
+//! exceptions:                   # 按顺序取第一个匹配且声明了该动作的例外
+//!   - job: "debug-*"            # glob，`re:` 前缀为正则；job 和 user 同时声明时都要匹配
+//!     actions: { kill_process: auto }
+//!   - user: oncall
+//!     actions: { kill_process: confirm }
+//! ```
+//!
+//! 策略文件不存在时所有动作都是 auto（与没有策略层时的行为一致）。
+
+use crate::exec::action::ActionType;
+use ark_core::rules::matches_pattern;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 策略级别（按限制程度从低到高排序）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyLevel {
+    /// 可以自动执行（`ark fix --yes`）
+    #[default]
+    Auto,
+    /// 必须交互确认，`--yes` 不会执行
+    Confirm,
+    /// 禁止执行
+    Deny,
+}
+
+impl PolicyLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyLevel::Auto => "auto",
+            PolicyLevel::Confirm => "confirm",
+            PolicyLevel::Deny => "deny",
+        }
+    }
+}
+
+/// 策略作用的对象：目标进程所属 job 和发起操作的用户
+#[derive(Debug, Clone, Default)]
+pub struct PolicySubject {
+    pub job: Option<String>,
+    pub user: Option<String>,
+}
+
+/// 按 job / 用户设置的例外
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolicyException {
+    #[serde(default)]
+    pub job: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    pub actions: HashMap<String, PolicyLevel>,
+}
+
+impl PolicyException {
+    fn applies_to(&self, subject: &PolicySubject) -> bool {
+        let matches = |pattern: &Option<String>, value: &Option<String>| match pattern {
+            Some(pattern) => value.as_deref().is_some_and(|value| matches_pattern(value, pattern)),
+            None => true,
+        };
+        (self.job.is_some() || self.user.is_some())
+            && matches(&self.job, &subject.job)
+            && matches(&self.user, &subject.user)
+    }
+}
+
+/// 动作策略
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ActionPolicy {
+    /// 未列出的动作的级别
+    #[serde(default = "default_level")]
+    pub default: PolicyLevel,
+    #[serde(default)]
+    pub actions: HashMap<String, PolicyLevel>,
+    #[serde(default)]
+    pub exceptions: Vec<PolicyException>,
+}
+
+fn default_level() -> PolicyLevel {
+    PolicyLevel::Confirm
+}
+
+/// 默认策略文件：`ARK_POLICY` 或 `/etc/ark/policy.yaml`
+pub fn default_policy_path() -> PathBuf {
+    std::env::var("ARK_POLICY")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("/etc/ark/policy.yaml"))
+}
+
+impl ActionPolicy {
+    /// 读取策略文件（不存在时返回全部 auto 的策略，格式错误时返回错误，调用方应拒绝执行）
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("读取动作策略 {} 失败: {}", path.display(), e)),
+        };
+        serde_yaml::from_str(&content).map_err(|e| format!("解析动作策略 {} 失败: {}", path.display(), e))
+    }
+
+    /// 读取默认位置的策略文件
+    pub fn load_default() -> Result<Self, String> {
+        Self::load(&default_policy_path())
+    }
+
+    /// 动作对该对象的策略级别：例外优先，其次是 actions，最后是 default
+    pub fn decide(&self, action: &ActionType, subject: &PolicySubject) -> PolicyLevel {
+        let kind = action.kind();
+        self.exceptions
+            .iter()
+            .filter(|exception| exception.applies_to(subject))
+            .find_map(|exception| exception.actions.get(kind))
+            .or_else(|| self.actions.get(kind))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// IPC 调用方（`uid=<uid>`）对应的用户名，供策略例外匹配（无法解析时为 None）
+pub fn caller_user(caller: &str) -> Option<String> {
+    let uid = caller.strip_prefix("uid=")?;
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        let name = fields.next()?;
+        (fields.nth(1)? == uid).then(|| name.to_string())
+    })
+}
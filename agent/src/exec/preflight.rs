@@ -0,0 +1,232 @@
+//! 执行前的权限预检
+//!
+//! daemon 权限不足时，shell 出去的 kill / ip / tc 只返回不透明的 "Permission denied"。执行前按动作检查
+//! daemon 进程的有效 capability（`/proc/self/status` 的 CapEff）、uid 和需要的外部命令，一次列出缺少的全部权限
+//! 及补齐方式。读不到 /proc 时不拦截，交给实际执行报错；Windows 上不做预检。
+
+use crate::exec::action::ActionType;
+
+/// 预检失败时错误信息的前缀（失败类型和审计状态据此区分权限不足和执行失败）
+pub const MISSING_PRIVILEGE: &str = "missing_privilege";
+
+/// 检查 daemon 是否具备执行动作所需的权限
+pub(crate) fn check(action: &ActionType, pid: u32) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        let Some(daemon) = unix::Credentials::read("self") else {
+            return Ok(());
+        };
+        let missing = unix::missing(&daemon, action, pid);
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let caps: Vec<&str> = missing.iter().filter_map(|m| m.capability).collect();
+        let mut message = format!(
+            "{}: daemon（uid {}）缺少执行 {} 所需的权限: {}",
+            MISSING_PRIVILEGE,
+            daemon.uid,
+            action.kind(),
+            missing.iter().map(|m| m.detail.as_str()).collect::<Vec<_>>().join("；")
+        );
+        if !caps.is_empty() {
+            message.push_str(&format!(
+                "。以 root 运行 daemon，或在 systemd 单元中设置 AmbientCapabilities={}",
+                caps.join(" ")
+            ));
+        }
+        Err(message)
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = (action, pid);
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use crate::exec::action::ActionType;
+    use crate::exec::executor::cgroup_path;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+
+    const CAP_DAC_OVERRIDE: u32 = 1;
+    const CAP_KILL: u32 = 5;
+    const CAP_NET_ADMIN: u32 = 12;
+
+    /// 缺少的一项权限
+    pub(super) struct Missing {
+        /// 可以通过授予该 capability 补齐
+        pub(super) capability: Option<&'static str>,
+        pub(super) detail: String,
+    }
+
+    impl Missing {
+        fn capability(name: &'static str, detail: String) -> Self {
+            Self { capability: Some(name), detail }
+        }
+
+        fn other(detail: String) -> Self {
+            Self { capability: None, detail }
+        }
+    }
+
+    /// 进程的 uid / gid 和有效 capability（`/proc/<pid>/status`）
+    pub(super) struct Credentials {
+        real_uid: u32,
+        pub(super) uid: u32,
+        saved_uid: u32,
+        gids: Vec<u32>,
+        cap_eff: u64,
+    }
+
+    impl Credentials {
+        pub(super) fn read(pid: &str) -> Option<Self> {
+            let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+            let field = |name: &str| {
+                status
+                    .lines()
+                    .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+                    .map(str::trim)
+            };
+            let ids = |name: &str| -> Vec<u32> {
+                field(name)
+                    .map(|v| v.split_whitespace().filter_map(|id| id.parse().ok()).collect())
+                    .unwrap_or_default()
+            };
+            // Uid: real effective saved fs
+            let uids = ids("Uid");
+            let mut gids = ids("Groups");
+            gids.extend(ids("Gid").get(1));
+            Some(Self {
+                real_uid: *uids.first()?,
+                uid: *uids.get(1)?,
+                saved_uid: *uids.get(2)?,
+                gids,
+                cap_eff: u64::from_str_radix(field("CapEff")?, 16).ok()?,
+            })
+        }
+
+        fn has(&self, cap: u32) -> bool {
+            self.cap_eff & (1 << cap) != 0
+        }
+
+        /// 不依赖 CAP_KILL 能否向目标发信号：发送方的实际或有效 uid 等于目标的实际或保存 uid
+        fn may_signal(&self, target: &Credentials) -> bool {
+            [self.real_uid, self.uid]
+                .iter()
+                .any(|uid| *uid == target.real_uid || *uid == target.saved_uid)
+        }
+
+        /// 能否在目录中创建和写入文件
+        fn may_write(&self, dir: &Path) -> bool {
+            let Ok(meta) = std::fs::metadata(dir) else {
+                return true;
+            };
+            let mode = meta.mode();
+            self.has(CAP_DAC_OVERRIDE)
+                || (meta.uid() == self.uid && mode & 0o200 != 0)
+                || (self.gids.contains(&meta.gid()) && mode & 0o020 != 0)
+                || mode & 0o002 != 0
+        }
+    }
+
+    /// daemon 执行动作缺少的权限
+    pub(super) fn missing(daemon: &Credentials, action: &ActionType, pid: u32) -> Vec<Missing> {
+        let mut missing = Vec::new();
+        match action {
+            ActionType::Signal { .. }
+            | ActionType::GracefulShutdown { .. }
+            | ActionType::Escalate { .. }
+            | ActionType::KillProcess
+            | ActionType::KillProcessTree => missing.extend(signal(daemon, pid)),
+            ActionType::CgroupThrottle { .. } => missing.extend(cgroup(daemon, pid)),
+            ActionType::NetworkRestart { .. } => {
+                missing.extend(net_admin(daemon));
+                missing.extend(command("ip", "iproute2"));
+            }
+            ActionType::NetworkThrottle { .. } => {
+                missing.extend(net_admin(daemon));
+                missing.extend(command("tc", "iproute2"));
+            }
+            ActionType::GpuReset { .. } => {
+                if daemon.uid != 0 {
+                    missing.push(Missing::other("root（nvidia-smi --gpu-reset 需要 root）".to_string()));
+                }
+                missing.extend(signal(daemon, pid));
+                missing.extend(command("nvidia-smi", "NVIDIA 驱动"));
+            }
+            ActionType::IsolateNode { .. }
+            | ActionType::CheckCheckpoint { .. }
+            | ActionType::Plugin { .. }
+            | ActionType::Custom { .. } => {}
+        }
+        missing
+    }
+
+    fn signal(daemon: &Credentials, pid: u32) -> Option<Missing> {
+        if daemon.has(CAP_KILL) {
+            return None;
+        }
+        // 目标已退出时交给实际执行处理
+        let target = Credentials::read(&pid.to_string())?;
+        (!daemon.may_signal(&target)).then(|| {
+            Missing::capability(
+                "CAP_KILL",
+                format!("CAP_KILL（进程 {} 属于 uid {}，不能跨用户发信号）", pid, target.real_uid),
+            )
+        })
+    }
+
+    fn net_admin(daemon: &Credentials) -> Option<Missing> {
+        (!daemon.has(CAP_NET_ADMIN))
+            .then(|| Missing::capability("CAP_NET_ADMIN", "CAP_NET_ADMIN（修改网络接口和 qdisc）".to_string()))
+    }
+
+    fn cgroup(daemon: &Credentials, pid: u32) -> Option<Missing> {
+        let root = Path::new("/sys/fs/cgroup");
+        if !root.is_dir() {
+            return Some(Missing::other("cgroup 文件系统（/sys/fs/cgroup 未挂载）".to_string()));
+        }
+        if mounted_read_only(root) {
+            return Some(Missing::other(
+                "cgroup 写权限（/sys/fs/cgroup 以只读方式挂载，容器中需挂载为可写）".to_string(),
+            ));
+        }
+        // 限流 cgroup 建在 /sys/fs/cgroup/ark 下，该目录不存在时需要在 cgroup 根目录创建
+        let path = cgroup_path(pid);
+        let parent = Path::new(&path).parent().filter(|dir| dir.is_dir()).unwrap_or(root);
+        (!daemon.may_write(parent)).then(|| {
+            Missing::capability(
+                "CAP_DAC_OVERRIDE",
+                format!("cgroup 写权限（{} 对 daemon 用户不可写，也可以把该目录授权给 daemon 用户）", parent.display()),
+            )
+        })
+    }
+
+    /// 挂载点是否为只读（按 /proc/self/mountinfo 中最后一次挂载判断）
+    fn mounted_read_only(mount_point: &Path) -> bool {
+        let Ok(mountinfo) = std::fs::read_to_string("/proc/self/mountinfo") else {
+            return false;
+        };
+        // 格式：id parent major:minor root mount_point options ...
+        mountinfo
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace().skip(4);
+                let point = fields.next()?;
+                let options = fields.next()?;
+                (Path::new(point) == mount_point).then_some(options)
+            })
+            .last()
+            .is_some_and(|options| options.split(',').any(|o| o == "ro"))
+    }
+
+    fn command(name: &str, package: &str) -> Option<Missing> {
+        let found = std::env::var_os("PATH")
+            .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(name).is_file()));
+        (!found).then(|| Missing::other(format!("命令 {}（未在 PATH 中找到，需安装 {}）", name, package)))
+    }
+}
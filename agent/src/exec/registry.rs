@@ -0,0 +1,144 @@
+//! 外部执行器插件：站点自定义的修复动作（厂商 NPU 复位工具、存储切换脚本等）无需重新编译 agent
+//!
+//! 执行器在动态配置（`ark run --config`）的 `actuators` 中注册，规则通过 `type: plugin` 的动作调用。
+//! 协议：daemon 启动可执行文件，向 stdin 写入一个 JSON 请求后关闭 stdin，从 stdout 读取一个 JSON 结果：
+//!
+//! ```text
+//! stdin:  {"protocol_version":1,"action":"npu-reset","pid":1234,"params":{"device":3}}
+//! stdout: {"success":true,"message":"NPU 3 已复位"}
+//! ```
+//!
+//! `success` 为 false、退出码非零且没有合法结果、或超时（进程被终止）都视为执行失败。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// 外部执行器协议版本（请求中的 protocol_version）
+pub const ACTUATOR_PROTOCOL_VERSION: u32 = 1;
+
+/// 单个外部执行器的配置
+///
+/// ```yaml
+/// actuators:
+///   - name: npu-reset
+///     command: /opt/vendor/bin/npu-reset-ark
+///     args: ["--force"]
+///     timeout_seconds: 120   # 默认 60
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ActuatorSpec {
+    /// 规则中 `plugin` 动作引用的名称
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+fn default_timeout_seconds() -> u64 {
+    60
+}
+
+/// 写入执行器 stdin 的请求
+#[derive(Debug, Serialize)]
+struct ActuatorRequest<'a> {
+    protocol_version: u32,
+    action: &'a str,
+    pid: u32,
+    params: &'a serde_json::Value,
+}
+
+/// 执行器 stdout 返回的结果
+#[derive(Debug, Deserialize)]
+struct ActuatorResponse {
+    success: bool,
+    #[serde(default)]
+    message: String,
+}
+
+/// 发给执行器的请求（JSON），预演时原样展示
+pub(crate) fn request_json(name: &str, pid: u32, params: &serde_json::Value) -> String {
+    serde_json::to_string(&ActuatorRequest {
+        protocol_version: ACTUATOR_PROTOCOL_VERSION,
+        action: name,
+        pid,
+        params,
+    })
+    .unwrap_or_default()
+}
+
+/// 已注册的外部执行器（动态配置重新加载时整体替换）
+#[derive(Default)]
+pub struct ActuatorRegistry {
+    actuators: RwLock<HashMap<String, ActuatorSpec>>,
+}
+
+impl ActuatorRegistry {
+    pub fn new(specs: Vec<ActuatorSpec>) -> Self {
+        let registry = Self::default();
+        registry.replace(specs);
+        registry
+    }
+
+    /// 替换全部执行器（重名时后者覆盖前者）
+    pub fn replace(&self, specs: Vec<ActuatorSpec>) {
+        let actuators = specs.into_iter().map(|spec| (spec.name.clone(), spec)).collect();
+        *self.actuators.write().unwrap_or_else(|e| e.into_inner()) = actuators;
+    }
+
+    fn get(&self, name: &str) -> Option<ActuatorSpec> {
+        self.actuators.read().unwrap_or_else(|e| e.into_inner()).get(name).cloned()
+    }
+
+    /// 调用外部执行器，返回其 message
+    pub async fn invoke(&self, name: &str, pid: u32, params: &serde_json::Value) -> Result<String, String> {
+        let spec = self
+            .get(name)
+            .ok_or_else(|| format!("未注册外部执行器 {}（在 --config 的 actuators 中配置）", name))?;
+
+        let mut child = Command::new(&spec.command)
+            .args(&spec.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // 超时后随 future 一起丢弃时终止子进程
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("启动外部执行器 {} 失败: {}", spec.command, e))?;
+
+        let mut stdin = child.stdin.take().ok_or_else(|| "无法获取外部执行器 stdin".to_string())?;
+        let request = request_json(name, pid, params);
+        stdin
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| format!("向外部执行器 {} 写入请求失败: {}", name, e))?;
+        drop(stdin);
+
+        let output = tokio::time::timeout(Duration::from_secs(spec.timeout_seconds), child.wait_with_output())
+            .await
+            .map_err(|_| format!("外部执行器 {} 超时（{} 秒），已终止", name, spec.timeout_seconds))?
+            .map_err(|e| format!("等待外部执行器 {} 失败: {}", name, e))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match serde_json::from_str::<ActuatorResponse>(stdout.trim()) {
+            Ok(response) if response.success => Ok(response.message),
+            Ok(response) => Err(format!("外部执行器 {} 执行失败: {}", name, response.message)),
+            Err(e) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                Err(format!(
+                    "外部执行器 {} 没有返回合法结果（退出码 {:?}，{}）: {}",
+                    name,
+                    output.status.code(),
+                    e,
+                    stderr.trim()
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! 可撤销动作的回滚
+//!
+//! cgroup 限流、网络限速、节点隔离和网络接口重启可以撤销：执行前记录撤销所需的上下文（如进程原来的 cgroup），
+//! 执行后作为 `RollbackAction` 放进 `FixResult`。修复中途失败时 `FixEngine` 立即按相反顺序自动回滚；
+//! 修复成功时 daemon 为其分配 fix_id 并写入修复日志（默认 `~/.ark/fixes.jsonl`，可用环境变量
+//! `ARK_FIX_JOURNAL` 覆盖），之后可以用 `ark fix --rollback <fix-id>` 撤销。
+
+use crate::exec::action::ActionType;
+use crate::exec::executor::cgroup_path;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// 撤销一个已执行动作所需的操作
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RollbackAction {
+    /// 把进程移回原来的 cgroup，删除 ark 创建的限流 cgroup
+    CgroupRestore {
+        pid: u32,
+        cgroup_path: String,
+        /// 执行前进程所在的 cgroup（/proc/<pid>/cgroup 中的路径，读取失败时为 None）
+        original_cgroup: Option<String>,
+    },
+    /// 重新启用网络接口（重启中途失败时接口可能仍处于 down 状态）
+    NetworkUp { interface: String },
+    /// 删除 ark 设置的 tc 限速（root qdisc）
+    NetworkUnthrottle { interface: String },
+    /// 取消节点隔离
+    Unisolate { reason: String },
+}
+
+impl RollbackAction {
+    /// 执行动作前记录撤销所需的上下文（不可撤销的动作返回 None）
+    pub async fn capture(action: &ActionType, pid: u32) -> Option<Self> {
+        match action {
+            ActionType::CgroupThrottle { .. } => Some(RollbackAction::CgroupRestore {
+                pid,
+                cgroup_path: cgroup_path(pid),
+                original_cgroup: current_cgroup(pid).await,
+            }),
+            ActionType::NetworkRestart { interface } => {
+                Some(RollbackAction::NetworkUp { interface: interface.clone() })
+            }
+            ActionType::NetworkThrottle { interface, .. } => {
+                Some(RollbackAction::NetworkUnthrottle { interface: interface.clone() })
+            }
+            ActionType::IsolateNode { reason } => Some(RollbackAction::Unisolate { reason: reason.clone() }),
+            _ => None,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            RollbackAction::CgroupRestore { pid, original_cgroup, .. } => match original_cgroup {
+                Some(original) => format!("撤销 Cgroup 限流: 进程 {} 移回 {}", pid, original),
+                None => format!("撤销 Cgroup 限流: 删除进程 {} 的限流 cgroup", pid),
+            },
+            RollbackAction::NetworkUp { interface } => format!("启用网络接口: {}", interface),
+            RollbackAction::NetworkUnthrottle { interface } => format!("撤销网络限速: {}", interface),
+            RollbackAction::Unisolate { reason } => format!("取消节点隔离: {}", reason),
+        }
+    }
+}
+
+/// 单个回滚操作的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollbackOutcome {
+    pub action: String,
+    pub success: bool,
+    /// 成功时为执行输出，失败时为错误信息
+    pub detail: String,
+}
+
+/// 进程当前所在的 cgroup（/proc/<pid>/cgroup 首行的路径部分）
+async fn current_cgroup(pid: u32) -> Option<String> {
+    let content = tokio::fs::read_to_string(format!("/proc/{}/cgroup", pid)).await.ok()?;
+    content
+        .lines()
+        .next()?
+        .splitn(3, ':')
+        .nth(2)
+        .map(str::to_string)
+        .filter(|path| !path.is_empty())
+}
+
+/// 修复日志中的一条记录
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum JournalEntry {
+    /// 修复已执行，记录撤销方式
+    Applied {
+        fix_id: String,
+        ts: String,
+        pid: u32,
+        operation: String,
+        rollback: Vec<RollbackAction>,
+    },
+    /// 修复已回滚
+    RolledBack { fix_id: String, ts: String },
+}
+
+/// 默认修复日志：`ARK_FIX_JOURNAL` 或 `~/.ark/fixes.jsonl`
+pub fn default_journal_path() -> PathBuf {
+    if let Ok(path) = std::env::var("ARK_FIX_JOURNAL") {
+        return PathBuf::from(path);
+    }
+    let mut home = std::env::var("HOME").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("."));
+    home.push(".ark");
+    home.push("fixes.jsonl");
+    home
+}
+
+/// 追加写入的修复日志，记录可回滚的修复
+pub struct FixJournal {
+    path: PathBuf,
+}
+
+impl FixJournal {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// 记录一次修复的撤销方式，返回分配的 fix_id
+    pub fn record(&self, pid: u32, operation: &str, rollback: &[RollbackAction]) -> Result<String, String> {
+        let now = chrono::Utc::now();
+        let fix_id = format!("fix-{}-{:04x}", now.format("%Y%m%d%H%M%S"), rand::thread_rng().gen::<u16>());
+        self.append(&JournalEntry::Applied {
+            fix_id: fix_id.clone(),
+            ts: now.to_rfc3339(),
+            pid,
+            operation: operation.to_string(),
+            rollback: rollback.to_vec(),
+        })?;
+        Ok(fix_id)
+    }
+
+    /// 查找尚未回滚的修复，返回目标进程和撤销操作
+    pub fn pending(&self, fix_id: &str) -> Result<(u32, Vec<RollbackAction>), String> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("读取修复日志 {} 失败: {}", self.path.display(), e)),
+        };
+        let mut found = None;
+        for entry in content.lines().filter_map(|line| serde_json::from_str::<JournalEntry>(line).ok()) {
+            match entry {
+                JournalEntry::Applied { fix_id: id, pid, rollback, .. } if id == fix_id => found = Some((pid, rollback)),
+                JournalEntry::RolledBack { fix_id: id, ts } if id == fix_id => {
+                    return Err(format!("修复 {} 已于 {} 回滚", fix_id, ts));
+                }
+                _ => {}
+            }
+        }
+        found.ok_or_else(|| format!("修复日志中没有 {}（只有包含可回滚动作的修复会记录）", fix_id))
+    }
+
+    /// 标记修复已回滚（同一修复不会被回滚两次）
+    pub fn mark_rolled_back(&self, fix_id: &str) -> Result<(), String> {
+        self.append(&JournalEntry::RolledBack {
+            fix_id: fix_id.to_string(),
+            ts: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+
+    fn append(&self, entry: &JournalEntry) -> Result<(), String> {
+        let mut line = serde_json::to_string(entry).map_err(|e| format!("序列化修复日志失败: {}", e))?;
+        line.push('\n');
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建修复日志目录失败: {}", e))?;
+        }
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| format!("写入修复日志 {} 失败: {}", self.path.display(), e))
+    }
+}
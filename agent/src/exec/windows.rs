@@ -0,0 +1,205 @@
+//! Windows 上的执行器实现：信号映射为控制台事件 / WM_CLOSE，Cgroup 限流映射为 Job Object
+//!
+//! - SIGINT → `CTRL_C_EVENT`，SIGTERM / SIGHUP / SIGQUIT → `CTRL_BREAK_EVENT`：附加到目标进程的控制台后发送，
+//!   共享该控制台的进程都会收到（相当于 Unix 的进程组）。目标没有控制台时，SIGTERM 类信号改为向其顶层窗口
+//!   发送 `WM_CLOSE`（daemon 以服务运行在 session 0 时看不到用户桌面上的窗口）
+//! - SIGKILL → `TerminateProcess`
+//! - Cgroup 限流 → 命名 Job Object（`ark-throttle-<pid>`）的 CPU 硬上限和单进程内存上限。
+//!   进程加入 Job 后无法移出，撤销时清除 Job 上的限制
+
+use std::ffi::c_void;
+use std::io;
+use std::sync::Mutex;
+use windows_sys::Win32::Foundation::{CloseHandle, BOOL, FALSE, HANDLE, HWND, LPARAM, STILL_ACTIVE, TRUE};
+use windows_sys::Win32::System::Console::{
+    AttachConsole, FreeConsole, GenerateConsoleCtrlEvent, GetConsoleWindow, SetConsoleCtrlHandler,
+    CTRL_BREAK_EVENT, CTRL_C_EVENT,
+};
+use windows_sys::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, JobObjectCpuRateControlInformation,
+    JobObjectExtendedLimitInformation, OpenJobObjectW, SetInformationJobObject, JOBOBJECTINFOCLASS,
+    JOBOBJECT_CPU_RATE_CONTROL_INFORMATION, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JOB_OBJECT_CPU_RATE_CONTROL_ENABLE, JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP, JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+    JOB_OBJECT_SET_ATTRIBUTES,
+};
+use windows_sys::Win32::System::Threading::{
+    GetExitCodeProcess, OpenProcess, TerminateProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SET_QUOTA,
+    PROCESS_TERMINATE,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{EnumWindows, GetWindowThreadProcessId, PostMessageW, WM_CLOSE};
+
+/// 进程同一时间只能附加一个控制台，发送控制台事件需要串行
+static CONSOLE: Mutex<()> = Mutex::new(());
+
+/// OpenProcess 对不存在的 pid 返回 ERROR_INVALID_PARAMETER
+const ERROR_INVALID_PARAMETER: i32 = 87;
+
+/// 离开作用域时关闭的句柄
+struct Handle(HANDLE);
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+fn open_process(pid: u32, access: u32) -> io::Result<Handle> {
+    let handle = unsafe { OpenProcess(access, FALSE, pid) };
+    if handle.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Handle(handle))
+}
+
+/// 进程是否已退出（pid 不存在或已有退出码；无权限打开时按仍在运行处理）
+pub(crate) fn process_exited(pid: u32) -> bool {
+    let process = match open_process(pid, PROCESS_QUERY_LIMITED_INFORMATION) {
+        Ok(process) => process,
+        Err(e) => return e.raw_os_error() == Some(ERROR_INVALID_PARAMETER),
+    };
+    let mut code = 0u32;
+    let ok = unsafe { GetExitCodeProcess(process.0, &mut code) };
+    ok != 0 && code != STILL_ACTIVE as u32
+}
+
+/// 按信号语义通知进程
+pub(crate) fn send_signal(signal: i32, pid: u32) -> Result<String, String> {
+    match signal {
+        9 => terminate(pid).map(|()| format!("已终止进程 {}（TerminateProcess）", pid)),
+        2 => console_ctrl(pid, CTRL_C_EVENT).map(|()| format!("已向进程 {} 的控制台发送 CTRL_C_EVENT", pid)),
+        1 | 3 | 15 => match console_ctrl(pid, CTRL_BREAK_EVENT) {
+            Ok(()) => Ok(format!("已向进程 {} 的控制台发送 CTRL_BREAK_EVENT", pid)),
+            Err(console_err) => match close_windows(pid) {
+                0 => Err(format!("{}，且进程没有可关闭的窗口", console_err)),
+                n => Ok(format!("已向进程 {} 的 {} 个窗口发送 WM_CLOSE", pid, n)),
+            },
+        },
+        _ => Err(format!(
+            "Windows 上没有与信号 {} 对应的机制（支持 SIGINT、SIGTERM、SIGHUP、SIGQUIT、SIGKILL）",
+            signal
+        )),
+    }
+}
+
+fn terminate(pid: u32) -> Result<(), String> {
+    let process = open_process(pid, PROCESS_TERMINATE).map_err(|e| format!("打开进程 {} 失败: {}", pid, e))?;
+    if unsafe { TerminateProcess(process.0, 1) } == 0 {
+        return Err(format!("终止进程 {} 失败: {}", pid, io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+/// 附加到目标进程的控制台并发送控制台事件
+fn console_ctrl(pid: u32, event: u32) -> Result<(), String> {
+    let _guard = CONSOLE.lock().unwrap_or_else(|e| e.into_inner());
+    unsafe {
+        // 前台运行的 daemon 释放自己的控制台后无法恢复输出，只在没有控制台（服务方式运行）时切换
+        if !GetConsoleWindow().is_null() {
+            return Err("daemon 自身附加了控制台，无法向其他控制台发送事件".to_string());
+        }
+        if AttachConsole(pid) == 0 {
+            return Err(format!("附加到进程 {} 的控制台失败: {}", pid, io::Error::last_os_error()));
+        }
+        // 事件会发给控制台上的所有进程，包括 daemon 自己；daemon 没有控制台，忽略后不需要恢复
+        SetConsoleCtrlHandler(None, TRUE);
+        let sent = GenerateConsoleCtrlEvent(event, 0);
+        let err = io::Error::last_os_error();
+        FreeConsole();
+        if sent == 0 {
+            return Err(format!("发送控制台事件失败: {}", err));
+        }
+    }
+    Ok(())
+}
+
+unsafe extern "system" fn close_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let target = &mut *(lparam as *mut (u32, usize));
+    let mut owner = 0u32;
+    GetWindowThreadProcessId(hwnd, &mut owner);
+    if owner == target.0 && PostMessageW(hwnd, WM_CLOSE, 0, 0) != 0 {
+        target.1 += 1;
+    }
+    TRUE
+}
+
+/// 向进程的所有顶层窗口发送 WM_CLOSE，返回发送的窗口数
+fn close_windows(pid: u32) -> usize {
+    let mut target = (pid, 0usize);
+    unsafe {
+        EnumWindows(Some(close_window), &mut target as *mut (u32, usize) as LPARAM);
+    }
+    target.1
+}
+
+fn job_name(pid: u32) -> Vec<u16> {
+    format!("ark-throttle-{}", pid).encode_utf16().chain(Some(0)).collect()
+}
+
+fn set_job_information<T>(job: &Handle, class: JOBOBJECTINFOCLASS, info: &T) -> Result<(), String> {
+    let ok = unsafe {
+        SetInformationJobObject(job.0, class, info as *const T as *const c_void, std::mem::size_of::<T>() as u32)
+    };
+    if ok == 0 {
+        return Err(format!("设置 Job Object 限制失败: {}", io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+/// 创建 Job Object、设置限制并把进程加入其中，返回已应用的限制
+///
+/// `cpu_quota` 与 cgroup 一致，为每 100ms 周期内可用的 CPU 微秒数
+pub(crate) fn apply_job_limits(
+    pid: u32,
+    cpu_quota: Option<u64>,
+    memory_limit: Option<u64>,
+) -> Result<Vec<String>, String> {
+    let name = job_name(pid);
+    let job = unsafe { CreateJobObjectW(std::ptr::null(), name.as_ptr()) };
+    if job.is_null() {
+        return Err(format!("创建 Job Object 失败: {}", io::Error::last_os_error()));
+    }
+    let job = Handle(job);
+    let mut applied = Vec::new();
+
+    if let Some(quota) = cpu_quota {
+        let mut info: JOBOBJECT_CPU_RATE_CONTROL_INFORMATION = unsafe { std::mem::zeroed() };
+        info.ControlFlags = JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP;
+        // CpuRate 以 1/100 个百分点为单位（10000 = 100%）
+        info.Anonymous.CpuRate = (quota / 10).clamp(1, 10000) as u32;
+        set_job_information(&job, JobObjectCpuRateControlInformation, &info)?;
+        applied.push(format!("CPU 限流: {}%", quota / 1000));
+    }
+
+    if let Some(limit) = memory_limit {
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+        info.ProcessMemoryLimit = limit as usize;
+        set_job_information(&job, JobObjectExtendedLimitInformation, &info)?;
+        applied.push(format!("内存限流: {}MB", limit / 1024 / 1024));
+    }
+
+    let process = open_process(pid, PROCESS_SET_QUOTA | PROCESS_TERMINATE)
+        .map_err(|e| format!("打开进程 {} 失败: {}", pid, e))?;
+    if unsafe { AssignProcessToJobObject(job.0, process.0) } == 0 {
+        return Err(format!("将进程加入 Job Object 失败: {}", io::Error::last_os_error()));
+    }
+    Ok(applied)
+}
+
+/// 清除 `apply_job_limits` 设置的限制（进程已退出、Job 已销毁时视为已撤销）
+pub(crate) fn clear_job_limits(pid: u32) -> Result<String, String> {
+    let name = job_name(pid);
+    let job = unsafe { OpenJobObjectW(JOB_OBJECT_SET_ATTRIBUTES, FALSE, name.as_ptr()) };
+    if job.is_null() {
+        return Ok(format!("进程 {} 的 Job Object 已不存在", pid));
+    }
+    let job = Handle(job);
+
+    let cpu: JOBOBJECT_CPU_RATE_CONTROL_INFORMATION = unsafe { std::mem::zeroed() };
+    set_job_information(&job, JobObjectCpuRateControlInformation, &cpu)?;
+    let limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+    set_job_information(&job, JobObjectExtendedLimitInformation, &limits)?;
+    Ok(format!("已清除进程 {} 的 Job Object 限制", pid))
+}
@@ -0,0 +1,366 @@
+//! gRPC 控制面（`grpc` feature，`ark run --grpc-listen`）
+//!
+//! 供把 ark 嵌入已有基础设施的集成方使用，提供与 IPC 相同的 list / why / subscribe / execute_action
+//! 操作。请求直接交给 IPC 的处理逻辑，审计、审批校验和指标与 IPC 一致。
+//!
+//! gRPC 监听 TCP 地址，拿不到调用方 uid：每个调用都需要在 metadata 中携带 IPC 共享 token
+//! （`authorization: Bearer <token>`），校验通过后获得 act 权限。服务本身不启用 TLS，应只监听回环地址，
+//! 或放在做 mTLS 终结的代理之后。消息定义与 `proto/ark.proto` 一致。
+
+use crate::exec::ActionType;
+use crate::ipc::{self, GraphChange, IpcServer, ProcessFilter, RequestContext, RpcRequest, StreamItem, SubscribeFilter};
+use futures_util::Stream;
+use serde::de::DeserializeOwned;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/ark.v1.ArkControl.rs"));
+}
+
+use generated::ark_control_server::{ArkControl, ArkControlServer};
+
+/// gRPC 消息（字段号与 `proto/ark.proto` 一致）
+///
+/// 响应消息同时实现 Deserialize，直接从 IPC 处理逻辑返回的 JSON 转换
+pub mod pb {
+    use serde::Deserialize;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ListProcessesRequest {
+        #[prost(string, optional, tag = "1")]
+        pub job_id: Option<String>,
+        #[prost(string, optional, tag = "2")]
+        pub state: Option<String>,
+        #[prost(string, optional, tag = "3")]
+        pub node_id: Option<String>,
+        #[prost(bool, tag = "4")]
+        pub blocked_only: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message, Deserialize)]
+    pub struct Process {
+        #[prost(uint32, tag = "1")]
+        pub pid: u32,
+        #[prost(string, tag = "2")]
+        pub id: String,
+        #[prost(string, optional, tag = "3")]
+        pub job_id: Option<String>,
+        #[prost(string, tag = "4")]
+        pub state: String,
+        #[prost(string, repeated, tag = "5")]
+        pub resources: Vec<String>,
+        #[prost(uint64, tag = "6")]
+        pub last_update: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message, Deserialize)]
+    pub struct ListProcessesResponse {
+        #[prost(message, repeated, tag = "1")]
+        pub processes: Vec<Process>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct WhyProcessRequest {
+        #[prost(uint32, tag = "1")]
+        pub pid: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message, Deserialize)]
+    pub struct WhyProcessResponse {
+        #[prost(uint32, tag = "1")]
+        pub pid: u32,
+        #[prost(string, repeated, tag = "2")]
+        pub causes: Vec<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SubscribeRequest {
+        #[prost(string, repeated, tag = "1")]
+        pub event_types: Vec<String>,
+        #[prost(string, optional, tag = "2")]
+        pub entity_pattern: Option<String>,
+        /// 缺省为 true
+        #[prost(bool, optional, tag = "3")]
+        pub events: Option<bool>,
+        #[prost(bool, tag = "4")]
+        pub graph_changes: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Event {
+        #[prost(uint64, tag = "1")]
+        pub ts: u64,
+        #[prost(string, tag = "2")]
+        pub event_type: String,
+        #[prost(string, tag = "3")]
+        pub entity_id: String,
+        #[prost(string, optional, tag = "4")]
+        pub job_id: Option<String>,
+        #[prost(uint32, optional, tag = "5")]
+        pub pid: Option<u32>,
+        #[prost(string, tag = "6")]
+        pub value: String,
+        #[prost(string, optional, tag = "7")]
+        pub node_id: Option<String>,
+        #[prost(string, optional, tag = "8")]
+        pub probe: Option<String>,
+        #[prost(string, optional, tag = "9")]
+        pub pod: Option<String>,
+        #[prost(string, optional, tag = "10")]
+        pub container_id: Option<String>,
+    }
+
+    /// 节点变化填 id / node_type，边变化填 from / to / edge_type
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GraphChange {
+        #[prost(string, tag = "1")]
+        pub change: String,
+        #[prost(string, tag = "2")]
+        pub id: String,
+        #[prost(string, tag = "3")]
+        pub node_type: String,
+        #[prost(string, tag = "4")]
+        pub from: String,
+        #[prost(string, tag = "5")]
+        pub to: String,
+        #[prost(string, tag = "6")]
+        pub edge_type: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StreamItem {
+        #[prost(oneof = "stream_item::Item", tags = "1, 2, 3")]
+        pub item: Option<stream_item::Item>,
+    }
+
+    pub mod stream_item {
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Item {
+            #[prost(message, tag = "1")]
+            Event(super::Event),
+            #[prost(message, tag = "2")]
+            GraphChange(super::GraphChange),
+            #[prost(string, tag = "3")]
+            Error(String),
+        }
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExecuteActionRequest {
+        #[prost(uint32, tag = "1")]
+        pub pid: u32,
+        /// JSON 数组，格式与规则文件中的 actions 相同
+        #[prost(string, tag = "2")]
+        pub actions_json: String,
+        #[prost(string, tag = "3")]
+        pub operation: String,
+        #[prost(string, optional, tag = "4")]
+        pub approval: Option<String>,
+        #[prost(bool, tag = "5")]
+        pub confirm: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message, Deserialize)]
+    pub struct ExecutedAction {
+        #[prost(string, tag = "1")]
+        pub action: String,
+        #[prost(string, tag = "2")]
+        pub result: String,
+        #[prost(uint32, tag = "3")]
+        pub priority: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message, Deserialize)]
+    pub struct FailedAction {
+        #[prost(string, tag = "1")]
+        pub action: String,
+        #[prost(string, tag = "2")]
+        pub error: String,
+        #[prost(uint32, tag = "3")]
+        pub priority: u32,
+        /// error / timeout / denied / rate_limited / missing_privilege
+        #[prost(string, tag = "4")]
+        pub kind: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message, Deserialize)]
+    pub struct ExecuteActionResponse {
+        #[prost(bool, tag = "1")]
+        pub success: bool,
+        #[prost(string, tag = "2")]
+        pub message: String,
+        #[prost(message, repeated, tag = "3")]
+        pub executed_actions: Vec<ExecutedAction>,
+        #[prost(message, repeated, tag = "4")]
+        pub failed_actions: Vec<FailedAction>,
+    }
+}
+
+impl From<ark_core::event::Event> for pb::Event {
+    fn from(event: ark_core::event::Event) -> Self {
+        Self {
+            ts: event.ts,
+            event_type: event.event_type.to_string(),
+            entity_id: event.entity_id,
+            job_id: event.job_id,
+            pid: event.pid,
+            value: event.value,
+            node_id: event.node_id,
+            probe: event.probe,
+            pod: event.pod,
+            container_id: event.container_id,
+        }
+    }
+}
+
+impl From<GraphChange> for pb::GraphChange {
+    fn from(change: GraphChange) -> Self {
+        let node = |change: &str, id: String, node_type: String| Self {
+            change: change.to_string(),
+            id,
+            node_type,
+            ..Default::default()
+        };
+        let edge = |change: &str, from: String, to: String, edge_type: String| Self {
+            change: change.to_string(),
+            from,
+            to,
+            edge_type,
+            ..Default::default()
+        };
+        match change {
+            GraphChange::NodeAdded { id, node_type } => node("node_added", id, node_type),
+            GraphChange::NodeRemoved { id, node_type } => node("node_removed", id, node_type),
+            GraphChange::EdgeAdded { from, to, edge_type } => edge("edge_added", from, to, edge_type),
+            GraphChange::EdgeRemoved { from, to, edge_type } => edge("edge_removed", from, to, edge_type),
+        }
+    }
+}
+
+impl From<ipc::SubscriptionItem> for pb::StreamItem {
+    fn from(item: ipc::SubscriptionItem) -> Self {
+        let item = match item {
+            Ok(StreamItem::Event(event)) => pb::stream_item::Item::Event(event.into()),
+            Ok(StreamItem::GraphChange(change)) => pb::stream_item::Item::GraphChange(change.into()),
+            Err(e) => pb::stream_item::Item::Error(e),
+        };
+        Self { item: Some(item) }
+    }
+}
+
+/// gRPC 服务：与 IPC 共用同一个 `IpcServer` 的状态和配置
+pub struct ArkControlService {
+    server: Arc<IpcServer>,
+}
+
+impl ArkControlService {
+    /// 校验 token 并构造请求上下文（审计中的调用方记为 `grpc:<对端地址>`）
+    fn context<T>(&self, request: &Request<T>) -> Result<RequestContext, Status> {
+        let caller = match request.remote_addr() {
+            Some(addr) => format!("grpc:{}", addr),
+            None => "grpc".to_string(),
+        };
+        let ctx = self.server.request_context(caller);
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("缺少 token（metadata authorization: Bearer <token>）"))?;
+        ipc::authenticate(token.trim(), ctx.auth_token.as_deref().map(String::as_str))
+            .map_err(Status::unauthenticated)?;
+        Ok(ctx)
+    }
+
+    /// 交给 IPC 处理逻辑，把返回的 JSON 转换为响应消息
+    async fn call<R: DeserializeOwned>(&self, ctx: RequestContext, request: RpcRequest) -> Result<R, Status> {
+        let data = ipc::handle_request(request, self.server.graph(), &ctx)
+            .await
+            .map_err(Status::failed_precondition)?;
+        serde_json::from_value(data).map_err(|e| Status::internal(format!("转换响应失败: {}", e)))
+    }
+}
+
+#[tonic::async_trait]
+impl ArkControl for ArkControlService {
+    async fn list_processes(
+        &self,
+        request: Request<pb::ListProcessesRequest>,
+    ) -> Result<Response<pb::ListProcessesResponse>, Status> {
+        let ctx = self.context(&request)?;
+        let request = request.into_inner();
+        let filter = ProcessFilter {
+            job_id: request.job_id,
+            state: request.state,
+            node_id: request.node_id,
+            blocked_only: request.blocked_only,
+        };
+        let processes = self.call(ctx, RpcRequest::ListProcesses { filter }).await?;
+        Ok(Response::new(pb::ListProcessesResponse { processes }))
+    }
+
+    async fn why_process(
+        &self,
+        request: Request<pb::WhyProcessRequest>,
+    ) -> Result<Response<pb::WhyProcessResponse>, Status> {
+        let ctx = self.context(&request)?;
+        let pid = request.into_inner().pid;
+        Ok(Response::new(self.call(ctx, RpcRequest::WhyProcess { pid }).await?))
+    }
+
+    type SubscribeStream = Pin<Box<dyn Stream<Item = Result<pb::StreamItem, Status>> + Send>>;
+
+    async fn subscribe(
+        &self,
+        request: Request<pb::SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let ctx = self.context(&request)?;
+        let request = request.into_inner();
+        let filter = SubscribeFilter {
+            event_types: request.event_types,
+            entity_pattern: request.entity_pattern,
+            events: request.events.unwrap_or(true),
+            graph_changes: request.graph_changes,
+        };
+        let items = ipc::start_subscription(self.server.graph(), &ctx, filter)
+            .await
+            .map_err(Status::failed_precondition)?;
+        // 客户端取消时 stream 被丢弃，后台订阅任务随之结束
+        let stream = futures_util::stream::unfold(items, |mut items| async move {
+            let item = items.recv().await?;
+            Some((Ok(item.into()), items))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn execute_action(
+        &self,
+        request: Request<pb::ExecuteActionRequest>,
+    ) -> Result<Response<pb::ExecuteActionResponse>, Status> {
+        let ctx = self.context(&request)?;
+        let request = request.into_inner();
+        let actions: Vec<ActionType> = serde_json::from_str(&request.actions_json)
+            .map_err(|e| Status::invalid_argument(format!("解析 actions_json 失败: {}", e)))?;
+        let rpc = RpcRequest::ExecuteAction {
+            pid: request.pid,
+            actions,
+            operation: request.operation,
+            approval: request.approval,
+            confirm: request.confirm,
+        };
+        Ok(Response::new(self.call(ctx, rpc).await?))
+    }
+}
+
+/// 启动 gRPC 服务（阻塞运行）
+pub async fn serve(addr: SocketAddr, server: Arc<IpcServer>) -> Result<(), Box<dyn std::error::Error>> {
+    println!("[ark] gRPC 服务已启动，监听: {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(ArkControlServer::new(ArkControlService { server }))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
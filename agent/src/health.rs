@@ -0,0 +1,246 @@
+//! daemon 健康状态：运行时长、探针存活、Hub 连接、事件吞吐和状态图规模
+//!
+//! 探针的事件先经过 `probe_sender` 创建的中转通道（标记探针名称）再进入事件总线，这样能按探针统计
+//! 事件数和最后一次上报时间，探针进程静默退出或卡住时 `ark status` 可以直接看出来。
+
+use ark_core::event::Event;
+use ark_core::graph::StateGraph;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+/// 吞吐统计窗口（秒）
+const THROUGHPUT_WINDOW_SECS: u64 = 60;
+
+/// 单个探针的健康状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeHealth {
+    pub name: String,
+    /// 探针任务是否仍在运行
+    pub running: bool,
+    pub events: u64,
+    /// 最后一次上报事件的时间（毫秒）
+    pub last_event_ts: Option<u64>,
+    /// 探针退出时的错误
+    pub error: Option<String>,
+}
+
+/// Hub 连接状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HubHealth {
+    pub url: String,
+    pub connected: bool,
+    /// 已推送到 Hub 的事件数
+    pub forwarded: u64,
+    pub last_error: Option<String>,
+    /// 断开后重连成功的次数
+    #[serde(default)]
+    pub reconnects: u64,
+    /// 等待补发的消息数
+    #[serde(default)]
+    pub buffered: usize,
+    /// 缓冲区满时丢弃的消息数
+    #[serde(default)]
+    pub dropped: u64,
+    /// Hub 要求减少推送（流控中）
+    #[serde(default)]
+    pub throttled: bool,
+}
+
+/// `status` RPC 的返回结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusReport {
+    /// daemon 启动时间（毫秒）
+    pub started_at: u64,
+    pub uptime_secs: u64,
+    pub probes: Vec<ProbeHealth>,
+    /// 未配置 --hub-url 时为空
+    pub hub: Option<HubHealth>,
+    pub events_total: u64,
+    /// 最近 60 秒的平均事件速率
+    pub events_per_sec: f64,
+    pub graph_nodes: usize,
+    pub graph_edges: usize,
+    /// 规则集代数（未配置规则目录时为空）
+    pub rules_generation: Option<u64>,
+    /// 只读模式（`ark run --read-only`）
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// daemon 运行期间持续更新的健康状态
+pub struct DaemonHealth {
+    started: Instant,
+    started_at: u64,
+    events_total: AtomicU64,
+    /// 按秒分桶的事件数：(秒级时间戳, 事件数)
+    throughput: Mutex<VecDeque<(u64, u64)>>,
+    probes: Mutex<BTreeMap<String, ProbeHealth>>,
+    hub: Mutex<Option<HubHealth>>,
+}
+
+impl Default for DaemonHealth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+impl DaemonHealth {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            started_at: now_ms(),
+            events_total: AtomicU64::new(0),
+            throughput: Mutex::new(VecDeque::new()),
+            probes: Mutex::new(BTreeMap::new()),
+            hub: Mutex::new(None),
+        }
+    }
+
+    /// 为探针创建事件通道：标记探针名称后转发到事件总线，同时记录该探针的事件数和最后上报时间
+    pub fn probe_sender(self: &Arc<Self>, name: &str, bus: mpsc::Sender<Event>) -> mpsc::Sender<Event> {
+        let (tx, mut rx) = mpsc::channel::<Event>(bus.max_capacity());
+        self.probes.lock().unwrap_or_else(|e| e.into_inner()).insert(
+            name.to_string(),
+            ProbeHealth { name: name.to_string(), running: true, events: 0, last_event_ts: None, error: None },
+        );
+
+        let health = Arc::clone(self);
+        let name = name.to_string();
+        tokio::spawn(async move {
+            while let Some(mut event) = rx.recv().await {
+                event.probe = Some(name.clone());
+                if let Some(probe) = health.probes.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&name) {
+                    probe.events += 1;
+                    probe.last_event_ts = Some(event.ts);
+                }
+                if bus.send(event).await.is_err() {
+                    break;
+                }
+            }
+        });
+        tx
+    }
+
+    /// 探针任务结束（正常退出时 error 为 None）
+    pub fn probe_exited(&self, name: &str, error: Option<String>) {
+        if let Some(probe) = self.probes.lock().unwrap_or_else(|e| e.into_inner()).get_mut(name) {
+            probe.running = false;
+            probe.error = error;
+        }
+    }
+
+    /// 探针已从配置中移除（重载后不再显示）
+    pub fn probe_removed(&self, name: &str) {
+        self.probes.lock().unwrap_or_else(|e| e.into_inner()).remove(name);
+    }
+
+    /// 事件消费循环每处理一条事件调用一次
+    pub fn record_event(&self) {
+        self.events_total.fetch_add(1, Ordering::Relaxed);
+        let second = now_ms() / 1000;
+        let mut buckets = self.throughput.lock().unwrap_or_else(|e| e.into_inner());
+        match buckets.back_mut() {
+            Some((ts, count)) if *ts == second => *count += 1,
+            _ => buckets.push_back((second, 1)),
+        }
+        while buckets.front().is_some_and(|(ts, _)| *ts + THROUGHPUT_WINDOW_SECS <= second) {
+            buckets.pop_front();
+        }
+    }
+
+    /// 记录 Hub 连接结果
+    pub fn hub_connected(&self, url: &str, result: Result<(), String>) {
+        *self.hub.lock().unwrap_or_else(|e| e.into_inner()) = Some(HubHealth {
+            url: url.to_string(),
+            connected: result.is_ok(),
+            forwarded: 0,
+            last_error: result.err(),
+            reconnects: 0,
+            buffered: 0,
+            dropped: 0,
+            throttled: false,
+        });
+    }
+
+    /// 记录一次断开后的重连成功
+    pub fn hub_reconnected(&self) {
+        if let Some(hub) = self.hub.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            hub.connected = true;
+            hub.reconnects += 1;
+        }
+    }
+
+    /// 记录 Hub 转发缓冲区的情况
+    pub fn hub_buffer(&self, buffered: usize, dropped: u64) {
+        if let Some(hub) = self.hub.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            hub.buffered = buffered;
+            hub.dropped = dropped;
+        }
+    }
+
+    /// 记录 Hub 的流控状态
+    pub fn hub_throttled(&self, throttled: bool) {
+        if let Some(hub) = self.hub.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            hub.throttled = throttled;
+        }
+    }
+
+    /// 记录一次事件推送结果（推送失败视为连接断开，成功后恢复）
+    pub fn hub_forwarded(&self, result: Result<(), String>) {
+        if let Some(hub) = self.hub.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            match result {
+                Ok(()) => {
+                    hub.connected = true;
+                    hub.forwarded += 1;
+                }
+                Err(e) => {
+                    hub.connected = false;
+                    hub.last_error = Some(e);
+                }
+            }
+        }
+    }
+
+    /// 生成当前状态报告
+    pub async fn report(&self, graph: &StateGraph, rules_generation: Option<u64>) -> StatusReport {
+        let now = now_ms() / 1000;
+        let uptime_secs = self.started.elapsed().as_secs();
+        let recent: u64 = self
+            .throughput
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|(ts, _)| *ts + THROUGHPUT_WINDOW_SECS > now)
+            .map(|(_, count)| count)
+            .sum();
+        // 启动不足一个窗口时按实际运行时长计算
+        let window = uptime_secs.clamp(1, THROUGHPUT_WINDOW_SECS);
+        // 先取出快照再 await，锁不能跨 await 持有
+        let probes = self.probes.lock().unwrap_or_else(|e| e.into_inner()).values().cloned().collect();
+        let hub = self.hub.lock().unwrap_or_else(|e| e.into_inner()).clone();
+
+        StatusReport {
+            started_at: self.started_at,
+            uptime_secs,
+            probes,
+            hub,
+            events_total: self.events_total.load(Ordering::Relaxed),
+            events_per_sec: recent as f64 / window as f64,
+            graph_nodes: graph.node_count().await,
+            graph_edges: graph.edge_count().await,
+            rules_generation,
+            read_only: false,
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! 本地事故历史：规则命中（含识别到的场景）、诊断结论、大模型调用和执行过的修复
+//!
+//! daemon 记录规则命中，`ark diag` / `ark fix` / `ark zap` 记录诊断、大模型调用和修复结果，统一追加到同一个 JSONL 文件
+//! （默认 `~/.ark/history.jsonl`，可用环境变量 `ARK_HISTORY` 覆盖），`ark history` 按 pid / job 浏览。
+//! 每条记录一次写入一行，daemon 和 CLI 同时追加也不会交错。
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 历史记录类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+    /// daemon 流式匹配到的规则
+    RuleMatch,
+    /// `ark diag` 的诊断结论
+    Diagnosis,
+    /// `ark diag` 对大模型的调用（提供商、模型、prompt 哈希、token 数、耗时和回答摘要）
+    LlmCall,
+    /// `ark fix` / `ark zap` 执行的修复
+    Fix,
+}
+
+impl HistoryKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HistoryKind::RuleMatch => "rule_match",
+            HistoryKind::Diagnosis => "diagnosis",
+            HistoryKind::LlmCall => "llm_call",
+            HistoryKind::Fix => "fix",
+        }
+    }
+}
+
+/// 单条历史记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// RFC 3339 时间
+    pub ts: String,
+    pub kind: HistoryKind,
+    /// 写入时由 `HistoryStore` 填充
+    #[serde(default)]
+    pub node_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    /// 识别到的场景
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scene: Option<String>,
+    /// 一行摘要（规则名、首个根因、修复结果）
+    pub summary: String,
+    /// 完整内容（诊断结果、修复报告等）
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub detail: serde_json::Value,
+}
+
+impl HistoryRecord {
+    pub fn new(kind: HistoryKind, summary: impl Into<String>) -> Self {
+        Self {
+            ts: chrono::Utc::now().to_rfc3339(),
+            kind,
+            node_id: String::new(),
+            pid: None,
+            job_id: None,
+            scene: None,
+            summary: summary.into(),
+            detail: serde_json::Value::Null,
+        }
+    }
+
+    pub fn with_target(mut self, pid: Option<u32>, job_id: Option<String>) -> Self {
+        self.pid = pid;
+        self.job_id = job_id;
+        self
+    }
+
+    pub fn with_scene(mut self, scene: Option<String>) -> Self {
+        self.scene = scene;
+        self
+    }
+
+    pub fn with_detail(mut self, detail: serde_json::Value) -> Self {
+        self.detail = detail;
+        self
+    }
+}
+
+/// 浏览历史时的过滤条件
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    pub pid: Option<u32>,
+    pub job_id: Option<String>,
+    pub kind: Option<HistoryKind>,
+}
+
+impl HistoryFilter {
+    fn matches(&self, record: &HistoryRecord) -> bool {
+        self.pid.is_none_or(|pid| record.pid == Some(pid))
+            && self.job_id.as_ref().is_none_or(|job| record.job_id.as_ref() == Some(job))
+            && self.kind.is_none_or(|kind| record.kind == kind)
+    }
+}
+
+/// 默认历史文件：`ARK_HISTORY` 或 `~/.ark/history.jsonl`
+pub fn default_history_path() -> PathBuf {
+    if let Ok(path) = std::env::var("ARK_HISTORY") {
+        return PathBuf::from(path);
+    }
+    let mut home = std::env::var("HOME").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("."));
+    home.push(".ark");
+    home.push("history.jsonl");
+    home
+}
+
+/// 追加写入的历史文件
+pub struct HistoryStore {
+    path: PathBuf,
+    node_id: String,
+}
+
+impl HistoryStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, node_id: crate::hub_forwarder::get_node_id() }
+    }
+
+    /// 追加一条记录（每次重新打开文件，历史文件被移走或删除后自动重建）
+    pub fn append(&self, mut record: HistoryRecord) -> Result<(), String> {
+        if record.node_id.is_empty() {
+            record.node_id = self.node_id.clone();
+        }
+        let mut line = serde_json::to_string(&record).map_err(|e| format!("序列化历史记录失败: {}", e))?;
+        line.push('\n');
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建历史目录失败: {}", e))?;
+        }
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| format!("写入历史文件 {} 失败: {}", self.path.display(), e))
+    }
+
+    /// 追加一条记录，失败时只打印警告（历史记录不影响命令本身的结果）
+    pub fn record(&self, record: HistoryRecord) {
+        if let Err(e) = self.append(record) {
+            eprintln!("[history] 警告：{}", e);
+        }
+    }
+}
+
+/// 读取满足条件的最近 `limit` 条记录（按时间先后排列，文件不存在时为空，无法解析的行跳过）
+pub fn load(path: &Path, filter: &HistoryFilter, limit: usize) -> Result<Vec<HistoryRecord>, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("读取历史文件 {} 失败: {}", path.display(), e)),
+    };
+    let mut records: Vec<HistoryRecord> = content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<HistoryRecord>(line).ok())
+        .filter(|record| filter.matches(record))
+        .take(limit)
+        .collect();
+    records.reverse();
+    Ok(records)
+}
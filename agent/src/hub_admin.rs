@@ -0,0 +1,250 @@
+//! `ark hub`：Hub 运维命令
+//!
+//! 封装 Hub 的管理接口，不必再用 curl 拼 JSON：移除节点、确认故障节点已修复、重新加载规则、导出全局状态图、
+//! 查看活动告警、创建和结束静默。查询需要 viewer 角色，静默需要 operator，移除节点、确认修复和重新加载规则需要 admin
+//! （API 密钥由 `ARK_HUB_TOKEN` 配置，见 `hub_auth`）。
+
+use crate::hub_auth;
+use crate::output::OutputFormat;
+use colored::*;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// 解析静默时长：带单位的整数（如 30m、2h、1d），不带单位时为秒
+pub fn parse_duration(value: &str) -> Result<u64, String> {
+    let (amount, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len()));
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("无效的时长: {}（如 30m、2h、1d）", value))?;
+    let scale = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("无效的时长单位: {}（支持 s、m、h、d）", value)),
+    };
+    Ok(amount.saturating_mul(scale))
+}
+
+fn api(hub_url: &str, path: &str) -> String {
+    format!("{}/api/v1/{}", hub_url.trim_end_matches('/'), path)
+}
+
+/// 毫秒时间戳距现在的时长（如 "5m 前"）
+fn ago(ts: u64) -> String {
+    let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+    let secs = now_ms.saturating_sub(ts) / 1000;
+    match secs {
+        0..60 => format!("{}s 前", secs),
+        60..3600 => format!("{}m 前", secs / 60),
+        _ => format!("{}h 前", secs / 3600),
+    }
+}
+
+/// 毫秒时间戳距现在还有多久
+fn remaining(ts: u64) -> String {
+    let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+    let secs = ts.saturating_sub(now_ms) / 1000;
+    match secs {
+        0..3600 => format!("{}m", secs.div_ceil(60)),
+        _ => format!("{}h{}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// 从节点清单中移除节点（在线时 Hub 断开其连接）
+pub async fn drop_node(hub_url: &str, node_id: &str, yes: bool, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    if !yes {
+        print!("{}", format!("将从 Hub 移除节点 {}（在线时断开连接），是否继续? [y/N]: ", node_id).bright_yellow());
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("y") && !input.trim().eq_ignore_ascii_case("yes") {
+            println!("{}", "已取消".bright_yellow());
+            return Ok(());
+        }
+    }
+    let body = hub_auth::send_json(reqwest::Client::new().delete(api(hub_url, &format!("nodes/{}", node_id)))).await?;
+    if output.is_structured() {
+        output.print(&body)?;
+    } else {
+        println!("{} 节点 {} 已移除", "✅".bright_green(), node_id.bright_cyan());
+    }
+    Ok(())
+}
+
+/// 确认故障节点已修复，Hub 解除其故障污点（发起人取自 $USER）
+pub async fn mark_repaired(hub_url: &str, node_id: &str, reason: Option<String>, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let request = serde_json::json!({
+        "requested_by": std::env::var("USER").ok(),
+        "reason": reason,
+    });
+    let url = api(hub_url, &format!("nodes/{}/repaired", node_id));
+    let body = hub_auth::send_json(reqwest::Client::new().post(url).json(&request)).await?;
+    if output.is_structured() {
+        output.print(&body)?;
+    } else if body["untainted"].as_bool().unwrap_or(false) {
+        println!("{} 节点 {} 已解除故障污点", "✅".bright_green(), node_id.bright_cyan());
+    } else {
+        println!("节点 {} 没有故障污点，无需解除", node_id.bright_cyan());
+    }
+    Ok(())
+}
+
+/// 校验 Hub 的规则目录并通知已连接的节点立即拉取
+pub async fn reload_rules(hub_url: &str, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let body = hub_auth::send_json(reqwest::Client::new().post(api(hub_url, "rules/reload"))).await?;
+    if output.is_structured() {
+        output.print(&body)?;
+    } else {
+        println!(
+            "{} 已重新加载 {} 条规则（{}），通知 {} 个节点",
+            "✅".bright_green(),
+            body["rules"].as_u64().unwrap_or(0),
+            body["checksum"].as_str().unwrap_or("-"),
+            body["notified"].as_u64().unwrap_or(0)
+        );
+    }
+    Ok(())
+}
+
+/// 导出 Hub 的全局状态图
+pub async fn export_graph(hub_url: &str, format: &str, out: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let text = hub_auth::get_text(&format!("{}?format={}", api(hub_url, "graph"), format)).await?;
+    match out {
+        Some(path) => {
+            std::fs::write(path, text)?;
+            eprintln!("[ark] 全局状态图已导出到 {}", path.display());
+        }
+        None => println!("{}", text.trim_end()),
+    }
+    Ok(())
+}
+
+/// 列出活动告警
+pub async fn list_alerts(hub_url: &str, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let body = hub_auth::get_json(&api(hub_url, "alerts")).await?;
+    if output.is_structured() {
+        output.print(&body)?;
+        return Ok(());
+    }
+    let alerts = body.get("alerts").and_then(|a| a.as_array()).ok_or("无法解析 Hub 响应")?;
+    if alerts.is_empty() {
+        println!("没有活动告警");
+        return Ok(());
+    }
+
+    println!(
+        "{:>8} | {:>16} | {:>12} | {:>5} | {:>8} | {}",
+        "SEVERITY".bright_cyan(),
+        "NODE_ID".bright_cyan(),
+        "JOB_ID".bright_cyan(),
+        "COUNT".bright_cyan(),
+        "LAST".bright_cyan(),
+        "TITLE".bright_cyan()
+    );
+    println!("{}", "-".repeat(90));
+    for active in alerts {
+        let alert = &active["alert"];
+        let severity = alert["severity"].as_str().unwrap_or("-");
+        let severity = match severity {
+            "critical" => severity.bright_red(),
+            "warning" => severity.bright_yellow(),
+            _ => severity.normal(),
+        };
+        let mut title = alert["title"].as_str().unwrap_or("-").to_string();
+        if let Some(silence) = active["silenced_by"].as_str() {
+            title = format!("{} {}", title, format!("[静默 {}]", silence).dimmed());
+        }
+        println!(
+            "{:>8} | {:>16} | {:>12} | {:>5} | {:>8} | {}",
+            severity,
+            alert["node_id"].as_str().unwrap_or("-"),
+            alert["job_id"].as_str().unwrap_or("-"),
+            active["count"].as_u64().unwrap_or(0),
+            ago(active["last_seen"].as_u64().unwrap_or(0)),
+            title
+        );
+    }
+    Ok(())
+}
+
+/// 列出未到期的静默
+pub async fn list_silences(hub_url: &str, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let body = hub_auth::get_json(&api(hub_url, "silences")).await?;
+    if output.is_structured() {
+        output.print(&body)?;
+        return Ok(());
+    }
+    let silences = body.get("silences").and_then(|s| s.as_array()).ok_or("无法解析 Hub 响应")?;
+    if silences.is_empty() {
+        println!("没有生效中的静默");
+        return Ok(());
+    }
+
+    println!(
+        "{:>20} | {:>16} | {:>12} | {:>8} | {:>10} | {}",
+        "ID".bright_cyan(),
+        "NODE_ID".bright_cyan(),
+        "JOB_ID".bright_cyan(),
+        "REMAINS".bright_cyan(),
+        "BY".bright_cyan(),
+        "REASON".bright_cyan()
+    );
+    println!("{}", "-".repeat(90));
+    for silence in silences {
+        println!(
+            "{:>20} | {:>16} | {:>12} | {:>8} | {:>10} | {}",
+            silence["id"].as_str().unwrap_or("-"),
+            silence["node_id"].as_str().unwrap_or("*"),
+            silence["job_id"].as_str().unwrap_or("*"),
+            remaining(silence["ends_at"].as_u64().unwrap_or(0)),
+            silence["created_by"].as_str().unwrap_or("-"),
+            silence["reason"].as_str().unwrap_or("")
+        );
+    }
+    Ok(())
+}
+
+/// 静默节点或 job 的告警（发起人取自 $USER）
+pub async fn create_silence(
+    hub_url: &str,
+    node_id: Option<String>,
+    job_id: Option<String>,
+    duration_secs: u64,
+    reason: String,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if node_id.is_none() && job_id.is_none() {
+        return Err("--node 和 --job 至少指定一个".into());
+    }
+    let request = serde_json::json!({
+        "node_id": node_id,
+        "job_id": job_id,
+        "duration_secs": duration_secs,
+        "reason": reason,
+        "created_by": std::env::var("USER").ok(),
+    });
+    let body = hub_auth::send_json(reqwest::Client::new().post(api(hub_url, "silences")).json(&request)).await?;
+    if output.is_structured() {
+        output.print(&body)?;
+    } else {
+        println!(
+            "{} 静默 {} 已创建，{} 后到期",
+            "✅".bright_green(),
+            body["id"].as_str().unwrap_or("-").bright_cyan(),
+            remaining(body["ends_at"].as_u64().unwrap_or(0))
+        );
+    }
+    Ok(())
+}
+
+/// 提前结束静默
+pub async fn remove_silence(hub_url: &str, id: &str, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let body = hub_auth::send_json(reqwest::Client::new().delete(api(hub_url, &format!("silences/{}", id)))).await?;
+    if output.is_structured() {
+        output.print(&body)?;
+    } else {
+        println!("{} 静默 {} 已结束", "✅".bright_green(), id.bright_cyan());
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! 访问 Hub 的令牌
+//!
+//! 令牌由 `ARK_HUB_TOKEN` 环境变量配置（不放在命令行参数中，避免出现在进程列表里）：
+//! daemon 在 WebSocket 握手、拉取规则和校验审批时以 `Authorization: Bearer <token>` 出示 Agent 令牌，
+//! `ark cluster` 和 `ark hub` 命令以同样方式出示 API 密钥。
+
+/// 令牌环境变量
+pub const HUB_TOKEN_ENV: &str = "ARK_HUB_TOKEN";
+
+/// 配置的令牌（未设置或为空时为 None）
+pub fn hub_token() -> Option<String> {
+    std::env::var(HUB_TOKEN_ENV)
+        .ok()
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+/// 为发往 Hub 的 HTTP 请求附加令牌
+pub fn authorize(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match hub_token() {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    }
+}
+
+/// GET Hub 的 JSON 接口（附加令牌；Hub 拒绝时提示配置令牌）
+pub async fn get_json(url: &str) -> Result<serde_json::Value, String> {
+    let response = authorize(reqwest::Client::new().get(url))
+        .send()
+        .await
+        .map_err(|e| format!("请求 Hub 失败: {}", e))?;
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(format!("Hub 拒绝请求（HTTP 401），请通过 {} 配置 API 密钥", HUB_TOKEN_ENV));
+    }
+    response.json().await.map_err(|e| format!("解析 Hub 响应失败: {}", e))
+}
+
+/// 调用 Hub 的管理接口（附加令牌）；Hub 返回错误状态时取响应中的 error 字段
+pub async fn send_json(request: reqwest::RequestBuilder) -> Result<serde_json::Value, String> {
+    let response = authorize(request)
+        .send()
+        .await
+        .map_err(|e| format!("请求 Hub 失败: {}", e))?;
+    let status = response.status();
+    match status {
+        reqwest::StatusCode::UNAUTHORIZED => {
+            return Err(format!("Hub 拒绝请求（HTTP 401），请通过 {} 配置 API 密钥", HUB_TOKEN_ENV));
+        }
+        reqwest::StatusCode::FORBIDDEN => return Err("Hub 拒绝请求（HTTP 403），API 密钥的角色不足".to_string()),
+        _ => {}
+    }
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    if !status.is_success() {
+        let error = body.get("error").and_then(|e| e.as_str()).unwrap_or("未知错误");
+        return Err(format!("Hub 返回错误（HTTP {}）: {}", status.as_u16(), error));
+    }
+    Ok(body)
+}
+
+/// GET Hub 的文本接口（如 dot / graphml 格式的状态图）
+pub async fn get_text(url: &str) -> Result<String, String> {
+    let response = authorize(reqwest::Client::new().get(url))
+        .send()
+        .await
+        .map_err(|e| format!("请求 Hub 失败: {}", e))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(format!("Hub 拒绝请求（HTTP 401），请通过 {} 配置 API 密钥", HUB_TOKEN_ENV));
+    }
+    let text = response.text().await.map_err(|e| format!("读取 Hub 响应失败: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("Hub 返回错误（HTTP {}）: {}", status.as_u16(), text.trim()));
+    }
+    Ok(text)
+}
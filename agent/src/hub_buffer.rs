@@ -0,0 +1,138 @@
+//! Hub 连接断开期间的消息缓冲
+//!
+//! 有界内存队列，满时丢弃最旧的消息并计数。配置 spool 文件（`ark run --hub-spool`）时，
+//! 缓冲的消息同时追加写入文件，daemon 在 Hub 不可用期间重启也不会丢失；补发完成后文件清空。
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 默认缓冲的最大消息数
+pub const DEFAULT_BUFFER_CAPACITY: usize = 10_000;
+
+/// 待补发到 Hub 的消息（已序列化的 JSON 文本）
+pub struct EventBuffer {
+    queue: VecDeque<String>,
+    capacity: usize,
+    /// 因缓冲区满而丢弃的消息数
+    dropped: u64,
+    spool: Option<PathBuf>,
+    /// spool 文件当前的行数（含已丢弃的消息，超过容量两倍时按队列重写）
+    spooled: usize,
+}
+
+impl EventBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            capacity: capacity.max(1),
+            dropped: 0,
+            spool: None,
+            spooled: 0,
+        }
+    }
+
+    /// 落盘到 spool 文件：载入上次未补发的消息（超出容量时只保留最新的）
+    pub fn with_spool(mut self, path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                    self.push_memory(line.to_string());
+                }
+                if !self.queue.is_empty() {
+                    println!("[hub-forwarder] 已载入 {} 条待补发消息: {}", self.queue.len(), path.display());
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("读取 Hub 缓冲文件 {} 失败: {}", path.display(), e)),
+        }
+        self.spool = Some(path.to_path_buf());
+        self.rewrite_spool()?;
+        Ok(self)
+    }
+
+    /// 待补发的消息数
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// 因缓冲区满而丢弃的消息数
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// 缓冲一条消息（写 spool 失败只打印错误，消息仍在内存中）
+    pub fn push(&mut self, message: String) {
+        if let Some(ref path) = self.spool {
+            let written = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", message));
+            match written {
+                Ok(()) => self.spooled += 1,
+                Err(e) => eprintln!("[hub-forwarder] 写入 Hub 缓冲文件 {} 失败: {}", path.display(), e),
+            }
+        }
+        self.push_memory(message);
+        if self.spooled > self.capacity * 2 {
+            if let Err(e) = self.rewrite_spool() {
+                eprintln!("[hub-forwarder] {}", e);
+            }
+        }
+    }
+
+    /// 已发送未确认的消息排回队列最前面（保持原顺序），重连后先重发
+    pub fn requeue(&mut self, messages: Vec<String>) {
+        for message in messages.into_iter().rev() {
+            // 缓冲区满时与 push 一致，丢弃最旧的（即排在最前面的这些）
+            if self.queue.len() == self.capacity {
+                self.dropped += 1;
+                continue;
+            }
+            self.queue.push_front(message);
+        }
+        self.sync_spool();
+    }
+
+    /// 最早的待补发消息
+    pub fn front(&self) -> Option<&String> {
+        self.queue.front()
+    }
+
+    /// 最早的消息已补发
+    pub fn pop_front(&mut self) {
+        self.queue.pop_front();
+    }
+
+    /// 补发结束后同步 spool 文件（全部补发时清空）
+    pub fn sync_spool(&mut self) {
+        if let Err(e) = self.rewrite_spool() {
+            eprintln!("[hub-forwarder] {}", e);
+        }
+    }
+
+    fn push_memory(&mut self, message: String) {
+        if self.queue.len() == self.capacity {
+            self.queue.pop_front();
+            self.dropped += 1;
+        }
+        self.queue.push_back(message);
+    }
+
+    /// 按内存队列重写 spool 文件
+    fn rewrite_spool(&mut self) -> Result<(), String> {
+        let Some(ref path) = self.spool else {
+            return Ok(());
+        };
+        let mut content = String::new();
+        for message in &self.queue {
+            content.push_str(message);
+            content.push('\n');
+        }
+        std::fs::write(path, content).map_err(|e| format!("写入 Hub 缓冲文件 {} 失败: {}", path.display(), e))?;
+        self.spooled = self.queue.len();
+        Ok(())
+    }
+}
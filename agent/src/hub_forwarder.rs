@@ -3,26 +3,143 @@
 //! 实现边缘折叠（Edge Roll-up）逻辑：
 //! - 只推送错误事件、进程状态变化、触发规则的事件
 //! - 过滤高频波动（如 gpu.util 的微小变化）
+//!
+//! 连接断开（含首次连接失败）后按带抖动的指数退避自动重连；断开期间的事件和审计记录进入
+//! 有界缓冲区（见 `hub_buffer`），重连后按原顺序补发。
+//!
+//! 每次建立连接先发送注册消息（node_id、hostname、labels、版本、能力），之后每隔 `HEARTBEAT_INTERVAL`
+//! 发送心跳，Hub 据此判断节点在线、失联（stale）或离线。
+//!
+//! 每条消息附加单调递增的 `seq`，Hub 处理后回复 `{"ack": seq}`（累计确认）。已发送未确认的消息
+//! 在连接断开或超过 `ACK_TIMEOUT` 未确认时重新排入缓冲区、重连后重发，Hub 按序号去重（至少一次投递）。
+//!
+//! Hub 下发的命令执行完成后回报 `{"type": "command_result", "command_id", "status", "message"}`，
+//! 与事件一样带 seq，断开期间同样缓冲补发。
+//!
+//! Hub 处理不过来时下发 `{"flow": "slow"}`，此后暂停推送利用率、存储和带宽类事件（`suppressed`），
+//! 直到收到 `{"flow": "normal"}` 或连接断开，避免 Hub 卡顿时各节点的发送队列无限增长。
+//!
+//! Hub 重新加载规则后下发 `{"rules": <checksum>}`，以 `--rules-source` 拉取规则的节点随即刷新，不等刷新间隔。
+//!
+//! Hub 对修复后的故障节点下发自检命令（`{"intent": "self_test"}`）：运行 `ark run --self-test` 指定的命令，
+//! 未指定时运行 nvidia-smi 或 npu-smi info，退出码为 0 时回报 success，Hub 据此解除节点的故障污点。
+//!
+//! Hub 优雅退出前下发 `{"shutdown": true}`，此时主动断开：已发送未确认的消息排回缓冲区，之后的事件留在本地，
+//! 按退避重连到新的 Hub 实例后补发。
 
 use ark_core::event::{Event, EventType};
-use tokio_tungstenite::{connect_async, tungstenite::Message, WebSocketStream, MaybeTlsStream};
+use ark_core::graph::NodeKey;
+use tokio_tungstenite::{connect_async_tls_with_config, tungstenite::Message, Connector, WebSocketStream, MaybeTlsStream};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::{HeaderValue, AUTHORIZATION};
+use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt, StreamExt};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, mpsc, Notify, RwLock};
 use tokio::net::TcpStream;
 use std::collections::HashSet;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use rand::Rng;
 use serde_json;
 use crate::exec::executor::ActionExecutor;
+use crate::exec::{ActionLimiter, ActionPolicy, Executor, PolicyLevel, PolicySubject, MISSING_PRIVILEGE, RATE_LIMITED};
 use crate::exec::action::ActionType;
+use crate::approval;
+use crate::audit::{AuditLogger, AuditOrigin};
+use crate::health::DaemonHealth;
+use crate::hub_buffer::{EventBuffer, DEFAULT_BUFFER_CAPACITY};
+use crate::hub_tls::HubTls;
+use crate::telemetry;
+use tracing::Instrument;
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type WsSender = SplitSink<WsStream, Message>;
+type WsReceiver = SplitStream<WsStream>;
+
+/// 重连退避的初始间隔
+const RECONNECT_INITIAL: Duration = Duration::from_secs(1);
+
+/// 重连退避的最大间隔
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+
+/// 已发送的消息超过该时间未被确认时，视为连接异常：断开重连并重发
+const ACK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 检查确认超时的间隔
+const ACK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 心跳间隔
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Hub 下发的自检命令的超时
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// daemon 的 Hub 配置：WebSocket 地址 + 断开期间的缓冲
+#[derive(Debug, Clone)]
+pub struct HubOptions {
+    pub url: Option<String>,
+    /// 缓冲的最大消息数
+    pub buffer: usize,
+    /// 缓冲落盘文件
+    pub spool: Option<PathBuf>,
+    /// 注册时上报的节点标签
+    pub labels: BTreeMap<String, String>,
+    /// wss:// 连接的 CA 和客户端证书
+    pub tls: HubTls,
+    /// Hub 通知规则已更新时唤醒规则刷新（规则来源为远程地址时）
+    pub rules_refresh: Option<Arc<Notify>>,
+    /// Hub 下发自检时运行的命令
+    pub self_test: Option<String>,
+}
+
+/// 解析 `key=value` 形式的节点标签
+pub fn parse_label(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => Ok((key.trim().to_string(), value.trim().to_string())),
+        _ => Err(format!("无效的节点标签: {}（格式为 key=value）", value)),
+    }
+}
+
+/// 事件推送结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// 已发送到 Hub
+    Sent,
+    /// 连接断开，已进入缓冲区，重连后补发
+    Buffered,
+}
 
 /// Hub 事件转发器
 pub struct HubForwarder {
     hub_url: String,
     node_id: String,
-    ws_sender: Option<Arc<RwLock<Option<WsSender>>>>,
-    command_listener_handle: Option<tokio::task::JoinHandle<()>>,
+    link: Option<Arc<HubLink>>,
+    buffer_capacity: usize,
+    spool: Option<PathBuf>,
+    labels: BTreeMap<String, String>,
+    tls: HubTls,
+    health: Option<Arc<DaemonHealth>>,
+    connection_handle: Option<tokio::task::JoinHandle<()>>,
+    audit_forward_handle: Option<tokio::task::JoinHandle<()>>,
     forwarded_bindings: Arc<RwLock<HashSet<(u32, String)>>>,
     last_util_values: Arc<RwLock<std::collections::HashMap<(u32, String), f64>>>,
+    command_ctx: CommandContext,
+}
+
+/// 执行 Hub 下发命令时的上下文：审批校验地址、审计日志、动作冷却、事件总线、规则刷新、自检命令和只读模式
+#[derive(Clone)]
+struct CommandContext {
+    node_id: String,
+    hub_api: Option<String>,
+    audit_logger: Option<Arc<AuditLogger>>,
+    limiter: Option<Arc<ActionLimiter>>,
+    events: Option<mpsc::Sender<Event>>,
+    rules_refresh: Option<Arc<Notify>>,
+    self_test: Option<String>,
+    read_only: bool,
 }
 
 impl HubForwarder {
@@ -30,98 +147,454 @@ impl HubForwarder {
     pub fn new(hub_url: String, node_id: String) -> Self {
         Self {
             hub_url,
-            node_id,
-            ws_sender: None,
-            command_listener_handle: None,
+            node_id: node_id.clone(),
+            link: None,
+            buffer_capacity: DEFAULT_BUFFER_CAPACITY,
+            spool: None,
+            labels: BTreeMap::new(),
+            tls: HubTls::default(),
+            health: None,
+            connection_handle: None,
+            audit_forward_handle: None,
             forwarded_bindings: Arc::new(RwLock::new(HashSet::new())),
             last_util_values: Arc::new(RwLock::new(std::collections::HashMap::new())),
+            command_ctx: CommandContext {
+                node_id,
+                hub_api: None,
+                audit_logger: None,
+                limiter: None,
+                events: None,
+                rules_refresh: None,
+                self_test: None,
+                read_only: false,
+            },
         }
     }
 
-    /// 连接到 Hub WebSocket 服务器
+    /// 配置下发命令的审批校验（Hub HTTP API 地址）、审计日志和动作冷却，需在 connect 之前调用
+    pub fn with_command_guard(
+        mut self,
+        hub_api: Option<String>,
+        audit_logger: Option<Arc<AuditLogger>>,
+        limiter: Option<Arc<ActionLimiter>>,
+    ) -> Self {
+        self.command_ctx.hub_api = hub_api;
+        self.command_ctx.audit_logger = audit_logger;
+        self.command_ctx.limiter = limiter;
+        self
+    }
+
+    /// 设置事件总线入口：执行下发命令后以 action.exec 事件发回总线，需在 connect 之前调用
+    pub fn with_event_sink(mut self, events: mpsc::Sender<Event>) -> Self {
+        self.command_ctx.events = Some(events);
+        self
+    }
+
+    /// Hub 通知规则已更新（`{"rules": <checksum>}`）时唤醒规则刷新，需在 connect 之前调用
+    pub fn with_rules_refresh(mut self, rules_refresh: Option<Arc<Notify>>) -> Self {
+        self.command_ctx.rules_refresh = rules_refresh;
+        self
+    }
+
+    /// Hub 下发自检（`{"intent": "self_test"}`）时运行的命令，未指定时运行 nvidia-smi 或 npu-smi info，需在 connect 之前调用
+    pub fn with_self_test(mut self, self_test: Option<String>) -> Self {
+        self.command_ctx.self_test = self_test;
+        self
+    }
+
+    /// 只读模式：Hub 下发的修复命令不执行，只记录审计日志，需在 connect 之前调用
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.command_ctx.read_only = read_only;
+        self
+    }
+
+    /// 断开期间的缓冲：最大消息数和可选的落盘文件，需在 connect 之前调用
+    pub fn with_buffer(mut self, capacity: usize, spool: Option<PathBuf>) -> Self {
+        self.buffer_capacity = capacity;
+        self.spool = spool;
+        self
+    }
+
+    /// 注册时上报的节点标签，需在 connect 之前调用
+    pub fn with_labels(mut self, labels: BTreeMap<String, String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// wss:// 连接的 CA 和客户端证书，需在 connect 之前调用
+    pub fn with_tls(mut self, tls: HubTls) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// 注册消息：每次建立连接后首先发送
+    fn registration(&self) -> serde_json::Value {
+        let mut capabilities = vec!["events", "ack", "heartbeat"];
+        if self.command_ctx.audit_logger.is_some() {
+            capabilities.push("audit");
+            // 执行下发命令需要审计日志，只读模式下拒绝执行
+            if !self.command_ctx.read_only {
+                capabilities.push("fix");
+            }
+        }
+        serde_json::json!({
+            "type": "register",
+            "node_id": self.node_id,
+            "hostname": hostname(),
+            "labels": self.labels,
+            "agent_version": env!("CARGO_PKG_VERSION"),
+            "capabilities": capabilities,
+            "heartbeat_secs": HEARTBEAT_INTERVAL.as_secs(),
+        })
+    }
+
+    /// 连接状态和缓冲情况写入 daemon 健康状态（`ark status`），需在 connect 之前调用
+    pub fn with_health(mut self, health: Arc<DaemonHealth>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// 连接到 Hub WebSocket 服务器并启动连接维护任务
+    ///
+    /// 首次连接失败不返回错误：后台按退避间隔重试，期间事件进入缓冲区。只有缓冲文件无法读写或证书有误时返回错误。
     pub async fn connect(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let url = url::Url::parse(&self.hub_url)?;
-        let ws_stream = connect_async(url).await?.0;
-        let (write, read) = ws_stream.split();
+        let connector = self.tls.connector()?;
+        let mut buffer = EventBuffer::new(self.buffer_capacity);
+        if let Some(ref spool) = self.spool {
+            buffer = buffer.with_spool(spool)?;
+        }
+        let link = Arc::new(HubLink {
+            url: self.hub_url.clone(),
+            connector,
+            token: crate::hub_auth::hub_token(),
+            node_id: self.node_id.clone(),
+            registration: self.registration().to_string(),
+            sender: RwLock::new(None),
+            buffer: std::sync::Mutex::new(buffer),
+            unacked: std::sync::Mutex::new(VecDeque::new()),
+            next_seq: AtomicU64::new(initial_seq()),
+            slow: AtomicBool::new(false),
+            health: self.health.clone(),
+        });
+        self.link = Some(Arc::clone(&link));
+
+        let first = link.open().await;
+        if let Some(ref health) = self.health {
+            health.hub_connected(&self.hub_url, first.as_ref().map(|_| ()).map_err(Clone::clone));
+        }
+        let first = match first {
+            Ok(receiver) => {
+                println!("[hub-forwarder] 已连接到 Hub: {}", self.hub_url);
+                Some(receiver)
+            }
+            Err(e) => {
+                eprintln!("[hub-forwarder] 连接 Hub {} 失败: {}，将在后台重试，期间事件进入缓冲区", self.hub_url, e);
+                None
+            }
+        };
+        link.report_buffer();
+
+        // 连接维护任务：监听 Hub 下发的命令，断开后重连
+        let ctx = Arc::new(self.command_ctx.clone());
+        self.connection_handle = Some(tokio::spawn(Self::maintain(Arc::clone(&link), first, ctx)));
         
-        // 保存 write 端用于发送事件
-        let sender = Arc::new(RwLock::new(Some(write)));
-        self.ws_sender = Some(sender);
+        // 审计记录镜像到 Hub（复用同一连接和缓冲区），由 Hub 集中保存
+        if let Some(ref logger) = self.command_ctx.audit_logger {
+            let mut entries = logger.subscribe();
+            let node_id = self.node_id.clone();
+            self.audit_forward_handle = Some(tokio::spawn(async move {
+                loop {
+                    let entry = match entries.recv().await {
+                        Ok(entry) => entry,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            eprintln!("[hub-forwarder] 审计记录转发落后，{} 条未上报（本地审计日志不受影响）", skipped);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    link.send(serde_json::json!({ "node_id": node_id, "audit": entry })).await;
+                }
+            }));
+        }
         
-        // 启动命令监听任务
-        let listener_handle = tokio::spawn(async move {
-            let mut receiver = read;
-            while let Some(msg) = receiver.next().await {
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        // 解析 Hub 下发的命令
-                        if let Ok(cmd) = serde_json::from_str::<HubCommand>(&text) {
-                            if let Err(e) = Self::handle_command(cmd).await {
-                                eprintln!("[hub-forwarder] 执行命令失败: {}", e);
+        Ok(())
+    }
+
+    /// 连接维护：监听当前连接上的命令，连接断开后按带抖动的指数退避重连
+    async fn maintain(link: Arc<HubLink>, mut receiver: Option<WsReceiver>, ctx: Arc<CommandContext>) {
+        let mut attempt = 0u32;
+        loop {
+            let current = match receiver.take() {
+                Some(current) => current,
+                None => {
+                    let delay = reconnect_delay(attempt);
+                    attempt = attempt.saturating_add(1);
+                    tokio::time::sleep(delay).await;
+                    match link.open().await {
+                        Ok(current) => {
+                            println!("[hub-forwarder] 已重新连接到 Hub: {}", link.url);
+                            if let Some(ref health) = link.health {
+                                health.hub_reconnected();
                             }
-                        } else {
-                            // 不是命令，可能是其他消息，忽略
+                            current
+                        }
+                        Err(e) => {
+                            eprintln!("[hub-forwarder] 重连 Hub 失败（第 {} 次）: {}", attempt, e);
+                            link.disconnected(e).await;
+                            continue;
                         }
                     }
-                    Ok(Message::Close(_)) => {
-                        println!("[hub-forwarder] Hub 关闭连接");
-                        break;
-                    }
-                    Err(e) => {
-                        eprintln!("[hub-forwarder] 接收消息错误: {}", e);
-                        break;
-                    }
-                    _ => {}
                 }
-            }
-        });
-        
-        self.command_listener_handle = Some(listener_handle);
-        
-        println!("[hub-forwarder] 已连接到 Hub: {}", self.hub_url);
-        Ok(())
+            };
+            attempt = 0;
+            let reason = Self::listen(current, &link, &ctx).await;
+            link.disconnected(reason).await;
+        }
     }
-    
-    /// 处理 Hub 下发的命令
-    async fn handle_command(cmd: HubCommand) -> Result<(), Box<dyn std::error::Error>> {
-        match cmd.intent.as_str() {
-            "fix" => {
-                println!("[hub-forwarder] 收到修复命令: PID={}, action={:?}", 
-                    cmd.target_pid, cmd.action);
-                
-                // 根据 action 字符串创建 ActionType
-                let action = if let Some(action_str) = &cmd.action {
-                    Self::action_from_string(action_str)?
-                } else {
-                    // 默认：优雅降级
-                    ActionType::GracefulShutdown {
-                        signal: 10, // SIGUSR1
-                        wait_seconds: 10,
-                        force_kill: true,
+
+    /// 处理一个连接上 Hub 的确认和下发的命令并定期发送心跳，直到连接关闭或确认超时，返回断开原因
+    async fn listen(mut receiver: WsReceiver, link: &HubLink, ctx: &CommandContext) -> String {
+        let mut ack_check = tokio::time::interval(ACK_CHECK_INTERVAL);
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        // 注册消息刚发送过，第一次心跳在一个间隔之后
+        heartbeat.tick().await;
+        loop {
+            // 先处理已到达的消息，避免执行命令期间积压的确认被误判为超时
+            let msg = tokio::select! {
+                biased;
+                msg = receiver.next() => msg,
+                _ = ack_check.tick() => {
+                    if let Some(overdue) = link.overdue() {
+                        let reason = format!("{} 条消息超过 {} 秒未确认", overdue, ACK_TIMEOUT.as_secs());
+                        eprintln!("[hub-forwarder] {}，断开重连并重发", reason);
+                        return reason;
                     }
-                };
-                
-                // 执行动作
-                let executor = ActionExecutor::new();
-                match executor.execute(&action, cmd.target_pid).await {
-                    Ok(msg) => {
-                        println!("[hub-forwarder] 命令执行成功: {}", msg);
+                    continue;
+                }
+                _ = heartbeat.tick() => {
+                    if let Err(e) = link.heartbeat().await {
+                        eprintln!("[hub-forwarder] 发送心跳失败: {}", e);
+                        return e;
                     }
-                    Err(e) => {
-                        eprintln!("[hub-forwarder] 命令执行失败: {}", e);
-                        return Err(e.into());
+                    continue;
+                }
+            };
+            let Some(msg) = msg else {
+                return "Hub 连接已断开".to_string();
+            };
+            match msg {
+                Ok(Message::Text(text)) => {
+                    if let Ok(ack) = serde_json::from_str::<HubAck>(&text) {
+                        link.acked(ack.ack);
+                    } else if let Ok(flow) = serde_json::from_str::<HubFlow>(&text) {
+                        link.set_slow(flow.flow == "slow");
+                    } else if let Ok(rules) = serde_json::from_str::<HubRules>(&text) {
+                        match ctx.rules_refresh {
+                            Some(ref refresh) => {
+                                println!("[hub-forwarder] Hub 规则已更新（{}），立即拉取", rules.rules);
+                                refresh.notify_one();
+                            }
+                            None => println!("[hub-forwarder] Hub 规则已更新，本节点未配置远程规则来源（--rules-source），忽略"),
+                        }
+                    } else if let Ok(shutdown) = serde_json::from_str::<HubShutdown>(&text) {
+                        if shutdown.shutdown {
+                            println!("[hub-forwarder] Hub 正在关闭，转为本地缓冲并等待重连");
+                            return "Hub 正在关闭".to_string();
+                        }
+                    } else if let Ok(cmd) = serde_json::from_str::<HubCommand>(&text) {
+                        // 解析 Hub 下发的命令
+                        Self::handle_command(cmd, ctx, link).await;
+                    } else {
+                        // 不是命令，可能是其他消息，忽略
                     }
                 }
+                Ok(Message::Close(_)) => {
+                    println!("[hub-forwarder] Hub 关闭连接");
+                    return "Hub 关闭连接".to_string();
+                }
+                Err(e) => {
+                    eprintln!("[hub-forwarder] 接收消息错误: {}", e);
+                    return e.to_string();
+                }
+                _ => {}
             }
-            _ => {
-                eprintln!("[hub-forwarder] 未知命令意图: {}", cmd.intent);
+        }
+    }
+    
+    /// 处理 Hub 下发的命令，带 command_id 时把执行结果回报给 Hub
+    async fn handle_command(cmd: HubCommand, ctx: &CommandContext, link: &HubLink) {
+        let span = tracing::info_span!("hub.fix", pid = cmd.target_pid, command_id = cmd.command_id.as_deref().unwrap_or("-"));
+        telemetry::set_parent(&span, cmd.traceparent.as_deref());
+        let (status, message) = match Self::run_command(&cmd, ctx).instrument(span).await {
+            Ok(msg) => {
+                println!("[hub-forwarder] 命令执行成功: {}", msg);
+                ("success", msg)
             }
+            Err((status, e)) => {
+                eprintln!("[hub-forwarder] 执行命令失败: {}", e);
+                (status, e)
+            }
+        };
+        if let Some(command_id) = cmd.command_id {
+            link.send(serde_json::json!({
+                "type": "command_result",
+                "node_id": ctx.node_id,
+                "command_id": command_id,
+                "status": status,
+                "message": message,
+            }))
+            .await;
         }
-        Ok(())
+    }
+    
+    /// 执行 Hub 下发的命令，失败时返回审计状态和错误信息
+    async fn run_command(cmd: &HubCommand, ctx: &CommandContext) -> Result<String, (&'static str, String)> {
+        // 自检只读取设备状态，只读模式下同样执行
+        if cmd.intent == "self_test" {
+            println!("[hub-forwarder] 收到自检命令: command_id={:?}", cmd.command_id);
+            return Self::self_test(ctx).await.map_err(|e| ("failed", e));
+        }
+        if cmd.intent != "fix" {
+            return Err(("rejected", format!("未知命令意图: {}", cmd.intent)));
+        }
+        println!("[hub-forwarder] 收到修复命令: PID={}, action={:?}, command_id={:?}",
+            cmd.target_pid, cmd.action, cmd.command_id);
+        
+        // 与 IPC 的修复请求一致，审计日志是强制的
+        let Some(ref logger) = ctx.audit_logger else {
+            return Err((
+                "rejected",
+                "daemon 未配置审计日志（ark run --audit-log），拒绝执行 Hub 下发的修复命令".to_string(),
+            ));
+        };
+        let origin = AuditOrigin {
+            source: "hub",
+            user: cmd.requested_by.clone().unwrap_or_else(|| "hub".to_string()),
+            command_id: cmd.command_id.clone(),
+        };
+        
+        // 执行、拒绝和失败都写入同一条审计日志（审批信息一并写入）
+        let mut details = vec!["来源: hub".to_string()];
+        let result = Self::execute_fix(cmd, ctx, &mut details).await;
+        let status = match &result {
+            Ok(_) => "success",
+            Err((status, e)) => {
+                details.push(format!("error={}", e));
+                status
+            }
+        };
+        logger
+            .record(&origin, "hub.fix", cmd.target_pid, cmd.job_id.as_deref(), status, &details.join("; "))
+            .await;
+        result
+    }
+    
+    /// 运行自检命令，退出码为 0 时通过
+    async fn self_test(ctx: &CommandContext) -> Result<String, String> {
+        let command_line = match ctx.self_test {
+            Some(ref command_line) => command_line.clone(),
+            None => default_self_test().ok_or("未配置自检命令（ark run --self-test），且 PATH 中没有 nvidia-smi 或 npu-smi")?,
+        };
+        let mut parts = command_line.split_whitespace();
+        let program = parts.next().ok_or("自检命令为空")?;
+        let output = tokio::time::timeout(SELF_TEST_TIMEOUT, tokio::process::Command::new(program).args(parts).output())
+            .await
+            .map_err(|_| format!("自检超时（{} 秒）: {}", SELF_TEST_TIMEOUT.as_secs(), command_line))?
+            .map_err(|e| format!("执行自检命令 {} 失败: {}", command_line, e))?;
+        if output.status.success() {
+            Ok(format!("自检通过: {}", command_line))
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let last_line = |text: &str| text.lines().rfind(|line| !line.trim().is_empty()).map(str::to_string);
+            let detail = last_line(&stderr).or_else(|| last_line(&stdout)).unwrap_or_default();
+            Err(format!("自检未通过: {}（{}）{}", command_line, output.status, detail.trim()))
+        }
+    }
+    
+    /// 执行 Hub 下发的修复命令，失败时返回审计状态和错误信息
+    async fn execute_fix(
+        cmd: &HubCommand,
+        ctx: &CommandContext,
+        details: &mut Vec<String>,
+    ) -> Result<String, (&'static str, String)> {
+        if ctx.read_only {
+            return Err((
+                "read_only",
+                "daemon 以只读模式运行（ark run --read-only），拒绝执行 Hub 下发的修复命令".to_string(),
+            ));
+        }
+        
+        // 根据 action 字符串创建 ActionType
+        let action = match &cmd.action {
+            Some(action_str) => Self::action_from_string(action_str).map_err(|e| ("rejected", e))?,
+            // 默认：优雅降级
+            None => ActionType::GracefulShutdown {
+                signal: 10, // SIGUSR1
+                wait_seconds: 10,
+                force_kill: true,
+            },
+        };
+        details.push(format!("动作: {}", action.description()));
+        
+        // 动作策略：禁止的动作不执行（Hub 下发的命令已在 Hub 侧确认，confirm 级别视为允许）
+        let subject = PolicySubject {
+            job: cmd.job_id.clone(),
+            user: Some("hub".to_string()),
+        };
+        let policy = ActionPolicy::load_default().map_err(|e| ("failed", e))?;
+        if policy.decide(&action, &subject) == PolicyLevel::Deny {
+            return Err(("denied", format!("动作策略禁止执行 {}", action.kind())));
+        }
+        
+        // 破窗审批：破坏性动作没有 token 一律拒绝（与本地 zap 一致），携带 token 时向 Hub 复核（须是为发起人签发的）
+        if action.is_destructive() || cmd.approval.is_some() {
+            let (scope, target) = match &cmd.job_id {
+                Some(job_id) => ("cluster_fix", format!("job-{}", job_id)),
+                None => ("fix", NodeKey::process(Some(&ctx.node_id), cmd.target_pid).to_string()),
+            };
+            let approval = approval::check(
+                ctx.hub_api.as_deref(),
+                cmd.approval.as_deref(),
+                scope,
+                &target,
+                cmd.requested_by.as_deref(),
+            )
+            .await
+            .map_err(|e| ("rejected", e))?;
+            details.push(approval.audit_summary());
+        }
+        
+        // 执行动作（Hub 重复下发的命令受动作冷却限制）
+        let mut executor = ActionExecutor::new().with_job(cmd.job_id.clone());
+        if let Some(ref limiter) = ctx.limiter {
+            executor = executor.with_limiter(Arc::clone(limiter));
+        }
+        if let Some(ref events) = ctx.events {
+            executor = executor.with_events(events.clone());
+        }
+        let result = match crate::config::action_timeout() {
+            Some(limit) => tokio::time::timeout(limit, executor.execute(&action, cmd.target_pid))
+                .await
+                .unwrap_or_else(|_| Err(format!("执行超时（{} 秒），已取消，动作可能已部分生效", limit.as_secs()))),
+            None => executor.execute(&action, cmd.target_pid).await,
+        };
+        result.map_err(|e| {
+            let status = if e.starts_with(RATE_LIMITED) {
+                RATE_LIMITED
+            } else if e.starts_with(MISSING_PRIVILEGE) {
+                MISSING_PRIVILEGE
+            } else if e.starts_with("执行超时") {
+                "timeout"
+            } else {
+                "failed"
+            };
+            (status, e)
+        })
     }
     
     /// 从字符串创建 ActionType
-    fn action_from_string(action_str: &str) -> Result<ActionType, String> {
+    pub(crate) fn action_from_string(action_str: &str) -> Result<ActionType, String> {
         match action_str.to_lowercase().as_str() {
             "gracefulshutdown" | "graceful_shutdown" => {
                 Ok(ActionType::GracefulShutdown {
@@ -158,6 +631,12 @@ impl HubForwarder {
             
             // 拓扑降级：必须推送
             EventType::TopoLinkDown => true,
+
+            // 规则命中：daemon 主动告警，必须推送
+            EventType::RuleMatched => true,
+
+            // 干预动作：因果链上的关键节点，必须推送
+            EventType::ActionExec => true,
             
             // 计算资源事件：只在建立新绑定或利用率剧烈变化时推送
             EventType::ComputeUtil | EventType::ComputeMem => {
@@ -223,37 +702,318 @@ impl HubForwarder {
         }
     }
 
-    /// 推送事件到 Hub
-    pub async fn forward_event(&self, mut event: Event) -> Result<(), Box<dyn std::error::Error>> {
+    /// Hub 处于流控（slow）状态时，利用率、存储和带宽类事件不推送
+    pub fn suppressed(&self, event: &Event) -> bool {
+        let slow = self.link.as_ref().is_some_and(|link| link.slow.load(Ordering::Relaxed));
+        slow && matches!(
+            event.event_type,
+            EventType::ComputeUtil
+                | EventType::ComputeMem
+                | EventType::StorageIops
+                | EventType::StorageQDepth
+                | EventType::TransportBw
+        )
+    }
+
+    /// 推送事件到 Hub（连接断开时进入缓冲区）
+    pub async fn forward_event(&self, mut event: Event) -> Result<Delivery, Box<dyn std::error::Error>> {
         // 注入 node_id
         event.node_id = Some(self.node_id.clone());
         
-        // 序列化为 JSON
-        let json = serde_json::to_string(&event)?;
+        // 序列化为 JSON（发送时附加 seq）
+        let json = serde_json::to_value(&event)?;
         
-        // 发送到 WebSocket
-        if let Some(ref sender_arc) = self.ws_sender {
-            let mut sender = sender_arc.write().await;
-            if let Some(ref mut ws_sender) = *sender {
-                ws_sender.send(Message::Text(json)).await?;
-                return Ok(());
+        match self.link {
+            Some(ref link) => Ok(link.send(json).await),
+            None => Err("Hub 转发器未启动".into()),
+        }
+    }
+}
+
+/// 第 attempt 次重连前的等待时间：指数增长到上限，再在 [一半, 全部] 之间随机抖动，避免各节点同时重连
+fn reconnect_delay(attempt: u32) -> Duration {
+    let base = RECONNECT_INITIAL
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(RECONNECT_MAX)
+        .as_millis() as u64;
+    Duration::from_millis(rand::thread_rng().gen_range(base / 2..=base))
+}
+
+/// 序号起点：Agent 启动时间（微秒），保证重启后序号仍大于 Hub 已接收的序号
+fn initial_seq() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64
+}
+
+/// 取出消息中的 seq（缓冲区中的消息已带 seq）
+fn message_seq(message: &str) -> u64 {
+    serde_json::from_str::<serde_json::Value>(message)
+        .ok()
+        .and_then(|value| value.get("seq").and_then(|seq| seq.as_u64()))
+        .unwrap_or(0)
+}
+
+/// 与 Hub 的连接：断开时发送端为空，消息进入缓冲区
+struct HubLink {
+    url: String,
+    /// 自定义 CA 或客户端证书时的 TLS 连接器
+    connector: Option<Connector>,
+    /// 握手时出示的 Agent 令牌（ARK_HUB_TOKEN）
+    token: Option<String>,
+    node_id: String,
+    /// 注册消息（JSON），每次建立连接后首先发送
+    registration: String,
+    sender: RwLock<Option<WsSender>>,
+    buffer: std::sync::Mutex<EventBuffer>,
+    /// 已发送未确认的消息：(seq, 消息, 发送时间)，按 seq 递增
+    unacked: std::sync::Mutex<VecDeque<(u64, String, Instant)>>,
+    next_seq: AtomicU64,
+    /// Hub 要求减少推送
+    slow: AtomicBool,
+    health: Option<Arc<DaemonHealth>>,
+}
+
+impl HubLink {
+    /// 建立连接、注册节点并补发缓冲的消息，返回接收端
+    ///
+    /// 补发期间持有发送端的写锁，新消息等补发完成后再发送，保证顺序。
+    async fn open(&self) -> Result<WsReceiver, String> {
+        let url = url::Url::parse(&self.url).map_err(|e| format!("无效的 Hub 地址: {}", e))?;
+        let mut request = url.into_client_request().map_err(|e| format!("无效的 Hub 地址: {}", e))?;
+        if let Some(ref token) = self.token {
+            let value = HeaderValue::from_str(&format!("Bearer {}", token)).map_err(|e| format!("无效的 Hub 令牌: {}", e))?;
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+        let (mut write, read) = connect_async_tls_with_config(request, None, false, self.connector.clone())
+            .await
+            .map_err(|e| e.to_string())?
+            .0
+            .split();
+        write
+            .send(Message::Text(self.registration.clone()))
+            .await
+            .map_err(|e| format!("发送注册消息失败: {}", e))?;
+        let mut sender = self.sender.write().await;
+        let flushed = self.flush(&mut write).await?;
+        if flushed > 0 {
+            println!("[hub-forwarder] 已补发 {} 条缓冲消息", flushed);
+        }
+        *sender = Some(write);
+        Ok(read)
+    }
+
+    /// 按顺序补发缓冲区中的消息，发送失败时剩余消息留在缓冲区
+    async fn flush(&self, write: &mut WsSender) -> Result<usize, String> {
+        let mut flushed = 0;
+        loop {
+            let next = self.buffer.lock().unwrap_or_else(|e| e.into_inner()).front().cloned();
+            let Some(message) = next else {
+                break;
+            };
+            if let Err(e) = write.send(Message::Text(message.clone())).await {
+                self.sync_buffer();
+                return Err(format!("补发缓冲消息失败（已补发 {} 条）: {}", flushed, e));
             }
+            self.buffer.lock().unwrap_or_else(|e| e.into_inner()).pop_front();
+            self.unacked
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push_back((message_seq(&message), message, Instant::now()));
+            flushed += 1;
+        }
+        self.sync_buffer();
+        Ok(flushed)
+    }
+
+    /// 附加 seq 后发送一条消息；未连接或发送失败时进入缓冲区
+    ///
+    /// 序号在持有发送端写锁时分配，保证发送（或进入缓冲区）的顺序与序号一致。
+    async fn send(&self, mut message: serde_json::Value) -> Delivery {
+        let mut sender = self.sender.write().await;
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        if let Some(object) = message.as_object_mut() {
+            object.insert("seq".to_string(), seq.into());
+        }
+        let message = message.to_string();
+
+        if let Some(ref mut ws_sender) = *sender {
+            match ws_sender.send(Message::Text(message.clone())).await {
+                Ok(()) => {
+                    self.unacked
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push_back((seq, message, Instant::now()));
+                    return Delivery::Sent;
+                }
+                Err(e) => {
+                    // 读端随后也会结束，由连接维护任务重连
+                    *sender = None;
+                    self.requeue_unacked();
+                    self.report_error(e.to_string());
+                }
+            }
+        }
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner()).push(message);
+        self.report_buffer();
+        Delivery::Buffered
+    }
+
+    /// 发送心跳（不带 seq，不缓冲）
+    async fn heartbeat(&self) -> Result<(), String> {
+        let mut sender = self.sender.write().await;
+        let Some(ref mut ws_sender) = *sender else {
+            return Err("Hub 连接已断开".to_string());
+        };
+        let message = serde_json::json!({ "type": "heartbeat", "node_id": self.node_id });
+        ws_sender.send(Message::Text(message.to_string())).await.map_err(|e| e.to_string())
+    }
+
+    /// Hub 确认收到 seq 及之前的消息
+    fn acked(&self, seq: u64) {
+        let mut unacked = self.unacked.lock().unwrap_or_else(|e| e.into_inner());
+        while unacked.front().is_some_and(|(sent, _, _)| *sent <= seq) {
+            unacked.pop_front();
+        }
+    }
+
+    /// 最早的未确认消息已超时时，返回未确认的消息数
+    fn overdue(&self) -> Option<usize> {
+        let unacked = self.unacked.lock().unwrap_or_else(|e| e.into_inner());
+        let (_, _, sent_at) = unacked.front()?;
+        (sent_at.elapsed() > ACK_TIMEOUT).then_some(unacked.len())
+    }
+
+    /// Hub 下发的流控状态
+    fn set_slow(&self, slow: bool) {
+        if self.slow.swap(slow, Ordering::Relaxed) == slow {
+            return;
+        }
+        if slow {
+            println!("[hub-forwarder] Hub 处理变慢，暂停推送利用率类事件");
+        } else {
+            println!("[hub-forwarder] Hub 已恢复，恢复推送利用率类事件");
+        }
+        if let Some(ref health) = self.health {
+            health.hub_throttled(slow);
+        }
+    }
+
+    /// 连接断开：清空发送端，未确认的消息排回缓冲区最前面（重连后先重发），并记录错误
+    ///
+    /// 流控状态随连接失效，新连接上由 Hub 重新下发
+    async fn disconnected(&self, error: String) {
+        let mut sender = self.sender.write().await;
+        *sender = None;
+        self.set_slow(false);
+        self.requeue_unacked();
+        drop(sender);
+        self.report_error(error);
+    }
+
+    /// 未确认的消息排回缓冲区最前面（调用方持有发送端写锁）
+    fn requeue_unacked(&self) {
+        let unacked: Vec<String> = self
+            .unacked
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain(..)
+            .map(|(_, message, _)| message)
+            .collect();
+        if !unacked.is_empty() {
+            println!("[hub-forwarder] {} 条已发送未确认的消息将在重连后重发", unacked.len());
+            self.buffer.lock().unwrap_or_else(|e| e.into_inner()).requeue(unacked);
+            self.report_buffer();
+        }
+    }
+
+    fn report_error(&self, error: String) {
+        if let Some(ref health) = self.health {
+            health.hub_forwarded(Err(error));
+        }
+    }
+
+    fn sync_buffer(&self) {
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner()).sync_spool();
+        self.report_buffer();
+    }
+
+    /// 缓冲情况写入健康状态
+    fn report_buffer(&self) {
+        if let Some(ref health) = self.health {
+            let buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+            health.hub_buffer(buffer.pending(), buffer.dropped());
         }
-        
-        Err("WebSocket 连接未建立".into())
     }
 }
 
+/// Hub 对已处理消息的确认
+#[derive(serde::Deserialize)]
+struct HubAck {
+    ack: u64,
+}
+
+/// Hub 的流控消息
+#[derive(serde::Deserialize)]
+struct HubFlow {
+    flow: String,
+}
+
+/// Hub 的规则更新通知（新规则包的校验和）
+#[derive(serde::Deserialize)]
+struct HubRules {
+    rules: String,
+}
+
+/// 未配置自检命令时按已安装的驱动工具选择
+fn default_self_test() -> Option<String> {
+    let path = std::env::var_os("PATH")?;
+    let found = |name: &str| std::env::split_paths(&path).any(|dir| dir.join(name).is_file());
+    if found("nvidia-smi") {
+        Some("nvidia-smi".to_string())
+    } else if found("npu-smi") {
+        Some("npu-smi info".to_string())
+    } else {
+        None
+    }
+}
+
+/// Hub 的退出通知
+#[derive(serde::Deserialize)]
+struct HubShutdown {
+    shutdown: bool,
+}
+
 /// Hub 命令结构
 #[derive(serde::Deserialize)]
 struct HubCommand {
     intent: String,
     target_pid: u32,
     action: Option<String>,
+    #[serde(default)]
+    job_id: Option<String>,
+    /// 破窗审批 token（高危动作需要）
+    #[serde(default)]
+    approval: Option<String>,
+    /// Hub 生成的命令 ID，写入审计日志并随执行结果回报，以便与 Hub 侧记录对应
+    #[serde(default)]
+    command_id: Option<String>,
+    /// 在 Hub 上发起命令的用户
+    #[serde(default)]
+    requested_by: Option<String>,
+    /// Hub 侧 span 的 W3C traceparent
+    #[serde(default)]
+    traceparent: Option<String>,
 }
 
 /// 获取当前节点 ID（使用 hostname）
 pub fn get_node_id() -> String {
+    hostname()
+}
+
+/// 本机 hostname
+fn hostname() -> String {
     use std::process::Command;
     
     // 尝试获取 hostname
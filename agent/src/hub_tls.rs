@@ -0,0 +1,74 @@
+//! 连接 Hub 的 TLS 配置（wss://）
+//!
+//! 未指定任何证书文件时使用内置的公共根证书校验 Hub；`--hub-ca` 指定私有 CA，
+//! `--hub-cert/--hub-key` 向 Hub 出示客户端证书（Hub 以 `--tls-client-ca` 要求时必需）。
+
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ClientConfig, RootCertStore};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_tungstenite::Connector;
+
+/// Hub 连接的证书文件（PEM）
+#[derive(Debug, Clone, Default)]
+pub struct HubTls {
+    /// 校验 Hub 服务端证书的 CA
+    pub ca: Option<PathBuf>,
+    /// 客户端证书
+    pub cert: Option<PathBuf>,
+    /// 客户端私钥
+    pub key: Option<PathBuf>,
+}
+
+impl HubTls {
+    /// 构建 TLS 连接器；未配置任何证书文件时返回 None（wss:// 使用默认根证书）
+    pub fn connector(&self) -> Result<Option<Connector>, String> {
+        if self.ca.is_none() && self.cert.is_none() && self.key.is_none() {
+            return Ok(None);
+        }
+        let mut roots = RootCertStore::empty();
+        match self.ca {
+            Some(ref path) => {
+                for cert in load_certs(path)? {
+                    roots
+                        .add(cert)
+                        .map_err(|e| format!("无效的 Hub CA 证书 {}: {}", path.display(), e))?;
+                }
+            }
+            None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        }
+        let builder = ClientConfig::builder().with_root_certificates(roots);
+        let config = match (&self.cert, &self.key) {
+            (Some(cert), Some(key)) => builder
+                .with_client_auth_cert(load_certs(cert)?, load_key(key)?)
+                .map_err(|e| format!("客户端证书 {} 与私钥 {} 不匹配: {}", cert.display(), key.display(), e))?,
+            (None, None) => builder.with_no_client_auth(),
+            _ => return Err("--hub-cert 和 --hub-key 须同时指定".to_string()),
+        };
+        Ok(Some(Connector::Rustls(Arc::new(config))))
+    }
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, String> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| format!("读取 {} 失败: {}", path.display(), e))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("解析证书 {} 失败: {}", path.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("{} 中没有 PEM 证书", path.display()));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>, String> {
+    rustls_pemfile::private_key(&mut open(path)?)
+        .map_err(|e| format!("解析私钥 {} 失败: {}", path.display(), e))?
+        .ok_or_else(|| format!("{} 中没有 PEM 私钥", path.display()))
+}
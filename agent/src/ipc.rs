@@ -1,24 +1,438 @@
-use ark_core::graph::StateGraph;
+use ark_core::event::{Event, EventType};
+use ark_core::export::ExportFormat;
+use ark_core::graph::{EdgeType, NodeKey, NodeType, StateGraph};
+use ark_core::rules::{matches_pattern, validate_pattern, ReloadableRuleEngine, RuleEngine, RuleMatch};
+use ark_core::straggler::DEFAULT_STRAGGLER_MARGIN;
+use crate::approval;
+use crate::audit::{AuditLogEntry, AuditLogger, AuditOrigin};
+use crate::exec::{
+    caller_user, default_journal_path, ActionExecutor, ActionLimiter, ActionPolicy, ActionType, ActuatorRegistry,
+    FailureKind, FixEngine, FixJournal, FixResult, PolicySubject, RollbackOutcome, MISSING_PRIVILEGE,
+};
+use crate::metrics::MetricsCollector;
+use crate::health::{DaemonHealth, StatusReport};
+use crate::ipc_auth::{self, Permission};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tracing::Instrument;
 #[cfg(unix)]
 use tokio::net::{UnixListener, UnixStream};
 #[cfg(windows)]
-use tokio::net::{TcpListener, TcpStream};
+use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeClient, NamedPipeServer, ServerOptions};
 use std::path::PathBuf;
 
+/// IPC 协议版本：新增 RPC 或改变已有 RPC 的请求/响应格式时递增
+pub const PROTOCOL_VERSION: u32 = 4;
+
+/// daemon 支持的全部方法（与 `RpcRequest::method` 一致，hello 响应中返回给客户端）
+pub const METHODS: &[&str] = &[
+    "hello",
+    "list_processes",
+    "why_process",
+    "ping",
+    "auth",
+    "graph_export",
+    "graph_remove_node",
+    "graph_remove_edge",
+    "graph_set_meta",
+    "match_rules",
+    "graph_snapshot",
+    "get_graph",
+    "status",
+    "recent_events",
+    "rule_history",
+    "audit_entries",
+    "subscribe",
+    "execute_action",
+    "get_events",
+    "rollback_fix",
+];
+
 /// RPC 请求类型
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "method")]
 pub enum RpcRequest {
+    /// 活跃进程列表（filter 缺省时返回全部）
     #[serde(rename = "list_processes")]
-    ListProcesses,
+    ListProcesses {
+        #[serde(default)]
+        filter: ProcessFilter,
+    },
     #[serde(rename = "why_process")]
     WhyProcess { pid: u32 },
     #[serde(rename = "ping")]
     Ping,
+    /// 协议协商：交换协议版本，返回 daemon 支持的方法（`ServerInfo`）
+    #[serde(rename = "hello")]
+    Hello {
+        protocol_version: u32,
+        #[serde(default)]
+        client_version: String,
+    },
+    /// 用共享 token 认证当前连接，成功后该连接获得 act 权限
+    #[serde(rename = "auth")]
+    Auth { token: String },
+    /// 导出状态图（format: dot / json / graphml）
+    #[serde(rename = "graph_export")]
+    GraphExport { format: String },
+    /// 运维操作：删除节点（需 confirm=true，记录审计日志）
+    #[serde(rename = "graph_remove_node")]
+    GraphRemoveNode { node_id: String, confirm: bool },
+    /// 运维操作：删除边（edge_type 为空时删除两点间所有边）
+    #[serde(rename = "graph_remove_edge")]
+    GraphRemoveEdge {
+        from: String,
+        to: String,
+        edge_type: Option<String>,
+        confirm: bool,
+    },
+    /// 运维操作：覆盖节点 metadata 字段
+    #[serde(rename = "graph_set_meta")]
+    GraphSetMeta {
+        node_id: String,
+        key: String,
+        value: String,
+        confirm: bool,
+    },
+    /// 运维操作：由 daemon 执行修复动作（高危动作需要审批 token，daemon 必须配置审计日志）
+    #[serde(rename = "execute_action")]
+    ExecuteAction {
+        pid: u32,
+        /// 按执行顺序排列的动作
+        actions: Vec<ActionType>,
+        /// 操作名（fix / zap），用于审批校验和审计记录
+        operation: String,
+        /// 破窗审批 token（由 daemon 向 Hub 校验）
+        #[serde(default)]
+        approval: Option<String>,
+        confirm: bool,
+    },
+    /// 运维操作：按修复日志撤销一次修复中的可回滚动作（同一修复只能回滚一次）
+    #[serde(rename = "rollback_fix")]
+    RollbackFix { fix_id: String, confirm: bool },
+    /// 用 daemon 持有的（热加载）规则集匹配当前状态图
+    #[serde(rename = "match_rules")]
+    MatchRules,
+    /// 状态图快照（JSON 导出）+ 最近的错误事件，供 `ark watch` 轮询
+    #[serde(rename = "graph_snapshot")]
+    GraphSnapshot { error_events: usize },
+    /// 完整节点和边（JSON 导出格式）；指定 pid 时只返回该进程 depth 跳内的邻域
+    #[serde(rename = "get_graph")]
+    GetGraph {
+        #[serde(default)]
+        pid: Option<u32>,
+        #[serde(default = "default_graph_depth")]
+        depth: usize,
+    },
+    /// daemon 健康状态：运行时长、探针、Hub 连接、事件吞吐和图规模
+    #[serde(rename = "status")]
+    Status,
+    /// 最近的事件（按时间先后，最多 limit 条）
+    #[serde(rename = "recent_events")]
+    RecentEvents { limit: usize },
+    /// 按条件查询 daemon 保留的事件历史（容量由状态图配置 history_capacity 决定），返回最近 limit 条
+    #[serde(rename = "get_events")]
+    GetEvents {
+        /// 只返回该时间戳（毫秒）及之后的事件
+        #[serde(default)]
+        since: Option<u64>,
+        /// 事件类型（为空时不过滤）
+        #[serde(default, rename = "type")]
+        event_types: Vec<String>,
+        /// 实体模式（支持 glob，`re:` 前缀为正则）
+        #[serde(default)]
+        entity: Option<String>,
+        #[serde(default = "default_event_limit")]
+        limit: usize,
+    },
+    /// 最近的规则命中（rule.matched 事件）和当前规则集代数
+    #[serde(rename = "rule_history")]
+    RuleHistory { limit: usize },
+    /// 审计日志中最近的记录（未配置审计日志时返回错误）
+    #[serde(rename = "audit_entries")]
+    AuditEntries { limit: usize },
+    /// 订阅实时推送：先返回一个确认响应，之后每条匹配的事件或图结构变化一个响应帧（`StreamItem`），
+    /// 直到客户端断开
+    #[serde(rename = "subscribe")]
+    Subscribe {
+        #[serde(default)]
+        filter: SubscribeFilter,
+    },
+}
+
+impl RpcRequest {
+    /// 方法名（与序列化的 method 字段一致）
+    pub fn method(&self) -> &'static str {
+        match self {
+            RpcRequest::ListProcesses { .. } => "list_processes",
+            RpcRequest::WhyProcess { .. } => "why_process",
+            RpcRequest::Hello { .. } => "hello",
+            RpcRequest::Ping => "ping",
+            RpcRequest::Auth { .. } => "auth",
+            RpcRequest::GraphExport { .. } => "graph_export",
+            RpcRequest::GraphRemoveNode { .. } => "graph_remove_node",
+            RpcRequest::GraphRemoveEdge { .. } => "graph_remove_edge",
+            RpcRequest::GraphSetMeta { .. } => "graph_set_meta",
+            RpcRequest::MatchRules => "match_rules",
+            RpcRequest::GraphSnapshot { .. } => "graph_snapshot",
+            RpcRequest::GetGraph { .. } => "get_graph",
+            RpcRequest::Status => "status",
+            RpcRequest::RecentEvents { .. } => "recent_events",
+            RpcRequest::GetEvents { .. } => "get_events",
+            RpcRequest::RuleHistory { .. } => "rule_history",
+            RpcRequest::AuditEntries { .. } => "audit_entries",
+            RpcRequest::Subscribe { .. } => "subscribe",
+            RpcRequest::ExecuteAction { .. } => "execute_action",
+            RpcRequest::RollbackFix { .. } => "rollback_fix",
+        }
+    }
+
+    /// 调用该方法所需的权限：运维操作为 act，其余查询为 read
+    pub fn required_permission(&self) -> Permission {
+        match self {
+            RpcRequest::Ping | RpcRequest::Hello { .. } | RpcRequest::Auth { .. } => Permission::None,
+            RpcRequest::GraphRemoveNode { .. }
+            | RpcRequest::GraphRemoveEdge { .. }
+            | RpcRequest::GraphSetMeta { .. }
+            | RpcRequest::ExecuteAction { .. }
+            | RpcRequest::RollbackFix { .. } => Permission::Act,
+            _ => Permission::Read,
+        }
+    }
+}
+
+/// hello 响应：daemon 的协议版本和能力
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerInfo {
+    /// 不支持 hello 的旧版 daemon 为 0
+    pub protocol_version: u32,
+    pub daemon_version: String,
+    /// 支持的方法（旧版 daemon 为空，表示未知）
+    pub methods: Vec<String>,
+}
+
+impl ServerInfo {
+    fn current() -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            daemon_version: env!("CARGO_PKG_VERSION").to_string(),
+            methods: METHODS.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    /// 不支持 hello 的旧版 daemon
+    fn legacy() -> Self {
+        Self { protocol_version: 0, daemon_version: "unknown".to_string(), methods: Vec::new() }
+    }
+
+    /// 方法是否可用（旧版 daemon 的能力未知，一律尝试）
+    pub fn supports(&self, method: &str) -> bool {
+        self.methods.is_empty() || self.methods.iter().any(|m| m == method)
+    }
+}
+
+/// 请求解析失败时的错误：不认识的方法单独说明，提示版本不一致而不是只给出 serde 错误
+fn parse_error(request_buf: &[u8], error: serde_json::Error) -> String {
+    let method = serde_json::from_slice::<serde_json::Value>(request_buf)
+        .ok()
+        .and_then(|v| v.get("method").and_then(|m| m.as_str()).map(str::to_string));
+    match method {
+        Some(method) if !METHODS.contains(&method.as_str()) => format!(
+            "daemon（{}，协议版本 {}）不支持方法 {}，请升级 daemon 或使用与其版本一致的 ark",
+            env!("CARGO_PKG_VERSION"),
+            PROTOCOL_VERSION,
+            method
+        ),
+        _ => format!("解析请求失败: {}", error),
+    }
+}
+
+/// 检查连接已获得的权限是否足以调用该方法
+fn authorize(request: &RpcRequest, granted: Permission) -> Result<(), String> {
+    let required = request.required_permission();
+    if granted >= required {
+        return Ok(());
+    }
+    Err(format!(
+        "权限不足：{} 需要 {} 权限（以 root 或 daemon 用户运行，或通过 {} 提供 token）",
+        request.method(),
+        required.as_str(),
+        ipc_auth::TOKEN_ENV
+    ))
+}
+
+/// 处理 auth 请求：token 正确时返回新的连接权限
+pub(crate) fn authenticate(token: &str, expected: Option<&str>) -> Result<Permission, String> {
+    match expected {
+        Some(expected) if ipc_auth::token_matches(expected, token) => Ok(Permission::Act),
+        Some(_) => Err("token 无效".to_string()),
+        None => Err("daemon 未启用 token 认证".to_string()),
+    }
+}
+
+/// `subscribe` 的推送内容和服务端过滤条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscribeFilter {
+    /// 只推送这些类型的事件（为空时不过滤）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub event_types: Vec<String>,
+    /// 实体模式（支持 glob，`re:` 前缀为正则）：匹配事件的 entity_id，以及图变化涉及的节点
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_pattern: Option<String>,
+    /// 推送事件
+    #[serde(default = "default_true")]
+    pub events: bool,
+    /// 推送状态图结构变化（节点、边的增删）
+    #[serde(default)]
+    pub graph_changes: bool,
+}
+
+impl Default for SubscribeFilter {
+    fn default() -> Self {
+        Self { event_types: Vec::new(), entity_pattern: None, events: true, graph_changes: false }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// `get_graph` 按进程裁剪时的默认跳数：覆盖同 job 其他进程的资源，满足场景分析器的需要
+pub const DEFAULT_GRAPH_DEPTH: usize = 3;
+
+fn default_graph_depth() -> usize {
+    DEFAULT_GRAPH_DEPTH
+}
+
+/// `get_events` 未指定 limit 时最多返回的事件数
+pub const DEFAULT_EVENT_LIMIT: usize = 1000;
+
+fn default_event_limit() -> usize {
+    DEFAULT_EVENT_LIMIT
+}
+
+/// `get_events` 的查询条件
+#[derive(Debug, Clone, Default)]
+pub struct EventQuery {
+    /// 只返回该时间戳（毫秒）及之后的事件
+    pub since: Option<u64>,
+    pub event_types: Vec<String>,
+    pub entity: Option<String>,
+    pub limit: usize,
+}
+
+/// 状态图结构变化（不含 metadata 更新，状态变化通过事件本身推送）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum GraphChange {
+    NodeAdded { id: String, node_type: String },
+    NodeRemoved { id: String, node_type: String },
+    EdgeAdded { from: String, to: String, edge_type: String },
+    EdgeRemoved { from: String, to: String, edge_type: String },
+}
+
+impl GraphChange {
+    /// 变化涉及的节点 ID
+    pub fn node_ids(&self) -> Vec<&str> {
+        match self {
+            GraphChange::NodeAdded { id, .. } | GraphChange::NodeRemoved { id, .. } => vec![id],
+            GraphChange::EdgeAdded { from, to, .. } | GraphChange::EdgeRemoved { from, to, .. } => vec![from, to],
+        }
+    }
+}
+
+/// 订阅连接上推送的数据帧（`RpcResponse.data`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamItem {
+    Event(Event),
+    GraphChange(GraphChange),
+}
+
+/// `list_processes` 的服务端过滤条件，同时给出时取交集
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessFilter {
+    /// job_id 模式（支持 glob，`re:` 前缀为正则）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    /// 进程状态（精确匹配，如 running / blocked）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// 主机 node_id 模式；不带命名空间的进程视为本机
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    /// 只返回有 waits_on / blocked_by 出边的进程
+    #[serde(default)]
+    pub blocked_only: bool,
+}
+
+/// 请求上下文：调用方标识、认证 token、审计日志、规则引擎、事件流、健康状态和指标
+pub(crate) struct RequestContext {
+    caller: String,
+    pub(crate) auth_token: Option<Arc<String>>,
+    audit_logger: Option<Arc<AuditLogger>>,
+    rule_engine: Option<Arc<ReloadableRuleEngine>>,
+    event_stream: Option<broadcast::Sender<Event>>,
+    health: Option<Arc<DaemonHealth>>,
+    metrics: Option<Arc<MetricsCollector>>,
+    /// 校验审批 token 的 Hub 地址
+    hub_api: Option<String>,
+    actuators: Arc<ActuatorRegistry>,
+    limiter: Option<Arc<ActionLimiter>>,
+    /// 修复动作的 action.exec 事件发回的事件总线
+    event_sink: Option<mpsc::Sender<Event>>,
+    /// 只读模式：拒绝执行和回滚修复动作
+    read_only: bool,
+}
+
+impl RequestContext {
+    /// 只读模式下拒绝修复类请求
+    fn check_writable(&self, operation: &str) -> Result<(), String> {
+        if self.read_only {
+            return Err(format!("daemon 以只读模式运行（ark run --read-only），拒绝执行 {}", operation));
+        }
+        Ok(())
+    }
+
+    /// 审计日志中的请求来源：调用方的用户名，无法解析时记录调用方标识
+    fn audit_origin(&self) -> AuditOrigin {
+        AuditOrigin {
+            source: "ipc",
+            user: caller_user(&self.caller).unwrap_or_else(|| self.caller.clone()),
+            command_id: None,
+        }
+    }
+
+    /// 记录被拒绝或未能执行的修复请求，返回原错误
+    async fn audit_refusal(
+        &self,
+        operation: &str,
+        pid: u32,
+        job: Option<&str>,
+        status: &str,
+        details: &str,
+        error: String,
+    ) -> String {
+        if let Some(ref logger) = self.audit_logger {
+            let details = format!("{}; error={}", details, error);
+            logger.record(&self.audit_origin(), operation, pid, job, status, &details).await;
+        }
+        error
+    }
+
+    /// 执行修复动作的执行器：daemon 的外部执行器插件，执行结果发回事件总线
+    fn action_executor(&self) -> ActionExecutor {
+        let executor = ActionExecutor::with_actuators(Arc::clone(&self.actuators));
+        match self.event_sink {
+            Some(ref events) => executor.with_events(events.clone()),
+            None => executor,
+        }
+    }
 }
 
 /// RPC 响应
@@ -68,13 +482,35 @@ pub fn default_socket_path() -> PathBuf {
 
 #[cfg(windows)]
 pub fn default_socket_path() -> PathBuf {
-    // Windows 不支持 UDS，返回空路径（将使用 TCP）
+    // Windows 不支持 UDS，返回空路径（将使用命名管道）
     PathBuf::new()
 }
 
+/// Windows 命名管道名：由 `--port` 区分同一台机器上的多个 daemon
+///
+/// 管道使用默认 DACL：只有创建者、Administrators 和 SYSTEM 能以读写方式打开，远程客户端被拒绝
+#[cfg(windows)]
+pub fn pipe_name(port: u16) -> String {
+    format!(r"\\.\pipe\ark-{}", port)
+}
+
 /// IPC 服务器：提供对 StateGraph 的远程查询接口
 pub struct IpcServer {
     graph: Arc<StateGraph>,
+    audit_logger: Option<Arc<AuditLogger>>,
+    rule_engine: Option<Arc<ReloadableRuleEngine>>,
+    event_stream: Option<broadcast::Sender<Event>>,
+    health: Option<Arc<DaemonHealth>>,
+    metrics: Option<Arc<MetricsCollector>>,
+    hub_api: Option<String>,
+    actuators: Arc<ActuatorRegistry>,
+    limiter: Option<Arc<ActionLimiter>>,
+    event_sink: Option<mpsc::Sender<Event>>,
+    read_only: bool,
+    /// 共享 token（None 时不接受 auth 请求）
+    auth_token: Option<Arc<String>>,
+    /// 开始监听后通知一次（systemd 就绪通知使用）
+    ready: std::sync::Mutex<Option<oneshot::Sender<()>>>,
     #[cfg(unix)]
     socket_path: PathBuf,
     #[cfg(windows)]
@@ -86,13 +522,142 @@ impl IpcServer {
     pub fn new(graph: Arc<StateGraph>, socket_path: Option<PathBuf>) -> Self {
         Self {
             graph,
+            audit_logger: None,
+            rule_engine: None,
+            event_stream: None,
+            health: None,
+            metrics: None,
+            hub_api: None,
+            auth_token: None,
+            actuators: Arc::new(ActuatorRegistry::default()),
+            limiter: None,
+            event_sink: None,
+            read_only: false,
+            ready: std::sync::Mutex::new(None),
             socket_path: socket_path.unwrap_or_else(default_socket_path),
         }
     }
 
     #[cfg(windows)]
     pub fn new(graph: Arc<StateGraph>, port: u16) -> Self {
-        Self { graph, port }
+        Self {
+            graph,
+            audit_logger: None,
+            rule_engine: None,
+            event_stream: None,
+            health: None,
+            metrics: None,
+            hub_api: None,
+            auth_token: None,
+            actuators: Arc::new(ActuatorRegistry::default()),
+            limiter: None,
+            event_sink: None,
+            read_only: false,
+            ready: std::sync::Mutex::new(None),
+            port,
+        }
+    }
+
+    /// 设置审计日志（运维类 RPC 会写入审计记录）
+    pub fn with_audit_logger(mut self, audit_logger: Option<Arc<AuditLogger>>) -> Self {
+        self.audit_logger = audit_logger;
+        self
+    }
+
+    /// 设置 daemon 持有的热加载规则引擎（match_rules RPC 使用）
+    pub fn with_rule_engine(mut self, rule_engine: Option<Arc<ReloadableRuleEngine>>) -> Self {
+        self.rule_engine = rule_engine;
+        self
+    }
+
+    /// 设置 daemon 的事件广播（subscribe_events RPC 使用）
+    pub fn with_event_stream(mut self, event_stream: broadcast::Sender<Event>) -> Self {
+        self.event_stream = Some(event_stream);
+        self
+    }
+
+    /// 设置 daemon 健康状态（status RPC 使用）
+    pub fn with_health(mut self, health: Arc<DaemonHealth>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// 设置 Prometheus 指标（execute_action RPC 记录动作执行结果）
+    pub fn with_metrics(mut self, metrics: Arc<MetricsCollector>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// 设置 Hub API 地址（execute_action RPC 校验审批 token 使用）
+    pub fn with_hub_api(mut self, hub_api: Option<String>) -> Self {
+        self.hub_api = hub_api;
+        self
+    }
+
+    /// 设置外部执行器（execute_action RPC 执行 plugin 动作使用，与动态配置共享以便热加载）
+    pub fn with_actuators(mut self, actuators: Arc<ActuatorRegistry>) -> Self {
+        self.actuators = actuators;
+        self
+    }
+
+    /// 设置动作冷却（execute_action RPC 与 Hub 下发的命令共享，同一目标不会被重复处置）
+    pub fn with_action_limiter(mut self, limiter: Arc<ActionLimiter>) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// 设置事件总线入口：execute_action / rollback_fix 执行的动作以 action.exec 事件发回总线
+    pub fn with_event_sink(mut self, event_sink: mpsc::Sender<Event>) -> Self {
+        self.event_sink = Some(event_sink);
+        self
+    }
+
+    /// 只读模式（`ark run --read-only`）：只提供观测类请求，修复和回滚请求一律拒绝
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// 设置共享 token：连接发送正确的 token 后获得 act 权限
+    pub fn with_auth_token(mut self, auth_token: Option<String>) -> Self {
+        self.auth_token = auth_token.map(Arc::new);
+        self
+    }
+
+    /// 设置就绪通知：监听成功后发送一次
+    pub fn with_ready(mut self, ready: oneshot::Sender<()>) -> Self {
+        *self.ready.get_mut().unwrap_or_else(|e| e.into_inner()) = Some(ready);
+        self
+    }
+
+    /// 单个连接的请求上下文
+    pub(crate) fn request_context(&self, caller: String) -> RequestContext {
+        RequestContext {
+            caller,
+            auth_token: self.auth_token.clone(),
+            audit_logger: self.audit_logger.clone(),
+            rule_engine: self.rule_engine.clone(),
+            event_stream: self.event_stream.clone(),
+            health: self.health.clone(),
+            metrics: self.metrics.clone(),
+            hub_api: self.hub_api.clone(),
+            actuators: Arc::clone(&self.actuators),
+            limiter: self.limiter.clone(),
+            event_sink: self.event_sink.clone(),
+            read_only: self.read_only,
+        }
+    }
+
+    /// daemon 的状态图（gRPC 服务与 IPC 共用）
+    #[cfg(feature = "grpc")]
+    pub(crate) fn graph(&self) -> Arc<StateGraph> {
+        Arc::clone(&self.graph)
+    }
+
+    fn notify_ready(&self) {
+        if let Some(ready) = self.ready.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = ready.send(());
+        }
     }
 
     /// 启动 IPC 服务器（阻塞运行）
@@ -119,14 +684,28 @@ impl IpcServer {
             std::fs::set_permissions(&self.socket_path, perms)?;
         }
         
+        // Socket 属主即 daemon 自身的 uid，同 uid 的调用方获得 act 权限
+        let owner_uid = {
+            use std::os::unix::fs::MetadataExt;
+            std::fs::metadata(&self.socket_path)?.uid()
+        };
+
         println!("[ark] IPC 服务器已启动，监听 Unix Socket: {}", self.socket_path.display());
+        self.notify_ready();
 
         loop {
             match listener.accept().await {
                 Ok((stream, _)) => {
+                    // 通过 SO_PEERCRED 获取调用方 uid，用于审计和确定初始权限
+                    let peer_uid = stream.peer_cred().ok().map(|cred| cred.uid());
+                    let granted = ipc_auth::peer_permission(peer_uid, owner_uid);
+                    let ctx = self.request_context(match peer_uid {
+                        Some(uid) => format!("uid={}", uid),
+                        None => "uid=unknown".to_string(),
+                    });
                     let graph = Arc::clone(&self.graph);
                     tokio::spawn(async move {
-                        if let Err(e) = handle_client_unix(stream, graph).await {
+                        if let Err(e) = handle_client_unix(stream, graph, ctx, granted).await {
                             eprintln!("[ark] 处理客户端请求失败: {}", e);
                         }
                     });
@@ -140,25 +719,35 @@ impl IpcServer {
 
     #[cfg(windows)]
     pub async fn serve(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let addr = format!("127.0.0.1:{}", self.port);
-        let listener = TcpListener::bind(&addr).await?;
-        
-        println!("[ark] IPC 服务器已启动，监听 TCP: {}", addr);
+        let name = pipe_name(self.port);
+        // first_pipe_instance：管道名已被其他进程占用时直接失败，避免两个 daemon 交替接收连接
+        let mut server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .reject_remote_clients(true)
+            .create(&name)?;
+
+        println!("[ark] IPC 服务器已启动，监听命名管道: {}", name);
+        self.notify_ready();
 
         loop {
-            match listener.accept().await {
-                Ok((stream, addr)) => {
-                    let graph = Arc::clone(&self.graph);
-                    tokio::spawn(async move {
-                        if let Err(e) = handle_client_tcp(stream, graph).await {
-                            eprintln!("[ark] 处理客户端 {} 请求失败: {}", addr, e);
-                        }
-                    });
-                }
-                Err(e) => {
-                    eprintln!("[ark] 接受连接失败: {}", e);
-                }
+            if let Err(e) = server.connect().await {
+                eprintln!("[ark] 接受连接失败: {}", e);
+                server = ServerOptions::new().reject_remote_clients(true).create(&name)?;
+                continue;
             }
+            // 先创建下一个实例再交出已连接的实例，保证管道名始终可连接
+            let stream = std::mem::replace(
+                &mut server,
+                ServerOptions::new().reject_remote_clients(true).create(&name)?,
+            );
+            let graph = Arc::clone(&self.graph);
+            let ctx = self.request_context("pipe".to_string());
+            tokio::spawn(async move {
+                // 管道 DACL 只允许 daemon 用户、Administrators 和 SYSTEM 连接，与 Unix 上的 root / 同 uid 对应
+                if let Err(e) = handle_client_pipe(stream, graph, ctx, Permission::Act).await {
+                    eprintln!("[ark] 处理客户端请求失败: {}", e);
+                }
+            });
         }
     }
 
@@ -179,6 +768,8 @@ impl IpcServer {
 async fn handle_client_unix(
     mut stream: UnixStream,
     graph: Arc<StateGraph>,
+    ctx: RequestContext,
+    mut granted: Permission,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut buf = vec![0u8; 4096];
 
@@ -210,14 +801,36 @@ async fn handle_client_unix(
         let request: RpcRequest = match serde_json::from_slice(&request_buf) {
             Ok(req) => req,
             Err(e) => {
-                let response = RpcResponse::error(format!("解析请求失败: {}", e));
+                let response = RpcResponse::error(parse_error(&request_buf, e));
                 send_response_unix(&mut stream, &response).await?;
                 continue;
             }
         };
 
+        if let RpcRequest::Auth { token } = &request {
+            let response = match authenticate(token, ctx.auth_token.as_deref().map(String::as_str)) {
+                Ok(permission) => {
+                    granted = granted.max(permission);
+                    RpcResponse::success(json!({ "permission": granted.as_str() }))
+                }
+                Err(e) => RpcResponse::error(e),
+            };
+            send_response_unix(&mut stream, &response).await?;
+            continue;
+        }
+
+        if let Err(e) = authorize(&request, granted) {
+            send_response_unix(&mut stream, &RpcResponse::error(e)).await?;
+            continue;
+        }
+
+        // 订阅占用整个连接，直到客户端断开
+        if let RpcRequest::Subscribe { filter } = request {
+            return stream_subscription(&mut stream, graph, &ctx, filter).await;
+        }
+
         // 处理请求
-        let response = match handle_request(request, Arc::clone(&graph)).await {
+        let response = match handle_request(request, Arc::clone(&graph), &ctx).await {
             Ok(data) => RpcResponse::success(data),
             Err(e) => RpcResponse::error(e),
         };
@@ -229,11 +842,13 @@ async fn handle_client_unix(
     Ok(())
 }
 
-/// 处理单个客户端连接（TCP Socket，Windows）
+/// 处理单个客户端连接（命名管道，Windows）
 #[cfg(windows)]
-async fn handle_client_tcp(
-    mut stream: TcpStream,
+async fn handle_client_pipe(
+    mut stream: NamedPipeServer,
     graph: Arc<StateGraph>,
+    ctx: RequestContext,
+    mut granted: Permission,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut buf = vec![0u8; 4096];
 
@@ -253,7 +868,7 @@ async fn handle_client_tcp(
                 "请求体过大: {} 字节（最大允许: {} 字节）",
                 n, MAX_REQUEST_SIZE
             ));
-            send_response_tcp(&mut stream, &response).await?;
+            send_response_pipe(&mut stream, &response).await?;
             continue;
         }
 
@@ -265,51 +880,99 @@ async fn handle_client_tcp(
         let request: RpcRequest = match serde_json::from_slice(&request_buf) {
             Ok(req) => req,
             Err(e) => {
-                let response = RpcResponse::error(format!("解析请求失败: {}", e));
-                send_response_tcp(&mut stream, &response).await?;
+                let response = RpcResponse::error(parse_error(&request_buf, e));
+                send_response_pipe(&mut stream, &response).await?;
                 continue;
             }
         };
 
+        if let RpcRequest::Auth { token } = &request {
+            let response = match authenticate(token, ctx.auth_token.as_deref().map(String::as_str)) {
+                Ok(permission) => {
+                    granted = granted.max(permission);
+                    RpcResponse::success(json!({ "permission": granted.as_str() }))
+                }
+                Err(e) => RpcResponse::error(e),
+            };
+            send_response_pipe(&mut stream, &response).await?;
+            continue;
+        }
+
+        if let Err(e) = authorize(&request, granted) {
+            send_response_pipe(&mut stream, &RpcResponse::error(e)).await?;
+            continue;
+        }
+
+        // 订阅占用整个连接，直到客户端断开
+        if let RpcRequest::Subscribe { filter } = request {
+            return stream_subscription(&mut stream, graph, &ctx, filter).await;
+        }
+
         // 处理请求
-        let response = match handle_request(request, Arc::clone(&graph)).await {
+        let response = match handle_request(request, Arc::clone(&graph), &ctx).await {
             Ok(data) => RpcResponse::success(data),
             Err(e) => RpcResponse::error(e),
         };
 
         // 发送响应
-        send_response_tcp(&mut stream, &response).await?;
+        send_response_pipe(&mut stream, &response).await?;
     }
 
     Ok(())
 }
 
 /// 处理 RPC 请求
-async fn handle_request(
+pub(crate) async fn handle_request(
     request: RpcRequest,
     graph: Arc<StateGraph>,
+    ctx: &RequestContext,
 ) -> Result<serde_json::Value, String> {
     match request {
-        RpcRequest::ListProcesses => {
-            let processes = graph.get_active_processes().await;
+        RpcRequest::ListProcesses { filter } => {
+            for pattern in [&filter.job_id, &filter.node_id].into_iter().flatten() {
+                validate_pattern(pattern)?;
+            }
+            let local_node = filter.node_id.as_ref().map(|_| crate::hub_forwarder::get_node_id());
+            let edges = graph.get_all_edges_async().await;
             let mut processes_json = Vec::new();
 
-            for node in processes {
-                let pid = node
-                    .id
-                    .strip_prefix("pid-")
-                    .unwrap_or(&node.id)
-                    .parse::<u32>()
-                    .unwrap_or(0);
+            for node in graph.get_active_processes().await {
+                let key = node.key();
+                let job_id = node.metadata.get("job_id").cloned();
+                let state = node.metadata.get("state").cloned().unwrap_or_else(|| "unknown".to_string());
+                let outgoing = || edges.iter().filter(|e| e.from == node.id);
+
+                if let Some(ref pattern) = filter.job_id {
+                    if !job_id.as_deref().is_some_and(|job| matches_pattern(job, pattern)) {
+                        continue;
+                    }
+                }
+                if filter.state.as_ref().is_some_and(|s| *s != state) {
+                    continue;
+                }
+                if let Some(ref pattern) = filter.node_id {
+                    let node_id = key.node_id().or(local_node.as_deref()).unwrap_or_default();
+                    if !matches_pattern(node_id, pattern) {
+                        continue;
+                    }
+                }
+                if filter.blocked_only
+                    && !outgoing().any(|e| matches!(e.edge_type, EdgeType::WaitsOn | EdgeType::BlockedBy))
+                {
+                    continue;
+                }
 
-                // 获取进程消耗的资源
-                let resources = graph.get_process_resources(pid).await;
+                // 进程消耗的资源
+                let resources: Vec<&str> = outgoing()
+                    .filter(|e| e.edge_type == EdgeType::Consumes)
+                    .map(|e| e.to.as_str())
+                    .collect();
 
                 processes_json.push(json!({
-                    "pid": pid,
+                    "pid": key.pid().unwrap_or(0),
                     "id": node.id,
-                    "job_id": node.metadata.get("job_id").cloned(),
-                    "state": node.metadata.get("state").cloned().unwrap_or_else(|| "unknown".to_string()),
+                    "job_id": job_id,
+                    "state": state,
                     "resources": resources,
                     "last_update": node.last_update,
                 }));
@@ -318,7 +981,21 @@ async fn handle_request(
             Ok(json!(processes_json))
         }
         RpcRequest::WhyProcess { pid } => {
-            let causes = graph.find_root_cause(pid).await;
+            let mut causes = graph.find_root_cause(pid).instrument(tracing::info_span!("diag", pid)).await;
+
+            // 进程所在 job 有掉队 rank 时一并给出（训练变慢但没有硬错误的常见原因）
+            let pid_str = format!("pid-{}", pid);
+            if let Some(job_id) = graph
+                .node(&pid_str)
+                .await
+                .and_then(|n| n.metadata.get("job_id").cloned())
+            {
+                let stragglers = graph
+                    .find_job_stragglers(&job_id, DEFAULT_STRAGGLER_MARGIN)
+                    .await;
+                causes.extend(stragglers.iter().map(|s| s.describe()));
+            }
+
             Ok(json!({
                 "pid": pid,
                 "causes": causes,
@@ -327,9 +1004,588 @@ async fn handle_request(
         RpcRequest::Ping => {
             Ok(json!({"status": "ok"}))
         }
+        RpcRequest::GraphExport { format } => {
+            let export_format = ExportFormat::parse(&format)
+                .ok_or_else(|| format!("不支持的导出格式: {}（可选: dot, json, graphml）", format))?;
+            Ok(json!({
+                "format": format,
+                "content": graph.export(export_format).await,
+            }))
+        }
+        RpcRequest::MatchRules => {
+            let reloadable = ctx
+                .rule_engine
+                .as_ref()
+                .ok_or_else(|| "daemon 未配置规则目录（ark run --rules-dir）".to_string())?;
+            // 使用全部保留的事件历史（容量由 GraphConfig.history_capacity 决定），时间窗口条件依赖它
+            let events = graph.recent_events(usize::MAX).await;
+            // 在当前规则集快照上匹配（期间发生重载不影响本次结果），并应用冷却与抑制
+            let mut matches = reloadable.evaluate(&graph, &events).await;
+            for m in &mut matches {
+                m.explanation = Some(RuleEngine::explain(&m.rule, &events, &graph).await);
+            }
+            let fired: Vec<_> = matches.iter().filter(|m| m.fired()).map(|m| &m.rule).collect();
+            Ok(json!({
+                "generation": reloadable.generation(),
+                "rules": fired,
+                "matches": matches,
+            }))
+        }
+        RpcRequest::GraphSnapshot { error_events } => {
+            let errors: Vec<Event> = graph
+                .recent_events(usize::MAX)
+                .await
+                .into_iter()
+                .rev()
+                .filter(|e| matches!(e.event_type, EventType::ErrorHw | EventType::ErrorNet | EventType::TopoLinkDown))
+                .take(error_events)
+                .collect();
+            Ok(json!({
+                "graph": graph.export(ExportFormat::Json).await,
+                "errors": errors,
+            }))
+        }
+        RpcRequest::GetGraph { pid, depth } => {
+            let export = match pid {
+                Some(pid) => {
+                    let node_id = find_process_node(&graph, pid)
+                        .await
+                        .ok_or_else(|| format!("进程 {} 不在状态图中", pid))?;
+                    let subgraph = graph.neighborhood(&node_id, depth).await.ok_or_else(|| format!("进程 {} 不在状态图中", pid))?;
+                    subgraph.export(ExportFormat::Json).await
+                }
+                None => graph.export(ExportFormat::Json).await,
+            };
+            let graph: serde_json::Value = serde_json::from_str(&export).map_err(|e| format!("导出状态图失败: {}", e))?;
+            Ok(json!({ "graph": graph }))
+        }
+        admin @ (RpcRequest::GraphRemoveNode { .. }
+        | RpcRequest::GraphRemoveEdge { .. }
+        | RpcRequest::GraphSetMeta { .. }) => {
+            handle_admin_request(admin, graph, ctx).await
+        }
+        RpcRequest::ExecuteAction { pid, actions, operation, approval, confirm } => {
+            if !confirm {
+                return Err(format!("运维操作 {} 需要确认（confirm=true）", operation));
+            }
+            let result = execute_actions(&graph, pid, actions, &operation, approval.as_deref(), ctx).await?;
+            Ok(json!(result))
+        }
+        RpcRequest::RollbackFix { fix_id, confirm } => {
+            if !confirm {
+                return Err("运维操作 rollback 需要确认（confirm=true）".to_string());
+            }
+            Ok(json!(rollback_fix(&fix_id, ctx).await?))
+        }
+        RpcRequest::Status => {
+            let health = ctx
+                .health
+                .as_ref()
+                .ok_or_else(|| "daemon 未启用健康状态统计".to_string())?;
+            let generation = ctx.rule_engine.as_ref().map(|engine| engine.generation());
+            let mut report = health.report(&graph, generation).await;
+            report.read_only = ctx.read_only;
+            Ok(json!(report))
+        }
+        RpcRequest::RecentEvents { limit } => Ok(json!(graph.recent_events(limit).await)),
+        RpcRequest::GetEvents { since, event_types, entity, limit } => {
+            let filter = StreamFilter::parse(&SubscribeFilter {
+                event_types,
+                entity_pattern: entity,
+                ..Default::default()
+            })?;
+            let mut events: Vec<Event> = graph
+                .recent_events(usize::MAX)
+                .await
+                .into_iter()
+                .rev()
+                .filter(|e| since.is_none_or(|since| e.ts >= since) && filter.matches(e))
+                .take(limit)
+                .collect();
+            events.reverse();
+            Ok(json!(events))
+        }
+        RpcRequest::RuleHistory { limit } => {
+            let mut matches: Vec<Event> = graph
+                .recent_events(usize::MAX)
+                .await
+                .into_iter()
+                .rev()
+                .filter(|e| e.event_type == EventType::RuleMatched)
+                .take(limit)
+                .collect();
+            matches.reverse();
+            Ok(json!({
+                "generation": ctx.rule_engine.as_ref().map(|engine| engine.generation()),
+                "matches": matches,
+            }))
+        }
+        RpcRequest::AuditEntries { limit } => {
+            let logger = ctx
+                .audit_logger
+                .as_ref()
+                .ok_or_else(|| "daemon 未配置审计日志".to_string())?;
+            Ok(json!(logger.recent(limit)?))
+        }
+        RpcRequest::Subscribe { .. } => Err("subscribe 只能作为连接上的首个请求".to_string()),
+        RpcRequest::Auth { .. } => Err("auth 由连接处理".to_string()),
+        RpcRequest::Hello { .. } => Ok(json!(ServerInfo::current())),
+    }
+}
+
+/// 查找进程节点 ID：优先本机（无命名空间）的节点
+async fn find_process_node(graph: &StateGraph, pid: u32) -> Option<String> {
+    let local = NodeKey::process(None, pid);
+    if graph.node_by_key(&local).await.is_some() {
+        return Some(local.to_string());
+    }
+    let mut candidates: Vec<String> = graph
+        .get_nodes_async()
+        .await
+        .into_values()
+        .filter(|n| n.node_type == NodeType::Process && n.key().pid() == Some(pid))
+        .map(|n| n.id)
+        .collect();
+    candidates.sort();
+    candidates.into_iter().next()
+}
+
+/// 进程所属 job（状态图中没有该进程或未标注 job 时为 None）
+async fn process_job_id(graph: &StateGraph, pid: u32) -> Option<String> {
+    let node_id = find_process_node(graph, pid).await?;
+    graph.node(&node_id).await?.metadata.get("job_id").cloned()
+}
+
+/// 订阅的过滤条件（已解析）
+struct StreamFilter {
+    event_types: Vec<EventType>,
+    entity_pattern: Option<String>,
+}
+
+impl StreamFilter {
+    fn parse(filter: &SubscribeFilter) -> Result<Self, String> {
+        let event_types = filter
+            .event_types
+            .iter()
+            .map(|t| serde_json::from_value(json!(t)).map_err(|_| format!("未知的事件类型: {}", t)))
+            .collect::<Result<_, _>>()?;
+        if let Some(ref pattern) = filter.entity_pattern {
+            validate_pattern(pattern)?;
+        }
+        Ok(Self { event_types, entity_pattern: filter.entity_pattern.clone() })
+    }
+
+    fn matches(&self, event: &Event) -> bool {
+        (self.event_types.is_empty() || self.event_types.contains(&event.event_type))
+            && self.entity_pattern.as_ref().is_none_or(|p| matches_pattern(&event.entity_id, p))
+    }
+
+    /// 图变化按节点匹配：完整 ID 或去掉命名空间的实体 ID 命中即可（边的任一端命中即推送）
+    fn matches_change(&self, change: &GraphChange) -> bool {
+        let Some(ref pattern) = self.entity_pattern else {
+            return true;
+        };
+        change.node_ids().into_iter().any(|id| {
+            matches_pattern(id, pattern) || matches_pattern(NodeKey::parse(id).entity(), pattern)
+        })
     }
 }
 
+/// 图结构快照：节点 ID -> 类型，边 (from, to, 类型)
+struct GraphShape {
+    nodes: HashMap<String, &'static str>,
+    edges: HashSet<(String, String, &'static str)>,
+}
+
+impl GraphShape {
+    async fn capture(graph: &StateGraph) -> Self {
+        let nodes = graph
+            .get_nodes_async()
+            .await
+            .into_iter()
+            .map(|(id, node)| (id, node.node_type.as_str()))
+            .collect();
+        let edges = graph
+            .get_all_edges_async()
+            .await
+            .into_iter()
+            .map(|e| (e.from, e.to, e.edge_type.as_str()))
+            .collect();
+        Self { nodes, edges }
+    }
+
+    /// 从 self 到 next 的变化：先删后增，同类变化按 ID 排序
+    fn diff(&self, next: &GraphShape) -> Vec<GraphChange> {
+        let mut removed_nodes: Vec<_> = self.nodes.iter().filter(|(id, _)| !next.nodes.contains_key(*id)).collect();
+        let mut added_nodes: Vec<_> = next.nodes.iter().filter(|(id, _)| !self.nodes.contains_key(*id)).collect();
+        let mut removed_edges: Vec<_> = self.edges.difference(&next.edges).collect();
+        let mut added_edges: Vec<_> = next.edges.difference(&self.edges).collect();
+        removed_nodes.sort();
+        added_nodes.sort();
+        removed_edges.sort();
+        added_edges.sort();
+
+        let edge = |(from, to, edge_type): &(String, String, &str)| (from.clone(), to.clone(), edge_type.to_string());
+        removed_edges
+            .into_iter()
+            .map(|e| {
+                let (from, to, edge_type) = edge(e);
+                GraphChange::EdgeRemoved { from, to, edge_type }
+            })
+            .chain(removed_nodes.into_iter().map(|(id, t)| GraphChange::NodeRemoved { id: id.clone(), node_type: t.to_string() }))
+            .chain(added_nodes.into_iter().map(|(id, t)| GraphChange::NodeAdded { id: id.clone(), node_type: t.to_string() }))
+            .chain(added_edges.into_iter().map(|e| {
+                let (from, to, edge_type) = edge(e);
+                GraphChange::EdgeAdded { from, to, edge_type }
+            }))
+            .collect()
+    }
+}
+
+/// 图变化的检测间隔：有新事件时最多每隔这么久对比一次图结构
+const GRAPH_DIFF_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 订阅推送的内容：匹配的事件或图结构变化；订阅端过慢丢事件时为错误提示
+pub(crate) type SubscriptionItem = Result<StreamItem, String>;
+
+/// 订阅推送在队列中最多积压的条数，积压满时后台任务等待，daemon 的事件广播随之提示丢弃
+const SUBSCRIPTION_BUFFER: usize = 256;
+
+/// 开始订阅：校验过滤条件后在后台任务中产生推送内容
+///
+/// 图变化通过对比前后两次图结构得到，只在收到新事件后（最多每 `GRAPH_DIFF_INTERVAL` 一次）计算。
+/// 接收端被丢弃或 daemon 的事件广播关闭时后台任务结束
+pub(crate) async fn start_subscription(
+    graph: Arc<StateGraph>,
+    ctx: &RequestContext,
+    filter: SubscribeFilter,
+) -> Result<mpsc::Receiver<SubscriptionItem>, String> {
+    let tx_events = ctx
+        .event_stream
+        .as_ref()
+        .ok_or_else(|| "daemon 未启用事件流".to_string())?;
+    let stream_filter = StreamFilter::parse(&filter)?;
+    let mut rx = tx_events.subscribe();
+    let mut shape = if filter.graph_changes { Some(GraphShape::capture(&graph).await) } else { None };
+    let (tx, items) = mpsc::channel(SUBSCRIPTION_BUFFER);
+
+    tokio::spawn(async move {
+        let mut diff_ticker = tokio::time::interval(GRAPH_DIFF_INTERVAL);
+        diff_ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut graph_dirty = false;
+        loop {
+            let pending: Vec<SubscriptionItem> = tokio::select! {
+                _ = tx.closed() => return,
+                received = rx.recv() => match received {
+                    Ok(event) => {
+                        graph_dirty = true;
+                        if filter.events && stream_filter.matches(&event) {
+                            vec![Ok(StreamItem::Event(event))]
+                        } else {
+                            Vec::new()
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        graph_dirty = true;
+                        vec![Err(format!("订阅端消费过慢，丢弃了 {} 条事件", n))]
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = diff_ticker.tick(), if graph_dirty && shape.is_some() => {
+                    graph_dirty = false;
+                    match shape {
+                        Some(ref mut previous) => {
+                            let current = GraphShape::capture(&graph).await;
+                            let changes = previous.diff(&current);
+                            *previous = current;
+                            changes
+                                .into_iter()
+                                .filter(|c| stream_filter.matches_change(c))
+                                .map(|c| Ok(StreamItem::GraphChange(c)))
+                                .collect()
+                        }
+                        None => Vec::new(),
+                    }
+                }
+            };
+            for item in pending {
+                if tx.send(item).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    Ok(items)
+}
+
+/// 向订阅连接推送数据：先发确认帧，之后每条推送内容一帧，客户端断开（读到 EOF）或订阅结束时返回
+async fn stream_subscription<S>(
+    stream: S,
+    graph: Arc<StateGraph>,
+    ctx: &RequestContext,
+    filter: SubscribeFilter,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    let mut items = match start_subscription(graph, ctx, filter).await {
+        Ok(items) => items,
+        Err(e) => return send_frame(&mut writer, &RpcResponse::error(e)).await,
+    };
+    send_frame(&mut writer, &RpcResponse::success(json!({ "subscribed": true }))).await?;
+
+    loop {
+        tokio::select! {
+            _ = reader.read_u8() => return Ok(()),
+            item = items.recv() => match item {
+                Some(Ok(item)) => send_frame(&mut writer, &RpcResponse::success(json!(item))).await?,
+                Some(Err(e)) => send_frame(&mut writer, &RpcResponse::error(e)).await?,
+                None => return Ok(()),
+            },
+        }
+    }
+}
+
+/// 发送一个长度前缀的响应帧
+async fn send_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    response: &RpcResponse,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let response_json = serde_json::to_vec(response)?;
+    writer.write_u32(response_json.len() as u32).await?;
+    writer.write_all(&response_json).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// 处理运维类 RPC：要求显式确认，并写入审计日志
+async fn handle_admin_request(
+    request: RpcRequest,
+    graph: Arc<StateGraph>,
+    ctx: &RequestContext,
+) -> Result<serde_json::Value, String> {
+    let (action, target, confirm) = match &request {
+        RpcRequest::GraphRemoveNode { node_id, confirm } => {
+            ("graph.rm_node", node_id.clone(), *confirm)
+        }
+        RpcRequest::GraphRemoveEdge { from, to, edge_type, confirm } => (
+            "graph.rm_edge",
+            format!("{} -[{}]-> {}", from, edge_type.as_deref().unwrap_or("*"), to),
+            *confirm,
+        ),
+        RpcRequest::GraphSetMeta { node_id, key, value, confirm } => {
+            ("graph.set_meta", format!("{}.{}={}", node_id, key, value), *confirm)
+        }
+        _ => return Err("不是运维请求".to_string()),
+    };
+
+    if !confirm {
+        return Err(format!("运维操作 {} 需要确认（confirm=true）", action));
+    }
+
+    let result = match request {
+        RpcRequest::GraphRemoveNode { node_id, .. } => match graph.remove_node(&node_id).await {
+            Some((node, removed_edges)) => Ok(json!({
+                "node_id": node.id,
+                "node_type": format!("{:?}", node.node_type),
+                "removed_edges": removed_edges,
+            })),
+            None => Err(format!("节点不存在: {}", node_id)),
+        },
+        RpcRequest::GraphRemoveEdge { from, to, edge_type, .. } => {
+            let edge_type = match edge_type {
+                Some(ref t) => Some(
+                    EdgeType::parse(t).ok_or_else(|| format!("未知边类型: {}", t))?,
+                ),
+                None => None,
+            };
+            match graph.remove_edge(&from, &to, edge_type).await {
+                0 => Err(format!("边不存在: {} -> {}", from, to)),
+                removed => Ok(json!({ "removed_edges": removed })),
+            }
+        }
+        RpcRequest::GraphSetMeta { node_id, key, value, .. } => graph
+            .set_node_metadata(&node_id, &key, &value)
+            .await
+            .map(|old| json!({ "node_id": node_id, "key": key, "old_value": old, "new_value": value })),
+        _ => return Err("不是运维请求".to_string()),
+    };
+
+    if let Some(ref logger) = ctx.audit_logger {
+        let details = match &result {
+            Ok(data) => format!("caller={}; target={}; data={}", ctx.caller, target, data),
+            Err(e) => format!("caller={}; target={}; error={}", ctx.caller, target, e),
+        };
+        let status = if result.is_ok() { "success" } else { "failure" };
+        // 图运维操作不针对具体进程
+        logger.record(&ctx.audit_origin(), action, 0, None, status, &details).await;
+    } else {
+        eprintln!("[ark] 运维操作 {} ({}) by {}: 未配置审计日志", action, target, ctx.caller);
+    }
+
+    result
+}
+
+/// 执行修复动作（`ark fix`、`ark zap` 及 gRPC 的 ExecuteAction）：校验审批和动作策略、逐个执行并记录审计日志和指标
+///
+/// 修复动作以 daemon 的身份执行，审计日志是强制的：daemon 未配置审计日志时拒绝执行。
+/// 只读模式、审批校验失败等拒绝同样写入审计日志。
+/// 动作策略每次重新读取（修改后立即生效），包含禁止动作的计划整体不执行，结果照常写入审计日志
+#[tracing::instrument(name = "fix.execute", skip_all, fields(pid, operation))]
+async fn execute_actions(
+    graph: &StateGraph,
+    pid: u32,
+    actions: Vec<ActionType>,
+    operation: &str,
+    approval_token: Option<&str>,
+    ctx: &RequestContext,
+) -> Result<FixResult, String> {
+    let logger = ctx
+        .audit_logger
+        .as_ref()
+        .ok_or_else(|| "daemon 未配置审计日志（ark run --audit-log），拒绝执行修复动作".to_string())?;
+    let origin = ctx.audit_origin();
+    let target = NodeKey::process(Some(&crate::hub_forwarder::get_node_id()), pid).to_string();
+    let job = process_job_id(graph, pid).await;
+    let base = format!("caller={}; target={}", ctx.caller, target);
+
+    if let Err(e) = ctx.check_writable(operation) {
+        return Err(ctx.audit_refusal(operation, pid, job.as_deref(), "read_only", &base, e).await);
+    }
+
+    // 高危动作执行前校验破窗审批
+    let approval = if actions.iter().any(|a| a.is_destructive()) || approval_token.is_some() {
+        match approval::check(ctx.hub_api.as_deref(), approval_token, operation, &target, None).await {
+            Ok(approval) => Some(approval),
+            Err(e) => return Err(ctx.audit_refusal(operation, pid, job.as_deref(), "rejected", &base, e).await),
+        }
+    } else {
+        None
+    };
+
+    let kinds: Vec<&'static str> = actions.iter().map(|a| a.kind()).collect();
+    let plan = actions
+        .into_iter()
+        .enumerate()
+        .map(|(idx, action)| (action, idx.min(u8::MAX as usize) as u8))
+        .collect();
+    let policy = match ActionPolicy::load_default() {
+        Ok(policy) => policy,
+        Err(e) => return Err(ctx.audit_refusal(operation, pid, job.as_deref(), "failure", &base, e).await),
+    };
+    let subject = PolicySubject {
+        job: job.clone(),
+        user: caller_user(&ctx.caller),
+    };
+    let mut executor = ctx.action_executor().with_job(job.clone());
+    if let Some(ref limiter) = ctx.limiter {
+        executor = executor.with_limiter(Arc::clone(limiter));
+    }
+    let result = FixEngine::with_action_executor(executor)
+        .with_policy(policy, subject)
+        .with_step_timeout(crate::config::action_timeout())
+        .execute_plan(plan, pid)
+        .await;
+    let mut result = match result {
+        Ok(result) => result,
+        Err(e) => return Err(ctx.audit_refusal(operation, pid, job.as_deref(), "failure", &base, e).await),
+    };
+
+    // 记录可回滚动作，供 `ark fix --rollback <fix-id>` 撤销
+    if !result.rollback.is_empty() {
+        match FixJournal::new(default_journal_path()).record(pid, operation, &result.rollback) {
+            Ok(fix_id) => result.fix_id = Some(fix_id),
+            Err(e) => eprintln!("[ark] 记录修复日志失败，该修复无法回滚: {}", e),
+        }
+    }
+
+    if let Some(ref metrics) = ctx.metrics {
+        // 优先级即动作在计划中的下标，据此对应回动作类型
+        let outcomes = result
+            .executed_actions
+            .iter()
+            .map(|a| (a.priority, true))
+            .chain(result.failed_actions.iter().map(|a| (a.priority, false)));
+        for (priority, success) in outcomes {
+            if let Some(kind) = kinds.get(priority as usize) {
+                metrics.record_action(kind, success);
+            }
+        }
+    }
+
+    let mut details = format!(
+        "{}; 成功: {}; 失败: {}",
+        base,
+        result.executed_actions.len(),
+        result.failed_actions.len()
+    );
+    for action in &result.executed_actions {
+        details.push_str(&format!("; ok={}", action.action));
+    }
+    for action in &result.failed_actions {
+        details.push_str(&format!("; failed={} ({})", action.action, action.error));
+    }
+    for outcome in &result.rolled_back {
+        details.push_str(&format!("; rolled_back={} ({})", outcome.action, if outcome.success { "ok" } else { &outcome.detail }));
+    }
+    if let Some(ref fix_id) = result.fix_id {
+        details.push_str(&format!("; fix_id={}", fix_id));
+    }
+    if let Some(ref approval) = approval {
+        details.push_str("; ");
+        details.push_str(&approval.audit_summary());
+    }
+    let status = if result.success {
+        "success"
+    } else if result.failed_actions.iter().any(|a| a.kind == FailureKind::Denied) {
+        "denied"
+    } else if result.failed_actions.iter().any(|a| a.kind == FailureKind::Timeout) {
+        "timeout"
+    } else if result.failed_actions.iter().any(|a| a.kind == FailureKind::MissingPrivilege) {
+        MISSING_PRIVILEGE
+    } else {
+        "partial_failure"
+    };
+    logger.record(&origin, operation, pid, job.as_deref(), status, &details).await;
+
+    Ok(result)
+}
+
+/// 撤销修复日志中记录的一次修复，记录审计日志（与执行修复一样要求配置审计日志）
+async fn rollback_fix(fix_id: &str, ctx: &RequestContext) -> Result<Vec<RollbackOutcome>, String> {
+    let logger = ctx
+        .audit_logger
+        .as_ref()
+        .ok_or_else(|| "daemon 未配置审计日志（ark run --audit-log），拒绝执行回滚".to_string())?;
+
+    let journal = FixJournal::new(default_journal_path());
+    let (pid, actions) = journal.pending(fix_id)?;
+    let mut details = format!("caller={}; fix_id={}", ctx.caller, fix_id);
+    if let Err(e) = ctx.check_writable("rollback") {
+        return Err(ctx.audit_refusal("rollback", pid, None, "read_only", &details, e).await);
+    }
+
+    let outcomes = FixEngine::with_action_executor(ctx.action_executor()).rollback(&actions).await;
+    let success = outcomes.iter().all(|o| o.success);
+    // 部分失败也标记为已回滚：重复执行已成功的撤销操作没有意义，失败项需要人工处理
+    if let Err(e) = journal.mark_rolled_back(fix_id) {
+        eprintln!("[ark] 记录回滚状态失败: {}", e);
+    }
+
+    for outcome in &outcomes {
+        if outcome.success {
+            details.push_str(&format!("; ok={}", outcome.action));
+        } else {
+            details.push_str(&format!("; failed={} ({})", outcome.action, outcome.detail));
+        }
+    }
+    let status = if success { "success" } else { "partial_failure" };
+    logger.record(&ctx.audit_origin(), "rollback", pid, None, status, &details).await;
+
+    Ok(outcomes)
+}
+
 /// 发送响应到客户端（Unix Domain Socket）
 #[cfg(unix)]
 async fn send_response_unix(
@@ -348,10 +1604,10 @@ async fn send_response_unix(
     Ok(())
 }
 
-/// 发送响应到客户端（TCP Socket）
+/// 发送响应到客户端（命名管道）
 #[cfg(windows)]
-async fn send_response_tcp(
-    stream: &mut TcpStream,
+async fn send_response_pipe(
+    stream: &mut NamedPipeServer,
     response: &RpcResponse,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let response_json = serde_json::to_vec(response)?;
@@ -366,97 +1622,252 @@ async fn send_response_tcp(
     Ok(())
 }
 
+#[cfg(unix)]
+type ClientStream = UnixStream;
+#[cfg(windows)]
+type ClientStream = NamedPipeClient;
+
+/// 单次请求的默认超时（可用环境变量 `ARK_IPC_TIMEOUT` 按秒覆盖）
+const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 客户端重试策略：连接失败（如 daemon 正在重启）时按指数退避重试
+#[derive(Debug, Clone, Copy)]
+struct RetryPolicy {
+    /// 首次失败后的最大重试次数（0 表示不重试）
+    retries: u32,
+    /// 第一次重试前的等待时间，之后每次翻倍
+    backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { retries: 3, backoff: Duration::from_millis(200) }
+    }
+}
+
+impl RetryPolicy {
+    /// 默认策略，可用 `ARK_IPC_RETRIES` 覆盖重试次数
+    fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Some(retries) = std::env::var("ARK_IPC_RETRIES").ok().and_then(|v| v.trim().parse().ok()) {
+            policy.retries = retries;
+        }
+        policy
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(1 << attempt.min(6))
+    }
+}
+
 /// IPC 客户端：用于 CLI 命令查询 daemon 状态
+///
+/// 同一个客户端的多次请求复用一条连接；连接断开或 daemon 重启时按 `RetryPolicy` 重连
 pub struct IpcClient {
     #[cfg(unix)]
     socket_path: PathBuf,
     #[cfg(windows)]
     port: u16,
+    /// 连接后发送的共享 token（`ARK_IPC_TOKEN` 或可读的 token 文件）
+    token: Option<String>,
+    /// 首次连接时 hello 得到的 daemon 信息
+    server_info: tokio::sync::OnceCell<ServerInfo>,
+    retry: RetryPolicy,
+    /// 单次请求（含连接和认证）的超时
+    timeout: Duration,
+    /// 复用的连接（请求失败后丢弃）
+    conn: tokio::sync::Mutex<Option<ClientStream>>,
 }
 
 impl IpcClient {
     #[cfg(unix)]
     pub fn new(socket_path: Option<PathBuf>) -> Self {
+        let socket_path = socket_path.unwrap_or_else(default_socket_path);
+        let token = ipc_auth::client_token(&ipc_auth::token_path(&socket_path));
         Self {
-            socket_path: socket_path.unwrap_or_else(default_socket_path),
+            socket_path,
+            token,
+            server_info: tokio::sync::OnceCell::new(),
+            retry: RetryPolicy::from_env(),
+            timeout: timeout_from_env(),
+            conn: tokio::sync::Mutex::new(None),
         }
     }
 
     #[cfg(windows)]
     pub fn new(port: u16) -> Self {
-        Self { port }
+        Self {
+            port,
+            token: ipc_auth::client_token(&ipc_auth::token_path()),
+            server_info: tokio::sync::OnceCell::new(),
+            retry: RetryPolicy::from_env(),
+            timeout: timeout_from_env(),
+            conn: tokio::sync::Mutex::new(None),
+        }
     }
 
     /// 连接到 daemon
     #[cfg(unix)]
     async fn connect(&self) -> Result<UnixStream, String> {
-        UnixStream::connect(&self.socket_path)
+        let mut stream = UnixStream::connect(&self.socket_path)
             .await
-            .map_err(|e| format!("无法连接到 daemon ({}): {}", self.socket_path.display(), e))
+            .map_err(|e| format!("无法连接到 daemon ({}): {}", self.socket_path.display(), e))?;
+        self.authenticate(&mut stream).await?;
+        Ok(stream)
     }
 
     #[cfg(windows)]
-    async fn connect(&self) -> Result<TcpStream, String> {
-        let addr = format!("127.0.0.1:{}", self.port);
-        TcpStream::connect(&addr)
-            .await
-            .map_err(|e| format!("无法连接到 daemon ({}): {}", addr, e))
+    async fn connect(&self) -> Result<NamedPipeClient, String> {
+        /// 所有管道实例都被占用（ERROR_PIPE_BUSY）
+        const ERROR_PIPE_BUSY: i32 = 231;
+        let name = pipe_name(self.port);
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let mut stream = loop {
+            match ClientOptions::new().open(&name) {
+                Ok(client) => break client,
+                // daemon 正在创建下一个实例，稍后重试
+                Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) && std::time::Instant::now() < deadline => {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+                Err(e) => return Err(format!("无法连接到 daemon ({}): {}", name, e)),
+            }
+        };
+        self.authenticate(&mut stream).await?;
+        Ok(stream)
     }
 
-    /// 发送 RPC 请求并接收响应
-    async fn call(&self, request: RpcRequest) -> Result<RpcResponse, String> {
-        let mut stream = self.connect().await?;
-
-        // 序列化请求
-        let request_json = serde_json::to_vec(&request)
-            .map_err(|e| format!("序列化请求失败: {}", e))?;
-
-        // 发送请求长度和内容
-        stream
-            .write_u32(request_json.len() as u32)
-            .await
-            .map_err(|e| format!("发送请求长度失败: {}", e))?;
-        stream
-            .write_all(&request_json)
-            .await
-            .map_err(|e| format!("发送请求内容失败: {}", e))?;
-        stream
-            .flush()
-            .await
-            .map_err(|e| format!("刷新流失败: {}", e))?;
-
-        // 读取响应长度
-        let response_len = stream
-            .read_u32()
-            .await
-            .map_err(|e| format!("读取响应长度失败: {}", e))?;
+    /// 有 token 时先认证连接
+    async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(&self, stream: &mut S) -> Result<(), String> {
+        let Some(token) = &self.token else {
+            return Ok(());
+        };
+        write_request(stream, &RpcRequest::Auth { token: token.clone() }).await?;
+        let response = read_response(stream).await?;
+        if !response.success {
+            return Err(format!("IPC 认证失败: {}", response.error.unwrap_or_else(|| "未知错误".to_string())));
+        }
+        Ok(())
+    }
 
-        // 安全检查：防止恶意服务器发送超大响应导致 OOM
-        const MAX_RESPONSE_SIZE: u32 = 100 * 1024 * 1024; // 100MB（响应可能包含大量进程数据）
-        if response_len > MAX_RESPONSE_SIZE {
+    /// 首次连接时协商协议版本并缓存 daemon 信息，之后只检查方法是否受支持
+    async fn negotiate<S: AsyncRead + AsyncWrite + Unpin>(&self, stream: &mut S, method: &str) -> Result<(), String> {
+        let info = self
+            .server_info
+            .get_or_try_init(|| async {
+                write_request(
+                    stream,
+                    &RpcRequest::Hello {
+                        protocol_version: PROTOCOL_VERSION,
+                        client_version: env!("CARGO_PKG_VERSION").to_string(),
+                    },
+                )
+                .await?;
+                let response = read_response(stream).await?;
+                // 旧版 daemon 不认识 hello，返回解析错误
+                let info = match response.data {
+                    Some(data) if response.success => serde_json::from_value::<ServerInfo>(data)
+                        .map_err(|e| format!("解析 hello 响应失败: {}", e))?,
+                    _ => ServerInfo::legacy(),
+                };
+                if info.protocol_version > PROTOCOL_VERSION {
+                    eprintln!(
+                        "[ark] 警告：daemon（{}）协议版本 {} 高于 ark 的 {}，部分命令的响应可能无法解析，建议升级 ark",
+                        info.daemon_version, info.protocol_version, PROTOCOL_VERSION
+                    );
+                }
+                Ok::<_, String>(info)
+            })
+            .await?;
+        if !info.supports(method) {
             return Err(format!(
-                "响应体过大: {} 字节（最大允许: {} 字节）",
-                response_len, MAX_RESPONSE_SIZE
+                "daemon（{}，协议版本 {}）不支持 {}，请升级 daemon 或使用与其版本一致的 ark",
+                info.daemon_version, info.protocol_version, method
             ));
         }
+        Ok(())
+    }
 
-        // 读取响应内容
-        let mut response_buf = vec![0u8; response_len as usize];
-        stream
-            .read_exact(&mut response_buf)
-            .await
-            .map_err(|e| format!("读取响应内容失败: {}", e))?;
+    /// 连接到 daemon，失败时按重试策略重连
+    async fn connect_with_retry(&self) -> Result<ClientStream, String> {
+        let mut attempt = 0;
+        loop {
+            let result = tokio::time::timeout(self.timeout, self.connect())
+                .await
+                .unwrap_or_else(|_| Err(format!("连接 daemon 超时（{} 秒）", self.timeout.as_secs())));
+            match result {
+                Ok(stream) => return Ok(stream),
+                Err(e) if attempt >= self.retry.retries => return Err(e),
+                Err(_) => {
+                    tokio::time::sleep(self.retry.delay(attempt)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
 
-        // 解析响应
-        let response: RpcResponse = serde_json::from_slice(&response_buf)
-            .map_err(|e| format!("解析响应失败: {}", e))?;
+    /// 发送 RPC 请求并接收响应
+    ///
+    /// 查询类请求在连接断开或超时后重连并重发；运维操作不幂等，只在请求发出前重试连接，
+    /// 并且总是使用新连接，避免在失效的复用连接上发出后无法判断 daemon 是否已执行
+    async fn call(&self, request: RpcRequest) -> Result<RpcResponse, String> {
+        let resend = request.required_permission() < Permission::Act;
+        let mut conn = self.conn.lock().await;
+        let mut attempt = 0;
+        loop {
+            let mut stream = match conn.take().filter(|_| resend) {
+                Some(stream) => stream,
+                None => self.connect_with_retry().await?,
+            };
+            let exchange = async {
+                self.negotiate(&mut stream, request.method()).await?;
+                write_request(&mut stream, &request).await?;
+                read_response(&mut stream).await
+            };
+            let result = tokio::time::timeout(self.timeout, exchange)
+                .await
+                .unwrap_or_else(|_| Err(format!("等待 daemon 响应超时（{} 秒）", self.timeout.as_secs())));
+            match result {
+                Ok(response) => {
+                    *conn = Some(stream);
+                    return Ok(response);
+                }
+                // 协商已完成但方法不受支持，重试没有意义
+                Err(e) if self.server_info.get().is_some_and(|info| !info.supports(request.method())) => return Err(e),
+                Err(e) if !resend || attempt >= self.retry.retries => return Err(e),
+                Err(_) => {
+                    tokio::time::sleep(self.retry.delay(attempt)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
 
-        Ok(response)
+    /// daemon 的协议版本和支持的方法
+    pub async fn server_info(&self) -> Result<ServerInfo, String> {
+        if let Some(info) = self.server_info.get() {
+            return Ok(info.clone());
+        }
+        let mut stream = self.connect_with_retry().await?;
+        self.negotiate(&mut stream, "ping").await?;
+        self.server_info.get().cloned().ok_or_else(|| "协议协商失败".to_string())
+    }
+
+    /// 订阅 daemon 的实时推送（事件和/或状态图结构变化，服务端按类型和实体模式过滤）
+    pub async fn subscribe(&self, filter: SubscribeFilter) -> Result<EventSubscription, String> {
+        // 订阅独占一条连接，不与请求复用
+        let mut stream = self.connect_with_retry().await?;
+        self.negotiate(&mut stream, "subscribe").await?;
+        write_request(&mut stream, &RpcRequest::Subscribe { filter }).await?;
+        let response = read_response(&mut stream).await?;
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+        Ok(EventSubscription { stream })
     }
 
     /// 查询进程列表
-    pub async fn list_processes(&self) -> Result<Vec<serde_json::Value>, String> {
-        let response = self.call(RpcRequest::ListProcesses).await?;
+    pub async fn list_processes(&self, filter: &ProcessFilter) -> Result<Vec<serde_json::Value>, String> {
+        let response = self.call(RpcRequest::ListProcesses { filter: filter.clone() }).await?;
         
         if !response.success {
             return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
@@ -489,6 +1900,188 @@ impl IpcClient {
         Ok(causes)
     }
 
+    /// 导出状态图文本
+    pub async fn graph_export(&self, format: &str) -> Result<String, String> {
+        let response = self
+            .call(RpcRequest::GraphExport { format: format.to_string() })
+            .await?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+
+        let data = response.data.ok_or_else(|| "响应数据为空".to_string())?;
+        data["content"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| "content 字段格式错误".to_string())
+    }
+
+    /// 获取状态图的节点和边；指定 pid 时只取该进程 depth 跳内的邻域（用于本地场景分析和规则匹配）
+    pub async fn get_graph(&self, pid: Option<u32>, depth: usize) -> Result<StateGraph, String> {
+        let response = self.call(RpcRequest::GetGraph { pid, depth }).await?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+
+        let data = response.data.ok_or_else(|| "响应数据为空".to_string())?;
+        StateGraph::import_json(&data["graph"].to_string())
+    }
+
+    /// 获取状态图快照和最近的错误事件（最新的在前）
+    pub async fn graph_snapshot(&self, error_events: usize) -> Result<(StateGraph, Vec<Event>), String> {
+        let response = self.call(RpcRequest::GraphSnapshot { error_events }).await?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+
+        let data = response.data.ok_or_else(|| "响应数据为空".to_string())?;
+        let graph = StateGraph::import_json(data["graph"].as_str().ok_or("graph 字段格式错误")?)?;
+        let errors = serde_json::from_value(data["errors"].clone())
+            .map_err(|e| format!("解析错误事件失败: {}", e))?;
+        Ok((graph, errors))
+    }
+
+    /// 查询 daemon 健康状态
+    pub async fn status(&self) -> Result<StatusReport, String> {
+        let response = self.call(RpcRequest::Status).await?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+
+        let data = response.data.ok_or_else(|| "响应数据为空".to_string())?;
+        serde_json::from_value(data).map_err(|e| format!("解析状态失败: {}", e))
+    }
+
+    /// 最近的事件（按时间先后）
+    pub async fn recent_events(&self, limit: usize) -> Result<Vec<Event>, String> {
+        let response = self.call(RpcRequest::RecentEvents { limit }).await?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+
+        let data = response.data.ok_or_else(|| "响应数据为空".to_string())?;
+        serde_json::from_value(data).map_err(|e| format!("解析事件失败: {}", e))
+    }
+
+    /// 按时间、类型和实体查询事件历史（按时间先后）
+    pub async fn get_events(&self, query: EventQuery) -> Result<Vec<Event>, String> {
+        let response = self
+            .call(RpcRequest::GetEvents {
+                since: query.since,
+                event_types: query.event_types,
+                entity: query.entity,
+                limit: query.limit,
+            })
+            .await?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+
+        let data = response.data.ok_or_else(|| "响应数据为空".to_string())?;
+        serde_json::from_value(data).map_err(|e| format!("解析事件失败: {}", e))
+    }
+
+    /// 最近的规则命中，返回（规则集代数，rule.matched 事件）
+    pub async fn rule_history(&self, limit: usize) -> Result<(Option<u64>, Vec<Event>), String> {
+        let response = self.call(RpcRequest::RuleHistory { limit }).await?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+
+        let data = response.data.ok_or_else(|| "响应数据为空".to_string())?;
+        let matches = serde_json::from_value(data["matches"].clone())
+            .map_err(|e| format!("解析规则命中失败: {}", e))?;
+        Ok((data["generation"].as_u64(), matches))
+    }
+
+    /// 审计日志中最近的记录
+    pub async fn audit_entries(&self, limit: usize) -> Result<Vec<AuditLogEntry>, String> {
+        let response = self.call(RpcRequest::AuditEntries { limit }).await?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+
+        let data = response.data.ok_or_else(|| "响应数据为空".to_string())?;
+        serde_json::from_value(data).map_err(|e| format!("解析审计记录失败: {}", e))
+    }
+
+    /// 由 daemon 执行修复动作（operation 为 fix / zap，高危动作需要审批 token）
+    pub async fn execute_action(
+        &self,
+        pid: u32,
+        actions: Vec<ActionType>,
+        operation: &str,
+        approval: Option<String>,
+    ) -> Result<FixResult, String> {
+        let response = self
+            .call(RpcRequest::ExecuteAction {
+                pid,
+                actions,
+                operation: operation.to_string(),
+                approval,
+                confirm: true,
+            })
+            .await?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+
+        let data = response.data.ok_or_else(|| "响应数据为空".to_string())?;
+        serde_json::from_value(data).map_err(|e| format!("解析执行结果失败: {}", e))
+    }
+
+    /// 由 daemon 撤销一次修复（fix_id 来自 `FixResult::fix_id`），返回每个撤销操作的结果
+    pub async fn rollback_fix(&self, fix_id: &str) -> Result<Vec<RollbackOutcome>, String> {
+        let response = self
+            .call(RpcRequest::RollbackFix {
+                fix_id: fix_id.to_string(),
+                confirm: true,
+            })
+            .await?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+
+        let data = response.data.ok_or_else(|| "响应数据为空".to_string())?;
+        serde_json::from_value(data).map_err(|e| format!("解析回滚结果失败: {}", e))
+    }
+
+    /// 用 daemon 的规则集匹配当前状态图，返回（规则集代数，命中结果）
+    /// 命中结果带冷却/抑制状态，只有 `fired()` 的规则应给出推荐
+    pub async fn match_rules(&self) -> Result<(u64, Vec<RuleMatch>), String> {
+        let response = self.call(RpcRequest::MatchRules).await?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+
+        let data = response.data.ok_or_else(|| "响应数据为空".to_string())?;
+        let matches = serde_json::from_value(data["matches"].clone())
+            .map_err(|e| format!("解析规则匹配结果失败: {}", e))?;
+        Ok((data["generation"].as_u64().unwrap_or(0), matches))
+    }
+
+    /// 发送运维请求并返回响应数据
+    pub async fn graph_admin(&self, request: RpcRequest) -> Result<serde_json::Value, String> {
+        let response = self.call(request).await?;
+
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+
+        response.data.ok_or_else(|| "响应数据为空".to_string())
+    }
+
     /// 检查 daemon 是否运行
     pub async fn ping(&self) -> Result<bool, String> {
         match self.call(RpcRequest::Ping).await {
@@ -497,3 +2090,89 @@ impl IpcClient {
         }
     }
 }
+
+/// 订阅收到的一帧
+pub enum SubscriptionFrame {
+    Event(Event),
+    GraphChange(GraphChange),
+    /// daemon 的提示（如订阅端消费过慢导致丢事件），订阅继续
+    Notice(String),
+}
+
+/// 订阅：持有到 daemon 的连接，逐帧读取事件和图变化
+pub struct EventSubscription {
+    stream: ClientStream,
+}
+
+impl EventSubscription {
+    /// 等待下一帧；连接断开时返回错误
+    pub async fn next(&mut self) -> Result<SubscriptionFrame, String> {
+        let response = read_response(&mut self.stream).await?;
+        if !response.success {
+            return Ok(SubscriptionFrame::Notice(response.error.unwrap_or_default()));
+        }
+        let data = response.data.ok_or_else(|| "响应数据为空".to_string())?;
+        match serde_json::from_value(data).map_err(|e| format!("解析推送数据失败: {}", e))? {
+            StreamItem::Event(event) => Ok(SubscriptionFrame::Event(event)),
+            StreamItem::GraphChange(change) => Ok(SubscriptionFrame::GraphChange(change)),
+        }
+    }
+}
+
+/// 单次请求超时：`ARK_IPC_TIMEOUT`（秒）或默认值
+fn timeout_from_env() -> Duration {
+    std::env::var("ARK_IPC_TIMEOUT")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_CALL_TIMEOUT)
+}
+
+/// 发送长度前缀的请求
+async fn write_request<W: AsyncWrite + Unpin>(stream: &mut W, request: &RpcRequest) -> Result<(), String> {
+    // 序列化请求
+    let request_json = serde_json::to_vec(request)
+        .map_err(|e| format!("序列化请求失败: {}", e))?;
+
+    // 发送请求长度和内容
+    stream
+        .write_u32(request_json.len() as u32)
+        .await
+        .map_err(|e| format!("发送请求长度失败: {}", e))?;
+    stream
+        .write_all(&request_json)
+        .await
+        .map_err(|e| format!("发送请求内容失败: {}", e))?;
+    stream
+        .flush()
+        .await
+        .map_err(|e| format!("刷新流失败: {}", e))
+}
+
+/// 读取一个长度前缀的响应
+async fn read_response<R: AsyncRead + Unpin>(stream: &mut R) -> Result<RpcResponse, String> {
+    // 读取响应长度
+    let response_len = stream
+        .read_u32()
+        .await
+        .map_err(|e| format!("读取响应长度失败: {}", e))?;
+
+    // 安全检查：防止恶意服务器发送超大响应导致 OOM
+    const MAX_RESPONSE_SIZE: u32 = 100 * 1024 * 1024; // 100MB（响应可能包含大量进程数据）
+    if response_len > MAX_RESPONSE_SIZE {
+        return Err(format!(
+            "响应体过大: {} 字节（最大允许: {} 字节）",
+            response_len, MAX_RESPONSE_SIZE
+        ));
+    }
+
+    // 读取响应内容
+    let mut response_buf = vec![0u8; response_len as usize];
+    stream
+        .read_exact(&mut response_buf)
+        .await
+        .map_err(|e| format!("读取响应内容失败: {}", e))?;
+
+    // 解析响应
+    serde_json::from_slice(&response_buf).map_err(|e| format!("解析响应失败: {}", e))
+}
@@ -0,0 +1,117 @@
+//! IPC 认证与授权
+//!
+//! 每个 RPC 需要一个权限级别：查询类为 read，修改 daemon 状态的运维操作为 act。连接的初始权限：
+//! - Unix Socket：通过 SO_PEERCRED 取调用方 uid，root 和 daemon 自身的 uid 为 act，其他能连上
+//!   Socket（0660，同组）的用户为 read
+//! - Windows 命名管道：默认 DACL 只允许 daemon 用户、Administrators 和 SYSTEM 连接，连接即为 act
+//!
+//! 连接上的 `auth` 请求携带共享 token，校验通过后该连接获得 act 权限。token 由 daemon 启动时
+//! 生成并写入只有属主可读的文件（Unix 为 Socket 同名的 `.token` 文件），客户端自动读取该文件，
+//! 也可以通过环境变量 `ARK_IPC_TOKEN` 指定。
+
+use rand::RngCore;
+use std::path::{Path, PathBuf};
+
+/// 客户端指定 token 的环境变量
+pub const TOKEN_ENV: &str = "ARK_IPC_TOKEN";
+
+/// RPC 权限级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Permission {
+    /// 未认证：只允许 ping 和 auth
+    None,
+    /// 查询类 RPC
+    Read,
+    /// 修改 daemon 状态的 RPC
+    Act,
+}
+
+impl Permission {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Permission::None => "none",
+            Permission::Read => "read",
+            Permission::Act => "act",
+        }
+    }
+}
+
+/// Unix 调用方的初始权限：root 和 daemon 自身（`owner_uid`）为 act，其他为 read
+#[cfg(unix)]
+pub fn peer_permission(peer_uid: Option<u32>, owner_uid: u32) -> Permission {
+    match peer_uid {
+        Some(uid) if uid == 0 || uid == owner_uid => Permission::Act,
+        Some(_) => Permission::Read,
+        None => Permission::None,
+    }
+}
+
+/// token 文件路径：Socket 同名的 `.token` 文件
+#[cfg(unix)]
+pub fn token_path(socket_path: &Path) -> PathBuf {
+    socket_path.with_extension("token")
+}
+
+/// token 文件路径：`%USERPROFILE%\.ark\ipc.token`
+#[cfg(windows)]
+pub fn token_path() -> PathBuf {
+    let mut home = std::env::var("USERPROFILE").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("."));
+    home.push(".ark");
+    home.push("ipc.token");
+    home
+}
+
+/// 读取已有的 token，不存在时生成一个新 token 并以仅属主可读的权限写入
+///
+/// 已有的文件不会被覆盖，运维可以预先放置 token（例如分发给需要 act 权限的运维账号）
+pub fn load_or_create_token(path: &Path) -> Result<String, String> {
+    if let Ok(content) = std::fs::read_to_string(path) {
+        let token = content.trim().to_string();
+        if token.is_empty() {
+            return Err(format!("token 文件 {} 为空", path.display()));
+        }
+        return Ok(token);
+    }
+
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建 token 目录失败: {}", e))?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("写入 token 文件 {} 失败: {}", path.display(), e))?;
+    std::io::Write::write_all(&mut file, format!("{}\n", token).as_bytes())
+        .map_err(|e| format!("写入 token 文件 {} 失败: {}", path.display(), e))?;
+    Ok(token)
+}
+
+/// 客户端使用的 token：环境变量优先，其次 token 文件（无权读取时为 None）
+pub fn client_token(path: &Path) -> Option<String> {
+    if let Ok(token) = std::env::var(TOKEN_ENV) {
+        return Some(token.trim().to_string()).filter(|t| !t.is_empty());
+    }
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|content| content.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// 逐字节比较，耗时不随首个不同字节的位置变化
+pub fn token_matches(expected: &str, actual: &str) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .bytes()
+            .zip(actual.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
@@ -1,34 +1,77 @@
 mod plugin;
+mod probe;
 mod exec;
 mod ipc;
 mod diag;
 mod scene;
 mod hub_forwarder;
+mod hub_admin;
+mod hub_auth;
+mod hub_buffer;
+mod hub_tls;
 mod metrics;
 mod audit;
-
-use clap::{Parser, Subcommand};
-use ark_core::event::{Event, EventBus};
-use ark_core::graph::StateGraph;
-use ipc::{IpcClient, IpcServer, default_socket_path};
-use plugin::SubprocessProbe;
-use exec::{SystemActuator, FixEngine};
+mod approval;
+mod rule_sync;
+mod watch;
+mod output;
+mod health;
+mod config;
+mod snapshot;
+mod report;
+mod history;
+mod ipc_auth;
+mod telemetry;
+mod pods;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(unix)]
+mod daemon;
+
+use clap::{CommandFactory, Parser, Subcommand};
+use ark_core::event::{Event, EventBus, EventType};
+use ark_core::graph::{GraphConfig, StateGraph};
+use ark_core::rules::ReloadableRuleEngine;
+use ipc::{IpcClient, IpcServer, ProcessFilter, SubscribeFilter, default_socket_path};
+use probe::{ProbeOptions, ProbeSet, ProbeSpec, ProbeType};
+use exec::{ActionLimiter, ActionPolicy, ActionType, ActuatorRegistry, FixEngine, FixPlan, PolicyLevel, PolicySubject, RollbackOutcome};
 use diag::run_diagnosis;
 use scene::{SceneIdentifier, SceneType};
-use hub_forwarder::{HubForwarder, get_node_id};
+use hub_forwarder::{Delivery, HubForwarder, HubOptions, get_node_id};
+use hub_tls::HubTls;
+use rule_sync::{RuleOptions, RuleSource};
 use metrics::MetricsCollector;
+use history::{HistoryFilter, HistoryKind, HistoryRecord, HistoryStore};
+use health::DaemonHealth;
+use pods::{PodOptions, PodResolver};
+use config::{DaemonConfig, LogLevel};
+use output::{
+    ActionReport, ClusterFixReport, ClusterFixTarget, ClusterScene, ClusterProcessReport, ClusterWhyReport, ExitStatus, FixReport,
+    OutputFormat, ProcessReport, RollbackReport, WhyDelta, WhyReport,
+};
 use std::sync::Arc;
-use std::path::PathBuf;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use tracing::Instrument;
 
 #[cfg(windows)]
 const DEFAULT_IPC_PORT: u16 = 9090;
 
+/// `ark events` 订阅的广播缓冲（订阅端落后超过该条数时丢弃最旧的事件）
+const EVENT_STREAM_CAPACITY: usize = 1024;
+
+/// `ark cluster fix` 下发给各节点的动作（节点按 `HubForwarder::action_from_string` 解析）
+const CLUSTER_FIX_ACTION: &str = "GracefulShutdown";
+
 #[derive(Parser)]
 #[command(name = "ark")]
 #[command(about = "极简主义异构 AI 算力集群管控底座", long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// 输出格式（json / yaml 输出稳定结构，供脚本使用）
+    #[arg(long, short = 'o', global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
 }
 
 #[derive(Subcommand)]
@@ -40,15 +83,142 @@ enum Commands {
         #[arg(long)]
         socket_path: Option<PathBuf>,
         #[cfg(windows)]
-        /// IPC 服务端口（默认: 9090）
+        /// IPC 端口号，对应命名管道 \\.\pipe\ark-<port>（默认: 9090）
         #[arg(long, default_value_t = DEFAULT_IPC_PORT)]
         port: u16,
-        /// 探针脚本路径（可选，默认使用内置 dummy_probe）
+        /// 探针脚本路径（可多次指定，各探针并发运行；未配置任何探针时使用内置 dummy_probe）
         #[arg(long)]
-        probe: Option<PathBuf>,
-        /// Hub WebSocket 地址（可选，如 ws://hub.example.com:8080）
+        probe: Vec<PathBuf>,
+        /// 原生探针（可多次指定）
+        #[arg(long, value_enum)]
+        native_probe: Vec<ProbeType>,
+        /// 探针配置文件（YAML，probes 列表，每项为 path / command + args / native）
+        #[arg(long)]
+        probe_config: Option<PathBuf>,
+        /// Hub WebSocket 地址（可选，如 ws://hub.example.com:8080；断开后自动重连）
         #[arg(long)]
         hub_url: Option<String>,
+        /// Hub 连接断开期间最多缓冲的消息数（满时丢弃最旧的），重连后补发
+        #[arg(long, default_value_t = hub_buffer::DEFAULT_BUFFER_CAPACITY)]
+        hub_buffer: usize,
+        /// Hub 缓冲落盘文件（如 /var/lib/ark/hub-spool.jsonl），daemon 重启后继续补发
+        #[arg(long)]
+        hub_spool: Option<PathBuf>,
+        /// 向 Hub 注册时上报的节点标签（key=value，可多次指定，如 --node-label rack=r12）
+        #[arg(long = "node-label", value_parser = hub_forwarder::parse_label)]
+        node_labels: Vec<(String, String)>,
+        /// 校验 Hub 证书的 CA（PEM，wss:// 连接使用私有 CA 签发的证书时指定）
+        #[arg(long)]
+        hub_ca: Option<PathBuf>,
+        /// 向 Hub 出示的客户端证书（PEM，Hub 以 --tls-client-ca 校验 Agent 时必需）
+        #[arg(long)]
+        hub_cert: Option<PathBuf>,
+        /// 客户端证书的私钥（PEM）
+        #[arg(long)]
+        hub_key: Option<PathBuf>,
+        /// 状态图配置文件（YAML，可配置错误窗口、清理策略、容量上限）
+        #[arg(long)]
+        graph_config: Option<PathBuf>,
+        /// 审计日志文件路径（记录运维类 IPC 操作，如 /var/log/ark/audit.log）
+        #[arg(long)]
+        audit_log: Option<PathBuf>,
+        /// Hub HTTP API 地址（用于校验 Hub 下发高危命令的审批 token，如 http://hub:8081）
+        #[arg(long)]
+        hub_api: Option<String>,
+        /// 规则文件目录（daemon 监听该目录，YAML 变化时自动热加载；远程来源时作为本地缓存）
+        #[arg(long)]
+        rules_dir: Option<PathBuf>,
+        /// 远程规则来源：hub（从 --hub-api 拉取）或 http(s) 地址；设置 ARK_RULES_KEY 时校验签名
+        #[arg(long)]
+        rules_source: Option<String>,
+        /// 远程规则刷新间隔（秒）
+        #[arg(long, default_value_t = 300)]
+        rules_refresh_secs: u64,
+        /// 动态配置文件（YAML：log_level、forward 推送过滤），Unix 上收到 SIGHUP 时重新加载
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// 同时启动 gRPC 控制面，监听该地址（如 127.0.0.1:50051；需以 --features grpc 构建，调用需携带 IPC token）
+        #[arg(long)]
+        grpc_listen: Option<SocketAddr>,
+        /// 只读模式：保留探针、状态图、诊断和 Hub 推送，拒绝一切修复动作（fix / zap / 回滚、Hub 下发的命令）
+        #[arg(long)]
+        read_only: bool,
+        /// Hub 自动解除故障污点前下发自检时运行的命令（如 "dcgmi diag -r 1"；未指定时运行 nvidia-smi 或 npu-smi info）
+        #[arg(long)]
+        self_test: Option<String>,
+        /// kubelet API 地址，用于把事件中的进程关联到 Pod（在 Kubernetes 中运行时默认为 https://127.0.0.1:10250）
+        #[arg(long)]
+        kubelet_url: Option<String>,
+        /// 不校验 kubelet 的服务证书（kubelet 使用自签名证书时）
+        #[arg(long)]
+        kubelet_insecure_tls: bool,
+        #[cfg(unix)]
+        /// 后台运行（fork 后脱离终端；由 systemd 托管时不需要，使用 Type=notify）
+        #[arg(long)]
+        daemonize: bool,
+        #[cfg(unix)]
+        /// pidfile 路径（如 /var/run/ark.pid），退出时删除
+        #[arg(long)]
+        pid_file: Option<PathBuf>,
+        #[cfg(unix)]
+        /// 后台运行时 stdout / stderr 重定向到该文件（未指定时丢弃）
+        #[arg(long, requires = "daemonize")]
+        log_file: Option<PathBuf>,
+    },
+    /// 查看 daemon 健康状态：运行时长、探针、Hub 连接、事件吞吐和图规模
+    Status {
+        #[cfg(unix)]
+        /// Unix Domain Socket 路径（默认: /var/run/ark.sock 或 ~/.ark/ark.sock）
+        #[arg(long)]
+        socket_path: Option<PathBuf>,
+        #[cfg(windows)]
+        /// IPC 端口号，对应命名管道 \\.\pipe\ark-<port>（默认: 9090）
+        #[arg(long, default_value_t = DEFAULT_IPC_PORT)]
+        port: u16,
+        /// 探针超过该秒数没有上报事件时视为异常
+        #[arg(long, default_value_t = 60)]
+        stale_secs: u64,
+    },
+    /// 打包诊断快照（状态图、最近事件、规则命中、审计记录、指标、探针健康）为 tar.gz，便于附到工单
+    Snapshot {
+        #[cfg(unix)]
+        /// Unix Domain Socket 路径（默认: /var/run/ark.sock 或 ~/.ark/ark.sock）
+        #[arg(long)]
+        socket_path: Option<PathBuf>,
+        #[cfg(windows)]
+        /// IPC 端口号，对应命名管道 \\.\pipe\ark-<port>（默认: 9090）
+        #[arg(long, default_value_t = DEFAULT_IPC_PORT)]
+        port: u16,
+        /// 输出文件（默认: 当前目录下的 ark-snapshot-<节点>-<时间>.tar.gz）
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// 打包的最近事件条数
+        #[arg(long, default_value_t = 1000)]
+        events: usize,
+        /// 打包的最近审计记录条数
+        #[arg(long, default_value_t = 200)]
+        audit_entries: usize,
+        /// daemon 的 Prometheus 指标地址
+        #[arg(long, default_value = "http://127.0.0.1:9091/metrics")]
+        metrics_url: String,
+    },
+    /// 浏览本地事故历史：规则命中、诊断结论和执行过的修复
+    History {
+        /// 只显示该进程的记录
+        #[arg(long)]
+        pid: Option<u32>,
+        /// 只显示该 job 的记录
+        #[arg(long)]
+        job: Option<String>,
+        /// 只显示该类型的记录
+        #[arg(long, value_enum)]
+        kind: Option<HistoryKind>,
+        /// 最多显示的记录数（最近的）
+        #[arg(long, short = 'n', default_value_t = 50)]
+        limit: usize,
+        /// 历史文件（默认: $ARK_HISTORY 或 ~/.ark/history.jsonl）
+        #[arg(long)]
+        file: Option<PathBuf>,
     },
     /// 查询当前活跃进程列表
     Ps {
@@ -57,9 +227,21 @@ enum Commands {
         #[arg(long)]
         socket_path: Option<PathBuf>,
         #[cfg(windows)]
-        /// IPC 服务端口（默认: 9090）
+        /// IPC 端口号，对应命名管道 \\.\pipe\ark-<port>（默认: 9090）
         #[arg(long, default_value_t = DEFAULT_IPC_PORT)]
         port: u16,
+        /// 只列出该 job 的进程（支持 glob，如 'train-*'）
+        #[arg(long)]
+        job: Option<String>,
+        /// 只列出该状态的进程（如 running / blocked）
+        #[arg(long)]
+        state: Option<String>,
+        /// 只列出该主机的进程（支持 glob；单机模式下进程属于本机）
+        #[arg(long)]
+        node: Option<String>,
+        /// 只列出有 waits_on / blocked_by 出边的进程
+        #[arg(long)]
+        blocked_only: bool,
     },
     /// 分析进程阻塞根因
     Why {
@@ -70,14 +252,31 @@ enum Commands {
         #[arg(long)]
         socket_path: Option<PathBuf>,
         #[cfg(windows)]
-        /// IPC 服务端口（默认: 9090）
+        /// IPC 端口号，对应命名管道 \\.\pipe\ark-<port>（默认: 9090）
         #[arg(long, default_value_t = DEFAULT_IPC_PORT)]
         port: u16,
+        /// 持续跟踪：定期（以及收到新事件时）重新分析，只输出根因的变化，Ctrl+C 退出
+        #[arg(long, short = 'f')]
+        follow: bool,
+        /// 跟踪模式下的重新分析间隔（秒）
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
     },
-    /// 强制终止进程（包括进程树）
+    /// 强制终止进程（包括进程树，由 daemon 执行并记录审计日志）
     Zap {
         /// 目标进程 PID
         pid: u32,
+        #[cfg(unix)]
+        /// Unix Domain Socket 路径（默认: /var/run/ark.sock 或 ~/.ark/ark.sock）
+        #[arg(long)]
+        socket_path: Option<PathBuf>,
+        #[cfg(windows)]
+        /// IPC 端口号，对应命名管道 \\.\pipe\ark-<port>（默认: 9090）
+        #[arg(long, default_value_t = DEFAULT_IPC_PORT)]
+        port: u16,
+        /// 破窗审批 token（由第二位运维通过 Hub 签发，daemon 向 Hub 校验）
+        #[arg(long)]
+        approval: Option<String>,
     },
     /// AI 诊断：使用大模型分析进程阻塞根因并提供修复建议
     Diag {
@@ -88,7 +287,7 @@ enum Commands {
         #[arg(long)]
         socket_path: Option<PathBuf>,
         #[cfg(windows)]
-        /// IPC 服务端口（默认: 9090）
+        /// IPC 端口号，对应命名管道 \\.\pipe\ark-<port>（默认: 9090）
         #[arg(long, default_value_t = DEFAULT_IPC_PORT)]
         port: u16,
         /// 大模型提供商（openai/claude/local，默认从环境变量读取）
@@ -98,19 +297,48 @@ enum Commands {
         #[arg(long)]
         rules_dir: Option<PathBuf>,
     },
-    /// 自动修复：根据诊断结果执行推荐动作（优雅降级、发信号、限流等）
-    Fix {
+    /// 生成事故报告（根因、场景分析、规则命中、状态图上下文、最近事件），用于事故复盘
+    Report {
         /// 目标进程 PID
         pid: u32,
-        /// 审计日志文件路径（可选，如 /var/log/ark/audit.log）
+        #[cfg(unix)]
+        /// Unix Domain Socket 路径（默认: /var/run/ark.sock 或 ~/.ark/ark.sock）
         #[arg(long)]
-        audit_log: Option<PathBuf>,
+        socket_path: Option<PathBuf>,
+        #[cfg(windows)]
+        /// IPC 端口号，对应命名管道 \\.\pipe\ark-<port>（默认: 9090）
+        #[arg(long, default_value_t = DEFAULT_IPC_PORT)]
+        port: u16,
+        /// 输出文件（未指定时输出到 stdout）
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// 报告格式（默认按 --out 扩展名推断，.html 为 HTML，其余为 Markdown）
+        #[arg(long, value_enum)]
+        format: Option<report::ReportFormat>,
+        /// 报告中包含的最近相关事件条数
+        #[arg(long, default_value_t = 50)]
+        events: usize,
+        /// 同时执行 diag 并附上诊断建议（规则未命中时会调用大模型）
+        #[arg(long)]
+        diag: bool,
+        /// 大模型提供商（openai/claude/local，默认从环境变量读取）
+        #[arg(long)]
+        provider: Option<String>,
+        /// 规则文件目录（默认: ./rules）
+        #[arg(long)]
+        rules_dir: Option<PathBuf>,
+    },
+    /// 自动修复：根据诊断结果执行推荐动作（优雅降级、发信号、限流等），动作由 daemon 执行并记录审计日志
+    Fix {
+        /// 目标进程 PID（--rollback 时不需要）
+        #[arg(required_unless_present = "rollback")]
+        pid: Option<u32>,
         #[cfg(unix)]
         /// Unix Domain Socket 路径（默认: /var/run/ark.sock 或 ~/.ark/ark.sock）
         #[arg(long)]
         socket_path: Option<PathBuf>,
         #[cfg(windows)]
-        /// IPC 服务端口（默认: 9090）
+        /// IPC 端口号，对应命名管道 \\.\pipe\ark-<port>（默认: 9090）
         #[arg(long, default_value_t = DEFAULT_IPC_PORT)]
         port: u16,
         /// 规则文件目录（默认: ./rules）
@@ -119,6 +347,86 @@ enum Commands {
         /// 是否自动执行（不询问确认）
         #[arg(long)]
         yes: bool,
+        /// 破窗审批 token（推荐动作包含终止/隔离等高危操作时由 daemon 向 Hub 校验）
+        #[arg(long)]
+        approval: Option<String>,
+        /// 预演：列出将要执行的命令、信号和 cgroup 写入（含解析出的进程组和路径），不修改系统
+        #[arg(long)]
+        dry_run: bool,
+        /// 撤销一次修复中的可回滚动作（cgroup 限流、节点隔离、网络接口重启），fix-id 见修复结果
+        #[arg(long, value_name = "FIX_ID", conflicts_with_all = ["pid", "dry_run", "approval", "rules_dir"])]
+        rollback: Option<String>,
+    },
+    /// 状态图命令：导出图，或手动修正错误的图状态（需确认，记录审计日志）
+    Graph {
+        #[command(subcommand)]
+        command: GraphCommands,
+        #[cfg(unix)]
+        /// Unix Domain Socket 路径（默认: /var/run/ark.sock 或 ~/.ark/ark.sock）
+        #[arg(long)]
+        socket_path: Option<PathBuf>,
+        #[cfg(windows)]
+        /// IPC 端口号，对应命名管道 \\.\pipe\ark-<port>（默认: 9090）
+        #[arg(long, default_value_t = DEFAULT_IPC_PORT)]
+        port: u16,
+    },
+    /// 实时视图：持续展示活跃进程、资源占用、阻塞边和最近的错误事件（q 退出）
+    Watch {
+        #[cfg(unix)]
+        /// Unix Domain Socket 路径（默认: /var/run/ark.sock 或 ~/.ark/ark.sock）
+        #[arg(long)]
+        socket_path: Option<PathBuf>,
+        #[cfg(windows)]
+        /// IPC 端口号，对应命名管道 \\.\pipe\ark-<port>（默认: 9090）
+        #[arg(long, default_value_t = DEFAULT_IPC_PORT)]
+        port: u16,
+        /// 刷新间隔（毫秒）
+        #[arg(long, default_value_t = 1000)]
+        interval_ms: u64,
+        /// 展示的最近错误事件条数
+        #[arg(long, default_value_t = 20)]
+        errors: usize,
+    },
+    /// 实时输出 daemon 收到的事件（可按类型和实体过滤），用于验证探针
+    Events {
+        #[cfg(unix)]
+        /// Unix Domain Socket 路径（默认: /var/run/ark.sock 或 ~/.ark/ark.sock）
+        #[arg(long)]
+        socket_path: Option<PathBuf>,
+        #[cfg(windows)]
+        /// IPC 端口号，对应命名管道 \\.\pipe\ark-<port>（默认: 9090）
+        #[arg(long, default_value_t = DEFAULT_IPC_PORT)]
+        port: u16,
+        /// 只输出这些类型的事件（可重复，如 --type transport.drop --type error.hw）
+        #[arg(long = "type")]
+        event_type: Vec<String>,
+        /// 实体 ID 模式（glob 或 regex: 前缀，如 'gpu-*'）
+        #[arg(long)]
+        entity: Option<String>,
+        /// 订阅前先回放 daemon 保留的最近 N 秒内的匹配事件
+        #[arg(long, value_name = "SECS")]
+        since: Option<u64>,
+        /// 同时输出状态图结构变化（节点、边的增删）
+        #[arg(long)]
+        graph_changes: bool,
+        /// 每行输出一个 JSON 对象（事件，或带 change 字段的图变化）
+        #[arg(long)]
+        json: bool,
+    },
+    /// 规则命令：列出、校验规则文件，或用样例事件测试规则
+    Rules {
+        #[command(subcommand)]
+        command: RulesCommands,
+    },
+    /// 探针开发工具
+    Probe {
+        #[command(subcommand)]
+        command: ProbeCommands,
+    },
+    /// 审计日志工具
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
     },
     /// 集群级命令：查询全局状态和根因分析
     Cluster {
@@ -128,12 +436,35 @@ enum Commands {
         #[arg(long, default_value = "http://localhost:8081")]
         hub: String,
     },
+    /// Hub 运维命令：节点、规则、全局状态图、告警和静默（API 密钥由 ARK_HUB_TOKEN 配置）
+    Hub {
+        #[command(subcommand)]
+        command: HubCommands,
+        /// Hub HTTP API 地址（如 http://hub.example.com:8081）
+        #[arg(long, default_value = "http://localhost:8081")]
+        hub: String,
+    },
+    /// 生成 shell 补全脚本（如: ark completions bash > /etc/bash_completion.d/ark）
+    Completions {
+        /// 目标 shell
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// 生成 man 手册（打包用）：未指定目录时输出 ark(1) 到 stdout，否则为每个子命令生成一页
+    #[command(hide = true)]
+    Man {
+        /// 输出目录
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
 }
 
 #[derive(Subcommand)]
 enum ClusterCommands {
     /// 查询集群中所有活跃进程
     Ps,
+    /// 列出 Hub 已知的节点：连接状态、最近在线时间、Agent 版本、GPU / NPU 数和近期错误数
+    Nodes,
     /// 分析集群中某个 job 的根因
     Why {
         /// 目标 job_id
@@ -146,82 +477,442 @@ enum ClusterCommands {
         /// 是否自动确认（跳过交互式确认）
         #[arg(long, short = 'y')]
         yes: bool,
+        /// 破窗审批 token（由第二位运维通过 Hub 签发，按 job 绑定；修复动作会终止进程，必须提供）
+        #[arg(long)]
+        approval: Option<String>,
+        /// 等待节点回报执行结果的秒数（0 表示下发后立即返回）
+        #[arg(long, default_value_t = 60)]
+        wait_secs: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum HubCommands {
+    /// 列出 Hub 已知的节点及连接状态（同 ark cluster nodes）
+    Nodes,
+    /// 从节点清单中移除节点，在线时断开其连接（需要 admin 角色）
+    Drop {
+        /// 节点 ID
+        node_id: String,
+        /// 跳过交互式确认
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// 确认故障节点已修复，解除其 K8s 故障污点（需要 admin 角色，Hub 需启用 K8s 控制器）
+    Repaired {
+        /// 节点 ID
+        node_id: String,
+        /// 原因（写入 Hub 审计和恢复告警）
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// 校验 Hub 的规则目录并通知节点立即拉取（需要 admin 角色）
+    ReloadRules,
+    /// 导出 Hub 的全局状态图
+    Graph {
+        /// 导出格式：dot / json / graphml
+        #[arg(long, default_value = "json")]
+        format: String,
+        /// 输出文件（默认输出到 stdout）
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+    /// 列出活动告警（按指纹合并，含出现次数和静默状态）
+    Alerts,
+    /// 列出生效中的静默
+    Silences,
+    /// 维护窗口内静默节点或 job 的告警（需要 operator 角色）
+    Silence {
+        /// 静默该节点的告警（含涉及该节点的跨节点场景）
+        #[arg(long)]
+        node: Option<String>,
+        /// 静默该 job 的告警（与 --node 同时指定时两者都匹配才静默）
+        #[arg(long)]
+        job: Option<String>,
+        /// 静默时长（如 30m、2h、1d，最长 7 天）
+        #[arg(long, value_parser = hub_admin::parse_duration)]
+        duration: u64,
+        /// 原因
+        #[arg(long, default_value = "")]
+        reason: String,
+    },
+    /// 提前结束静默（需要 operator 角色）
+    Unsilence {
+        /// 静默 ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum RulesCommands {
+    /// 按优先级列出规则目录中的规则
+    List {
+        /// 规则文件目录
+        #[arg(long, default_value = "rules")]
+        dir: PathBuf,
+    },
+    /// 校验规则目录，报告带文件/行号的错误（有错误时返回非零退出码）
+    Validate {
+        /// 规则文件目录
+        #[arg(long, default_value = "rules")]
+        dir: PathBuf,
+    },
+    /// 用 JSONL 样例事件测试单条规则（每行一个事件）
+    Test {
+        /// 规则文件
+        #[arg(long)]
+        rule: PathBuf,
+        /// 样例事件文件（JSONL）
+        #[arg(long)]
+        events: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProbeCommands {
+    /// 试运行探针并检查输出：格式错误、未知事件类型、缺少 entity_id 和事件速率（有问题时返回非零退出码）
+    Validate {
+        /// 探针脚本（.py 用 python3 运行）或可执行文件
+        target: PathBuf,
+        /// 传给探针的参数
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+        /// 运行时长（秒）
+        #[arg(long, default_value = "5")]
+        duration: u64,
+        /// 最多列出的问题行数
+        #[arg(long, default_value = "10")]
+        max_issues: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditCommands {
+    /// 校验审计日志的哈希链，检测记录被修改、删除或文件被截断（设置 ARK_AUDIT_KEY 时同时校验 HMAC；有问题时返回非零退出码）
+    Verify {
+        /// 审计日志文件（如 /var/log/ark/audit.log）
+        file: PathBuf,
+    },
+    /// 查询审计日志（同目录下已轮转的文件一并查询），按时间、进程、job、动作和结果过滤
+    Search {
+        /// 审计日志文件（如 /var/log/ark/audit.log）
+        file: PathBuf,
+        /// 起始时间：RFC3339 时间或相对时长（如 30m、2h、7d）
+        #[arg(long, value_parser = audit::parse_time)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        /// 截止时间：RFC3339 时间或相对时长
+        #[arg(long, value_parser = audit::parse_time)]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        /// 只显示针对该进程的记录
+        #[arg(long)]
+        pid: Option<u32>,
+        /// 只显示该 job 的记录
+        #[arg(long)]
+        job: Option<String>,
+        /// 只显示该动作的记录（如 fix、zap、rollback、hub.fix）
+        #[arg(long)]
+        action: Option<String>,
+        /// 只显示该结果的记录（如 success、partial_failure、denied、read_only）
+        #[arg(long)]
+        result: Option<String>,
+        /// 最多显示的记录数（最近的）
+        #[arg(long, short = 'n', default_value_t = 50)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum GraphCommands {
+    /// 导出状态图（用于 Graphviz 可视化或导入其他工具）
+    Export {
+        /// 导出格式：dot / json / graphml
+        #[arg(long, default_value = "dot")]
+        format: String,
+        /// 输出文件（默认输出到 stdout）
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+    /// 对比两个 JSON 快照（ark graph export --format json），确认修复后阻塞边是否消失
+    Diff {
+        /// 修复前的快照文件
+        before: PathBuf,
+        /// 修复后的快照文件
+        after: PathBuf,
+    },
+    /// 删除节点及其所有关联边（如卡住的过期错误节点）
+    RmNode {
+        /// 完整节点 ID（如 error-gpu-0 或 node-a::pid-1234）
+        node_id: String,
+        /// 跳过交互式确认
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// 删除两个节点之间的边
+    RmEdge {
+        /// 源节点 ID
+        from: String,
+        /// 目标节点 ID
+        to: String,
+        /// 边类型（consumes/waits_on/blocked_by，默认删除所有类型）
+        #[arg(long)]
+        edge_type: Option<String>,
+        /// 跳过交互式确认
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// 覆盖节点的 metadata 字段（如将 state 改为 exit）
+    SetMeta {
+        /// 完整节点 ID
+        node_id: String,
+        /// metadata 键
+        key: String,
+        /// metadata 值
+        value: String,
+        /// 跳过交互式确认
+        #[arg(long, short = 'y')]
+        yes: bool,
     },
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
+    // 后台化需要在创建 tokio 运行时之前 fork；pidfile 在 daemon 退出时删除
+    #[cfg(unix)]
+    let _pid_file = match &cli.command {
+        Commands::Run { daemonize, pid_file, log_file, .. } => {
+            daemon::start(*daemonize, pid_file.clone(), log_file.as_deref())?
+        }
+        _ => None,
+    };
+
+    let status = tokio::runtime::Runtime::new()?.block_on(run(cli))?;
+    if status != ExitStatus::Clean {
+        std::process::exit(status as i32);
+    }
+    Ok(())
+}
+
+async fn run(cli: Cli) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    let output = cli.output;
+    let mut status = ExitStatus::Clean;
+
     match cli.command {
         #[cfg(unix)]
-        Commands::Run { socket_path, probe, hub_url } => {
-            run_daemon(socket_path, probe, hub_url).await?;
+        Commands::Run { socket_path, probe, native_probe, probe_config, hub_url, hub_buffer, hub_spool, node_labels, hub_ca, hub_cert, hub_key, graph_config, audit_log, hub_api, rules_dir, rules_source, rules_refresh_secs, config, grpc_listen, read_only, self_test, kubelet_url, kubelet_insecure_tls, .. } => {
+            let rules = rule_options(rules_dir, rules_source, rules_refresh_secs, hub_api.as_deref())?;
+            let probes = probe_options(probe, native_probe, probe_config);
+            let hub = HubOptions { url: hub_url, buffer: hub_buffer, spool: hub_spool, labels: node_labels.into_iter().collect(), tls: HubTls { ca: hub_ca, cert: hub_cert, key: hub_key }, rules_refresh: rules.source.url().map(|_| Arc::clone(&rules.refresh_now)), self_test };
+            let pods = PodOptions { kubelet_url, insecure_tls: kubelet_insecure_tls };
+            run_daemon(socket_path, probes, hub, pods, graph_config, audit_log, hub_api, rules, config, grpc_listen, read_only).await?;
+        }
+        #[cfg(windows)]
+        Commands::Run { port, probe, native_probe, probe_config, hub_url, hub_buffer, hub_spool, node_labels, hub_ca, hub_cert, hub_key, graph_config, audit_log, hub_api, rules_dir, rules_source, rules_refresh_secs, config, grpc_listen, read_only, self_test, kubelet_url, kubelet_insecure_tls } => {
+            let rules = rule_options(rules_dir, rules_source, rules_refresh_secs, hub_api.as_deref())?;
+            let probes = probe_options(probe, native_probe, probe_config);
+            let hub = HubOptions { url: hub_url, buffer: hub_buffer, spool: hub_spool, labels: node_labels.into_iter().collect(), tls: HubTls { ca: hub_ca, cert: hub_cert, key: hub_key }, rules_refresh: rules.source.url().map(|_| Arc::clone(&rules.refresh_now)), self_test };
+            let pods = PodOptions { kubelet_url, insecure_tls: kubelet_insecure_tls };
+            run_daemon(port, probes, hub, pods, graph_config, audit_log, hub_api, rules, config, grpc_listen, read_only).await?;
+        }
+        #[cfg(unix)]
+        Commands::Status { socket_path, stale_secs } => {
+            status = daemon_status(IpcClient::new(socket_path), stale_secs, output).await?;
         }
         #[cfg(windows)]
-        Commands::Run { port, probe, hub_url } => {
-            run_daemon(port, probe, hub_url).await?;
+        Commands::Status { port, stale_secs } => {
+            status = daemon_status(IpcClient::new(port), stale_secs, output).await?;
         }
         #[cfg(unix)]
-        Commands::Ps { socket_path } => {
-            query_processes(socket_path).await?;
+        Commands::Snapshot { socket_path, out, events, audit_entries, metrics_url } => {
+            let options = snapshot::SnapshotOptions { out, events, audit_entries, metrics_url };
+            create_snapshot(IpcClient::new(socket_path), options, output).await?;
         }
         #[cfg(windows)]
-        Commands::Ps { port } => {
-            query_processes(port).await?;
+        Commands::Snapshot { port, out, events, audit_entries, metrics_url } => {
+            let options = snapshot::SnapshotOptions { out, events, audit_entries, metrics_url };
+            create_snapshot(IpcClient::new(port), options, output).await?;
         }
         #[cfg(unix)]
-        Commands::Why { pid, socket_path } => {
-            query_why(pid, socket_path).await?;
+        Commands::Ps { socket_path, job, state, node, blocked_only } => {
+            let filter = ProcessFilter { job_id: job, state, node_id: node, blocked_only };
+            query_processes(IpcClient::new(socket_path), &filter, output).await?;
         }
         #[cfg(windows)]
-        Commands::Why { pid, port } => {
-            query_why(pid, port).await?;
+        Commands::Ps { port, job, state, node, blocked_only } => {
+            let filter = ProcessFilter { job_id: job, state, node_id: node, blocked_only };
+            query_processes(IpcClient::new(port), &filter, output).await?;
+        }
+        #[cfg(unix)]
+        Commands::Why { pid, socket_path, follow, interval } => {
+            status = if follow {
+                follow_why(pid, IpcClient::new(socket_path), interval, output).await?
+            } else {
+                query_why(pid, IpcClient::new(socket_path), output).await?
+            };
+        }
+        #[cfg(windows)]
+        Commands::Why { pid, port, follow, interval } => {
+            status = if follow {
+                follow_why(pid, IpcClient::new(port), interval, output).await?
+            } else {
+                query_why(pid, IpcClient::new(port), output).await?
+            };
         }
-        Commands::Zap { pid } => {
-            zap_process(pid).await?;
+        #[cfg(unix)]
+        Commands::Zap { pid, socket_path, approval } => {
+            zap_process(pid, IpcClient::new(socket_path), approval).await?;
+        }
+        #[cfg(windows)]
+        Commands::Zap { pid, port, approval } => {
+            zap_process(pid, IpcClient::new(port), approval).await?;
         }
         #[cfg(unix)]
         Commands::Diag { pid, socket_path, provider, rules_dir } => {
-            diagnose_process(pid, socket_path, provider, rules_dir).await?;
+            status = diagnose_process(pid, socket_path, provider, rules_dir, output).await?;
         }
         #[cfg(windows)]
         Commands::Diag { pid, port, provider, rules_dir } => {
-            diagnose_process(pid, port, provider, rules_dir).await?;
+            status = diagnose_process(pid, port, provider, rules_dir, output).await?;
+        }
+        #[cfg(unix)]
+        Commands::Report { pid, socket_path, out, format, events, diag, provider, rules_dir } => {
+            let diagnosis = if diag {
+                Some(run_diagnosis(pid, socket_path.clone(), provider, default_rules_dir(rules_dir)).await?)
+            } else {
+                None
+            };
+            status = write_report(pid, IpcClient::new(socket_path), out, format, events, diagnosis, output).await?;
+        }
+        #[cfg(windows)]
+        Commands::Report { pid, port, out, format, events, diag, provider, rules_dir } => {
+            let diagnosis = if diag {
+                Some(run_diagnosis(pid, port, provider, default_rules_dir(rules_dir)).await?)
+            } else {
+                None
+            };
+            status = write_report(pid, IpcClient::new(port), out, format, events, diagnosis, output).await?;
+        }
+        #[cfg(unix)]
+        Commands::Fix { pid, socket_path, rules_dir, yes, approval, dry_run, rollback } => {
+            let client = IpcClient::new(socket_path);
+            status = match (rollback, pid) {
+                (Some(fix_id), _) => rollback_fix(&fix_id, client, yes, output).await?,
+                (None, Some(pid)) => fix_process(pid, client, rules_dir, yes, approval, dry_run, output).await?,
+                (None, None) => return Err("缺少目标进程 PID".into()),
+            };
+        }
+        #[cfg(windows)]
+        Commands::Fix { pid, port, rules_dir, yes, approval, dry_run, rollback } => {
+            let client = IpcClient::new(port);
+            status = match (rollback, pid) {
+                (Some(fix_id), _) => rollback_fix(&fix_id, client, yes, output).await?,
+                (None, Some(pid)) => fix_process(pid, client, rules_dir, yes, approval, dry_run, output).await?,
+                (None, None) => return Err("缺少目标进程 PID".into()),
+            };
+        }
+        #[cfg(unix)]
+        Commands::Graph { command, socket_path } => {
+            run_graph_command(command, IpcClient::new(socket_path)).await?;
+        }
+        #[cfg(windows)]
+        Commands::Graph { command, port } => {
+            run_graph_command(command, IpcClient::new(port)).await?;
+        }
+        #[cfg(unix)]
+        Commands::Watch { socket_path, interval_ms, errors } => {
+            watch::run_watch(IpcClient::new(socket_path), std::time::Duration::from_millis(interval_ms), errors).await?;
+        }
+        #[cfg(windows)]
+        Commands::Watch { port, interval_ms, errors } => {
+            watch::run_watch(IpcClient::new(port), std::time::Duration::from_millis(interval_ms), errors).await?;
         }
         #[cfg(unix)]
-        Commands::Fix { pid, socket_path, rules_dir, yes, audit_log } => {
-            fix_process(pid, socket_path, rules_dir, yes, audit_log).await?;
+        Commands::Events { socket_path, event_type, entity, since, graph_changes, json } => {
+            let filter = SubscribeFilter { event_types: event_type, entity_pattern: entity, graph_changes, ..Default::default() };
+            tail_events(IpcClient::new(socket_path), filter, since, json || output == OutputFormat::Json).await?;
         }
         #[cfg(windows)]
-        Commands::Fix { pid, port, rules_dir, yes, audit_log } => {
-            fix_process(pid, port, rules_dir, yes, audit_log).await?;
+        Commands::Events { port, event_type, entity, since, graph_changes, json } => {
+            let filter = SubscribeFilter { event_types: event_type, entity_pattern: entity, graph_changes, ..Default::default() };
+            tail_events(IpcClient::new(port), filter, since, json || output == OutputFormat::Json).await?;
+        }
+        Commands::Rules { command } => {
+            run_rules_command(command).await?;
+        }
+        Commands::History { pid, job, kind, limit, file } => {
+            let filter = HistoryFilter { pid, job_id: job, kind };
+            show_history(&file.unwrap_or_else(history::default_history_path), &filter, limit, output)?;
+        }
+        Commands::Probe { command } => {
+            run_probe_command(command, output).await?;
+        }
+        Commands::Audit { command } => {
+            run_audit_command(command, output)?;
+        }
+        Commands::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "ark", &mut std::io::stdout());
+        }
+        Commands::Man { out_dir } => {
+            generate_man(out_dir)?;
         }
         Commands::Cluster { command, hub } => {
             match command {
                 ClusterCommands::Ps => {
-                    cluster_ps(&hub).await?;
+                    cluster_ps(&hub, output).await?;
+                }
+                ClusterCommands::Nodes => {
+                    cluster_nodes(&hub, output).await?;
                 }
                 ClusterCommands::Why { job_id } => {
-                    cluster_why(&hub, &job_id).await?;
+                    status = cluster_why(&hub, &job_id, output).await?;
                 }
-                ClusterCommands::Fix { job_id, yes } => {
-                    cluster_fix(&hub, &job_id, yes).await?;
+                ClusterCommands::Fix { job_id, yes, approval, wait_secs } => {
+                    let wait = std::time::Duration::from_secs(wait_secs);
+                    status = cluster_fix(&hub, &job_id, yes, approval.as_deref(), wait, output).await?;
                 }
             }
         }
+        Commands::Hub { command, hub } => {
+            match command {
+                HubCommands::Nodes => cluster_nodes(&hub, output).await?,
+                HubCommands::Drop { node_id, yes } => hub_admin::drop_node(&hub, &node_id, yes, output).await?,
+                HubCommands::Repaired { node_id, reason } => hub_admin::mark_repaired(&hub, &node_id, reason, output).await?,
+                HubCommands::ReloadRules => hub_admin::reload_rules(&hub, output).await?,
+                HubCommands::Graph { format, output: out } => hub_admin::export_graph(&hub, &format, out.as_deref()).await?,
+                HubCommands::Alerts => hub_admin::list_alerts(&hub, output).await?,
+                HubCommands::Silences => hub_admin::list_silences(&hub, output).await?,
+                HubCommands::Silence { node, job, duration, reason } => {
+                    hub_admin::create_silence(&hub, node, job, duration, reason, output).await?;
+                }
+                HubCommands::Unsilence { id } => hub_admin::remove_silence(&hub, &id, output).await?,
+            }
+        }
     }
 
-    Ok(())
+    Ok(status)
 }
 
 /// Daemon 模式：启动事件总线、状态图、IPC 服务和探针
 #[cfg(unix)]
+#[allow(clippy::too_many_arguments)]
 async fn run_daemon(
     socket_path: Option<PathBuf>,
-    probe_path: Option<PathBuf>,
-    hub_url: Option<String>,
+    probes: ProbeOptions,
+    hub: HubOptions,
+    pods: PodOptions,
+    graph_config: Option<PathBuf>,
+    audit_log: Option<PathBuf>,
+    hub_api: Option<String>,
+    rules: RuleOptions,
+    config_path: Option<PathBuf>,
+    grpc_listen: Option<SocketAddr>,
+    read_only: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    check_grpc_listen(grpc_listen)?;
+    if read_only {
+        println!("[ark] 只读模式：不执行任何修复动作");
+    }
+    let _telemetry = telemetry::init("ark-agent")?;
     println!("[ark] 启动事件总线...");
     
     // 创建事件总线
@@ -229,7 +920,13 @@ async fn run_daemon(
     let tx = bus.sender();
 
     // 创建状态图
-    let graph = Arc::new(StateGraph::new());
+    let graph = Arc::new(StateGraph::with_config(load_graph_config(graph_config)?));
+    let pods = PodResolver::from_options(pods)?;
+    rule_sync::initial_sync(&rules).await?;
+    let rule_engine = load_rule_engine(rules.dir.clone())?;
+    let rule_sync_handle = rule_sync::spawn_refresh(&rules);
+    let (event_stream, _) = tokio::sync::broadcast::channel(EVENT_STREAM_CAPACITY);
+    let health = Arc::new(DaemonHealth::new());
     
     // 创建 Metrics 收集器
     let metrics = Arc::new(MetricsCollector::new()?);
@@ -280,87 +977,166 @@ async fn run_daemon(
         })
     };
 
-    // 启动探针
-    let probe_handle = {
-        let tx = tx.clone();
-        tokio::spawn(async move {
-            if let Some(ref path) = probe_path {
-                // 使用外部探针脚本
-                // 尝试 python3，如果失败则尝试 python（Windows 兼容）
-                let python_cmd = if cfg!(windows) {
-                    "python"
-                } else {
-                    "python3"
-                };
-                
-                let probe = SubprocessProbe::new(
-                    python_cmd.to_string(),
-                    vec![path.to_string_lossy().to_string()],
-                );
-                
-                if let Err(e) = probe.start_stream(tx).await {
-                    eprintln!("[ark] 外部探针异常退出: {}", e);
-                }
-            } else {
-                // 使用内置 dummy_probe（向后兼容）
-                eprintln!("[ark] 警告：使用内置 dummy_probe，建议使用 --probe 指定外部探针脚本");
-                if let Err(e) = event::dummy_probe(tx).await {
-                    eprintln!("[ark] 内置探针异常退出: {}", e);
-                }
-            }
-        })
-    };
+    let config = Arc::new(std::sync::RwLock::new(load_daemon_config(config_path.as_deref())?));
+    let actuators = Arc::new(ActuatorRegistry::new(config.read().unwrap_or_else(|e| e.into_inner()).actuators.clone()));
+    let limiter = Arc::new(ActionLimiter::new(config.read().unwrap_or_else(|e| e.into_inner()).action_cooldown.clone()));
+
+    // 并发启动所有探针
+    let probe_set = Arc::new(std::sync::Mutex::new(ProbeSet::start(probes.specs()?, tx.clone(), &health)));
+
+    let audit_logger = open_audit_logger(audit_log)?;
+
+    // 初始化 Hub 转发器（如果配置了 hub_url）
+    let hub_forwarder = connect_hub_forwarder(hub, hub_api.clone(), audit_logger.clone(), Arc::clone(&limiter), tx.clone(), read_only, &health).await;
 
-    // 启动事件消费和图形更新任务
+    // 启动事件消费和图形更新任务（同时推送到 Hub）
     let graph_handle = {
         let graph = Arc::clone(&graph);
         let metrics = Arc::clone(&metrics);
+        let hub_forwarder = hub_forwarder.map(|f| Arc::new(tokio::sync::RwLock::new(f)));
+        let rule_engine = rule_engine.clone();
+        let event_stream = event_stream.clone();
+        let health = Arc::clone(&health);
+        let config = Arc::clone(&config);
+        let history = HistoryStore::new(history::default_history_path());
+        let tx = tx.clone();
         let mut rx = bus.receiver();
         tokio::spawn(async move {
             loop {
                 match rx.recv().await {
-                    Some(event) => {
+                    Some(mut event) => {
                         // 记录事件处理指标
                         metrics.record_event(&event.event_type);
+                        // rule.matched 事件接在触发它的事件的 trace 之下
+                        let span = tracing::info_span!("event.ingest", event_type = %event.event_type, entity_id = %event.entity_id);
+                        telemetry::set_parent(&span, event.traceparent.as_deref());
                         
-                        if let Err(e) = graph.process_event(&event).await {
-                            eprintln!("[ark] 处理事件失败: {}", e);
+                        if config::log_enabled(LogLevel::Debug) {
+                            println!("[ark] 事件: {} {}={}", event.event_type, event.entity_id, event.value);
                         }
-                    }
-                    None => {
-                        eprintln!("[ark] 事件通道已关闭");
-                        break;
-                    }
-                }
-            }
-        })
+                        // 按 PID 关联所属 Pod
+                        if let Some(ref pods) = pods {
+                            pods.annotate(&mut event).await;
+                        }
+                        if let Err(e) = graph.process_event(&event).instrument(tracing::info_span!(parent: &span, "graph.update")).await {
+                            if config::log_enabled(LogLevel::Warn) {
+                                eprintln!("[ark] 处理事件失败: {}", e);
+                            }
+                        }
+
+                        health.record_event();
+
+                        // 广播给 `ark events` 订阅者（没有订阅者时发送失败，忽略）
+                        let _ = event_stream.send(event.clone());
+
+                        // 流式规则匹配，命中时发出 rule.matched 事件
+                        if let Some(ref engine) = rule_engine {
+                            emit_rule_matches(engine, &graph, &event, &tx, &history)
+                                .instrument(tracing::info_span!(parent: &span, "rule.match"))
+                                .await;
+                        }
+                        
+                        // 推送到 Hub（如果配置了且事件需要推送）
+                        if let Some(ref forwarder_arc) = hub_forwarder {
+                            event.traceparent = telemetry::traceparent(&span);
+                            forward_to_hub(&*forwarder_arc.read().await, &config, &event, &health).await;
+                        }
+                    }
+                    None => {
+                        eprintln!("[ark] 事件通道已关闭");
+                        break;
+                    }
+                }
+            }
+        })
+    };
+
+    // SIGHUP 时重新加载规则、探针和动态配置
+    let reload_handle = {
+        let rule_engine = rule_engine.clone();
+        let probe_set = Arc::clone(&probe_set);
+        let config = Arc::clone(&config);
+        let actuators = Arc::clone(&actuators);
+        let limiter = Arc::clone(&limiter);
+        let mut hangup = daemon::hangup_signal()?;
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                println!("[ark] 收到 SIGHUP，重新加载配置...");
+                daemon::notify_reloading();
+                reload_daemon(rule_engine.as_deref(), &probes, &probe_set, config_path.as_deref(), &config, &actuators, &limiter);
+                daemon::notify_ready();
+            }
+        })
     };
 
     // 启动 IPC 服务器（在后台任务中运行）
     let socket_path = socket_path.unwrap_or_else(default_socket_path);
     let socket_path_clone = socket_path.clone();
+    // 共享 token 写入 Socket 旁的 .token 文件（0600），无法写入时只接受 SO_PEERCRED 认证
+    let auth_token = match ipc_auth::load_or_create_token(&ipc_auth::token_path(&socket_path)) {
+        Ok(token) => Some(token),
+        Err(e) => {
+            eprintln!("[ark] 警告：{}，IPC token 认证已禁用", e);
+            None
+        }
+    };
+    
+    if grpc_listen.is_some() && auth_token.is_none() {
+        eprintln!("[ark] 警告：IPC token 不可用，gRPC 调用将全部被拒绝");
+    }
     
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+    let server = Arc::new(
+        IpcServer::new(Arc::clone(&graph), Some(socket_path_clone))
+            .with_audit_logger(audit_logger)
+            .with_rule_engine(rule_engine)
+            .with_event_stream(event_stream)
+            .with_health(health)
+            .with_metrics(metrics)
+            .with_hub_api(hub_api)
+            .with_actuators(Arc::clone(&actuators))
+            .with_action_limiter(Arc::clone(&limiter))
+            .with_event_sink(tx.clone())
+            .with_read_only(read_only)
+            .with_auth_token(auth_token)
+            .with_ready(ready_tx),
+    );
     let ipc_handle = {
-        let graph = Arc::clone(&graph);
+        let server = Arc::clone(&server);
         tokio::spawn(async move {
-            let server = IpcServer::new(graph, Some(socket_path_clone));
             if let Err(e) = server.serve().await {
                 eprintln!("[ark] IPC 服务器异常退出: {}", e);
             }
         })
     };
+    #[cfg(feature = "grpc")]
+    let grpc_handle = grpc_listen.map(|addr| spawn_grpc(&server, addr));
 
     println!("[ark] 探针已启动，状态图已初始化");
     println!("[ark] IPC 服务器已启动，监听 Unix Socket: {}", socket_path.display());
     println!("[ark] 按 Ctrl+C 退出\n");
 
+    // IPC 开始监听后才算就绪（监听失败时 sender 被丢弃，不发送就绪通知）
+    if ready_rx.await.is_ok() {
+        daemon::notify_ready();
+    }
+
     // 等待退出信号
-    tokio::signal::ctrl_c().await?;
+    daemon::shutdown_signal().await?;
+    daemon::notify_stopping();
     println!("\n[ark] 收到退出信号，正在关闭...");
     
-    probe_handle.abort();
+    reload_handle.abort();
+    probe_set.lock().unwrap_or_else(|e| e.into_inner()).abort_all();
     graph_handle.abort();
     ipc_handle.abort();
+    #[cfg(feature = "grpc")]
+    if let Some(handle) = grpc_handle {
+        handle.abort();
+    }
+    if let Some(handle) = rule_sync_handle {
+        handle.abort();
+    }
     metrics_server_handle.abort();
     metrics_update_handle.abort();
 
@@ -376,11 +1152,25 @@ async fn run_daemon(
 }
 
 #[cfg(windows)]
+#[allow(clippy::too_many_arguments)]
 async fn run_daemon(
     port: u16,
-    probe_path: Option<PathBuf>,
-    hub_url: Option<String>,
+    probes: ProbeOptions,
+    hub: HubOptions,
+    pods: PodOptions,
+    graph_config: Option<PathBuf>,
+    audit_log: Option<PathBuf>,
+    hub_api: Option<String>,
+    rules: RuleOptions,
+    config_path: Option<PathBuf>,
+    grpc_listen: Option<SocketAddr>,
+    read_only: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    check_grpc_listen(grpc_listen)?;
+    if read_only {
+        println!("[ark] 只读模式：不执行任何修复动作");
+    }
+    let _telemetry = telemetry::init("ark-agent")?;
     println!("[ark] 启动事件总线...");
     
     // 创建事件总线
@@ -388,65 +1178,74 @@ async fn run_daemon(
     let tx = bus.sender();
 
     // 创建状态图
-    let graph = Arc::new(StateGraph::new());
+    let graph = Arc::new(StateGraph::with_config(load_graph_config(graph_config)?));
+    let pods = PodResolver::from_options(pods)?;
+    rule_sync::initial_sync(&rules).await?;
+    let rule_engine = load_rule_engine(rules.dir.clone())?;
+    let rule_sync_handle = rule_sync::spawn_refresh(&rules);
+    let (event_stream, _) = tokio::sync::broadcast::channel(EVENT_STREAM_CAPACITY);
+    let health = Arc::new(DaemonHealth::new());
 
-    // 启动探针
-    let probe_handle = {
-        let tx = tx.clone();
-        tokio::spawn(async move {
-            if let Some(ref path) = probe_path {
-                let probe = SubprocessProbe::new(
-                    "python".to_string(),
-                    vec![path.to_string_lossy().to_string()],
-                );
-                
-                if let Err(e) = probe.start_stream(tx).await {
-                    eprintln!("[ark] 外部探针异常退出: {}", e);
-                }
-            } else {
-                eprintln!("[ark] 警告：使用内置 dummy_probe，建议使用 --probe 指定外部探针脚本");
-                if let Err(e) = event::dummy_probe(tx).await {
-                    eprintln!("[ark] 内置探针异常退出: {}", e);
-                }
-            }
-        })
-    };
+    let config = Arc::new(std::sync::RwLock::new(load_daemon_config(config_path.as_deref())?));
+    let actuators = Arc::new(ActuatorRegistry::new(config.read().unwrap_or_else(|e| e.into_inner()).actuators.clone()));
+    let limiter = Arc::new(ActionLimiter::new(config.read().unwrap_or_else(|e| e.into_inner()).action_cooldown.clone()));
+
+    // 并发启动所有探针
+    let probe_set = Arc::new(std::sync::Mutex::new(ProbeSet::start(probes.specs()?, tx.clone(), &health)));
+
+    let audit_logger = open_audit_logger(audit_log)?;
 
     // 初始化 Hub 转发器（如果配置了 hub_url）
-    let mut hub_forwarder: Option<HubForwarder> = None;
-    if let Some(ref url) = hub_url {
-        let node_id = get_node_id();
-        let mut forwarder = HubForwarder::new(url.clone(), node_id.clone());
-        if let Err(e) = forwarder.connect().await {
-            eprintln!("[ark] 警告：无法连接到 Hub {}: {}，将继续运行但不推送事件", url, e);
-        } else {
-            hub_forwarder = Some(forwarder);
-            println!("[ark] Hub 转发器已启动，节点ID: {}", node_id);
-        }
-    }
+    let hub_forwarder = connect_hub_forwarder(hub, hub_api.clone(), audit_logger.clone(), Arc::clone(&limiter), tx.clone(), read_only, &health).await;
 
     // 启动事件消费和图形更新任务（同时推送到 Hub）
     let graph_handle = {
         let graph = Arc::clone(&graph);
         let hub_forwarder = hub_forwarder.map(|f| Arc::new(tokio::sync::RwLock::new(f)));
+        let rule_engine = rule_engine.clone();
+        let event_stream = event_stream.clone();
+        let health = Arc::clone(&health);
+        let config = Arc::clone(&config);
+        let history = HistoryStore::new(history::default_history_path());
+        let tx = tx.clone();
         let mut rx = bus.receiver();
         tokio::spawn(async move {
             loop {
                 match rx.recv().await {
-                    Some(event) => {
+                    Some(mut event) => {
+                        // rule.matched 事件接在触发它的事件的 trace 之下
+                        let span = tracing::info_span!("event.ingest", event_type = %event.event_type, entity_id = %event.entity_id);
+                        telemetry::set_parent(&span, event.traceparent.as_deref());
                         // 更新本地图
-                        if let Err(e) = graph.process_event(&event).await {
-                            eprintln!("[ark] 处理事件失败: {}", e);
+                        if config::log_enabled(LogLevel::Debug) {
+                            println!("[ark] 事件: {} {}={}", event.event_type, event.entity_id, event.value);
+                        }
+                        // 按 PID 关联所属 Pod
+                        if let Some(ref pods) = pods {
+                            pods.annotate(&mut event).await;
+                        }
+                        if let Err(e) = graph.process_event(&event).instrument(tracing::info_span!(parent: &span, "graph.update")).await {
+                            if config::log_enabled(LogLevel::Warn) {
+                                eprintln!("[ark] 处理事件失败: {}", e);
+                            }
+                        }
+
+                        health.record_event();
+
+                        // 广播给 `ark events` 订阅者（没有订阅者时发送失败，忽略）
+                        let _ = event_stream.send(event.clone());
+
+                        // 流式规则匹配，命中时发出 rule.matched 事件
+                        if let Some(ref engine) = rule_engine {
+                            emit_rule_matches(engine, &graph, &event, &tx, &history)
+                                .instrument(tracing::info_span!(parent: &span, "rule.match"))
+                                .await;
                         }
                         
                         // 推送到 Hub（如果配置了且事件需要推送）
                         if let Some(ref forwarder_arc) = hub_forwarder {
-                            let forwarder = forwarder_arc.read().await;
-                            if forwarder.should_forward(&event).await {
-                                if let Err(e) = forwarder.forward_event(event.clone()).await {
-                                    eprintln!("[ark] 推送事件到 Hub 失败: {}", e);
-                                }
-                            }
+                            event.traceparent = telemetry::traceparent(&span);
+                            forward_to_hub(&*forwarder_arc.read().await, &config, &event, &health).await;
                         }
                     }
                     None => {
@@ -458,117 +1257,958 @@ async fn run_daemon(
         })
     };
 
+    let auth_token = match ipc_auth::load_or_create_token(&ipc_auth::token_path()) {
+        Ok(token) => Some(token),
+        Err(e) => {
+            eprintln!("[ark] 警告：{}，IPC token 认证已禁用", e);
+            None
+        }
+    };
+
+    if grpc_listen.is_some() && auth_token.is_none() {
+        eprintln!("[ark] 警告：IPC token 不可用，gRPC 调用将全部被拒绝");
+    }
+
     // 启动 IPC 服务器（在后台任务中运行）
+    let server = Arc::new(
+        IpcServer::new(Arc::clone(&graph), port)
+            .with_audit_logger(audit_logger)
+            .with_rule_engine(rule_engine)
+            .with_event_stream(event_stream)
+            .with_health(health)
+            .with_hub_api(hub_api)
+            .with_actuators(Arc::clone(&actuators))
+            .with_action_limiter(Arc::clone(&limiter))
+            .with_event_sink(tx.clone())
+            .with_read_only(read_only)
+            .with_auth_token(auth_token),
+    );
     let ipc_handle = {
-        let graph = Arc::clone(&graph);
+        let server = Arc::clone(&server);
         tokio::spawn(async move {
-            let server = IpcServer::new(graph, port);
             if let Err(e) = server.serve().await {
                 eprintln!("[ark] IPC 服务器异常退出: {}", e);
             }
         })
     };
+    #[cfg(feature = "grpc")]
+    let grpc_handle = grpc_listen.map(|addr| spawn_grpc(&server, addr));
 
     println!("[ark] 探针已启动，状态图已初始化");
-    println!("[ark] IPC 服务器已启动，监听端口 {}", port);
+    println!("[ark] IPC 服务器已启动，监听命名管道: {}", ipc::pipe_name(port));
     println!("[ark] 按 Ctrl+C 退出\n");
 
     // 等待退出信号
     tokio::signal::ctrl_c().await?;
     println!("\n[ark] 收到退出信号，正在关闭...");
     
-    probe_handle.abort();
+    probe_set.lock().unwrap_or_else(|e| e.into_inner()).abort_all();
     graph_handle.abort();
     ipc_handle.abort();
+    #[cfg(feature = "grpc")]
+    if let Some(handle) = grpc_handle {
+        handle.abort();
+    }
+    if let Some(handle) = rule_sync_handle {
+        handle.abort();
+    }
 
     println!("[ark] 退出完成");
     Ok(())
 }
 
-/// 查询进程列表（通过 IPC）
+/// `--grpc-listen` 需要以 grpc feature 构建
+fn check_grpc_listen(grpc_listen: Option<SocketAddr>) -> Result<(), String> {
+    if cfg!(feature = "grpc") || grpc_listen.is_none() {
+        Ok(())
+    } else {
+        Err("当前构建未启用 gRPC，请以 --features grpc 重新编译 ark".to_string())
+    }
+}
+
+/// 在后台启动 gRPC 控制面，与 IPC 共用同一个服务器的状态和配置
+#[cfg(feature = "grpc")]
+fn spawn_grpc(server: &Arc<IpcServer>, addr: SocketAddr) -> tokio::task::JoinHandle<()> {
+    let server = Arc::clone(server);
+    tokio::spawn(async move {
+        if let Err(e) = grpc::serve(addr, server).await {
+            eprintln!("[ark] gRPC 服务异常退出: {}", e);
+        }
+    })
+}
+
+/// 加载状态图配置（未指定时使用默认配置）
+fn load_graph_config(path: Option<PathBuf>) -> Result<GraphConfig, Box<dyn std::error::Error>> {
+    match path {
+        Some(path) => {
+            let config = GraphConfig::load_from_file(&path)?;
+            println!("[ark] 已加载状态图配置: {}", path.display());
+            Ok(config)
+        }
+        None => Ok(GraphConfig::default()),
+    }
+}
+
+/// 组合 daemon 的规则配置
+fn rule_options(
+    dir: Option<PathBuf>,
+    source: Option<String>,
+    refresh_secs: u64,
+    hub_api: Option<&str>,
+) -> Result<RuleOptions, String> {
+    Ok(RuleOptions {
+        dir,
+        source: RuleSource::parse(source.as_deref(), hub_api)?,
+        refresh: std::time::Duration::from_secs(refresh_secs.max(1)),
+        refresh_now: Arc::new(tokio::sync::Notify::new()),
+    })
+}
+
+/// 加载规则目录并开始监听（未指定目录时返回 None）
+fn load_rule_engine(
+    path: Option<PathBuf>,
+) -> Result<Option<Arc<ReloadableRuleEngine>>, Box<dyn std::error::Error>> {
+    match path {
+        Some(path) => {
+            let engine = ReloadableRuleEngine::load(&path)?;
+            engine.watch()?;
+            println!(
+                "[ark] 已加载 {} 条规则并监听变化: {}",
+                engine.current().rules().len(),
+                path.display()
+            );
+            Ok(Some(engine))
+        }
+        None => Ok(None),
+    }
+}
+
+/// 用 daemon 的规则集流式匹配刚处理的事件，把需要告警的命中作为 rule.matched 事件放回总线
+/// （随后与普通事件一样进入状态图历史并推送到 Hub），同时写入本地事故历史
+///
+/// 消费者自己向同一总线发送，用 try_send 避免通道满时阻塞消费循环
+async fn emit_rule_matches(
+    engine: &ReloadableRuleEngine,
+    graph: &StateGraph,
+    event: &Event,
+    tx: &tokio::sync::mpsc::Sender<Event>,
+    history: &HistoryStore,
+) {
+    for m in engine.on_event(graph, event).await {
+        if config::log_enabled(LogLevel::Info) {
+            println!("[rules] 规则命中: {} ({})", m.rule.name, m.entities.join(", "));
+        }
+        history.record(
+            HistoryRecord::new(HistoryKind::RuleMatch, m.rule.name.clone())
+                .with_target(event.pid, event.job_id.clone())
+                .with_scene(Some(m.rule.scene.clone()))
+                .with_detail(serde_json::json!({ "entities": m.entities, "trigger": event })),
+        );
+        let mut matched = Event::new(EventType::RuleMatched, m.rule.name, m.entities.join(","), None, None);
+        matched.node_id = event.node_id.clone();
+        matched.traceparent = telemetry::traceparent(&tracing::Span::current());
+        if let Err(e) = tx.try_send(matched) {
+            eprintln!("[rules] 发送 rule.matched 事件失败: {}", e);
+        }
+    }
+}
+
+/// 连接 Hub 转发器；Hub 下发的高危命令按 hub_api 校验审批并写入审计日志，重复命令受动作冷却限制，
+/// 执行结果作为 action.exec 事件发回总线，只读模式下不执行。连接断开后自动重连，期间事件进入缓冲区
+async fn connect_hub_forwarder(
+    hub: HubOptions,
+    hub_api: Option<String>,
+    audit_logger: Option<Arc<audit::AuditLogger>>,
+    limiter: Arc<ActionLimiter>,
+    events: tokio::sync::mpsc::Sender<Event>,
+    read_only: bool,
+    health: &Arc<DaemonHealth>,
+) -> Option<HubForwarder> {
+    let url = hub.url?;
+    let node_id = get_node_id();
+    let mut forwarder = HubForwarder::new(url.clone(), node_id.clone())
+        .with_command_guard(hub_api, audit_logger, Some(limiter))
+        .with_event_sink(events)
+        .with_read_only(read_only)
+        .with_buffer(hub.buffer, hub.spool)
+        .with_labels(hub.labels)
+        .with_tls(hub.tls)
+        .with_rules_refresh(hub.rules_refresh)
+        .with_self_test(hub.self_test)
+        .with_health(Arc::clone(health));
+    if let Err(e) = forwarder.connect().await {
+        eprintln!("[ark] 警告：无法启动 Hub 转发器 {}: {}，将继续运行但不推送事件", url, e);
+        health.hub_connected(&url, Err(e.to_string()));
+        return None;
+    }
+    println!("[ark] Hub 转发器已启动，节点ID: {}", node_id);
+    Some(forwarder)
+}
+
+/// 汇总命令行和配置文件中的探针（--probe 脚本、--native-probe、--probe-config）
+fn probe_options(scripts: Vec<PathBuf>, natives: Vec<ProbeType>, config: Option<PathBuf>) -> ProbeOptions {
+    let mut cli: Vec<ProbeSpec> = scripts.into_iter().map(ProbeSpec::script).collect();
+    cli.extend(natives.into_iter().map(ProbeSpec::native));
+    ProbeOptions { cli, config }
+}
+
+/// 加载动态配置（未指定时使用默认配置）并使其生效
+fn load_daemon_config(path: Option<&std::path::Path>) -> Result<DaemonConfig, String> {
+    let config = match path {
+        Some(path) => {
+            let config = DaemonConfig::load(path)?;
+            println!("[ark] 已加载动态配置: {}", path.display());
+            config
+        }
+        None => DaemonConfig::default(),
+    };
+    config.apply();
+    Ok(config)
+}
+
+/// 按动态配置的推送过滤和边缘折叠逻辑推送事件到 Hub
+async fn forward_to_hub(
+    forwarder: &HubForwarder,
+    config: &std::sync::RwLock<DaemonConfig>,
+    event: &Event,
+    health: &DaemonHealth,
+) {
+    // Hub 流控优先于推送配置
+    if forwarder.suppressed(event) {
+        return;
+    }
+    let decision = config.read().unwrap_or_else(|e| e.into_inner()).forward.decide(&event.event_type);
+    let forward = match decision {
+        Some(forward) => forward,
+        None => forwarder.should_forward(event).await,
+    };
+    if !forward {
+        return;
+    }
+    // 连接断开时事件进入缓冲区，由转发器记录连接状态，这里不逐条报错
+    match forwarder.forward_event(event.clone()).await {
+        Ok(Delivery::Sent) => health.hub_forwarded(Ok(())),
+        Ok(Delivery::Buffered) => {}
+        Err(e) => {
+            if config::log_enabled(LogLevel::Warn) {
+                eprintln!("[ark] 推送事件到 Hub 失败: {}", e);
+            }
+            health.hub_forwarded(Err(e.to_string()));
+        }
+    }
+}
+
+/// SIGHUP：重新加载规则、探针和动态配置（含外部执行器和动作冷却）；状态图、IPC 连接和 Hub 连接保持不变
+///
+/// 任一部分加载失败时该部分保持原样，其余部分照常生效
 #[cfg(unix)]
-async fn query_processes(socket_path: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
-    let client = IpcClient::new(socket_path);
-    
-    // 检查 daemon 是否运行
+fn reload_daemon(
+    rule_engine: Option<&ReloadableRuleEngine>,
+    probes: &ProbeOptions,
+    probe_set: &std::sync::Mutex<ProbeSet>,
+    config_path: Option<&std::path::Path>,
+    config: &std::sync::RwLock<DaemonConfig>,
+    actuators: &ActuatorRegistry,
+    limiter: &ActionLimiter,
+) {
+    if let Some(engine) = rule_engine {
+        match engine.reload() {
+            Ok(generation) => println!("[ark] 规则已重新加载（第 {} 代）", generation),
+            Err(e) => eprintln!("[ark] 规则重新加载失败，继续使用旧规则: {}", e),
+        }
+    }
+
+    match probes.specs() {
+        Ok(specs) => probe_set.lock().unwrap_or_else(|e| e.into_inner()).update(specs),
+        Err(e) => eprintln!("[ark] 探针配置重新加载失败，保持当前探针: {}", e),
+    }
+
+    if let Some(path) = config_path {
+        match load_daemon_config(Some(path)) {
+            Ok(new_config) => {
+                actuators.replace(new_config.actuators.clone());
+                limiter.update(new_config.action_cooldown.clone());
+                *config.write().unwrap_or_else(|e| e.into_inner()) = new_config;
+            }
+            Err(e) => eprintln!("[ark] 动态配置重新加载失败，保持当前配置: {}", e),
+        }
+    }
+}
+
+/// 打开审计日志（未指定路径时返回 None）
+fn open_audit_logger(
+    path: Option<PathBuf>,
+) -> Result<Option<Arc<audit::AuditLogger>>, Box<dyn std::error::Error>> {
+    match path {
+        Some(path) => {
+            let key = audit::audit_key_from_env();
+            let signed = if key.is_some() { "，HMAC 签名" } else { "" };
+            let logger = audit::AuditLogger::new(path.clone(), 100)?.with_key(key); // 100MB 最大大小
+            println!("[ark] 审计日志: {}（哈希链{}）", path.display(), signed);
+            Ok(Some(Arc::new(logger)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// 生成 man 手册
+fn generate_man(out_dir: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let command = Cli::command();
+    match out_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(&dir)?;
+            clap_mangen::generate_to(command, &dir)?;
+            println!("[ark] man 手册已生成: {}", dir.display());
+        }
+        None => clap_mangen::Man::new(command).render(&mut std::io::stdout())?,
+    }
+    Ok(())
+}
+
+/// 规则命令：离线操作规则文件，不需要 daemon
+/// 按时间顺序打印事故历史
+fn show_history(path: &Path, filter: &HistoryFilter, limit: usize, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    use colored::*;
+
+    let records = history::load(path, filter, limit)?;
+    if output.is_structured() {
+        output.print(&records)?;
+        return Ok(());
+    }
+    if records.is_empty() {
+        println!("{}", format!("没有历史记录（{}）", path.display()).bright_yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{:<20} | {:<10} | {:>7} | {:<16} | {:<18} | {}",
+        "TIME".bright_cyan(),
+        "KIND".bright_cyan(),
+        "PID".bright_cyan(),
+        "JOB".bright_cyan(),
+        "SCENE".bright_cyan(),
+        "SUMMARY".bright_cyan()
+    );
+    println!("{}", "-".repeat(110));
+    for record in &records {
+        let time = chrono::DateTime::parse_from_rfc3339(&record.ts)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|_| record.ts.clone());
+        let kind = match record.kind {
+            HistoryKind::RuleMatch => record.kind.as_str().bright_yellow(),
+            HistoryKind::Diagnosis => record.kind.as_str().bright_blue(),
+            HistoryKind::LlmCall => record.kind.as_str().bright_magenta(),
+            HistoryKind::Fix => record.kind.as_str().bright_green(),
+        };
+        println!(
+            "{:<20} | {:<10} | {:>7} | {:<16} | {:<18} | {}",
+            time,
+            kind,
+            record.pid.map(|pid| pid.to_string()).unwrap_or_else(|| "-".to_string()),
+            record.job_id.as_deref().unwrap_or("-"),
+            record.scene.as_deref().unwrap_or("-"),
+            record.summary
+        );
+    }
+    Ok(())
+}
+
+async fn run_probe_command(command: ProbeCommands, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    use colored::*;
+    use probe::validate::validate_probe;
+    use probe::ProbeKind;
+
+    match command {
+        ProbeCommands::Validate { target, args, duration, max_issues } => {
+            let spec = if target.extension().is_some_and(|ext| ext == "py") && args.is_empty() {
+                ProbeSpec::script(target)
+            } else {
+                ProbeSpec {
+                    name: None,
+                    kind: ProbeKind::Command { command: target.to_string_lossy().into_owned(), args },
+                }
+            };
+            if !output.is_structured() {
+                eprintln!("运行探针 {} 秒...", duration);
+            }
+            let report = validate_probe(&spec, std::time::Duration::from_secs(duration), max_issues).await?;
+
+            if output.is_structured() {
+                output.print(&report)?;
+            } else {
+                println!("{} {}", "探针:".bright_cyan(), report.command);
+                println!(
+                    "{} {} 行，{} 个有效事件，{:.1} 事件/秒（{:.1} 秒）",
+                    "输出:".bright_cyan(),
+                    report.lines,
+                    report.events,
+                    report.events_per_sec,
+                    report.duration_secs
+                );
+                for (event_type, count) in &report.event_types {
+                    println!("  {:<20} {:>6}  {:.1}/s", event_type, count, *count as f64 / report.duration_secs.max(f64::EPSILON));
+                }
+                if let Some(code) = report.exited {
+                    println!("{}", format!("探针在校验期间退出（退出码 {}）", code).bright_yellow());
+                }
+                for issue in &report.issues {
+                    eprintln!("{} 第 {} 行 [{}] {}", "✗".bright_red(), issue.line, issue.kind.as_str(), issue.message);
+                    eprintln!("    {}", issue.content.dimmed());
+                }
+            }
+
+            if !report.issue_counts.is_empty() {
+                let summary: Vec<String> = report.issue_counts.iter().map(|(kind, n)| format!("{} {}", kind, n)).collect();
+                return Err(format!("探针输出存在问题: {}", summary.join(", ")).into());
+            }
+            if report.events == 0 {
+                return Err("校验期间探针没有输出任何事件".into());
+            }
+            if !output.is_structured() {
+                println!("{}", "✓ 探针输出全部通过校验".bright_green());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_audit_command(command: AuditCommands, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    use colored::*;
+
+    match command {
+        AuditCommands::Verify { file } => {
+            let key = audit::audit_key_from_env();
+            let report = audit::verify_file(&file, key.as_deref())?;
+
+            if output.is_structured() {
+                output.print(&report)?;
+            } else {
+                let range = match (report.first_seq, report.last_seq) {
+                    (Some(first), Some(last)) => format!("，序号 {}..={}", first, last),
+                    _ => String::new(),
+                };
+                println!("{} {}（{} 条记录{}）", "审计日志:".bright_cyan(), report.file, report.entries, range);
+                if report.unchained > 0 {
+                    println!("{}", format!("{} 条旧格式记录没有哈希链，无法校验", report.unchained).bright_yellow());
+                }
+                if !report.hmac_checked {
+                    println!("{}", format!("未设置 {}，只校验哈希链，不校验 HMAC", audit::AUDIT_KEY_ENV).bright_yellow());
+                }
+                for warning in &report.warnings {
+                    println!("{} {}", "!".bright_yellow(), warning);
+                }
+                for problem in &report.problems {
+                    eprintln!("{} {}", "✗".bright_red(), problem);
+                }
+                if let Some(ref hash) = report.last_hash {
+                    println!("最后一条记录的哈希: {}（留存后可用于发现尾部截断）", hash);
+                }
+            }
+
+            if !report.problems.is_empty() {
+                return Err(format!("审计日志完整性校验失败: {} 个问题", report.problems.len()).into());
+            }
+            if !output.is_structured() {
+                println!("{}", "✓ 审计日志完整性校验通过".bright_green());
+            }
+        }
+        AuditCommands::Search { file, since, until, pid, job, action, result, limit } => {
+            let filter = audit::AuditFilter { since, until, pid, job_id: job, action, result };
+            let entries = audit::search(&file, &filter, limit)?;
+            if output.is_structured() {
+                output.print(&entries)?;
+                return Ok(());
+            }
+            if entries.is_empty() {
+                println!("{}", "没有匹配的审计记录".bright_yellow());
+                return Ok(());
+            }
+
+            println!(
+                "{:<20} | {:<12} | {:<12} | {:>7} | {:<16} | {:<18} | {}",
+                "TIME".bright_cyan(),
+                "USER".bright_cyan(),
+                "ACTION".bright_cyan(),
+                "PID".bright_cyan(),
+                "JOB".bright_cyan(),
+                "RESULT".bright_cyan(),
+                "DETAILS".bright_cyan()
+            );
+            println!("{}", "-".repeat(120));
+            for entry in &entries {
+                let time = chrono::DateTime::parse_from_rfc3339(&entry.timestamp)
+                    .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_else(|_| entry.timestamp.clone());
+                let result = match entry.result.as_str() {
+                    "success" => entry.result.bright_green(),
+                    "partial_failure" | "timeout" => entry.result.bright_yellow(),
+                    _ => entry.result.bright_red(),
+                };
+                let mut details = entry.details.clone();
+                if let Some(ref command_id) = entry.command_id {
+                    details = format!("command_id={}; {}", command_id, details);
+                }
+                println!(
+                    "{:<20} | {:<12} | {:<12} | {:>7} | {:<16} | {:<18} | {}",
+                    time,
+                    entry.user,
+                    entry.action,
+                    entry.target_pid,
+                    entry.target_job_id.as_deref().unwrap_or("-"),
+                    result,
+                    details
+                );
+            }
+            println!();
+            println!("共 {} 条记录", entries.len());
+        }
+    }
+    Ok(())
+}
+
+async fn run_rules_command(command: RulesCommands) -> Result<(), Box<dyn std::error::Error>> {
+    use ark_core::rules::{load_rule_file, test_rule, validate_dir};
+    use colored::*;
+
+    match command {
+        RulesCommands::List { dir } => {
+            let (rules, errors) = validate_dir(&dir)?;
+            println!(
+                "{:>8} | {:<24} | {:<32} | {}",
+                "PRIORITY".bright_cyan(),
+                "SCENE".bright_cyan(),
+                "NAME".bright_cyan(),
+                "FILE".bright_cyan()
+            );
+            println!("{}", "-".repeat(90));
+            for (path, rule) in &rules {
+                let file = path.file_name().map(|f| f.to_string_lossy()).unwrap_or_default();
+                println!("{:>8} | {:<24} | {:<32} | {}", rule.priority, rule.scene, rule.name, file);
+            }
+            if !errors.is_empty() {
+                eprintln!(
+                    "\n{}",
+                    format!("{} 个规则文件无法加载，运行 ark rules validate 查看详情", errors.len()).bright_yellow()
+                );
+            }
+        }
+        RulesCommands::Validate { dir } => {
+            let (rules, errors) = validate_dir(&dir)?;
+            for error in &errors {
+                eprintln!("{} {}", "✗".bright_red(), error);
+            }
+            if !errors.is_empty() {
+                return Err(format!("{} 个规则文件校验失败（{} 个通过）", errors.len(), rules.len()).into());
+            }
+            println!("{}", format!("✓ {} 个规则文件全部通过校验", rules.len()).bright_green());
+        }
+        RulesCommands::Test { rule, events } => {
+            let rule = load_rule_file(&rule)?;
+            let content = std::fs::read_to_string(&events)
+                .map_err(|e| format!("读取事件文件 {} 失败: {}", events.display(), e))?;
+            let mut sample = Vec::new();
+            for (idx, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let event: Event = serde_json::from_str(line)
+                    .map_err(|e| format!("{}:{}: 事件解析失败: {}", events.display(), idx + 1, e))?;
+                sample.push(event);
+            }
+
+            let report = test_rule(&rule, &sample).await;
+            println!("规则: {}（{} 条样例事件）", rule.name.bright_cyan(), sample.len());
+            for (condition, matched) in &report.conditions {
+                let mark = if *matched { "✓".bright_green() } else { "✗".bright_red() };
+                println!("  {} {}", mark, condition);
+            }
+            if report.matched {
+                println!("{}", "规则命中".bright_green().bold());
+            } else {
+                return Err("规则未命中".into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 对比两个状态图快照并打印差异
+async fn diff_graph_snapshots(before: &PathBuf, after: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    use colored::*;
+
+    let load = |path: &PathBuf| -> Result<StateGraph, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("读取快照 {} 失败: {}", path.display(), e))?;
+        StateGraph::import_json(&content)
+            .map_err(|e| format!("解析快照 {} 失败: {}", path.display(), e).into())
+    };
+    let diff = load(before)?.diff(&load(after)?).await;
+
+    if diff.is_empty() {
+        println!("两个快照没有差异");
+        return Ok(());
+    }
+
+    let resolved = diff.resolved_blocking_edges();
+    if !resolved.is_empty() {
+        println!("{}", "已消失的阻塞边（修复生效）:".bright_green().bold());
+        for edge in &resolved {
+            println!("  ✅ {} -[{}]-> {}", edge.from, edge.edge_type.as_str(), edge.to);
+        }
+        println!();
+    }
+
+    println!("{}", "节点变化:".bright_cyan().bold());
+    for node in &diff.added_nodes {
+        println!("  {} {} ({})", "+".bright_green(), node.id, node.node_type.as_str());
+    }
+    for node in &diff.removed_nodes {
+        println!("  {} {} ({})", "-".bright_red(), node.id, node.node_type.as_str());
+    }
+    for change in &diff.changed_nodes {
+        println!("  {} {}", "~".bright_yellow(), change.after.id);
+        for (key, old, new) in change.changed_metadata() {
+            println!(
+                "      {}: {} -> {}",
+                key,
+                old.as_deref().unwrap_or("-"),
+                new.as_deref().unwrap_or("-")
+            );
+        }
+    }
+
+    println!("{}", "边变化:".bright_cyan().bold());
+    for edge in &diff.added_edges {
+        println!("  {} {} -[{}]-> {}", "+".bright_green(), edge.from, edge.edge_type.as_str(), edge.to);
+    }
+    for edge in &diff.removed_edges {
+        println!("  {} {} -[{}]-> {}", "-".bright_red(), edge.from, edge.edge_type.as_str(), edge.to);
+    }
+    for change in &diff.changed_edges {
+        println!(
+            "  {} {} -[{}]-> {}: 置信度 {:.2} -> {:.2}，佐证 {} -> {} 次",
+            "~".bright_yellow(),
+            change.after.from,
+            change.after.edge_type.as_str(),
+            change.after.to,
+            change.before.confidence,
+            change.after.confidence,
+            change.before.weight,
+            change.after.weight
+        );
+    }
+
+    Ok(())
+}
+
+/// 持续输出 daemon 收到的事件（和图结构变化），直到 Ctrl+C 或 daemon 断开
+async fn tail_events(
+    client: IpcClient,
+    filter: SubscribeFilter,
+    since_secs: Option<u64>,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use colored::*;
+    use ipc::{GraphChange, SubscriptionFrame};
+
+    // 先在本地校验过滤条件，给出更直接的错误提示
+    for t in &filter.event_types {
+        serde_json::from_value::<EventType>(serde_json::json!(t)).map_err(|_| format!("未知的事件类型: {}", t))?;
+    }
+    if let Some(ref pattern) = filter.entity_pattern {
+        ark_core::rules::validate_pattern(pattern)?;
+    }
+
+    // 先回放历史再订阅：两者之间到达的少量事件可能漏掉，但不会重复输出
+    if let Some(secs) = since_secs {
+        let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        let query = ipc::EventQuery {
+            since: Some(now_ms.saturating_sub(secs * 1000)),
+            event_types: filter.event_types.clone(),
+            entity: filter.entity_pattern.clone(),
+            limit: ipc::DEFAULT_EVENT_LIMIT,
+        };
+        for event in client.get_events(query).await? {
+            print_event(&event, json)?;
+        }
+    }
+
+    let mut subscription = client.subscribe(filter).await?;
+    if !json {
+        eprintln!("[ark] 已订阅事件流，按 Ctrl+C 退出");
+    }
+
+    loop {
+        match subscription.next().await? {
+            SubscriptionFrame::Event(event) => print_event(&event, json)?,
+            SubscriptionFrame::GraphChange(change) if json => println!("{}", serde_json::to_string(&change)?),
+            SubscriptionFrame::GraphChange(change) => {
+                let line = match change {
+                    GraphChange::NodeAdded { id, node_type } => format!("{} {} ({})", "+ node".bright_green(), id, node_type),
+                    GraphChange::NodeRemoved { id, node_type } => format!("{} {} ({})", "- node".bright_red(), id, node_type),
+                    GraphChange::EdgeAdded { from, to, edge_type } => {
+                        format!("{} {} -[{}]-> {}", "+ edge".bright_green(), from, edge_type, to)
+                    }
+                    GraphChange::EdgeRemoved { from, to, edge_type } => {
+                        format!("{} {} -[{}]-> {}", "- edge".bright_red(), from, edge_type, to)
+                    }
+                };
+                let ts = chrono::Local::now().format("%H:%M:%S%.3f").to_string();
+                println!("{} {}", ts.dimmed(), line);
+            }
+            SubscriptionFrame::Notice(notice) => eprintln!("[ark] 警告：{}", notice),
+        }
+    }
+}
+
+/// 输出一条事件：JSON 模式每行一个对象，否则为带颜色的单行摘要
+fn print_event(event: &Event, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    use colored::*;
+
+    if json {
+        println!("{}", serde_json::to_string(event)?);
+        return Ok(());
+    }
+    let ts = chrono::DateTime::from_timestamp_millis(event.ts as i64)
+        .map(|t| t.with_timezone(&chrono::Local).format("%H:%M:%S%.3f").to_string())
+        .unwrap_or_else(|| event.ts.to_string());
+    let mut line = format!(
+        "{} {:<16} {:<20} {}",
+        ts.dimmed(),
+        event.event_type.to_string().bright_cyan(),
+        event.entity_id,
+        event.value
+    );
+    if let Some(pid) = event.pid {
+        line.push_str(&format!(" pid={}", pid));
+    }
+    if let Some(ref job_id) = event.job_id {
+        line.push_str(&format!(" job={}", job_id));
+    }
+    println!("{}", line);
+    Ok(())
+}
+
+/// 状态图命令：导出直接执行，运维操作确认后通过 IPC 下发到 daemon
+async fn run_graph_command(
+    command: GraphCommands,
+    client: IpcClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use colored::*;
+    use ipc::RpcRequest;
+    use std::io::{self, Write};
+
+    // diff 只读取本地快照文件，不需要 daemon
+    if let GraphCommands::Diff { before, after } = command {
+        return diff_graph_snapshots(&before, &after).await;
+    }
+
     if !client.ping().await? {
         eprintln!("[ark] 错误：无法连接到 daemon");
         eprintln!("[ark] 请先运行: ark run");
         return Err("daemon 未运行".into());
     }
 
-    // 查询进程列表
-    let processes = client.list_processes().await?;
+    let (description, yes, request) = match command {
+        GraphCommands::Export { format, output } => {
+            let content = client.graph_export(&format).await?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, content)?;
+                    eprintln!("[ark] 状态图已导出到 {}", path.display());
+                }
+                None => print!("{}", content),
+            }
+            return Ok(());
+        }
+        GraphCommands::Diff { .. } => unreachable!("diff 已在连接 daemon 前处理"),
+        GraphCommands::RmNode { node_id, yes } => (
+            format!("删除节点 {} 及其所有关联边", node_id),
+            yes,
+            RpcRequest::GraphRemoveNode { node_id, confirm: true },
+        ),
+        GraphCommands::RmEdge { from, to, edge_type, yes } => (
+            format!(
+                "删除边 {} -[{}]-> {}",
+                from,
+                edge_type.as_deref().unwrap_or("*"),
+                to
+            ),
+            yes,
+            RpcRequest::GraphRemoveEdge { from, to, edge_type, confirm: true },
+        ),
+        GraphCommands::SetMeta { node_id, key, value, yes } => (
+            format!("设置节点 {} 的 {} = {}", node_id, key, value),
+            yes,
+            RpcRequest::GraphSetMeta { node_id, key, value, confirm: true },
+        ),
+    };
+
+    println!("将执行图运维操作: {}", description.bright_yellow());
+    if !yes {
+        print!("{}", "该操作会直接修改 daemon 中的状态图，是否继续? [y/N]: ".bright_yellow());
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        if !input.trim().eq_ignore_ascii_case("y") && !input.trim().eq_ignore_ascii_case("yes") {
+            println!("{}", "已取消".bright_yellow());
+            return Ok(());
+        }
+    }
+
+    let data = client.graph_admin(request).await?;
+    println!("{} {}", "✅ 操作完成:".bright_green(), data);
+
+    Ok(())
+}
+
+/// 打包诊断快照并列出包内容
+async fn create_snapshot(
+    client: IpcClient,
+    options: snapshot::SnapshotOptions,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use colored::*;
+
+    let manifest = snapshot::create_snapshot(&client, &options).await?;
+    if output.is_structured() {
+        output.print(&manifest)?;
+        return Ok(());
+    }
+
+    println!("{} {}", "诊断快照已写入:".bright_green(), manifest.path.display());
+    for file in &manifest.files {
+        println!("  {} {}", "✓".bright_green(), file);
+    }
+    for (file, error) in &manifest.errors {
+        println!("  {} {}: {}", "✗".bright_red(), file, error);
+    }
+    Ok(())
+}
+
+/// 查询 daemon 健康状态；有探针停止或长时间无事件、Hub 断开时退出码为 4
+async fn daemon_status(client: IpcClient, stale_secs: u64, output: OutputFormat) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    use colored::*;
 
-    if processes.is_empty() {
-        println!("没有活跃进程");
-        return Ok(());
+    let report = client.status().await?;
+    let now_ms = report.started_at + report.uptime_secs * 1000;
+    // 探针启动后超过 stale_secs 仍无事件，或最后一次事件已超过 stale_secs
+    let stale = |probe: &health::ProbeHealth| {
+        let last = probe.last_event_ts.unwrap_or(report.started_at);
+        now_ms.saturating_sub(last) > stale_secs * 1000
+    };
+    let healthy = report.probes.iter().all(|p| p.running && !stale(p))
+        && report.hub.as_ref().is_none_or(|hub| hub.connected);
+    let status = if healthy { ExitStatus::Clean } else { ExitStatus::Degraded };
+
+    if output.is_structured() {
+        output.print(&report)?;
+        return Ok(status);
     }
 
-    // 打印表头
-    use colored::*;
+    let started = chrono::DateTime::from_timestamp_millis(report.started_at as i64)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default();
     println!(
-        "{:>8} | {:>12} | {:>20} | {}",
-        "PID".bright_cyan(),
-        "JOB_ID".bright_cyan(),
-        "RESOURCES".bright_cyan(),
-        "STATE".bright_cyan()
+        "{} {}",
+        "daemon:".bright_cyan().bold(),
+        if healthy { "正常".bright_green() } else { "异常".bright_red() }
     );
-    println!("{}", "-".repeat(80));
+    if let Ok(info) = client.server_info().await {
+        let version = if info.protocol_version == 0 { "未知（旧版 daemon）".to_string() } else { info.protocol_version.to_string() };
+        println!("  版本: {}（协议版本 {}）", info.daemon_version, version);
+    }
+    println!(
+        "  运行时长: {}h{:02}m{:02}s（启动于 {}）",
+        report.uptime_secs / 3600,
+        report.uptime_secs / 60 % 60,
+        report.uptime_secs % 60,
+        started
+    );
+    println!("  事件: 共 {}，最近 {:.1}/s", report.events_total, report.events_per_sec);
+    println!("  状态图: {} 个节点，{} 条边", report.graph_nodes, report.graph_edges);
+    if let Some(generation) = report.rules_generation {
+        println!("  规则集代数: {}", generation);
+    }
+    if report.read_only {
+        println!("  模式: {}（不执行修复动作）", "只读".bright_yellow());
+    }
 
-    // 打印每个进程
-    for proc in processes {
-        let pid = proc["pid"].as_u64().unwrap_or(0) as u32;
-        let job_id = proc["job_id"]
-            .as_str()
-            .unwrap_or("-")
-            .to_string();
-        let state = proc["state"]
-            .as_str()
-            .unwrap_or("unknown")
-            .to_string();
-
-        // 从 IPC 响应中获取资源列表
-        let resources: Vec<String> = proc["resources"]
-            .as_array()
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        let resources_str = if resources.is_empty() {
-            "-".to_string()
+    match report.hub {
+        Some(ref hub) if hub.connected => {
+            println!(
+                "  Hub: {} {}（已推送 {} 条，重连 {} 次）",
+                hub.url,
+                "已连接".bright_green(),
+                hub.forwarded,
+                hub.reconnects
+            );
+            if hub.throttled {
+                println!("  Hub 流控: {}（暂停推送利用率类事件）", "slow".bright_yellow());
+            }
+        }
+        Some(ref hub) => println!(
+            "  Hub: {} {}（{}；重连中，已缓冲 {} 条）",
+            hub.url,
+            "未连接".bright_red(),
+            hub.last_error.as_deref().unwrap_or("-"),
+            hub.buffered
+        ),
+        None => println!("  Hub: 未配置"),
+    }
+
+    println!("\n{} ({})", "探针:".bright_cyan().bold(), report.probes.len());
+    for probe in &report.probes {
+        let last = probe
+            .last_event_ts
+            .map(|ts| format!("{}s 前", now_ms.saturating_sub(ts) / 1000))
+            .unwrap_or_else(|| "从未上报".to_string());
+        let state = if !probe.running {
+            format!("已退出{}", probe.error.as_ref().map(|e| format!(": {}", e)).unwrap_or_default()).bright_red()
+        } else if stale(probe) {
+            "无事件".bright_yellow()
         } else {
-            resources.join(", ")
+            "运行中".bright_green()
         };
-
-        println!(
-            "{:>8} | {:>12} | {:>20} | {}",
-            pid.to_string().bright_green(),
-            job_id.bright_yellow(),
-            resources_str.bright_white(),
-            state.bright_blue()
-        );
+        println!("  {:<24} {} | 事件 {} | 最后事件 {}", probe.name, state, probe.events, last);
     }
 
-    Ok(())
+    Ok(status)
 }
 
-#[cfg(windows)]
-async fn query_processes(port: u16) -> Result<(), Box<dyn std::error::Error>> {
-    let client = IpcClient::new(port);
-    
+/// 查询进程列表（通过 IPC）
+async fn query_processes(client: IpcClient, filter: &ProcessFilter, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
     // 检查 daemon 是否运行
     if !client.ping().await? {
-        eprintln!("[ark] 错误：无法连接到 daemon (端口 {})", port);
+        eprintln!("[ark] 错误：无法连接到 daemon");
         eprintln!("[ark] 请先运行: ark run");
         return Err("daemon 未运行".into());
     }
 
     // 查询进程列表
-    let processes = client.list_processes().await?;
+    let processes: Vec<ProcessReport> = client
+        .list_processes(filter)
+        .await?
+        .into_iter()
+        .map(|proc| ProcessReport {
+            pid: proc["pid"].as_u64().unwrap_or(0) as u32,
+            job_id: proc["job_id"].as_str().map(str::to_string),
+            // 从 IPC 响应中获取资源列表
+            resources: proc["resources"]
+                .as_array()
+                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+                .unwrap_or_default(),
+            state: proc["state"].as_str().unwrap_or("unknown").to_string(),
+        })
+        .collect();
+
+    if output.is_structured() {
+        output.print(&serde_json::json!({ "processes": processes }))?;
+        return Ok(());
+    }
 
     if processes.is_empty() {
         println!("没有活跃进程");
@@ -588,52 +2228,28 @@ async fn query_processes(port: u16) -> Result<(), Box<dyn std::error::Error>> {
 
     // 打印每个进程
     for proc in processes {
-        let pid = proc["pid"].as_u64().unwrap_or(0) as u32;
-        let job_id = proc["job_id"]
-            .as_str()
-            .unwrap_or("-")
-            .to_string();
-        let state = proc["state"]
-            .as_str()
-            .unwrap_or("unknown")
-            .to_string();
-
-        // 从 IPC 响应中获取资源列表
-        let resources: Vec<String> = proc["resources"]
-            .as_array()
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        let resources_str = if resources.is_empty() {
+        let resources_str = if proc.resources.is_empty() {
             "-".to_string()
         } else {
-            resources.join(", ")
+            proc.resources.join(", ")
         };
 
         println!(
             "{:>8} | {:>12} | {:>20} | {}",
-            pid.to_string().bright_green(),
-            job_id.bright_yellow(),
+            proc.pid.to_string().bright_green(),
+            proc.job_id.as_deref().unwrap_or("-").bright_yellow(),
             resources_str.bright_white(),
-            state.bright_blue()
+            proc.state.bright_blue()
         );
     }
 
     Ok(())
 }
 
-/// 查询进程阻塞根因（通过 IPC）
-#[cfg(unix)]
-async fn query_why(pid: u32, socket_path: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+/// 查询进程阻塞根因（通过 IPC），发现根因时退出码为 2
+async fn query_why(pid: u32, client: IpcClient, output: OutputFormat) -> Result<ExitStatus, Box<dyn std::error::Error>> {
     use colored::*;
-    use crate::ipc::IpcClient;
-    
-    let client = IpcClient::new(socket_path);
-    
+
     // 检查 daemon 是否运行
     if !client.ping().await? {
         eprintln!("[ark] 错误：无法连接到 daemon");
@@ -643,16 +2259,26 @@ async fn query_why(pid: u32, socket_path: Option<PathBuf>) -> Result<(), Box<dyn
 
     // 查询根因
     let causes = client.why_process(pid).await?;
+    let status = ExitStatus::from_causes(&causes);
+
+    // 用进程在状态图中的邻域识别场景，取图失败时退回根因文本匹配
+    let scene = match analyze_process_scene(&client, pid).await {
+        Some((scene, _)) => Some(scene),
+        None => identify_scene_from_causes(&causes),
+    };
+    let scene_hint = scene.as_ref().map(|scene| scene.as_str());
+
+    if output.is_structured() {
+        output.print(&WhyReport { pid, scene: scene_hint.map(str::to_string), causes })?;
+        return Ok(status);
+    }
 
-    // 尝试场景识别和分析（需要访问图状态，当前通过 IPC 无法直接访问）
-    // 这里先使用基本的根因分析，场景分析功能可以在未来扩展 IPC 接口后启用
-    
     if causes.is_empty() {
         println!(
             "进程 {} 未发现阻塞问题",
             pid.to_string().bright_green()
         );
-        return Ok(());
+        return Ok(status);
     }
 
     println!(
@@ -661,17 +2287,6 @@ async fn query_why(pid: u32, socket_path: Option<PathBuf>) -> Result<(), Box<dyn
     );
     println!("{}", "-".repeat(60));
 
-    // 尝试识别场景类型（基于根因文本）
-    let scene_hint = if causes.iter().any(|c| c.contains("GPU") || c.contains("OOM") || c.contains("显存")) {
-        Some("GPU OOM")
-    } else if causes.iter().any(|c| c.contains("网络") || c.contains("network") || c.contains("等待资源")) {
-        Some("网络阻塞")
-    } else if causes.iter().any(|c| c.contains("exit") || c.contains("crash") || c.contains("failed")) {
-        Some("进程崩溃")
-    } else {
-        None
-    };
-
     if let Some(scene) = scene_hint {
         println!("  [场景识别] {}", scene.bright_cyan());
         println!();
@@ -687,15 +2302,153 @@ async fn query_why(pid: u32, socket_path: Option<PathBuf>) -> Result<(), Box<dyn
         }
     }
 
-    Ok(())
+    Ok(status)
+}
+
+/// `why --follow` 两次分析之间的最短间隔（事件密集时避免反复查询）
+const FOLLOW_MIN_GAP: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// 持续跟踪进程根因：按间隔或在 daemon 推送新事件时重新分析，只输出变化，Ctrl+C 退出
+///
+/// 退出码与最后一次分析结果一致
+async fn follow_why(
+    pid: u32,
+    client: IpcClient,
+    interval: u64,
+    output: OutputFormat,
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    use colored::*;
+    use ipc::SubscriptionFrame;
+
+    if !client.ping().await? {
+        return Err("无法连接到 daemon，请先运行: ark run".into());
+    }
+
+    // 事件订阅放在独立任务中读取（读帧不能被 select! 中途取消），只用来提前触发重新分析；
+    // 订阅失败时退化为按间隔轮询
+    let changed = Arc::new(tokio::sync::Notify::new());
+    let subscription_handle = match client.subscribe(SubscribeFilter::default()).await {
+        Ok(mut subscription) => {
+            let changed = Arc::clone(&changed);
+            Some(tokio::spawn(async move {
+                while let Ok(frame) = subscription.next().await {
+                    if let SubscriptionFrame::Event(_) = frame {
+                        changed.notify_one();
+                    }
+                }
+            }))
+        }
+        Err(e) => {
+            eprintln!("[ark] 订阅事件流失败，按 {} 秒间隔轮询: {}", interval, e);
+            None
+        }
+    };
+
+    if !output.is_structured() {
+        eprintln!("[ark] 跟踪进程 {} 的阻塞根因，按 Ctrl+C 退出", pid);
+    }
+
+    let interval = std::time::Duration::from_secs(interval.max(1));
+    let mut previous: Option<Vec<String>> = None;
+    let mut status = ExitStatus::Clean;
+    loop {
+        match client.why_process(pid).await {
+            Ok(causes) => {
+                status = ExitStatus::from_causes(&causes);
+                let old = previous.as_deref().unwrap_or_default();
+                let added: Vec<String> = causes.iter().filter(|c| !old.contains(c)).cloned().collect();
+                let removed: Vec<String> = old.iter().filter(|c| !causes.contains(c)).cloned().collect();
+                if previous.is_none() || !added.is_empty() || !removed.is_empty() {
+                    let delta = WhyDelta { pid, ts: chrono::Local::now().to_rfc3339(), added, removed, causes: causes.clone() };
+                    if output.is_structured() {
+                        output.print(&delta)?;
+                    } else {
+                        print_why_delta(&delta, previous.is_none());
+                    }
+                }
+                previous = Some(causes);
+            }
+            // daemon 短暂不可用时继续重试
+            Err(e) => eprintln!("[ark] {} 分析失败: {}", chrono::Local::now().format("%H:%M:%S"), e),
+        }
+
+        let wait_started = tokio::time::Instant::now();
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = tokio::time::sleep(interval) => {}
+            _ = changed.notified() => {
+                tokio::time::sleep_until(wait_started + FOLLOW_MIN_GAP).await;
+            }
+        }
+    }
+
+    if let Some(handle) = subscription_handle {
+        handle.abort();
+    }
+    if !output.is_structured() {
+        println!("{}", "已停止跟踪".dimmed());
+    }
+    Ok(status)
+}
+
+fn print_why_delta(delta: &WhyDelta, first: bool) {
+    use colored::*;
+
+    let ts = chrono::Local::now().format("%H:%M:%S").to_string();
+    if first {
+        if delta.causes.is_empty() {
+            println!("{} 进程 {} 未发现阻塞问题", ts.dimmed(), delta.pid.to_string().bright_green());
+        } else {
+            println!("{} 进程 {} 的阻塞根因:", ts.dimmed(), delta.pid.to_string().bright_green());
+            for cause in &delta.causes {
+                println!("  {} {}", "*".bright_yellow(), cause);
+            }
+        }
+        return;
+    }
+
+    for cause in &delta.added {
+        println!("{} {} {}", ts.dimmed(), "+".bright_red().bold(), cause);
+    }
+    for cause in &delta.removed {
+        println!("{} {} {}", ts.dimmed(), "-".bright_green().bold(), cause);
+    }
+    if delta.causes.is_empty() {
+        println!("{} 进程 {} 已无阻塞根因", ts.dimmed(), delta.pid.to_string().bright_green());
+    }
 }
 
-/// 强制终止进程
-async fn zap_process(pid: u32) -> Result<(), Box<dyn std::error::Error>> {
+/// 强制终止进程树（高危操作，由 daemon 校验审批并记录审计日志）
+async fn zap_process(
+    pid: u32,
+    client: IpcClient,
+    approval_token: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !client.ping().await? {
+        return Err("无法连接到 daemon，请先运行: ark run".into());
+    }
+
     println!("[ark] 正在终止进程 {}...", pid);
-    
-    let actuator = SystemActuator::new();
-    match actuator.execute(pid, "zap").await {
+
+    let result = match client.execute_action(pid, vec![ActionType::KillProcessTree], "zap", approval_token).await {
+        Ok(result) if !result.success => {
+            Err(result.failed_actions.into_iter().map(|a| a.error).collect::<Vec<_>>().join("; "))
+        }
+        other => other,
+    };
+
+    HistoryStore::new(history::default_history_path()).record(
+        HistoryRecord::new(
+            HistoryKind::Fix,
+            match &result {
+                Ok(_) => "zap: 进程已终止".to_string(),
+                Err(e) => format!("zap 失败: {}", e),
+            },
+        )
+        .with_target(Some(pid), lookup_job_id(&client, pid).await),
+    );
+
+    match result {
         Ok(_) => {
             println!("[ark] 进程 {} 已成功终止", pid);
         }
@@ -708,64 +2461,83 @@ async fn zap_process(pid: u32) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-#[cfg(windows)]
-async fn query_why(pid: u32, port: u16) -> Result<(), Box<dyn std::error::Error>> {
-    use colored::*;
-    use crate::ipc::IpcClient;
-    
-    let client = IpcClient::new(port);
-    
-    // 检查 daemon 是否运行
-    if !client.ping().await? {
-        eprintln!("[ark] 错误：无法连接到 daemon (端口 {})", port);
-        eprintln!("[ark] 请先运行: ark run");
-        return Err("daemon 未运行".into());
+/// 生成修复计划：优先使用规则声明的动作，没有时从推荐动作文本推断
+/// 规则来源依次为 daemon 的热加载规则集（匹配当前状态图）、本地规则目录（按场景查找）
+/// fix 是显式操作，冷却中的规则同样可用，只跳过被抑制的规则
+async fn plan_fix(
+    client: &IpcClient,
+    rules_dir: Option<&PathBuf>,
+    analysis: &scene::AnalysisResult,
+    fix_engine: &FixEngine,
+    pid: u32,
+) -> FixPlan {
+    let mut rule = client.match_rules().await.ok().and_then(|(_, matches)| {
+        matches
+            .into_iter()
+            .filter(|m| !matches!(m.status, ark_core::rules::MatchStatus::Suppressed { .. }))
+            .map(|m| m.rule)
+            .find(|r| !r.actions.is_empty())
+    });
+    if rule.is_none() {
+        if let Some(dir) = rules_dir {
+            rule = ark_core::rules::RuleEngine::load_from_dir(dir).ok().and_then(|engine| {
+                engine
+                    .rules()
+                    .iter()
+                    .find(|r| r.scene == analysis.scene.as_str() && !r.actions.is_empty())
+                    .cloned()
+            });
+        }
     }
 
-    // 查询根因
-    let causes = client.why_process(pid).await?;
-
-    if causes.is_empty() {
-        println!(
-            "进程 {} 未发现阻塞问题",
-            pid.to_string().bright_green()
-        );
-        return Ok(());
+    match rule {
+        Some(rule) => fix_engine.plan_from_rule(&rule, pid),
+        None => fix_engine.plan_from_analysis(analysis, pid),
     }
+}
 
-    println!(
-        "进程 {} 的阻塞根因分析:",
-        pid.to_string().bright_green()
-    );
-    println!("{}", "-".repeat(60));
-
-    // 尝试识别场景类型（基于根因文本）
-    let scene_hint = if causes.iter().any(|c| c.contains("GPU") || c.contains("OOM") || c.contains("显存")) {
-        Some("GPU OOM")
-    } else if causes.iter().any(|c| c.contains("网络") || c.contains("network") || c.contains("等待资源")) {
-        Some("网络阻塞")
-    } else if causes.iter().any(|c| c.contains("exit") || c.contains("crash") || c.contains("failed")) {
-        Some("进程崩溃")
-    } else {
-        None
-    };
+/// 未指定规则目录时，尝试使用默认的 ./rules
+fn default_rules_dir(rules_dir: Option<PathBuf>) -> Option<PathBuf> {
+    rules_dir.or_else(|| {
+        let default = PathBuf::from("rules");
+        if default.exists() {
+            Some(default)
+        } else {
+            None
+        }
+    })
+}
 
-    if let Some(scene) = scene_hint {
-        println!("  [场景识别] {}", scene.bright_cyan());
-        println!();
+/// 生成事故报告并写入文件或 stdout
+async fn write_report(
+    pid: u32,
+    client: IpcClient,
+    out: Option<PathBuf>,
+    format: Option<report::ReportFormat>,
+    events: usize,
+    diagnosis: Option<diag::Diagnosis>,
+    output: OutputFormat,
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    let incident = report::collect_report(&client, pid, events, diagnosis).await?;
+    let status = ExitStatus::from_causes(&incident.causes);
+
+    if output.is_structured() && out.is_none() {
+        output.print(&incident)?;
+        return Ok(status);
     }
 
-    for (idx, cause) in causes.iter().enumerate() {
-        if cause.starts_with("等待资源") {
-            println!("  {}. {}", idx + 1, cause.bright_yellow());
-        } else if cause.contains("error") {
-            println!("  {}. {}", idx + 1, cause.bright_red());
-        } else {
-            println!("  {}. {}", idx + 1, cause);
+    let format = format
+        .or_else(|| out.as_deref().map(report::ReportFormat::from_path))
+        .unwrap_or(report::ReportFormat::Markdown);
+    let content = incident.render(format);
+    match out {
+        Some(path) => {
+            std::fs::write(&path, content).map_err(|e| format!("写入报告 {} 失败: {}", path.display(), e))?;
+            eprintln!("[ark] 事故报告已写入: {}", path.display());
         }
+        None => print!("{}", content),
     }
-
-    Ok(())
+    Ok(status)
 }
 
 /// AI 诊断：使用大模型分析进程问题
@@ -775,27 +2547,20 @@ async fn diagnose_process(
     socket_path: Option<PathBuf>,
     provider: Option<String>,
     rules_dir: Option<PathBuf>,
-) -> Result<(), Box<dyn std::error::Error>> {
+    output: OutputFormat,
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
     use colored::*;
 
-    println!(
-        "[ark] 正在诊断进程 {}...",
-        pid.to_string().bright_green()
-    );
-    println!("[ark] 收集诊断信息...\n");
-
-    // 如果没有指定规则目录，尝试使用默认的 ./rules
-    let rules_path = rules_dir.or_else(|| {
-        let default = PathBuf::from("rules");
-        if default.exists() {
-            Some(default)
-        } else {
-            None
-        }
-    });
+    if !output.is_structured() {
+        println!(
+            "[ark] 正在诊断进程 {}...",
+            pid.to_string().bright_green()
+        );
+        println!("[ark] 收集诊断信息...\n");
+    }
 
     // 执行诊断
-    let diagnosis = match run_diagnosis(pid, socket_path, provider, rules_path).await {
+    let diagnosis = match run_diagnosis(pid, socket_path, provider, default_rules_dir(rules_dir)).await {
         Ok(d) => d,
         Err(e) => {
             eprintln!("[ark] 诊断失败: {}", e);
@@ -809,6 +2574,21 @@ async fn diagnose_process(
             return Err(e);
         }
     };
+    let status = ExitStatus::from_causes(&diagnosis.causes);
+    HistoryStore::new(history::default_history_path()).record(
+        HistoryRecord::new(
+            HistoryKind::Diagnosis,
+            diagnosis.causes.first().cloned().unwrap_or_else(|| "未发现阻塞根因".to_string()),
+        )
+        .with_target(Some(pid), None)
+        .with_scene(identify_scene_from_causes(&diagnosis.causes).map(|scene| scene.as_str().to_string()))
+        .with_detail(serde_json::to_value(&diagnosis).unwrap_or_default()),
+    );
+
+    if output.is_structured() {
+        output.print(&diagnosis)?;
+        return Ok(status);
+    }
 
     // 显示诊断结果
     println!("{}", "=".repeat(70).bright_cyan());
@@ -860,240 +2640,404 @@ async fn diagnose_process(
     );
     println!();
 
-    Ok(())
+    Ok(status)
 }
 
 /// 自动修复进程：根据诊断结果执行推荐动作
-#[cfg(unix)]
+///
+/// 退出码：全部动作成功或无需修复为 0，发现根因但无法自动修复为 2，有动作失败为 3
+#[allow(clippy::too_many_arguments)]
 async fn fix_process(
     pid: u32,
-    socket_path: Option<PathBuf>,
+    client: IpcClient,
     rules_dir: Option<PathBuf>,
     auto_yes: bool,
-    audit_log: Option<PathBuf>,
-) -> Result<(), Box<dyn std::error::Error>> {
+    approval_token: Option<String>,
+    dry_run: bool,
+    output: OutputFormat,
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
     use colored::Colorize;
-    
-    println!(
-        "[ark] 正在修复进程 {}...",
-        pid.to_string().bright_green()
-    );
-    
+
+    // 机器可读输出不能夹杂交互确认（预演不需要确认）
+    let structured = output.is_structured();
+    if structured && !auto_yes && !dry_run {
+        return Err("--output json/yaml 时需要同时指定 --yes".into());
+    }
+
+    if !structured {
+        println!(
+            "[ark] 正在修复进程 {}...",
+            pid.to_string().bright_green()
+        );
+    }
+
     // 连接到 daemon
-    let client = IpcClient::new(socket_path);
     if !client.ping().await? {
         return Err("无法连接到 daemon，请先运行: ark run".into());
     }
-    
+
     // 获取根因分析（用于场景识别）
     let causes = client.why_process(pid).await?;
-    
-    // 识别场景（简化版：基于根因文本）
-    let scene = identify_scene_from_causes(&causes);
-    
-    if scene.is_none() {
-        println!("{}", "[ark] 未识别到问题场景，无法自动修复".bright_yellow());
-        println!("提示: 可以尝试手动执行: ark zap {}", pid);
-        return Ok(());
+    let mut report = FixReport {
+        pid,
+        causes: causes.clone(),
+        scene: None,
+        rule: None,
+        planned_actions: Vec::new(),
+        plan: None,
+        skipped_steps: Vec::new(),
+        denied_steps: Vec::new(),
+        dry_run,
+        executed: false,
+        success: false,
+        message: String::new(),
+        executed_actions: Vec::new(),
+        failed_actions: Vec::new(),
+        rolled_back: Vec::new(),
+        fix_id: None,
+    };
+
+    // 识别场景：优先用状态图运行场景分析器，取图失败时基于根因文本
+    let graph_scene = analyze_process_scene(&client, pid).await;
+    let Some(scene) = graph_scene.as_ref().map(|(scene, _)| scene.clone()).or_else(|| identify_scene_from_causes(&causes)) else {
+        report.message = "未识别到问题场景，无法自动修复".to_string();
+        if structured {
+            output.print(&report)?;
+        } else {
+            println!("{}", format!("[ark] {}", report.message).bright_yellow());
+            println!("提示: 可以尝试手动执行: ark zap {}", pid);
+        }
+        return Ok(ExitStatus::from_causes(&causes));
+    };
+    if !structured {
+        println!("[ark] 识别到场景: {:?}", scene);
     }
-    
-    let scene = scene.unwrap();
-    println!("[ark] 识别到场景: {:?}", scene);
-    
-    // 创建分析结果（基于根因）
-    let analysis = create_analysis_from_causes(scene, &causes);
-    
-    if analysis.is_none() {
-        println!("{}", "[ark] 无法分析场景，无法自动修复".bright_yellow());
-        return Ok(());
+    report.scene = Some(scene.as_str().to_string());
+
+    // 分析结果：场景分析器的结论，场景没有专门分析器时基于根因构造
+    let analysis = match graph_scene {
+        Some((_, Some(analysis))) => analysis,
+        _ => create_analysis_from_causes(scene, &causes),
+    };
+
+    // 生成执行计划并显示
+    // 动作策略：标注每步的级别，禁止的步骤不会执行，--yes 只执行 auto 级别的步骤
+    let job_id = lookup_job_id(&client, pid).await;
+    let policy = ActionPolicy::load_default()?;
+    let subject = PolicySubject { job: job_id.clone(), user: std::env::var("USER").ok() };
+    let fix_engine = FixEngine::new().with_policy(policy.clone(), subject.clone());
+    let plan = plan_fix(&client, rules_dir.as_ref(), &analysis, &fix_engine, pid).await;
+    report.planned_actions = plan.steps.iter().map(|step| step.description.clone()).collect();
+    report.rule = plan.rule.clone();
+    if !structured && !plan.is_empty() {
+        let title = match plan.rule {
+            Some(ref name) => format!("修复计划（规则 {} 声明的动作）:", name),
+            None => "修复计划（推荐动作）:".to_string(),
+        };
+        println!("\n{}", title.bright_cyan().bold());
+        print_fix_plan(&plan);
+        println!();
     }
-    
-    let analysis = analysis.unwrap();
-    
-    // 显示推荐动作
-    if !analysis.recommended_actions.is_empty() {
-        println!("\n{}", "推荐动作:".bright_cyan().bold());
-        for (idx, action) in analysis.recommended_actions.iter().enumerate() {
-            println!("  {}. {}", idx + 1, action);
+    report.plan = Some(plan.clone());
+    let allowed_steps = plan.steps_within(PolicyLevel::Confirm);
+    report.denied_steps = plan
+        .steps
+        .iter()
+        .filter(|step| step.policy == PolicyLevel::Deny)
+        .map(|step| step.order)
+        .collect();
+
+    // 预演：在本机按执行器的方式展开每个动作，不经过 daemon 执行，也不记录历史
+    if dry_run {
+        let result = FixEngine::dry_run()
+            .with_policy(policy, subject)
+            .execute_plan(plan.selected_actions(&allowed_steps), pid)
+            .await?;
+        report.success = result.success;
+        report.message = result.message;
+        report.executed_actions = result
+            .executed_actions
+            .into_iter()
+            .map(|a| ActionReport { action: a.action, detail: a.result })
+            .collect();
+        report.failed_actions = result
+            .failed_actions
+            .into_iter()
+            .map(|a| ActionReport { action: a.action, detail: a.error })
+            .collect();
+        let status = if report.failed_actions.is_empty() { ExitStatus::Clean } else { ExitStatus::ActionFailed };
+        if structured {
+            output.print(&report)?;
+            return Ok(status);
         }
-        println!();
+        println!("{}", "预演（--dry-run），不会修改系统，将执行:".bright_cyan().bold());
+        for (idx, action) in report.executed_actions.iter().enumerate() {
+            println!("  {}. {}", idx + 1, action.action);
+            for step in action.detail.lines() {
+                println!("     - {}", step);
+            }
+        }
+        for action in &report.failed_actions {
+            println!("  ❌ {}: {}", action.action, action.detail);
+        }
+        return Ok(status);
     }
-    
-    // 确认执行
-    if !auto_yes {
-        use std::io::{self, Write};
-        print!("{}", "是否执行修复? [y/N]: ".bright_yellow());
-        io::stdout().flush()?;
-        
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        
-        if !input.trim().eq_ignore_ascii_case("y") && !input.trim().eq_ignore_ascii_case("yes") {
-            println!("{}", "已取消".bright_yellow());
-            return Ok(());
+
+    if !report.denied_steps.is_empty() && !structured {
+        println!("{}", format!("动作策略禁止的步骤不会执行: {:?}", report.denied_steps).bright_yellow());
+    }
+    if allowed_steps.is_empty() && !plan.is_empty() {
+        report.message = "所有步骤都被动作策略禁止".to_string();
+        if structured {
+            output.print(&report)?;
+        } else {
+            println!("{}", format!("[ark] {}", report.message).bright_yellow());
         }
+        return Ok(ExitStatus::from_causes(&causes));
     }
-    
-    // 初始化审计日志（如果指定了路径）
-    let audit_logger = if let Some(ref log_path) = audit_log {
-        Some(Arc::new(audit::AuditLogger::new(log_path.clone(), 100)?)) // 100MB 最大大小
+
+    // 确认执行：--yes 只执行 auto 级别的步骤；交互时整体确认，或逐步确认后只执行选中的步骤
+    let approved = if auto_yes {
+        plan.steps_within(PolicyLevel::Auto)
     } else {
-        None
+        match confirm_fix_plan(&plan)? {
+            Some(approved) => approved,
+            None => {
+                println!("{}", "已取消".bright_yellow());
+                return Ok(ExitStatus::from_causes(&causes));
+            }
+        }
     };
-    
-    // 执行修复
-    let fix_engine = FixEngine::new();
-    let result = fix_engine.fix_from_analysis(&analysis, pid).await?;
-    
-    // 记录审计日志
-    if let Some(ref logger) = audit_logger {
-        // 尝试从分析结果中获取 job_id（如果有）
-        let job_id = analysis.recommended_actions.iter()
-            .find(|a| a.contains("job"))
-            .and_then(|_| None); // 简化：暂时不提取 job_id
-        
-        let action_str = if !result.executed_actions.is_empty() {
-            result.executed_actions[0].action.clone()
-        } else if !analysis.recommended_actions.is_empty() {
-            analysis.recommended_actions[0].clone()
-        } else {
-            "Unknown".to_string()
-        };
-        
-        let details = format!(
-            "执行动作: {}; 成功: {}; 失败: {}; 场景: {:?}",
-            action_str,
-            result.executed_actions.len(),
-            result.failed_actions.len(),
-            analysis.scene
-        );
-        
-        let entry = audit::create_audit_entry(
-            &action_str,
-            pid,
-            None, // job_id 暂时为 None
-            if result.success { "success" } else { "partial_failure" },
-            &details,
+    report.skipped_steps = allowed_steps.into_iter().filter(|order| !approved.contains(order)).collect();
+    if auto_yes && !report.skipped_steps.is_empty() && !structured {
+        println!(
+            "{}",
+            format!("动作策略要求人工确认的步骤 --yes 不会执行: {:?}", report.skipped_steps).bright_yellow()
         );
-        
-        if let Err(e) = logger.log(entry).await {
-            eprintln!("[audit] 记录审计日志失败: {}", e);
+    }
+    if approved.is_empty() && !plan.is_empty() {
+        report.message = "没有可自动执行的步骤（动作策略要求人工确认）".to_string();
+        if structured {
+            output.print(&report)?;
+        } else {
+            println!("{}", format!("[ark] {}", report.message).bright_yellow());
         }
+        return Ok(ExitStatus::from_causes(&causes));
     }
-    
+
+    // 由 daemon 执行：审批校验、审计日志和指标都在 daemon 侧完成
+    let actions = plan.selected_actions(&approved).into_iter().map(|(action, _)| action).collect();
+    let result = client.execute_action(pid, actions, "fix", approval_token).await?;
+
+    let status = if result.success { ExitStatus::Clean } else { ExitStatus::ActionFailed };
+    report.executed = true;
+    report.success = result.success;
+    report.message = result.message;
+    report.executed_actions = result
+        .executed_actions
+        .into_iter()
+        .map(|a| ActionReport { action: a.action, detail: a.result })
+        .collect();
+    report.failed_actions = result
+        .failed_actions
+        .into_iter()
+        .map(|a| ActionReport { action: a.action, detail: a.error })
+        .collect();
+    report.rolled_back = result.rolled_back;
+    report.fix_id = result.fix_id;
+    HistoryStore::new(history::default_history_path()).record(
+        HistoryRecord::new(HistoryKind::Fix, report.message.clone())
+            .with_target(Some(pid), job_id)
+            .with_scene(report.scene.clone())
+            .with_detail(serde_json::to_value(&report).unwrap_or_default()),
+    );
+
+    if structured {
+        output.print(&report)?;
+        return Ok(status);
+    }
+
     // 显示结果
     println!("\n{}", "=".repeat(70).bright_cyan());
     println!("{}", "修复结果".bright_cyan().bold());
     println!("{}", "=".repeat(70).bright_cyan());
     println!();
-    
-    if result.success {
-        println!("{}", format!("✅ {}", result.message).bright_green());
+
+    if report.success {
+        println!("{}", format!("✅ {}", report.message).bright_green());
     } else {
-        println!("{}", format!("⚠️  {}", result.message).bright_yellow());
+        println!("{}", format!("⚠️  {}", report.message).bright_yellow());
     }
-    
-    if !result.executed_actions.is_empty() {
+
+    if !report.executed_actions.is_empty() {
         println!("\n{}", "已执行的动作:".bright_green().bold());
-        for action in &result.executed_actions {
-            println!("  ✅ {}: {}", action.action, action.result);
+        for action in &report.executed_actions {
+            println!("  ✅ {}: {}", action.action, action.detail);
         }
     }
-    
-    if !result.failed_actions.is_empty() {
+
+    if !report.failed_actions.is_empty() {
         println!("\n{}", "失败的动作:".bright_red().bold());
-        for action in &result.failed_actions {
-            println!("  ❌ {}: {}", action.action, action.error);
+        for action in &report.failed_actions {
+            println!("  ❌ {}: {}", action.action, action.detail);
         }
     }
-    
-    Ok(())
+
+    if !report.rolled_back.is_empty() {
+        println!("\n{}", "已自动回滚:".bright_yellow().bold());
+        print_rollback_outcomes(&report.rolled_back);
+    }
+
+    if let Some(ref fix_id) = report.fix_id {
+        println!("\n撤销可回滚的动作: ark fix --rollback {}", fix_id.bright_white());
+    }
+
+    Ok(status)
 }
 
-#[cfg(windows)]
-async fn fix_process(
-    pid: u32,
-    port: u16,
-    rules_dir: Option<PathBuf>,
+/// 撤销一次修复（由 daemon 按修复日志执行并记录审计日志）
+///
+/// 退出码：全部撤销成功为 0，有撤销操作失败为 3
+async fn rollback_fix(
+    fix_id: &str,
+    client: IpcClient,
     auto_yes: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
+    output: OutputFormat,
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
     use colored::Colorize;
-    
-    println!(
-        "[ark] 正在修复进程 {}...",
-        pid.to_string().bright_green()
-    );
-    
-    // 连接到 daemon
-    let client = IpcClient::new(port);
+    use std::io::{self, Write};
+
+    let structured = output.is_structured();
+    if structured && !auto_yes {
+        return Err("--output json/yaml 时需要同时指定 --yes".into());
+    }
+
     if !client.ping().await? {
         return Err("无法连接到 daemon，请先运行: ark run".into());
     }
-    
-    // 获取根因分析
-    let causes = client.why_process(pid).await?;
-    
-    // 识别场景
-    let scene = identify_scene_from_causes(&causes);
-    
-    if scene.is_none() {
-        println!("{}", "[ark] 未识别到问题场景，无法自动修复".bright_yellow());
-        return Ok(());
+
+    if !auto_yes {
+        print!("{}", format!("是否回滚修复 {}? [y/N]: ", fix_id).bright_yellow());
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+            println!("{}", "已取消".bright_yellow());
+            return Ok(ExitStatus::Clean);
+        }
     }
-    
-    let scene = scene.unwrap();
-    
-    // 创建分析结果
-    let analysis = create_analysis_from_causes(scene, &causes);
-    
-    // 初始化审计日志（如果指定了路径）
-    let audit_logger = if let Some(ref log_path) = audit_log {
-        Some(Arc::new(audit::AuditLogger::new(log_path.clone(), 100)?)) // 100MB 最大大小
+
+    let outcomes = client.rollback_fix(fix_id).await?;
+    let report = RollbackReport {
+        fix_id: fix_id.to_string(),
+        success: outcomes.iter().all(|o| o.success),
+        outcomes,
+    };
+    let status = if report.success { ExitStatus::Clean } else { ExitStatus::ActionFailed };
+
+    let summary = if report.success {
+        format!("rollback {}: 已撤销 {} 个动作", fix_id, report.outcomes.len())
     } else {
-        None
+        format!("rollback {}: 部分撤销失败", fix_id)
     };
-    
-    // 执行修复
-    let fix_engine = FixEngine::new();
-    let result = fix_engine.fix_from_analysis(&analysis, pid).await?;
-    
-    // 记录审计日志
-    if let Some(ref logger) = audit_logger {
-        let action_str = if !result.executed_actions.is_empty() {
-            result.executed_actions[0].action.clone()
-        } else if !analysis.recommended_actions.is_empty() {
-            analysis.recommended_actions[0].clone()
-        } else {
-            "Unknown".to_string()
-        };
-        
-        let details = format!(
-            "执行动作: {}; 成功: {}; 失败: {}; 场景: {:?}",
-            action_str,
-            result.executed_actions.len(),
-            result.failed_actions.len(),
-            analysis.scene
-        );
-        
-        let entry = audit::create_audit_entry(
-            &action_str,
-            pid,
-            None, // job_id 暂时为 None
-            if result.success { "success" } else { "partial_failure" },
-            &details,
-        );
-        
-        if let Err(e) = logger.log(entry).await {
-            eprintln!("[audit] 记录审计日志失败: {}", e);
+    HistoryStore::new(history::default_history_path()).record(
+        HistoryRecord::new(HistoryKind::Fix, summary.clone())
+            .with_detail(serde_json::to_value(&report).unwrap_or_default()),
+    );
+
+    if structured {
+        output.print(&report)?;
+        return Ok(status);
+    }
+
+    if report.success {
+        println!("{}", format!("✅ {}", summary).bright_green());
+    } else {
+        println!("{}", format!("⚠️  {}", summary).bright_yellow());
+    }
+    print_rollback_outcomes(&report.outcomes);
+
+    Ok(status)
+}
+
+fn print_rollback_outcomes(outcomes: &[RollbackOutcome]) {
+    for outcome in outcomes {
+        let marker = if outcome.success { "↩️" } else { "❌" };
+        println!("  {} {}: {}", marker, outcome.action, outcome.detail);
+    }
+}
+
+/// 展示修复计划：每步的动作、前置条件和预期效果
+fn print_fix_plan(plan: &FixPlan) {
+    use colored::Colorize;
+
+    for step in &plan.steps {
+        let mut marker = if step.destructive { " [高危]".bright_red().to_string() } else { String::new() };
+        match step.policy {
+            PolicyLevel::Auto => {}
+            PolicyLevel::Confirm => marker.push_str(&" [需确认]".bright_yellow().to_string()),
+            PolicyLevel::Deny => marker.push_str(&" [策略禁止]".bright_red().bold().to_string()),
         }
+        println!("  {}. {}{}", step.order, step.description, marker);
+        for condition in &step.preconditions {
+            println!("     前置条件: {}", condition);
+        }
+        println!("     预期效果: {}", step.expected_effect.bright_white());
     }
-    
-    println!("修复结果: {}", result.message);
-    
-    Ok(())
 }
 
-/// 从根因识别场景（简化版）
+/// 交互确认修复计划：y 执行全部（策略允许的）步骤，s 逐步确认（每步 y/N，q 跳过剩余步骤）
+///
+/// 返回确认执行的步骤序号，取消或一步都没有确认时为 None
+fn confirm_fix_plan(plan: &FixPlan) -> Result<Option<Vec<usize>>, Box<dyn std::error::Error>> {
+    use colored::Colorize;
+    use std::io::{self, Write};
+
+    let ask = |question: String| -> io::Result<String> {
+        print!("{}", question.bright_yellow());
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        Ok(input.trim().to_lowercase())
+    };
+
+    let allowed = plan.steps_within(PolicyLevel::Confirm);
+    match ask("是否执行修复? [y 全部 / s 逐步确认 / N]: ".to_string())?.as_str() {
+        "y" | "yes" => Ok(Some(allowed)),
+        "s" | "step" => {
+            let mut approved = Vec::new();
+            for step in plan.steps.iter().filter(|step| allowed.contains(&step.order)) {
+                match ask(format!("  执行第 {} 步（{}）? [y/N/q]: ", step.order, step.description))?.as_str() {
+                    "y" | "yes" => approved.push(step.order),
+                    "q" | "quit" => break,
+                    _ => {}
+                }
+            }
+            Ok(Some(approved).filter(|approved| !approved.is_empty()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// 从 daemon 的状态图查询进程所属 job（查询失败或不属于任何 job 时为 None）
+async fn lookup_job_id(client: &IpcClient, pid: u32) -> Option<String> {
+    let processes = client.list_processes(&ProcessFilter::default()).await.ok()?;
+    let process = processes.iter().find(|p| p["pid"].as_u64() == Some(pid as u64))?;
+    process["job_id"].as_str().map(str::to_string)
+}
+
+/// 取进程在 daemon 状态图中的邻域，识别场景并运行对应的分析器
+///
+/// 取图失败或未识别到场景时返回 None；场景没有专门的分析器时分析结果为 None
+async fn analyze_process_scene(client: &IpcClient, pid: u32) -> Option<(SceneType, Option<scene::AnalysisResult>)> {
+    let graph = client.get_graph(Some(pid), ipc::DEFAULT_GRAPH_DEPTH).await.ok()?;
+    let identifier = SceneIdentifier::new();
+    let scene = identifier.identify_scene(&graph, pid).await?;
+    let analysis = identifier.analyze_scene(scene.clone(), &graph, pid).await;
+    Some((scene, analysis))
+}
+
+/// 从根因文本识别场景（无法获取状态图时的回退）
 fn identify_scene_from_causes(causes: &[String]) -> Option<SceneType> {
     for cause in causes {
         let cause_lower = cause.to_lowercase();
@@ -1109,6 +3053,9 @@ fn identify_scene_from_causes(causes: &[String]) -> Option<SceneType> {
         if cause_lower.contains("crash") || cause_lower.contains("崩溃") {
             return Some(SceneType::ProcessCrash);
         }
+        if cause_lower.contains("掉队") {
+            return Some(SceneType::JobStraggler);
+        }
     }
     Some(SceneType::WorkloadStalled) // 默认场景
 }
@@ -1127,6 +3074,10 @@ fn create_analysis_from_causes(scene: SceneType, causes: &[String]) -> scene::An
             recommended_actions.push("检查交换机 PFC 配置".to_string());
             recommended_actions.push("检查 RoCE/HCCS 连接状态".to_string());
         }
+        SceneType::JobStraggler => {
+            recommended_actions.push("检查掉队 GPU 的温度与降频状态".to_string());
+            recommended_actions.push("保存 Checkpoint 后在健康节点上重启掉队 rank".to_string());
+        }
         SceneType::WorkloadStalled => {
             recommended_actions.push("如果确认卡死，执行 ark zap 终止进程".to_string());
             recommended_actions.push("检查是否有 Checkpoint 可以恢复".to_string());
@@ -1147,118 +3098,240 @@ fn create_analysis_from_causes(scene: SceneType, causes: &[String]) -> scene::An
 }
 
 /// 集群级进程列表查询
-async fn cluster_ps(hub_url: &str) -> Result<(), Box<dyn std::error::Error>> {
+async fn cluster_ps(hub_url: &str, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
     use colored::*;
-    
+
     let url = format!("{}/api/v1/ps", hub_url.trim_end_matches('/'));
-    let response = reqwest::get(&url).await?;
-    let json: serde_json::Value = response.json().await?;
-    
-    if let Some(processes) = json.get("processes").and_then(|p| p.as_array()) {
-        if processes.is_empty() {
-            println!("集群中没有活跃进程");
-            return Ok(());
-        }
-        
+    let json = hub_auth::get_json(&url).await?;
+
+    let processes: Vec<ClusterProcessReport> = json
+        .get("processes")
+        .and_then(|p| p.as_array())
+        .ok_or("无法解析 Hub 响应")?
+        .iter()
+        .map(|proc| ClusterProcessReport {
+            node_id: proc["node_id"].as_str().unwrap_or("local").to_string(),
+            job_id: proc["job_id"].as_str().map(str::to_string),
+            pid: proc["pid"]
+                .as_u64()
+                .map(|p| p.to_string())
+                .unwrap_or_else(|| proc["id"].as_str().unwrap_or("-").to_string()),
+            pod: proc["pod"].as_str().map(str::to_string),
+            state: proc["state"].as_str().unwrap_or("unknown").to_string(),
+        })
+        .collect();
+
+    if output.is_structured() {
+        output.print(&serde_json::json!({ "processes": processes }))?;
+        return Ok(());
+    }
+
+    if processes.is_empty() {
+        println!("集群中没有活跃进程");
+        return Ok(());
+    }
+
+    println!(
+        "{:>20} | {:>12} | {:>15} | {:>30} | {}",
+        "NODE_ID".bright_cyan(),
+        "JOB_ID".bright_cyan(),
+        "PID".bright_cyan(),
+        "POD".bright_cyan(),
+        "STATE".bright_cyan()
+    );
+    println!("{}", "-".repeat(110));
+
+    for proc in &processes {
         println!(
-            "{:>20} | {:>12} | {:>15} | {}",
-            "NODE_ID".bright_cyan(),
-            "JOB_ID".bright_cyan(),
-            "PID".bright_cyan(),
-            "STATE".bright_cyan()
+            "{:>20} | {:>12} | {:>15} | {:>30} | {}",
+            proc.node_id,
+            proc.job_id.as_deref().unwrap_or("-"),
+            proc.pid,
+            proc.pod.as_deref().unwrap_or("-"),
+            proc.state
         );
-        println!("{}", "-".repeat(80));
-        
-        for proc in processes {
-            let id = proc["id"].as_str().unwrap_or("-");
-            let job_id = proc["job_id"].as_str().unwrap_or("-");
-            let state = proc["state"].as_str().unwrap_or("unknown");
-            
-            // 从 id 中提取节点和 PID
-            let (node_id, pid) = if id.contains("::") {
-                let parts: Vec<&str> = id.split("::").collect();
-                (parts[0], parts.get(1).unwrap_or(&"-"))
-            } else {
-                ("local", id)
-            };
-            
-            println!("{:>20} | {:>12} | {:>15} | {}", node_id, job_id, pid, state);
-        }
-    } else {
-        eprintln!("错误：无法解析 Hub 响应");
     }
-    
+
     Ok(())
 }
 
-/// 集群级根因分析
-async fn cluster_why(hub_url: &str, job_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+async fn cluster_nodes(hub_url: &str, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
     use colored::*;
-    
+
+    let url = format!("{}/api/v1/nodes", hub_url.trim_end_matches('/'));
+    let json = hub_auth::get_json(&url).await?;
+    let nodes = json.get("nodes").and_then(|n| n.as_array()).ok_or("无法解析 Hub 响应")?;
+
+    if output.is_structured() {
+        output.print(&json)?;
+        return Ok(());
+    }
+
+    if nodes.is_empty() {
+        println!("Hub 尚未收到任何节点的注册或事件");
+        return Ok(());
+    }
+
+    println!(
+        "{:>20} | {:>8} | {:>10} | {:>4} | {:>4} | {:>6} | {}",
+        "NODE_ID".bright_cyan(),
+        "STATUS".bright_cyan(),
+        "VERSION".bright_cyan(),
+        "GPU".bright_cyan(),
+        "NPU".bright_cyan(),
+        "ERRORS".bright_cyan(),
+        "LAST_SEEN".bright_cyan()
+    );
+    println!("{}", "-".repeat(90));
+
+    let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+    for node in nodes {
+        let status = node["status"].as_str().unwrap_or("-");
+        let status = match status {
+            "online" => status.bright_green(),
+            "stale" => status.bright_yellow(),
+            _ => status.bright_red(),
+        };
+        let last_seen = match node["last_seen"].as_u64() {
+            Some(ts) if ts > 0 => format!("{}s 前", now_ms.saturating_sub(ts) / 1000),
+            _ => "-".to_string(),
+        };
+        let version = node["agent_version"].as_str().filter(|v| !v.is_empty()).unwrap_or("-");
+        println!(
+            "{:>20} | {:>8} | {:>10} | {:>4} | {:>4} | {:>6} | {}",
+            node["node_id"].as_str().unwrap_or("-"),
+            status,
+            version,
+            node["gpus"].as_u64().unwrap_or(0),
+            node["npus"].as_u64().unwrap_or(0),
+            node["recent_errors"].as_u64().unwrap_or(0),
+            last_seen
+        );
+    }
+
+    Ok(())
+}
+
+/// 查询 Hub 的集群级根因（Hub 返回 error 字段时视为失败）
+async fn fetch_cluster_why(hub_url: &str, job_id: &str) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
     let url = format!("{}/api/v1/why?job_id={}", hub_url.trim_end_matches('/'), job_id);
-    let response = reqwest::get(&url).await?;
-    let json: serde_json::Value = response.json().await?;
-    
+    let json = hub_auth::get_json(&url).await?;
+
     if let Some(error) = json.get("error") {
-        eprintln!("错误: {}", error.as_str().unwrap_or("unknown"));
-        return Ok(());
+        return Err(format!("Hub 返回错误: {}", error.as_str().unwrap_or("unknown")).into());
     }
-    
+    Ok(json)
+}
+
+fn cluster_causes(json: &serde_json::Value) -> Vec<String> {
+    json.get("causes")
+        .and_then(|c| c.as_array())
+        .map(|causes| causes.iter().filter_map(|c| c.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+/// Hub 识别的跨节点场景（旧版 Hub 没有该字段）
+fn cluster_scenes(json: &serde_json::Value) -> Vec<ClusterScene> {
+    json.get("scenes")
+        .and_then(|scenes| serde_json::from_value(scenes.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// 集群级根因分析，发现根因或跨节点场景时退出码为 2
+async fn cluster_why(hub_url: &str, job_id: &str, output: OutputFormat) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    use colored::*;
+
+    let json = fetch_cluster_why(hub_url, job_id).await?;
+    let causes = cluster_causes(&json);
+    let scenes = cluster_scenes(&json);
+    let status = if scenes.is_empty() { ExitStatus::from_causes(&causes) } else { ExitStatus::CausesFound };
+
+    if output.is_structured() {
+        output.print(&ClusterWhyReport { job_id: job_id.to_string(), causes, scenes })?;
+        return Ok(status);
+    }
+
     println!("🔍 集群级根因分析：job_id = {}", job_id.bright_green());
     println!();
-    
-    if let Some(causes) = json.get("causes").and_then(|c| c.as_array()) {
-        if causes.is_empty() {
-            println!("未发现阻塞根因");
-        } else {
-            println!("发现的根因：");
-            for (i, cause) in causes.iter().enumerate() {
-                if let Some(cause_str) = cause.as_str() {
-                    println!("  {}. {}", i + 1, cause_str.bright_red());
-                }
-            }
+
+    if causes.is_empty() {
+        println!("未发现阻塞根因");
+    } else {
+        println!("发现的根因：");
+        for (i, cause) in causes.iter().enumerate() {
+            println!("  {}. {}", i + 1, cause.bright_red());
         }
     }
-    
-    Ok(())
+
+    for scene in &scenes {
+        println!();
+        println!("跨节点场景: {}（置信度 {:.0}%）", scene.scene.bright_yellow(), scene.confidence * 100.0);
+        for cause in &scene.root_causes {
+            println!("  • {}", cause);
+        }
+        if !scene.nodes.is_empty() {
+            println!("  涉及节点: {}", scene.nodes.join(", ").bright_cyan());
+        }
+        for recommendation in &scene.recommendations {
+            println!("  → {}", recommendation);
+        }
+    }
+
+    Ok(status)
 }
 
 /// 集群级修复：自动诊断并下发修复命令
-async fn cluster_fix(hub_url: &str, job_id: &str, auto_confirm: bool) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// 下发后在 `wait` 内轮询各命令的执行结果（节点经 WebSocket 回报给 Hub）。
+///
+/// 退出码：无需修复或全部执行成功为 0，发现根因但没有可修复进程为 2，有命令下发或执行失败为 3
+/// （等待超时仍未回报的命令不计为失败）
+async fn cluster_fix(
+    hub_url: &str,
+    job_id: &str,
+    auto_confirm: bool,
+    approval_token: Option<&str>,
+    wait: std::time::Duration,
+    output: OutputFormat,
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
     use colored::*;
     use std::io::{self, Write};
-    
-    println!("🔧 集群级修复：job_id = {}", job_id.bright_green());
-    println!();
-    
-    // 步骤 1：调用 why 接口获取根因和涉及的节点/PID
-    let url = format!("{}/api/v1/why?job_id={}", hub_url.trim_end_matches('/'), job_id);
-    let response = reqwest::get(&url).await?;
-    let json: serde_json::Value = response.json().await?;
-    
-    if let Some(error) = json.get("error") {
-        eprintln!("错误: {}", error.as_str().unwrap_or("unknown"));
-        return Ok(());
+
+    // 机器可读输出不能夹杂交互确认
+    let structured = output.is_structured();
+    if structured && !auto_confirm {
+        return Err("--output json/yaml 时需要同时指定 --yes".into());
     }
-    
+
+    if !structured {
+        println!("🔧 集群级修复：job_id = {}", job_id.bright_green());
+        println!();
+    }
+
+    // 步骤 1：调用 why 接口获取根因和涉及的节点/PID
+    let json = fetch_cluster_why(hub_url, job_id).await?;
+    let causes = cluster_causes(&json);
+    let mut report = ClusterFixReport { job_id: job_id.to_string(), causes, targets: Vec::new() };
+
     // 步骤 2：显示根因
-    if let Some(causes) = json.get("causes").and_then(|c| c.as_array()) {
-        if causes.is_empty() {
+    if report.causes.is_empty() {
+        if structured {
+            output.print(&report)?;
+        } else {
             println!("未发现阻塞根因，无需修复");
-            return Ok(());
         }
-        
+        return Ok(ExitStatus::Clean);
+    }
+    if !structured {
         println!("发现的根因：");
-        for (i, cause) in causes.iter().enumerate() {
-            if let Some(cause_str) = cause.as_str() {
-                println!("  {}. {}", i + 1, cause_str.bright_red());
-            }
+        for (i, cause) in report.causes.iter().enumerate() {
+            println!("  {}. {}", i + 1, cause.bright_red());
         }
     }
-    
+
     // 步骤 3：从进程列表中提取节点和 PID
     let mut target_nodes: Vec<(String, u32)> = Vec::new(); // (node_id, pid)
-    
+
     if let Some(processes) = json.get("processes").and_then(|p| p.as_array()) {
         for process in processes {
             if let (Some(node_id), Some(pid)) = (
@@ -1269,89 +3342,139 @@ async fn cluster_fix(hub_url: &str, job_id: &str, auto_confirm: bool) -> Result<
             }
         }
     }
-    
-    // 如果进程列表为空，尝试从根因字符串中解析（向后兼容）
+
     if target_nodes.is_empty() {
-        if let Some(causes) = json.get("causes").and_then(|c| c.as_array()) {
-            for cause in causes {
-                if let Some(cause_str) = cause.as_str() {
-                    if let Some((node_id, pid)) = extract_node_and_pid(cause_str) {
-                        target_nodes.push((node_id, pid));
-                    }
-                }
-            }
+        if structured {
+            output.print(&report)?;
+        } else {
+            println!("⚠️  Hub 未返回可修复的进程（节点 ID 与 PID），请手动指定");
         }
+        return Ok(ExitStatus::CausesFound);
     }
-    
-    if target_nodes.is_empty() {
-        println!("⚠️  无法从响应中提取节点和 PID 信息，请手动指定");
-        return Ok(());
-    }
-    
-    // 步骤 4：显示将要执行的操作并确认
-    println!();
-    println!("将执行以下修复操作：");
-    for (node_id, pid) in &target_nodes {
-        println!("  • 节点 {} 上的 PID {}: 优雅降级 (GracefulShutdown)", 
-            node_id.bright_cyan(), pid.to_string().bright_yellow());
+
+    // 节点收到命令后按同样的方式解析动作，计划与实际执行一致
+    let action = HubForwarder::action_from_string(CLUSTER_FIX_ACTION)?;
+    let plans: Vec<FixPlan> = target_nodes
+        .iter()
+        .map(|(_, pid)| FixPlan::new(*pid, None, vec![(action.clone(), 0)]))
+        .collect();
+
+    if !structured {
+        // 步骤 4：显示将要执行的操作并确认
+        println!();
+        println!("将执行以下修复操作：");
+        for ((node_id, pid), plan) in target_nodes.iter().zip(&plans) {
+            println!("  • 节点 {} 上的 PID {}:", node_id.bright_cyan(), pid.to_string().bright_yellow());
+            print_fix_plan(plan);
+        }
+        println!();
     }
-    println!();
-    
+
     // 步骤 5：用户确认
     if !auto_confirm {
         print!("是否确认执行？[y/N]: ");
         io::stdout().flush()?;
-        
+
         let mut input = String::new();
         io::stdin().read_line(&mut input)?;
-        
+
         if input.trim().to_lowercase() != "y" {
             println!("已取消");
-            return Ok(());
+            return Ok(ExitStatus::CausesFound);
         }
     }
-    
+
     // 步骤 6：调用 fix API 下发命令
-    println!();
-    println!("正在下发修复命令...");
-    
+    if !structured {
+        println!();
+        println!("正在下发修复命令...");
+    }
+
+    // 大范围修复是否需要审批由 Hub 判断，发起人取自 Hub 认证的身份
     let client = reqwest::Client::new();
-    let mut success_count = 0;
-    let mut fail_count = 0;
-    
-    for (node_id, pid) in target_nodes {
+
+    for ((node_id, pid), plan) in target_nodes.into_iter().zip(plans) {
         let fix_url = format!("{}/api/v1/fix", hub_url.trim_end_matches('/'));
         let fix_request = serde_json::json!({
             "node_id": node_id,
             "target_pid": pid,
-            "action": "GracefulShutdown"
+            "action": CLUSTER_FIX_ACTION,
+            "job_id": job_id,
+            "approval": approval_token
         });
-        
-        match client.post(&fix_url)
+
+        let (command_id, error) = match hub_auth::authorize(client.post(&fix_url))
             .json(&fix_request)
             .send()
             .await
         {
-            Ok(response) => {
-                if response.status().is_success() {
-                    println!("  ✅ 节点 {} PID {}: 命令已发送", 
-                        node_id.bright_cyan(), pid.to_string().bright_yellow());
-                    success_count += 1;
-                } else {
-                    let error_text = response.text().await.unwrap_or_default();
-                    eprintln!("  ❌ 节点 {} PID {}: 发送失败 - {}", 
-                        node_id.bright_red(), pid.to_string().bright_yellow(), error_text);
-                    fail_count += 1;
-                }
+            Ok(response) if response.status().is_success() => {
+                let body: serde_json::Value = response.json().await.unwrap_or_default();
+                (body.get("command_id").and_then(|id| id.as_str()).map(str::to_string), None)
             }
-            Err(e) => {
-                eprintln!("  ❌ 节点 {} PID {}: 请求失败 - {}", 
-                    node_id.bright_red(), pid.to_string().bright_yellow(), e);
-                fail_count += 1;
+            Ok(response) => (None, Some(format!("发送失败 - {}", response.text().await.unwrap_or_default()))),
+            Err(e) => (None, Some(format!("请求失败 - {}", e))),
+        };
+
+        if !structured {
+            match error {
+                None => println!("  ✅ 节点 {} PID {}: 命令已发送{}",
+                    node_id.bright_cyan(), pid.to_string().bright_yellow(),
+                    command_id.as_deref().map(|id| format!(" ({})", id)).unwrap_or_default()),
+                Some(ref error) => eprintln!("  ❌ 节点 {} PID {}: {}",
+                    node_id.bright_red(), pid.to_string().bright_yellow(), error),
             }
         }
+        let status = command_id.as_ref().map(|_| FIX_PENDING.to_string());
+        report.targets.push(ClusterFixTarget {
+            node_id,
+            pid,
+            plan,
+            sent: error.is_none(),
+            command_id,
+            status,
+            message: None,
+            error,
+        });
     }
-    
+
+    // 步骤 7：等待节点回报执行结果
+    if !wait.is_zero() && report.targets.iter().any(|t| t.status.is_some()) {
+        if !structured {
+            println!();
+            println!("等待节点回报执行结果（最多 {} 秒）...", wait.as_secs());
+        }
+        wait_fix_results(hub_url, &mut report.targets, wait).await;
+        if !structured {
+            for target in &report.targets {
+                let Some(ref status) = target.status else { continue };
+                let message = target.message.as_deref().map(|m| format!(" - {}", m)).unwrap_or_default();
+                match status.as_str() {
+                    "success" => println!("  ✅ 节点 {} PID {}: 执行成功{}",
+                        target.node_id.bright_cyan(), target.pid.to_string().bright_yellow(), message),
+                    FIX_PENDING => println!("  ⏳ 节点 {} PID {}: 未在 {} 秒内收到回报",
+                        target.node_id.bright_cyan(), target.pid.to_string().bright_yellow(), wait.as_secs()),
+                    other => eprintln!("  ❌ 节点 {} PID {}: {}{}",
+                        target.node_id.bright_red(), target.pid.to_string().bright_yellow(), other, message),
+                }
+            }
+        }
+    }
+
+    let success_count = report.targets.iter().filter(|t| t.sent).count();
+    let fail_count = report.targets.len() - success_count;
+    let failed_runs = report
+        .targets
+        .iter()
+        .filter(|t| t.status.as_deref().is_some_and(|s| s != "success" && s != FIX_PENDING))
+        .count();
+    let status = if fail_count > 0 || failed_runs > 0 { ExitStatus::ActionFailed } else { ExitStatus::Clean };
+
+    if structured {
+        output.print(&report)?;
+        return Ok(status);
+    }
+
     println!();
     if success_count > 0 {
         println!("✅ 成功发送 {} 个修复命令", success_count.to_string().bright_green());
@@ -1359,49 +3482,37 @@ async fn cluster_fix(hub_url: &str, job_id: &str, auto_confirm: bool) -> Result<
     if fail_count > 0 {
         println!("❌ 失败 {} 个命令", fail_count.to_string().bright_red());
     }
-    
-    Ok(())
+    if failed_runs > 0 {
+        println!("❌ {} 个命令在节点上未能执行", failed_runs.to_string().bright_red());
+    }
+
+    Ok(status)
 }
 
-/// 从根因字符串中提取节点 ID 和 PID
-/// 支持格式：
-/// - "node-a: pid-1234 WaitsOn network"
-/// - "node-a::pid-1234: ..."
-/// - "node-a::pid-1234 WaitsOn ..."
-fn extract_node_and_pid(cause_str: &str) -> Option<(String, u32)> {
-    // 尝试匹配 "node-xxx::pid-yyy" 格式
-    if let Some(pos) = cause_str.find("::pid-") {
-        let node_part = &cause_str[..pos];
-        if let Some(pid_start) = cause_str[pos + 6..].find(|c: char| c.is_ascii_digit()) {
-            let pid_str = &cause_str[pos + 6 + pid_start..];
-            let pid_end = pid_str.find(|c: char| !c.is_ascii_digit()).unwrap_or(pid_str.len());
-            if let Ok(pid) = pid_str[..pid_end].parse::<u32>() {
-                return Some((node_part.to_string(), pid));
+/// Hub 尚未收到节点回报的命令状态
+const FIX_PENDING: &str = "pending";
+
+/// 轮询 `GET /api/v1/fix/<command_id>`，直到所有命令都有结果或超过 `wait`
+async fn wait_fix_results(hub_url: &str, targets: &mut [ClusterFixTarget], wait: std::time::Duration) {
+    let deadline = tokio::time::Instant::now() + wait;
+    loop {
+        for target in targets.iter_mut().filter(|t| t.status.as_deref() == Some(FIX_PENDING)) {
+            let Some(ref command_id) = target.command_id else { continue };
+            let url = format!("{}/api/v1/fix/{}", hub_url.trim_end_matches('/'), command_id);
+            match hub_auth::get_json(&url).await {
+                Ok(record) => {
+                    if let Some(status) = record.get("status").and_then(|s| s.as_str()) {
+                        target.status = Some(status.to_string());
+                        target.message = record.get("message").and_then(|m| m.as_str()).map(str::to_string);
+                    }
+                }
+                Err(e) => eprintln!("查询命令 {} 状态失败: {}", command_id, e),
             }
         }
-    }
-    
-    // 尝试匹配 "node-xxx: pid-yyy" 格式（单冒号）
-    if let Some(node_end) = cause_str.find(": pid-") {
-        let node_part = cause_str[..node_end].trim();
-        let pid_start = node_end + 6;
-        let pid_str = &cause_str[pid_start..];
-        let pid_end = pid_str.find(|c: char| !c.is_ascii_digit()).unwrap_or(pid_str.len());
-        if let Ok(pid) = pid_str[..pid_end].parse::<u32>() {
-            return Some((node_part.to_string(), pid));
-        }
-    }
-    
-    // 尝试匹配 "node-xxx pid-yyy" 格式（空格分隔）
-    if let Some(node_end) = cause_str.find(" pid-") {
-        let node_part = cause_str[..node_end].trim();
-        let pid_start = node_end + 5;
-        let pid_str = &cause_str[pid_start..];
-        let pid_end = pid_str.find(|c: char| !c.is_ascii_digit()).unwrap_or(pid_str.len());
-        if let Ok(pid) = pid_str[..pid_end].parse::<u32>() {
-            return Some((node_part.to_string(), pid));
+        let pending = targets.iter().any(|t| t.status.as_deref() == Some(FIX_PENDING));
+        if !pending || tokio::time::Instant::now() >= deadline {
+            return;
         }
+        tokio::time::sleep(std::time::Duration::from_secs(2).min(deadline - tokio::time::Instant::now())).await;
     }
-    
-    None
 }
@@ -3,8 +3,8 @@
 //! 暴露 Ark Agent 的指标供 Prometheus 抓取
 
 use prometheus::{
-    register_counter_vec, register_gauge_vec, register_histogram_vec,
-    CounterVec, GaugeVec, HistogramVec, Encoder, TextEncoder,
+    register_counter_vec, register_gauge_vec, register_histogram_vec, register_int_counter_vec,
+    CounterVec, GaugeVec, HistogramVec, IntCounterVec, Encoder, TextEncoder,
 };
 use std::sync::Arc;
 use ark_core::graph::StateGraph;
@@ -15,6 +15,7 @@ pub struct MetricsCollector {
     // 基础指标
     graph_nodes_total: GaugeVec,
     graph_edges_total: GaugeVec,
+    graph_reordered_events_total: IntCounterVec,
     events_processed_total: CounterVec,
     probe_errors_total: CounterVec,
     
@@ -23,6 +24,7 @@ pub struct MetricsCollector {
     process_wait_time_seconds: HistogramVec,
     error_count: CounterVec,
     rule_matches_total: CounterVec,
+    actions_executed_total: CounterVec,
 }
 
 impl MetricsCollector {
@@ -40,6 +42,11 @@ impl MetricsCollector {
                 "图中边总数",
                 &["edge_type"]
             )?,
+            graph_reordered_events_total: register_int_counter_vec!(
+                "ark_graph_reordered_events_total",
+                "乱序到达的事件数（reordered: 早于水位线；stale_update: 被跳过的节点状态覆盖）",
+                &["kind"]
+            )?,
             events_processed_total: register_counter_vec!(
                 "ark_events_processed_total",
                 "已处理事件总数",
@@ -73,6 +80,11 @@ impl MetricsCollector {
                 "规则匹配次数",
                 &["rule_name"]
             )?,
+            actions_executed_total: register_counter_vec!(
+                "ark_actions_executed_total",
+                "daemon 执行的修复动作数（execute_action RPC）",
+                &["action", "result"]
+            )?,
         })
     }
     
@@ -84,12 +96,7 @@ impl MetricsCollector {
         // 统计节点类型
         let mut node_counts = std::collections::HashMap::new();
         for node in nodes.values() {
-            let node_type = match node.node_type {
-                ark_core::graph::NodeType::Process => "process",
-                ark_core::graph::NodeType::Resource => "resource",
-                ark_core::graph::NodeType::Error => "error",
-            };
-            *node_counts.entry(node_type).or_insert(0) += 1;
+            *node_counts.entry(node.node_type.as_str()).or_insert(0) += 1;
         }
         
         // 更新节点指标
@@ -102,12 +109,7 @@ impl MetricsCollector {
         // 统计边类型
         let mut edge_counts = std::collections::HashMap::new();
         for edge in &edges {
-            let edge_type = match edge.edge_type {
-                ark_core::graph::EdgeType::Consumes => "consumes",
-                ark_core::graph::EdgeType::WaitsOn => "waits_on",
-                ark_core::graph::EdgeType::BlockedBy => "blocked_by",
-            };
-            *edge_counts.entry(edge_type).or_insert(0) += 1;
+            *edge_counts.entry(edge.edge_type.as_str()).or_insert(0) += 1;
         }
         
         // 更新边指标
@@ -116,6 +118,15 @@ impl MetricsCollector {
                 .with_label_values(&[edge_type])
                 .set(count as f64);
         }
+
+        // 更新乱序事件指标（图中的计数只增不减，计数器补上差值）
+        for (kind, count) in [
+            ("reordered", graph.reordered_event_count()),
+            ("stale_update", graph.stale_update_count()),
+        ] {
+            let counter = self.graph_reordered_events_total.with_label_values(&[kind]);
+            counter.inc_by(count.saturating_sub(counter.get()));
+        }
     }
     
     /// 记录事件处理
@@ -133,6 +144,7 @@ impl MetricsCollector {
             EventType::TopoLinkDown => "topo_link_down",
             EventType::IntentRun => "intent_run",
             EventType::ActionExec => "action_exec",
+            EventType::RuleMatched => "rule_matched",
         };
         
         self.events_processed_total
@@ -199,6 +211,13 @@ impl MetricsCollector {
             .inc();
     }
     
+    /// 记录修复动作执行结果
+    pub fn record_action(&self, action: &str, success: bool) {
+        self.actions_executed_total
+            .with_label_values(&[action, if success { "success" } else { "failure" }])
+            .inc();
+    }
+    
     /// 生成 Prometheus 格式的指标输出
     pub fn gather(&self) -> Result<String, prometheus::Error> {
         let encoder = TextEncoder::new();
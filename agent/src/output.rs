@@ -0,0 +1,208 @@
+//! CLI 输出格式与退出码
+//!
+//! 全局 `--output json|yaml|table` 控制查询类命令的输出：table 为彩色表格（默认），
+//! json / yaml 输出下面定义的稳定结构，进度提示不再写入 stdout，便于脚本解析。
+//!
+//! 退出码：0 未发现问题（或修复全部成功），1 执行出错，2 发现阻塞根因，3 修复动作部分失败，
+//! 4 daemon 健康检查异常。
+
+use crate::exec::{FixPlan, RollbackOutcome};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    /// 是否为机器可读格式（json / yaml）
+    pub fn is_structured(self) -> bool {
+        self != OutputFormat::Table
+    }
+
+    /// 以 json / yaml 格式输出到 stdout（table 格式由各命令自行打印）
+    pub fn print<T: Serialize>(self, value: &T) -> Result<(), String> {
+        let text = match self {
+            OutputFormat::Json => serde_json::to_string_pretty(value).map_err(|e| format!("序列化输出失败: {}", e))?,
+            OutputFormat::Yaml => serde_yaml::to_string(value).map_err(|e| format!("序列化输出失败: {}", e))?,
+            OutputFormat::Table => return Err("table 格式不支持结构化输出".to_string()),
+        };
+        println!("{}", text.trim_end());
+        Ok(())
+    }
+}
+
+/// 命令结果对应的退出码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// 未发现问题 / 执行成功
+    Clean = 0,
+    /// 发现阻塞根因
+    CausesFound = 2,
+    /// 修复动作部分或全部失败
+    ActionFailed = 3,
+    /// daemon 健康检查异常（探针停止或无事件、Hub 断开）
+    Degraded = 4,
+}
+
+impl ExitStatus {
+    /// 根据是否发现根因返回退出码
+    pub fn from_causes<T>(causes: &[T]) -> Self {
+        if causes.is_empty() {
+            ExitStatus::Clean
+        } else {
+            ExitStatus::CausesFound
+        }
+    }
+}
+
+/// `ps` 的单个进程
+#[derive(Debug, Serialize)]
+pub struct ProcessReport {
+    pub pid: u32,
+    pub job_id: Option<String>,
+    pub resources: Vec<String>,
+    pub state: String,
+}
+
+/// `why` 的结果
+#[derive(Debug, Serialize)]
+pub struct WhyReport {
+    pub pid: u32,
+    /// 基于根因文本的场景提示（如 "GPU OOM"）
+    pub scene: Option<String>,
+    pub causes: Vec<String>,
+}
+
+/// `why --follow` 输出的一次根因变化
+#[derive(Debug, Serialize)]
+pub struct WhyDelta {
+    pub pid: u32,
+    /// 本次评估时间（RFC 3339）
+    pub ts: String,
+    /// 新出现的根因
+    pub added: Vec<String>,
+    /// 已消失的根因
+    pub removed: Vec<String>,
+    /// 当前全部根因
+    pub causes: Vec<String>,
+}
+
+/// `fix` 的结果
+#[derive(Debug, Serialize)]
+pub struct FixReport {
+    pub pid: u32,
+    pub causes: Vec<String>,
+    /// 识别到的场景（未识别时为空，此时不会执行任何动作）
+    pub scene: Option<String>,
+    /// 提供动作的规则（未命中规则时按场景分析推荐）
+    pub rule: Option<String>,
+    pub planned_actions: Vec<String>,
+    /// 结构化的修复计划（步骤、前置条件、预期效果）
+    pub plan: Option<FixPlan>,
+    /// 逐步确认时未被确认（或 --yes 时动作策略要求人工确认）、没有执行的步骤序号
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_steps: Vec<usize>,
+    /// 动作策略禁止、没有执行的步骤序号
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub denied_steps: Vec<usize>,
+    /// 预演模式：executed_actions 为每个动作将要执行的步骤（按行分隔），failed_actions 为无法执行的动作
+    pub dry_run: bool,
+    /// 是否实际执行了修复
+    pub executed: bool,
+    pub success: bool,
+    pub message: String,
+    pub executed_actions: Vec<ActionReport>,
+    pub failed_actions: Vec<ActionReport>,
+    /// 中途失败时自动回滚的结果
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rolled_back: Vec<RollbackOutcome>,
+    /// 包含可回滚动作时的修复 ID（`ark fix --rollback <fix-id>`）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix_id: Option<String>,
+}
+
+/// `ark fix --rollback` 的结果
+#[derive(Debug, Serialize)]
+pub struct RollbackReport {
+    pub fix_id: String,
+    pub success: bool,
+    /// 按执行顺序（与原修复相反）的撤销结果
+    pub outcomes: Vec<RollbackOutcome>,
+}
+
+/// 单个修复动作的执行结果
+#[derive(Debug, Serialize)]
+pub struct ActionReport {
+    pub action: String,
+    /// 成功时为执行输出，失败时为错误信息
+    pub detail: String,
+}
+
+/// `cluster ps` 的单个进程
+#[derive(Debug, Serialize)]
+pub struct ClusterProcessReport {
+    pub node_id: String,
+    pub job_id: Option<String>,
+    pub pid: String,
+    /// 所属 Pod（<namespace>/<name>），不在 Pod 中时为空
+    pub pod: Option<String>,
+    pub state: String,
+}
+
+/// `cluster why` 的结果
+#[derive(Debug, Serialize)]
+pub struct ClusterWhyReport {
+    pub job_id: String,
+    pub causes: Vec<String>,
+    /// Hub 识别出的跨节点场景
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scenes: Vec<ClusterScene>,
+}
+
+/// Hub 的跨节点场景分析结果（如 collective_stall、pfc_storm、checkpoint_stall）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClusterScene {
+    pub scene: String,
+    pub confidence: f64,
+    pub root_causes: Vec<String>,
+    #[serde(default)]
+    pub recommendations: Vec<String>,
+    /// 涉及的节点
+    #[serde(default)]
+    pub nodes: Vec<String>,
+}
+
+/// `cluster fix` 的结果
+#[derive(Debug, Serialize)]
+pub struct ClusterFixReport {
+    pub job_id: String,
+    pub causes: Vec<String>,
+    pub targets: Vec<ClusterFixTarget>,
+}
+
+/// `cluster fix` 下发到单个进程的命令
+#[derive(Debug, Serialize)]
+pub struct ClusterFixTarget {
+    pub node_id: String,
+    pub pid: u32,
+    /// 节点上将执行的修复计划
+    pub plan: FixPlan,
+    /// 命令是否已成功提交给 Hub
+    pub sent: bool,
+    /// Hub 分配的命令 ID（与节点审计日志中的 command_id 对应）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_id: Option<String>,
+    /// 节点回报的执行状态（pending 表示尚未回报）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// 节点回报的执行结果或错误信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub error: Option<String>,
+}
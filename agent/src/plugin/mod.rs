@@ -9,6 +9,17 @@ use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::sync::mpsc;
 
+/// 解析探针 stdout 的一行（每行一个 JSON 事件，空行返回 None）
+///
+/// `SubprocessProbe` 和 `ark probe validate` 共用，保证校验结果与 daemon 的实际行为一致
+pub fn parse_event_line(line: &str) -> Option<Result<Event, serde_json::Error>> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    Some(serde_json::from_str::<Event>(line))
+}
+
 /// 子进程探针：通过启动外部脚本并读取其 stdout 来获取事件
 pub struct SubprocessProbe {
     command: String,
@@ -35,6 +46,8 @@ impl EventSource for SubprocessProbe {
                 .args(&self.args)
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
+                // 探针任务被中止（daemon 退出或重载时移除该探针）时一并结束子进程
+                .kill_on_drop(true)
                 .spawn()
                 .map_err(|e| format!("启动探针进程失败: {}", e))?;
 
@@ -55,13 +68,12 @@ impl EventSource for SubprocessProbe {
                         break;
                     }
                     Ok(_) => {
-                        // 解析 JSON 行
                         let line = line_buf.trim();
-                        if line.is_empty() {
+                        let Some(parsed) = parse_event_line(line) else {
                             continue;
-                        }
+                        };
 
-                        match serde_json::from_str::<Event>(line) {
+                        match parsed {
                             Ok(event) => {
                                 // 发送事件
                                 if let Err(e) = tx.send(event).await {
@@ -0,0 +1,208 @@
+//! 进程与 Pod 的对应关系
+//!
+//! 从 `/proc/<pid>/cgroup` 的 cgroup 路径解析容器 ID（containerd、CRI-O、Docker 的 cgroupfs 和 systemd 两种布局），
+//! 再到 kubelet 的 `/pods` 接口查询容器所在的 Pod，注入事件的 `pod`（`<namespace>/<name>`）和 `container_id` 字段。
+//! Hub 据此在"驱逐这个 Pod"和"向这个 PID 发信号"之间换算。
+//!
+//! 在 Kubernetes 中运行（有 `KUBERNETES_SERVICE_HOST`）或指定 `--kubelet-url` 时启用，默认地址为
+//! `https://127.0.0.1:10250`（DaemonSet 使用 hostNetwork），以 ServiceAccount 令牌认证（需要 `nodes/proxy` 的 get 权限）。
+//! Pod 列表缓存在内存中，遇到未知的容器 ID 时刷新，两次刷新至少间隔 5 秒。
+
+use ark_core::event::Event;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+
+/// 在 Kubernetes 中运行时的默认 kubelet 地址
+pub const DEFAULT_KUBELET_URL: &str = "https://127.0.0.1:10250";
+
+/// ServiceAccount 令牌和 CA
+const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+/// 两次刷新 Pod 列表的最小间隔
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// kubelet 连接参数
+#[derive(Debug, Clone, Default)]
+pub struct PodOptions {
+    pub kubelet_url: Option<String>,
+    /// 不校验 kubelet 的服务证书（kubelet 使用自签名证书时）
+    pub insecure_tls: bool,
+}
+
+/// 容器所在的 Pod
+#[derive(Debug, Clone)]
+pub struct PodInfo {
+    pub namespace: String,
+    pub name: String,
+    pub container_id: String,
+}
+
+impl PodInfo {
+    /// `<namespace>/<name>`
+    pub fn pod_ref(&self) -> String {
+        format!("{}/{}", self.namespace, self.name)
+    }
+}
+
+/// 按 PID 查找所属 Pod
+pub struct PodResolver {
+    client: reqwest::Client,
+    url: String,
+    /// 容器 ID → Pod
+    containers: RwLock<HashMap<String, PodInfo>>,
+    last_refresh: Mutex<Option<Instant>>,
+}
+
+impl PodResolver {
+    /// 不在 Kubernetes 中且未指定 kubelet 地址时返回 None
+    pub fn from_options(options: PodOptions) -> Result<Option<Arc<Self>>, String> {
+        let url = match options.kubelet_url {
+            Some(url) => url,
+            None if std::env::var_os("KUBERNETES_SERVICE_HOST").is_some() => DEFAULT_KUBELET_URL.to_string(),
+            None => return Ok(None),
+        };
+
+        let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(5));
+        if options.insecure_tls {
+            builder = builder.danger_accept_invalid_certs(true);
+        } else if let Ok(pem) = std::fs::read(Path::new(SERVICE_ACCOUNT_DIR).join("ca.crt")) {
+            let ca = reqwest::Certificate::from_pem(&pem).map_err(|e| format!("读取 ServiceAccount CA 失败: {}", e))?;
+            builder = builder.add_root_certificate(ca);
+        }
+        let client = builder.build().map_err(|e| format!("创建 kubelet 客户端失败: {}", e))?;
+
+        println!("[ark] 进程与 Pod 关联：kubelet {}", url);
+        Ok(Some(Arc::new(Self {
+            client,
+            url: url.trim_end_matches('/').to_string(),
+            containers: RwLock::new(HashMap::new()),
+            last_refresh: Mutex::new(None),
+        })))
+    }
+
+    /// 为带 PID 的事件注入所属 Pod 和容器（已有时不覆盖）
+    pub async fn annotate(&self, event: &mut Event) {
+        if event.pod.is_some() {
+            return;
+        }
+        let Some(pid) = event.pid else {
+            return;
+        };
+        if let Some(pod) = self.resolve(pid).await {
+            event.pod = Some(pod.pod_ref());
+            event.container_id = Some(pod.container_id);
+        }
+    }
+
+    /// 进程所属的 Pod，不在容器中或 kubelet 不可用时为 None
+    pub async fn resolve(&self, pid: u32) -> Option<PodInfo> {
+        let cgroup = tokio::fs::read_to_string(format!("/proc/{}/cgroup", pid)).await.ok()?;
+        let container_id = container_id_from_cgroup(&cgroup)?;
+        if let Some(pod) = self.containers.read().await.get(&container_id) {
+            return Some(pod.clone());
+        }
+
+        // 新的容器：刷新 Pod 列表（限频，避免非 Pod 容器的进程反复请求 kubelet）
+        {
+            let mut last_refresh = self.last_refresh.lock().await;
+            if last_refresh.is_some_and(|at| at.elapsed() < REFRESH_INTERVAL) {
+                return None;
+            }
+            *last_refresh = Some(Instant::now());
+        }
+        if let Err(e) = self.refresh().await {
+            eprintln!("[ark] 从 kubelet 获取 Pod 列表失败: {}", e);
+            return None;
+        }
+        self.containers.read().await.get(&container_id).cloned()
+    }
+
+    /// 重新获取本节点的 Pod 列表
+    async fn refresh(&self) -> Result<(), String> {
+        let mut request = self.client.get(format!("{}/pods", self.url));
+        // 令牌会轮换，每次读取
+        if let Ok(token) = std::fs::read_to_string(Path::new(SERVICE_ACCOUNT_DIR).join("token")) {
+            request = request.bearer_auth(token.trim());
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+        let pods: PodList = response.json().await.map_err(|e| format!("解析 Pod 列表失败: {}", e))?;
+
+        let mut containers = HashMap::new();
+        for pod in pods.items {
+            let statuses = pod.status.container_statuses.into_iter().chain(pod.status.init_container_statuses);
+            for status in statuses {
+                // containerID 形如 containerd://<id>、cri-o://<id>、docker://<id>
+                let Some(container_id) = status.container_id.as_deref().and_then(|id| id.split("://").nth(1)) else {
+                    continue;
+                };
+                containers.insert(
+                    container_id.to_string(),
+                    PodInfo {
+                        namespace: pod.metadata.namespace.clone(),
+                        name: pod.metadata.name.clone(),
+                        container_id: container_id.to_string(),
+                    },
+                );
+            }
+        }
+        *self.containers.write().await = containers;
+        Ok(())
+    }
+}
+
+/// 从 /proc/<pid>/cgroup 的内容中取容器 ID：路径中 64 位十六进制的一段，
+/// 兼容 `.../<id>`（cgroupfs）和 `.../cri-containerd-<id>.scope`、`crio-<id>.scope`、`docker-<id>.scope`（systemd）
+fn container_id_from_cgroup(content: &str) -> Option<String> {
+    content
+        .lines()
+        .filter_map(|line| line.splitn(3, ':').nth(2))
+        .flat_map(|path| path.rsplit('/'))
+        .find_map(|segment| {
+            let segment = segment.strip_suffix(".scope").unwrap_or(segment);
+            let id = segment.rsplit('-').next().unwrap_or(segment);
+            (id.len() == 64 && id.bytes().all(|b| b.is_ascii_hexdigit())).then(|| id.to_string())
+        })
+}
+
+/// kubelet `/pods` 的响应（只取需要的字段）
+#[derive(Deserialize)]
+struct PodList {
+    #[serde(default)]
+    items: Vec<PodItem>,
+}
+
+#[derive(Deserialize)]
+struct PodItem {
+    metadata: PodMetadata,
+    #[serde(default)]
+    status: PodStatus,
+}
+
+#[derive(Deserialize)]
+struct PodMetadata {
+    name: String,
+    #[serde(default)]
+    namespace: String,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PodStatus {
+    #[serde(default)]
+    container_statuses: Vec<ContainerStatus>,
+    #[serde(default)]
+    init_container_statuses: Vec<ContainerStatus>,
+}
+
+#[derive(Deserialize)]
+struct ContainerStatus {
+    #[serde(default, rename = "containerID")]
+    container_id: Option<String>,
+}
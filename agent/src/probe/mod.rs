@@ -1,21 +1,28 @@
 //! 原生探针模块
 //! 
-//! 使用 FFI 直接绑定 NVML 和华为 CANN 库，淘汰 Python 包装层
+//! 使用 FFI 直接绑定 NVML 和华为 CANN 库，淘汰 Python 包装层。
+//! 同时负责按配置并发启动多个探针（外部脚本 / 命令 / 原生探针）。
 
 pub mod nvml;
 pub mod cann;
+pub mod validate;
 
 use async_trait::async_trait;
 use ark_core::event::Event;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::sync::mpsc;
-use crate::plugin::EventSource;
+use crate::health::DaemonHealth;
+use crate::plugin::{EventSource, SubprocessProbe};
 
 /// 原生探针（统一接口）
 pub struct NativeProbe {
     probe_type: ProbeType,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
 pub enum ProbeType {
     Nvml,
     Cann,
@@ -47,3 +54,219 @@ impl EventSource for NativeProbe {
         }
     }
 }
+
+/// 探针配置文件（`ark run --probe-config`）
+///
+/// ```yaml
+/// probes:
+///   - path: /opt/ark/probes/ark-probe-nvml.py   # Python 脚本，名称默认取文件名
+///   - name: rdma
+///     command: /usr/local/bin/rdma-probe        # 任意可执行文件，stdout 每行一个 JSON 事件
+///     args: ["--interval", "1"]
+///   - native: cann                              # 原生探针：nvml / cann
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct ProbeConfig {
+    pub probes: Vec<ProbeSpec>,
+}
+
+/// 单个探针的配置
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ProbeSpec {
+    /// 探针名称（用于事件标记和 `ark status`），缺省时按类型推导
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub kind: ProbeKind,
+}
+
+/// 探针类型
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum ProbeKind {
+    /// Python 探针脚本
+    Script { path: PathBuf },
+    /// 外部命令
+    Command {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// 原生探针
+    Native { native: ProbeType },
+}
+
+impl ProbeSpec {
+    /// Python 探针脚本
+    pub fn script(path: PathBuf) -> Self {
+        Self { name: None, kind: ProbeKind::Script { path } }
+    }
+
+    /// 原生探针
+    pub fn native(probe_type: ProbeType) -> Self {
+        Self { name: None, kind: ProbeKind::Native { native: probe_type } }
+    }
+
+    /// 探针名称：显式配置优先，脚本取文件名（不含扩展名），命令取可执行文件名
+    fn default_name(&self) -> String {
+        if let Some(ref name) = self.name {
+            return name.clone();
+        }
+        let stem = |path: &Path| path.file_stem().map(|s| s.to_string_lossy().into_owned());
+        match &self.kind {
+            ProbeKind::Script { path } => stem(path),
+            ProbeKind::Command { command, .. } => stem(Path::new(command)),
+            ProbeKind::Native { native } => Some(NativeProbe::new(native.clone()).name().to_string()),
+        }
+        .unwrap_or_else(|| "probe".to_string())
+    }
+
+    /// 外部探针的命令行（原生探针返回 None）
+    pub fn command_line(&self) -> Option<(String, Vec<String>)> {
+        match &self.kind {
+            ProbeKind::Script { path } => {
+                // Windows 上通常没有 python3
+                let python = if cfg!(windows) { "python" } else { "python3" };
+                Some((python.to_string(), vec![path.to_string_lossy().to_string()]))
+            }
+            ProbeKind::Command { command, args } => Some((command.clone(), args.clone())),
+            ProbeKind::Native { .. } => None,
+        }
+    }
+
+    fn source(&self) -> Box<dyn EventSource> {
+        match &self.kind {
+            ProbeKind::Native { native } => Box::new(NativeProbe::new(native.clone())),
+            _ => {
+                let (command, args) = self.command_line().unwrap_or_default();
+                Box::new(SubprocessProbe::new(command, args))
+            }
+        }
+    }
+}
+
+/// 读取探针配置文件
+pub fn load_probe_config(path: &Path) -> Result<Vec<ProbeSpec>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取探针配置 {} 失败: {}", path.display(), e))?;
+    let config: ProbeConfig = serde_yaml::from_str(&content)
+        .map_err(|e| format!("解析探针配置 {} 失败: {}", path.display(), e))?;
+    Ok(config.probes)
+}
+
+/// daemon 的探针来源：命令行指定的探针加上探针配置文件（SIGHUP 时重新读取配置文件）
+pub struct ProbeOptions {
+    pub cli: Vec<ProbeSpec>,
+    pub config: Option<PathBuf>,
+}
+
+impl ProbeOptions {
+    /// 汇总当前的探针列表
+    pub fn specs(&self) -> Result<Vec<ProbeSpec>, String> {
+        let mut specs = self.cli.clone();
+        if let Some(path) = &self.config {
+            specs.extend(load_probe_config(path)?);
+        }
+        Ok(specs)
+    }
+}
+
+/// 为每个探针分配唯一名称（重名时追加序号，如 `nvml-2`）
+fn unique_names(specs: &[ProbeSpec]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let base = spec.default_name();
+        let mut name = base.clone();
+        let mut n = 1;
+        while names.contains(&name) {
+            n += 1;
+            name = format!("{}-{}", base, n);
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// 运行中的探针集合
+///
+/// 每个探针的事件经 `DaemonHealth::probe_sender` 中转：标记探针名称、统计健康状态后进入事件总线。
+/// `update` 只重启配置发生变化的探针，未变化的探针继续运行。
+pub struct ProbeSet {
+    tx: mpsc::Sender<Event>,
+    health: Arc<DaemonHealth>,
+    /// (名称, 配置, 任务)；配置为 None 表示内置 dummy_probe
+    running: Vec<(String, Option<ProbeSpec>, tokio::task::JoinHandle<()>)>,
+}
+
+impl ProbeSet {
+    /// 并发启动所有探针；未配置任何探针时启动内置 dummy_probe（向后兼容）
+    pub fn start(specs: Vec<ProbeSpec>, tx: mpsc::Sender<Event>, health: &Arc<DaemonHealth>) -> Self {
+        let mut set = Self { tx, health: Arc::clone(health), running: Vec::new() };
+        set.update(specs);
+        set
+    }
+
+    /// 切换到新的探针列表：停止已删除或配置变化的探针，启动新增的探针
+    pub fn update(&mut self, specs: Vec<ProbeSpec>) {
+        let wanted: Vec<(String, Option<ProbeSpec>)> = if specs.is_empty() {
+            vec![("dummy_probe".to_string(), None)]
+        } else {
+            unique_names(&specs).into_iter().zip(specs.into_iter().map(Some)).collect()
+        };
+
+        let (keep, stop): (Vec<_>, Vec<_>) = std::mem::take(&mut self.running)
+            .into_iter()
+            .partition(|(name, spec, _)| wanted.iter().any(|(n, s)| n == name && s == spec));
+        for (name, _, handle) in stop {
+            println!("[ark] 停止探针: {}", name);
+            handle.abort();
+            self.health.probe_removed(&name);
+        }
+        self.running = keep;
+
+        for (name, spec) in wanted {
+            if self.running.iter().any(|(n, _, _)| *n == name) {
+                continue;
+            }
+            let handle = self.spawn(&name, spec.as_ref());
+            self.running.push((name, spec, handle));
+        }
+    }
+
+    fn spawn(&self, name: &str, spec: Option<&ProbeSpec>) -> tokio::task::JoinHandle<()> {
+        let tx = self.health.probe_sender(name, self.tx.clone());
+        let health = Arc::clone(&self.health);
+        let name = name.to_string();
+        let Some(spec) = spec else {
+            eprintln!("[ark] 警告：使用内置 dummy_probe，建议使用 --probe 指定外部探针脚本");
+            return tokio::spawn(async move {
+                match ark_core::event::dummy_probe(tx).await {
+                    Ok(()) => health.probe_exited(&name, None),
+                    Err(e) => {
+                        eprintln!("[ark] 内置探针异常退出: {}", e);
+                        health.probe_exited(&name, Some(e.to_string()));
+                    }
+                }
+            });
+        };
+
+        let source = spec.source();
+        println!("[ark] 启动探针: {}", name);
+        tokio::spawn(async move {
+            match source.start_stream(tx).await {
+                Ok(()) => health.probe_exited(&name, None),
+                Err(e) => {
+                    eprintln!("[ark] 探针 {} 异常退出: {}", name, e);
+                    health.probe_exited(&name, Some(e));
+                }
+            }
+        })
+    }
+
+    /// 停止所有探针
+    pub fn abort_all(&mut self) {
+        for (_, _, handle) in self.running.drain(..) {
+            handle.abort();
+        }
+    }
+}
@@ -37,6 +37,10 @@ pub async fn start_nvml_probe(tx: mpsc::Sender<Event>) -> Result<(), String> {
             pid: None,
             value: "0".to_string(), // 占位值
             node_id: None,
+            probe: None,
+            traceparent: None,
+            pod: None,
+            container_id: None,
         };
         
         if let Err(e) = tx.send(event).await {
@@ -0,0 +1,173 @@
+//! `ark probe validate`：试运行外部探针并检查其输出
+//!
+//! 按 daemon 相同的方式启动探针（`.py` 脚本用 python3 运行），用 `parse_event_line` 解析 stdout，
+//! 统计格式错误、未知事件类型、缺少 entity_id 的行以及各类事件的速率。探针作者不必再到 daemon
+//! 日志里找解析失败的输出。
+
+use super::ProbeSpec;
+use crate::plugin::parse_event_line;
+use ark_core::event::{Event, EventType};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+/// 问题行在报告中最多保留的字符数
+const MAX_LINE_CHARS: usize = 200;
+
+/// 输出问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// 不是合法的 JSON 对象
+    Malformed,
+    /// event_type 不是已知的事件类型
+    UnknownEventType,
+    /// 缺少 entity_id 或为空
+    MissingEntityId,
+    /// 其他字段缺失或类型错误（如 ts）
+    InvalidField,
+}
+
+impl IssueKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IssueKind::Malformed => "malformed",
+            IssueKind::UnknownEventType => "unknown_event_type",
+            IssueKind::MissingEntityId => "missing_entity_id",
+            IssueKind::InvalidField => "invalid_field",
+        }
+    }
+}
+
+/// 单个问题行
+#[derive(Debug, Serialize)]
+pub struct LineIssue {
+    /// 行号（从 1 开始，含空行）
+    pub line: usize,
+    pub kind: IssueKind,
+    pub message: String,
+    pub content: String,
+}
+
+/// 校验结果
+#[derive(Debug, Serialize)]
+pub struct ValidationReport {
+    pub command: String,
+    pub duration_secs: f64,
+    pub lines: usize,
+    /// 解析成功的事件数
+    pub events: usize,
+    pub events_per_sec: f64,
+    /// 各事件类型的条数
+    pub event_types: BTreeMap<String, usize>,
+    /// 各问题类型的行数
+    pub issue_counts: BTreeMap<String, usize>,
+    /// 问题行示例（最多 max_issues 条）
+    pub issues: Vec<LineIssue>,
+    /// 探针在校验时间内自行退出时的退出码
+    pub exited: Option<i32>,
+}
+
+/// 解析一行并归类问题（空行返回 None）
+pub fn check_line(line: &str) -> Option<Result<Event, (IssueKind, String)>> {
+    let parsed = parse_event_line(line)?;
+    Some(match parsed {
+        Ok(event) if event.entity_id.trim().is_empty() => Err((IssueKind::MissingEntityId, "entity_id 为空".to_string())),
+        Ok(event) => Ok(event),
+        Err(e) => Err(classify_error(line, e)),
+    })
+}
+
+/// 解析失败时进一步定位原因，给出比 serde 错误更直接的提示
+fn classify_error(line: &str, error: serde_json::Error) -> (IssueKind, String) {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(line.trim()) else {
+        return (IssueKind::Malformed, format!("不是合法的 JSON: {}", error));
+    };
+    let Some(object) = value.as_object() else {
+        return (IssueKind::Malformed, "每行应为一个 JSON 对象".to_string());
+    };
+    match object.get("event_type") {
+        None => return (IssueKind::InvalidField, "缺少 event_type".to_string()),
+        Some(t) if serde_json::from_value::<EventType>(t.clone()).is_err() => {
+            return (IssueKind::UnknownEventType, format!("未知的事件类型: {}", t));
+        }
+        Some(_) => {}
+    }
+    if object.get("entity_id").and_then(|v| v.as_str()).is_none_or(|s| s.trim().is_empty()) {
+        return (IssueKind::MissingEntityId, "缺少 entity_id".to_string());
+    }
+    (IssueKind::InvalidField, error.to_string())
+}
+
+/// 运行探针 `duration` 时长并校验其输出
+pub async fn validate_probe(spec: &ProbeSpec, duration: Duration, max_issues: usize) -> Result<ValidationReport, String> {
+    let (command, args) = spec
+        .command_line()
+        .ok_or_else(|| "原生探针不经过 stdout 输出，无需校验".to_string())?;
+    let mut child = Command::new(&command)
+        .args(&args)
+        .stdout(Stdio::piped())
+        // 探针自己的报错直接显示给作者
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("启动探针 {} 失败: {}", command, e))?;
+    let stdout = child.stdout.take().ok_or_else(|| "无法获取探针 stdout".to_string())?;
+
+    let mut report = ValidationReport {
+        command: std::iter::once(command).chain(args).collect::<Vec<_>>().join(" "),
+        duration_secs: 0.0,
+        lines: 0,
+        events: 0,
+        events_per_sec: 0.0,
+        event_types: BTreeMap::new(),
+        issue_counts: BTreeMap::new(),
+        issues: Vec::new(),
+        exited: None,
+    };
+
+    let started = Instant::now();
+    let deadline = tokio::time::Instant::now() + duration;
+    let mut lines = BufReader::new(stdout).lines();
+    loop {
+        // next_line 可安全取消，到时后直接结束
+        let line = match tokio::time::timeout_at(deadline, lines.next_line()).await {
+            Err(_) => break,
+            Ok(Ok(Some(line))) => line,
+            Ok(Ok(None)) => break,
+            Ok(Err(e)) => return Err(format!("读取探针输出失败: {}", e)),
+        };
+        report.lines += 1;
+        match check_line(&line) {
+            None => {}
+            Some(Ok(event)) => {
+                report.events += 1;
+                *report.event_types.entry(event.event_type.to_string()).or_default() += 1;
+            }
+            Some(Err((kind, message))) => {
+                *report.issue_counts.entry(kind.as_str().to_string()).or_default() += 1;
+                if report.issues.len() < max_issues {
+                    report.issues.push(LineIssue {
+                        line: report.lines,
+                        kind,
+                        message,
+                        content: line.chars().take(MAX_LINE_CHARS).collect(),
+                    });
+                }
+            }
+        }
+    }
+
+    let elapsed = started.elapsed().as_secs_f64();
+    report.duration_secs = elapsed;
+    report.events_per_sec = if elapsed > 0.0 { report.events as f64 / elapsed } else { 0.0 };
+    report.exited = match child.try_wait() {
+        Ok(Some(status)) => Some(status.code().unwrap_or(-1)),
+        _ => None,
+    };
+    let _ = child.kill().await;
+    Ok(report)
+}
@@ -0,0 +1,371 @@
+//! `ark report`：生成事故报告（Markdown / HTML）
+//!
+//! 汇总根因分析（why）、场景分析、daemon 规则命中、可选的诊断建议（diag）、进程在状态图中的
+//! 上下文和相关的最近事件，直接用于事故复盘，不再需要复制彩色终端输出。
+
+use crate::diag::Diagnosis;
+use crate::hub_forwarder::get_node_id;
+use crate::ipc::IpcClient;
+use crate::scene::SceneIdentifier;
+use ark_core::event::Event;
+use ark_core::graph::{NodeKey, NodeType};
+use ark_core::rules::MatchStatus;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::Path;
+
+/// 报告格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// 按输出文件扩展名推断格式（.html / .htm 为 HTML，其余为 Markdown）
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("html") | Some("htm") => ReportFormat::Html,
+            _ => ReportFormat::Markdown,
+        }
+    }
+}
+
+/// 场景分析结果
+#[derive(Debug, Serialize)]
+pub struct SceneSection {
+    pub scene: String,
+    pub severity: String,
+    pub confidence: f64,
+    pub root_causes: Vec<String>,
+    pub recommendations: Vec<String>,
+    pub recommended_actions: Vec<String>,
+}
+
+/// 与进程相关的规则命中
+#[derive(Debug, Serialize)]
+pub struct RuleSection {
+    pub name: String,
+    /// fired / cooling_down / suppressed
+    pub status: String,
+    pub root_cause: String,
+    pub steps: Vec<String>,
+}
+
+/// 事故报告
+#[derive(Debug, Serialize)]
+pub struct IncidentReport {
+    pub pid: u32,
+    pub node_id: String,
+    pub generated_at: String,
+    pub job_id: Option<String>,
+    pub state: Option<String>,
+    pub causes: Vec<String>,
+    pub scene: Option<SceneSection>,
+    pub rules: Vec<RuleSection>,
+    pub diagnosis: Option<Diagnosis>,
+    /// 进程相关的边（出边和入边）
+    pub edges: Vec<String>,
+    /// 与进程及其关联实体相关的最近事件
+    pub events: Vec<Event>,
+}
+
+/// 从 daemon 收集报告内容；`diagnosis` 由调用方按需执行 diag 后传入
+pub async fn collect_report(
+    client: &IpcClient,
+    pid: u32,
+    events: usize,
+    diagnosis: Option<Diagnosis>,
+) -> Result<IncidentReport, String> {
+    if !client.ping().await? {
+        return Err("无法连接到 daemon，请先运行: ark run".to_string());
+    }
+
+    let causes = client.why_process(pid).await?;
+    let (graph, _) = client.graph_snapshot(0).await?;
+    let nodes = graph.get_nodes_async().await;
+    let process = nodes
+        .values()
+        .find(|n| n.node_type == NodeType::Process && n.key().pid() == Some(pid));
+
+    // 进程的边，以及边另一端的实体（用于筛选相关事件和规则命中）
+    let mut related: HashSet<String> = HashSet::from([format!("pid-{}", pid)]);
+    let mut edges = Vec::new();
+    if let Some(process) = process {
+        for edge in graph.get_all_edges_async().await {
+            if edge.from == process.id || edge.to == process.id {
+                edges.push(format!("{} -[{}]-> {}", edge.from, edge.edge_type.as_str(), edge.to));
+                related.insert(NodeKey::parse(&edge.from).entity().to_string());
+                related.insert(NodeKey::parse(&edge.to).entity().to_string());
+            }
+        }
+    }
+
+    let identifier = SceneIdentifier::new();
+    let scene = match identifier.identify_scene(&graph, pid).await {
+        Some(scene) => identifier.analyze_scene(scene, &graph, pid).await.map(|analysis| SceneSection {
+            scene: analysis.scene.as_str().to_string(),
+            severity: format!("{:?}", analysis.severity),
+            confidence: analysis.confidence,
+            root_causes: analysis.root_causes,
+            recommendations: analysis.recommendations,
+            recommended_actions: analysis.recommended_actions,
+        }),
+        None => None,
+    };
+
+    // daemon 未配置规则目录时没有规则命中
+    let rules = match client.match_rules().await {
+        Ok((_, matches)) => matches
+            .into_iter()
+            .filter(|m| m.entities.is_empty() || m.entities.iter().any(|e| related.contains(NodeKey::parse(e).entity())))
+            .map(|m| RuleSection {
+                status: match m.status {
+                    MatchStatus::Fired => "fired".to_string(),
+                    MatchStatus::CoolingDown { remaining_secs } => format!("cooling_down ({}s)", remaining_secs),
+                    MatchStatus::Suppressed { ref by } => format!("suppressed by {}", by),
+                },
+                root_cause: m.rule.root_cause_pattern.primary.clone(),
+                steps: m.rule.solution_steps.iter().map(|s| s.action.clone()).collect(),
+                name: m.rule.name,
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    let mut recent: Vec<Event> = client
+        .recent_events(usize::MAX)
+        .await?
+        .into_iter()
+        .rev()
+        .filter(|e| e.pid == Some(pid) || related.contains(&e.entity_id))
+        .take(events)
+        .collect();
+    recent.reverse();
+
+    Ok(IncidentReport {
+        pid,
+        node_id: get_node_id(),
+        generated_at: chrono::Local::now().to_rfc3339(),
+        job_id: process.and_then(|p| p.metadata.get("job_id").cloned()),
+        state: process.and_then(|p| p.metadata.get("state").cloned()),
+        causes,
+        scene,
+        rules,
+        diagnosis,
+        edges,
+        events: recent,
+    })
+}
+
+fn format_ts(ts: u64) -> String {
+    chrono::DateTime::from_timestamp_millis(ts as i64)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+impl IncidentReport {
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => self.to_markdown(),
+            ReportFormat::Html => self.to_html(),
+        }
+    }
+
+    /// 概要信息：(字段, 值)
+    fn summary(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("PID", self.pid.to_string()),
+            ("节点", self.node_id.clone()),
+            ("Job", self.job_id.clone().unwrap_or_else(|| "-".to_string())),
+            ("状态", self.state.clone().unwrap_or_else(|| "unknown".to_string())),
+            ("生成时间", self.generated_at.clone()),
+        ]
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# 事故报告：进程 {}\n", self.pid);
+        md.push_str("| 字段 | 值 |\n|---|---|\n");
+        for (key, value) in self.summary() {
+            let _ = writeln!(md, "| {} | {} |", key, value);
+        }
+
+        md.push_str("\n## 阻塞根因\n\n");
+        if self.causes.is_empty() {
+            md.push_str("未发现阻塞问题\n");
+        }
+        for (idx, cause) in self.causes.iter().enumerate() {
+            let _ = writeln!(md, "{}. {}", idx + 1, cause);
+        }
+
+        if let Some(scene) = &self.scene {
+            let _ = writeln!(
+                md,
+                "\n## 场景分析\n\n- 场景：`{}`\n- 严重程度：{}\n- 置信度：{:.0}%",
+                scene.scene,
+                scene.severity,
+                scene.confidence * 100.0
+            );
+            for (title, items) in [
+                ("根因", &scene.root_causes),
+                ("建议", &scene.recommendations),
+                ("推荐动作", &scene.recommended_actions),
+            ] {
+                if !items.is_empty() {
+                    let _ = writeln!(md, "\n**{}**\n", title);
+                    for item in items {
+                        let _ = writeln!(md, "- {}", item);
+                    }
+                }
+            }
+        }
+
+        if !self.rules.is_empty() {
+            md.push_str("\n## 规则命中\n");
+            for rule in &self.rules {
+                let _ = writeln!(md, "\n### {}（{}）\n\n根因：{}\n", rule.name, rule.status, rule.root_cause);
+                for (idx, step) in rule.steps.iter().enumerate() {
+                    let _ = writeln!(md, "{}. {}", idx + 1, step);
+                }
+            }
+        }
+
+        if let Some(diagnosis) = &self.diagnosis {
+            let _ = writeln!(
+                md,
+                "\n## 诊断建议\n\n置信度：{:.0}%\n\n{}",
+                diagnosis.confidence * 100.0,
+                diagnosis.recommendation.trim()
+            );
+        }
+
+        md.push_str("\n## 状态图上下文\n\n");
+        if self.edges.is_empty() {
+            md.push_str("进程不在状态图中或没有关联边\n");
+        } else {
+            md.push_str("```\n");
+            for edge in &self.edges {
+                let _ = writeln!(md, "{}", edge);
+            }
+            md.push_str("```\n");
+        }
+
+        let _ = writeln!(md, "\n## 最近事件（{} 条）\n", self.events.len());
+        if !self.events.is_empty() {
+            md.push_str("| 时间 | 类型 | 实体 | 值 |\n|---|---|---|---|\n");
+            for event in &self.events {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} |",
+                    format_ts(event.ts),
+                    event.event_type,
+                    event.entity_id,
+                    event.value.replace('|', "\\|")
+                );
+            }
+        }
+        md
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"zh\">\n<head>\n<meta charset=\"utf-8\">\n<title>事故报告：进程 {}</title>\n\
+             <style>body{{font-family:sans-serif;max-width:960px;margin:2em auto}}\
+             table{{border-collapse:collapse}}td,th{{border:1px solid #ccc;padding:4px 8px;text-align:left}}\
+             pre{{background:#f4f4f4;padding:8px}}</style>\n</head>\n<body>\n",
+            self.pid
+        );
+        let _ = writeln!(html, "<h1>事故报告：进程 {}</h1>\n<table>", self.pid);
+        for (key, value) in self.summary() {
+            let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", key, escape(&value));
+        }
+        html.push_str("</table>\n<h2>阻塞根因</h2>\n");
+        if self.causes.is_empty() {
+            html.push_str("<p>未发现阻塞问题</p>\n");
+        } else {
+            html.push_str(&list("ol", &self.causes));
+        }
+
+        if let Some(scene) = &self.scene {
+            let _ = writeln!(
+                html,
+                "<h2>场景分析</h2>\n<p>场景：<code>{}</code>，严重程度：{}，置信度：{:.0}%</p>",
+                escape(&scene.scene),
+                escape(&scene.severity),
+                scene.confidence * 100.0
+            );
+            for (title, items) in [
+                ("根因", &scene.root_causes),
+                ("建议", &scene.recommendations),
+                ("推荐动作", &scene.recommended_actions),
+            ] {
+                if !items.is_empty() {
+                    let _ = writeln!(html, "<h3>{}</h3>", title);
+                    html.push_str(&list("ul", items));
+                }
+            }
+        }
+
+        if !self.rules.is_empty() {
+            html.push_str("<h2>规则命中</h2>\n");
+            for rule in &self.rules {
+                let _ = writeln!(
+                    html,
+                    "<h3>{}（{}）</h3>\n<p>根因：{}</p>",
+                    escape(&rule.name),
+                    escape(&rule.status),
+                    escape(&rule.root_cause)
+                );
+                html.push_str(&list("ol", &rule.steps));
+            }
+        }
+
+        if let Some(diagnosis) = &self.diagnosis {
+            let _ = writeln!(
+                html,
+                "<h2>诊断建议</h2>\n<p>置信度：{:.0}%</p>\n<pre>{}</pre>",
+                diagnosis.confidence * 100.0,
+                escape(diagnosis.recommendation.trim())
+            );
+        }
+
+        html.push_str("<h2>状态图上下文</h2>\n");
+        if self.edges.is_empty() {
+            html.push_str("<p>进程不在状态图中或没有关联边</p>\n");
+        } else {
+            let _ = writeln!(html, "<pre>{}</pre>", escape(&self.edges.join("\n")));
+        }
+
+        let _ = writeln!(html, "<h2>最近事件（{} 条）</h2>", self.events.len());
+        if !self.events.is_empty() {
+            html.push_str("<table>\n<tr><th>时间</th><th>类型</th><th>实体</th><th>值</th></tr>\n");
+            for event in &self.events {
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    format_ts(event.ts),
+                    event.event_type,
+                    escape(&event.entity_id),
+                    escape(&event.value)
+                );
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+fn list(tag: &str, items: &[String]) -> String {
+    let items: String = items.iter().map(|item| format!("<li>{}</li>", escape(item))).collect();
+    format!("<{tag}>{items}</{tag}>\n")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,138 @@
+//! 规则远程同步
+//!
+//! 500 个节点手工维护 `rules/` 目录不可行：daemon 从 Hub（`GET /api/v1/rules`）或任意
+//! HTTP 地址拉取规则包，校验后写入本地缓存目录（即 `--rules-dir`），并定期刷新。
+//! 缓存目录由热加载规则引擎监听，写入后自动生效；拉取失败时继续使用缓存。
+
+use ark_core::rules::{rules_key_from_env, RuleBundle};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// 规则来源
+#[derive(Debug, Clone)]
+pub enum RuleSource {
+    /// 本地目录（默认，不做同步）
+    Local,
+    /// 远程 HTTP 地址，返回 JSON 格式的规则包
+    Http(String),
+    /// Hub 的规则接口（请求携带 ARK_HUB_TOKEN）
+    Hub(String),
+}
+
+impl RuleSource {
+    /// 解析 `--rules-source`：`hub` 表示从 `--hub-api` 的 `/api/v1/rules` 拉取，
+    /// 也可以直接给出 http(s) 地址
+    pub fn parse(spec: Option<&str>, hub_api: Option<&str>) -> Result<Self, String> {
+        match spec {
+            None => Ok(RuleSource::Local),
+            Some("hub") => {
+                let hub_api = hub_api.ok_or("--rules-source hub 需要同时指定 --hub-api")?;
+                Ok(RuleSource::Hub(format!("{}/api/v1/rules", hub_api.trim_end_matches('/'))))
+            }
+            Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+                Ok(RuleSource::Http(url.to_string()))
+            }
+            Some(other) => Err(format!("无效的规则来源: {}（可选: hub 或 http(s):// 地址）", other)),
+        }
+    }
+
+    /// 远程来源的地址（本地来源为 None）
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            RuleSource::Local => None,
+            RuleSource::Http(url) | RuleSource::Hub(url) => Some(url),
+        }
+    }
+}
+
+/// daemon 的规则配置：本地目录（远程来源时作为缓存目录）+ 来源 + 刷新间隔
+#[derive(Debug, Clone)]
+pub struct RuleOptions {
+    pub dir: Option<PathBuf>,
+    pub source: RuleSource,
+    pub refresh: Duration,
+    /// 唤醒后立即刷新（Hub 通知规则已更新时，见 `POST /api/v1/rules/reload`）
+    pub refresh_now: Arc<Notify>,
+}
+
+/// 拉取规则包（只向 Hub 出示令牌，不泄露给其他地址）
+async fn fetch(source: &RuleSource, url: &str) -> Result<RuleBundle, String> {
+    let mut request = reqwest::Client::new().get(url);
+    if let RuleSource::Hub(_) = source {
+        request = crate::hub_auth::authorize(request);
+    }
+    let response = request
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| format!("拉取规则失败: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("拉取规则失败: HTTP {}", response.status()));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("解析规则包失败: {}", e))
+}
+
+/// 同步一次：拉取、校验（配置了 ARK_RULES_KEY 时要求签名），写入缓存目录
+/// 返回缓存是否有变化
+pub async fn sync_once(source: &RuleSource, cache_dir: &Path) -> Result<bool, String> {
+    let Some(url) = source.url() else {
+        return Ok(false);
+    };
+    let bundle = fetch(source, url).await?;
+    let key = rules_key_from_env();
+    let rules = bundle.verify(key.as_deref())?;
+    let changed = bundle.write_to_dir(cache_dir)?;
+    if changed {
+        println!(
+            "[rules] 已同步 {} 条规则（校验和 {}…）: {}",
+            rules.len(),
+            &bundle.checksum[..12],
+            url
+        );
+    }
+    Ok(changed)
+}
+
+/// 启动时的首次同步：失败时打印警告并使用缓存目录中已有的规则
+pub async fn initial_sync(options: &RuleOptions) -> Result<(), String> {
+    if options.source.url().is_none() {
+        return Ok(());
+    }
+    let dir = options
+        .dir
+        .as_ref()
+        .ok_or("远程规则来源需要通过 --rules-dir 指定本地缓存目录")?;
+    if let Err(e) = sync_once(&options.source, dir).await {
+        eprintln!("[rules] 警告：首次同步规则失败，使用缓存目录中的规则: {}", e);
+        std::fs::create_dir_all(dir).map_err(|e| format!("创建规则缓存目录失败: {}", e))?;
+    }
+    Ok(())
+}
+
+/// 后台定期刷新，`refresh_now` 被唤醒时提前刷新（本地来源时不启动）
+pub fn spawn_refresh(options: &RuleOptions) -> Option<tokio::task::JoinHandle<()>> {
+    options.source.url()?;
+    let source = options.source.clone();
+    let dir = options.dir.clone()?;
+    let refresh = options.refresh;
+    let refresh_now = Arc::clone(&options.refresh_now);
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(refresh);
+        // 首个 tick 立即返回，启动时已同步过
+        interval.tick().await;
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = refresh_now.notified() => interval.reset(),
+            }
+            if let Err(e) = sync_once(&source, &dir).await {
+                eprintln!("[rules] 刷新规则失败，继续使用缓存: {}", e);
+            }
+        }
+    }))
+}
@@ -1,4 +1,4 @@
-use ark_core::graph::StateGraph;
+use ark_core::graph::{Edge, EdgeType, StateGraph};
 use crate::scene::types::{AnalysisResult, SceneType};
 
 /// 场景分析器 trait
@@ -11,6 +11,29 @@ pub trait SceneAnalyzer: Send + Sync {
     fn scene_type(&self) -> SceneType;
 }
 
+/// 从 target 出发的指定类型边，按置信度从高到低排序
+pub fn outgoing_edges_by_confidence<'a>(
+    edges: &'a [Edge],
+    target: &str,
+    edge_type: EdgeType,
+) -> Vec<&'a Edge> {
+    let mut matched: Vec<&Edge> = edges
+        .iter()
+        .filter(|e| e.from == target && e.edge_type == edge_type)
+        .collect();
+    matched.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    matched
+}
+
+/// 按最强证据边的置信度折算场景置信度：证据确定时取 base，仅有弱证据时最低降到 base 的一半
+pub fn scale_by_evidence(base: f64, edge_confidence: f64) -> f64 {
+    base * (0.5 + 0.5 * edge_confidence.clamp(0.0, 1.0))
+}
+
 /// 场景注册表
 pub struct SceneRegistry {
     analyzers: Vec<Box<dyn SceneAnalyzer>>,
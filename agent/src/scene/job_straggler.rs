@@ -0,0 +1,83 @@
+use ark_core::graph::StateGraph;
+use ark_core::straggler::DEFAULT_STRAGGLER_MARGIN;
+use crate::scene::analyzer::SceneAnalyzer;
+use crate::scene::types::{AnalysisResult, SceneType, Severity};
+
+/// 多卡任务掉队场景分析器
+/// 对比同一 job 内所有进程的 GPU 利用率/吞吐，找出落后同伴超过 margin 的 rank
+pub struct JobStragglerAnalyzer {
+    /// 相对同伴中位数的落后比例阈值（如 0.2 表示低 20%）
+    pub margin: f64,
+}
+
+impl Default for JobStragglerAnalyzer {
+    fn default() -> Self {
+        Self {
+            margin: DEFAULT_STRAGGLER_MARGIN,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SceneAnalyzer for JobStragglerAnalyzer {
+    fn scene_type(&self) -> SceneType {
+        SceneType::JobStraggler
+    }
+
+    async fn analyze(&self, graph: &StateGraph, target: &str) -> AnalysisResult {
+        let mut root_causes = Vec::new();
+        let mut recommendations = Vec::new();
+
+        let job_id = graph
+            .node(target)
+            .await
+            .and_then(|n| n.metadata.get("job_id").cloned());
+
+        let Some(job_id) = job_id else {
+            return AnalysisResult {
+                scene: SceneType::JobStraggler,
+                root_causes: vec!["进程未关联 job，无法对比同伴".to_string()],
+                confidence: 0.0,
+                recommendations: vec![],
+                recommended_actions: vec![],
+                severity: Severity::Info,
+            };
+        };
+
+        let stragglers = graph.find_job_stragglers(&job_id, self.margin).await;
+        let target_is_straggler = stragglers.iter().any(|s| s.process_id == target);
+
+        for straggler in &stragglers {
+            root_causes.push(straggler.describe());
+        }
+
+        if stragglers.is_empty() {
+            root_causes.push(format!("job {} 内各进程进度相近，未发现掉队 rank", job_id));
+        } else {
+            recommendations.push("检查掉队 GPU 的温度、功耗和降频状态（nvidia-smi -q -d PERFORMANCE）".to_string());
+            recommendations.push("检查掉队 rank 所在节点的 PCIe/NVLink 带宽".to_string());
+            recommendations.push("检查该 rank 的数据分片是否明显更大".to_string());
+        }
+
+        let mut recommended_actions = Vec::new();
+        if !stragglers.is_empty() {
+            recommended_actions.push("将掉队 GPU 所在节点加入隔离候选，从下一次调度中排除".to_string());
+            recommended_actions.push("保存 Checkpoint 后在健康节点上重启该 rank".to_string());
+        }
+
+        AnalysisResult {
+            scene: SceneType::JobStraggler,
+            root_causes,
+            confidence: if target_is_straggler {
+                0.85
+            } else if !stragglers.is_empty() {
+                0.6
+            } else {
+                0.2
+            },
+            recommendations,
+            recommended_actions,
+            severity: Severity::Warning,
+        }
+    }
+}
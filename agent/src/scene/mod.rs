@@ -9,6 +9,7 @@ mod workload_stalled;
 mod storage_io_error;
 mod storage_slow;
 mod checkpoint_timeout;
+mod job_straggler;
 
 pub use types::{SceneType, AnalysisResult, Severity};
 pub use analyzer::{SceneAnalyzer, SceneRegistry};
@@ -21,16 +22,23 @@ pub use workload_stalled::WorkloadStalledAnalyzer;
 pub use storage_io_error::StorageIoErrorAnalyzer;
 pub use storage_slow::StorageSlowAnalyzer;
 pub use checkpoint_timeout::CheckpointTimeoutAnalyzer;
+pub use job_straggler::JobStragglerAnalyzer;
 
 use ark_core::graph::StateGraph;
 
 /// 场景识别器
 pub struct SceneIdentifier {
     registry: SceneRegistry,
+    straggler_margin: f64,
 }
 
 impl SceneIdentifier {
     pub fn new() -> Self {
+        Self::with_straggler_margin(ark_core::straggler::DEFAULT_STRAGGLER_MARGIN)
+    }
+
+    /// 使用指定的掉队阈值创建（相对同伴中位数的落后比例）
+    pub fn with_straggler_margin(straggler_margin: f64) -> Self {
         let mut registry = SceneRegistry::new();
         
         // 注册所有场景分析器（按优先级顺序）
@@ -43,8 +51,9 @@ impl SceneIdentifier {
         registry.register(StorageIoErrorAnalyzer);
         registry.register(StorageSlowAnalyzer);
         registry.register(CheckpointTimeoutAnalyzer);
+        registry.register(JobStragglerAnalyzer { margin: straggler_margin });
         
-        Self { registry }
+        Self { registry, straggler_margin }
     }
 
     /// 识别场景类型
@@ -143,6 +152,14 @@ impl SceneIdentifier {
             }
         }
 
+        // 检查多卡任务掉队：进程本身无异常，但明显慢于同 job 的其他 rank
+        if let Some(job_id) = nodes.get(&pid_str).and_then(|n| n.metadata.get("job_id")) {
+            let stragglers = graph.find_job_stragglers(job_id, self.straggler_margin).await;
+            if stragglers.iter().any(|s| s.process_id == pid_str) {
+                return Some(SceneType::JobStraggler);
+            }
+        }
+
         None
     }
 
@@ -1,5 +1,5 @@
 use ark_core::graph::{EdgeType, StateGraph};
-use crate::scene::analyzer::SceneAnalyzer;
+use crate::scene::analyzer::{outgoing_edges_by_confidence, scale_by_evidence, SceneAnalyzer};
 use crate::scene::types::{AnalysisResult, SceneType};
 
 /// 网络阻塞场景分析器
@@ -18,20 +18,23 @@ impl SceneAnalyzer for NetworkStallAnalyzer {
         let edges = graph.get_all_edges_async().await;
         let nodes = graph.get_nodes_async().await;
 
-        // 查找 WaitsOn 网络资源的边
+        // 查找 WaitsOn 网络资源的边（按置信度排序，反复丢包的边排在单次低带宽采样之前）
         let mut network_wait_count = 0;
-        for edge in &edges {
-            if edge.from == target && edge.edge_type == EdgeType::WaitsOn {
-                if edge.to.starts_with("network-") || edge.to.contains("net") {
-                    network_wait_count += 1;
-                    root_causes.push(format!("等待网络资源: {}", edge.to));
-                    
-                    if let Some(node) = nodes.get(&edge.to) {
-                        if let Some(drop_rate) = node.metadata.get("drop_rate") {
-                            if let Ok(rate) = drop_rate.parse::<f64>() {
-                                if rate > 10.0 {
-                                    root_causes.push(format!("网络 {} 丢包率过高: {:.1}%", edge.to, rate));
-                                }
+        let mut strongest_wait: f64 = 0.0;
+        for edge in outgoing_edges_by_confidence(&edges, target, EdgeType::WaitsOn) {
+            if edge.to.starts_with("network-") || edge.to.contains("net") {
+                network_wait_count += 1;
+                strongest_wait = strongest_wait.max(edge.confidence);
+                root_causes.push(format!(
+                    "等待网络资源: {}（置信度 {:.2}，佐证 {} 次）",
+                    edge.to, edge.confidence, edge.weight
+                ));
+                
+                if let Some(node) = nodes.get(&edge.to) {
+                    if let Some(drop_rate) = node.metadata.get("drop_rate") {
+                        if let Ok(rate) = drop_rate.parse::<f64>() {
+                            if rate > 10.0 {
+                                root_causes.push(format!("网络 {} 丢包率过高: {:.1}%", edge.to, rate));
                             }
                         }
                     }
@@ -67,7 +70,11 @@ impl SceneAnalyzer for NetworkStallAnalyzer {
         AnalysisResult {
             scene: SceneType::NetworkStall,
             root_causes,
-            confidence: if network_wait_count > 0 { 0.85 } else { 0.6 },
+            confidence: if network_wait_count > 0 {
+                scale_by_evidence(0.85, strongest_wait)
+            } else {
+                0.6
+            },
             recommendations,
             recommended_actions,
             severity: crate::scene::types::Severity::Warning,
@@ -1,5 +1,5 @@
 use ark_core::graph::{EdgeType, StateGraph};
-use crate::scene::analyzer::SceneAnalyzer;
+use crate::scene::analyzer::{outgoing_edges_by_confidence, scale_by_evidence, SceneAnalyzer};
 use crate::scene::types::{AnalysisResult, SceneType, Severity};
 
 /// 存储慢速场景分析器
@@ -18,37 +18,37 @@ impl SceneAnalyzer for StorageSlowAnalyzer {
         let edges = graph.get_all_edges_async().await;
         let nodes = graph.get_nodes_async().await;
 
-        // 查找 WaitsOn 存储的边，并检查 IOPS 和延迟
+        // 查找 WaitsOn 存储的边，并检查 IOPS 和延迟（按边置信度排序）
         let mut slow_storage = Vec::new();
+        let mut strongest_wait: f64 = 0.0;
         
-        for edge in &edges {
-            if edge.from == target && edge.edge_type == EdgeType::WaitsOn {
-                if edge.to.contains("storage") || edge.to.contains("disk") || edge.to.contains("nvme") {
-                    if let Some(node) = nodes.get(&edge.to) {
-                        // 检查 IOPS（如果低于阈值）
-                        if let Some(iops) = node.metadata.get("iops") {
-                            if let Ok(iops_val) = iops.parse::<f64>() {
-                                if iops_val < 100.0 {
-                                    slow_storage.push((edge.to.clone(), format!("IOPS 过低: {:.0}", iops_val)));
-                                }
+        for edge in outgoing_edges_by_confidence(&edges, target, EdgeType::WaitsOn) {
+            if edge.to.contains("storage") || edge.to.contains("disk") || edge.to.contains("nvme") {
+                strongest_wait = strongest_wait.max(edge.confidence);
+                if let Some(node) = nodes.get(&edge.to) {
+                    // 检查 IOPS（如果低于阈值）
+                    if let Some(iops) = node.metadata.get("iops") {
+                        if let Ok(iops_val) = iops.parse::<f64>() {
+                            if iops_val < 100.0 {
+                                slow_storage.push((edge.to.clone(), format!("IOPS 过低: {:.0}", iops_val)));
                             }
                         }
-                        
-                        // 检查 IO 延迟
-                        if let Some(latency) = node.metadata.get("latency_ms") {
-                            if let Ok(latency_val) = latency.parse::<f64>() {
-                                if latency_val > 100.0 {
-                                    slow_storage.push((edge.to.clone(), format!("IO 延迟过高: {:.1}ms", latency_val)));
-                                }
+                    }
+                    
+                    // 检查 IO 延迟
+                    if let Some(latency) = node.metadata.get("latency_ms") {
+                        if let Ok(latency_val) = latency.parse::<f64>() {
+                            if latency_val > 100.0 {
+                                slow_storage.push((edge.to.clone(), format!("IO 延迟过高: {:.1}ms", latency_val)));
                             }
                         }
-                        
-                        // 检查队列深度
-                        if let Some(qdepth) = node.metadata.get("qdepth") {
-                            if let Ok(qdepth_val) = qdepth.parse::<f64>() {
-                                if qdepth_val > 100.0 {
-                                    slow_storage.push((edge.to.clone(), format!("队列深度过高: {:.0}", qdepth_val)));
-                                }
+                    }
+                    
+                    // 检查队列深度
+                    if let Some(qdepth) = node.metadata.get("qdepth") {
+                        if let Ok(qdepth_val) = qdepth.parse::<f64>() {
+                            if qdepth_val > 100.0 {
+                                slow_storage.push((edge.to.clone(), format!("队列深度过高: {:.0}", qdepth_val)));
                             }
                         }
                     }
@@ -76,7 +76,11 @@ impl SceneAnalyzer for StorageSlowAnalyzer {
         AnalysisResult {
             scene: SceneType::StorageSlow,
             root_causes,
-            confidence: if !slow_storage.is_empty() { 0.8 } else { 0.6 },
+            confidence: if !slow_storage.is_empty() {
+                scale_by_evidence(0.8, strongest_wait)
+            } else {
+                0.6
+            },
             recommendations,
             recommended_actions,
             severity: Severity::Warning,
@@ -10,6 +10,9 @@ pub enum SceneType {
     NpuSubhealth,        // NPU 亚健康
     WorkloadStalled,     // 工作负载卡死
     
+    // 任务相关
+    JobStraggler,        // 多卡任务中单个 rank 掉队
+    
     // 网络相关
     NetworkStall,        // 网络阻塞
     NetworkDrop,         // 网络丢包
@@ -31,6 +34,7 @@ impl SceneType {
             SceneType::GpuError => "gpu_error",
             SceneType::NpuSubhealth => "npu_subhealth",
             SceneType::WorkloadStalled => "workload_stalled",
+            SceneType::JobStraggler => "job_straggler",
             SceneType::NetworkStall => "network_stall",
             SceneType::NetworkDrop => "network_drop",
             SceneType::StorageIoError => "storage_io_error",
@@ -0,0 +1,139 @@
+//! `ark snapshot`：把诊断信息打包成一个 tar.gz，便于附到工单
+//!
+//! 通过 IPC 收集状态图导出、最近事件、规则命中历史、审计记录和 daemon 健康状态，再从 Prometheus
+//! 端点抓取指标。单项收集失败只记录在 manifest.json 中，不影响其他内容的打包。
+
+use crate::hub_forwarder::get_node_id;
+use crate::ipc::IpcClient;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// 快照参数
+pub struct SnapshotOptions {
+    /// 输出文件（未指定时在当前目录按节点和时间命名）
+    pub out: Option<PathBuf>,
+    /// 打包的最近事件条数
+    pub events: usize,
+    /// 打包的最近审计记录条数
+    pub audit_entries: usize,
+    /// daemon 的 Prometheus 指标地址
+    pub metrics_url: String,
+}
+
+/// 快照清单（同时写入包内的 manifest.json）
+#[derive(Debug, Serialize)]
+pub struct SnapshotManifest {
+    pub path: PathBuf,
+    pub created_at: String,
+    pub node_id: String,
+    pub version: String,
+    /// 成功收集的文件
+    pub files: Vec<String>,
+    /// 收集失败的项及原因
+    pub errors: BTreeMap<String, String>,
+}
+
+/// 收集诊断信息并写入 tar.gz
+pub async fn create_snapshot(client: &IpcClient, options: &SnapshotOptions) -> Result<SnapshotManifest, String> {
+    if !client.ping().await? {
+        return Err("无法连接到 daemon，请先运行: ark run".to_string());
+    }
+
+    let now = chrono::Local::now();
+    let node_id = get_node_id();
+    let prefix = format!("ark-snapshot-{}-{}", node_id, now.format("%Y%m%d-%H%M%S"));
+    let path = options.out.clone().unwrap_or_else(|| PathBuf::from(format!("{}.tar.gz", prefix)));
+
+    let collected: Vec<(&str, Result<String, String>)> = vec![
+        ("graph.json", client.graph_export("json").await),
+        ("events.jsonl", client.recent_events(options.events).await.map(|events| to_json_lines(&events))),
+        (
+            "rule_history.json",
+            client
+                .rule_history(options.events)
+                .await
+                .and_then(|(generation, matches)| to_pretty(&json!({ "generation": generation, "matches": matches }))),
+        ),
+        ("audit.jsonl", client.audit_entries(options.audit_entries).await.map(|entries| to_json_lines(&entries))),
+        ("status.json", client.status().await.and_then(|report| to_pretty(&report))),
+        ("metrics.prom", fetch_metrics(&options.metrics_url).await),
+    ];
+
+    let mut manifest = SnapshotManifest {
+        path: path.clone(),
+        created_at: now.to_rfc3339(),
+        node_id,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        files: Vec::new(),
+        errors: BTreeMap::new(),
+    };
+    let mut files = Vec::new();
+    for (name, result) in collected {
+        match result {
+            Ok(content) => {
+                manifest.files.push(name.to_string());
+                files.push((name, content));
+            }
+            Err(e) => {
+                manifest.errors.insert(name.to_string(), e);
+            }
+        }
+    }
+    files.push(("manifest.json", to_pretty(&manifest)?));
+
+    write_archive(&path, &prefix, &files)?;
+    Ok(manifest)
+}
+
+fn to_pretty<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| format!("序列化失败: {}", e))
+}
+
+/// 每行一个 JSON 对象
+fn to_json_lines<T: Serialize>(items: &[T]) -> String {
+    items
+        .iter()
+        .filter_map(|item| serde_json::to_string(item).ok())
+        .map(|line| line + "\n")
+        .collect()
+}
+
+async fn fetch_metrics(url: &str) -> Result<String, String> {
+    let response = reqwest::Client::new()
+        .get(url)
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+        .map_err(|e| format!("抓取指标 {} 失败: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("抓取指标 {} 失败: HTTP {}", url, response.status()));
+    }
+    response.text().await.map_err(|e| format!("读取指标失败: {}", e))
+}
+
+/// 写入 tar.gz，所有文件放在 `prefix/` 目录下
+fn write_archive(path: &Path, prefix: &str, files: &[(&str, String)]) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("创建 {} 失败: {}", path.display(), e))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    let mtime = chrono::Utc::now().timestamp().max(0) as u64;
+    for (name, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, format!("{}/{}", prefix, name), content.as_bytes())
+            .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+    }
+    archive
+        .into_inner()
+        .and_then(|gz| gz.finish())
+        .map_err(|e| format!("写入 {} 失败: {}", path.display(), e))?;
+    Ok(())
+}
@@ -0,0 +1,195 @@
+//! `ark watch`：终端实时视图
+//!
+//! 定期通过 IPC 拉取状态图快照，展示活跃进程及其 consumes / waits_on / blocked_by 边、
+//! 全部边列表和最近的错误事件，替代反复执行 `ark ps` / `ark why`。按 q 或 Esc 退出。
+
+use crate::ipc::IpcClient;
+use ark_core::event::Event;
+use ark_core::graph::{Edge, EdgeType, Node, NodeType, StateGraph};
+use ratatui::crossterm::event::{self, Event as TermEvent, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+use std::time::{Duration, Instant};
+
+/// 键盘事件轮询间隔
+const INPUT_POLL: Duration = Duration::from_millis(100);
+
+/// 一次刷新得到的视图数据
+struct Snapshot {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    errors: Vec<Event>,
+}
+
+impl Snapshot {
+    async fn from_graph(graph: StateGraph, errors: Vec<Event>) -> Self {
+        let mut nodes: Vec<Node> = graph.get_nodes_async().await.into_values().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut edges = graph.get_all_edges_async().await;
+        edges.sort_by(|a, b| a.from.cmp(&b.from).then_with(|| a.to.cmp(&b.to)));
+        Self { nodes, edges, errors }
+    }
+
+    fn count(&self, node_type: NodeType) -> usize {
+        self.nodes.iter().filter(|n| n.node_type == node_type).count()
+    }
+
+    /// 进程某类出边的目标节点
+    fn targets(&self, from: &str, edge_type: EdgeType) -> String {
+        let targets: Vec<&str> = self
+            .edges
+            .iter()
+            .filter(|e| e.from == from && e.edge_type == edge_type)
+            .map(|e| e.to.as_str())
+            .collect();
+        if targets.is_empty() {
+            "-".to_string()
+        } else {
+            targets.join(",")
+        }
+    }
+}
+
+/// 运行实时视图，直到用户按 q / Esc 退出
+pub async fn run_watch(client: IpcClient, interval: Duration, error_events: usize) -> Result<(), String> {
+    if !client.ping().await? {
+        return Err("无法连接到 daemon，请先运行: ark run".to_string());
+    }
+
+    let mut terminal = ratatui::init();
+    let result = watch_loop(&mut terminal, &client, interval, error_events).await;
+    ratatui::restore();
+    result
+}
+
+async fn watch_loop(
+    terminal: &mut DefaultTerminal,
+    client: &IpcClient,
+    interval: Duration,
+    error_events: usize,
+) -> Result<(), String> {
+    let mut snapshot: Option<Snapshot> = None;
+    let mut status = String::from("连接中…");
+    let mut next_refresh = Instant::now();
+
+    loop {
+        if Instant::now() >= next_refresh {
+            match client.graph_snapshot(error_events).await {
+                Ok((graph, errors)) => {
+                    snapshot = Some(Snapshot::from_graph(graph, errors).await);
+                    status = format!("已更新 {}", chrono::Local::now().format("%H:%M:%S"));
+                }
+                // daemon 短暂不可用时保留上一帧，继续重试
+                Err(e) => status = format!("刷新失败: {}", e),
+            }
+            next_refresh = Instant::now() + interval;
+        }
+
+        terminal
+            .draw(|frame| draw(frame, snapshot.as_ref(), &status))
+            .map_err(|e| format!("绘制界面失败: {}", e))?;
+
+        if event::poll(Duration::ZERO).map_err(|e| format!("读取键盘事件失败: {}", e))? {
+            if let TermEvent::Key(key) = event::read().map_err(|e| format!("读取键盘事件失败: {}", e))? {
+                if key.kind == KeyEventKind::Press && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                    return Ok(());
+                }
+            }
+        }
+        tokio::time::sleep(INPUT_POLL).await;
+    }
+}
+
+fn draw(frame: &mut Frame, snapshot: Option<&Snapshot>, status: &str) {
+    let [header, processes, bottom] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Percentage(50),
+        Constraint::Fill(1),
+    ])
+    .areas(frame.area());
+    let [edges_area, errors_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(bottom);
+
+    let Some(snapshot) = snapshot else {
+        frame.render_widget(Paragraph::new(format!("ark watch | {}", status)), header);
+        return;
+    };
+
+    let title = format!(
+        "ark watch | 进程 {} | 资源 {} | 错误 {} | 边 {} | {} | q 退出",
+        snapshot.count(NodeType::Process),
+        snapshot.count(NodeType::Resource),
+        snapshot.count(NodeType::Error),
+        snapshot.edges.len(),
+        status
+    );
+    frame.render_widget(Paragraph::new(title).style(Style::new().add_modifier(Modifier::BOLD)), header);
+
+    let header_style = Style::new().fg(Color::Cyan);
+    let rows = snapshot
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::Process)
+        .map(|node| {
+            let blocked_by = snapshot.targets(&node.id, EdgeType::BlockedBy);
+            let row = Row::new(vec![
+                node.key().to_string(),
+                node.metadata.get("job_id").cloned().unwrap_or_else(|| "-".to_string()),
+                node.metadata.get("state").cloned().unwrap_or_else(|| "unknown".to_string()),
+                snapshot.targets(&node.id, EdgeType::Consumes),
+                snapshot.targets(&node.id, EdgeType::WaitsOn),
+                blocked_by.clone(),
+            ]);
+            if blocked_by == "-" {
+                row
+            } else {
+                row.style(Style::new().fg(Color::Red))
+            }
+        });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(20),
+            Constraint::Length(12),
+            Constraint::Length(10),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(Row::new(vec!["PROCESS", "JOB_ID", "STATE", "CONSUMES", "WAITS_ON", "BLOCKED_BY"]).style(header_style))
+    .block(Block::new().borders(Borders::ALL).title("进程"));
+    frame.render_widget(table, processes);
+
+    let edges: Vec<ListItem> = snapshot
+        .edges
+        .iter()
+        .map(|e| {
+            let item = ListItem::new(format!("{} -[{}]-> {} (x{})", e.from, e.edge_type.as_str(), e.to, e.weight));
+            if e.edge_type == EdgeType::BlockedBy {
+                item.style(Style::new().fg(Color::Red))
+            } else {
+                item
+            }
+        })
+        .collect();
+    frame.render_widget(List::new(edges).block(Block::new().borders(Borders::ALL).title("边")), edges_area);
+
+    let errors: Vec<ListItem> = snapshot
+        .errors
+        .iter()
+        .map(|e| {
+            let ts = chrono::DateTime::from_timestamp_millis(e.ts as i64)
+                .map(|t| t.with_timezone(&chrono::Local).format("%H:%M:%S").to_string())
+                .unwrap_or_else(|| e.ts.to_string());
+            ListItem::new(Line::from(format!("{} {} {} {}", ts, e.event_type, e.entity_id, e.value)))
+        })
+        .collect();
+    frame.render_widget(
+        List::new(errors).block(Block::new().borders(Borders::ALL).title("最近错误")),
+        errors_area,
+    );
+}
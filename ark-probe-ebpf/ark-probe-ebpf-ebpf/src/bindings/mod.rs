@@ -25,6 +25,15 @@ pub struct sock_common {
     pub skc_daddr: u32,            // 目的 IP（IPv4，网络字节序）
     pub skc_rcv_saddr: u32,        // 源 IP（IPv4，网络字节序）
     // ... 其他字段
+    pub skc_v6_daddr: in6_addr,     // 目的 IP（IPv6，网络字节序）
+    pub skc_v6_rcv_saddr: in6_addr, // 源 IP（IPv6，网络字节序）
+}
+
+/// in6_addr 结构体（IPv6 地址）
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct in6_addr {
+    pub s6_addr: [u8; 16],
 }
 
 /// sock 结构体（完整的 socket）
@@ -1,13 +1,48 @@
 #![no_std]
 
+/// DROPPED_SAMPLES 中各探针的下标：写入 RingBuf 失败（缓冲区已满）时计数
+pub const DROPPED_NETWORK: u32 = 0;
+pub const DROPPED_STORAGE: u32 = 1;
+pub const DROPPED_RDMA: u32 = 2;
+/// DROPPED_SAMPLES 的条目数
+pub const DROPPED_SLOTS: u32 = 3;
+
+/// SOCKET_TO_PID 的容量（LRU，满时淘汰最久未使用的连接）
+pub const SOCKET_TO_PID_ENTRIES: u32 = 8192;
+
+/// 地址族
+pub const AF_INET: u16 = 2;
+pub const AF_INET6: u16 = 10;
+
 /// Socket 四元组（用于映射到 PID）
+/// 地址按网络字节序存放在 16 字节数组中：IPv6 占满，IPv4 只用前 4 字节（其余为 0）
 #[repr(C)]
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SocketTuple {
-    pub src_ip: u32,      // 源 IP（IPv4，网络字节序）
-    pub dst_ip: u32,      // 目的 IP（IPv4，网络字节序）
-    pub src_port: u16,    // 源端口（网络字节序）
-    pub dst_port: u16,    // 目的端口（网络字节序）
+    pub family: u16,      // AF_INET 或 AF_INET6，0 表示未能提取
+    pub src_port: u16,    // 源端口（主机字节序）
+    pub dst_port: u16,    // 目的端口（主机字节序）
+    pub _pad: u16,        // 显式填充，保证 map key 的每个字节都已初始化
+    pub src_ip: [u8; 16], // 源 IP（网络字节序）
+    pub dst_ip: [u8; 16], // 目的 IP（网络字节序）
+}
+
+impl SocketTuple {
+    /// 提取失败时的空值
+    pub const fn empty() -> Self {
+        Self {
+            family: 0,
+            src_port: 0,
+            dst_port: 0,
+            _pad: 0,
+            src_ip: [0; 16],
+            dst_ip: [0; 16],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.family == 0
+    }
 }
 
 /// 网络事件结构体（内核态和用户态共享）
@@ -25,21 +60,23 @@ pub struct NetworkEvent {
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct StorageEvent {
-    pub pid: u32,
-    pub event_type: u8, // 1 = io_latency, 2 = io_size
-    pub io_latency_ns: u64, // I/O 延迟（纳秒）
+    pub pid: u32, // 提交 I/O 的进程（从 bio 提交时的映射反查）
+    pub event_type: u8, // 1 = io_complete（同时携带延迟和大小）
+    pub io_latency_ns: u64, // I/O 延迟（纳秒，下发到完成）
     pub io_size: u64, // I/O 大小（字节）
     pub timestamp: u64, // 纳秒级时间戳
+    pub dev: u32, // 块设备号（内核 dev_t：主设备号 << 20 | 次设备号）
+    pub queue_depth: u32, // 完成时设备的在途请求数（含本请求）
 }
 
 /// RDMA 事件结构体（内核态和用户态共享）
+/// 拥塞（CNP/ECN）和 PFC 由网卡硬件处理，内核态不可见，由用户态从硬件计数器采样
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct RdmaEvent {
     pub pid: u32,
-    pub event_type: u8, // 1 = rdma_congestion, 2 = pfc_storm, 3 = rdma_latency
-    pub congestion_level: u32, // 拥塞级别
-    pub pfc_count: u32, // PFC 帧计数
-    pub latency_ns: u64, // RDMA 操作延迟（纳秒）
+    pub event_type: u8, // 3 = rdma_latency, 4 = rdma_wc_error
+    pub wc_status: u32, // 完成状态（enum ib_wc_status，0 为成功，12 为重传超限）
+    pub latency_ns: u64, // 发送到完成的延迟（纳秒）
     pub timestamp: u64, // 纳秒级时间戳
 }
@@ -4,27 +4,53 @@
 use aya_bpf::{
     helpers::bpf_probe_read_kernel,
     macros::{kprobe, map},
-    maps::{HashMap, PerfEventArray},
+    maps::{LruHashMap, PerCpuArray, RingBuf},
     programs::ProbeContext,
     BpfContext,
 };
 use aya_log_ebpf::info;
 
-use ark_probe_ebpf_ebpf::{NetworkEvent, SocketTuple, StorageEvent, RdmaEvent};
+use ark_probe_ebpf_ebpf::{
+    NetworkEvent, SocketTuple, AF_INET, AF_INET6, DROPPED_NETWORK, DROPPED_SLOTS, SOCKET_TO_PID_ENTRIES,
+};
 
 // 导入内核绑定（CO-RE 支持）
 // 注意：实际使用时，这些应该从 generate-bindings.sh 生成
 // 当前使用示例绑定，生产环境应使用真实生成的文件
 mod bindings;
 
+// 存储探针：块设备 I/O 延迟、大小和队列深度
+mod storage_probe;
+
+// RDMA 探针：mlx5 verbs 的发送字节数和完成延迟
+mod rdma_probe;
+
+/// TCP_CLOSE（include/net/tcp_states.h）
+const TCP_CLOSE: i32 = 7;
+
 /// Socket 四元组到 PID 的映射表
-/// 在 tcp_sendmsg 中建立映射，在 tcp_retransmit_skb 中查询
+/// 在 tcp_sendmsg 中建立映射，在 tcp_retransmit_skb 中查询，在 tcp_set_state(TCP_CLOSE) 中删除；
+/// 使用 LRU：漏删的条目（如探针加载前建立、或 PID key）在表满时被淘汰，不会挤掉新连接
+#[map]
+static mut SOCKET_TO_PID: LruHashMap<SocketTuple, u32> = LruHashMap::with_max_entries(SOCKET_TO_PID_ENTRIES, 0);
+
+/// 网络事件输出（所有 CPU 共享一个环形缓冲区，突发时不会因单个 CPU 的缓冲区满而丢事件）
 #[map]
-static mut SOCKET_TO_PID: HashMap<SocketTuple, u32> = HashMap::with_max_entries(8192, 0);
+static mut NETWORK_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
 
-/// 网络事件输出
+/// 各探针写入 RingBuf 失败的次数（按 DROPPED_* 下标），用户态汇总为自身指标
 #[map]
-static mut NETWORK_EVENTS: PerfEventArray<NetworkEvent> = PerfEventArray::with_max_entries(1024, 0);
+static mut DROPPED_SAMPLES: PerCpuArray<u64> = PerCpuArray::with_max_entries(DROPPED_SLOTS, 0);
+
+/// 记录一次丢弃的事件
+#[inline]
+pub(crate) fn record_dropped(index: u32) {
+    unsafe {
+        if let Some(count) = DROPPED_SAMPLES.get_ptr_mut(index) {
+            *count += 1;
+        }
+    }
+}
 
 /// Hook tcp_sendmsg：在真实的进程上下文中建立 socket -> PID 映射
 /// 这是解决软中断 PID 陷阱的关键！
@@ -46,24 +72,22 @@ fn try_tcp_sendmsg(ctx: ProbeContext) -> Result<u32, u32> {
     // tcp_sendmsg(struct sock *sk, struct msghdr *msg, size_t size)
     // 
     // CO-RE 实现：使用 bpf_probe_read_kernel 读取 socket 字段
-    // 注意：在 Aya 中，获取函数参数需要使用架构特定的方法
-    // 这里使用简化方法，实际生产环境应使用完整的 CO-RE 实现
+    let socket_tuple = extract_socket_tuple(&ctx);
     
-    // 尝试提取 socket 四元组（CO-RE 版本）
-    let socket_tuple = extract_socket_tuple_from_sendmsg(ctx, pid);
+    // 提取失败时以 PID 为 key（与 tcp_retransmit_skb 的回退查询对应）
+    let key = if socket_tuple.is_empty() { pid_key(pid) } else { socket_tuple };
     
     // 存入 SOCKET_TO_PID map
     unsafe {
-        let _ = SOCKET_TO_PID.insert(&socket_tuple, &pid, 0);
+        let _ = SOCKET_TO_PID.insert(&key, &pid, 0);
     }
     
-    if socket_tuple.src_ip != 0 || socket_tuple.dst_ip != 0 {
-        info!(&ctx, "tcp_sendmsg: pid={}, socket={}:{}->{}:{} (CO-RE)", 
+    if !socket_tuple.is_empty() {
+        info!(&ctx, "tcp_sendmsg: pid={}, family={}, port={}->{} (CO-RE)", 
               pid,
-              socket_tuple.src_ip,
-              u16::from_be(socket_tuple.src_port),
-              socket_tuple.dst_ip,
-              u16::from_be(socket_tuple.dst_port));
+              socket_tuple.family,
+              socket_tuple.src_port,
+              socket_tuple.dst_port);
     } else {
         info!(&ctx, "tcp_sendmsg: pid={} (CO-RE: socket 提取失败，使用 PID key)", pid);
     }
@@ -71,57 +95,25 @@ fn try_tcp_sendmsg(ctx: ProbeContext) -> Result<u32, u32> {
     Ok(0)
 }
 
-/// 从 tcp_sendmsg 的上下文提取 socket 四元组（CO-RE 版本）
-/// 
-/// 这是解决软中断 PID 陷阱的关键：在真实的进程上下文中建立 socket -> PID 映射
-/// 
-/// 实现细节：
-/// 1. 从 ctx.arg(0) 获取 struct sock *sk 指针
-/// 2. 使用 bpf_probe_read_kernel 安全读取内核结构体字段（CO-RE 兼容）
-/// 3. 只处理 IPv4 连接（skc_family == AF_INET，值为 2）
-/// 4. 所有 IP 和端口都是网络字节序（大端序）
-#[inline]
-fn extract_socket_tuple_from_sendmsg(ctx: ProbeContext, _pid: u32) -> SocketTuple {
-    // 默认返回值（提取失败时返回）
-    let mut tuple = SocketTuple {
-        src_ip: 0,
-        dst_ip: 0,
-        src_port: 0,
-        dst_port: 0,
+/// Hook tcp_set_state：连接彻底关闭（进入 TCP_CLOSE）时删除 socket -> PID 映射
+/// 不在 tcp_close 中删除：close() 之后 FIN 和未确认的数据仍可能重传，届时还需要反查 PID
+#[kprobe(name = "tcp_set_state")]
+pub fn tcp_set_state(ctx: ProbeContext) -> u32 {
+    // tcp_set_state(struct sock *sk, int state)
+    let Some(state) = ctx.arg::<i32>(1) else {
+        return 0;
     };
-    
-    // 步骤 1：从函数参数获取 struct sock *sk 指针
-    // tcp_sendmsg(struct sock *sk, struct msghdr *msg, size_t size)
-    // 第一个参数（索引 0）是 sk
-    let sk_ptr: *const bindings::sock = match ctx.arg(0) {
-        Ok(ptr) => ptr as *const bindings::sock,
-        Err(_) => return tuple, // 无法获取 sk 指针，返回空值
-    };
-    
-    // 步骤 2：读取 sock_common 部分（包含网络信息）
-    // 使用 bpf_probe_read_kernel 进行 CO-RE 兼容的读取
-    let sk_common = unsafe {
-        let mut common = core::mem::zeroed::<bindings::sock_common>();
-        let common_ptr = &(*sk_ptr).__sk_common as *const bindings::sock_common;
-        if bpf_probe_read_kernel(common_ptr as *const _, &mut common as *mut _ as *mut u8, core::mem::size_of::<bindings::sock_common>()).is_err() {
-            return tuple; // 读取失败，返回空值
+    if state != TCP_CLOSE {
+        return 0;
+    }
+
+    let socket_tuple = extract_socket_tuple(&ctx);
+    if !socket_tuple.is_empty() {
+        unsafe {
+            let _ = SOCKET_TO_PID.remove(&socket_tuple);
         }
-        common
-    };
-    
-    // 步骤 3：只处理 IPv4 连接（AF_INET = 2）
-    // 跳过 IPv6 和其他协议族
-    if sk_common.skc_family != 2 {
-        return tuple; // 不是 IPv4，返回空值
     }
-    
-    // 步骤 4：提取四元组（所有值都是网络字节序）
-    tuple.src_ip = sk_common.skc_rcv_saddr;      // 源 IP（网络字节序）
-    tuple.dst_ip = sk_common.skc_daddr;           // 目的 IP（网络字节序）
-    tuple.src_port = sk_common.skc_num;          // 源端口（网络字节序）
-    tuple.dst_port = sk_common.skc_dport;        // 目的端口（网络字节序）
-    
-    tuple
+    0
 }
 
 /// Hook tcp_retransmit_skb：在软中断上下文中捕获重传
@@ -145,7 +137,7 @@ fn try_tcp_retransmit_skb(ctx: ProbeContext) -> Result<u32, u32> {
     // tcp_retransmit_skb(struct sock *sk, struct sk_buff *skb, int segs)
     //
     // CO-RE 实现：提取真实的 socket 四元组，然后从 Map 查询真实 PID
-    let socket_tuple = extract_socket_tuple_from_retransmit(ctx, fallback_pid);
+    let socket_tuple = extract_socket_tuple(&ctx);
     
     // 从 SOCKET_TO_PID map 中查询真实 PID
     let real_pid = unsafe {
@@ -153,13 +145,7 @@ fn try_tcp_retransmit_skb(ctx: ProbeContext) -> Result<u32, u32> {
             .copied()
             .unwrap_or_else(|| {
                 // 如果使用真实 socket 查询失败，尝试使用 PID key（向后兼容）
-                let pid_key = SocketTuple {
-                    src_ip: fallback_pid,
-                    dst_ip: 0,
-                    src_port: 0,
-                    dst_port: 0,
-                };
-                SOCKET_TO_PID.get(&pid_key)
+                SOCKET_TO_PID.get(&pid_key(fallback_pid))
                     .copied()
                     .unwrap_or(fallback_pid)
             })
@@ -174,27 +160,25 @@ fn try_tcp_retransmit_skb(ctx: ProbeContext) -> Result<u32, u32> {
         socket_tuple,
     };
     
-    // 输出到 PerfEventArray
-    unsafe {
-        NETWORK_EVENTS.output(&ctx, &event, 0);
+    // 输出到 RingBuf，缓冲区满时计入丢弃数
+    if unsafe { NETWORK_EVENTS.output(&event, 0) }.is_err() {
+        record_dropped(DROPPED_NETWORK);
     }
     
     // 日志输出
-    if socket_tuple.src_ip != 0 || socket_tuple.dst_ip != 0 {
+    if !socket_tuple.is_empty() {
         if real_pid == fallback_pid {
-            info!(&ctx, "TCP retransmit: pid={}, socket={}:{}->{}:{} (CO-RE: 未找到映射)", 
+            info!(&ctx, "TCP retransmit: pid={}, family={}, port={}->{} (CO-RE: 未找到映射)", 
                   real_pid,
-                  socket_tuple.src_ip,
-                  u16::from_be(socket_tuple.src_port),
-                  socket_tuple.dst_ip,
-                  u16::from_be(socket_tuple.dst_port));
+                  socket_tuple.family,
+                  socket_tuple.src_port,
+                  socket_tuple.dst_port);
         } else {
-            info!(&ctx, "TCP retransmit: pid={}, socket={}:{}->{}:{} (CO-RE: 成功)", 
+            info!(&ctx, "TCP retransmit: pid={}, family={}, port={}->{} (CO-RE: 成功)", 
                   real_pid,
-                  socket_tuple.src_ip,
-                  u16::from_be(socket_tuple.src_port),
-                  socket_tuple.dst_ip,
-                  u16::from_be(socket_tuple.dst_port));
+                  socket_tuple.family,
+                  socket_tuple.src_port,
+                  socket_tuple.dst_port);
         }
     } else {
         if real_pid == fallback_pid {
@@ -207,55 +191,66 @@ fn try_tcp_retransmit_skb(ctx: ProbeContext) -> Result<u32, u32> {
     Ok(0)
 }
 
-/// 从 tcp_retransmit_skb 的上下文提取 socket 四元组（CO-RE 版本）
+/// socket 提取失败时使用的 key：PID 放在 src_ip 的前 4 字节，family 为 0
+#[inline]
+fn pid_key(pid: u32) -> SocketTuple {
+    let mut key = SocketTuple::empty();
+    let bytes = pid.to_ne_bytes();
+    key.src_ip[0] = bytes[0];
+    key.src_ip[1] = bytes[1];
+    key.src_ip[2] = bytes[2];
+    key.src_ip[3] = bytes[3];
+    key
+}
+
+/// 从 kprobe 上下文提取 socket 四元组（CO-RE 版本）
 /// 
-/// 这是解决软中断 PID 陷阱的关键：通过 socket 四元组从 Map 中反查真实 PID
+/// 这是解决软中断 PID 陷阱的关键：tcp_sendmsg 在真实的进程上下文中建立 socket -> PID 映射，
+/// tcp_retransmit_skb 通过同一个四元组从 Map 中反查真实 PID，tcp_set_state 据此删除映射，三者的第一个参数都是 struct sock *sk
 /// 
 /// 实现细节：
 /// 1. 从 ctx.arg(0) 获取 struct sock *sk 指针
 /// 2. 使用 bpf_probe_read_kernel 安全读取内核结构体字段（CO-RE 兼容）
-/// 3. 只处理 IPv4 连接（skc_family == AF_INET，值为 2）
-/// 4. 所有 IP 和端口都是网络字节序（大端序）
+/// 3. 处理 IPv4（AF_INET）和 IPv6（AF_INET6）连接，其他协议族返回空值
+/// 4. IP 保持网络字节序；端口转换为主机字节序（skc_num 本身是主机字节序，skc_dport 是网络字节序）
 #[inline]
-fn extract_socket_tuple_from_retransmit(ctx: ProbeContext, _fallback_pid: u32) -> SocketTuple {
+fn extract_socket_tuple(ctx: &ProbeContext) -> SocketTuple {
     // 默认返回值（提取失败时返回）
-    let mut tuple = SocketTuple {
-        src_ip: 0,
-        dst_ip: 0,
-        src_port: 0,
-        dst_port: 0,
-    };
+    let mut tuple = SocketTuple::empty();
     
-    // 步骤 1：从函数参数获取 struct sock *sk 指针
-    // tcp_retransmit_skb(struct sock *sk, struct sk_buff *skb, int segs)
-    // 第一个参数（索引 0）是 sk
+    // 步骤 1：从函数参数获取 struct sock *sk 指针（索引 0）
     let sk_ptr: *const bindings::sock = match ctx.arg(0) {
-        Ok(ptr) => ptr as *const bindings::sock,
-        Err(_) => return tuple, // 无法获取 sk 指针，返回空值
+        Some(ptr) => ptr,
+        None => return tuple, // 无法获取 sk 指针，返回空值
     };
     
     // 步骤 2：读取 sock_common 部分（包含网络信息）
     // 使用 bpf_probe_read_kernel 进行 CO-RE 兼容的读取
     let sk_common = unsafe {
-        let mut common = core::mem::zeroed::<bindings::sock_common>();
         let common_ptr = &(*sk_ptr).__sk_common as *const bindings::sock_common;
-        if bpf_probe_read_kernel(common_ptr as *const _, &mut common as *mut _ as *mut u8, core::mem::size_of::<bindings::sock_common>()).is_err() {
-            return tuple; // 读取失败，返回空值
+        match bpf_probe_read_kernel(common_ptr) {
+            Ok(common) => common,
+            Err(_) => return tuple, // 读取失败，返回空值
         }
-        common
     };
     
-    // 步骤 3：只处理 IPv4 连接（AF_INET = 2）
-    // 跳过 IPv6 和其他协议族
-    if sk_common.skc_family != 2 {
-        return tuple; // 不是 IPv4，返回空值
+    // 步骤 3：按协议族提取地址（网络字节序）
+    match sk_common.skc_family {
+        AF_INET => {
+            tuple.src_ip[..4].copy_from_slice(&sk_common.skc_rcv_saddr.to_ne_bytes());
+            tuple.dst_ip[..4].copy_from_slice(&sk_common.skc_daddr.to_ne_bytes());
+        }
+        AF_INET6 => {
+            tuple.src_ip = sk_common.skc_v6_rcv_saddr.s6_addr;
+            tuple.dst_ip = sk_common.skc_v6_daddr.s6_addr;
+        }
+        _ => return tuple, // 其他协议族，返回空值
     }
     
-    // 步骤 4：提取四元组（所有值都是网络字节序）
-    tuple.src_ip = sk_common.skc_rcv_saddr;      // 源 IP（网络字节序）
-    tuple.dst_ip = sk_common.skc_daddr;           // 目的 IP（网络字节序）
-    tuple.src_port = sk_common.skc_num;          // 源端口（网络字节序）
-    tuple.dst_port = sk_common.skc_dport;        // 目的端口（网络字节序）
+    // 步骤 4：提取端口
+    tuple.family = sk_common.skc_family;
+    tuple.src_port = sk_common.skc_num;
+    tuple.dst_port = u16::from_be(sk_common.skc_dport);
     
     tuple
 }
@@ -1,60 +1,175 @@
 //! 高级 RDMA 探针
-//! 
-//! 深入 RoCEv2 协议栈，直接抓取 PFC（优先流量控制）拥塞风暴
+//!
+//! 跟踪 mlx5 驱动的 verbs 调用，按进程统计 RDMA 发送字节数和"发送 → 完成"延迟：
+//! 1. `mlx5_ib_post_send`：在发起进程的上下文中累计发送字节数，记录 QP 上最早未完成发送的时间和 PID
+//! 2. `mlx5_ib_poll_cq`（kprobe + kretprobe）：轮询到完成时取第一个完成项（wc[0]）的 QP，
+//!    计算该 QP 的发送到完成延迟；完成状态非成功（如重传超限）时一并上报
+//!
+//! ⚠️ 只有经过内核 verbs 的流量可见（NVMe-oF、NFS over RDMA、内核 ULP 等）；NCCL 等用户态 verbs
+//! 绕过内核直接操作网卡，其吞吐、拥塞（CNP/ECN）和 PFC 由用户态从 hw_counters 和 ethtool 统计采样。
+//!
+//! 只读取长期稳定的结构体前缀（ib_send_wr、ib_sge、ib_wc 的前几个字段），不依赖内核 BTF。
 
 use aya_bpf::{
-    macros::{kprobe, map},
-    maps::PerfEventArray,
-    programs::ProbeContext,
+    helpers::{bpf_get_current_pid_tgid, bpf_ktime_get_ns, bpf_probe_read_kernel},
+    macros::{kprobe, kretprobe, map},
+    maps::{HashMap, LruHashMap, RingBuf},
+    programs::{ProbeContext, RetProbeContext},
 };
 
-use ark_probe_ebpf_ebpf::RdmaEvent;
+use ark_probe_ebpf_ebpf::{RdmaEvent, DROPPED_RDMA};
 
-#[map]
-static mut RDMA_EVENTS: PerfEventArray<RdmaEvent> = PerfEventArray::with_max_entries(1024, 0);
-
-/// Hook mlx5_cq_poll - Mellanox 驱动完成队列轮询
-#[kprobe(name = "mlx5_cq_poll")]
-pub fn mlx5_cq_poll(ctx: ProbeContext) -> u32 {
-    // TODO: 检测 PFC 拥塞
-    // 1. 从完成队列中提取拥塞标记
-    // 2. 检测 PFC 风暴（连续多个 PFC 帧）
-    // 3. 记录拥塞事件
-    
-    // 占位实现
-    0
+use crate::record_dropped;
+
+/// struct ib_send_wr 中 sg_list（struct ib_sge *）的偏移
+const WR_SG_LIST_OFFSET: usize = 16;
+/// struct ib_send_wr 中 num_sge（int）的偏移
+const WR_NUM_SGE_OFFSET: usize = 24;
+/// struct ib_sge 的大小（addr u64、length u32、lkey u32）
+const SGE_SIZE: usize = 16;
+/// struct ib_sge 中 length 的偏移
+const SGE_LENGTH_OFFSET: usize = 8;
+/// 每个 WR 最多统计的 SGE 数（verifier 要求循环有界）
+const MAX_SGE: usize = 4;
+/// struct ib_wc 中 status（enum ib_wc_status）的偏移
+const WC_STATUS_OFFSET: usize = 8;
+/// struct ib_wc 中 qp（struct ib_qp *）的偏移
+const WC_QP_OFFSET: usize = 24;
+
+/// QP 上最早一个未完成的发送
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct PendingSend {
+    pid: u32,
+    _pad: u32,
+    ts: u64,
 }
 
-/// Hook ib_post_send - InfiniBand 发送
-#[kprobe(name = "ib_post_send")]
-pub fn ib_post_send(ctx: ProbeContext) -> u32 {
-    // TODO: 记录发送操作
-    // 1. 提取 QP (Queue Pair) 信息
-    // 2. 记录发送时间戳
-    // 3. 关联到进程 PID
-    
-    // 占位实现
-    0
+/// 各进程的 RDMA 发送字节数，用户态每个周期读取后清零
+#[map]
+static mut RDMA_TX_BYTES: HashMap<u32, u64> = HashMap::with_max_entries(4096, 0);
+
+/// QP 指针 → 最早未完成的发送
+#[map]
+static mut QP_PENDING: LruHashMap<u64, PendingSend> = LruHashMap::with_max_entries(16384, 0);
+
+/// 线程 ID → 正在轮询的 wc 数组指针（kprobe 记录，kretprobe 读取）
+#[map]
+static mut POLL_WC: HashMap<u64, u64> = HashMap::with_max_entries(4096, 0);
+
+/// RDMA 事件输出
+#[map]
+static mut RDMA_EVENTS: RingBuf = RingBuf::with_byte_size(256 * 1024, 0);
+
+/// Hook mlx5_ib_post_send(struct ib_qp *ibqp, const struct ib_send_wr *wr, const struct ib_send_wr **bad_wr)
+#[kprobe(name = "mlx5_ib_post_send")]
+pub fn mlx5_ib_post_send(ctx: ProbeContext) -> u32 {
+    match try_mlx5_ib_post_send(&ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
 }
 
-/// Hook ib_post_recv - InfiniBand 接收
-#[kprobe(name = "ib_post_recv")]
-pub fn ib_post_recv(ctx: ProbeContext) -> u32 {
-    // TODO: 记录接收操作
-    // 类似 ib_post_send 的实现
-    
-    // 占位实现
-    0
+#[inline]
+fn try_mlx5_ib_post_send(ctx: &ProbeContext) -> Result<u32, i64> {
+    let qp: u64 = ctx.arg(0).ok_or(1)?;
+    let wr: *const u8 = ctx.arg(1).ok_or(1)?;
+    let pid = (bpf_get_current_pid_tgid() >> 32) as u32;
+
+    // 只统计 WR 链表中第一个 WR 的 SGE（批量提交时为近似值）
+    let bytes = unsafe {
+        let sg_list: u64 = bpf_probe_read_kernel(wr.add(WR_SG_LIST_OFFSET) as *const u64)?;
+        let num_sge: i32 = bpf_probe_read_kernel(wr.add(WR_NUM_SGE_OFFSET) as *const i32)?;
+        let mut bytes = 0u64;
+        for i in 0..MAX_SGE {
+            if i as i32 >= num_sge {
+                break;
+            }
+            let length: u32 = bpf_probe_read_kernel((sg_list as usize + i * SGE_SIZE + SGE_LENGTH_OFFSET) as *const u32)?;
+            bytes += length as u64;
+        }
+        bytes
+    };
+
+    unsafe {
+        match RDMA_TX_BYTES.get_ptr_mut(&pid) {
+            // 多 CPU 并发更新时为近似值，仅用于观测
+            Some(total) => *total += bytes,
+            None => {
+                let _ = RDMA_TX_BYTES.insert(&pid, &bytes, 0);
+            }
+        }
+
+        // 已有未完成的发送时保留更早的时间戳
+        if QP_PENDING.get(&qp).is_none() {
+            let pending = PendingSend { pid, _pad: 0, ts: bpf_ktime_get_ns() };
+            let _ = QP_PENDING.insert(&qp, &pending, 0);
+        }
+    }
+    Ok(0)
 }
 
-/// Hook mlx5_ib_poll_cq - 轮询完成队列（检测 PFC）
+/// Hook mlx5_ib_poll_cq(struct ib_cq *ibcq, int num_entries, struct ib_wc *wc)：记录 wc 数组
 #[kprobe(name = "mlx5_ib_poll_cq")]
 pub fn mlx5_ib_poll_cq(ctx: ProbeContext) -> u32 {
-    // TODO: 检测 PFC 风暴
-    // 1. 从完成队列中提取 PFC 标记
-    // 2. 统计 PFC 帧数量
-    // 3. 如果超过阈值，触发拥塞事件
-    
-    // 占位实现
+    let Some(wc) = ctx.arg::<u64>(2) else {
+        return 0;
+    };
+    let tid = bpf_get_current_pid_tgid();
+    unsafe {
+        let _ = POLL_WC.insert(&tid, &wc, 0);
+    }
     0
 }
+
+/// mlx5_ib_poll_cq 返回：返回值为轮询到的完成数
+#[kretprobe(name = "mlx5_ib_poll_cq_ret")]
+pub fn mlx5_ib_poll_cq_ret(ctx: RetProbeContext) -> u32 {
+    match try_mlx5_ib_poll_cq_ret(&ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[inline]
+fn try_mlx5_ib_poll_cq_ret(ctx: &RetProbeContext) -> Result<u32, i64> {
+    let tid = bpf_get_current_pid_tgid();
+    let wc = unsafe {
+        let wc = POLL_WC.get(&tid).copied();
+        let _ = POLL_WC.remove(&tid);
+        wc.ok_or(1)?
+    };
+    let completed: i32 = ctx.ret().ok_or(1)?;
+    if completed <= 0 {
+        return Ok(0);
+    }
+
+    // 只看第一个完成项：ib_wc 的大小随内核版本变化，后续元素的偏移不可靠
+    let (status, qp) = unsafe {
+        let status: u32 = bpf_probe_read_kernel((wc as usize + WC_STATUS_OFFSET) as *const u32)?;
+        let qp: u64 = bpf_probe_read_kernel((wc as usize + WC_QP_OFFSET) as *const u64)?;
+        (status, qp)
+    };
+
+    // 不是本探针加载后发起的发送（如接收完成），跳过
+    let pending = match unsafe { QP_PENDING.get(&qp) } {
+        Some(pending) => *pending,
+        None => return Ok(0),
+    };
+    unsafe {
+        let _ = QP_PENDING.remove(&qp);
+    }
+
+    let now = unsafe { bpf_ktime_get_ns() };
+    let event = RdmaEvent {
+        pid: pending.pid,
+        event_type: if status == 0 { 3 } else { 4 },
+        wc_status: status,
+        latency_ns: now.saturating_sub(pending.ts),
+        timestamp: now,
+    };
+    if unsafe { RDMA_EVENTS.output(&event, 0) }.is_err() {
+        record_dropped(DROPPED_RDMA);
+    }
+    Ok(0)
+}
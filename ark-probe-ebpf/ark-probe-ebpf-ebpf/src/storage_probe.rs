@@ -1,46 +1,191 @@
-//! eBPF 存储探针（NVMe/VFS）
-//! 
-//! 监控底层文件系统读取延迟，抓出导致 Dataloader 卡顿的慢 I/O
+//! eBPF 存储探针（块设备层）
+//!
+//! 监控块设备 I/O 的延迟、大小和队列深度，抓出导致 Dataloader 卡顿的慢 I/O。
+//! 使用 block tracepoint（各内核版本中 dev、sector、nr_sector 字段的偏移一致，不依赖 BTF）：
+//! 1. `block_bio_queue`：bio 提交时运行在发起 I/O 的进程上下文中，建立 (dev, sector) -> PID 映射
+//! 2. `block_rq_issue`：请求下发到驱动，记录开始时间、大小，设备在途请求数加一
+//! 3. `block_rq_complete`：请求完成，计算延迟并输出 StorageEvent，设备在途请求数减一
+//!
+//! ⚠️ 与 tcp_retransmit_skb 相同的 PID 陷阱：请求的下发和完成常发生在 kworker 或中断上下文中，
+//! 当前 PID 不可信，需要从 bio_queue 建立的映射中反查真实 PID
 
 use aya_bpf::{
-    macros::{kprobe, map},
-    maps::PerfEventArray,
-    programs::ProbeContext,
+    helpers::{bpf_get_current_pid_tgid, bpf_ktime_get_ns},
+    macros::{map, tracepoint},
+    maps::{HashMap, LruHashMap, RingBuf},
+    programs::TracePointContext,
 };
 
-use ark_probe_ebpf_ebpf::StorageEvent;
+use ark_probe_ebpf_ebpf::{StorageEvent, DROPPED_STORAGE};
 
+use crate::record_dropped;
+
+/// tracepoint 参数中 dev（dev_t，u32）的偏移（前 8 字节为通用字段）
+const DEV_OFFSET: usize = 8;
+/// sector（sector_t，u64）的偏移
+const SECTOR_OFFSET: usize = 16;
+/// nr_sector（u32）的偏移
+const NR_SECTOR_OFFSET: usize = 24;
+/// 扇区大小（字节）
+const SECTOR_SIZE: u64 = 512;
+
+/// 一次 I/O 的标识：设备 + 起始扇区
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IoKey {
+    dev: u32,
+    _pad: u32, // 显式填充，保证 map key 的每个字节都已初始化
+    sector: u64,
+}
+
+/// 已下发、未完成的请求
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IoStart {
+    pid: u32,
+    bytes: u32,
+    ts: u64,
+}
+
+/// bio 提交时的 PID（LRU：被合并或未下发的 bio 自动淘汰）
+#[map]
+static mut BIO_TO_PID: LruHashMap<IoKey, u32> = LruHashMap::with_max_entries(16384, 0);
+
+/// 在途请求的开始时间
 #[map]
-static mut STORAGE_EVENTS: PerfEventArray<StorageEvent> = PerfEventArray::with_max_entries(1024, 0);
-
-/// Hook vfs_read - 文件系统读取
-#[kprobe(name = "vfs_read")]
-pub fn vfs_read(ctx: ProbeContext) -> u32 {
-    // TODO: 使用 CO-RE 提取文件路径和 I/O 大小
-    // 1. 从 kiocb 结构体提取文件描述符
-    // 2. 从 file 结构体提取路径（如果可获取）
-    // 3. 记录 I/O 开始时间
-    // 4. 在 vfs_read 返回时计算延迟
-    
-    // 占位实现
-    0
-}
-
-/// Hook vfs_write - 文件系统写入
-#[kprobe(name = "vfs_write")]
-pub fn vfs_write(ctx: ProbeContext) -> u32 {
-    // TODO: 类似 vfs_read 的实现
-    0
-}
-
-/// Hook blk_mq_submit_bio - 块设备层提交
-#[kprobe(name = "blk_mq_submit_bio")]
-pub fn blk_mq_submit_bio(ctx: ProbeContext) -> u32 {
-    // TODO: 提取 I/O 大小和延迟
-    // 1. 从 bio 结构体提取大小
-    // 2. 记录提交时间
-    // 3. 在完成时计算延迟
-    
-    // 占位实现
-    0
+static mut IO_START: LruHashMap<IoKey, IoStart> = LruHashMap::with_max_entries(16384, 0);
+
+/// 各设备的在途请求数（队列深度）
+#[map]
+static mut INFLIGHT: HashMap<u32, u32> = HashMap::with_max_entries(256, 0);
+
+/// 存储事件输出（每个 I/O 一条，高 IOPS 设备上事件量大，缓冲区比网络探针大）
+#[map]
+static mut STORAGE_EVENTS: RingBuf = RingBuf::with_byte_size(1024 * 1024, 0);
+
+/// Hook block:block_bio_queue：在发起 I/O 的进程上下文中记录 PID
+#[tracepoint(name = "block_bio_queue")]
+pub fn block_bio_queue(ctx: TracePointContext) -> u32 {
+    match try_block_bio_queue(&ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[inline]
+fn try_block_bio_queue(ctx: &TracePointContext) -> Result<u32, i64> {
+    let key = read_io_key(ctx)?;
+    let pid = (bpf_get_current_pid_tgid() >> 32) as u32;
+    unsafe {
+        BIO_TO_PID.insert(&key, &pid, 0)?;
+    }
+    Ok(0)
+}
+
+/// Hook block:block_rq_issue：请求下发到驱动
+#[tracepoint(name = "block_rq_issue")]
+pub fn block_rq_issue(ctx: TracePointContext) -> u32 {
+    match try_block_rq_issue(&ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[inline]
+fn try_block_rq_issue(ctx: &TracePointContext) -> Result<u32, i64> {
+    let key = read_io_key(ctx)?;
+    let nr_sector: u32 = unsafe { ctx.read_at(NR_SECTOR_OFFSET)? };
+
+    // 反查提交 bio 的进程；找不到时（如 bio 被合并到其他请求）退回当前 PID
+    let pid = unsafe {
+        BIO_TO_PID
+            .get(&key)
+            .copied()
+            .unwrap_or_else(|| (bpf_get_current_pid_tgid() >> 32) as u32)
+    };
+
+    unsafe {
+        // 重新下发（requeue）的请求不重复计入在途数
+        if IO_START.get(&key).is_none() {
+            match INFLIGHT.get_ptr_mut(&key.dev) {
+                // 多 CPU 并发更新时为近似值，仅用于观测
+                Some(depth) => *depth += 1,
+                None => {
+                    let _ = INFLIGHT.insert(&key.dev, &1, 0);
+                }
+            }
+        }
+        IO_START.insert(
+            &key,
+            &IoStart {
+                pid,
+                bytes: (nr_sector as u64 * SECTOR_SIZE) as u32,
+                ts: bpf_ktime_get_ns(),
+            },
+            0,
+        )?;
+    }
+    Ok(0)
+}
+
+/// Hook block:block_rq_complete：请求完成，输出延迟和大小
+#[tracepoint(name = "block_rq_complete")]
+pub fn block_rq_complete(ctx: TracePointContext) -> u32 {
+    match try_block_rq_complete(&ctx) {
+        Ok(ret) => ret,
+        Err(_) => 0,
+    }
+}
+
+#[inline]
+fn try_block_rq_complete(ctx: &TracePointContext) -> Result<u32, i64> {
+    let key = read_io_key(ctx)?;
+
+    // 探针加载前已下发的请求没有开始时间，跳过
+    let start = match unsafe { IO_START.get(&key) } {
+        Some(start) => *start,
+        None => return Ok(0),
+    };
+
+    // 完成时的队列深度（含本请求）
+    let queue_depth = unsafe {
+        match INFLIGHT.get_ptr_mut(&key.dev) {
+            Some(depth) => {
+                let current = *depth;
+                *depth = current.saturating_sub(1);
+                current
+            }
+            None => 1,
+        }
+    };
+
+    unsafe {
+        let _ = IO_START.remove(&key);
+        let _ = BIO_TO_PID.remove(&key);
+    }
+
+    let now = unsafe { bpf_ktime_get_ns() };
+    let event = StorageEvent {
+        pid: start.pid,
+        event_type: 1, // I/O 完成
+        io_latency_ns: now.saturating_sub(start.ts),
+        io_size: start.bytes as u64,
+        timestamp: now,
+        dev: key.dev,
+        queue_depth,
+    };
+
+    if unsafe { STORAGE_EVENTS.output(&event, 0) }.is_err() {
+        record_dropped(DROPPED_STORAGE);
+    }
+
+    Ok(0)
+}
+
+/// 从 tracepoint 参数读取设备号和起始扇区
+#[inline]
+fn read_io_key(ctx: &TracePointContext) -> Result<IoKey, i64> {
+    let dev: u32 = unsafe { ctx.read_at(DEV_OFFSET)? };
+    let sector: u64 = unsafe { ctx.read_at(SECTOR_OFFSET)? };
+    Ok(IoKey { dev, _pad: 0, sector })
 }
@@ -1,26 +1,78 @@
 use aya::{
-    maps::perf::AsyncPerfEventArrayBuffer,
+    maps::{HashMap as BpfHashMap, MapData, RingBuf},
     programs::KProbe,
-    util::online_cpus,
     Bpf,
 };
 use aya_log::BpfLogger;
-use bytes::BytesMut;
 use clap::Parser;
 use log::{info, warn};
 use std::convert::TryFrom;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::unix::AsyncFd;
 use tokio::signal;
-use ark_probe_ebpf_ebpf::NetworkEvent;
+use tokio::task::JoinHandle;
+use ark_probe_ebpf_ebpf::{NetworkEvent, RdmaEvent, SocketTuple, StorageEvent, AF_INET, SOCKET_TO_PID_ENTRIES};
 
-/// Ark eBPF 网络探针
-/// 监控 TCP 重传事件，输出 JSONL 格式给 Ark 核心
+mod metrics;
+mod rdma;
+mod storage;
+
+use metrics::DroppedSamples;
+use rdma::{RdmaAggregator, RdmaThresholds};
+use storage::StorageAggregator;
+
+/// 支持的探针
+const PROBES: [&str; 3] = ["network", "storage", "rdma"];
+
+/// debug 格式下打印 SOCKET_TO_PID 占用的周期
+const SOCKET_MAP_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// SOCKET_TO_PID 的 key（aya 要求用户态读取的 map key 实现 Pod）
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+struct SocketKey(SocketTuple);
+
+unsafe impl aya::Pod for SocketKey {}
+
+/// Ark eBPF 探针
+/// 监控 TCP 重传、块设备 I/O 和 RDMA，输出 JSONL 格式给 Ark 核心
 #[derive(Parser)]
 #[command(name = "ark-probe-ebpf")]
-#[command(about = "eBPF 网络探针：监控 TCP 重传和丢包事件")]
+#[command(about = "eBPF 探针：监控 TCP 重传和丢包事件、块设备 I/O 延迟和队列深度、RDMA 吞吐和拥塞")]
 struct Cli {
     /// 输出格式：jsonl（默认）或 debug
     #[arg(long, default_value = "jsonl")]
     format: String,
+
+    /// 启用的探针，逗号分隔：network（TCP 重传）、storage（块设备 I/O）、rdma（RDMA 吞吐和拥塞）
+    #[arg(long, value_delimiter = ',', default_value = "network,storage,rdma")]
+    probes: Vec<String>,
+
+    /// 存储事件的聚合周期（毫秒），每个周期按进程和设备输出一次 storage.iops / storage.qdepth
+    #[arg(long, default_value_t = 1000)]
+    storage_interval_ms: u64,
+
+    /// RDMA 计数器的采样周期（毫秒）
+    #[arg(long, default_value_t = 1000)]
+    rdma_interval_ms: u64,
+
+    /// 每秒 PFC pause 帧数超过该值时上报 pfc_storm
+    #[arg(long, default_value_t = 1000.0)]
+    rdma_pfc_threshold: f64,
+
+    /// 每秒 CNP（拥塞通知）数超过该值时上报 rdma_congestion
+    #[arg(long, default_value_t = 1000.0)]
+    rdma_cnp_threshold: f64,
+
+    /// 内核 verbs 的发送到完成延迟超过该值（毫秒）时上报 transport.drop
+    #[arg(long, default_value_t = 10.0)]
+    rdma_slow_ms: f64,
+
+    /// 自身指标（RingBuf 丢弃的事件数）的监听地址，如 0.0.0.0:9101；不指定时只在有丢弃时打印警告
+    #[arg(long)]
+    metrics_addr: Option<SocketAddr>,
 }
 
 #[tokio::main]
@@ -28,6 +80,10 @@ async fn main() -> Result<(), anyhow::Error> {
     env_logger::init();
     
     let cli = Cli::parse();
+    if let Some(probe) = cli.probes.iter().find(|p| !PROBES.contains(&p.as_str())) {
+        return Err(anyhow::anyhow!("未知的探针: {}（可选 {}）", probe, PROBES.join(", ")));
+    }
+    let enabled = |probe: &str| cli.probes.iter().any(|p| p == probe);
     
     // 加载 eBPF 程序
     // 注意：实际运行时，eBPF 字节码应该从文件系统加载
@@ -41,58 +97,124 @@ async fn main() -> Result<(), anyhow::Error> {
         warn!("Failed to initialize eBPF logger: {}", e);
     }
 
-    // 加载 kprobe 程序
-    // 1. tcp_retransmit_skb：捕获 TCP 重传事件
-    let program: &mut KProbe = bpf.program_mut("tcp_retransmit_skb").unwrap().try_into()?;
-    program.load()?;
-    program.attach("tcp_retransmit_skb", 0)?;
-    info!("eBPF 程序已加载并附加到 tcp_retransmit_skb");
-
-    // 2. tcp_sendmsg：建立 socket -> PID 映射
-    let program2: &mut KProbe = bpf.program_mut("tcp_sendmsg").unwrap().try_into()?;
-    program2.load()?;
-    program2.attach("tcp_sendmsg", 0)?;
-    info!("eBPF 程序已加载并附加到 tcp_sendmsg");
-
-    // 获取 PerfEventArray
-    let mut perf_array = bpf.take_map("NETWORK_EVENTS").unwrap();
-    let perf_array = perf_array.try_into().unwrap();
-
-    // 为每个 CPU 创建异步缓冲区
-    let cpus = online_cpus().unwrap();
     let mut handles = Vec::new();
 
-    for cpu_id in cpus {
-        let mut buf = AsyncPerfEventArrayBuffer::new(perf_array.clone(), cpu_id)?;
+    // RingBuf 写满时内核态丢弃事件并计数
+    let dropped = Arc::new(DroppedSamples::new(&mut bpf)?);
+    handles.push(metrics::spawn_monitor(Arc::clone(&dropped)));
+    if let Some(addr) = cli.metrics_addr {
+        handles.push(metrics::serve(addr, Arc::clone(&dropped)).await?);
+    }
+
+    if enabled("network") {
+        // 加载 kprobe 程序
+        // 1. tcp_retransmit_skb：捕获 TCP 重传事件
+        let program: &mut KProbe = bpf.program_mut("tcp_retransmit_skb").unwrap().try_into()?;
+        program.load()?;
+        program.attach("tcp_retransmit_skb", 0)?;
+        info!("eBPF 程序已加载并附加到 tcp_retransmit_skb");
+
+        // 2. tcp_sendmsg：建立 socket -> PID 映射
+        let program2: &mut KProbe = bpf.program_mut("tcp_sendmsg").unwrap().try_into()?;
+        program2.load()?;
+        program2.attach("tcp_sendmsg", 0)?;
+        info!("eBPF 程序已加载并附加到 tcp_sendmsg");
+
+        // 3. tcp_set_state：连接关闭时删除 socket -> PID 映射
+        let program3: &mut KProbe = bpf.program_mut("tcp_set_state").unwrap().try_into()?;
+        program3.load()?;
+        program3.attach("tcp_set_state", 0)?;
+        info!("eBPF 程序已加载并附加到 tcp_set_state");
+
         let format = cli.format.clone();
-        
-        let handle = tokio::spawn(async move {
-            let mut buffers = (0..10)
-                .map(|_| BytesMut::with_capacity(1024))
-                .collect::<Vec<_>>();
+        handles.push(spawn_reader(&mut bpf, "NETWORK_EVENTS", move |buf| {
+            if let Ok(event) = parse_event::<NetworkEvent>(buf) {
+                output_event(&event, &format);
+            }
+        })?);
+
+        // 调试时定期打印映射表占用，确认连接关闭后条目被及时删除
+        if cli.format == "debug" {
+            let map = bpf
+                .take_map("SOCKET_TO_PID")
+                .ok_or_else(|| anyhow::anyhow!("eBPF map SOCKET_TO_PID 不存在"))?;
+            let socket_to_pid = BpfHashMap::<MapData, SocketKey, u32>::try_from(map)?;
+            handles.push(tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(SOCKET_MAP_REPORT_INTERVAL);
+                loop {
+                    ticker.tick().await;
+                    let entries = socket_to_pid.keys().filter(Result::is_ok).count();
+                    info!("SOCKET_TO_PID 占用: {}/{}", entries, SOCKET_TO_PID_ENTRIES);
+                }
+            }));
+        }
+        info!("开始监控 TCP 重传事件...");
+    }
 
+    if enabled("storage") {
+        // block_bio_queue / block_rq_issue / block_rq_complete：块设备 I/O 的 PID、延迟和队列深度
+        storage::attach(&mut bpf)?;
+
+        let aggregator = Arc::new(StorageAggregator::default());
+        let recorder = Arc::clone(&aggregator);
+        handles.push(spawn_reader(&mut bpf, "STORAGE_EVENTS", move |buf| {
+            if let Ok(event) = parse_event::<StorageEvent>(buf) {
+                recorder.record(&event);
+            }
+        })?);
+
+        // 按周期输出聚合结果
+        let interval = Duration::from_millis(cli.storage_interval_ms.max(100));
+        let format = cli.format.clone();
+        handles.push(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
             loop {
-                let events = buf.read_events(&mut buffers).await;
-                match events {
-                    Ok(events) => {
-                        for buf in buffers.iter().take(events.read) {
-                            if let Ok(event) = parse_network_event(buf) {
-                                output_event(&event, &format);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        warn!("读取 PerfEventArray 失败: {}", e);
-                        break;
-                    }
+                ticker.tick().await;
+                aggregator.flush(interval, &format);
+            }
+        }));
+        info!("开始监控块设备 I/O...");
+    }
+
+    if enabled("rdma") {
+        // mlx5_ib_post_send / mlx5_ib_poll_cq：内核 verbs 的发送字节数和完成延迟（未加载 mlx5_ib 时跳过）
+        let aggregator = Arc::new(RdmaAggregator::new(RdmaThresholds {
+            pfc_per_sec: cli.rdma_pfc_threshold,
+            cnp_per_sec: cli.rdma_cnp_threshold,
+            slow_ms: cli.rdma_slow_ms,
+        }));
+        let mut tx_bytes = None;
+        if rdma::attach(&mut bpf)? {
+            let recorder = Arc::clone(&aggregator);
+            handles.push(spawn_reader(&mut bpf, "RDMA_EVENTS", move |buf| {
+                if let Ok(event) = parse_event::<RdmaEvent>(buf) {
+                    recorder.record(&event);
                 }
+            })?);
+            let map = bpf
+                .take_map("RDMA_TX_BYTES")
+                .ok_or_else(|| anyhow::anyhow!("eBPF map RDMA_TX_BYTES 不存在"))?;
+            tx_bytes = Some(BpfHashMap::<MapData, u32, u64>::try_from(map)?);
+        }
+
+        // 按周期采样硬件计数器并输出
+        let interval = Duration::from_millis(cli.rdma_interval_ms.max(100));
+        let format = cli.format.clone();
+        handles.push(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let bytes = tx_bytes.as_mut().map(rdma::drain_tx_bytes).unwrap_or_default();
+                // 读取 sysfs、/proc 和执行 ethtool 是阻塞操作
+                let aggregator = Arc::clone(&aggregator);
+                let format = format.clone();
+                let _ = tokio::task::spawn_blocking(move || aggregator.flush(interval, bytes, &format)).await;
             }
-        });
-        
-        handles.push(handle);
+        }));
+        info!("开始监控 RDMA...");
     }
 
-    info!("开始监控 TCP 重传事件...");
     info!("按 Ctrl+C 退出");
 
     // 等待退出信号
@@ -107,14 +229,45 @@ async fn main() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
-/// 解析网络事件
-fn parse_network_event(buf: &BytesMut) -> Result<NetworkEvent, anyhow::Error> {
-    if buf.len() < core::mem::size_of::<NetworkEvent>() {
+/// 异步读取 RingBuf 中的事件交给 handler（所有 CPU 共用一个缓冲区，一个任务消费）
+fn spawn_reader<F>(bpf: &mut Bpf, map_name: &str, handler: F) -> Result<JoinHandle<()>, anyhow::Error>
+where
+    F: Fn(&[u8]) + Send + 'static,
+{
+    let map = bpf
+        .take_map(map_name)
+        .ok_or_else(|| anyhow::anyhow!("eBPF map {} 不存在", map_name))?;
+    let ring_buf = RingBuf::try_from(map)?;
+    let mut fd = AsyncFd::new(ring_buf)?;
+    let map_name = map_name.to_string();
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let mut guard = match fd.readable_mut().await {
+                Ok(guard) => guard,
+                Err(e) => {
+                    warn!("读取 {} 失败: {}", map_name, e);
+                    break;
+                }
+            };
+            // 一次唤醒取空缓冲区中的所有事件
+            let ring_buf = guard.get_inner_mut();
+            while let Some(item) = ring_buf.next() {
+                handler(&item);
+            }
+            guard.clear_ready();
+        }
+    }))
+}
+
+/// 解析内核态输出的事件结构体
+fn parse_event<T: Copy>(buf: &[u8]) -> Result<T, anyhow::Error> {
+    if buf.len() < core::mem::size_of::<T>() {
         return Err(anyhow::anyhow!("缓冲区太小"));
     }
 
     let event = unsafe {
-        core::ptr::read(buf.as_ptr() as *const NetworkEvent)
+        core::ptr::read_unaligned(buf.as_ptr() as *const T)
     };
 
     Ok(event)
@@ -122,17 +275,18 @@ fn parse_network_event(buf: &BytesMut) -> Result<NetworkEvent, anyhow::Error> {
 
 /// 输出事件（JSONL 格式）
 fn output_event(event: &NetworkEvent, format: &str) {
+    let tuple = &event.socket_tuple;
     match format {
         "jsonl" => {
             // 构建 entity_id：优先使用 socket 四元组，否则使用 PID
-            let entity_id = if event.socket_tuple.src_ip != 0 || event.socket_tuple.dst_ip != 0 {
+            let entity_id = if !tuple.is_empty() {
                 // 有 socket 信息，使用四元组构建 entity_id
                 format!(
                     "network-{}-{}-{}-{}",
-                    u32_to_ip_string(event.socket_tuple.src_ip),
-                    u32_to_ip_string(event.socket_tuple.dst_ip),
-                    u16::from_be(event.socket_tuple.src_port),
-                    u16::from_be(event.socket_tuple.dst_port)
+                    ip_to_string(tuple.family, &tuple.src_ip),
+                    ip_to_string(tuple.family, &tuple.dst_ip),
+                    tuple.src_port,
+                    tuple.dst_port
                 )
             } else {
                 // 没有 socket 信息，使用 PID（第一版）
@@ -151,14 +305,12 @@ fn output_event(event: &NetworkEvent, format: &str) {
             println!("{}", serde_json::to_string(&json_event).unwrap());
         }
         "debug" => {
-            if event.socket_tuple.src_ip != 0 || event.socket_tuple.dst_ip != 0 {
+            if !tuple.is_empty() {
                 info!(
-                    "TCP Retransmit: pid={}, socket={}:{}->{}:{}, count={}, ts={}",
+                    "TCP Retransmit: pid={}, socket={}->{}, count={}, ts={}",
                     event.pid,
-                    u32_to_ip_string(event.socket_tuple.src_ip),
-                    u16::from_be(event.socket_tuple.src_port),
-                    u32_to_ip_string(event.socket_tuple.dst_ip),
-                    u16::from_be(event.socket_tuple.dst_port),
+                    socket_addr_string(tuple.family, &tuple.src_ip, tuple.src_port),
+                    socket_addr_string(tuple.family, &tuple.dst_ip, tuple.dst_port),
                     event.retransmit_count,
                     event.timestamp
                 );
@@ -175,17 +327,33 @@ fn output_event(event: &NetworkEvent, format: &str) {
     }
 }
 
-/// 将 u32 IP 地址转换为点分十进制字符串
-fn u32_to_ip_string(ip: u32) -> String {
-    if ip == 0 {
-        "0.0.0.0".to_string()
+/// 将网络字节序的 IP 地址转换为字符串
+///
+/// IPv4（以及 IPv4 映射的 IPv6 地址 `::ffff:a.b.c.d`）输出点分十进制；IPv6 输出不压缩的 8 段十六进制，
+/// 不使用 `::` 缩写——状态图的节点 ID 以 `::` 分隔节点命名空间
+fn ip_to_string(family: u16, ip: &[u8; 16]) -> String {
+    let v4 = if family == AF_INET {
+        Some(Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3]))
+    } else {
+        Ipv6Addr::from(*ip).to_ipv4_mapped()
+    };
+    match v4 {
+        Some(v4) => v4.to_string(),
+        None => Ipv6Addr::from(*ip)
+            .segments()
+            .iter()
+            .map(|segment| format!("{:x}", segment))
+            .collect::<Vec<_>>()
+            .join(":"),
+    }
+}
+
+/// `a.b.c.d:port` 或 `[v6]:port`
+fn socket_addr_string(family: u16, ip: &[u8; 16], port: u16) -> String {
+    let addr = ip_to_string(family, ip);
+    if addr.contains(':') {
+        format!("[{}]:{}", addr, port)
     } else {
-        format!(
-            "{}.{}.{}.{}",
-            (ip >> 24) & 0xFF,
-            (ip >> 16) & 0xFF,
-            (ip >> 8) & 0xFF,
-            ip & 0xFF
-        )
+        format!("{}:{}", addr, port)
     }
 }
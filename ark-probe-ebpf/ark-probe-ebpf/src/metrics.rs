@@ -0,0 +1,126 @@
+//! 探针自身指标
+//!
+//! 内核态写 RingBuf 失败（缓冲区已满，用户态来不及消费）时在 `DROPPED_SAMPLES`（PerCpuArray，按探针分槽）中计数。
+//! 用户态定期汇总各 CPU 的计数，新增丢弃时打印警告；指定 `--metrics-addr` 时以 Prometheus 文本格式暴露：
+//! `ark_probe_ebpf_dropped_samples_total{probe="network|storage|rdma"}`。
+
+use aya::{
+    maps::{MapData, PerCpuArray},
+    Bpf,
+};
+use log::{info, warn};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use ark_probe_ebpf_ebpf::{DROPPED_NETWORK, DROPPED_RDMA, DROPPED_STORAGE};
+
+/// 丢弃计数的检查周期
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 探针名与 DROPPED_SAMPLES 下标
+const SLOTS: [(&str, u32); 3] = [
+    ("network", DROPPED_NETWORK),
+    ("storage", DROPPED_STORAGE),
+    ("rdma", DROPPED_RDMA),
+];
+
+/// 内核态的丢弃计数
+pub struct DroppedSamples {
+    array: PerCpuArray<MapData, u64>,
+}
+
+impl DroppedSamples {
+    pub fn new(bpf: &mut Bpf) -> Result<Self, anyhow::Error> {
+        let map = bpf
+            .take_map("DROPPED_SAMPLES")
+            .ok_or_else(|| anyhow::anyhow!("eBPF map DROPPED_SAMPLES 不存在"))?;
+        Ok(Self {
+            array: PerCpuArray::try_from(map)?,
+        })
+    }
+
+    /// 各探针的累计丢弃数（所有 CPU 之和）
+    pub fn totals(&self) -> Vec<(&'static str, u64)> {
+        SLOTS
+            .iter()
+            .map(|&(probe, index)| {
+                let total = self
+                    .array
+                    .get(&index, 0)
+                    .map(|values| values.iter().sum())
+                    .unwrap_or_default();
+                (probe, total)
+            })
+            .collect()
+    }
+}
+
+/// 定期检查丢弃计数，有新增时打印警告
+pub fn spawn_monitor(dropped: Arc<DroppedSamples>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut last = vec![0u64; SLOTS.len()];
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            ticker.tick().await;
+            for (i, (probe, total)) in dropped.totals().into_iter().enumerate() {
+                if total > last[i] {
+                    warn!(
+                        "{} 探针的 RingBuf 已满，{} 秒内丢弃 {} 个事件（累计 {}）",
+                        probe,
+                        CHECK_INTERVAL.as_secs(),
+                        total - last[i],
+                        total
+                    );
+                }
+                last[i] = total;
+            }
+        }
+    })
+}
+
+/// 在 addr 上以 Prometheus 文本格式暴露自身指标（任意路径均返回指标）
+pub async fn serve(addr: SocketAddr, dropped: Arc<DroppedSamples>) -> Result<JoinHandle<()>, anyhow::Error> {
+    let listener = TcpListener::bind(addr).await?;
+    info!("自身指标：http://{}/metrics", addr);
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let mut stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("接受指标连接失败: {}", e);
+                    continue;
+                }
+            };
+            let body = render(&dropped.totals());
+            tokio::spawn(async move {
+                // 只读取请求头，不解析
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    }))
+}
+
+fn render(totals: &[(&str, u64)]) -> String {
+    let mut body = String::from(
+        "# HELP ark_probe_ebpf_dropped_samples_total RingBuf 已满而丢弃的事件数\n\
+         # TYPE ark_probe_ebpf_dropped_samples_total counter\n",
+    );
+    for (probe, total) in totals {
+        body.push_str(&format!(
+            "ark_probe_ebpf_dropped_samples_total{{probe=\"{}\"}} {}\n",
+            probe, total
+        ));
+    }
+    body
+}
@@ -0,0 +1,307 @@
+//! RDMA 探针的用户态部分
+//!
+//! 两个数据来源，每个周期汇总输出一次：
+//! - eBPF（内核 verbs，mlx5 驱动）：按进程的发送字节数和"发送 → 完成"延迟、完成错误
+//! - 硬件计数器（覆盖 NCCL 等绕过内核的用户态 verbs）：`/sys/class/infiniband/<设备>/ports/<端口>/` 下的
+//!   端口吞吐（`counters/port_xmit_data`、`port_rcv_data`）、拥塞通知（`hw_counters/np_cnp_sent`、`rp_cnp_handled`）、
+//!   链路状态，以及对应网卡 `ethtool -S` 中的 PFC pause 帧计数
+//!
+//! 输出事件（entity_id 为 `roce-<设备>-<端口>`，内核 verbs 的按进程统计为 `roce-kernel`）：
+//! - `transport.bw`：吞吐（Mbps）。端口吞吐按打开了 `/dev/infiniband/uverbs*` 的进程各输出一条，
+//!   在状态图中建立进程到端口的 Consumes 边，端口上的错误据此关联到进程
+//! - `error.net`：`pfc_storm:<每秒 pause 帧数>`、`rdma_congestion:<每秒 CNP 数>`、`rdma_link_down`、
+//!   `rdma_wc_error:<完成状态>`（如重传超限）
+//! - `transport.drop`：内核 verbs 的完成延迟超过 `--rdma-slow-ms` 时，值为毫秒数
+
+use aya::{
+    maps::{HashMap as BpfHashMap, MapData},
+    programs::KProbe,
+    Bpf,
+};
+use log::{info, warn};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use ark_probe_ebpf_ebpf::RdmaEvent;
+
+/// RDMA 设备的 sysfs 目录
+const INFINIBAND_SYSFS: &str = "/sys/class/infiniband";
+
+/// 内核 verbs 按进程统计时的 entity_id（eBPF 侧取不到设备名）
+const KERNEL_VERBS_ENTITY: &str = "roce-kernel";
+
+/// 完成状态：重传超限（enum ib_wc_status）
+const IB_WC_RETRY_EXC_ERR: u32 = 12;
+
+/// 触发告警的阈值
+#[derive(Debug, Clone)]
+pub struct RdmaThresholds {
+    /// 每秒 PFC pause 帧数超过该值时上报 pfc_storm
+    pub pfc_per_sec: f64,
+    /// 每秒 CNP 数超过该值时上报 rdma_congestion
+    pub cnp_per_sec: f64,
+    /// 内核 verbs 完成延迟超过该值（毫秒）时上报 transport.drop
+    pub slow_ms: f64,
+}
+
+/// 加载并附加 RDMA 探针；未加载 mlx5_ib 驱动时只采样硬件计数器，返回 false
+pub fn attach(bpf: &mut Bpf) -> Result<bool, anyhow::Error> {
+    let programs = [
+        ("mlx5_ib_post_send", "mlx5_ib_post_send"),
+        ("mlx5_ib_poll_cq", "mlx5_ib_poll_cq"),
+        ("mlx5_ib_poll_cq_ret", "mlx5_ib_poll_cq"),
+    ];
+    for (name, symbol) in programs {
+        let program: &mut KProbe = bpf
+            .program_mut(name)
+            .ok_or_else(|| anyhow::anyhow!("eBPF 程序 {} 不存在", name))?
+            .try_into()?;
+        program.load()?;
+        if let Err(e) = program.attach(symbol, 0) {
+            warn!("附加到 {} 失败（未加载 mlx5_ib 驱动？）: {}，只采样 RDMA 硬件计数器", symbol, e);
+            return Ok(false);
+        }
+        info!("eBPF 程序已加载并附加到 {}", symbol);
+    }
+    Ok(true)
+}
+
+/// 一个周期内某进程的内核 verbs 完成统计
+#[derive(Default)]
+struct CompletionStats {
+    completions: u64,
+    max_latency_ns: u64,
+    /// 完成错误（状态 → 次数）
+    errors: HashMap<u32, u64>,
+}
+
+/// 端口计数器的一次采样
+#[derive(Clone, Copy)]
+struct PortCounters {
+    /// 发送 + 接收字节数
+    bytes: u64,
+    cnp: u64,
+    pfc: u64,
+    active: bool,
+}
+
+/// 汇总 eBPF 事件和硬件计数器
+pub struct RdmaAggregator {
+    thresholds: RdmaThresholds,
+    completions: Mutex<HashMap<u32, CompletionStats>>,
+    /// (设备, 端口) → 上次采样
+    ports: Mutex<HashMap<(String, String), PortCounters>>,
+}
+
+impl RdmaAggregator {
+    pub fn new(thresholds: RdmaThresholds) -> Self {
+        Self {
+            thresholds,
+            completions: Mutex::new(HashMap::new()),
+            ports: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 记录一个内核 verbs 完成事件
+    pub fn record(&self, event: &RdmaEvent) {
+        let mut completions = self.completions.lock().unwrap();
+        let entry = completions.entry(event.pid).or_default();
+        entry.completions += 1;
+        entry.max_latency_ns = entry.max_latency_ns.max(event.latency_ns);
+        if event.event_type == 4 {
+            *entry.errors.entry(event.wc_status).or_default() += 1;
+        }
+    }
+
+    /// 输出本周期的事件。tx_bytes 为 eBPF 侧按进程统计的发送字节数（读取后已清零）
+    pub fn flush(&self, interval: Duration, tx_bytes: HashMap<u32, u64>, format: &str) {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let secs = interval.as_secs_f64().max(0.001);
+        let mut events = Vec::new();
+
+        // 内核 verbs：按进程的吞吐、慢完成和完成错误
+        for (pid, bytes) in tx_bytes {
+            if bytes > 0 {
+                let mbps = bytes as f64 * 8.0 / secs / 1_000_000.0;
+                events.push(event(ts, "transport.bw", KERNEL_VERBS_ENTITY, Some(pid), format!("{:.2}", mbps)));
+            }
+        }
+        let completions = std::mem::take(&mut *self.completions.lock().unwrap());
+        for (pid, stat) in completions {
+            let max_ms = stat.max_latency_ns as f64 / 1_000_000.0;
+            if max_ms > self.thresholds.slow_ms {
+                events.push(event(ts, "transport.drop", KERNEL_VERBS_ENTITY, Some(pid), format!("{:.2}", max_ms)));
+            }
+            for (status, count) in stat.errors {
+                let kind = if status == IB_WC_RETRY_EXC_ERR { "retry_exceeded".to_string() } else { status.to_string() };
+                events.push(event(ts, "error.net", KERNEL_VERBS_ENTITY, Some(pid), format!("rdma_wc_error:{}:{}", kind, count)));
+            }
+            if format == "debug" {
+                info!("RDMA verbs: pid={}, completions={}, max_latency={:.2} ms", pid, stat.completions, max_ms);
+            }
+        }
+
+        // 硬件计数器：端口吞吐、拥塞、PFC、链路状态
+        let consumers = verbs_consumers();
+        let mut ports = self.ports.lock().unwrap();
+        for ((device, port), current) in sample_ports() {
+            let entity_id = format!("roce-{}-{}", device, port);
+            let previous = ports.insert((device, port), current);
+            let Some(previous) = previous else {
+                continue;
+            };
+
+            if previous.active && !current.active {
+                events.push(event(ts, "error.net", &entity_id, None, "rdma_link_down".to_string()));
+            }
+            let pfc_rate = current.pfc.saturating_sub(previous.pfc) as f64 / secs;
+            if pfc_rate > self.thresholds.pfc_per_sec {
+                events.push(event(ts, "error.net", &entity_id, None, format!("pfc_storm:{:.0}", pfc_rate)));
+            }
+            let cnp_rate = current.cnp.saturating_sub(previous.cnp) as f64 / secs;
+            if cnp_rate > self.thresholds.cnp_per_sec {
+                events.push(event(ts, "error.net", &entity_id, None, format!("rdma_congestion:{:.0}", cnp_rate)));
+            }
+            let mbps = current.bytes.saturating_sub(previous.bytes) as f64 * 8.0 / secs / 1_000_000.0;
+            if mbps > 0.1 {
+                let value = format!("{:.2}", mbps);
+                if consumers.is_empty() {
+                    events.push(event(ts, "transport.bw", &entity_id, None, value));
+                } else {
+                    for pid in &consumers {
+                        events.push(event(ts, "transport.bw", &entity_id, Some(*pid), value.clone()));
+                    }
+                }
+            }
+            if format == "debug" {
+                info!(
+                    "RDMA port {}: bw={:.2} Mbps, cnp={:.0}/s, pfc={:.0}/s, active={}",
+                    entity_id, mbps, cnp_rate, pfc_rate, current.active
+                );
+            }
+        }
+
+        if format == "jsonl" {
+            for json_event in events {
+                println!("{}", serde_json::to_string(&json_event).unwrap());
+            }
+        }
+    }
+}
+
+/// 读取并清零 eBPF 侧的按进程发送字节数
+pub fn drain_tx_bytes(map: &mut BpfHashMap<MapData, u32, u64>) -> HashMap<u32, u64> {
+    let totals: HashMap<u32, u64> = map.iter().filter_map(Result::ok).collect();
+    for pid in totals.keys() {
+        let _ = map.remove(pid);
+    }
+    totals
+}
+
+fn event(ts: u64, event_type: &str, entity_id: &str, pid: Option<u32>, value: String) -> serde_json::Value {
+    serde_json::json!({
+        "ts": ts,
+        "event_type": event_type,
+        "entity_id": entity_id,
+        "pid": pid,
+        "value": value,
+    })
+}
+
+/// 采样所有 RDMA 端口的计数器
+fn sample_ports() -> Vec<((String, String), PortCounters)> {
+    let mut samples = Vec::new();
+    let Ok(devices) = std::fs::read_dir(INFINIBAND_SYSFS) else {
+        return samples;
+    };
+    for device in devices.flatten() {
+        let device_name = device.file_name().to_string_lossy().to_string();
+        let Ok(ports) = std::fs::read_dir(device.path().join("ports")) else {
+            continue;
+        };
+        for port in ports.flatten() {
+            let port_dir = port.path();
+            // port_xmit_data / port_rcv_data 以 4 字节为单位
+            let bytes = (read_counter(&port_dir.join("counters/port_xmit_data"))
+                + read_counter(&port_dir.join("counters/port_rcv_data")))
+                * 4;
+            let cnp = read_counter(&port_dir.join("hw_counters/np_cnp_sent"))
+                + read_counter(&port_dir.join("hw_counters/rp_cnp_handled"));
+            // state 形如 "4: ACTIVE"
+            let active = std::fs::read_to_string(port_dir.join("state"))
+                .map(|state| state.contains("ACTIVE"))
+                .unwrap_or(true);
+            let pfc = std::fs::read_to_string(port_dir.join("gid_attrs/ndevs/0"))
+                .ok()
+                .map(|netdev| pause_frames(netdev.trim()))
+                .unwrap_or(0);
+            samples.push((
+                (device_name.clone(), port.file_name().to_string_lossy().to_string()),
+                PortCounters { bytes, cnp, pfc, active },
+            ));
+        }
+    }
+    samples
+}
+
+fn read_counter(path: &Path) -> u64 {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// 网卡收到的 PFC pause 帧总数（`ethtool -S` 中的 rx_prio<N>_pause，没有按优先级统计时取 rx_pause_ctrl_phy）
+fn pause_frames(netdev: &str) -> u64 {
+    let Ok(output) = Command::new("ethtool").args(["-S", netdev]).output() else {
+        return 0;
+    };
+    let stats = String::from_utf8_lossy(&output.stdout);
+    let counters: Vec<(&str, u64)> = stats
+        .lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .filter_map(|(name, value)| Some((name.trim(), value.trim().parse().ok()?)))
+        .collect();
+    let per_priority: u64 = counters
+        .iter()
+        .filter(|(name, _)| name.starts_with("rx_prio") && name.ends_with("_pause"))
+        .map(|(_, value)| value)
+        .sum();
+    if per_priority > 0 {
+        return per_priority;
+    }
+    counters
+        .iter()
+        .find(|(name, _)| *name == "rx_pause_ctrl_phy")
+        .map(|(_, value)| *value)
+        .unwrap_or(0)
+}
+
+/// 打开了 uverbs 设备的进程（使用用户态 verbs 的 RDMA 应用）
+fn verbs_consumers() -> BTreeSet<u32> {
+    let mut pids = BTreeSet::new();
+    let Ok(procs) = std::fs::read_dir("/proc") else {
+        return pids;
+    };
+    for entry in procs.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+            continue;
+        };
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let uses_verbs = fds.flatten().any(|fd| {
+            std::fs::read_link(fd.path())
+                .is_ok_and(|target| target.to_string_lossy().starts_with("/dev/infiniband/uverbs"))
+        });
+        if uses_verbs {
+            pids.insert(pid);
+        }
+    }
+    pids
+}
@@ -0,0 +1,133 @@
+//! 存储探针的用户态部分
+//!
+//! 内核态每完成一个块设备 I/O 输出一个 StorageEvent，逐条输出会淹没 Ark，这里按 (PID, 设备) 聚合，
+//! 每个周期输出一次：
+//! - `storage.iops`：该进程在该设备上每秒完成的 I/O 数
+//! - `storage.qdepth`：周期内该进程的 I/O 完成时观察到的设备最大在途请求数
+//!
+//! entity_id 为 `storage-<设备名>`（如 `storage-nvme0n1`），设备名取自 `/sys/dev/block/<major>:<minor>/uevent`。
+
+use aya::{programs::TracePoint, Bpf};
+use log::info;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use ark_probe_ebpf_ebpf::StorageEvent;
+
+/// 存储探针的 tracepoint（均在 block 类别下）
+const TRACEPOINTS: [&str; 3] = ["block_bio_queue", "block_rq_issue", "block_rq_complete"];
+
+/// 加载并附加存储探针的 tracepoint
+pub fn attach(bpf: &mut Bpf) -> Result<(), anyhow::Error> {
+    for name in TRACEPOINTS {
+        let program: &mut TracePoint = bpf
+            .program_mut(name)
+            .ok_or_else(|| anyhow::anyhow!("eBPF 程序 {} 不存在", name))?
+            .try_into()?;
+        program.load()?;
+        program.attach("block", name)?;
+        info!("eBPF 程序已加载并附加到 block:{}", name);
+    }
+    Ok(())
+}
+
+/// 一个周期内 (PID, 设备) 的 I/O 统计
+#[derive(Default)]
+struct IoStats {
+    ios: u64,
+    bytes: u64,
+    total_latency_ns: u64,
+    max_latency_ns: u64,
+    max_queue_depth: u32,
+}
+
+/// 按 (PID, 设备) 聚合 I/O 完成事件
+#[derive(Default)]
+pub struct StorageAggregator {
+    stats: Mutex<HashMap<(u32, u32), IoStats>>,
+    /// 设备号 → 设备名
+    devices: Mutex<HashMap<u32, String>>,
+}
+
+impl StorageAggregator {
+    /// 记录一个 I/O 完成事件
+    pub fn record(&self, event: &StorageEvent) {
+        let mut stats = self.stats.lock().unwrap();
+        let entry = stats.entry((event.pid, event.dev)).or_default();
+        entry.ios += 1;
+        entry.bytes += event.io_size;
+        entry.total_latency_ns += event.io_latency_ns;
+        entry.max_latency_ns = entry.max_latency_ns.max(event.io_latency_ns);
+        entry.max_queue_depth = entry.max_queue_depth.max(event.queue_depth);
+    }
+
+    /// 输出并清空本周期的统计
+    pub fn flush(&self, interval: Duration, format: &str) {
+        let stats = std::mem::take(&mut *self.stats.lock().unwrap());
+        if stats.is_empty() {
+            return;
+        }
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let secs = interval.as_secs_f64().max(0.001);
+
+        for ((pid, dev), stat) in stats {
+            let entity_id = format!("storage-{}", self.device_name(dev));
+            let iops = stat.ios as f64 / secs;
+            match format {
+                "jsonl" => {
+                    // 内核线程（如 writeback）发起的 I/O 没有可归属的进程
+                    let pid = (pid != 0).then_some(pid);
+                    for (event_type, value) in [
+                        ("storage.iops", format!("{:.1}", iops)),
+                        ("storage.qdepth", stat.max_queue_depth.to_string()),
+                    ] {
+                        let json_event = serde_json::json!({
+                            "ts": ts,
+                            "event_type": event_type,
+                            "entity_id": entity_id,
+                            "pid": pid,
+                            "value": value,
+                        });
+                        println!("{}", serde_json::to_string(&json_event).unwrap());
+                    }
+                }
+                "debug" => {
+                    info!(
+                        "Block I/O: pid={}, device={}, iops={:.1}, throughput={:.1} MiB/s, avg_latency={:.2} ms, max_latency={:.2} ms, max_qdepth={}",
+                        pid,
+                        entity_id,
+                        iops,
+                        stat.bytes as f64 / secs / (1024.0 * 1024.0),
+                        stat.total_latency_ns as f64 / stat.ios as f64 / 1_000_000.0,
+                        stat.max_latency_ns as f64 / 1_000_000.0,
+                        stat.max_queue_depth
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// 设备名，读取失败时为 `<major>-<minor>`
+    fn device_name(&self, dev: u32) -> String {
+        let mut devices = self.devices.lock().unwrap();
+        devices
+            .entry(dev)
+            .or_insert_with(|| {
+                // 内核内部的 dev_t：高 12 位为主设备号，低 20 位为次设备号
+                let (major, minor) = (dev >> 20, dev & 0xfffff);
+                std::fs::read_to_string(format!("/sys/dev/block/{}:{}/uevent", major, minor))
+                    .ok()
+                    .and_then(|uevent| {
+                        uevent
+                            .lines()
+                            .find_map(|line| line.strip_prefix("DEVNAME=").map(str::to_string))
+                    })
+                    .unwrap_or_else(|| format!("{}-{}", major, minor))
+            })
+            .clone()
+    }
+}
@@ -0,0 +1,187 @@
+//! 状态图对比：修复前后快照的差异
+//!
+//! SRE 在执行修复后用它确认阻塞边确实消失，而不是只看进程状态
+
+use crate::graph::{Edge, EdgeType, Node, StateGraph};
+use std::collections::HashMap;
+
+/// 同一节点在两个快照中的前后状态
+#[derive(Debug, Clone)]
+pub struct NodeChange {
+    pub before: Node,
+    pub after: Node,
+}
+
+impl NodeChange {
+    /// 发生变化的 metadata 键：(键, 之前的值, 之后的值)，按键排序
+    pub fn changed_metadata(&self) -> Vec<(String, Option<String>, Option<String>)> {
+        let mut keys: Vec<&String> = self
+            .before
+            .metadata
+            .keys()
+            .chain(self.after.metadata.keys())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys.into_iter()
+            .filter_map(|k| {
+                let before = self.before.metadata.get(k);
+                let after = self.after.metadata.get(k);
+                (before != after).then(|| (k.clone(), before.cloned(), after.cloned()))
+            })
+            .collect()
+    }
+}
+
+/// 同一条边（类型、起点、终点相同）在两个快照中的前后状态
+#[derive(Debug, Clone)]
+pub struct EdgeChange {
+    pub before: Edge,
+    pub after: Edge,
+}
+
+/// 两个状态图之间的差异
+///
+/// 节点按 ID 比较；`last_update` 不计入变化，只有类型或 metadata 不同才算变化。
+/// 边按 (类型, from, to) 比较；佐证次数或置信度不同算变化，`ts` 不计入。
+/// 所有列表均按 ID 排序，同样的两个快照多次对比结果一致。
+#[derive(Debug, Clone, Default)]
+pub struct GraphDiff {
+    pub added_nodes: Vec<Node>,
+    pub removed_nodes: Vec<Node>,
+    pub changed_nodes: Vec<NodeChange>,
+    pub added_edges: Vec<Edge>,
+    pub removed_edges: Vec<Edge>,
+    pub changed_edges: Vec<EdgeChange>,
+}
+
+impl GraphDiff {
+    /// 两个快照是否等价
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.changed_nodes.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+            && self.changed_edges.is_empty()
+    }
+
+    /// 之后的快照中已经消失的阻塞边（BlockedBy / WaitsOn），即修复生效的证据
+    pub fn resolved_blocking_edges(&self) -> Vec<&Edge> {
+        self.removed_edges
+            .iter()
+            .filter(|e| matches!(e.edge_type, EdgeType::BlockedBy | EdgeType::WaitsOn))
+            .collect()
+    }
+}
+
+type EdgeKey<'a> = (&'static str, &'a str, &'a str);
+
+fn edge_key(edge: &Edge) -> EdgeKey<'_> {
+    (edge.edge_type.as_str(), &edge.from, &edge.to)
+}
+
+impl StateGraph {
+    /// 对比两个状态图：`self` 为之前的快照，`other` 为之后的快照
+    pub async fn diff(&self, other: &StateGraph) -> GraphDiff {
+        let before_nodes = self.get_nodes_async().await;
+        let after_nodes = other.get_nodes_async().await;
+        let before_edges = self.get_all_edges_async().await;
+        let after_edges = other.get_all_edges_async().await;
+
+        let mut diff = GraphDiff::default();
+
+        for (id, before) in &before_nodes {
+            match after_nodes.get(id) {
+                None => diff.removed_nodes.push(before.clone()),
+                Some(after) => {
+                    if before.node_type != after.node_type || before.metadata != after.metadata {
+                        diff.changed_nodes.push(NodeChange {
+                            before: before.clone(),
+                            after: after.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        diff.added_nodes = after_nodes
+            .iter()
+            .filter(|(id, _)| !before_nodes.contains_key(*id))
+            .map(|(_, n)| n.clone())
+            .collect();
+
+        let before_index: HashMap<EdgeKey, &Edge> =
+            before_edges.iter().map(|e| (edge_key(e), e)).collect();
+        let after_index: HashMap<EdgeKey, &Edge> =
+            after_edges.iter().map(|e| (edge_key(e), e)).collect();
+
+        for (key, before) in &before_index {
+            match after_index.get(key) {
+                None => diff.removed_edges.push((*before).clone()),
+                Some(after) => {
+                    if before.weight != after.weight || before.confidence != after.confidence {
+                        diff.changed_edges.push(EdgeChange {
+                            before: (*before).clone(),
+                            after: (*after).clone(),
+                        });
+                    }
+                }
+            }
+        }
+        diff.added_edges = after_index
+            .iter()
+            .filter(|(key, _)| !before_index.contains_key(*key))
+            .map(|(_, e)| (*e).clone())
+            .collect();
+
+        diff.added_nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        diff.removed_nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        diff.changed_nodes.sort_by(|a, b| a.after.id.cmp(&b.after.id));
+        diff.added_edges.sort_by(|a, b| edge_key(a).cmp(&edge_key(b)));
+        diff.removed_edges.sort_by(|a, b| edge_key(a).cmp(&edge_key(b)));
+        diff.changed_edges.sort_by(|a, b| edge_key(&a.after).cmp(&edge_key(&b.after)));
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{Event, EventType};
+    use crate::export::ExportFormat;
+    use crate::graph::StateGraph;
+
+    #[tokio::test]
+    async fn test_diff_after_fix_reports_resolved_blocking_edge() {
+        let before = StateGraph::new();
+        before
+            .process_event(&Event::new(EventType::ComputeUtil, "gpu-0".into(), "90".into(), None, Some(7)))
+            .await
+            .unwrap();
+        before
+            .process_event(&Event::new(EventType::ErrorHw, "gpu-0".into(), "XID_79".into(), None, None))
+            .await
+            .unwrap();
+
+        // 经 JSON 快照往返后与原图无差异
+        let snapshot = before.export(ExportFormat::Json).await;
+        let restored = StateGraph::import_json(&snapshot).unwrap();
+        assert!(before.diff(&restored).await.is_empty());
+
+        let after = StateGraph::new();
+        after
+            .process_event(&Event::new(EventType::ComputeUtil, "gpu-0".into(), "10".into(), None, Some(7)))
+            .await
+            .unwrap();
+
+        let diff = restored.diff(&after).await;
+        assert_eq!(diff.removed_nodes.len(), 1);
+        assert_eq!(diff.resolved_blocking_edges().len(), 1);
+        assert_eq!(diff.resolved_blocking_edges()[0].to, diff.removed_nodes[0].id);
+        assert_eq!(diff.changed_nodes.len(), 1);
+        assert_eq!(
+            diff.changed_nodes[0].changed_metadata(),
+            vec![("util".to_string(), Some("90".to_string()), Some("10".to_string()))]
+        );
+        assert!(diff.added_nodes.is_empty() && diff.added_edges.is_empty());
+    }
+}
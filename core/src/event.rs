@@ -39,6 +39,8 @@ pub enum EventType {
     // 8. 动作域
     #[serde(rename = "action.exec")]
     ActionExec,     // 系统干预动作 (如 kill/reset)
+    #[serde(rename = "rule.matched")]
+    RuleMatched,    // 规则命中 (daemon 流式匹配产生，entity_id 为规则名)
 }
 
 /// 统一的事件载体
@@ -52,6 +54,14 @@ pub struct Event {
     pub value: String,            // 具体载荷 (如 "85", "XID_79")
     #[serde(skip_serializing_if = "Option::is_none")]
     pub node_id: Option<String>,  // 节点ID（用于 Hub 命名空间隔离，如 "node-a", "node-b"）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe: Option<String>,    // 产生事件的探针名称（由 Agent 在中转时注入）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<String>, // W3C trace 上下文（启用 OpenTelemetry 时由 Agent 注入，Hub 据此关联 trace）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pod: Option<String>,      // 进程所属的 Pod（<namespace>/<name>，由 Agent 按 PID 的 cgroup 解析后注入）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_id: Option<String>, // 进程所属的容器 ID
 }
 
 impl Event {
@@ -76,6 +86,10 @@ impl Event {
             pid,
             value,
             node_id: None, // 默认无节点ID，由 Agent 在推送时注入
+            probe: None,
+            traceparent: None,
+            pod: None,
+            container_id: None,
         }
     }
 }
@@ -106,10 +120,11 @@ impl EventBus {
 
 /// 模拟探针：每秒随机生成几条事件用于测试
 pub async fn dummy_probe(tx: mpsc::Sender<Event>) -> Result<(), Box<dyn std::error::Error>> {
-    use rand::Rng;
+    use rand::{Rng, SeedableRng};
     use tokio::time::{sleep, Duration};
 
-    let mut rng = rand::thread_rng();
+    // thread_rng 不是 Send，跨 await 持有会导致无法 tokio::spawn
+    let mut rng = rand::rngs::StdRng::from_entropy();
     let mut pid_counter = 1000u32;
 
     loop {
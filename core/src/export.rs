@@ -0,0 +1,246 @@
+//! 状态图导出：DOT（Graphviz）、JSON、GraphML
+//!
+//! 用于事故复盘时把因果图交给可视化工具或其他分析系统；
+//! JSON 导出也可以作为快照重新导入（见 `StateGraph::import_json`）
+
+use crate::graph::{Edge, EdgeType, GraphConfig, Node, NodeType, StateGraph};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExportFormat {
+    Dot,
+    Json,
+    GraphMl,
+}
+
+impl ExportFormat {
+    /// 从名称解析导出格式（dot / json / graphml，大小写不敏感）
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "dot" | "graphviz" => Some(ExportFormat::Dot),
+            "json" => Some(ExportFormat::Json),
+            "graphml" => Some(ExportFormat::GraphMl),
+            _ => None,
+        }
+    }
+
+    /// 对应的 HTTP Content-Type
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Dot => "text/vnd.graphviz",
+            ExportFormat::Json => "application/json",
+            ExportFormat::GraphMl => "application/graphml+xml",
+        }
+    }
+}
+
+impl StateGraph {
+    /// 将当前图快照导出为指定格式的文本
+    /// 节点按 ID、边按 (ts, from, to) 排序，保证同一状态多次导出结果一致
+    pub async fn export(&self, format: ExportFormat) -> String {
+        let mut nodes: Vec<Node> = self.get_nodes_async().await.into_values().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut edges = self.get_all_edges_async().await;
+        edges.sort_by(|a, b| (a.ts, &a.from, &a.to).cmp(&(b.ts, &b.from, &b.to)));
+
+        match format {
+            ExportFormat::Dot => export_dot(&nodes, &edges),
+            ExportFormat::Json => export_json(&nodes, &edges),
+            ExportFormat::GraphMl => export_graphml(&nodes, &edges),
+        }
+    }
+}
+
+impl StateGraph {
+    /// 从 `ExportFormat::Json` 导出的快照恢复状态图
+    ///
+    /// 只恢复节点和边（导出不包含事件历史），用于离线对比和复盘
+    pub fn import_json(content: &str) -> Result<StateGraph, String> {
+        Self::import_json_with_config(content, GraphConfig::default())
+    }
+
+    /// 从快照恢复状态图并使用指定配置，恢复后可以继续处理事件（如 Hub 重启时从持久化的快照重建）
+    pub fn import_json_with_config(content: &str, config: GraphConfig) -> Result<StateGraph, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(content).map_err(|e| format!("快照不是合法 JSON: {}", e))?;
+
+        let mut nodes = Vec::new();
+        for n in value["nodes"].as_array().ok_or("快照缺少 nodes 数组")? {
+            let id = n["id"].as_str().ok_or("节点缺少 id")?;
+            let type_name = n["type"].as_str().unwrap_or_default();
+            let node_type = NodeType::parse(type_name)
+                .ok_or_else(|| format!("节点 {} 的类型无效: {}", id, type_name))?;
+            let metadata: HashMap<String, String> = n["metadata"]
+                .as_object()
+                .map(|m| {
+                    m.iter()
+                        .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                        .collect()
+                })
+                .unwrap_or_default();
+            nodes.push(Node {
+                id: id.to_string(),
+                node_type,
+                last_update: n["last_update"].as_u64().unwrap_or(0),
+                metadata,
+            });
+        }
+
+        let mut edges = Vec::new();
+        for e in value["edges"].as_array().ok_or("快照缺少 edges 数组")? {
+            let (Some(from), Some(to)) = (e["from"].as_str(), e["to"].as_str()) else {
+                return Err("边缺少 from/to".to_string());
+            };
+            let type_name = e["type"].as_str().unwrap_or_default();
+            let edge_type = EdgeType::parse(type_name)
+                .ok_or_else(|| format!("边 {} -> {} 的类型无效: {}", from, to, type_name))?;
+            edges.push(Edge {
+                edge_type,
+                from: from.to_string(),
+                to: to.to_string(),
+                ts: e["ts"].as_u64().unwrap_or(0),
+                // 旧版本导出不含 weight/confidence，视为一次直接观测
+                weight: e["weight"].as_u64().map(|w| w as u32).unwrap_or(1),
+                confidence: e["confidence"].as_f64().unwrap_or(1.0),
+            });
+        }
+
+        Ok(StateGraph::from_parts(nodes, edges, config))
+    }
+}
+
+fn sorted_metadata(node: &Node) -> Vec<(&String, &String)> {
+    let mut metadata: Vec<_> = node.metadata.iter().collect();
+    metadata.sort();
+    metadata
+}
+
+fn export_json(nodes: &[Node], edges: &[Edge]) -> String {
+    let nodes: Vec<serde_json::Value> = nodes
+        .iter()
+        .map(|n| {
+            json!({
+                "id": n.id,
+                "type": n.node_type.as_str(),
+                "last_update": n.last_update,
+                "metadata": n.metadata,
+            })
+        })
+        .collect();
+    let edges: Vec<serde_json::Value> = edges
+        .iter()
+        .map(|e| {
+            json!({
+                "from": e.from,
+                "to": e.to,
+                "type": e.edge_type.as_str(),
+                "ts": e.ts,
+                "weight": e.weight,
+                "confidence": e.confidence,
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&json!({ "nodes": nodes, "edges": edges }))
+        .unwrap_or_default()
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn export_dot(nodes: &[Node], edges: &[Edge]) -> String {
+    let mut out = String::from("digraph ark {\n    rankdir=LR;\n");
+
+    for node in nodes {
+        let (shape, color) = match node.node_type {
+            NodeType::Process => ("box", "steelblue"),
+            NodeType::Resource => ("ellipse", "gray40"),
+            NodeType::Error => ("octagon", "red"),
+            NodeType::Job => ("folder", "darkgreen"),
+            NodeType::Host => ("box3d", "black"),
+        };
+        let mut label = node.id.clone();
+        for (key, value) in sorted_metadata(node) {
+            label.push_str(&format!("\n{}={}", key, value));
+        }
+        out.push_str(&format!(
+            "    \"{}\" [label=\"{}\", shape={}, color={}];\n",
+            dot_escape(&node.id),
+            dot_escape(&label),
+            shape,
+            color
+        ));
+    }
+
+    for edge in edges {
+        out.push_str(&format!(
+            "    \"{}\" -> \"{}\" [label=\"{} ({:.2})\", penwidth={:.1}];\n",
+            dot_escape(&edge.from),
+            dot_escape(&edge.to),
+            edge.edge_type.as_str(),
+            edge.confidence,
+            1.0 + edge.confidence * 2.0
+        ));
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn export_graphml(nodes: &[Node], edges: &[Edge]) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"type\" for=\"node\" attr.name=\"type\" attr.type=\"string\"/>\n",
+        "  <key id=\"last_update\" for=\"node\" attr.name=\"last_update\" attr.type=\"long\"/>\n",
+        "  <key id=\"metadata\" for=\"node\" attr.name=\"metadata\" attr.type=\"string\"/>\n",
+        "  <key id=\"edge_type\" for=\"edge\" attr.name=\"type\" attr.type=\"string\"/>\n",
+        "  <key id=\"ts\" for=\"edge\" attr.name=\"ts\" attr.type=\"long\"/>\n",
+        "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>\n",
+        "  <key id=\"confidence\" for=\"edge\" attr.name=\"confidence\" attr.type=\"double\"/>\n",
+        "  <graph id=\"ark\" edgedefault=\"directed\">\n",
+    ));
+
+    for node in nodes {
+        let metadata: serde_json::Map<String, serde_json::Value> = sorted_metadata(node)
+            .into_iter()
+            .map(|(k, v)| (k.clone(), json!(v)))
+            .collect();
+        out.push_str(&format!("    <node id=\"{}\">\n", xml_escape(&node.id)));
+        out.push_str(&format!("      <data key=\"type\">{}</data>\n", node.node_type.as_str()));
+        out.push_str(&format!("      <data key=\"last_update\">{}</data>\n", node.last_update));
+        out.push_str(&format!(
+            "      <data key=\"metadata\">{}</data>\n",
+            xml_escape(&serde_json::Value::Object(metadata).to_string())
+        ));
+        out.push_str("    </node>\n");
+    }
+
+    for edge in edges {
+        out.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\">\n",
+            xml_escape(&edge.from),
+            xml_escape(&edge.to)
+        ));
+        out.push_str(&format!("      <data key=\"edge_type\">{}</data>\n", edge.edge_type.as_str()));
+        out.push_str(&format!("      <data key=\"ts\">{}</data>\n", edge.ts));
+        out.push_str(&format!("      <data key=\"weight\">{}</data>\n", edge.weight));
+        out.push_str(&format!("      <data key=\"confidence\">{}</data>\n", edge.confidence));
+        out.push_str("    </edge>\n");
+    }
+
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
@@ -1,23 +1,163 @@
 use crate::event::{Event, EventType};
-use std::collections::{HashMap, HashSet};
+use im::{HashMap as ImHashMap, Vector};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 
 /// 三大推导边类型
+///
+/// 后续版本可能新增边类型，外部 `match` 需要保留通配分支
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum EdgeType {
     Consumes,   // 进程 PID 消耗某物理资源
     WaitsOn,    // 进程 PID 正在等待某网络/存储资源完成
     BlockedBy,  // 资源/进程被某个 Error 彻底阻塞（根因）
+    BelongsTo,  // 进程隶属于某个 Job / Host
 }
 
-/// 图中的边
+impl EdgeType {
+    /// 规则文件和 API 中使用的蛇形名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgeType::Consumes => "consumes",
+            EdgeType::WaitsOn => "waits_on",
+            EdgeType::BlockedBy => "blocked_by",
+            EdgeType::BelongsTo => "belongs_to",
+        }
+    }
+
+    /// 从蛇形名称解析边类型
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "consumes" => Some(EdgeType::Consumes),
+            "waits_on" => Some(EdgeType::WaitsOn),
+            "blocked_by" => Some(EdgeType::BlockedBy),
+            "belongs_to" => Some(EdgeType::BelongsTo),
+            _ => None,
+        }
+    }
+}
+
+/// 图中的边（由状态图推导生成，外部只读）
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct Edge {
     pub edge_type: EdgeType,
     pub from: String,  // 源节点ID
     pub to: String,    // 目标节点ID
-    pub ts: u64,       // 事件时间戳
+    pub ts: u64,       // 最近一次佐证事件的时间戳
+    pub weight: u32,   // 佐证事件次数
+    pub confidence: f64, // 累积置信度（0.0 ~ 1.0）
+}
+
+/// 单次观测对边的佐证强度
+/// 直接观测（进程占用资源、隶属关系）视为确定；重传/丢包事件较强；单个低带宽采样较弱
+const EVIDENCE_DIRECT: f64 = 1.0;
+const EVIDENCE_ERROR: f64 = 0.9;
+const EVIDENCE_DROP: f64 = 0.6;
+const EVIDENCE_LOW_BW: f64 = 0.3;
+
+impl Edge {
+    /// 用一次新的佐证事件更新边：置信度按独立证据累积 1 - Π(1 - s)
+    fn corroborate(&mut self, strength: f64, ts: u64) {
+        self.weight = self.weight.saturating_add(1);
+        self.confidence = 1.0 - (1.0 - self.confidence) * (1.0 - strength);
+        self.ts = self.ts.max(ts);
+    }
+}
+
+/// 插入或佐证一条边：已存在时累积置信度，否则以本次证据强度新建
+/// 返回 true 表示新建了边
+fn upsert_edge(
+    edges: &mut Vector<Edge>,
+    edge_type: EdgeType,
+    from: &str,
+    to: &str,
+    ts: u64,
+    strength: f64,
+) -> bool {
+    if let Some(edge) = edges
+        .iter_mut()
+        .find(|e| e.edge_type == edge_type && e.from == from && e.to == to)
+    {
+        edge.corroborate(strength, ts);
+        return false;
+    }
+
+    edges.push_back(Edge {
+        edge_type,
+        from: from.to_string(),
+        to: to.to_string(),
+        ts,
+        weight: 1,
+        confidence: strength,
+    });
+    true
+}
+
+/// 节点 ID 中主机命名空间与实体之间的分隔符
+const NAMESPACE_SEPARATOR: &str = "::";
+
+/// 带命名空间的节点键
+///
+/// 集群模式下（Hub 汇聚多台主机的事件），同一个 `pid-1234` 在不同主机上是不同进程，
+/// 因此图中的节点 ID 以 `{node_id}::{entity}` 形式存储。外部代码应通过 `NodeKey`
+/// 构造和拆解节点 ID，而不是自行拼接或切分字符串。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NodeKey {
+    node_id: Option<String>,
+    entity: String,
+}
+
+impl NodeKey {
+    /// 构造节点键；`node_id` 为 None 表示单机模式（无命名空间）
+    pub fn new(node_id: Option<&str>, entity: impl Into<String>) -> Self {
+        Self {
+            node_id: node_id.map(str::to_string),
+            entity: entity.into(),
+        }
+    }
+
+    /// 进程节点键（实体为 "pid-{pid}"）
+    pub fn process(node_id: Option<&str>, pid: u32) -> Self {
+        Self::new(node_id, format!("pid-{}", pid))
+    }
+
+    /// 从图中的节点 ID 解析（不含分隔符时视为无命名空间）
+    pub fn parse(id: &str) -> Self {
+        match id.split_once(NAMESPACE_SEPARATOR) {
+            Some((node_id, entity)) => Self::new(Some(node_id), entity),
+            None => Self::new(None, id),
+        }
+    }
+
+    /// 所属主机的 node_id（单机模式下为 None）
+    pub fn node_id(&self) -> Option<&str> {
+        self.node_id.as_deref()
+    }
+
+    /// 去掉命名空间后的实体 ID（如 "pid-1234"、"gpu-0"）
+    pub fn entity(&self) -> &str {
+        &self.entity
+    }
+
+    /// 进程节点的 PID（非进程实体返回 None）
+    pub fn pid(&self) -> Option<u32> {
+        self.entity.strip_prefix("pid-")?.parse().ok()
+    }
+}
+
+impl std::fmt::Display for NodeKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.node_id {
+            Some(node_id) => write!(f, "{}{}{}", node_id, NAMESPACE_SEPARATOR, self.entity),
+            None => f.write_str(&self.entity),
+        }
+    }
 }
 
 /// 节点状态
@@ -29,42 +169,272 @@ pub struct Node {
     pub metadata: HashMap<String, String>, // 存储额外信息（如利用率、状态等）
 }
 
+impl Node {
+    /// 解析节点 ID 得到带命名空间的节点键
+    pub fn key(&self) -> NodeKey {
+        NodeKey::parse(&self.id)
+    }
+}
+
+/// 节点类型
+///
+/// 后续版本可能新增节点类型，外部 `match` 需要保留通配分支
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum NodeType {
     Process,  // 进程节点
     Resource, // 资源节点（GPU、网络、存储等）
     Error,    // 错误节点
+    Job,      // 训练/推理任务节点（跨主机全局唯一，ID 为 "job-{job_id}"）
+    Host,     // 主机节点（ID 为 "host-{node_id}"，仅在带 node_id 的事件中出现）
+}
+
+impl NodeType {
+    /// 指标标签和 API 中使用的蛇形名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeType::Process => "process",
+            NodeType::Resource => "resource",
+            NodeType::Error => "error",
+            NodeType::Job => "job",
+            NodeType::Host => "host",
+        }
+    }
+
+    /// 从蛇形名称解析节点类型
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "process" => Some(NodeType::Process),
+            "resource" => Some(NodeType::Resource),
+            "error" => Some(NodeType::Error),
+            "job" => Some(NodeType::Job),
+            "host" => Some(NodeType::Host),
+            _ => None,
+        }
+    }
+}
+
+/// 状态图配置：时间窗口与清理策略
+///
+/// 所有字段都有默认值，配置文件中只需写需要覆盖的项：
+/// ```yaml
+/// error_window_ms: 600000
+/// resource_stale_ms: 3600000
+/// max_nodes: 50000
+/// ```
+///
+/// 代码中请从 `GraphConfig::default()` 出发修改字段，后续版本可能新增配置项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct GraphConfig {
+    /// 错误节点保留窗口（毫秒），超过后错误节点及其 BlockedBy 边被移除
+    pub error_window_ms: u64,
+    /// 非 running 进程的过期时间（毫秒）
+    pub process_stale_ms: u64,
+    /// 资源节点过期时间（毫秒），None 表示资源节点永不过期（依赖探针心跳维持）
+    pub resource_stale_ms: Option<u64>,
+    /// 节点数上限，超出时按 last_update 淘汰最旧的节点，None 表示不限制
+    pub max_nodes: Option<usize>,
+    /// 边数上限，超出时按 ts 淘汰最旧的边，None 表示不限制
+    pub max_edges: Option<usize>,
+    /// 事件历史保留条数
+    pub history_capacity: usize,
+}
+
+impl Default for GraphConfig {
+    fn default() -> Self {
+        Self {
+            error_window_ms: 5 * 60 * 1000,    // 5分钟
+            process_stale_ms: 10 * 60 * 1000,  // 10分钟
+            resource_stale_ms: None,
+            max_nodes: None,
+            max_edges: None,
+            history_capacity: 1000,
+        }
+    }
+}
+
+impl GraphConfig {
+    /// 从 YAML 配置文件加载
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("读取图配置文件失败 {}: {}", path.display(), e))?;
+        serde_yaml::from_str(&content)
+            .map_err(|e| format!("解析图配置文件失败 {}: {}", path.display(), e))
+    }
+}
+
+/// job 节点 ID（不带命名空间，跨主机共享）
+fn job_node_id(job_id: &str) -> String {
+    format!("job-{}", job_id)
+}
+
+/// 把事件携带的 Pod 和容器 ID 写入进程节点的元数据
+fn insert_pod_metadata(metadata: &mut HashMap<String, String>, event: &Event) {
+    if let Some(ref pod) = event.pod {
+        metadata.insert("pod".to_string(), pod.clone());
+    }
+    if let Some(ref container_id) = event.container_id {
+        metadata.insert("container_id".to_string(), container_id.clone());
+    }
+}
+
+/// 图的只读快照
+///
+/// 节点和边使用持久化数据结构（`im`），克隆为 O(1)，写入方修改时只复制被改动的部分。
+/// 根因分析等查询在快照上进行，只在克隆时短暂持有读锁，不会阻塞事件写入
+#[derive(Clone)]
+struct GraphSnapshot {
+    nodes: ImHashMap<String, Node>,
+    edges: Vector<Edge>,
+}
+
+impl GraphSnapshot {
+    /// 逆向深度优先搜索根因，结果按置信度降序
+    fn root_causes(&self, node_id: &str) -> Vec<(String, f64)> {
+        let mut visited = HashSet::new();
+        let mut causes = Vec::new();
+        self.dfs_backward(node_id, 1.0, &mut visited, &mut causes);
+        causes.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        causes
+    }
+
+    fn dfs_backward(
+        &self,
+        node_id: &str,
+        path_confidence: f64,
+        visited: &mut HashSet<String>,
+        causes: &mut Vec<(String, f64)>,
+    ) {
+        if visited.contains(node_id) {
+            return;
+        }
+        visited.insert(node_id.to_string());
+
+        // 查找指向当前节点的 BlockedBy 边
+        for edge in self.edges.iter() {
+            if edge.edge_type == EdgeType::BlockedBy && edge.from == node_id {
+                if let Some(node) = self.nodes.get(&edge.to) {
+                    let confidence = path_confidence * edge.confidence;
+                    if node.node_type == NodeType::Error {
+                        let error_desc = format!(
+                            "{}: {}",
+                            edge.to,
+                            node.metadata
+                                .get("error_type")
+                                .unwrap_or(&"未知错误".to_string())
+                        );
+                        causes.push((error_desc, confidence));
+                    }
+                    // 继续递归查找
+                    self.dfs_backward(&edge.to, confidence, visited, causes);
+                }
+            }
+        }
+
+        // 查找 WaitsOn 边
+        for edge in self.edges.iter() {
+            if edge.edge_type == EdgeType::WaitsOn && edge.from == node_id {
+                causes.push((format!("等待资源: {}", edge.to), path_confidence * edge.confidence));
+            }
+        }
+    }
 }
 
 /// 状态图：基于事件流构建的实时因果图
+///
+/// 锁顺序：同时持有时先 nodes 后 edges
 pub struct StateGraph {
-    nodes: RwLock<HashMap<String, Node>>,
-    edges: RwLock<Vec<Edge>>,
-    error_window_ms: u64, // 错误窗口时间（默认5分钟）
+    nodes: RwLock<ImHashMap<String, Node>>,
+    edges: RwLock<Vector<Edge>>,
+    history: RwLock<VecDeque<Event>>, // 按时间戳有序的事件历史（乱序事件也会插入到正确位置）
+    config: GraphConfig,
+    max_seen_ts: AtomicU64,       // 已处理事件的最大时间戳（水位线）
+    reordered_events: AtomicU64,  // 早于水位线到达的事件数
+    stale_node_updates: AtomicU64, // 因时间戳落后而被跳过的节点状态覆盖次数
 }
 
 impl StateGraph {
-    /// 创建新的状态图
+    /// 创建新的状态图（使用默认配置）
     pub fn new() -> Self {
+        Self::with_config(GraphConfig::default())
+    }
+
+    /// 使用指定配置创建状态图
+    pub fn with_config(config: GraphConfig) -> Self {
         Self {
-            nodes: RwLock::new(HashMap::new()),
-            edges: RwLock::new(Vec::new()),
-            error_window_ms: 5 * 60 * 1000, // 5分钟
+            nodes: RwLock::new(ImHashMap::new()),
+            edges: RwLock::new(Vector::new()),
+            history: RwLock::new(VecDeque::with_capacity(config.history_capacity)),
+            config,
+            max_seen_ts: AtomicU64::new(0),
+            reordered_events: AtomicU64::new(0),
+            stale_node_updates: AtomicU64::new(0),
         }
     }
 
-    /// 根据 event.node_id 为节点 ID 添加命名空间前缀
-    /// 如果 event.node_id 存在，返回 "{node_id}::{node_id}"，否则返回原 ID
-    fn namespace_node_id(&self, event: &Event, node_id: &str) -> String {
-        if let Some(ref node_id_prefix) = event.node_id {
-            format!("{}::{}", node_id_prefix, node_id)
+    /// 用已有的节点和边构造状态图（用于从导出快照恢复，不含事件历史）
+    pub(crate) fn from_parts(nodes: Vec<Node>, edges: Vec<Edge>, config: GraphConfig) -> Self {
+        let graph = Self::with_config(config);
+        let max_ts = nodes.iter().map(|n| n.last_update).chain(edges.iter().map(|e| e.ts)).max();
+        graph.max_seen_ts.store(max_ts.unwrap_or(0), Ordering::Relaxed);
+        Self {
+            nodes: RwLock::new(nodes.into_iter().map(|n| (n.id.clone(), n)).collect()),
+            edges: RwLock::new(edges.into_iter().collect()),
+            ..graph
+        }
+    }
+
+    /// 取当前图的只读快照（只在克隆期间持有读锁）
+    async fn snapshot(&self) -> GraphSnapshot {
+        let nodes = self.nodes.read().await;
+        let edges = self.edges.read().await;
+        GraphSnapshot {
+            nodes: nodes.clone(),
+            edges: edges.clone(),
+        }
+    }
+
+    /// 单调推进节点时间戳：last_update 只前进不后退
+    /// 返回 true 表示事件不早于节点当前状态，可以覆盖 metadata；
+    /// 返回 false 表示乱序到达的旧事件，只保留关系，不回写状态
+    fn advance_node(&self, node: &mut Node, ts: u64) -> bool {
+        if ts >= node.last_update {
+            node.last_update = ts;
+            true
         } else {
-            node_id.to_string()
+            self.stale_node_updates.fetch_add(1, Ordering::Relaxed);
+            false
         }
     }
 
+    /// 将事件记录到历史（按 ts 有序插入，超出容量时丢弃最旧的事件）
+    async fn record_history(&self, event: &Event) {
+        let mut history = self.history.write().await;
+        let pos = history.partition_point(|e| e.ts <= event.ts);
+        history.insert(pos, event.clone());
+        while history.len() > self.config.history_capacity {
+            history.pop_front();
+        }
+    }
+
+    /// 根据 event.node_id 为节点 ID 添加命名空间前缀
+    /// 如果 event.node_id 存在，返回 "{node_id}::{entity}"，否则返回原 ID
+    fn namespace_node_id(&self, event: &Event, entity: &str) -> String {
+        NodeKey::new(event.node_id.as_deref(), entity).to_string()
+    }
+
     /// 处理事件，更新图状态
     pub async fn process_event(&self, event: &Event) -> Result<(), String> {
+        // 乱序检测：探针重连回放、多 CPU perf buffer 都可能送来比已处理事件更旧的时间戳
+        let watermark = self.max_seen_ts.fetch_max(event.ts, Ordering::Relaxed);
+        if event.ts < watermark {
+            self.reordered_events.fetch_add(1, Ordering::Relaxed);
+        }
+        self.record_history(event).await;
+
         match event.event_type {
             EventType::ProcessState => {
                 self.handle_process_state(event).await?;
@@ -84,17 +454,81 @@ impl StateGraph {
             EventType::TopoLinkDown => {
                 self.handle_topo_event(event).await?;
             }
+            EventType::ActionExec => {
+                self.handle_action_event(event).await;
+            }
             _ => {
-                // IntentRun, ActionExec 等其他事件类型暂不处理
+                // IntentRun, RuleMatched 等其他事件类型暂不处理
             }
         }
 
-        // 清理过期错误（只保留近5分钟的错误）
-        self.cleanup_old_errors(event.ts).await;
+        // 进程隶属关系：Process -> Job / Host
+        self.link_process_membership(event).await;
+
+        // 清理过期错误（只保留 error_window_ms 内的错误）
+        // 以水位线为准，避免旧事件把清理窗口拉回过去
+        self.cleanup_old_errors(watermark.max(event.ts)).await;
 
         Ok(())
     }
 
+    /// 为事件中的进程建立 BelongsTo 边：指向 job 节点（有 job_id 时）和 host 节点（有 node_id 时），
+    /// 并记录进程所属的 Pod 和容器。Job / Host 节点不加命名空间前缀，跨主机的同一任务汇聚到同一个 job 节点
+    async fn link_process_membership(&self, event: &Event) {
+        let Some(pid) = event.pid else {
+            return;
+        };
+        if event.job_id.is_none() && event.node_id.is_none() && event.pod.is_none() {
+            return;
+        }
+
+        let mut nodes = self.nodes.write().await;
+        let mut edges = self.edges.write().await;
+
+        let pid_str = self.namespace_node_id(event, &format!("pid-{}", pid));
+        // 只为已存在的进程建立关系，不凭隶属信息凭空创建进程
+        match nodes.get_mut(&pid_str) {
+            Some(node) if node.node_type == NodeType::Process => {
+                if let Some(ref job_id) = event.job_id {
+                    node.metadata
+                        .entry("job_id".to_string())
+                        .or_insert_with(|| job_id.clone());
+                }
+                insert_pod_metadata(&mut node.metadata, event);
+            }
+            _ => return,
+        }
+
+        let mut targets = Vec::new();
+        if let Some(ref job_id) = event.job_id {
+            targets.push((job_node_id(job_id), NodeType::Job, "job_id", job_id.clone()));
+        }
+        if let Some(ref host) = event.node_id {
+            targets.push((format!("host-{}", host), NodeType::Host, "host", host.clone()));
+        }
+
+        for (target_id, node_type, key, value) in targets {
+            let node = nodes.entry(target_id.clone()).or_insert_with(|| Node {
+                id: target_id.clone(),
+                node_type,
+                last_update: event.ts,
+                metadata: HashMap::from([(key.to_string(), value)]),
+            });
+            if event.ts > node.last_update {
+                node.last_update = event.ts;
+            }
+
+            upsert_edge(
+                &mut edges,
+                EdgeType::BelongsTo,
+                &pid_str,
+                &target_id,
+                event.ts,
+                EVIDENCE_DIRECT,
+            );
+        }
+    }
+
     /// 处理进程状态事件
     async fn handle_process_state(&self, event: &Event) -> Result<(), String> {
         if let Some(pid) = event.pid {
@@ -103,11 +537,19 @@ impl StateGraph {
             let mut nodes = self.nodes.write().await;
 
             if event.value == "start" {
+                // 已有更新的状态（如 exit 先于回放的 start 到达），不回退
+                if let Some(node) = nodes.get_mut(&pid_str) {
+                    if !self.advance_node(node, event.ts) {
+                        return Ok(());
+                    }
+                }
+
                 // 创建进程节点
                 let mut metadata = HashMap::new();
                 if let Some(ref job_id) = event.job_id {
                     metadata.insert("job_id".to_string(), job_id.clone());
                 }
+                insert_pod_metadata(&mut metadata, event);
                 metadata.insert("state".to_string(), "running".to_string());
 
                 nodes.insert(
@@ -122,8 +564,9 @@ impl StateGraph {
             } else if event.value == "exit" || event.value == "zombie" {
                 // 移除进程节点（或标记为已退出）
                 if let Some(node) = nodes.get_mut(&pid_str) {
-                    node.metadata.insert("state".to_string(), event.value.clone());
-                    node.last_update = event.ts;
+                    if self.advance_node(node, event.ts) {
+                        node.metadata.insert("state".to_string(), event.value.clone());
+                    }
                 }
             }
         }
@@ -151,8 +594,9 @@ impl StateGraph {
 
         // 更新资源状态
         if let Some(node) = nodes.get_mut(&resource_id) {
-            node.metadata.insert("util".to_string(), event.value.clone());
-            node.last_update = event.ts;
+            if self.advance_node(node, event.ts) {
+                node.metadata.insert("util".to_string(), event.value.clone());
+            }
         }
 
         // 如果有 PID，建立 Consumes 边
@@ -173,21 +617,14 @@ impl StateGraph {
                 );
             }
 
-            // 检查是否已存在相同的边
-            let edge_exists = edges.iter().any(|e| {
-                e.edge_type == EdgeType::Consumes
-                    && e.from == pid_str
-                    && e.to == event.entity_id
-            });
-
-            if !edge_exists {
-                edges.push(Edge {
-                    edge_type: EdgeType::Consumes,
-                    from: pid_str,
-                    to: resource_id.clone(),
-                    ts: event.ts,
-                });
-            }
+            upsert_edge(
+                &mut edges,
+                EdgeType::Consumes,
+                &pid_str,
+                &resource_id,
+                event.ts,
+                EVIDENCE_DIRECT,
+            );
         }
 
         Ok(())
@@ -233,8 +670,9 @@ impl StateGraph {
                 EventType::TransportDrop => "drop",
                 _ => "unknown",
             };
-            node.metadata.insert(key.to_string(), event.value.clone());
-            node.last_update = event.ts;
+            if self.advance_node(node, event.ts) {
+                node.metadata.insert(key.to_string(), event.value.clone());
+            }
         }
 
         // 处理 transport.drop 事件：建立 WaitsOn 边
@@ -271,21 +709,17 @@ impl StateGraph {
                     );
                 }
 
-                // 检查是否已存在 WaitsOn 边
-                let edge_exists = edges.iter().any(|e| {
-                    e.edge_type == EdgeType::WaitsOn
-                        && e.from == pid_str
-                        && e.to == resource_id
-                });
+                // 重复的丢包事件会累积 WaitsOn 边的置信度
+                let created = upsert_edge(
+                    &mut edges,
+                    EdgeType::WaitsOn,
+                    &pid_str,
+                    &resource_id,
+                    event.ts,
+                    EVIDENCE_DROP,
+                );
 
-                if !edge_exists {
-                    edges.push(Edge {
-                        edge_type: EdgeType::WaitsOn,
-                        from: pid_str.clone(),
-                        to: resource_id.clone(),
-                        ts: event.ts,
-                    });
-                    
+                if created {
                     // 日志输出（用于调试）
                     eprintln!(
                         "🔗 [图引擎] 建立阻塞关联: {} WaitsOn {} (transport.drop)",
@@ -295,43 +729,48 @@ impl StateGraph {
             }
         }
 
-        // 处理 TransportBw 事件（带宽低时也可能阻塞）
+        // 处理 TransportBw 事件：带 PID 时建立 Consumes 边（网卡 / RDMA 端口上的错误据此关联到进程），
+        // 带宽低时也可能阻塞
         if event.event_type == EventType::TransportBw {
             if let Some(pid) = event.pid {
+                let pid_str = format!("pid-{}", pid);
+                let pid_str = self.namespace_node_id(event, &pid_str);
+
+                if !nodes.contains_key(&pid_str) {
+                    nodes.insert(
+                        pid_str.clone(),
+                        Node {
+                            id: pid_str.clone(),
+                            node_type: NodeType::Process,
+                            last_update: event.ts,
+                            metadata: HashMap::new(),
+                        },
+                    );
+                }
+
+                upsert_edge(
+                    &mut edges,
+                    EdgeType::Consumes,
+                    &pid_str,
+                    &resource_id,
+                    event.ts,
+                    EVIDENCE_DIRECT,
+                );
+
                 let should_create_waitson = 
                     event.value.contains("IO_WAIT") || 
                     event.value.parse::<f64>().unwrap_or(1000.0) < 1.0;
 
                 if should_create_waitson {
-                    let pid_str = format!("pid-{}", pid);
-                    let pid_str = self.namespace_node_id(event, &pid_str);
-                    
-                    if !nodes.contains_key(&pid_str) {
-                        nodes.insert(
-                            pid_str.clone(),
-                            Node {
-                                id: pid_str.clone(),
-                                node_type: NodeType::Process,
-                                last_update: event.ts,
-                                metadata: HashMap::new(),
-                            },
-                        );
-                    }
-
-                    let edge_exists = edges.iter().any(|e| {
-                        e.edge_type == EdgeType::WaitsOn
-                            && e.from == pid_str
-                            && e.to == resource_id
-                    });
-
-                    if !edge_exists {
-                        edges.push(Edge {
-                            edge_type: EdgeType::WaitsOn,
-                            from: pid_str,
-                            to: resource_id.clone(),
-                            ts: event.ts,
-                        });
-                    }
+                    // 单个低带宽采样只是弱证据
+                    upsert_edge(
+                        &mut edges,
+                        EdgeType::WaitsOn,
+                        &pid_str,
+                        &resource_id,
+                        event.ts,
+                        EVIDENCE_LOW_BW,
+                    );
                 }
             }
         }
@@ -353,8 +792,12 @@ impl StateGraph {
         let error_id_base = format!("error-{}", event.entity_id);
         let error_id = self.namespace_node_id(event, &error_id_base);
         
-        // 创建错误节点
-        if !nodes.contains_key(&error_id) {
+        // 创建错误节点；重复上报时按时间戳合并（旧事件不回写 error_type）
+        if let Some(node) = nodes.get_mut(&error_id) {
+            if self.advance_node(node, event.ts) {
+                node.metadata.insert("error_type".to_string(), event.value.clone());
+            }
+        } else {
             nodes.insert(
                 error_id.clone(),
                 Node {
@@ -384,20 +827,14 @@ impl StateGraph {
         };
 
         for pid_str in affected_pids {
-            let edge_exists = edges.iter().any(|e| {
-                e.edge_type == EdgeType::BlockedBy
-                    && e.from == pid_str
-                    && e.to == error_id
-            });
-
-            if !edge_exists {
-                edges.push(Edge {
-                    edge_type: EdgeType::BlockedBy,
-                    from: pid_str,
-                    to: error_id.clone(),
-                    ts: event.ts,
-                });
-            }
+            upsert_edge(
+                &mut edges,
+                EdgeType::BlockedBy,
+                &pid_str,
+                &error_id,
+                event.ts,
+                EVIDENCE_ERROR,
+            );
         }
 
         Ok(())
@@ -409,12 +846,37 @@ impl StateGraph {
         self.handle_error_event(event).await
     }
 
-    /// 清理过期的错误节点和边（只保留近 error_window_ms 的错误）
+    /// 处理干预动作事件：在目标进程节点上记录最近一次动作及其结果
+    ///
+    /// entity_id 为动作类型，value 为结果；进程节点不存在时只保留在事件历史中。
+    /// 动作不是进程自身的状态变化，不推进 last_update
+    async fn handle_action_event(&self, event: &Event) {
+        let Some(pid) = event.pid else {
+            return;
+        };
+        let pid_str = self.namespace_node_id(event, &format!("pid-{}", pid));
+        let mut nodes = self.nodes.write().await;
+        let Some(node) = nodes.get_mut(&pid_str).filter(|n| n.node_type == NodeType::Process) else {
+            return;
+        };
+        let last_ts = node
+            .metadata
+            .get("last_action_ts")
+            .and_then(|ts| ts.parse::<u64>().ok())
+            .unwrap_or(0);
+        if event.ts >= last_ts {
+            node.metadata.insert("last_action".to_string(), event.entity_id.clone());
+            node.metadata.insert("last_action_result".to_string(), event.value.clone());
+            node.metadata.insert("last_action_ts".to_string(), event.ts.to_string());
+        }
+    }
+
+    /// 清理过期的错误节点和边（只保留近 error_window_ms 的错误），并执行容量限制
     async fn cleanup_old_errors(&self, current_ts: u64) {
         let mut nodes = self.nodes.write().await;
         let mut edges = self.edges.write().await;
 
-        let cutoff_ts = current_ts.saturating_sub(self.error_window_ms);
+        let cutoff_ts = current_ts.saturating_sub(self.config.error_window_ms);
 
         // 移除过期的错误节点
         let error_ids: Vec<String> = nodes
@@ -434,10 +896,10 @@ impl StateGraph {
             !(e.edge_type == EdgeType::BlockedBy && error_ids.contains(&e.to))
         });
 
-        // 清理非活跃进程（超过10分钟未更新）
+        // 清理非活跃进程（超过 process_stale_ms 未更新）
         // 重要：只清理明确标记为 exit/zombie 的进程，不清理稳态运行的进程
         // 即使长时间没有事件更新，只要状态是 running，就保留（可能是稳态工作负载）
-        let process_cutoff = current_ts.saturating_sub(10 * 60 * 1000);
+        let process_cutoff = current_ts.saturating_sub(self.config.process_stale_ms);
         let dead_pids: Vec<String> = nodes
             .iter()
             .filter(|(_, node)| {
@@ -467,13 +929,121 @@ impl StateGraph {
             !dead_pids.contains(&e.from) && !dead_pids.contains(&e.to)
         });
 
-        // 注意：资源节点（Resource）不会被清理，即使长时间没有更新
-        // 因为资源可能处于稳态（如 GPU 利用率保持 100%），需要探针发送心跳事件来维持
+        // 资源节点默认不清理，因为资源可能处于稳态（如 GPU 利用率保持 100%），
+        // 需要探针发送心跳事件来维持；配置了 resource_stale_ms 时才按过期时间清理
+        if let Some(resource_stale_ms) = self.config.resource_stale_ms {
+            let resource_cutoff = current_ts.saturating_sub(resource_stale_ms);
+            let stale_resources: HashSet<String> = nodes
+                .iter()
+                .filter(|(_, node)| {
+                    node.node_type == NodeType::Resource && node.last_update < resource_cutoff
+                })
+                .map(|(id, _)| id.clone())
+                .collect();
+
+            if !stale_resources.is_empty() {
+                nodes.retain(|id, _| !stale_resources.contains(id));
+                edges.retain(|e| {
+                    !stale_resources.contains(&e.from) && !stale_resources.contains(&e.to)
+                });
+            }
+        }
+
+        // Job / Host 节点没有任何进程隶属时随之移除
+        let members: HashSet<&str> = edges
+            .iter()
+            .filter(|e| e.edge_type == EdgeType::BelongsTo)
+            .map(|e| e.to.as_str())
+            .collect();
+        let orphans: Vec<String> = nodes
+            .values()
+            .filter(|n| {
+                matches!(n.node_type, NodeType::Job | NodeType::Host)
+                    && !members.contains(n.id.as_str())
+            })
+            .map(|n| n.id.clone())
+            .collect();
+        for id in &orphans {
+            nodes.remove(id);
+        }
+
+        // 节点数上限：按 last_update 淘汰最旧的节点及其关联边
+        if let Some(max_nodes) = self.config.max_nodes {
+            if nodes.len() > max_nodes {
+                let mut by_age: Vec<(u64, String)> = nodes
+                    .iter()
+                    .map(|(id, node)| (node.last_update, id.clone()))
+                    .collect();
+                by_age.sort();
+                let evicted: HashSet<String> = by_age
+                    .into_iter()
+                    .take(nodes.len() - max_nodes)
+                    .map(|(_, id)| id)
+                    .collect();
+                nodes.retain(|id, _| !evicted.contains(id));
+                edges.retain(|e| !evicted.contains(&e.from) && !evicted.contains(&e.to));
+            }
+        }
+
+        // 边数上限：按 ts 淘汰最旧的边
+        if let Some(max_edges) = self.config.max_edges {
+            if edges.len() > max_edges {
+                edges.sort_by(|a, b| a.ts.cmp(&b.ts));
+                let excess = edges.len() - max_edges;
+                *edges = edges.skip(excess);
+            }
+        }
+    }
+
+    /// 当前生效的图配置
+    pub fn config(&self) -> &GraphConfig {
+        &self.config
+    }
+
+    /// 运维操作：删除节点及其所有关联边
+    /// 返回被删除的节点和关联边数量；节点不存在时返回 None
+    pub async fn remove_node(&self, node_id: &str) -> Option<(Node, usize)> {
+        let mut nodes = self.nodes.write().await;
+        let mut edges = self.edges.write().await;
+
+        let node = nodes.remove(node_id)?;
+        let before = edges.len();
+        edges.retain(|e| e.from != node_id && e.to != node_id);
+
+        Some((node, before - edges.len()))
+    }
+
+    /// 运维操作：删除 from -> to 的边（edge_type 为 None 时删除两点间所有类型的边）
+    /// 返回删除的边数量
+    pub async fn remove_edge(&self, from: &str, to: &str, edge_type: Option<EdgeType>) -> usize {
+        let mut edges = self.edges.write().await;
+        let before = edges.len();
+        edges.retain(|e| {
+            !(e.from == from
+                && e.to == to
+                && edge_type.as_ref().map_or(true, |t| e.edge_type == *t))
+        });
+        before - edges.len()
+    }
+
+    /// 运维操作：覆盖节点的某个 metadata 字段
+    /// 返回字段的旧值；节点不存在时返回错误
+    pub async fn set_node_metadata(
+        &self,
+        node_id: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<Option<String>, String> {
+        let mut nodes = self.nodes.write().await;
+        let node = nodes
+            .get_mut(node_id)
+            .ok_or_else(|| format!("节点不存在: {}", node_id))?;
+        Ok(node.metadata.insert(key.to_string(), value.to_string()))
     }
 
     /// 获取所有活跃进程
     pub async fn get_active_processes(&self) -> Vec<Node> {
-        let nodes = self.nodes.read().await;
+        let nodes = self.snapshot().await.nodes;
         nodes
             .values()
             .filter(|node| {
@@ -488,7 +1058,7 @@ impl StateGraph {
     /// 获取进程消耗的资源
     pub async fn get_process_resources(&self, pid: u32) -> Vec<String> {
         let pid_str = format!("pid-{}", pid);
-        let edges = self.edges.read().await;
+        let edges = self.edges.read().await.clone();
         edges
             .iter()
             .filter(|e| {
@@ -506,66 +1076,162 @@ impl StateGraph {
 
     /// 逆向深度优先搜索：查找进程阻塞的根因（通过完整节点 ID，支持命名空间）
     /// 这是集群模式下的标准方法，可以直接处理 "node-a::pid-1234" 格式的节点 ID
+    /// 结果按置信度从高到低排序
     pub async fn find_root_cause_by_id(&self, node_id: &str) -> Vec<String> {
-        let edges = self.edges.read().await;
-        let nodes = self.nodes.read().await;
-        let mut visited = HashSet::new();
-        let mut causes = Vec::new();
+        self.find_root_cause_ranked_by_id(node_id)
+            .await
+            .into_iter()
+            .map(|(cause, _)| cause)
+            .collect()
+    }
 
-        self.dfs_backward(node_id, &edges, &nodes, &mut visited, &mut causes).await;
+    /// 带置信度的根因分析：置信度为路径上各边置信度之积，结果按置信度降序
+    ///
+    /// 在快照上搜索，查询期间不持有图的锁，事件写入不受影响
+    pub async fn find_root_cause_ranked_by_id(&self, node_id: &str) -> Vec<(String, f64)> {
+        self.snapshot().await.root_causes(node_id)
+    }
 
-        causes
+    /// 按完整节点 ID 获取节点快照
+    pub async fn node(&self, node_id: &str) -> Option<Node> {
+        self.nodes.read().await.get(node_id).cloned()
     }
 
-    async fn dfs_backward(
-        &self,
-        node_id: &str,
-        edges: &[Edge],
-        nodes: &HashMap<String, Node>,
-        visited: &mut HashSet<String>,
-        causes: &mut Vec<String>,
-    ) {
-        if visited.contains(node_id) {
-            return;
+    /// 按带命名空间的节点键查询节点
+    pub async fn node_by_key(&self, key: &NodeKey) -> Option<Node> {
+        self.node(&key.to_string()).await
+    }
+
+    /// 按节点键分析根因（集群模式下区分不同主机上的同名实体）
+    pub async fn find_root_cause_by_key(&self, key: &NodeKey) -> Vec<String> {
+        self.find_root_cause_by_id(&key.to_string()).await
+    }
+
+    /// 当前节点数
+    pub async fn node_count(&self) -> usize {
+        self.nodes.read().await.len()
+    }
+
+    /// 当前边数
+    pub async fn edge_count(&self) -> usize {
+        self.edges.read().await.len()
+    }
+
+    /// 查找属于指定 job_id 的所有进程节点（跨命名空间）
+    /// 沿 job 节点的 BelongsTo 入边遍历，结果按节点 ID 排序
+    pub async fn find_processes_by_job(&self, job_id: &str) -> Vec<Node> {
+        let job_id = job_node_id(job_id);
+        let GraphSnapshot { nodes, edges } = self.snapshot().await;
+        let mut processes: Vec<Node> = edges
+            .iter()
+            .filter(|e| e.edge_type == EdgeType::BelongsTo && e.to == job_id)
+            .filter_map(|e| nodes.get(&e.from))
+            .filter(|n| n.node_type == NodeType::Process)
+            .cloned()
+            .collect();
+        processes.sort_by(|a, b| a.id.cmp(&b.id));
+        processes
+    }
+
+    /// 查找属于指定 Pod（<namespace>/<name>）的所有进程节点（跨命名空间），结果按节点 ID 排序
+    pub async fn find_processes_by_pod(&self, pod: &str) -> Vec<Node> {
+        let nodes = self.snapshot().await.nodes;
+        let mut processes: Vec<Node> = nodes
+            .values()
+            .filter(|n| n.node_type == NodeType::Process && n.metadata.get("pod").map(String::as_str) == Some(pod))
+            .cloned()
+            .collect();
+        processes.sort_by(|a, b| a.id.cmp(&b.id));
+        processes
+    }
+
+    /// 查找运行在指定主机（node_id）上的所有进程节点
+    pub async fn find_processes_by_host(&self, host: &str) -> Vec<Node> {
+        let host_id = format!("host-{}", host);
+        let GraphSnapshot { nodes, edges } = self.snapshot().await;
+        let mut processes: Vec<Node> = edges
+            .iter()
+            .filter(|e| e.edge_type == EdgeType::BelongsTo && e.to == host_id)
+            .filter_map(|e| nodes.get(&e.from))
+            .filter(|n| n.node_type == NodeType::Process)
+            .cloned()
+            .collect();
+        processes.sort_by(|a, b| a.id.cmp(&b.id));
+        processes
+    }
+
+    /// 以节点为中心、沿边（不分方向）扩展 depth 跳的子图，不含事件历史；节点不存在时返回 None
+    ///
+    /// 用于按进程裁剪图：depth = 3 时包含进程 -> job <- 同 job 进程 -> 其资源，足够场景分析使用
+    pub async fn neighborhood(&self, node_id: &str, depth: usize) -> Option<StateGraph> {
+        let GraphSnapshot { nodes, edges } = self.snapshot().await;
+        if !nodes.contains_key(node_id) {
+            return None;
         }
-        visited.insert(node_id.to_string());
 
-        // 查找指向当前节点的 BlockedBy 边
-        for edge in edges.iter() {
-            if edge.edge_type == EdgeType::BlockedBy && edge.from == node_id {
-                if let Some(node) = nodes.get(&edge.to) {
-                    if node.node_type == NodeType::Error {
-                        let error_desc = format!(
-                            "{}: {}",
-                            edge.to,
-                            node.metadata
-                                .get("error_type")
-                                .unwrap_or(&"未知错误".to_string())
-                        );
-                        causes.push(error_desc);
+        let mut included: HashSet<&str> = HashSet::from([node_id]);
+        let mut frontier: HashSet<&str> = HashSet::from([node_id]);
+        for _ in 0..depth {
+            let mut next = HashSet::new();
+            for edge in edges.iter() {
+                for (near, far) in [(&edge.from, &edge.to), (&edge.to, &edge.from)] {
+                    if frontier.contains(near.as_str()) && included.insert(far.as_str()) {
+                        next.insert(far.as_str());
                     }
-                    // 继续递归查找
-                    self.dfs_backward(&edge.to, edges, nodes, visited, causes).await;
                 }
             }
-        }
-
-        // 查找 WaitsOn 边
-        for edge in edges.iter() {
-            if edge.edge_type == EdgeType::WaitsOn && edge.from == node_id {
-                causes.push(format!("等待资源: {}", edge.to));
+            if next.is_empty() {
+                break;
             }
+            frontier = next;
         }
+
+        let sub_nodes = included.iter().filter_map(|id| nodes.get(*id)).cloned().collect();
+        let sub_edges = edges
+            .iter()
+            .filter(|e| included.contains(e.from.as_str()) && included.contains(e.to.as_str()))
+            .cloned()
+            .collect();
+        Some(StateGraph::from_parts(sub_nodes, sub_edges, GraphConfig::default()))
     }
 
     /// 异步获取所有边（用于规则匹配）
     pub async fn get_all_edges_async(&self) -> Vec<Edge> {
-        self.edges.read().await.clone()
+        let edges = self.edges.read().await.clone();
+        edges.into_iter().collect()
     }
 
     /// 异步获取所有节点（用于场景分析）
     pub async fn get_nodes_async(&self) -> HashMap<String, Node> {
-        self.nodes.read().await.clone()
+        let nodes = self.nodes.read().await.clone();
+        nodes.into_iter().collect()
+    }
+
+    /// 获取最近的事件历史（按时间戳升序，最多 limit 条）
+    pub async fn recent_events(&self, limit: usize) -> Vec<Event> {
+        let history = self.history.read().await;
+        let skip = history.len().saturating_sub(limit);
+        history.iter().skip(skip).cloned().collect()
+    }
+
+    /// 以最新事件时间为终点、最近 window_ms 毫秒内的事件历史（按时间戳有序）
+    pub async fn recent_events_within(&self, window_ms: u64) -> Vec<Event> {
+        let history = self.history.read().await;
+        let Some(latest) = history.back().map(|e| e.ts) else {
+            return Vec::new();
+        };
+        let start = history.partition_point(|e| e.ts < latest.saturating_sub(window_ms));
+        history.range(start..).cloned().collect()
+    }
+
+    /// 早于水位线到达的乱序事件总数
+    pub fn reordered_event_count(&self) -> u64 {
+        self.reordered_events.load(Ordering::Relaxed)
+    }
+
+    /// 因时间戳落后而被跳过的节点状态覆盖总数
+    pub fn stale_update_count(&self) -> u64 {
+        self.stale_node_updates.load(Ordering::Relaxed)
     }
 }
 
@@ -574,3 +1240,302 @@ impl Default for StateGraph {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn util_event(ts: u64, value: &str) -> Event {
+        Event {
+            ts,
+            event_type: EventType::ComputeUtil,
+            entity_id: "gpu-0".to_string(),
+            job_id: None,
+            pid: Some(42),
+            value: value.to_string(),
+            node_id: None,
+            probe: None,
+            traceparent: None,
+            pod: None,
+            container_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order_event_does_not_rewind_node() {
+        let graph = StateGraph::new();
+        graph.process_event(&util_event(2000, "90")).await.unwrap();
+        graph.process_event(&util_event(1000, "10")).await.unwrap();
+
+        let nodes = graph.get_nodes_async().await;
+        let gpu = nodes.get("gpu-0").unwrap();
+        assert_eq!(gpu.last_update, 2000);
+        assert_eq!(gpu.metadata.get("util").map(String::as_str), Some("90"));
+
+        assert_eq!(graph.reordered_event_count(), 1);
+        assert_eq!(graph.stale_update_count(), 1);
+
+        // 乱序事件仍然进入历史，并按时间戳排序
+        let history: Vec<u64> = graph.recent_events(10).await.iter().map(|e| e.ts).collect();
+        assert_eq!(history, vec![1000, 2000]);
+    }
+
+    #[tokio::test]
+    async fn test_config_limits_and_resource_staleness() {
+        let graph = StateGraph::with_config(GraphConfig {
+            resource_stale_ms: Some(1000),
+            max_nodes: Some(2),
+            ..GraphConfig::default()
+        });
+
+        let mut gpu1 = util_event(1000, "50");
+        gpu1.entity_id = "gpu-1".to_string();
+        gpu1.pid = None;
+        graph.process_event(&gpu1).await.unwrap();
+        graph.process_event(&util_event(5000, "90")).await.unwrap();
+
+        // gpu-1 超过 resource_stale_ms 未更新，被清理；剩余 gpu-0 与 pid-42
+        let nodes = graph.get_nodes_async().await;
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes.contains_key("gpu-0"));
+        assert!(!nodes.contains_key("gpu-1"));
+    }
+
+    #[tokio::test]
+    async fn test_action_exec_annotates_process_without_advancing_it() {
+        let graph = StateGraph::new();
+        graph.process_event(&util_event(1000, "90")).await.unwrap();
+        let mut action = util_event(3000, "ok");
+        action.event_type = EventType::ActionExec;
+        action.entity_id = "signal".to_string();
+        graph.process_event(&action).await.unwrap();
+        let mut older = action.clone();
+        older.ts = 2000;
+        older.entity_id = "kill_process".to_string();
+        graph.process_event(&older).await.unwrap();
+
+        let nodes = graph.get_nodes_async().await;
+        let process = nodes.get("pid-42").unwrap();
+        assert_eq!(process.last_update, 1000);
+        assert_eq!(process.metadata.get("last_action").map(String::as_str), Some("signal"));
+        assert_eq!(process.metadata.get("last_action_result").map(String::as_str), Some("ok"));
+        assert!(!nodes.contains_key("signal"));
+        assert_eq!(graph.recent_events(10).await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_remove_node_drops_incident_edges() {
+        let graph = StateGraph::new();
+        graph.process_event(&util_event(1000, "90")).await.unwrap();
+        assert_eq!(graph.get_all_edges_async().await.len(), 1);
+
+        let (node, removed_edges) = graph.remove_node("gpu-0").await.unwrap();
+        assert_eq!(node.node_type, NodeType::Resource);
+        assert_eq!(removed_edges, 1);
+        assert!(graph.get_all_edges_async().await.is_empty());
+        assert!(graph.remove_node("gpu-0").await.is_none());
+
+        assert!(graph.set_node_metadata("pid-42", "state", "exit").await.unwrap().is_none());
+        assert!(graph.set_node_metadata("gpu-0", "util", "0").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_neighborhood_limits_hops() {
+        let graph = StateGraph::new();
+        graph.process_event(&util_event(1000, "90")).await.unwrap();
+        let mut other = util_event(1000, "80");
+        other.pid = Some(7);
+        other.entity_id = "gpu-1".to_string();
+        graph.process_event(&other).await.unwrap();
+
+        let sub = graph.neighborhood("pid-42", 1).await.unwrap();
+        let mut ids: Vec<String> = sub.get_nodes_async().await.into_keys().collect();
+        ids.sort();
+        assert_eq!(ids, vec!["gpu-0", "pid-42"]);
+        assert_eq!(sub.edge_count().await, 1);
+
+        assert_eq!(graph.neighborhood("pid-42", 0).await.unwrap().node_count().await, 1);
+        assert!(graph.neighborhood("pid-404", 3).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_job_and_host_membership() {
+        let graph = StateGraph::new();
+        for (host, pid) in [("node-a", 1), ("node-b", 2)] {
+            let mut start = util_event(1000, "start");
+            start.event_type = EventType::ProcessState;
+            start.pid = Some(pid);
+            start.job_id = Some("job-7".to_string());
+            start.node_id = Some(host.to_string());
+            graph.process_event(&start).await.unwrap();
+        }
+
+        let job = graph.node("job-job-7").await.unwrap();
+        assert_eq!(job.node_type, NodeType::Job);
+        let ids: Vec<String> = graph
+            .find_processes_by_job("job-7")
+            .await
+            .into_iter()
+            .map(|n| n.id)
+            .collect();
+        assert_eq!(ids, vec!["node-a::pid-1", "node-b::pid-2"]);
+        assert_eq!(graph.find_processes_by_host("node-b").await.len(), 1);
+
+        // 通过 NodeKey 拆解命名空间，而非切分字符串
+        let key = graph.find_processes_by_job("job-7").await[1].key();
+        assert_eq!(key.node_id(), Some("node-b"));
+        assert_eq!(key.pid(), Some(2));
+        assert_eq!(key, NodeKey::process(Some("node-b"), 2));
+        assert!(graph.node_by_key(&key).await.is_some());
+        assert_eq!(NodeKey::parse("gpu-0").node_id(), None);
+        assert_eq!(NodeKey::parse("gpu-0").to_string(), "gpu-0");
+
+        // 进程退出后，无成员的 host 节点随之清理，job 节点仍被另一进程引用
+        let mut exit = util_event(2000, "exit");
+        exit.event_type = EventType::ProcessState;
+        exit.pid = Some(2);
+        exit.node_id = Some("node-b".to_string());
+        graph.process_event(&exit).await.unwrap();
+
+        assert!(graph.node("host-node-b").await.is_none());
+        assert!(graph.node("job-job-7").await.is_some());
+        assert_eq!(graph.find_processes_by_job("job-7").await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_edge_confidence_accumulates_and_ranks_causes() {
+        let graph = StateGraph::new();
+
+        // 单个低带宽采样：弱证据
+        let mut low_bw = util_event(1000, "0.5");
+        low_bw.event_type = EventType::TransportBw;
+        low_bw.entity_id = "storage-0".to_string();
+        graph.process_event(&low_bw).await.unwrap();
+
+        // 重复的丢包事件：证据累积
+        for ts in [1100, 1200, 1300] {
+            let mut drop = util_event(ts, "3");
+            drop.event_type = EventType::TransportDrop;
+            drop.entity_id = "eth0".to_string();
+            graph.process_event(&drop).await.unwrap();
+        }
+
+        let edges = graph.get_all_edges_async().await;
+        let drop_edge = edges.iter().find(|e| e.to == "eth0").unwrap();
+        assert_eq!(drop_edge.weight, 3);
+        assert_eq!(drop_edge.ts, 1300);
+        assert!((drop_edge.confidence - 0.936).abs() < 1e-9);
+
+        let ranked = graph.find_root_cause_ranked_by_id("pid-42").await;
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].0, "等待资源: eth0");
+        assert!((ranked[1].1 - 0.3).abs() < 1e-9);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_root_cause_query_does_not_block_ingest() {
+        let graph = std::sync::Arc::new(StateGraph::new());
+
+        // pid-42 消耗 gpu-0，gpu-0 上出现硬件错误
+        graph.process_event(&util_event(1000, "90")).await.unwrap();
+        let mut hw_error = util_event(1001, "xid_79");
+        hw_error.event_type = EventType::ErrorHw;
+        hw_error.pid = None;
+        graph.process_event(&hw_error).await.unwrap();
+
+        // 查询持有快照期间写入不被阻塞，快照也不受之后的写入影响
+        let snapshot = graph.snapshot().await;
+        let mut low_bw = util_event(1002, "0.5");
+        low_bw.event_type = EventType::TransportBw;
+        low_bw.entity_id = "eth0".to_string();
+        tokio::time::timeout(std::time::Duration::from_secs(1), graph.process_event(&low_bw))
+            .await
+            .expect("写入被根因查询阻塞")
+            .unwrap();
+        assert_eq!(snapshot.root_causes("pid-42").len(), 1);
+        assert_eq!(graph.find_root_cause(42).await.len(), 2);
+
+        // 持续写入的同时反复查询：每次查询都看到一致的根因
+        let writer = {
+            let graph = std::sync::Arc::clone(&graph);
+            tokio::spawn(async move {
+                for i in 0..500u32 {
+                    let mut event = util_event(2000 + i as u64, "50");
+                    event.pid = Some(1000 + i);
+                    graph.process_event(&event).await.unwrap();
+                }
+            })
+        };
+        while !writer.is_finished() {
+            let causes = graph.find_root_cause(42).await;
+            assert!(causes.iter().any(|c| c == "error-gpu-0: xid_79"));
+        }
+        writer.await.unwrap();
+        assert_eq!(graph.get_process_resources(1499).await, vec!["gpu-0".to_string()]);
+    }
+
+    #[test]
+    fn test_config_yaml_partial_override() {
+        let config: GraphConfig = serde_yaml::from_str("error_window_ms: 1000\nmax_edges: 10\n").unwrap();
+        assert_eq!(config.error_window_ms, 1000);
+        assert_eq!(config.max_edges, Some(10));
+        assert_eq!(config.process_stale_ms, GraphConfig::default().process_stale_ms);
+    }
+
+    #[tokio::test]
+    async fn test_replayed_start_does_not_overwrite_newer_process_state() {
+        let graph = StateGraph::new();
+        let mut start = util_event(2000, "start");
+        start.event_type = EventType::ProcessState;
+        start.job_id = Some("job-new".to_string());
+        let mut replayed = start.clone();
+        replayed.ts = 1000;
+        replayed.job_id = Some("job-old".to_string());
+
+        graph.process_event(&start).await.unwrap();
+        graph.process_event(&replayed).await.unwrap();
+
+        let procs = graph.get_active_processes().await;
+        assert_eq!(procs.len(), 1);
+        assert_eq!(procs[0].last_update, 2000);
+        assert_eq!(procs[0].metadata.get("job_id").map(String::as_str), Some("job-new"));
+    }
+
+    #[tokio::test]
+    async fn test_find_processes_by_pod() {
+        let graph = StateGraph::new();
+        let mut start = util_event(1000, "start");
+        start.event_type = EventType::ProcessState;
+        graph.process_event(&start).await.unwrap();
+
+        let mut util = util_event(2000, "90");
+        util.pod = Some("train/worker-0".to_string());
+        util.container_id = Some("abc123".to_string());
+        graph.process_event(&util).await.unwrap();
+
+        let procs = graph.find_processes_by_pod("train/worker-0").await;
+        assert_eq!(procs.len(), 1);
+        assert_eq!(procs[0].metadata.get("container_id").map(String::as_str), Some("abc123"));
+        assert!(graph.find_processes_by_pod("train/worker-1").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_net_error_blocks_bandwidth_consumers() {
+        let graph = StateGraph::new();
+        let mut bw = util_event(1000, "25000.00");
+        bw.event_type = EventType::TransportBw;
+        bw.entity_id = "roce-mlx5_0-1".to_string();
+        graph.process_event(&bw).await.unwrap();
+
+        let mut pfc = bw.clone();
+        pfc.ts = 2000;
+        pfc.event_type = EventType::ErrorNet;
+        pfc.pid = None;
+        pfc.value = "pfc_storm".to_string();
+        graph.process_event(&pfc).await.unwrap();
+
+        let causes = graph.find_root_cause(42).await;
+        assert_eq!(causes.len(), 1);
+        assert!(causes[0].contains("pfc_storm"));
+    }
+}
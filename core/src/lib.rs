@@ -1,12 +1,50 @@
 //! ark-core: 共享底座
 //! 
 //! 包含事件系统、状态图引擎、规则引擎等核心组件
-//! 供 agent 和 hub 共同使用
+//! 供 agent 和 hub 共同使用，也可以被其他工具单独嵌入：
+//!
+//! ```no_run
+//! use ark_core::{Event, EventType, RuleEngine, StateGraph};
+//!
+//! # async fn demo() -> Result<(), String> {
+//! let graph = StateGraph::new();
+//! graph.process_event(&Event::new(EventType::ErrorHw, "gpu-0".into(), "XID_79".into(), None, Some(1234))).await?;
+//!
+//! let engine = RuleEngine::load_from_dir("rules")?;
+//! let events = graph.recent_events(100).await;
+//! let matched = engine.match_rules(&graph, &events).await;
+//! # let _ = matched;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # 版本兼容性（semver）
+//!
+//! 公开 API 即本 crate 根部重新导出的类型及 `event`/`graph`/`rules`/`export`/`diff` 模块中的 `pub` 项，
+//! 遵循语义化版本：
+//!
+//! - 补丁版本只修复缺陷，不改变任何公开签名
+//! - 次版本只做增量修改：新增方法、新增 `#[non_exhaustive]` 枚举变体
+//!   （`NodeType`、`EdgeType`、`ExportFormat`）、新增 `GraphConfig` 配置项
+//! - 删除或修改已有签名、改变事件/规则 YAML 的既有语义，只会出现在主版本
+//!
+//! `StateGraph` 的内部存储不属于公开 API，只能通过方法访问（如 `node`、
+//! `get_nodes_async`、`find_processes_by_job`）。
+//! 集群模式下节点 ID 带主机命名空间，请用 `NodeKey` 构造和拆解，不要自行拼接字符串。
+//! 当前处于 0.x 阶段，按 Cargo 惯例次版本号升级视同主版本升级。
 
 pub mod event;
 pub mod graph;
+pub mod export;
+pub mod diff;
 pub mod rules;
+pub mod straggler;
+pub mod digest;
 
 // 重新导出常用类型
-pub use graph::{StateGraph, EdgeType, Edge, NodeType, Node};
+pub use graph::{StateGraph, GraphConfig, EdgeType, Edge, NodeType, Node, NodeKey};
 pub use event::{Event, EventType, EventBus};
+pub use rules::{RuleEngine, Rule};
+pub use export::ExportFormat;
+pub use diff::GraphDiff;
+pub use straggler::{Straggler, DEFAULT_STRAGGLER_MARGIN};
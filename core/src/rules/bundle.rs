@@ -0,0 +1,189 @@
+//! 规则包：集群统一分发规则文件
+//!
+//! Hub（或任意 HTTP 服务）把规则目录打包成 `RuleBundle` 下发，Agent 校验校验和与签名后
+//! 写入本地缓存目录，再由 `ReloadableRuleEngine` 的目录监听自动热加载。
+//! 签名为共享密钥的 HMAC-SHA256，密钥由双方的 `ARK_RULES_KEY` 环境变量配置。
+
+use super::validate::{is_rule_file, validate_rule};
+use super::Rule;
+use crate::digest::{hmac_sha256, to_hex};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// 规则签名密钥的环境变量
+pub const RULES_KEY_ENV: &str = "ARK_RULES_KEY";
+
+/// 缓存目录中记录当前规则包校验和的文件（以点开头，不会被当作规则加载）
+const CHECKSUM_FILE: &str = ".bundle-checksum";
+
+/// 规则包中的单个规则文件
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RuleFile {
+    /// 文件名（不含目录，如 `gpu-oom.yaml`）
+    pub name: String,
+    pub content: String,
+}
+
+/// 规则包：规则文件 + 校验和（+ 可选签名）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RuleBundle {
+    /// 按文件名排序
+    pub files: Vec<RuleFile>,
+    /// 所有文件名和内容的 SHA-256（十六进制）
+    pub checksum: String,
+    /// 校验和的 HMAC-SHA256（十六进制），未配置密钥时为空
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl RuleBundle {
+    /// 由规则文件构建规则包（计算校验和，不签名）
+    pub fn new(mut files: Vec<RuleFile>) -> Self {
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+        let checksum = checksum(&files);
+        Self { files, checksum, signature: None }
+    }
+
+    /// 打包规则目录中的所有规则文件
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self, String> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir.as_ref()).map_err(|e| format!("读取规则目录失败: {}", e))? {
+            let path = entry.map_err(|e| format!("读取目录项失败: {}", e))?.path();
+            if !is_rule_file(&path) {
+                continue;
+            }
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .ok_or_else(|| format!("无效的规则文件名: {}", path.display()))?
+                .to_string();
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("读取规则文件 {} 失败: {}", path.display(), e))?;
+            files.push(RuleFile { name, content });
+        }
+        Ok(Self::new(files))
+    }
+
+    /// 用共享密钥签名
+    pub fn signed(mut self, key: &[u8]) -> Self {
+        self.signature = Some(to_hex(&hmac_sha256(key, self.checksum.as_bytes())));
+        self
+    }
+
+    /// 校验规则包：校验和必须与内容一致；给定密钥时签名必须存在且匹配；
+    /// 文件名必须是普通的规则文件名；每个规则都必须能解析且通过语义校验
+    pub fn verify(&self, key: Option<&[u8]>) -> Result<Vec<Rule>, String> {
+        if checksum(&self.files) != self.checksum {
+            return Err("规则包校验和不匹配".to_string());
+        }
+        if let Some(key) = key {
+            let expected = to_hex(&hmac_sha256(key, self.checksum.as_bytes()));
+            match &self.signature {
+                Some(signature) if *signature == expected => {}
+                Some(_) => return Err("规则包签名不匹配".to_string()),
+                None => return Err("规则包未签名".to_string()),
+            }
+        }
+
+        let mut rules = Vec::with_capacity(self.files.len());
+        for file in &self.files {
+            let path = Path::new(&file.name);
+            if path.components().count() != 1 || file.name.starts_with('.') || !is_rule_file(path) {
+                return Err(format!("规则包包含非法文件名: {}", file.name));
+            }
+            let rule: Rule = serde_yaml::from_str(&file.content)
+                .map_err(|e| format!("{}: {}", file.name, e))?;
+            let problems = validate_rule(&rule);
+            if !problems.is_empty() {
+                return Err(format!("{}: {}", file.name, problems.join("; ")));
+            }
+            rules.push(rule);
+        }
+        Ok(rules)
+    }
+
+    /// 写入缓存目录：先写临时文件再重命名，并删除包中已不存在的规则文件
+    ///
+    /// 调用前应先 `verify`。缓存中的校验和与本包相同时不做任何修改，返回 false。
+    pub fn write_to_dir<P: AsRef<Path>>(&self, dir: P) -> Result<bool, String> {
+        let dir = dir.as_ref();
+        if cached_checksum(dir).as_deref() == Some(self.checksum.as_str()) {
+            return Ok(false);
+        }
+        fs::create_dir_all(dir).map_err(|e| format!("创建规则缓存目录失败: {}", e))?;
+
+        for file in &self.files {
+            let tmp = dir.join(format!(".{}.tmp", file.name));
+            fs::write(&tmp, &file.content).map_err(|e| format!("写入 {} 失败: {}", file.name, e))?;
+            fs::rename(&tmp, dir.join(&file.name)).map_err(|e| format!("写入 {} 失败: {}", file.name, e))?;
+        }
+        for entry in fs::read_dir(dir).map_err(|e| format!("读取规则缓存目录失败: {}", e))? {
+            let path = entry.map_err(|e| format!("读取目录项失败: {}", e))?.path();
+            let stale = is_rule_file(&path)
+                && path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|name| !self.files.iter().any(|f| f.name == name));
+            if stale {
+                fs::remove_file(&path).map_err(|e| format!("删除过期规则 {} 失败: {}", path.display(), e))?;
+            }
+        }
+        fs::write(dir.join(CHECKSUM_FILE), &self.checksum).map_err(|e| format!("写入规则包校验和失败: {}", e))?;
+        Ok(true)
+    }
+}
+
+/// 缓存目录中当前规则包的校验和（从未同步过时为 None）
+pub fn cached_checksum<P: AsRef<Path>>(dir: P) -> Option<String> {
+    fs::read_to_string(dir.as_ref().join(CHECKSUM_FILE))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// 从环境变量读取规则签名密钥
+pub fn rules_key_from_env() -> Option<Vec<u8>> {
+    std::env::var(RULES_KEY_ENV)
+        .ok()
+        .filter(|k| !k.is_empty())
+        .map(String::into_bytes)
+}
+
+fn checksum(files: &[RuleFile]) -> String {
+    let mut hasher = Sha256::new();
+    for file in files {
+        hasher.update(file.name.as_bytes());
+        hasher.update([0]);
+        hasher.update(file.content.as_bytes());
+        hasher.update([0]);
+    }
+    to_hex(&hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_verify_and_write_to_cache() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/../rules");
+        let bundle = RuleBundle::from_dir(src).unwrap().signed(b"secret");
+        assert!(!bundle.verify(Some(b"secret")).unwrap().is_empty());
+        assert!(bundle.verify(Some(b"other")).is_err());
+
+        let mut tampered = bundle.clone();
+        tampered.files[0].content.push_str("\n# tampered\n");
+        assert!(tampered.verify(None).is_err());
+
+        let cache = std::env::temp_dir().join(format!("ark-rules-bundle-{}", std::process::id()));
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("stale.yaml"), "name: stale").unwrap();
+        assert!(bundle.write_to_dir(&cache).unwrap());
+        assert!(!bundle.write_to_dir(&cache).unwrap());
+        assert!(!cache.join("stale.yaml").exists());
+        assert_eq!(RuleBundle::from_dir(&cache).unwrap().checksum, bundle.checksum);
+
+        fs::remove_dir_all(&cache).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+//! 规则冷却与抑制：避免同一规则对同一实体反复推荐
+//!
+//! 匹配本身是无状态的（`RuleEngine::match_detailed`），冷却状态由调用方（daemon）
+//! 持有的 `FiringState` 记录，二者组合得到带状态的 `RuleMatch`。
+
+use super::{Rule, RuleExplanation};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 命中规则的处理结果
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MatchStatus {
+    /// 本次触发，应给出推荐
+    Fired,
+    /// 所有相关实体都在冷却期内
+    CoolingDown { remaining_secs: u64 },
+    /// 被同时命中的另一条规则抑制
+    Suppressed { by: String },
+}
+
+/// 带冷却/抑制状态的规则命中结果
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RuleMatch {
+    pub rule: Rule,
+    /// 触发规则的事件实体（规则只有图/指标条件时为空，此时按规则整体冷却）
+    pub entities: Vec<String>,
+    #[serde(flatten)]
+    pub status: MatchStatus,
+    /// 逐条件的命中解释（由调用方按需填充，见 `RuleEngine::explain`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<RuleExplanation>,
+}
+
+impl RuleMatch {
+    /// 是否本次触发
+    pub fn fired(&self) -> bool {
+        self.status == MatchStatus::Fired
+    }
+}
+
+/// 冷却键：(规则名, 实体)
+type CooldownKey = (String, String);
+
+/// 规则触发状态：(规则名, 实体) -> 冷却截止时间（毫秒）
+///
+/// 按规则名记录，规则热加载后状态依然有效；过期条目在每次 `apply` 时清理
+#[derive(Debug, Default)]
+pub struct FiringState {
+    until: HashMap<CooldownKey, u64>,
+}
+
+impl FiringState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 对一次匹配结果（按优先级降序）应用抑制和冷却，并记录本次触发
+    ///
+    /// 先处理抑制：被任一同时命中的规则列入 `suppresses` 的规则标记为 Suppressed，不记录触发；
+    /// 其余规则中，设置了 `cooldown_seconds` 且所有相关实体都在冷却期内的标记为 CoolingDown，
+    /// 否则触发，并只为不在冷却期内的实体开始新的冷却（不延长已有冷却）。
+    pub fn apply(&mut self, matched: Vec<(&Rule, Vec<String>)>, now_ms: u64) -> Vec<RuleMatch> {
+        self.until.retain(|_, until| *until > now_ms);
+        let (results, started) = self.resolve(matched, now_ms);
+        self.until.extend(started);
+        results
+    }
+
+    /// 与 `apply` 相同地判断抑制和冷却，但不记录触发（按需诊断用，不影响流式告警）
+    pub fn peek(&self, matched: Vec<(&Rule, Vec<String>)>, now_ms: u64) -> Vec<RuleMatch> {
+        self.resolve(matched, now_ms).0
+    }
+
+    /// 判断每条命中的状态，同时返回本次触发应开始的冷却
+    fn resolve(
+        &self,
+        matched: Vec<(&Rule, Vec<String>)>,
+        now_ms: u64,
+    ) -> (Vec<RuleMatch>, Vec<(CooldownKey, u64)>) {
+        let cooling_until = |name: &str, entity: &str| {
+            self.until
+                .get(&(name.to_string(), entity.to_string()))
+                .copied()
+                .filter(|until| *until > now_ms)
+        };
+        let suppressed_by = |name: &str| {
+            matched
+                .iter()
+                .find(|(rule, _)| rule.name != name && rule.suppresses.iter().any(|s| s == name))
+                .map(|(rule, _)| rule.name.clone())
+        };
+
+        let mut results = Vec::with_capacity(matched.len());
+        let mut started = Vec::new();
+        for (rule, entities) in &matched {
+            let status = if let Some(by) = suppressed_by(&rule.name) {
+                MatchStatus::Suppressed { by }
+            } else if let Some(cooldown) = rule.cooldown_seconds.filter(|&c| c > 0) {
+                // 没有实体的规则用空字符串作为整体冷却键
+                let keys: Vec<String> = if entities.is_empty() {
+                    vec![String::new()]
+                } else {
+                    entities.clone()
+                };
+                let fresh: Vec<&String> = keys
+                    .iter()
+                    .filter(|e| cooling_until(&rule.name, e).is_none())
+                    .collect();
+                if fresh.is_empty() {
+                    let remaining_ms = keys
+                        .iter()
+                        .filter_map(|e| cooling_until(&rule.name, e))
+                        .map(|until| until - now_ms)
+                        .min()
+                        .unwrap_or(0);
+                    MatchStatus::CoolingDown { remaining_secs: remaining_ms.div_ceil(1000) }
+                } else {
+                    let until = now_ms.saturating_add(cooldown.saturating_mul(1000));
+                    started.extend(fresh.into_iter().map(|entity| ((rule.name.clone(), entity.clone()), until)));
+                    MatchStatus::Fired
+                }
+            } else {
+                MatchStatus::Fired
+            };
+            results.push(RuleMatch {
+                rule: (*rule).clone(),
+                entities: entities.clone(),
+                status,
+                explanation: None,
+            });
+        }
+        (results, started)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(yaml_extra: &str, name: &str) -> Rule {
+        let seed = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/../rules/gpu-oom.yaml")).unwrap();
+        let mut rule: Rule = serde_yaml::from_str(&format!("{}\n{}", seed, yaml_extra)).unwrap();
+        rule.name = name.to_string();
+        rule
+    }
+
+    #[test]
+    fn test_cooldown_per_entity_and_suppression() {
+        let specific = rule("cooldown_seconds: 60\nsuppresses: [generic]", "specific");
+        let generic = rule("", "generic");
+        let mut state = FiringState::new();
+
+        let results = state.apply(
+            vec![(&specific, vec!["gpu-0".into()]), (&generic, vec!["gpu-0".into()])],
+            1_000,
+        );
+        assert!(results[0].fired());
+        assert_eq!(results[1].status, MatchStatus::Suppressed { by: "specific".into() });
+
+        // 同一实体在冷却期内不再触发，新实体照常触发
+        let results = state.apply(vec![(&specific, vec!["gpu-0".into()])], 31_000);
+        assert_eq!(results[0].status, MatchStatus::CoolingDown { remaining_secs: 30 });
+        let results = state.apply(vec![(&specific, vec!["gpu-0".into(), "gpu-1".into()])], 31_000);
+        assert!(results[0].fired());
+
+        // 冷却结束后再次触发
+        let results = state.apply(vec![(&specific, vec!["gpu-0".into()])], 61_000);
+        assert!(results[0].fired());
+    }
+
+    #[test]
+    fn test_peek_does_not_start_cooldown() {
+        let specific = rule("cooldown_seconds: 60", "specific");
+        let mut state = FiringState::new();
+
+        assert!(state.peek(vec![(&specific, vec!["gpu-0".into()])], 1_000)[0].fired());
+        assert!(state.apply(vec![(&specific, vec!["gpu-0".into()])], 2_000)[0].fired());
+        // 已开始的冷却照常显示
+        let results = state.peek(vec![(&specific, vec!["gpu-0".into()])], 32_000);
+        assert_eq!(results[0].status, MatchStatus::CoolingDown { remaining_secs: 30 });
+    }
+}
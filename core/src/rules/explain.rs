@@ -0,0 +1,269 @@
+//! 规则命中解释：逐条件列出命中与否以及对应的事件 / 边 / 节点证据
+//!
+//! 运维排查误报时需要知道规则"为什么命中"，而不只是"命中了"
+
+use super::expr;
+use super::matcher::{event_matches, match_metric_condition};
+use super::pattern::matches_pattern;
+use super::validate::describe_condition;
+use super::{Condition, ExprTarget, Rule, RuleEngine, RuleMatcher};
+use crate::event::Event;
+use crate::graph::{Edge, Node, StateGraph};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+/// 每个条件最多列出的证据条数
+const MAX_EVIDENCE: usize = 5;
+
+/// 单个条件的解释
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConditionExplanation {
+    /// 条件简述
+    pub condition: String,
+    pub matched: bool,
+    /// 支持结论的事件 / 边 / 节点（最多 5 条，超出时最后一条为总数说明）
+    pub evidence: Vec<String>,
+    /// any / all 的子条件
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ConditionExplanation>,
+}
+
+/// 规则的解释
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RuleExplanation {
+    pub rule: String,
+    pub matched: bool,
+    pub conditions: Vec<ConditionExplanation>,
+}
+
+impl fmt::Display for RuleExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "规则 {}: {}", self.rule, if self.matched { "命中" } else { "未命中" })?;
+        for condition in &self.conditions {
+            write_condition(f, condition, 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_condition(f: &mut fmt::Formatter<'_>, c: &ConditionExplanation, depth: usize) -> fmt::Result {
+    let indent = "  ".repeat(depth);
+    writeln!(f, "{}[{}] {}", indent, if c.matched { "✓" } else { "✗" }, c.condition)?;
+    for evidence in &c.evidence {
+        writeln!(f, "{}    - {}", indent, evidence)?;
+    }
+    for child in &c.children {
+        write_condition(f, child, depth + 1)?;
+    }
+    Ok(())
+}
+
+impl RuleEngine {
+    /// 解释规则在给定事件和状态图上的匹配过程
+    pub async fn explain(rule: &Rule, events: &[Event], graph: &StateGraph) -> RuleExplanation {
+        let edges = graph.get_all_edges_async().await;
+        let nodes = graph.get_nodes_async().await;
+        let ctx = Context { events, graph, edges: &edges, nodes: &nodes };
+
+        let mut conditions = Vec::with_capacity(rule.conditions.len());
+        for condition in &rule.conditions {
+            conditions.push(explain_condition(condition, &ctx).await);
+        }
+        RuleExplanation {
+            rule: rule.name.clone(),
+            matched: conditions.iter().all(|c| c.matched),
+            conditions,
+        }
+    }
+}
+
+struct Context<'a> {
+    events: &'a [Event],
+    graph: &'a StateGraph,
+    edges: &'a [Edge],
+    nodes: &'a HashMap<String, Node>,
+}
+
+/// 递归解释条件；命中结论以 `RuleMatcher` 为准，证据只用于展示
+fn explain_condition<'a>(
+    condition: &'a Condition,
+    ctx: &'a Context<'a>,
+) -> Pin<Box<dyn Future<Output = ConditionExplanation> + Send + 'a>> {
+    Box::pin(async move {
+        let matched = RuleMatcher::match_condition(condition, ctx.events, ctx.graph).await;
+        let mut children = Vec::new();
+        if let Condition::Any { conditions } | Condition::All { conditions } = condition {
+            for child in conditions {
+                children.push(explain_condition(child, ctx).await);
+            }
+        }
+        ConditionExplanation {
+            condition: describe_condition(condition),
+            matched,
+            evidence: evidence(condition, ctx),
+            children,
+        }
+    })
+}
+
+fn describe_event(event: &Event) -> String {
+    format!("事件 {} {}={} @{}", event.event_type, event.entity_id, event.value, event.ts)
+}
+
+fn evidence(condition: &Condition, ctx: &Context<'_>) -> Vec<String> {
+    let events = ctx.events;
+    let items: Vec<String> = match condition {
+        Condition::Event { event_type, entity_id_pattern, value_pattern, value_threshold } => events
+            .iter()
+            .filter(|e| event_matches(e, event_type, entity_id_pattern, value_pattern, value_threshold))
+            .map(describe_event)
+            .collect(),
+        Condition::Window {
+            event_type,
+            entity_id_pattern,
+            value_pattern,
+            value_threshold,
+            window_secs,
+            ..
+        } => {
+            let latest = events.iter().map(|e| e.ts).max().unwrap_or(0);
+            let window_start = latest.saturating_sub(window_secs.saturating_mul(1000));
+            let matching: Vec<&Event> = events
+                .iter()
+                .filter(|e| e.ts >= window_start)
+                .filter(|e| event_matches(e, event_type, entity_id_pattern, value_pattern, value_threshold))
+                .collect();
+            let mut evidence = vec![format!("窗口内 {} 条匹配事件", matching.len())];
+            evidence.extend(truncate(matching.into_iter().map(describe_event).collect()));
+            return evidence;
+        }
+        Condition::Absent { event_type, entity_id_pattern, window_secs } => {
+            let latest = events.iter().map(|e| e.ts).max().unwrap_or(0);
+            let window_start = latest.saturating_sub(window_secs.saturating_mul(1000));
+            let mut last_seen: HashMap<&str, u64> = HashMap::new();
+            for event in events {
+                if event_matches(event, event_type, entity_id_pattern, &None, &None) {
+                    let seen = last_seen.entry(event.entity_id.as_str()).or_insert(event.ts);
+                    *seen = (*seen).max(event.ts);
+                }
+            }
+            if last_seen.is_empty() {
+                return vec!["没有任何匹配事件".to_string()];
+            }
+            let mut silent: Vec<(&str, u64)> = last_seen
+                .into_iter()
+                .filter(|(_, ts)| *ts < window_start)
+                .collect();
+            silent.sort();
+            silent
+                .into_iter()
+                .map(|(entity, ts)| format!("{} 最后一次上报 @{}，距窗口终点 {}s", entity, ts, (latest - ts) / 1000))
+                .collect()
+        }
+        Condition::Expr { expr, over } => {
+            let Ok(ast) = expr::compile(expr) else {
+                return vec!["表达式编译失败".to_string()];
+            };
+            match over {
+                ExprTarget::Event => events
+                    .iter()
+                    .filter(|e| expr::event_matches(&ast, e))
+                    .map(describe_event)
+                    .collect(),
+                ExprTarget::Node => sorted_nodes(ctx)
+                    .into_iter()
+                    .filter(|n| expr::any_node(&ast, [*n]))
+                    .map(|n| format!("节点 {}", n.id))
+                    .collect(),
+            }
+        }
+        Condition::Graph { edge_type, from_pattern, to_pattern } => ctx
+            .edges
+            .iter()
+            .filter(|edge| edge.edge_type.as_str() == edge_type.as_str())
+            .filter(|edge| from_pattern.as_ref().is_none_or(|p| matches_pattern(&edge.from, p)))
+            .filter(|edge| to_pattern.as_ref().is_none_or(|p| matches_pattern(&edge.to, p)))
+            .map(|edge| format!("边 {} -[{}]-> {}", edge.from, edge.edge_type.as_str(), edge.to))
+            .collect(),
+        Condition::Metric { node_type, entity_id_pattern, metrics } => sorted_nodes(ctx)
+            .into_iter()
+            .filter(|node| node_type.as_ref().is_none_or(|t| node.node_type.as_str() == t.as_str()))
+            .filter(|node| entity_id_pattern.as_ref().is_none_or(|p| matches_pattern(&node.id, p)))
+            .filter(|node| metrics.iter().all(|m| match_metric_condition(m, &node.metadata)))
+            .map(|node| {
+                let values: Vec<String> = metrics
+                    .iter()
+                    .map(|m| format!("{}={}", m.key, node.metadata.get(&m.key).map_or("-", String::as_str)))
+                    .collect();
+                format!("节点 {} ({})", node.id, values.join(", "))
+            })
+            .collect(),
+        Condition::Any { .. } | Condition::All { .. } => Vec::new(),
+    };
+    truncate(items)
+}
+
+/// 节点按 ID 排序，保证解释输出稳定
+fn sorted_nodes<'a>(ctx: &Context<'a>) -> Vec<&'a Node> {
+    let mut nodes: Vec<&Node> = ctx.nodes.values().collect();
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+    nodes
+}
+
+fn truncate(mut evidence: Vec<String>) -> Vec<String> {
+    if evidence.len() > MAX_EVIDENCE {
+        let total = evidence.len();
+        evidence.truncate(MAX_EVIDENCE - 1);
+        evidence.push(format!("…共 {} 条", total));
+    }
+    evidence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventType;
+
+    #[tokio::test]
+    async fn test_explain_lists_matching_events_and_edges() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+name: xid-blocks-process
+scene: gpu_error
+priority: 1
+conditions:
+  - type: "event"
+    event_type: "error.hw"
+    value_pattern: "XID"
+  - type: "graph"
+    edge_type: "blocked_by"
+    from_pattern: "pid-*"
+root_cause_pattern: { primary: "GPU 硬件错误", secondary: [] }
+solution_steps: [{ step: 1, action: "隔离 GPU", manual: true }]
+related_evidences: []
+applicability: { min_confidence: 0.8 }
+"#,
+        )
+        .unwrap();
+        let graph = StateGraph::new();
+        let events = vec![
+            Event::new(EventType::ComputeUtil, "gpu-0".into(), "90".into(), None, Some(7)),
+            Event::new(EventType::ErrorHw, "gpu-0".into(), "XID_79".into(), None, None),
+        ];
+        for event in &events {
+            graph.process_event(event).await.unwrap();
+        }
+
+        let explanation = RuleEngine::explain(&rule, &events, &graph).await;
+        assert!(explanation.matched);
+        assert_eq!(explanation.conditions[0].evidence.len(), 1);
+        assert!(explanation.conditions[0].evidence[0].contains("XID_79"));
+        assert!(explanation.conditions[1].evidence[0].starts_with("边 pid-7 -[blocked_by]->"));
+
+        let text = explanation.to_string();
+        assert!(text.contains("[✓] graph pid-* -[blocked_by]-> *"));
+    }
+}
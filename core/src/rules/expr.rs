@@ -0,0 +1,134 @@
+//! 表达式条件：用 Rhai 表达式描述 glob/阈值无法表达的匹配逻辑
+//!
+//! ```yaml
+//! - type: "expr"
+//!   expr: 'event.type == "compute.util" && event.value.to_f() > 80 && event.entity_id.startsWith("gpu-")'
+//! ```
+//!
+//! 表达式对每个事件（或每个节点，`over: node`）求值，任一结果为 true 即条件成立。
+//! 编译结果按表达式文本缓存，同一规则重复匹配不会重复解析。
+
+use crate::event::Event;
+use crate::graph::Node;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// 表达式求值的对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExprTarget {
+    /// 事件（变量名 `event`：type / entity_id / value / ts / pid / job_id / node_id / probe）
+    #[default]
+    Event,
+    /// 状态图节点（变量名 `node`：id / type / last_update / meta）
+    Node,
+}
+
+/// 单个表达式的最大执行步数，防止规则中写出死循环拖住 daemon
+const MAX_OPERATIONS: u64 = 10_000;
+
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        // 字符串转数值：无法解析时返回 NaN，与任何阈值比较都为 false
+        engine.register_fn("to_f", |s: &str| s.trim().parse::<f64>().unwrap_or(f64::NAN));
+        engine.register_fn("startsWith", |s: &str, prefix: &str| s.starts_with(prefix));
+        engine.register_fn("endsWith", |s: &str, suffix: &str| s.ends_with(suffix));
+        engine
+    })
+}
+
+/// 编译表达式（带缓存）；语法错误信息包含行列位置
+pub fn compile(expr: &str) -> Result<Arc<AST>, String> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Arc<AST>>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+
+    if let Some(ast) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(expr) {
+        return Ok(Arc::clone(ast));
+    }
+    let ast = Arc::new(
+        engine()
+            .compile_expression(expr)
+            .map_err(|e| format!("表达式语法错误: {}", e))?,
+    );
+    cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(expr.to_string(), Arc::clone(&ast));
+    Ok(ast)
+}
+
+fn event_map(event: &Event) -> Map {
+    let mut map = Map::new();
+    map.insert("type".into(), event.event_type.to_string().into());
+    map.insert("entity_id".into(), event.entity_id.clone().into());
+    map.insert("value".into(), event.value.clone().into());
+    map.insert("ts".into(), (event.ts as i64).into());
+    map.insert("pid".into(), event.pid.map(|p| Dynamic::from(p as i64)).unwrap_or(Dynamic::UNIT));
+    map.insert("job_id".into(), event.job_id.clone().map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+    map.insert("node_id".into(), event.node_id.clone().map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+    map.insert("probe".into(), event.probe.clone().map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+    map.insert("pod".into(), event.pod.clone().map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+    map
+}
+
+fn node_map(node: &Node) -> Map {
+    let meta: Map = node
+        .metadata
+        .iter()
+        .map(|(k, v)| (k.as_str().into(), v.clone().into()))
+        .collect();
+    let mut map = Map::new();
+    map.insert("id".into(), node.id.clone().into());
+    map.insert("type".into(), node.node_type.as_str().into());
+    map.insert("last_update".into(), (node.last_update as i64).into());
+    map.insert("meta".into(), meta.into());
+    map
+}
+
+/// 对单个对象求值；运行时错误或结果不是布尔值都视为不匹配
+fn eval(ast: &AST, name: &str, value: Map) -> bool {
+    let mut scope = Scope::new();
+    scope.push_constant(name, value);
+    engine()
+        .eval_ast_with_scope::<bool>(&mut scope, ast)
+        .unwrap_or(false)
+}
+
+/// 单个事件是否满足表达式
+pub(crate) fn event_matches(ast: &AST, event: &Event) -> bool {
+    eval(ast, "event", event_map(event))
+}
+
+/// 任一事件满足表达式
+pub(crate) fn any_event(ast: &AST, events: &[Event]) -> bool {
+    events.iter().any(|event| event_matches(ast, event))
+}
+
+/// 任一节点满足表达式
+pub(crate) fn any_node<'a>(ast: &AST, nodes: impl IntoIterator<Item = &'a Node>) -> bool {
+    nodes.into_iter().any(|node| eval(ast, "node", node_map(node)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventType;
+
+    #[test]
+    fn test_expr_over_events() {
+        let ast = compile(r#"event.type == "compute.util" && event.value.to_f() > 80 && event.entity_id.startsWith("gpu-")"#)
+            .unwrap();
+        let util = |entity: &str, value: &str| {
+            Event::new(EventType::ComputeUtil, entity.to_string(), value.to_string(), None, None)
+        };
+
+        assert!(!any_event(&ast, &[util("gpu-0", "50"), util("cpu-0", "95"), util("gpu-1", "D")]));
+        assert!(any_event(&ast, &[util("gpu-0", "50"), util("gpu-1", "95")]));
+        assert!(compile("event.value >").is_err());
+    }
+}
@@ -1,6 +1,9 @@
 use crate::event::Event;
-use crate::graph::{EdgeType, NodeType, StateGraph};
-use crate::rules::rule::{ComparisonOp, Condition, MetricCondition, ValueType};
+use crate::graph::StateGraph;
+use crate::rules::expr;
+use crate::rules::pattern::matches_pattern;
+use crate::rules::rule::{ComparisonOp, Condition, ExprTarget, MetricCondition, ValueType};
+use std::collections::{BTreeSet, HashMap};
 
 /// 规则匹配器
 pub struct RuleMatcher;
@@ -18,45 +21,67 @@ impl RuleMatcher {
                 entity_id_pattern,
                 value_pattern,
                 value_threshold,
+            } => events.iter().any(|event| {
+                event_matches(event, event_type, entity_id_pattern, value_pattern, value_threshold)
+            }),
+            Condition::Window {
+                event_type,
+                entity_id_pattern,
+                value_pattern,
+                value_threshold,
+                min_count,
+                window_secs,
             } => {
-                events.iter().any(|event| {
-                    // 匹配事件类型
-                    if event.event_type.to_string() != *event_type {
-                        return false;
-                    }
-
-                    // 匹配实体 ID 模式
-                    if let Some(pattern) = entity_id_pattern {
-                        if !matches_pattern(&event.entity_id, pattern) {
-                            return false;
-                        }
-                    }
-
-                    // 匹配值模式
-                    if let Some(pattern) = value_pattern {
-                        if !event.value.contains(pattern) {
-                            return false;
-                        }
-                    }
+                // 窗口以事件切片中最新的事件时间为终点（事件时间而非墙钟，离线测试结果可复现）
+                let Some(latest) = events.iter().map(|e| e.ts).max() else {
+                    return false;
+                };
+                let window_start = latest.saturating_sub(window_secs.saturating_mul(1000));
+                let count = events
+                    .iter()
+                    .filter(|e| e.ts >= window_start)
+                    .filter(|e| {
+                        event_matches(e, event_type, entity_id_pattern, value_pattern, value_threshold)
+                    })
+                    .count();
+                count >= *min_count
+            }
+            Condition::Absent {
+                event_type,
+                entity_id_pattern,
+                window_secs,
+            } => {
+                let (Some(earliest), Some(latest)) = (
+                    events.iter().map(|e| e.ts).min(),
+                    events.iter().map(|e| e.ts).max(),
+                ) else {
+                    return false;
+                };
+                let window_start = latest.saturating_sub(window_secs.saturating_mul(1000));
+                // 事件历史不足一个窗口时无法判断缺失（如 daemon 刚启动）
+                if earliest > window_start {
+                    return false;
+                }
 
-                    // 匹配值阈值（改进：更安全的数值解析）
-                    if let Some(threshold) = value_threshold {
-                        match event.value.parse::<f64>() {
-                            Ok(value) => {
-                                if value < *threshold {
-                                    return false;
-                                }
-                            }
-                            Err(_) => {
-                                // 如果无法解析为数值，且阈值存在，则不匹配
-                                // 这避免了将 "D" (Disk Sleep) 误解析为 0.0
-                                return false;
-                            }
-                        }
+                // 每个匹配实体最后一次出现的时间
+                let mut last_seen: HashMap<&str, u64> = HashMap::new();
+                for event in events {
+                    if event_matches(event, event_type, entity_id_pattern, &None, &None) {
+                        let seen = last_seen.entry(event.entity_id.as_str()).or_insert(event.ts);
+                        *seen = (*seen).max(event.ts);
                     }
-
-                    true
-                })
+                }
+                last_seen.is_empty() || last_seen.values().any(|&ts| ts < window_start)
+            }
+            Condition::Expr { expr, over } => {
+                // 编译失败在加载规则时已报告，这里按不匹配处理
+                let Ok(ast) = expr::compile(expr) else {
+                    return false;
+                };
+                match over {
+                    ExprTarget::Event => expr::any_event(&ast, events),
+                    ExprTarget::Node => expr::any_node(&ast, graph.get_nodes_async().await.values()),
+                }
             }
             Condition::Graph {
                 edge_type,
@@ -68,13 +93,7 @@ impl RuleMatcher {
                 
                 edges.iter().any(|edge| {
                     // 匹配边类型
-                    let edge_type_str = match edge.edge_type {
-                        EdgeType::Consumes => "consumes",
-                        EdgeType::WaitsOn => "waits_on",
-                        EdgeType::BlockedBy => "blocked_by",
-                    };
-                    
-                    if edge_type_str != edge_type.as_str() {
+                    if edge.edge_type.as_str() != edge_type.as_str() {
                         return false;
                     }
 
@@ -106,12 +125,7 @@ impl RuleMatcher {
                 nodes.values().any(|node| {
                     // 匹配节点类型
                     if let Some(ref nt) = node_type {
-                        let node_type_str = match node.node_type {
-                            NodeType::Process => "process",
-                            NodeType::Resource => "resource",
-                            NodeType::Error => "error",
-                        };
-                        if node_type_str != nt.as_str() {
+                        if node.node_type.as_str() != nt.as_str() {
                             return false;
                         }
                     }
@@ -165,8 +179,107 @@ impl RuleMatcher {
     }
 }
 
+/// 收集触发条件的事件实体（用于按实体冷却）
+///
+/// 只有事件类条件（event / window / 事件表达式）能确定实体；图、指标、缺失条件不贡献实体
+pub(crate) fn collect_entities(condition: &Condition, events: &[Event], out: &mut BTreeSet<String>) {
+    match condition {
+        Condition::Event {
+            event_type,
+            entity_id_pattern,
+            value_pattern,
+            value_threshold,
+        } => out.extend(
+            events
+                .iter()
+                .filter(|e| event_matches(e, event_type, entity_id_pattern, value_pattern, value_threshold))
+                .map(|e| e.entity_id.clone()),
+        ),
+        Condition::Window {
+            event_type,
+            entity_id_pattern,
+            value_pattern,
+            value_threshold,
+            window_secs,
+            ..
+        } => {
+            let latest = events.iter().map(|e| e.ts).max().unwrap_or(0);
+            let window_start = latest.saturating_sub(window_secs.saturating_mul(1000));
+            out.extend(
+                events
+                    .iter()
+                    .filter(|e| e.ts >= window_start)
+                    .filter(|e| event_matches(e, event_type, entity_id_pattern, value_pattern, value_threshold))
+                    .map(|e| e.entity_id.clone()),
+            );
+        }
+        Condition::Expr { expr, over: ExprTarget::Event } => {
+            if let Ok(ast) = expr::compile(expr) {
+                out.extend(
+                    events
+                        .iter()
+                        .filter(|e| expr::event_matches(&ast, e))
+                        .map(|e| e.entity_id.clone()),
+                );
+            }
+        }
+        Condition::Any { conditions } | Condition::All { conditions } => {
+            for condition in conditions {
+                collect_entities(condition, events, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 单个事件是否满足事件条件的各项过滤（类型、实体、值模式、值阈值）
+pub(crate) fn event_matches(
+    event: &Event,
+    event_type: &str,
+    entity_id_pattern: &Option<String>,
+    value_pattern: &Option<String>,
+    value_threshold: &Option<f64>,
+) -> bool {
+    // 匹配事件类型
+    if event.event_type.to_string() != event_type {
+        return false;
+    }
+
+    // 匹配实体 ID 模式
+    if let Some(pattern) = entity_id_pattern {
+        if !matches_pattern(&event.entity_id, pattern) {
+            return false;
+        }
+    }
+
+    // 匹配值模式
+    if let Some(pattern) = value_pattern {
+        if !event.value.contains(pattern) {
+            return false;
+        }
+    }
+
+    // 匹配值阈值（改进：更安全的数值解析）
+    if let Some(threshold) = value_threshold {
+        match event.value.parse::<f64>() {
+            Ok(value) => {
+                if value < *threshold {
+                    return false;
+                }
+            }
+            Err(_) => {
+                // 如果无法解析为数值，且阈值存在，则不匹配
+                // 这避免了将 "D" (Disk Sleep) 误解析为 0.0
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
 /// 匹配指标条件（支持数值和字符串比较）
-fn match_metric_condition(metric: &MetricCondition, metadata: &std::collections::HashMap<String, String>) -> bool {
+pub(crate) fn match_metric_condition(metric: &MetricCondition, metadata: &std::collections::HashMap<String, String>) -> bool {
     let actual_str = match metadata.get(&metric.key) {
         Some(v) => v,
         None => return false,
@@ -230,35 +343,62 @@ fn match_metric_condition(metric: &MetricCondition, metadata: &std::collections:
     }
 }
 
-/// 简单的通配符模式匹配
-/// 支持 * 通配符（如 "gpu-*"）
-fn matches_pattern(text: &str, pattern: &str) -> bool {
-    if pattern.contains('*') {
-        let parts: Vec<&str> = pattern.split('*').collect();
-        if parts.len() == 2 {
-            // 简单的前缀和后缀匹配
-            text.starts_with(parts[0]) && text.ends_with(parts[1])
-        } else if parts.len() == 1 {
-            // 只有前缀或后缀
-            text.starts_with(parts[0]) || text.ends_with(parts[0])
-        } else {
-            // 多个 * 暂不支持，使用精确匹配
-            text == pattern
-        }
-    } else {
-        text == pattern
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_matches_pattern() {
-        assert!(matches_pattern("gpu-0", "gpu-*"));
-        assert!(matches_pattern("gpu-1", "gpu-*"));
-        assert!(!matches_pattern("cpu-0", "gpu-*"));
-        assert!(matches_pattern("mlx5_0", "mlx5_*"));
+    #[tokio::test]
+    async fn test_window_condition_counts_events_within_window() {
+        use crate::event::EventType;
+
+        let condition: Condition = serde_yaml::from_str(
+            "type: window\nevent_type: transport.drop\nentity_id_pattern: \"mlx5_*\"\nmin_count: 3\nwindow_secs: 60\n",
+        )
+        .unwrap();
+        let drop_at = |ts: u64, entity: &str| {
+            let mut event = Event::new(EventType::TransportDrop, entity.to_string(), "1".to_string(), None, None);
+            event.ts = ts;
+            event
+        };
+        let graph = StateGraph::new();
+
+        // 第一条事件落在窗口之外，窗口内只有 2 条
+        let mut events = vec![drop_at(0, "mlx5_0"), drop_at(100_000, "mlx5_0"), drop_at(130_000, "mlx5_1")];
+        assert!(!RuleMatcher::match_condition(&condition, &events, &graph).await);
+
+        // 其他网卡的事件不计数
+        events.push(drop_at(140_000, "eth0"));
+        assert!(!RuleMatcher::match_condition(&condition, &events, &graph).await);
+
+        events.push(drop_at(150_000, "mlx5_0"));
+        assert!(RuleMatcher::match_condition(&condition, &events, &graph).await);
+    }
+
+    #[tokio::test]
+    async fn test_absent_condition_detects_silent_entity() {
+        use crate::event::EventType;
+
+        let condition: Condition = serde_yaml::from_str(
+            "type: absent\nevent_type: compute.util\nentity_id_pattern: \"gpu-*\"\nwindow_secs: 30\n",
+        )
+        .unwrap();
+        let util_at = |ts: u64, entity: &str| {
+            let mut event = Event::new(EventType::ComputeUtil, entity.to_string(), "80".to_string(), None, None);
+            event.ts = ts;
+            event
+        };
+        let graph = StateGraph::new();
+
+        // 历史不足一个窗口：不判定缺失
+        let mut events = vec![util_at(0, "gpu-0"), util_at(0, "gpu-1")];
+        assert!(!RuleMatcher::match_condition(&condition, &events, &graph).await);
+
+        // 两张卡都持续上报
+        events.extend([util_at(50_000, "gpu-0"), util_at(50_000, "gpu-1")]);
+        assert!(!RuleMatcher::match_condition(&condition, &events, &graph).await);
+
+        // gpu-1 停止上报超过 30 秒
+        events.push(util_at(90_000, "gpu-0"));
+        assert!(RuleMatcher::match_condition(&condition, &events, &graph).await);
     }
 }
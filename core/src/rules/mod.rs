@@ -1,12 +1,31 @@
 mod rule;
 mod matcher;
+mod expr;
+mod watch;
+mod cooldown;
+mod bundle;
+mod explain;
+mod pattern;
+mod validate;
 
-pub use rule::{Rule, Condition, RootCausePattern, SolutionStep, Applicability};
+pub use rule::{
+    Rule, Condition, RootCausePattern, SolutionStep, RuleAction, EscalationStep, Applicability,
+    MetricCondition, ComparisonOp, ValueType, ExprTarget,
+};
 pub use matcher::RuleMatcher;
+pub use cooldown::{FiringState, MatchStatus, RuleMatch};
+pub use explain::{ConditionExplanation, RuleExplanation};
+pub use bundle::{cached_checksum, rules_key_from_env, RuleBundle, RuleFile, RULES_KEY_ENV};
+pub use watch::ReloadableRuleEngine;
+pub use validate::{
+    load_rule_file, test_rule, validate_dir, validate_rule, DirValidation, RuleFileError, RuleTestReport,
+};
 
+pub use pattern::{matches_pattern, validate_pattern};
+use std::collections::BTreeSet;
 use std::fs;
 use std::path::{Path, PathBuf};
-use crate::event::Event;
+use crate::event::{Event, EventType};
 use crate::graph::StateGraph;
 
 /// 规则引擎
@@ -15,6 +34,12 @@ pub struct RuleEngine {
 }
 
 impl RuleEngine {
+    /// 从内存中的规则构建引擎（嵌入方无需规则目录）
+    pub fn from_rules(mut rules: Vec<Rule>) -> Self {
+        rules.sort_by(|a, b| b.priority.cmp(&a.priority));
+        Self { rules }
+    }
+
     /// 从目录加载所有规则文件
     pub fn load_from_dir<P: AsRef<Path>>(dir: P) -> Result<Self, String> {
         let mut rules = Vec::new();
@@ -32,15 +57,9 @@ impl RuleEngine {
             let entry = entry.map_err(|e| format!("读取目录项失败: {}", e))?;
             let path = entry.path();
 
-            if path.extension().and_then(|s| s.to_str()) == Some("yaml")
-                || path.extension().and_then(|s| s.to_str()) == Some("yml")
-            {
-                let content = fs::read_to_string(&path)
-                    .map_err(|e| format!("读取规则文件失败 {}: {}", path.display(), e))?;
-                
-                let rule: Rule = serde_yaml::from_str(&content)
-                    .map_err(|e| format!("解析规则文件失败 {}: {}", path.display(), e))?;
-                
+            if validate::is_rule_file(&path) {
+                let rule = validate::parse_rule_file(&path)
+                    .map_err(|e| format!("解析规则文件失败 {}（可用 ark rules validate 检查）", e))?;
                 rules.push(rule);
             }
         }
@@ -69,6 +88,63 @@ impl RuleEngine {
         matched
     }
 
+    /// 匹配规则并附带触发规则的事件实体（按优先级排序）
+    pub async fn match_detailed(
+        &self,
+        graph: &StateGraph,
+        events: &[Event],
+    ) -> Vec<(&Rule, Vec<String>)> {
+        let mut matched = Vec::new();
+
+        for rule in &self.rules {
+            if RuleMatcher::match_all_conditions(&rule.conditions, events, graph).await {
+                let mut entities = BTreeSet::new();
+                for condition in &rule.conditions {
+                    matcher::collect_entities(condition, events, &mut entities);
+                }
+                matched.push((rule, entities.into_iter().collect()));
+            }
+        }
+
+        matched
+    }
+
+    /// 匹配规则并应用冷却与抑制，`state` 记录本次触发（`now_ms` 为毫秒时间戳）
+    pub async fn evaluate(
+        &self,
+        graph: &StateGraph,
+        events: &[Event],
+        state: &mut FiringState,
+        now_ms: u64,
+    ) -> Vec<RuleMatch> {
+        let matched = self.match_detailed(graph, events).await;
+        state.apply(matched, now_ms)
+    }
+
+    /// 该类型的新事件是否可能让某条规则从不命中变为命中
+    ///
+    /// 只含其他类型事件/窗口条件的规则不受影响；图、指标、缺失、表达式条件随任意事件变化。
+    /// 流式匹配用它跳过无关事件。
+    pub fn is_relevant(&self, event_type: &EventType) -> bool {
+        if *event_type == EventType::RuleMatched {
+            return false;
+        }
+        let type_str = event_type.to_string();
+        self.rules
+            .iter()
+            .any(|rule| rule.conditions.iter().any(|c| condition_watches(c, &type_str)))
+    }
+
+    /// 规则中时间窗口和缺失条件的最长窗口（秒），没有这类条件时为 0
+    pub fn lookback_secs(&self) -> u64 {
+        self.rules
+            .iter()
+            .flat_map(|rule| &rule.conditions)
+            .map(condition_lookback_secs)
+            .max()
+            .unwrap_or(0)
+    }
+
     /// 获取第一个匹配的规则
     pub async fn match_first(
         &self,
@@ -147,19 +223,24 @@ impl RuleEngine {
     }
 }
 
-/// 简单的通配符模式匹配（从 matcher.rs 复制）
-fn matches_pattern(text: &str, pattern: &str) -> bool {
-    if pattern.contains('*') {
-        let parts: Vec<&str> = pattern.split('*').collect();
-        if parts.len() == 2 {
-            text.starts_with(parts[0]) && text.ends_with(parts[1])
-        } else if parts.len() == 1 {
-            text.starts_with(parts[0]) || text.ends_with(parts[0])
-        } else {
-            text == pattern
+/// 条件是否可能因该类型的事件而变为成立
+fn condition_watches(condition: &Condition, event_type: &str) -> bool {
+    match condition {
+        Condition::Event { event_type: t, .. } | Condition::Window { event_type: t, .. } => t == event_type,
+        Condition::Any { conditions } | Condition::All { conditions } => {
+            conditions.iter().any(|c| condition_watches(c, event_type))
+        }
+        Condition::Absent { .. } | Condition::Expr { .. } | Condition::Graph { .. } | Condition::Metric { .. } => true,
+    }
+}
+
+fn condition_lookback_secs(condition: &Condition) -> u64 {
+    match condition {
+        Condition::Window { window_secs, .. } | Condition::Absent { window_secs, .. } => *window_secs,
+        Condition::Any { conditions } | Condition::All { conditions } => {
+            conditions.iter().map(condition_lookback_secs).max().unwrap_or(0)
         }
-    } else {
-        text == pattern
+        Condition::Event { .. } | Condition::Expr { .. } | Condition::Graph { .. } | Condition::Metric { .. } => 0,
     }
 }
 
@@ -168,4 +249,9 @@ impl RuleEngine {
     pub fn rule_count(&self) -> usize {
         self.rules.len()
     }
+
+    /// 已加载的规则（按优先级降序）
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
 }
@@ -0,0 +1,189 @@
+//! 实体 / 节点 ID 模式匹配
+//!
+//! 规则中的 `entity_id_pattern`、`from_pattern`、`to_pattern` 均为整串匹配：
+//! - glob（默认）：`*` 匹配任意长度字符（含 `::`），`?` 匹配单个字符，
+//!   `[abc]` / `[a-z]` / `[!0-3]` 匹配字符集合，`\` 转义下一个字符
+//! - `regex:` 前缀：正则表达式，自动加 `^...$` 锚定（如 `regex:gpu-[0-3]`）
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+const REGEX_PREFIX: &str = "regex:";
+
+/// 文本是否匹配模式；无效的正则按不匹配处理（规则加载时已由校验报告）
+pub fn matches_pattern(text: &str, pattern: &str) -> bool {
+    match pattern.strip_prefix(REGEX_PREFIX) {
+        Some(source) => compile_regex(source).is_ok_and(|re| re.is_match(text)),
+        None => {
+            let text: Vec<char> = text.chars().collect();
+            let pattern: Vec<char> = pattern.chars().collect();
+            glob_match(&text, &pattern)
+        }
+    }
+}
+
+/// 检查模式是否有效（正则能编译、glob 字符集合已闭合）
+pub fn validate_pattern(pattern: &str) -> Result<(), String> {
+    match pattern.strip_prefix(REGEX_PREFIX) {
+        Some(source) => compile_regex(source).map(|_| ()),
+        None => {
+            let chars: Vec<char> = pattern.chars().collect();
+            let mut i = 0;
+            while i < chars.len() {
+                match chars[i] {
+                    '\\' => i += 2,
+                    '[' => match parse_class(&chars, i) {
+                        Some((_, next)) => i = next,
+                        None => return Err(format!("模式 {} 中的 [ 未闭合", pattern)),
+                    },
+                    _ => i += 1,
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// 编译锚定的正则（带缓存，规则每次匹配不重复编译）
+fn compile_regex(source: &str) -> Result<Arc<Regex>, String> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Arc<Regex>>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+
+    if let Some(re) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(source) {
+        return Ok(Arc::clone(re));
+    }
+    let re = Arc::new(
+        Regex::new(&format!("^(?:{})$", source)).map_err(|e| format!("无效的正则 {}: {}", source, e))?,
+    );
+    cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(source.to_string(), Arc::clone(&re));
+    Ok(re)
+}
+
+/// 解析从 `start`（指向 `[`）开始的字符集合，返回 (匹配函数所需的集合, 下一个位置)
+fn parse_class(pattern: &[char], start: usize) -> Option<(CharClass, usize)> {
+    let mut i = start + 1;
+    let negated = matches!(pattern.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+    let mut ranges = Vec::new();
+    // 紧跟在 [ 之后的 ] 视为普通字符
+    let mut first = true;
+    while let Some(&c) = pattern.get(i) {
+        if c == ']' && !first {
+            return Some((CharClass { negated, ranges }, i + 1));
+        }
+        first = false;
+        let lo = if c == '\\' {
+            i += 1;
+            *pattern.get(i)?
+        } else {
+            c
+        };
+        if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).is_some_and(|&c| c != ']') {
+            ranges.push((lo, pattern[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((lo, lo));
+            i += 1;
+        }
+    }
+    None
+}
+
+struct CharClass {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != self.negated
+    }
+}
+
+/// glob 整串匹配：遇到 `*` 时记录回溯点，失配时让最近的 `*` 多吞一个字符
+fn glob_match(text: &[char], pattern: &[char]) -> bool {
+    let (mut t, mut p) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(p + 1),
+            Some('[') => match parse_class(pattern, p) {
+                Some((class, next)) if class.matches(text[t]) => Some(next),
+                Some(_) => None,
+                // 未闭合的 [ 按普通字符处理
+                None => (text[t] == '[').then_some(p + 1),
+            },
+            Some('\\') if p + 1 < pattern.len() => (text[t] == pattern[p + 1]).then_some(p + 2),
+            Some(&c) => (text[t] == c).then_some(p + 1),
+            None => None,
+        };
+        match (step, backtrack) {
+            (Some(next), _) => {
+                p = next;
+                t += 1;
+            }
+            (None, Some((star_p, star_t))) => {
+                p = star_p + 1;
+                t = star_t + 1;
+                backtrack = Some((star_p, star_t + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("gpu-0", "gpu-*"));
+        assert!(matches_pattern("gpu-1", "gpu-*"));
+        assert!(!matches_pattern("cpu-0", "gpu-*"));
+        assert!(matches_pattern("mlx5_0", "mlx5_*"));
+        assert!(matches_pattern("gpu-0", "gpu-0"));
+        assert!(!matches_pattern("gpu-01", "gpu-0"));
+
+        // 多个通配符（集群命名空间）
+        assert!(matches_pattern("node-a::gpu-3", "node-*::gpu-*"));
+        assert!(!matches_pattern("node-a::mlx5_0", "node-*::gpu-*"));
+        assert!(matches_pattern("node-a::pid-42", "*::pid-*"));
+        assert!(matches_pattern("a-b-c", "*-*-*"));
+        assert!(!matches_pattern("a-b", "*-*-*"));
+
+        // ? 与字符集合
+        assert!(matches_pattern("gpu-3", "gpu-?"));
+        assert!(!matches_pattern("gpu-13", "gpu-?"));
+        assert!(matches_pattern("gpu-2", "gpu-[0-3]"));
+        assert!(!matches_pattern("gpu-5", "gpu-[0-3]"));
+        assert!(matches_pattern("gpu-5", "gpu-[!0-3]"));
+        assert!(matches_pattern("a*b", "a\\*b"));
+        assert!(!matches_pattern("axb", "a\\*b"));
+
+        // 正则（整串锚定）
+        assert!(matches_pattern("node-a::gpu-12", "regex:node-[a-z]+::gpu-\\d+"));
+        assert!(!matches_pattern("xnode-a::gpu-12", "regex:node-[a-z]+::gpu-\\d+"));
+        assert!(!matches_pattern("anything", "regex:("));
+    }
+
+    #[test]
+    fn test_validate_pattern() {
+        assert!(validate_pattern("node-*::gpu-[0-7]").is_ok());
+        assert!(validate_pattern("gpu-[0-7").is_err());
+        assert!(validate_pattern("regex:gpu-(").is_err());
+    }
+}
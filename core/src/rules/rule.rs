@@ -1,15 +1,28 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 
+pub use super::expr::ExprTarget;
+
 /// 规则数据结构
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Rule {
     pub name: String,
     pub scene: String,
     pub priority: u32,
+    /// 顶层条件（AND）；YAML 中既可写成列表，也可写成 `all:` / `any:` 分组
+    #[serde(deserialize_with = "deserialize_conditions")]
     pub conditions: Vec<Condition>,
     pub root_cause_pattern: RootCausePattern,
     pub solution_steps: Vec<SolutionStep>,
+    /// 可直接执行的修复动作（按声明顺序执行）；为空时 `ark fix` 回退到从文本推断动作
+    #[serde(default)]
+    pub actions: Vec<RuleAction>,
+    /// 同一实体上重复命中的冷却时间（秒）；冷却期内命中只记录不再推荐
+    #[serde(default)]
+    pub cooldown_seconds: Option<u64>,
+    /// 与本规则同时命中时被抑制的规则名（如具体根因规则抑制泛化规则）
+    #[serde(default)]
+    pub suppresses: Vec<String>,
     pub related_evidences: Vec<String>,
     pub applicability: Applicability,
 }
@@ -62,6 +75,33 @@ pub enum Condition {
         value_pattern: Option<String>,
         value_threshold: Option<f64>,
     },
+    /// 时间窗口条件：最近 window_secs 秒内满足过滤条件的事件不少于 min_count 条
+    /// （如 60 秒内 mlx5_* 上超过 50 次 transport.drop）
+    #[serde(rename = "window")]
+    Window {
+        event_type: String,
+        entity_id_pattern: Option<String>,
+        value_pattern: Option<String>,
+        value_threshold: Option<f64>,
+        min_count: usize,
+        window_secs: u64,
+    },
+    /// 缺失条件：最近 window_secs 秒内某类遥测没有到达
+    /// 匹配实体中任一个在窗口内沉默（之前有事件、窗口内没有），或者窗口内完全没有匹配事件时成立
+    #[serde(rename = "absent")]
+    Absent {
+        event_type: String,
+        entity_id_pattern: Option<String>,
+        window_secs: u64,
+    },
+    /// 表达式条件：对每个事件（或节点）求值 Rhai 表达式，任一为 true 即成立
+    /// （如 `event.value.to_f() > 80 && event.entity_id.startsWith("gpu-")`）
+    #[serde(rename = "expr")]
+    Expr {
+        expr: String,
+        #[serde(default)]
+        over: ExprTarget,
+    },
     /// 图边条件
     #[serde(rename = "graph")]
     Graph {
@@ -105,6 +145,71 @@ pub struct SolutionStep {
     pub manual: bool,
 }
 
+/// 规则声明的修复动作（与 agent 的 `ActionType` 一一对应）
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    /// 发送信号（如 10 = SIGUSR1 触发 Checkpoint）
+    Signal { signal: i32 },
+    /// Cgroup 限流
+    CgroupThrottle {
+        cpu_quota: Option<u64>,    // CPU 配额（微秒 / 100ms 周期）
+        memory_limit: Option<u64>, // 内存限制（字节）
+        io_limit: Option<u64>,     // IO 限制（字节/秒）
+    },
+    /// 重启网络接口
+    NetworkRestart { interface: String },
+    /// 用 tc（HTB）限制网络接口出向带宽，不中断接口上的其他流量
+    NetworkThrottle { interface: String, rate_mbps: u64 },
+    /// 优雅降级：先发信号，等待后按需强制终止
+    GracefulShutdown {
+        #[serde(default = "default_shutdown_signal")]
+        signal: i32,
+        #[serde(default = "default_shutdown_wait")]
+        wait_seconds: u64,
+        #[serde(default)]
+        force_kill: bool,
+    },
+    /// 逐级处置：按顺序发送信号并等待，进程退出后不再升级（如 USR1 → TERM → KILL）
+    Escalate { steps: Vec<EscalationStep> },
+    /// 强制终止进程
+    KillProcess,
+    /// 隔离节点
+    IsolateNode { reason: String },
+    /// 检查 Checkpoint 文件
+    CheckCheckpoint { checkpoint_dir: String },
+    /// 重置 GPU：先终止该 GPU 上目标作业的进程再重置设备（其他作业在用时拒绝执行）
+    GpuReset { gpu_id: u32 },
+    /// 调用 daemon 注册的外部执行器（参数原样传给执行器）
+    Plugin {
+        name: String,
+        #[serde(default)]
+        params: serde_json::Value,
+    },
+    /// 自定义命令
+    Custom {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+/// 逐级处置中的一级：发送信号后最多等待 wait_seconds 秒
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EscalationStep {
+    pub signal: i32,
+    #[serde(default = "default_shutdown_wait")]
+    pub wait_seconds: u64,
+}
+
+fn default_shutdown_signal() -> i32 {
+    15 // SIGTERM
+}
+
+fn default_shutdown_wait() -> u64 {
+    10
+}
+
 /// 适用条件
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Applicability {
@@ -116,3 +221,44 @@ pub struct Applicability {
 fn default_min_confidence() -> f64 {
     0.8
 }
+
+/// 顶层条件分组写法：`conditions: { all: [...] }` 或 `conditions: { any: [...] }`
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+enum ConditionGroup {
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+/// 顶层条件兼容列表和分组两种写法
+/// 手写 Visitor 而非 untagged 枚举，保留内部条件解析错误的原始信息和行号
+fn deserialize_conditions<'de, D>(deserializer: D) -> Result<Vec<Condition>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+    use serde::de::{MapAccess, SeqAccess, Visitor};
+
+    struct ConditionsVisitor;
+
+    impl<'de> Visitor<'de> for ConditionsVisitor {
+        type Value = Vec<Condition>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("条件列表，或 all/any 条件分组")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+            Vec::deserialize(SeqAccessDeserializer::new(seq))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            Ok(match ConditionGroup::deserialize(MapAccessDeserializer::new(map))? {
+                ConditionGroup::All(conditions) => conditions,
+                ConditionGroup::Any(conditions) => vec![Condition::Any { conditions }],
+            })
+        }
+    }
+
+    deserializer.deserialize_any(ConditionsVisitor)
+}
@@ -0,0 +1,336 @@
+//! 规则文件校验与离线测试
+//!
+//! 供 `ark rules` 命令使用：在规则上线前报告带文件/行号的解析错误，
+//! 并能用样例事件验证规则是否按预期命中
+
+use super::pattern::validate_pattern;
+use super::{Condition, ExprTarget, Rule, RuleAction, RuleMatcher};
+use crate::event::{Event, EventType};
+use crate::graph::{EdgeType, NodeType, StateGraph};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 规则文件错误（带文件与行列位置，行列从 1 开始）
+#[derive(Debug, Clone)]
+pub struct RuleFileError {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for RuleFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(f, "{}:{}:{}: {}", self.path.display(), line, column, self.message)
+            }
+            _ => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+impl std::error::Error for RuleFileError {}
+
+/// 是否为规则文件（.yaml / .yml）
+pub(crate) fn is_rule_file(path: &Path) -> bool {
+    matches!(path.extension().and_then(|s| s.to_str()), Some("yaml") | Some("yml"))
+}
+
+/// 解析单个规则文件（只检查 YAML 语法和结构，错误带行列号）
+pub(crate) fn parse_rule_file(path: &Path) -> Result<Rule, RuleFileError> {
+    let error = |line, column, message| RuleFileError {
+        path: path.to_path_buf(),
+        line,
+        column,
+        message,
+    };
+
+    let content = fs::read_to_string(path).map_err(|e| error(None, None, format!("读取失败: {}", e)))?;
+    serde_yaml::from_str(&content).map_err(|e| {
+        let location = e.location();
+        error(
+            location.as_ref().map(|l| l.line()),
+            location.as_ref().map(|l| l.column()),
+            e.to_string(),
+        )
+    })
+}
+
+/// 加载并校验单个规则文件：YAML 错误带行列号，语义错误（未知事件类型等）只带文件名
+pub fn load_rule_file<P: AsRef<Path>>(path: P) -> Result<Rule, RuleFileError> {
+    let path = path.as_ref();
+    let rule = parse_rule_file(path)?;
+    let problems = validate_rule(&rule);
+    if !problems.is_empty() {
+        return Err(RuleFileError {
+            path: path.to_path_buf(),
+            line: None,
+            column: None,
+            message: problems.join("; "),
+        });
+    }
+    Ok(rule)
+}
+
+/// 检查规则中 YAML 结构之外的语义问题（返回空列表表示通过）
+///
+/// 例如事件类型拼写错误时，规则能解析但永远不会命中
+pub fn validate_rule(rule: &Rule) -> Vec<String> {
+    let mut problems = Vec::new();
+    if rule.conditions.is_empty() {
+        problems.push("conditions 为空，规则会无条件命中".to_string());
+    }
+    for condition in &rule.conditions {
+        validate_condition(condition, &mut problems);
+    }
+    if rule.solution_steps.is_empty() {
+        problems.push("solution_steps 为空".to_string());
+    }
+    if rule.suppresses.contains(&rule.name) {
+        problems.push("suppresses 不能包含规则自身".to_string());
+    }
+    for action in &rule.actions {
+        match action {
+            RuleAction::Signal { signal } | RuleAction::GracefulShutdown { signal, .. }
+                if !(1..=64).contains(signal) =>
+            {
+                problems.push(format!("无效信号: {}", signal));
+            }
+            RuleAction::CgroupThrottle { cpu_quota: None, memory_limit: None, io_limit: None } => {
+                problems.push("cgroup_throttle 动作至少需要一个限制".to_string());
+            }
+            RuleAction::NetworkThrottle { rate_mbps: 0, .. } => {
+                problems.push("network_throttle 动作的 rate_mbps 必须大于 0".to_string());
+            }
+            RuleAction::Escalate { steps } if steps.is_empty() => {
+                problems.push("escalate 动作至少需要一级".to_string());
+            }
+            RuleAction::Escalate { steps } => {
+                for step in steps.iter().filter(|step| !(1..=64).contains(&step.signal)) {
+                    problems.push(format!("无效信号: {}", step.signal));
+                }
+            }
+            _ => {}
+        }
+    }
+    problems
+}
+
+fn validate_condition(condition: &Condition, problems: &mut Vec<String>) {
+    let patterns: Vec<&String> = match condition {
+        Condition::Event { entity_id_pattern, .. }
+        | Condition::Window { entity_id_pattern, .. }
+        | Condition::Absent { entity_id_pattern, .. }
+        | Condition::Metric { entity_id_pattern, .. } => entity_id_pattern.iter().collect(),
+        Condition::Graph { from_pattern, to_pattern, .. } => from_pattern.iter().chain(to_pattern).collect(),
+        _ => Vec::new(),
+    };
+    for pattern in patterns {
+        if let Err(e) = validate_pattern(pattern) {
+            problems.push(e);
+        }
+    }
+
+    match condition {
+        Condition::Event { event_type, .. } => {
+            if serde_json::from_value::<EventType>(serde_json::json!(event_type)).is_err() {
+                problems.push(format!("未知事件类型: {}", event_type));
+            }
+        }
+        Condition::Window { event_type, min_count, window_secs, .. } => {
+            if serde_json::from_value::<EventType>(serde_json::json!(event_type)).is_err() {
+                problems.push(format!("未知事件类型: {}", event_type));
+            }
+            if *min_count == 0 || *window_secs == 0 {
+                problems.push("window 条件的 min_count 和 window_secs 必须大于 0".to_string());
+            }
+        }
+        Condition::Absent { event_type, window_secs, .. } => {
+            if serde_json::from_value::<EventType>(serde_json::json!(event_type)).is_err() {
+                problems.push(format!("未知事件类型: {}", event_type));
+            }
+            if *window_secs == 0 {
+                problems.push("absent 条件的 window_secs 必须大于 0".to_string());
+            }
+        }
+        Condition::Expr { expr, .. } => {
+            if let Err(e) = super::expr::compile(expr) {
+                problems.push(e);
+            }
+        }
+        Condition::Graph { edge_type, .. } => {
+            if EdgeType::parse(edge_type).is_none() {
+                problems.push(format!("未知边类型: {}", edge_type));
+            }
+        }
+        Condition::Metric { node_type, metrics, .. } => {
+            if let Some(node_type) = node_type {
+                if NodeType::parse(node_type).is_none() {
+                    problems.push(format!("未知节点类型: {}", node_type));
+                }
+            }
+            if metrics.is_empty() {
+                problems.push("metric 条件缺少 metrics".to_string());
+            }
+        }
+        Condition::Any { conditions } | Condition::All { conditions } => {
+            if conditions.is_empty() {
+                problems.push("any/all 条件组为空".to_string());
+            }
+            for condition in conditions {
+                validate_condition(condition, problems);
+            }
+        }
+    }
+}
+
+/// 目录校验结果：(解析成功的规则及其路径, 错误列表)
+pub type DirValidation = (Vec<(PathBuf, Rule)>, Vec<RuleFileError>);
+
+/// 校验目录中的所有规则文件（不在第一个错误处停止）
+/// 规则按优先级降序
+pub fn validate_dir<P: AsRef<Path>>(dir: P) -> Result<DirValidation, String> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir.as_ref())
+        .map_err(|e| format!("读取规则目录失败: {}", e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| is_rule_file(p))
+        .collect();
+    paths.sort();
+
+    let mut rules = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        match load_rule_file(&path) {
+            Ok(rule) => rules.push((path, rule)),
+            Err(e) => errors.push(e),
+        }
+    }
+    rules.sort_by(|a, b| b.1.priority.cmp(&a.1.priority));
+    Ok((rules, errors))
+}
+
+/// 规则离线测试结果：整体是否命中，以及每个顶层条件的结果
+#[derive(Debug, Clone)]
+pub struct RuleTestReport {
+    pub matched: bool,
+    pub conditions: Vec<(String, bool)>,
+}
+
+/// 用样例事件测试规则：事件先按顺序灌入一张新状态图，再逐条件匹配
+pub async fn test_rule(rule: &Rule, events: &[Event]) -> RuleTestReport {
+    let graph = StateGraph::new();
+    for event in events {
+        // 单条事件处理失败不影响其他事件，与 daemon 行为一致
+        let _ = graph.process_event(event).await;
+    }
+
+    let mut conditions = Vec::new();
+    for condition in &rule.conditions {
+        let matched = RuleMatcher::match_condition(condition, events, &graph).await;
+        conditions.push((describe_condition(condition), matched));
+    }
+    RuleTestReport {
+        matched: conditions.iter().all(|(_, matched)| *matched),
+        conditions,
+    }
+}
+
+/// 条件的一行简述（用于测试报告）
+pub(crate) fn describe_condition(condition: &Condition) -> String {
+    match condition {
+        Condition::Event { event_type, entity_id_pattern, .. } => format!(
+            "event {} {}",
+            event_type,
+            entity_id_pattern.as_deref().unwrap_or("*")
+        ),
+        Condition::Window { event_type, entity_id_pattern, min_count, window_secs, .. } => format!(
+            "window {} {} >= {} 次 / {}s",
+            event_type,
+            entity_id_pattern.as_deref().unwrap_or("*"),
+            min_count,
+            window_secs
+        ),
+        Condition::Absent { event_type, entity_id_pattern, window_secs } => format!(
+            "absent {} {} {}s 内无事件",
+            event_type,
+            entity_id_pattern.as_deref().unwrap_or("*"),
+            window_secs
+        ),
+        Condition::Expr { expr, over } => format!(
+            "expr[{}] {}",
+            match over {
+                ExprTarget::Event => "event",
+                ExprTarget::Node => "node",
+            },
+            expr
+        ),
+        Condition::Graph { edge_type, from_pattern, to_pattern } => format!(
+            "graph {} -[{}]-> {}",
+            from_pattern.as_deref().unwrap_or("*"),
+            edge_type,
+            to_pattern.as_deref().unwrap_or("*")
+        ),
+        Condition::Metric { node_type, entity_id_pattern, metrics } => format!(
+            "metric {} {} ({})",
+            node_type.as_deref().unwrap_or("*"),
+            entity_id_pattern.as_deref().unwrap_or("*"),
+            metrics.iter().map(|m| m.key.as_str()).collect::<Vec<_>>().join(", ")
+        ),
+        Condition::Any { conditions } => format!("any（{} 个子条件）", conditions.len()),
+        Condition::All { conditions } => format!("all（{} 个子条件）", conditions.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::EscalationStep;
+
+    #[test]
+    fn test_parse_error_carries_line_and_semantic_errors_are_reported() {
+        let dir = std::env::temp_dir().join(format!("ark-rules-validate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("bad-yaml.yaml"), "name: x\nscene: y\npriority: [\n").unwrap();
+        let seed = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/../rules/gpu-oom.yaml")).unwrap();
+        fs::write(dir.join("typo.yaml"), seed.replace("error.hw", "error.hardware")).unwrap();
+        fs::write(dir.join("gpu-oom.yaml"), &seed).unwrap();
+
+        let (rules, errors) = validate_dir(&dir).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].line.is_some());
+        assert!(errors[1].to_string().contains("未知事件类型: error.hardware"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_escalate_action_parses_and_validates_signals() {
+        let seed = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/../rules/gpu-oom.yaml")).unwrap();
+        let yaml = seed.replace(
+            "actions:\n",
+            "actions:\n  - type: \"escalate\"\n    steps:\n      - { signal: 10, wait_seconds: 30 }\n      - { signal: 99 }\n",
+        );
+        let rule: Rule = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(
+            rule.actions[0],
+            RuleAction::Escalate {
+                steps: vec![
+                    EscalationStep { signal: 10, wait_seconds: 30 },
+                    EscalationStep { signal: 99, wait_seconds: 10 },
+                ]
+            }
+        );
+        assert_eq!(validate_rule(&rule), vec!["无效信号: 99".to_string()]);
+    }
+
+    #[test]
+    fn test_shipped_rules_validate_cleanly() {
+        let (rules, errors) = validate_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../rules")).unwrap();
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert!(errors.is_empty(), "{:#?}", errors);
+        assert!(!rules.is_empty());
+    }
+}
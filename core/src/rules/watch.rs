@@ -0,0 +1,290 @@
+//! 规则热加载：监听规则目录，YAML 变化时整体重建并原子替换规则引擎
+
+use super::{FiringState, RuleEngine, RuleMatch};
+use crate::event::Event;
+use crate::graph::StateGraph;
+use notify::{Event as FsEvent, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 可热加载的规则引擎（由 daemon 长期持有）
+///
+/// 每次重载都从目录完整解析出新的 `RuleEngine` 后再替换，读者要么看到旧规则集，
+/// 要么看到新规则集，不会看到一半。任一文件解析失败时保留旧规则集，代数不变。
+/// 规则冷却状态也由它持有，跨重载保留。
+pub struct ReloadableRuleEngine {
+    dir: PathBuf,
+    engine: RwLock<Arc<RuleEngine>>,
+    generation: AtomicU64,
+    firing: Mutex<FiringState>,
+    /// 流式匹配中上一次评估时处于命中状态的规则名
+    active: Mutex<HashSet<String>>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl ReloadableRuleEngine {
+    /// 首次加载规则目录（代数为 1）
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Arc<Self>, String> {
+        let dir = dir.as_ref().to_path_buf();
+        let engine = RuleEngine::load_from_dir(&dir)?;
+        Ok(Arc::new(Self {
+            dir,
+            engine: RwLock::new(Arc::new(engine)),
+            generation: AtomicU64::new(1),
+            firing: Mutex::new(FiringState::new()),
+            active: Mutex::new(HashSet::new()),
+            watcher: Mutex::new(None),
+        }))
+    }
+
+    /// 当前规则集快照；持有期间即使发生重载也不受影响
+    pub fn current(&self) -> Arc<RuleEngine> {
+        Arc::clone(&self.engine.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// 规则集代数：每次成功重载加 1，可用于判断规则是否已生效
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// 规则目录
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 用当前规则集按需匹配，附带冷却与抑制状态（按墙钟计时）
+    ///
+    /// 只读取冷却状态、不记录触发，按需诊断不会让流式告警进入冷却
+    pub async fn evaluate(&self, graph: &StateGraph, events: &[Event]) -> Vec<RuleMatch> {
+        let engine = self.current();
+        let matched = engine.match_detailed(graph, events).await;
+        self.firing
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .peek(matched, now_ms())
+    }
+
+    /// 流式匹配：daemon 每处理完一个事件调用一次，返回需要发出告警的命中
+    ///
+    /// 与该事件无关的规则集直接跳过。告警是边沿触发的：规则由不命中变为命中时发出；
+    /// 设置了 `cooldown_seconds` 的规则在持续命中期间每个冷却周期（或出现新实体时）再发出一次。
+    /// 冷却状态由流式匹配记录，流式告警过的规则在按需诊断时显示为冷却中。
+    ///
+    /// 只在最近的事件上匹配（见 `stream_window_secs`），开销不随事件历史容量增长。
+    pub async fn on_event(&self, graph: &StateGraph, event: &Event) -> Vec<RuleMatch> {
+        let engine = self.current();
+        if !engine.is_relevant(&event.event_type) {
+            return Vec::new();
+        }
+        let events = graph
+            .recent_events_within(stream_window_secs(&engine).saturating_mul(1000))
+            .await;
+        let matched = engine.match_detailed(graph, &events).await;
+
+        let matches = self
+            .firing
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .apply(matched, now_ms());
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        let alerts = matches
+            .iter()
+            .filter(|m| m.fired() && (m.rule.cooldown_seconds.is_some() || !active.contains(&m.rule.name)))
+            .cloned()
+            .collect();
+        *active = matches.into_iter().map(|m| m.rule.name).collect();
+        alerts
+    }
+
+    /// 立即重新加载规则目录，成功时返回新的代数
+    pub fn reload(&self) -> Result<u64, String> {
+        let engine = RuleEngine::load_from_dir(&self.dir)?;
+        let mut slot = self.engine.write().unwrap_or_else(|e| e.into_inner());
+        *slot = Arc::new(engine);
+        Ok(self.generation.fetch_add(1, Ordering::AcqRel) + 1)
+    }
+
+    /// 开始监听规则目录，YAML 文件新增、修改、删除时自动重载
+    ///
+    /// 监听器随 `ReloadableRuleEngine` 一起释放；重复调用会替换之前的监听器
+    pub fn watch(self: &Arc<Self>) -> Result<(), String> {
+        let weak = Arc::downgrade(self);
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<FsEvent>| {
+            let Some(this) = weak.upgrade() else { return };
+            match res {
+                Ok(event) if is_rule_change(&event) => match this.reload() {
+                    Ok(generation) => {
+                        eprintln!("[rules] 规则已重新加载（第 {} 代）: {}", generation, this.dir.display());
+                    }
+                    Err(e) => eprintln!("[rules] 规则重新加载失败，继续使用旧规则: {}", e),
+                },
+                Ok(_) => {}
+                Err(e) => eprintln!("[rules] 监听规则目录出错: {}", e),
+            }
+        })
+        .map_err(|e| format!("创建规则目录监听器失败: {}", e))?;
+
+        watcher
+            .watch(&self.dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("监听规则目录 {} 失败: {}", self.dir.display(), e))?;
+        *self.watcher.lock().unwrap_or_else(|e| e.into_inner()) = Some(watcher);
+        Ok(())
+    }
+}
+
+/// 流式匹配的最短事件窗口（秒）
+const STREAM_WINDOW_SECS: u64 = 300;
+
+/// 流式匹配使用的事件窗口：至少 `STREAM_WINDOW_SECS`，并覆盖两倍的最长规则窗口
+/// （缺失条件需要窗口之前的事件才能判断实体是否沉默）
+fn stream_window_secs(engine: &RuleEngine) -> u64 {
+    STREAM_WINDOW_SECS.max(engine.lookback_secs().saturating_mul(2))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 只关心 YAML 文件的内容变化（忽略编辑器临时文件和纯访问事件）
+fn is_rule_change(event: &FsEvent) -> bool {
+    matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) && event.paths.iter().any(|p| {
+        matches!(p.extension().and_then(|s| s.to_str()), Some("yaml") | Some("yml"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventType;
+
+    #[test]
+    fn test_reload_bumps_generation_and_keeps_old_rules_on_error() {
+        let dir = std::env::temp_dir().join(format!("ark-rules-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let seed = concat!(env!("CARGO_MANIFEST_DIR"), "/../rules/gpu-oom.yaml");
+        std::fs::copy(seed, dir.join("gpu-oom.yaml")).unwrap();
+
+        let engine = ReloadableRuleEngine::load(&dir).unwrap();
+        assert_eq!(engine.generation(), 1);
+        let before = engine.current();
+        assert_eq!(before.rules().len(), 1);
+
+        std::fs::copy(seed, dir.join("gpu-oom-copy.yml")).unwrap();
+        assert_eq!(engine.reload().unwrap(), 2);
+        assert_eq!(engine.current().rules().len(), 2);
+        // 重载前取得的快照不受影响
+        assert_eq!(before.rules().len(), 1);
+
+        std::fs::write(dir.join("broken.yaml"), "name: [").unwrap();
+        assert!(engine.reload().is_err());
+        assert_eq!(engine.generation(), 2);
+        assert_eq!(engine.current().rules().len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 在临时目录写入一条匹配 XID 硬件错误的规则（extra 追加到规则 YAML 末尾）
+    fn xid_rule_dir(tag: &str, extra: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ark-rules-{}-{}", tag, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("xid.yaml"),
+            format!(
+                r#"
+name: xid
+scene: gpu_error
+priority: 1
+conditions:
+  - type: "event"
+    event_type: "error.hw"
+    value_pattern: "XID"
+root_cause_pattern: {{ primary: "GPU 硬件错误", secondary: [] }}
+solution_steps: [{{ step: 1, action: "隔离 GPU", manual: true }}]
+related_evidences: []
+applicability: {{ min_confidence: 0.8 }}
+{}
+"#,
+                extra
+            ),
+        )
+        .unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_streaming_alerts_on_rising_edge_only() {
+        let dir = xid_rule_dir("stream", "");
+        let engine = ReloadableRuleEngine::load(&dir).unwrap();
+        let graph = StateGraph::new();
+        let feed = |event: Event| {
+            let (graph, engine) = (&graph, &engine);
+            async move {
+                graph.process_event(&event).await.unwrap();
+                engine.on_event(graph, &event).await
+            }
+        };
+
+        let util = Event::new(EventType::ComputeUtil, "gpu-0".into(), "90".into(), None, None);
+        let xid = Event::new(EventType::ErrorHw, "gpu-0".into(), "XID_79".into(), None, None);
+        assert!(feed(util).await.is_empty());
+        let alerts = feed(xid.clone()).await;
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].entities, vec!["gpu-0".to_string()]);
+        // 持续命中不重复告警
+        assert!(feed(xid).await.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_does_not_start_streaming_cooldown() {
+        let dir = xid_rule_dir("evaluate", "cooldown_seconds: 600");
+        let engine = ReloadableRuleEngine::load(&dir).unwrap();
+        let graph = StateGraph::new();
+        let xid = Event::new(EventType::ErrorHw, "gpu-0".into(), "XID_79".into(), None, None);
+        graph.process_event(&xid).await.unwrap();
+
+        // 按需诊断看到命中，但不记录触发
+        let events = graph.recent_events(usize::MAX).await;
+        assert!(engine.evaluate(&graph, &events).await[0].fired());
+        assert!(engine.evaluate(&graph, &events).await[0].fired());
+
+        // 流式告警照常发出，之后按需诊断显示为冷却中
+        assert_eq!(engine.on_event(&graph, &xid).await.len(), 1);
+        assert!(!engine.evaluate(&graph, &events).await[0].fired());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_streaming_matches_only_recent_events() {
+        let dir = xid_rule_dir("window", "");
+        let engine = ReloadableRuleEngine::load(&dir).unwrap();
+        let graph = StateGraph::new();
+
+        // 早于流式窗口的 XID 不再触发告警
+        let mut xid = Event::new(EventType::ErrorHw, "gpu-0".into(), "XID_79".into(), None, None);
+        xid.ts = 1_000;
+        graph.process_event(&xid).await.unwrap();
+        let mut ecc = Event::new(EventType::ErrorHw, "gpu-1".into(), "ECC".into(), None, None);
+        ecc.ts = 1_000 + (STREAM_WINDOW_SECS + 1) * 1000;
+        graph.process_event(&ecc).await.unwrap();
+        assert!(engine.on_event(&graph, &ecc).await.is_empty());
+
+        // 窗口内的 XID 照常触发
+        xid.ts = ecc.ts + 1;
+        graph.process_event(&xid).await.unwrap();
+        assert_eq!(engine.on_event(&graph, &xid).await.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
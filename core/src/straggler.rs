@@ -0,0 +1,180 @@
+//! 多卡任务掉队检测：同一 job 内对比各进程的利用率/吞吐，找出明显落后于同伴的 rank
+//!
+//! "训练变慢但什么都没坏" 的最常见原因是单个 rank 拖慢了整个集合通信，
+//! 这里把同伴中位数作为基线，落后超过 margin 的进程视为掉队者
+
+use crate::graph::{EdgeType, Node, StateGraph};
+
+/// 默认掉队阈值：低于同伴中位数 20% 视为掉队
+pub const DEFAULT_STRAGGLER_MARGIN: f64 = 0.2;
+
+/// 掉队进程
+#[derive(Debug, Clone, PartialEq)]
+pub struct Straggler {
+    /// 进程节点 ID（集群模式下带命名空间，如 "node-a::pid-1234"）
+    pub process_id: String,
+    /// 利用率最低的资源节点（按 util 比较时给出，如 "gpu-3"）
+    pub resource_id: Option<String>,
+    /// 比较使用的指标：throughput 或 util
+    pub metric: &'static str,
+    /// 该进程的指标值
+    pub value: f64,
+    /// 其余同伴的中位数
+    pub peer_median: f64,
+}
+
+impl Straggler {
+    /// 相对同伴中位数的落后比例（0.0 ~ 1.0）
+    pub fn lag(&self) -> f64 {
+        if self.peer_median <= 0.0 {
+            return 0.0;
+        }
+        1.0 - self.value / self.peer_median
+    }
+
+    /// 可读描述，用于根因列表
+    pub fn describe(&self) -> String {
+        let target = match &self.resource_id {
+            Some(resource) => format!("{} ({})", self.process_id, resource),
+            None => self.process_id.clone(),
+        };
+        format!(
+            "掉队进程 {}: {} {:.1} 落后同 job 中位数 {:.1} 约 {:.0}%",
+            target,
+            self.metric,
+            self.value,
+            self.peer_median,
+            self.lag() * 100.0
+        )
+    }
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+impl StateGraph {
+    /// 检测 job 内的掉队进程
+    ///
+    /// 所有进程都上报了 `throughput` metadata 时按吞吐比较，否则按所消耗资源的 `util` 比较
+    /// （多卡进程取最慢的一张卡）。至少需要两个有指标的进程才会比较。
+    /// margin 为相对同伴中位数的落后比例，如 0.2 表示低于中位数 20%。
+    pub async fn find_job_stragglers(&self, job_id: &str, margin: f64) -> Vec<Straggler> {
+        let processes = self.find_processes_by_job(job_id).await;
+        if processes.len() < 2 {
+            return Vec::new();
+        }
+
+        let by_throughput = processes.iter().all(|p| throughput(p).is_some());
+        let samples: Vec<(String, Option<String>, f64)> = if by_throughput {
+            processes
+                .iter()
+                .filter_map(|p| throughput(p).map(|v| (p.id.clone(), None, v)))
+                .collect()
+        } else {
+            let nodes = self.get_nodes_async().await;
+            let edges = self.get_all_edges_async().await;
+            processes
+                .iter()
+                .filter_map(|p| {
+                    edges
+                        .iter()
+                        .filter(|e| e.edge_type == EdgeType::Consumes && e.from == p.id)
+                        .filter_map(|e| {
+                            let util = nodes.get(&e.to)?.metadata.get("util")?.parse::<f64>().ok()?;
+                            Some((e.to.clone(), util))
+                        })
+                        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+                        .map(|(resource, util)| (p.id.clone(), Some(resource), util))
+                })
+                .collect()
+        };
+
+        if samples.len() < 2 {
+            return Vec::new();
+        }
+
+        let metric = if by_throughput { "throughput" } else { "util" };
+        samples
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (process_id, resource_id, value))| {
+                let mut peers: Vec<f64> = samples
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, s)| s.2)
+                    .collect();
+                let peer_median = median(&mut peers);
+                if peer_median > 0.0 && *value < peer_median * (1.0 - margin) {
+                    Some(Straggler {
+                        process_id: process_id.clone(),
+                        resource_id: resource_id.clone(),
+                        metric,
+                        value: *value,
+                        peer_median,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+fn throughput(process: &Node) -> Option<f64> {
+    process.metadata.get("throughput")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{Event, EventType};
+
+    fn event(event_type: EventType, entity_id: &str, value: &str, host: &str, pid: u32) -> Event {
+        Event {
+            ts: 1000,
+            event_type,
+            entity_id: entity_id.to_string(),
+            job_id: Some("job-1".to_string()),
+            pid: Some(pid),
+            value: value.to_string(),
+            node_id: Some(host.to_string()),
+            probe: None,
+            traceparent: None,
+            pod: None,
+            container_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_straggler_across_hosts() {
+        let graph = StateGraph::new();
+        let ranks = [
+            ("node-a", 1, "gpu-0", "92"),
+            ("node-a", 2, "gpu-1", "90"),
+            ("node-b", 3, "gpu-0", "41"),
+        ];
+        for (host, pid, gpu, util) in ranks {
+            let start = event(EventType::ProcessState, "", "start", host, pid);
+            graph.process_event(&start).await.unwrap();
+            let util = event(EventType::ComputeUtil, gpu, util, host, pid);
+            graph.process_event(&util).await.unwrap();
+        }
+
+        let stragglers = graph.find_job_stragglers("job-1", DEFAULT_STRAGGLER_MARGIN).await;
+        assert_eq!(stragglers.len(), 1);
+        assert_eq!(stragglers[0].process_id, "node-b::pid-3");
+        assert_eq!(stragglers[0].resource_id.as_deref(), Some("node-b::gpu-0"));
+        assert_eq!(stragglers[0].peer_median, 91.0);
+
+        // 放宽阈值后不再视为掉队
+        assert!(graph.find_job_stragglers("job-1", 0.6).await.is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+//! 只通过 ark-core 的公开 API 使用状态图与规则引擎（模拟外部嵌入方）
+
+use ark_core::rules::Rule;
+use ark_core::{
+    EdgeType, Event, EventType, ExportFormat, GraphConfig, NodeType, RuleEngine, StateGraph,
+};
+
+fn event(event_type: EventType, entity_id: &str, value: &str, job_id: Option<&str>, pid: Option<u32>) -> Event {
+    Event::new(
+        event_type,
+        entity_id.to_string(),
+        value.to_string(),
+        job_id.map(str::to_string),
+        pid,
+    )
+}
+
+#[tokio::test]
+async fn test_root_cause_through_public_api() {
+    let graph = StateGraph::new();
+    graph
+        .process_event(&event(EventType::ProcessState, "pid-42", "start", Some("job-1"), Some(42)))
+        .await
+        .unwrap();
+    graph
+        .process_event(&event(EventType::ComputeUtil, "gpu-0", "95", None, Some(42)))
+        .await
+        .unwrap();
+    graph
+        .process_event(&event(EventType::ErrorHw, "gpu-0", "XID_79", None, None))
+        .await
+        .unwrap();
+
+    let gpu = graph.node("gpu-0").await.expect("资源节点应存在");
+    assert_eq!(gpu.node_type, NodeType::Resource);
+    assert_eq!(gpu.node_type.as_str(), "resource");
+
+    let job = graph.find_processes_by_job("job-1").await;
+    assert_eq!(job.len(), 1);
+    assert_eq!(job[0].id, "pid-42");
+
+    let edges = graph.get_all_edges_async().await;
+    assert!(edges.iter().any(|e| e.edge_type == EdgeType::BlockedBy && e.to == "error-gpu-0"));
+    assert_eq!(graph.edge_count().await, edges.len());
+
+    let causes = graph.find_root_cause(42).await;
+    assert!(causes.iter().any(|c| c.contains("XID_79")), "causes: {:?}", causes);
+}
+
+#[tokio::test]
+async fn test_config_built_from_default() {
+    let mut config = GraphConfig::default();
+    config.max_nodes = Some(1);
+    let graph = StateGraph::with_config(config);
+
+    graph
+        .process_event(&event(EventType::ComputeUtil, "gpu-0", "50", None, Some(7)))
+        .await
+        .unwrap();
+
+    assert_eq!(graph.config().max_nodes, Some(1));
+    assert_eq!(graph.node_count().await, 1);
+}
+
+#[tokio::test]
+async fn test_rule_engine_from_in_memory_rules() {
+    let rule: Rule = serde_yaml::from_str(
+        r#"
+name: "GPU 硬件故障"
+scene: "gpu_error"
+priority: 10
+conditions:
+  - type: "event"
+    event_type: "error.hw"
+  - type: "graph"
+    edge_type: "blocked_by"
+    from_pattern: "pid-*"
+    to_pattern: "error-*"
+root_cause_pattern:
+  primary: "GPU 硬件错误"
+solution_steps: []
+related_evidences: []
+applicability:
+  min_confidence: 0.5
+"#,
+    )
+    .unwrap();
+    let engine = RuleEngine::from_rules(vec![rule]);
+    assert_eq!(engine.rule_count(), 1);
+    assert_eq!(engine.rules()[0].scene, "gpu_error");
+
+    let graph = StateGraph::new();
+    let events = vec![
+        event(EventType::ComputeUtil, "gpu-0", "95", None, Some(42)),
+        event(EventType::ErrorHw, "gpu-0", "XID_79", None, None),
+    ];
+    for e in &events {
+        graph.process_event(e).await.unwrap();
+    }
+
+    let matched = engine.match_rules(&graph, &events).await;
+    assert_eq!(matched.len(), 1);
+    assert_eq!(matched[0].name, "GPU 硬件故障");
+}
+
+#[tokio::test]
+async fn test_export_formats() {
+    let graph = StateGraph::new();
+    graph
+        .process_event(&event(EventType::ComputeUtil, "gpu-0", "95", None, Some(42)))
+        .await
+        .unwrap();
+
+    let dot = graph.export(ExportFormat::Dot).await;
+    assert!(dot.starts_with("digraph ark {"));
+    assert!(dot.contains("\"pid-42\" -> \"gpu-0\" [label=\"consumes (1.00)\""));
+
+    let json: serde_json::Value = serde_json::from_str(&graph.export(ExportFormat::Json).await).unwrap();
+    assert_eq!(json["nodes"].as_array().unwrap().len(), 2);
+    assert_eq!(json["edges"][0]["type"], "consumes");
+
+    let graphml = graph.export(ExportFormat::GraphMl).await;
+    assert!(graphml.contains("<edge source=\"pid-42\" target=\"gpu-0\">"));
+
+    assert_eq!(ExportFormat::parse("GraphML"), Some(ExportFormat::GraphMl));
+    assert_eq!(ExportFormat::parse("svg"), None);
+}
+
+#[tokio::test]
+async fn test_import_snapshot_and_resume() {
+    let graph = StateGraph::new();
+    graph
+        .process_event(&event(EventType::ComputeUtil, "gpu-0", "95", None, Some(42)))
+        .await
+        .unwrap();
+    let snapshot = graph.export(ExportFormat::Json).await;
+
+    let mut config = GraphConfig::default();
+    config.history_capacity = 10;
+    let restored = StateGraph::import_json_with_config(&snapshot, config).unwrap();
+    assert_eq!(restored.config().history_capacity, 10);
+    assert_eq!(restored.node_count().await, 2);
+
+    restored
+        .process_event(&event(EventType::ComputeUtil, "gpu-1", "80", None, Some(43)))
+        .await
+        .unwrap();
+    assert_eq!(restored.node_count().await, 4);
+}
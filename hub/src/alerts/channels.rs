@@ -0,0 +1,187 @@
+//! 聊天渠道的告警卡片：Slack（Block Kit）、飞书（交互卡片）、钉钉（Markdown）
+//!
+//! 卡片包含级别、来源、job、节点、根因和建议操作。飞书和钉钉机器人开启"加签"时在配置中填写 `secret`：
+//! 飞书在请求体中附 timestamp 和 sign，钉钉在 URL 中附 timestamp 和 sign。
+
+use super::{Alert, AlertSeverity};
+use ark_core::digest::hmac_sha256;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Webhook 类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelKind {
+    /// 任意 HTTP 接收端（Alert JSON 或模板）
+    #[default]
+    Generic,
+    Slack,
+    Feishu,
+    Dingtalk,
+}
+
+impl ChannelKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelKind::Generic => "generic",
+            ChannelKind::Slack => "slack",
+            ChannelKind::Feishu => "feishu",
+            ChannelKind::Dingtalk => "dingtalk",
+        }
+    }
+}
+
+/// 发给渠道的请求：地址（钉钉加签时附带签名参数）和请求体
+pub fn request(kind: ChannelKind, url: &str, secret: Option<&str>, alert: &Alert) -> Result<(String, Value), String> {
+    match kind {
+        ChannelKind::Generic => Ok((url.to_string(), json!(alert))),
+        ChannelKind::Slack => Ok((url.to_string(), slack(alert))),
+        ChannelKind::Feishu => {
+            let mut body = feishu(alert);
+            if let Some(secret) = secret {
+                let timestamp = now_secs().to_string();
+                // 飞书：以 "timestamp\nsecret" 为密钥对空消息做 HMAC-SHA256
+                let sign = STANDARD.encode(hmac_sha256(format!("{}\n{}", timestamp, secret).as_bytes(), b""));
+                body["timestamp"] = json!(timestamp);
+                body["sign"] = json!(sign);
+            }
+            Ok((url.to_string(), body))
+        }
+        ChannelKind::Dingtalk => {
+            let url = match secret {
+                Some(secret) => {
+                    let timestamp = (now_secs() * 1000).to_string();
+                    // 钉钉：以 secret 为密钥对 "timestamp\nsecret" 做 HMAC-SHA256
+                    let sign = STANDARD.encode(hmac_sha256(secret.as_bytes(), format!("{}\n{}", timestamp, secret).as_bytes()));
+                    let mut url = reqwest::Url::parse(url).map_err(|e| format!("无效的钉钉 Webhook 地址: {}", e))?;
+                    url.query_pairs_mut().append_pair("timestamp", &timestamp).append_pair("sign", &sign);
+                    url.to_string()
+                }
+                None => url.to_string(),
+            };
+            Ok((url, dingtalk(alert)))
+        }
+    }
+}
+
+/// 检查渠道的响应：飞书和钉钉出错时也返回 HTTP 200，错误码在响应体中
+pub fn check_response(kind: ChannelKind, body: &str) -> Result<(), String> {
+    let field = match kind {
+        ChannelKind::Feishu => "code",
+        ChannelKind::Dingtalk => "errcode",
+        ChannelKind::Generic | ChannelKind::Slack => return Ok(()),
+    };
+    let response: Value = serde_json::from_str(body).unwrap_or_default();
+    match response.get(field).and_then(Value::as_i64) {
+        Some(0) | None => Ok(()),
+        Some(code) => {
+            let message = response
+                .get("msg")
+                .or_else(|| response.get("errmsg"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            Err(format!("{} 返回错误 {}: {}", kind.as_str(), code, message))
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn severity_icon(severity: AlertSeverity) -> &'static str {
+    match severity {
+        AlertSeverity::Critical => "🔴",
+        AlertSeverity::Warning => "🟠",
+        AlertSeverity::Info => "🔵",
+    }
+}
+
+/// 卡片中的字段（名称，值），没有值的不显示
+fn facts(alert: &Alert) -> Vec<(&'static str, String)> {
+    let mut facts = vec![
+        ("级别", alert.severity.as_str().to_string()),
+        ("来源", alert.source.as_str().to_string()),
+    ];
+    if let Some(ref job_id) = alert.job_id {
+        facts.push(("Job", job_id.clone()));
+    }
+    if let Some(ref node_id) = alert.node_id {
+        facts.push(("节点", node_id.clone()));
+    }
+    facts
+}
+
+fn slack(alert: &Alert) -> Value {
+    let title = format!("{} {}", severity_icon(alert.severity), alert.title);
+    let fields: Vec<Value> = facts(alert)
+        .into_iter()
+        .map(|(name, value)| json!({ "type": "mrkdwn", "text": format!("*{}*\n{}", name, value) }))
+        .collect();
+    let mut blocks = vec![
+        json!({ "type": "header", "text": { "type": "plain_text", "text": title } }),
+        json!({ "type": "section", "fields": fields }),
+        json!({ "type": "section", "text": { "type": "mrkdwn", "text": format!("*根因*\n{}", alert.message) } }),
+    ];
+    if !alert.actions.is_empty() {
+        let actions: Vec<String> = alert.actions.iter().map(|action| format!("• {}", action)).collect();
+        blocks.push(json!({ "type": "section", "text": { "type": "mrkdwn", "text": format!("*建议操作*\n{}", actions.join("\n")) } }));
+    }
+    blocks.push(json!({ "type": "context", "elements": [{ "type": "mrkdwn", "text": format!("{} · ark-hub", alert.id) }] }));
+    // text 用于通知预览
+    json!({ "text": title, "blocks": blocks })
+}
+
+fn feishu(alert: &Alert) -> Value {
+    let template = match alert.severity {
+        AlertSeverity::Critical => "red",
+        AlertSeverity::Warning => "orange",
+        AlertSeverity::Info => "blue",
+    };
+    let fields: Vec<Value> = facts(alert)
+        .into_iter()
+        .map(|(name, value)| json!({ "is_short": true, "text": { "tag": "lark_md", "content": format!("**{}**\n{}", name, value) } }))
+        .collect();
+    let mut elements = vec![
+        json!({ "tag": "div", "fields": fields }),
+        json!({ "tag": "div", "text": { "tag": "lark_md", "content": format!("**根因**\n{}", alert.message) } }),
+    ];
+    if !alert.actions.is_empty() {
+        let actions: Vec<String> = alert.actions.iter().map(|action| format!("- {}", action)).collect();
+        elements.push(json!({ "tag": "div", "text": { "tag": "lark_md", "content": format!("**建议操作**\n{}", actions.join("\n")) } }));
+    }
+    elements.push(json!({ "tag": "note", "elements": [{ "tag": "plain_text", "content": format!("{} · ark-hub", alert.id) }] }));
+    json!({
+        "msg_type": "interactive",
+        "card": {
+            "config": { "wide_screen_mode": true },
+            "header": { "title": { "tag": "plain_text", "content": alert.title }, "template": template },
+            "elements": elements
+        }
+    })
+}
+
+fn dingtalk(alert: &Alert) -> Value {
+    let mut text = format!("### {} {}\n\n", severity_icon(alert.severity), alert.title);
+    for (name, value) in facts(alert) {
+        text.push_str(&format!("- **{}**: {}\n", name, value));
+    }
+    text.push_str(&format!("\n**根因**: {}\n", alert.message));
+    if !alert.actions.is_empty() {
+        text.push_str("\n**建议操作**:\n\n");
+        for (i, action) in alert.actions.iter().enumerate() {
+            text.push_str(&format!("{}. {}\n", i + 1, action));
+        }
+    }
+    text.push_str(&format!("\n> {} · ark-hub", alert.id));
+    json!({
+        "msgtype": "markdown",
+        "markdown": { "title": alert.title, "text": text }
+    })
+}
@@ -0,0 +1,543 @@
+//! 告警：把规则命中、跨节点场景和不可逆故障转为 Alert，推送到配置的 Webhook
+//!
+//! `ark-hub --alert-config <FILE>`（YAML）：
+//! ```yaml
+//! webhooks:
+//!   - name: oncall
+//!     url: https://hooks.example.com/ark
+//!     min_severity: critical        # 只发送该级别及以上（info / warning / critical，默认 warning）
+//!     sources: [fault, scene]       # 只发送这些来源（rule / scene / fault，默认全部）
+//!     headers:
+//!       Authorization: Bearer xxx
+//!     template: '{"text": "[{{severity}}] {{title}}: {{message}}"}'
+//!   - name: gpu-oncall
+//!     kind: feishu                  # generic（默认）/ slack / feishu / dingtalk，后三者发送卡片消息（见 `channels`）
+//!     url: https://open.feishu.cn/open-apis/bot/v2/hook/xxx
+//!     secret: xxx                   # 飞书 / 钉钉机器人的加签密钥
+//!     severities: [critical]        # 只发送这些级别（指定后代替 min_severity）
+//! dedup_secs: 300          # 相同指纹的告警在该时间内再次出现时合并计数、不再通知，超过该时间未出现视为恢复
+//! max_per_minute: 30       # 每个 Webhook 每分钟最多发送的告警数，超出的丢弃
+//! retries: 3               # 网络错误或 5xx 时的重试次数（指数退避）
+//! scene_interval_secs: 60  # 对运行中的 job 做跨节点场景分析的间隔
+//! ```
+//!
+//! generic 类型未指定 template 时发送 Alert 的 JSON；模板中的 `{{field}}` 替换为告警字段
+//! （id、source、severity、title、message、actions、node_id、job_id、ts），值按 JSON 字符串转义。
+//!
+//! 告警按指纹（来源 + job + 节点 + 场景，场景为规则名、场景名或故障类型）合并为活动告警并累计出现次数，
+//! 只有新出现的活动告警会通知（`GET /api/v1/alerts` 查询）；静默见 `silences`。
+
+mod channels;
+mod silences;
+
+pub use channels::ChannelKind;
+pub use silences::{SilenceRequest, SilenceStore};
+
+use crate::k8s_controller::IrreversibleFault;
+use crate::scene::{ClusterAnalysis, Severity};
+use ark_core::event::Event;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 单次 Webhook 请求的超时
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 告警来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSource {
+    /// Agent 上报的规则命中（rule.matched 事件）
+    Rule,
+    /// 跨节点场景
+    Scene,
+    /// 不可逆硬件故障
+    Fault,
+}
+
+impl AlertSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertSource::Rule => "rule",
+            AlertSource::Scene => "scene",
+            AlertSource::Fault => "fault",
+        }
+    }
+}
+
+/// 告警级别（由低到高排列）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Info,
+    #[default]
+    Warning,
+    Critical,
+}
+
+impl AlertSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertSeverity::Info => "info",
+            AlertSeverity::Warning => "warning",
+            AlertSeverity::Critical => "critical",
+        }
+    }
+}
+
+/// 一条告警
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub id: String,
+    pub source: AlertSource,
+    pub severity: AlertSeverity,
+    pub title: String,
+    /// 根因
+    pub message: String,
+    /// 建议操作
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    /// 场景涉及的节点（按节点静默时也匹配这些节点）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<String>,
+    /// 规则名、场景名或故障类型
+    pub scene: String,
+    /// 产生时间（毫秒）
+    pub ts: u64,
+}
+
+/// 合并后的活动告警
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveAlert {
+    pub fingerprint: String,
+    /// 最近一次出现的告警
+    pub alert: Alert,
+    /// 出现次数
+    pub count: u64,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    /// 抑制通知的静默 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub silenced_by: Option<String>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn new_id(prefix: &str) -> String {
+    use rand::Rng;
+    let id: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+    format!("{}-{}", prefix, id)
+}
+
+impl Alert {
+    fn new(source: AlertSource, severity: AlertSeverity, scene: String, title: String, message: String) -> Self {
+        Self {
+            id: new_id("alert"),
+            source,
+            severity,
+            title,
+            message,
+            actions: Vec::new(),
+            node_id: None,
+            job_id: None,
+            nodes: Vec::new(),
+            scene,
+            ts: now_ms(),
+        }
+    }
+
+    /// 指纹：同一 job、节点上的同一场景重复出现时相同
+    pub fn fingerprint(&self) -> String {
+        format!(
+            "{}/{}/{}/{}",
+            self.source.as_str(),
+            self.job_id.as_deref().unwrap_or("-"),
+            self.node_id.as_deref().unwrap_or("-"),
+            self.scene
+        )
+    }
+
+    /// rule.matched 事件（entity_id 为规则名，value 为命中的实体）
+    pub fn rule_match(event: &Event) -> Self {
+        let node = event.node_id.as_deref().unwrap_or("-");
+        let mut alert = Self::new(
+            AlertSource::Rule,
+            AlertSeverity::Warning,
+            event.entity_id.clone(),
+            format!("规则命中: {}", event.entity_id),
+            format!("节点 {} 上规则 {} 命中: {}", node, event.entity_id, event.value),
+        );
+        alert.node_id = event.node_id.clone();
+        alert.job_id = event.job_id.clone();
+        alert
+    }
+
+    /// job 的跨节点场景
+    pub fn scene(job_id: &str, analysis: &ClusterAnalysis) -> Self {
+        let severity = match analysis.severity {
+            Severity::Critical => AlertSeverity::Critical,
+            Severity::Warning => AlertSeverity::Warning,
+        };
+        let scene = analysis.scene.as_str();
+        let mut alert = Self::new(
+            AlertSource::Scene,
+            severity,
+            scene.to_string(),
+            format!("job {} 跨节点场景: {}", job_id, scene),
+            analysis.root_causes.join("; "),
+        );
+        alert.actions = analysis.recommendations.clone();
+        alert.job_id = Some(job_id.to_string());
+        alert.nodes = analysis.nodes.clone();
+        // 只涉及一个节点时按节点合并
+        if let [node_id] = analysis.nodes.as_slice() {
+            alert.node_id = Some(node_id.clone());
+        }
+        alert
+    }
+
+    /// 不可逆硬件故障
+    pub fn fault(fault: &IrreversibleFault) -> Self {
+        let message = fault.describe();
+        let mut alert = Self::new(
+            AlertSource::Fault,
+            AlertSeverity::Critical,
+            fault.kind().to_string(),
+            format!("不可逆硬件故障: {}", fault.node_id()),
+            message,
+        );
+        alert.actions = vec![
+            "隔离该节点，避免新任务调度上去（--enable-k8s-controller 时自动打污点并驱逐 Pod）".to_string(),
+            "将受影响的 job 从最近的 Checkpoint 迁移到健康节点重启".to_string(),
+            "联系硬件运维更换或复位故障部件".to_string(),
+        ];
+        alert.node_id = Some(fault.node_id().to_string());
+        alert
+    }
+
+    /// 故障节点已恢复，K8s 控制器解除了污点
+    pub fn recovered(node_id: &str, message: String) -> Self {
+        let mut alert = Self::new(
+            AlertSource::Fault,
+            AlertSeverity::Info,
+            "recovered".to_string(),
+            format!("节点已恢复: {}", node_id),
+            message,
+        );
+        alert.node_id = Some(node_id.to_string());
+        alert
+    }
+
+    /// 模板中可引用的字段
+    fn field(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "id" => self.id.clone(),
+            "source" => self.source.as_str().to_string(),
+            "severity" => self.severity.as_str().to_string(),
+            "title" => self.title.clone(),
+            "message" => self.message.clone(),
+            "actions" => self.actions.join("; "),
+            "node_id" => self.node_id.clone().unwrap_or_default(),
+            "job_id" => self.job_id.clone().unwrap_or_default(),
+            "ts" => self.ts.to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// 按模板渲染告警（未知字段原样保留）
+pub fn render(template: &str, alert: &Alert) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            output.push_str(&rest[start..]);
+            return output;
+        };
+        match alert.field(after[..end].trim()) {
+            Some(value) => {
+                // 按 JSON 字符串转义，去掉两侧的引号
+                let escaped = serde_json::to_string(&value).unwrap_or_default();
+                output.push_str(&escaped[1..escaped.len() - 1]);
+            }
+            None => output.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// 一个 Webhook
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub kind: ChannelKind,
+    pub url: String,
+    /// 飞书 / 钉钉机器人的加签密钥
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub min_severity: AlertSeverity,
+    /// 只发送这些级别的告警（指定后代替 min_severity）
+    #[serde(default)]
+    pub severities: Vec<AlertSeverity>,
+    /// 只发送这些来源的告警（为空时全部发送）
+    #[serde(default)]
+    pub sources: Vec<AlertSource>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 请求体模板（generic 类型，未指定时发送 Alert 的 JSON）
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default = "default_content_type")]
+    pub content_type: String,
+}
+
+fn default_content_type() -> String {
+    "application/json".to_string()
+}
+
+impl WebhookConfig {
+    fn accepts(&self, alert: &Alert) -> bool {
+        let severity = match self.severities.is_empty() {
+            true => alert.severity >= self.min_severity,
+            false => self.severities.contains(&alert.severity),
+        };
+        severity && (self.sources.is_empty() || self.sources.contains(&alert.source))
+    }
+}
+
+/// 告警配置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    pub webhooks: Vec<WebhookConfig>,
+    pub dedup_secs: u64,
+    pub max_per_minute: u32,
+    pub retries: u32,
+    pub scene_interval_secs: u64,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            webhooks: Vec::new(),
+            dedup_secs: 300,
+            max_per_minute: 30,
+            retries: 3,
+            scene_interval_secs: 60,
+        }
+    }
+}
+
+impl AlertConfig {
+    pub fn load_from_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+        let config: Self = serde_yaml::from_str(&content).map_err(|e| format!("解析 {} 失败: {}", path.display(), e))?;
+        for webhook in &config.webhooks {
+            if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+                return Err(format!("{} 中的无效 Webhook 地址: {}", path.display(), webhook.url));
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// 告警分发器
+pub struct Alerter {
+    config: AlertConfig,
+    client: reqwest::Client,
+    /// 指纹 → 活动告警
+    active: Mutex<HashMap<String, ActiveAlert>>,
+    silences: SilenceStore,
+    /// 每个 Webhook 当前一分钟窗口的开始时间和已发送数
+    windows: Mutex<Vec<(Instant, u32)>>,
+}
+
+impl Alerter {
+    pub fn new(config: AlertConfig) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let windows = vec![(Instant::now(), 0); config.webhooks.len()];
+        Ok(Self {
+            config,
+            client,
+            active: Mutex::new(HashMap::new()),
+            silences: SilenceStore::new(),
+            windows: Mutex::new(windows),
+        })
+    }
+
+    /// 未配置 Webhook 时不发送任何告警
+    pub fn enabled(&self) -> bool {
+        !self.config.webhooks.is_empty()
+    }
+
+    pub fn webhook_count(&self) -> usize {
+        self.config.webhooks.len()
+    }
+
+    /// 跨节点场景分析的间隔
+    pub fn scene_interval(&self) -> Duration {
+        Duration::from_secs(self.config.scene_interval_secs.max(1))
+    }
+
+    pub fn silences(&self) -> &SilenceStore {
+        &self.silences
+    }
+
+    /// 活动告警（按最近出现时间倒序）
+    pub fn active(&self) -> Vec<ActiveAlert> {
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        self.expire(&mut active);
+        let mut alerts: Vec<ActiveAlert> = active.values().cloned().collect();
+        alerts.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+        alerts
+    }
+
+    /// 记录告警：合并到活动告警，新出现且未被静默的在后台任务中限流投递（不阻塞调用方）
+    pub fn fire(self: &Arc<Self>, alert: Alert) {
+        let silenced_by = self.silences.matching(&alert);
+        if !self.record(&alert, silenced_by.clone()) {
+            return;
+        }
+        if let Some(silence) = silenced_by {
+            println!("🔕 [alerts] {} 已静默（{}）: {}", alert.id, silence, alert.title);
+            return;
+        }
+        println!("🔔 [alerts] {} [{}] {}: {}", alert.id, alert.severity.as_str(), alert.title, alert.message);
+        if !self.enabled() {
+            return;
+        }
+        for (index, webhook) in self.config.webhooks.iter().enumerate() {
+            if !webhook.accepts(&alert) {
+                continue;
+            }
+            if !self.take_quota(index) {
+                eprintln!("[alerts] Webhook {} 超过每分钟 {} 条的限制，丢弃告警 {}", webhook.name, self.config.max_per_minute, alert.id);
+                continue;
+            }
+            let request = match (webhook.kind, &webhook.template) {
+                (ChannelKind::Generic, Some(template)) => Ok((webhook.url.clone(), render(template, &alert))),
+                (kind, _) => channels::request(kind, &webhook.url, webhook.secret.as_deref(), &alert)
+                    .map(|(url, body)| (url, body.to_string())),
+            };
+            let (url, body) = match request {
+                Ok(request) => request,
+                Err(e) => {
+                    eprintln!("[alerts] 构建发往 Webhook {} 的告警 {} 失败: {}", webhook.name, alert.id, e);
+                    continue;
+                }
+            };
+            let alerter = Arc::clone(self);
+            let alert_id = alert.id.clone();
+            tokio::spawn(async move {
+                let webhook = &alerter.config.webhooks[index];
+                if let Err(e) = alerter.deliver(webhook, &url, body).await {
+                    eprintln!("[alerts] 告警 {} 发送到 Webhook {} 失败: {}", alert_id, webhook.name, e);
+                }
+            });
+        }
+    }
+
+    /// 合并到活动告警，返回是否为新出现的告警
+    fn record(&self, alert: &Alert, silenced_by: Option<String>) -> bool {
+        let fingerprint = alert.fingerprint();
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        self.expire(&mut active);
+        if let Some(existing) = active.get_mut(&fingerprint) {
+            existing.alert = alert.clone();
+            existing.count += 1;
+            existing.last_seen = alert.ts;
+            existing.silenced_by = silenced_by;
+            return false;
+        }
+        active.insert(
+            fingerprint.clone(),
+            ActiveAlert {
+                fingerprint,
+                alert: alert.clone(),
+                count: 1,
+                first_seen: alert.ts,
+                last_seen: alert.ts,
+                silenced_by,
+            },
+        );
+        true
+    }
+
+    /// 超过 dedup_secs 未再出现的告警视为恢复
+    fn expire(&self, active: &mut HashMap<String, ActiveAlert>) {
+        let cutoff = now_ms().saturating_sub(self.config.dedup_secs * 1000);
+        active.retain(|_, alert| alert.last_seen >= cutoff);
+    }
+
+    /// 占用 Webhook 当前一分钟窗口的一个名额
+    fn take_quota(&self, index: usize) -> bool {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        let (start, count) = &mut windows[index];
+        if start.elapsed() >= Duration::from_secs(60) {
+            *start = Instant::now();
+            *count = 0;
+        }
+        if *count >= self.config.max_per_minute {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+
+    /// 投递到一个 Webhook；网络错误和 5xx 按指数退避重试，4xx 和渠道返回的错误码不重试
+    async fn deliver(&self, webhook: &WebhookConfig, url: &str, body: String) -> Result<(), String> {
+        let mut attempt = 0;
+        loop {
+            let mut request = self
+                .client
+                .post(url)
+                .header("content-type", &webhook.content_type)
+                .body(body.clone());
+            for (name, value) in &webhook.headers {
+                request = request.header(name, value);
+            }
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    let text = response.text().await.unwrap_or_default();
+                    return channels::check_response(webhook.kind, &text);
+                }
+                Ok(response) if response.status().is_client_error() => {
+                    return Err(format!("HTTP {}", response.status()));
+                }
+                Ok(response) => format!("HTTP {}", response.status()),
+                Err(e) => e.to_string(),
+            };
+            if attempt >= self.config.retries {
+                return Err(format!("{}（已重试 {} 次）", error, attempt));
+            }
+            tokio::time::sleep(Duration::from_secs(1 << attempt.min(6))).await;
+            attempt += 1;
+        }
+    }
+}
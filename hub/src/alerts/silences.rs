@@ -0,0 +1,116 @@
+//! 告警静默：维护窗口内不通知指定节点或 job 的告警
+//!
+//! `POST /api/v1/silences` 创建（`{"node_id", "job_id", "duration_secs", "reason", "created_by"}`，node_id 和 job_id 至少指定一个，
+//! 同时指定时两者都匹配才静默）。被静默的告警仍计入活动告警（`silenced_by` 为静默 ID），只是不发送到 Webhook。
+//! 静默只保存在内存中，到期后自动失效，Hub 重启后需重新创建。
+
+use super::{new_id, now_ms, Alert};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// 单个静默的最长时间（7 天）
+pub const MAX_SILENCE_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// 创建静默的请求
+#[derive(Debug, Deserialize)]
+pub struct SilenceRequest {
+    #[serde(default)]
+    pub node_id: Option<String>,
+    #[serde(default)]
+    pub job_id: Option<String>,
+    pub duration_secs: u64,
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default)]
+    pub created_by: Option<String>,
+}
+
+/// 一个静默
+#[derive(Debug, Clone, Serialize)]
+pub struct Silence {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    /// 开始和结束时间（毫秒）
+    pub starts_at: u64,
+    pub ends_at: u64,
+}
+
+impl Silence {
+    /// 告警的节点（含场景涉及的节点）和 job 是否都匹配
+    fn matches(&self, alert: &Alert) -> bool {
+        let node = match self.node_id {
+            Some(ref node_id) => alert.node_id.as_ref() == Some(node_id) || alert.nodes.contains(node_id),
+            None => true,
+        };
+        let job = match self.job_id {
+            Some(ref job_id) => alert.job_id.as_ref() == Some(job_id),
+            None => true,
+        };
+        node && job
+    }
+}
+
+/// 静默存储
+#[derive(Default)]
+pub struct SilenceStore {
+    silences: Mutex<Vec<Silence>>,
+}
+
+impl SilenceStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&self, req: SilenceRequest) -> Result<Silence, String> {
+        let node_id = req.node_id.filter(|id| !id.is_empty());
+        let job_id = req.job_id.filter(|id| !id.is_empty());
+        if node_id.is_none() && job_id.is_none() {
+            return Err("node_id 和 job_id 至少指定一个".to_string());
+        }
+        if req.duration_secs == 0 || req.duration_secs > MAX_SILENCE_SECS {
+            return Err(format!("duration_secs 须在 1 到 {} 之间", MAX_SILENCE_SECS));
+        }
+        let starts_at = now_ms();
+        let silence = Silence {
+            id: new_id("silence"),
+            node_id,
+            job_id,
+            reason: req.reason,
+            created_by: req.created_by,
+            starts_at,
+            ends_at: starts_at + req.duration_secs * 1000,
+        };
+        let mut silences = self.silences.lock().unwrap_or_else(|e| e.into_inner());
+        silences.push(silence.clone());
+        Ok(silence)
+    }
+
+    /// 未到期的静默
+    pub fn list(&self) -> Vec<Silence> {
+        let mut silences = self.silences.lock().unwrap_or_else(|e| e.into_inner());
+        let now = now_ms();
+        silences.retain(|silence| silence.ends_at > now);
+        silences.clone()
+    }
+
+    /// 提前结束静默
+    pub fn remove(&self, id: &str) -> Option<Silence> {
+        let mut silences = self.silences.lock().unwrap_or_else(|e| e.into_inner());
+        let index = silences.iter().position(|silence| silence.id == id)?;
+        Some(silences.remove(index))
+    }
+
+    /// 匹配告警的静默 ID
+    pub fn matching(&self, alert: &Alert) -> Option<String> {
+        self.list()
+            .into_iter()
+            .find(|silence| silence.matches(alert))
+            .map(|silence| silence.id)
+    }
+}
@@ -0,0 +1,252 @@
+//! 破窗审批：高危操作的双人审批 token
+//!
+//! 第二位运维通过 `POST /api/v1/approvals` 为某个操作签发短时有效的 token，
+//! Agent 执行 zap / 节点隔离 / 大范围集群修复前通过 `POST /api/v1/approvals/verify` 校验。
+//!
+//! 审批人取自签发请求的认证身份，token 只能由 requested_by 指定的发起人使用（Hub 下发修复时
+//! 发起人同样取自认证身份），因此同一个身份无法为自己签发并使用审批。
+//!
+//! 破坏性的修复动作（终止进程、重置 GPU、重启网卡等）无论是否为集群修复都必须携带审批 token。
+
+use dashmap::DashMap;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 默认有效期（秒）
+pub const DEFAULT_APPROVAL_TTL_SECS: u64 = 600;
+/// 有效期上限（秒），避免签发长期有效的 token
+pub const MAX_APPROVAL_TTL_SECS: u64 = 3600;
+/// 默认审批阈值：集群修复涉及的进程数超过该值时必须携带审批 token
+pub const DEFAULT_APPROVAL_THRESHOLD: usize = 4;
+
+/// 不终止进程的修复动作（Agent 解析为 SIGUSR1 或 cgroup 限流）
+const NON_DESTRUCTIVE_ACTIONS: &[&str] = &["signal", "sigusr1", "throttle", "cgroupthrottle", "cgroup_throttle"];
+
+/// 修复动作是否为破坏性操作
+///
+/// Hub 只拿到动作字符串，按保守原则判断：除明确不终止进程的动作外都视为破坏性，
+/// Agent 执行前还会按解析出的动作再次判断
+pub fn is_destructive_action(action: &str) -> bool {
+    !NON_DESTRUCTIVE_ACTIONS.contains(&action.to_lowercase().as_str())
+}
+
+/// 审批记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Approval {
+    pub token: String,
+    /// 被批准的操作：zap / fix / isolate / cluster_fix
+    pub action: String,
+    /// 操作目标：如 "node-a::pid-1234"、"node-a"、"job-xxx"
+    pub target: String,
+    pub requested_by: String,
+    pub approved_by: String,
+    pub reason: Option<String>,
+    pub issued_at: u64,  // 毫秒时间戳
+    pub expires_at: u64, // 毫秒时间戳
+}
+
+/// 签发请求
+#[derive(Debug, Deserialize)]
+pub struct ApprovalRequest {
+    pub action: String,
+    pub target: String,
+    /// 将使用该 token 发起操作的身份（API 密钥的 name 或 OIDC 的 sub）
+    pub requested_by: String,
+    pub reason: Option<String>,
+    pub ttl_secs: Option<u64>,
+}
+
+/// 校验请求
+#[derive(Debug, Deserialize)]
+pub struct VerifyRequest {
+    pub token: String,
+    pub action: String,
+    pub target: String,
+    /// 操作发起人（Hub 下发的修复命令携带）；指定时须与审批的 requested_by 一致
+    #[serde(default)]
+    pub requested_by: Option<String>,
+}
+
+/// 审批存储（内存，Hub 重启后所有 token 失效）
+pub struct ApprovalStore {
+    approvals: DashMap<String, Approval>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+impl ApprovalStore {
+    pub fn new() -> Self {
+        Self {
+            approvals: DashMap::new(),
+        }
+    }
+
+    /// 签发审批 token：approved_by 为签发请求的认证身份，必须与申请人不同
+    pub fn issue(&self, req: ApprovalRequest, approved_by: &str) -> Result<Approval, String> {
+        let requested_by = req.requested_by.trim();
+        if requested_by.is_empty() {
+            return Err("requested_by 不能为空".to_string());
+        }
+        if requested_by.eq_ignore_ascii_case(approved_by) {
+            return Err(format!("双人审批要求审批人与申请人不同（{} 不能批准自己的操作）", approved_by));
+        }
+        if req.action.is_empty() || req.target.is_empty() {
+            return Err("action 和 target 不能为空".to_string());
+        }
+
+        let ttl_secs = req.ttl_secs.unwrap_or(DEFAULT_APPROVAL_TTL_SECS);
+        if ttl_secs == 0 || ttl_secs > MAX_APPROVAL_TTL_SECS {
+            return Err(format!("ttl_secs 必须在 1 ~ {} 之间", MAX_APPROVAL_TTL_SECS));
+        }
+
+        self.purge_expired();
+
+        let token: String = rand::thread_rng()
+            .sample_iter(&rand::distributions::Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+        let issued_at = now_ms();
+        let approval = Approval {
+            token: token.clone(),
+            action: req.action,
+            target: req.target,
+            requested_by: requested_by.to_string(),
+            approved_by: approved_by.to_string(),
+            reason: req.reason,
+            issued_at,
+            expires_at: issued_at + ttl_secs * 1000,
+        };
+        self.approvals.insert(token, approval.clone());
+
+        Ok(approval)
+    }
+
+    /// 校验 token 是否在有效期内，且与操作和目标匹配；指定发起人时须是审批的申请人
+    /// 有效期内可重复使用（集群修复会对多个进程逐一下发）
+    pub fn verify(&self, token: &str, action: &str, target: &str, requested_by: Option<&str>) -> Result<Approval, String> {
+        let approval = self
+            .approvals
+            .get(token)
+            .map(|a| a.clone())
+            .ok_or_else(|| "审批 token 不存在或已失效".to_string())?;
+
+        if now_ms() >= approval.expires_at {
+            self.approvals.remove(token);
+            return Err("审批 token 已过期".to_string());
+        }
+        if approval.action != action || approval.target != target {
+            return Err(format!(
+                "审批 token 仅适用于 {} {}，不能用于 {} {}",
+                approval.action, approval.target, action, target
+            ));
+        }
+        if let Some(requested_by) = requested_by {
+            if !approval.requested_by.eq_ignore_ascii_case(requested_by) {
+                return Err(format!(
+                    "审批 token 是为 {} 签发的，不能由 {} 使用",
+                    approval.requested_by, requested_by
+                ));
+            }
+        }
+
+        Ok(approval)
+    }
+
+    /// 清理过期的审批
+    pub fn purge_expired(&self) {
+        let now = now_ms();
+        self.approvals.retain(|_, a| a.expires_at > now);
+    }
+}
+
+impl Default for ApprovalStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(action: &str, target: &str, requested_by: &str) -> ApprovalRequest {
+        ApprovalRequest {
+            action: action.to_string(),
+            target: target.to_string(),
+            requested_by: requested_by.to_string(),
+            reason: None,
+            ttl_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_issue_rejects_self_approval() {
+        let store = ApprovalStore::new();
+        assert!(store.issue(request("zap", "node-a::pid-1", "alice"), "Alice").is_err());
+        assert!(store.issue(request("zap", "node-a::pid-1", "alice"), "bob").is_ok());
+    }
+
+    #[test]
+    fn test_verify_binds_action_target_and_requester() {
+        let store = ApprovalStore::new();
+        let token = store.issue(request("fix", "node-a::pid-1", "alice"), "bob").unwrap().token;
+
+        assert!(store.verify(&token, "fix", "node-a::pid-1", Some("alice")).is_ok());
+        assert!(store.verify(&token, "zap", "node-a::pid-1", Some("alice")).is_err());
+        assert!(store.verify(&token, "fix", "node-a::pid-2", Some("alice")).is_err());
+        assert!(store.verify(&token, "fix", "node-b::pid-1", Some("alice")).is_err());
+        assert!(store.verify(&token, "fix", "node-a::pid-1", Some("mallory")).is_err());
+        assert!(store.verify("unknown", "fix", "node-a::pid-1", Some("alice")).is_err());
+    }
+
+    #[test]
+    fn test_token_reuse_stays_bound_to_its_scope() {
+        let store = ApprovalStore::new();
+        let token = store.issue(request("cluster_fix", "job-42", "alice"), "bob").unwrap().token;
+
+        // 有效期内可对同一 job 重复使用（集群修复逐个进程下发）
+        for _ in 0..3 {
+            assert!(store.verify(&token, "cluster_fix", "job-42", Some("alice")).is_ok());
+        }
+        // 用过之后同样不能挪作他用
+        assert!(store.verify(&token, "cluster_fix", "job-43", Some("alice")).is_err());
+        assert!(store.verify(&token, "cluster_fix", "job-42", Some("bob")).is_err());
+    }
+
+    #[test]
+    fn test_expired_token_is_rejected_and_removed() {
+        let store = ApprovalStore::new();
+        let token = store.issue(request("zap", "node-a::pid-1", "alice"), "bob").unwrap().token;
+        store.approvals.get_mut(&token).unwrap().expires_at = now_ms() - 1;
+
+        assert_eq!(store.verify(&token, "zap", "node-a::pid-1", None).unwrap_err(), "审批 token 已过期");
+        assert_eq!(
+            store.verify(&token, "zap", "node-a::pid-1", None).unwrap_err(),
+            "审批 token 不存在或已失效"
+        );
+    }
+
+    #[test]
+    fn test_ttl_is_bounded() {
+        let store = ApprovalStore::new();
+        let mut req = request("zap", "node-a::pid-1", "alice");
+        req.ttl_secs = Some(MAX_APPROVAL_TTL_SECS + 1);
+        assert!(store.issue(req, "bob").is_err());
+    }
+
+    #[test]
+    fn test_destructive_actions() {
+        assert!(is_destructive_action("GracefulShutdown"));
+        assert!(is_destructive_action("kill"));
+        assert!(is_destructive_action("gpu-reset 0"));
+        assert!(!is_destructive_action("SIGUSR1"));
+        assert!(!is_destructive_action("throttle"));
+    }
+}
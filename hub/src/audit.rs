@@ -0,0 +1,158 @@
+//! 集中审计：Agent 通过 WebSocket 镜像上报的审计记录
+//!
+//! Agent 每写一条本地审计日志，就通过与事件相同的连接上报 `{"node_id": ..., "audit": {...}}`。
+//! Hub 按节点保存，并通过 `GET /api/v1/audit?job_id=...` 查询"谁在什么时候对 job X 做了什么"。
+//! 本地审计日志仍是权威记录（带哈希链），Hub 侧是便于集中查询的副本：连接断开期间的记录不会补报。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 内存中保留的审计记录数上限（超出时丢弃最旧的；配置了 `--audit-log` 时完整记录保存在文件中）
+pub const MAX_AUDIT_RECORDS: usize = 100_000;
+
+/// 查询默认返回的记录数
+const DEFAULT_QUERY_LIMIT: usize = 100;
+
+/// 一条审计记录：Agent 的审计日志条目加上来源节点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub node_id: String,
+    pub timestamp: String,
+    pub user: String,
+    pub action: String,
+    pub target_pid: u32,
+    #[serde(default)]
+    pub target_job_id: Option<String>,
+    pub result: String,
+    #[serde(default)]
+    pub details: String,
+    /// 其余字段原样保留（source、command_id、seq、prev_hash、hmac 等）
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl AuditRecord {
+    /// Hub 自身对节点执行的操作（如 K8s 控制器解除污点），source 为 hub
+    pub fn hub(node_id: &str, user: &str, action: &str, result: &str, details: String) -> Self {
+        let mut extra = serde_json::Map::new();
+        extra.insert("source".to_string(), serde_json::Value::String("hub".to_string()));
+        Self {
+            node_id: node_id.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            user: user.to_string(),
+            action: action.to_string(),
+            target_pid: 0,
+            target_job_id: None,
+            result: result.to_string(),
+            details,
+            extra,
+        }
+    }
+}
+
+/// Agent 上报的审计消息
+#[derive(Debug, Deserialize)]
+pub struct AuditMessage {
+    pub node_id: String,
+    pub audit: serde_json::Map<String, serde_json::Value>,
+}
+
+impl AuditMessage {
+    /// 转为审计记录（以消息的 node_id 为准）
+    pub fn into_record(mut self) -> Result<AuditRecord, String> {
+        self.audit.insert("node_id".to_string(), serde_json::Value::String(self.node_id));
+        serde_json::from_value(serde_json::Value::Object(self.audit)).map_err(|e| format!("无效的审计记录: {}", e))
+    }
+}
+
+/// 审计记录存储
+pub struct AuditStore {
+    records: Mutex<VecDeque<AuditRecord>>,
+    file: Option<(PathBuf, Mutex<File>)>,
+}
+
+impl AuditStore {
+    pub fn new() -> Self {
+        Self {
+            records: Mutex::new(VecDeque::new()),
+            file: None,
+        }
+    }
+
+    /// 持久化到 JSONL 文件：启动时载入已有记录（无法解析的行跳过），之后每条记录追加写入
+    pub fn with_file(mut self, path: &Path) -> Result<Self, String> {
+        if let Ok(content) = std::fs::read_to_string(path) {
+            let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+            for record in content.lines().filter_map(|line| serde_json::from_str::<AuditRecord>(line).ok()) {
+                if records.len() == MAX_AUDIT_RECORDS {
+                    records.pop_front();
+                }
+                records.push_back(record);
+            }
+            println!("[hub] 已载入 {} 条审计记录: {}", records.len(), path.display());
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("打开审计日志 {} 失败: {}", path.display(), e))?;
+        self.file = Some((path.to_path_buf(), Mutex::new(file)));
+        Ok(self)
+    }
+
+    /// 保存一条记录（写文件失败只打印错误，记录仍可查询）
+    pub fn append(&self, record: AuditRecord) {
+        if let Some((ref path, ref file)) = self.file {
+            let written = serde_json::to_string(&record).map_err(|e| e.to_string()).and_then(|line| {
+                let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+                writeln!(file, "{}", line).map_err(|e| e.to_string())
+            });
+            if let Err(e) = written {
+                eprintln!("[hub] 写入审计日志 {} 失败: {}", path.display(), e);
+            }
+        }
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        if records.len() == MAX_AUDIT_RECORDS {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// 按查询参数（job_id、node_id、pid、action、result、user、command_id、limit）过滤，返回最近的记录（按时间顺序）
+    pub fn query(&self, params: &HashMap<String, String>) -> Result<Vec<AuditRecord>, String> {
+        let pid = match params.get("pid") {
+            Some(pid) => Some(pid.parse::<u32>().map_err(|_| format!("无效的 pid: {}", pid))?),
+            None => None,
+        };
+        let limit = match params.get("limit") {
+            Some(limit) => limit.parse::<usize>().map_err(|_| format!("无效的 limit: {}", limit))?,
+            None => DEFAULT_QUERY_LIMIT,
+        };
+        let field = |name: &str| params.get(name).map(String::as_str);
+        let matches = |record: &AuditRecord| {
+            field("job_id").is_none_or(|job| record.target_job_id.as_deref() == Some(job))
+                && field("node_id").is_none_or(|node| record.node_id == node)
+                && pid.is_none_or(|pid| record.target_pid == pid)
+                && field("action").is_none_or(|action| record.action == action)
+                && field("result").is_none_or(|result| record.result == result)
+                && field("user").is_none_or(|user| record.user == user)
+                && field("command_id")
+                    .is_none_or(|id| record.extra.get("command_id").and_then(|v| v.as_str()) == Some(id))
+        };
+
+        let records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        let mut found: Vec<AuditRecord> = records.iter().rev().filter(|r| matches(r)).take(limit).cloned().collect();
+        found.reverse();
+        Ok(found)
+    }
+}
+
+impl Default for AuditStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,344 @@
+//! Agent 令牌、HTTP API 密钥和角色
+//!
+//! - `--agent-tokens <FILE>`：每行 `<node_id> <token>`，Agent 在 WebSocket 握手时以 `Authorization: Bearer <token>` 出示；
+//!   令牌绑定节点，注册和上报的 node_id 须与之一致（node_id 写 `*` 的令牌可用于任意节点）
+//! - `--api-keys <FILE>`：每行 `<role> <key> [name]`（只有密钥一列时为 admin），`/api/v1/*` 请求须带 `Authorization: Bearer <key>`；
+//!   name 是密钥持有人的身份，省略时取密钥指纹 `key-xxxxxxxx`
+//! - `--oidc-issuer <URL>`：Bearer 令牌也可以是该身份提供方签发的 JWT，角色取自声明、身份取自 `sub`（见 `oidc`）
+//!
+//! 角色由低到高为 viewer（ps / why / graph / nodes / jobs / audit）、operator（fix、签发审批）、
+//! admin（节点管理），高角色包含低角色的权限。Agent 调用的规则下发和审批校验接口同时接受 Agent 令牌。
+//!
+//! 修复的发起人和审批的审批人取自请求的身份（`Principal`），不信任请求体中的字段。
+//!
+//! 两个文件中 `#` 开头的行和空行忽略。未配置时不做校验（与旧版本行为一致，身份为 `anonymous`）。`/metrics` 不校验，供 Prometheus 抓取。
+
+use crate::oidc::OidcVerifier;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+use warp::http::StatusCode;
+use warp::Filter;
+
+/// API 角色（按权限由低到高排列）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Viewer,
+    Operator,
+    Admin,
+}
+
+impl Role {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "viewer" => Some(Role::Viewer),
+            "operator" => Some(Role::Operator),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+/// 未启用 API 鉴权时请求的身份
+pub const ANONYMOUS: &str = "anonymous";
+
+/// 通过鉴权的请求身份：API 密钥的 name 或 OIDC 令牌的 sub
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub name: String,
+    pub role: Role,
+}
+
+impl Principal {
+    /// 未启用 API 鉴权时无法识别身份
+    pub fn is_anonymous(&self) -> bool {
+        self.name == ANONYMOUS
+    }
+}
+
+/// Hub 的访问控制
+#[derive(Default)]
+pub struct HubAuth {
+    /// Agent 令牌 → 绑定的 node_id（None 表示任意节点）；None 表示未启用
+    agent_tokens: Option<HashMap<String, Option<String>>>,
+    /// API 密钥 → 持有人身份；None 表示未启用
+    api_keys: Option<HashMap<String, Principal>>,
+    oidc: Option<OidcVerifier>,
+}
+
+/// 请求被拒绝：401（缺少或无效的凭据）或 403（角色不足）
+#[derive(Debug)]
+pub struct Denied {
+    status: StatusCode,
+    message: String,
+}
+
+impl warp::reject::Reject for Denied {}
+
+impl Denied {
+    fn unauthorized(message: impl Into<String>) -> Self {
+        Self { status: StatusCode::UNAUTHORIZED, message: message.into() }
+    }
+
+    fn forbidden(message: impl Into<String>) -> Self {
+        Self { status: StatusCode::FORBIDDEN, message: message.into() }
+    }
+}
+
+/// 读取令牌文件中的有效行
+fn read_lines(path: &Path) -> Result<Vec<String>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// 未指定 name 的 API 密钥以指纹作为身份（不暴露密钥本身）
+fn key_fingerprint(key: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    format!("key-{:08x}", hasher.finish() as u32)
+}
+
+/// Agent 令牌绑定的节点（None 表示不限节点）是否允许以 node_id 注册
+pub fn agent_may_act_as(bound: Option<&str>, node_id: &str) -> bool {
+    bound.is_none_or(|bound| bound == node_id)
+}
+
+/// 取出 `Authorization: Bearer <token>` 中的令牌
+pub fn bearer(header: Option<&str>) -> Option<&str> {
+    header?.strip_prefix("Bearer ").map(str::trim).filter(|token| !token.is_empty())
+}
+
+impl HubAuth {
+    /// 按命令行参数加载（未指定的文件不启用对应的校验）
+    pub fn load(agent_tokens: Option<&Path>, api_keys: Option<&Path>) -> Result<Self, String> {
+        let mut auth = Self::default();
+        if let Some(path) = agent_tokens {
+            let mut tokens = HashMap::new();
+            for line in read_lines(path)? {
+                let (node_id, token) = line
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| format!("{} 中的无效行: {}（格式为 <node_id> <token>）", path.display(), line))?;
+                let node_id = (node_id != "*").then(|| node_id.to_string());
+                tokens.insert(token.trim().to_string(), node_id);
+            }
+            if tokens.is_empty() {
+                return Err(format!("{} 中没有 Agent 令牌", path.display()));
+            }
+            auth.agent_tokens = Some(tokens);
+        }
+        if let Some(path) = api_keys {
+            let mut keys = HashMap::new();
+            for line in read_lines(path)? {
+                let (role, key, name) = match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                    [key] => (Role::Admin, key.to_string(), None),
+                    [role, key, rest @ ..] if rest.len() <= 1 => {
+                        let role = Role::parse(role).ok_or_else(|| {
+                            format!("{} 中的无效角色: {}（可选 viewer、operator、admin）", path.display(), role)
+                        })?;
+                        (role, key.to_string(), rest.first().map(|name| name.to_string()))
+                    }
+                    _ => return Err(format!("{} 中的无效行: {}（格式为 <role> <key> [name]）", path.display(), line)),
+                };
+                let name = name.unwrap_or_else(|| key_fingerprint(&key));
+                keys.insert(key, Principal { name, role });
+            }
+            if keys.is_empty() {
+                return Err(format!("{} 中没有 API 密钥", path.display()));
+            }
+            auth.api_keys = Some(keys);
+        }
+        Ok(auth)
+    }
+
+    /// 同时接受 OIDC 身份提供方签发的 JWT
+    pub fn with_oidc(mut self, oidc: OidcVerifier) -> Self {
+        self.oidc = Some(oidc);
+        self
+    }
+
+    /// 校验 Agent 的 WebSocket 握手，返回令牌绑定的 node_id（未启用或令牌不限节点时为 None）
+    pub fn agent(&self, header: Option<&str>) -> Result<Option<String>, String> {
+        let Some(ref tokens) = self.agent_tokens else {
+            return Ok(None);
+        };
+        let token = bearer(header).ok_or("缺少 Agent 令牌")?;
+        tokens.get(token).cloned().ok_or_else(|| "无效的 Agent 令牌".to_string())
+    }
+
+    /// HTTP API 请求的身份（未启用 API 鉴权时为 anonymous，视为 admin）
+    async fn identify(&self, header: Option<&str>) -> Result<Principal, Denied> {
+        if !self.api_enabled() {
+            return Ok(Principal { name: ANONYMOUS.to_string(), role: Role::Admin });
+        }
+        let token = bearer(header).ok_or_else(|| Denied::unauthorized("缺少 API 密钥或令牌"))?;
+        if let Some(principal) = self.api_keys.as_ref().and_then(|keys| keys.get(token)) {
+            return Ok(principal.clone());
+        }
+        match self.oidc {
+            Some(ref oidc) => match oidc.identify(token).await {
+                Ok(Some(principal)) => Ok(principal),
+                Ok(None) => Err(Denied::forbidden("令牌中没有可识别的角色（viewer、operator、admin）")),
+                Err(e) => Err(Denied::unauthorized(e)),
+            },
+            None => Err(Denied::unauthorized("无效的 API 密钥")),
+        }
+    }
+
+    /// 校验请求是否具有指定角色，返回请求的身份
+    async fn authorize(&self, header: Option<&str>, required: Role) -> Result<Principal, Denied> {
+        let principal = self.identify(header).await?;
+        if principal.role < required {
+            return Err(Denied::forbidden(format!(
+                "需要 {} 角色（当前 {}）",
+                required.as_str(),
+                principal.role.as_str()
+            )));
+        }
+        Ok(principal)
+    }
+
+    async fn check(&self, header: Option<&str>, required: Role) -> Result<(), Denied> {
+        self.authorize(header, required).await.map(drop)
+    }
+
+    /// 校验 Agent 也会调用的接口：Agent 令牌或任一角色
+    async fn check_agent(&self, header: Option<&str>) -> Result<(), Denied> {
+        let token = bearer(header);
+        if token.is_some_and(|token| self.agent_tokens.as_ref().is_some_and(|tokens| tokens.contains_key(token))) {
+            return Ok(());
+        }
+        self.check(header, Role::Viewer).await
+    }
+
+    pub fn agents_enabled(&self) -> bool {
+        self.agent_tokens.is_some()
+    }
+
+    pub fn api_enabled(&self) -> bool {
+        self.api_keys.is_some() || self.oidc.is_some()
+    }
+}
+
+/// Warp Filter：要求指定角色，失败时以 Denied 拒绝（由 `recover_denied` 转为 401 / 403）
+pub fn require(auth: Arc<HubAuth>, required: Role) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |header: Option<String>| {
+            let auth = Arc::clone(&auth);
+            async move { auth.check(header.as_deref(), required).await.map_err(warp::reject::custom) }
+        })
+        .untuple_one()
+}
+
+/// Warp Filter：要求指定角色，并取出请求的身份（修复发起人、审批人）
+pub fn principal(
+    auth: Arc<HubAuth>,
+    required: Role,
+) -> impl Filter<Extract = (Principal,), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("authorization").and_then(move |header: Option<String>| {
+        let auth = Arc::clone(&auth);
+        async move { auth.authorize(header.as_deref(), required).await.map_err(warp::reject::custom) }
+    })
+}
+
+/// Warp Filter：Agent 令牌或任一角色
+pub fn require_agent(auth: Arc<HubAuth>) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |header: Option<String>| {
+            let auth = Arc::clone(&auth);
+            async move { auth.check_agent(header.as_deref()).await.map_err(warp::reject::custom) }
+        })
+        .untuple_one()
+}
+
+/// 把 Denied 转为 401 / 403，其余拒绝原样交给 warp 处理
+pub async fn recover_denied(rejection: warp::Rejection) -> Result<impl warp::Reply, warp::Rejection> {
+    match rejection.find::<Denied>() {
+        Some(denied) => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "error": denied.message })),
+            denied.status,
+        )),
+        None => Err(rejection),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_file(name: &str, content: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("ark-hub-{}-{}", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_agent_tokens_bind_node() {
+        let path = write_file("agent-tokens", "# 节点令牌\nnode-a tok-a\n\n* tok-any\n");
+        let auth = HubAuth::load(Some(&path), None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let bound = auth.agent(Some("Bearer tok-a")).unwrap();
+        assert_eq!(bound.as_deref(), Some("node-a"));
+        assert!(agent_may_act_as(bound.as_deref(), "node-a"));
+        assert!(!agent_may_act_as(bound.as_deref(), "node-b"));
+
+        let any = auth.agent(Some("Bearer tok-any")).unwrap();
+        assert_eq!(any, None);
+        assert!(agent_may_act_as(any.as_deref(), "node-b"));
+
+        assert!(auth.agent(Some("Bearer tok-b")).is_err());
+        assert!(auth.agent(Some("tok-a")).is_err());
+        assert!(auth.agent(None).is_err());
+    }
+
+    #[test]
+    fn test_agent_tokens_disabled_accepts_any_connection() {
+        let auth = HubAuth::load(None, None).unwrap();
+        assert_eq!(auth.agent(None).unwrap(), None);
+        assert!(!auth.agents_enabled());
+    }
+
+    #[test]
+    fn test_load_rejects_malformed_files() {
+        let path = write_file("agent-tokens-bad", "node-a\n");
+        assert!(HubAuth::load(Some(&path), None).is_err());
+        std::fs::write(&path, "# 只有注释\n").unwrap();
+        assert!(HubAuth::load(Some(&path), None).is_err());
+        std::fs::write(&path, "root key-1\n").unwrap();
+        assert!(HubAuth::load(None, Some(&path)).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_api_key_identity_and_roles() {
+        let path = write_file("api-keys", "operator key-op alice\nviewer key-view\nkey-admin\n");
+        let auth = HubAuth::load(None, Some(&path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let alice = auth.authorize(Some("Bearer key-op"), Role::Operator).await.unwrap();
+        assert_eq!(alice, Principal { name: "alice".to_string(), role: Role::Operator });
+        assert_eq!(auth.authorize(Some("Bearer key-op"), Role::Admin).await.unwrap_err().status, StatusCode::FORBIDDEN);
+
+        let viewer = auth.authorize(Some("Bearer key-view"), Role::Viewer).await.unwrap();
+        assert_eq!(viewer.name, key_fingerprint("key-view"));
+        assert_eq!(auth.authorize(Some("Bearer key-admin"), Role::Admin).await.unwrap().role, Role::Admin);
+
+        assert_eq!(auth.authorize(Some("Bearer nope"), Role::Viewer).await.unwrap_err().status, StatusCode::UNAUTHORIZED);
+        assert_eq!(auth.authorize(None, Role::Viewer).await.unwrap_err().status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,164 @@
+//! 下发命令的执行状态
+//!
+//! `POST /api/v1/fix` 下发命令时登记为 pending；Agent 执行完成后通过 WebSocket 回报
+//! `{"type": "command_result", "node_id", "command_id", "status", "message"}`（与事件一样带 seq、至少一次投递），
+//! Hub 据此更新状态，`GET /api/v1/fix/<command_id>` 查询。status 与节点审计日志的 result 一致
+//! （success、failed、rejected、denied、read_only、timeout 等）。
+//!
+//! 配置了 `--storage` 时每次更新写入 `hub_commands`，Hub 重启后仍可查询。
+
+use crate::storage::StorageHandle;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 内存中保留的命令数上限（超出时丢弃最早下发的）
+pub const MAX_COMMANDS: usize = 10_000;
+
+/// 尚未收到 Agent 回报的状态
+pub const PENDING: &str = "pending";
+
+/// 一条下发命令及其执行状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandRecord {
+    pub command_id: String,
+    pub node_id: String,
+    pub target_pid: u32,
+    pub action: String,
+    #[serde(default)]
+    pub job_id: Option<String>,
+    #[serde(default)]
+    pub requested_by: Option<String>,
+    pub status: String,
+    /// Agent 回报的执行结果或错误信息
+    #[serde(default)]
+    pub message: Option<String>,
+    /// 下发时间（毫秒）
+    pub issued_at: u64,
+    /// 收到回报的时间（毫秒）
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+}
+
+/// Agent 的回报消息（带 `type` 字段）
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentReport {
+    CommandResult(CommandResult),
+}
+
+/// 命令执行结果
+#[derive(Debug, Deserialize)]
+pub struct CommandResult {
+    pub node_id: String,
+    pub command_id: String,
+    pub status: String,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[derive(Default)]
+struct Commands {
+    records: HashMap<String, CommandRecord>,
+    /// 按下发顺序排列的 command_id（淘汰最早的）
+    order: VecDeque<String>,
+}
+
+/// 下发命令的状态存储
+#[derive(Default)]
+pub struct CommandStore {
+    commands: Mutex<Commands>,
+    storage: StorageHandle,
+}
+
+impl CommandStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 每次更新同时写入存储
+    pub fn with_storage(mut self, storage: StorageHandle) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// 载入从存储恢复的记录（按下发时间排序）
+    pub fn restore(&self, mut records: Vec<CommandRecord>) {
+        records.sort_by_key(|record| record.issued_at);
+        let mut commands = self.commands.lock().unwrap_or_else(|e| e.into_inner());
+        for record in records {
+            commands.insert(record);
+        }
+    }
+
+    /// 登记一条已下发的命令
+    pub fn issue(
+        &self,
+        command_id: &str,
+        node_id: &str,
+        target_pid: u32,
+        action: &str,
+        job_id: Option<String>,
+        requested_by: Option<String>,
+    ) -> CommandRecord {
+        let record = CommandRecord {
+            command_id: command_id.to_string(),
+            node_id: node_id.to_string(),
+            target_pid,
+            action: action.to_string(),
+            job_id,
+            requested_by,
+            status: PENDING.to_string(),
+            message: None,
+            issued_at: now_ms(),
+            finished_at: None,
+        };
+        self.storage.record_command(&record);
+        self.commands.lock().unwrap_or_else(|e| e.into_inner()).insert(record.clone());
+        record
+    }
+
+    /// 记录 Agent 回报的结果；命令不存在或不是发给该节点的返回错误
+    pub fn complete(&self, result: CommandResult) -> Result<CommandRecord, String> {
+        let mut commands = self.commands.lock().unwrap_or_else(|e| e.into_inner());
+        let record = commands
+            .records
+            .get_mut(&result.command_id)
+            .ok_or_else(|| format!("未知的命令: {}", result.command_id))?;
+        if record.node_id != result.node_id {
+            return Err(format!("命令 {} 不是下发给节点 {} 的", result.command_id, result.node_id));
+        }
+        record.status = result.status;
+        record.message = result.message;
+        record.finished_at = Some(now_ms());
+        self.storage.record_command(record);
+        Ok(record.clone())
+    }
+
+    pub fn get(&self, command_id: &str) -> Option<CommandRecord> {
+        let commands = self.commands.lock().unwrap_or_else(|e| e.into_inner());
+        commands.records.get(command_id).cloned()
+    }
+}
+
+impl Commands {
+    fn insert(&mut self, record: CommandRecord) {
+        if !self.records.contains_key(&record.command_id) {
+            if self.order.len() == MAX_COMMANDS {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.records.remove(&oldest);
+                }
+            }
+            self.order.push_back(record.command_id.clone());
+        }
+        self.records.insert(record.command_id.clone(), record);
+    }
+}
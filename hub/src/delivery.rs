@@ -0,0 +1,45 @@
+//! Agent → Hub 的至少一次投递
+//!
+//! Agent 为每条事件和审计记录附加单调递增的 `seq`（起点取 Agent 启动时间，重启后仍递增），
+//! Hub 处理后回复 `{"ack": seq}`。Agent 超时未收到确认或连接断开时重发未确认的消息，
+//! Hub 按节点记录已接收的最大序号，重复的消息只确认、不再处理。旧版 Agent 不带 seq，照常处理。
+
+use dashmap::DashMap;
+use serde::Deserialize;
+use tokio_tungstenite::tungstenite::Message;
+
+/// 消息信封：只取投递相关的字段，其余内容按事件或审计记录解析
+#[derive(Debug, Default, Deserialize)]
+pub struct Envelope {
+    #[serde(default)]
+    pub seq: Option<u64>,
+    #[serde(default)]
+    pub node_id: Option<String>,
+}
+
+/// 各节点已接收的最大序号（只在内存中，Hub 重启后重发的消息会再处理一次）
+#[derive(Default)]
+pub struct DeliveryTracker {
+    last_seq: DashMap<String, u64>,
+}
+
+impl DeliveryTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 接收一条带序号的消息：新消息返回 true，重复（不大于已接收的最大序号）返回 false
+    pub fn accept(&self, node_id: &str, seq: u64) -> bool {
+        let mut last = self.last_seq.entry(node_id.to_string()).or_insert(0);
+        if seq <= *last {
+            return false;
+        }
+        *last = seq;
+        true
+    }
+}
+
+/// 确认消息
+pub fn ack_message(seq: u64) -> Message {
+    Message::Text(serde_json::json!({ "ack": seq }).to_string())
+}
@@ -0,0 +1,146 @@
+//! 事件历史：Hub 收到的最近事件
+//!
+//! 事件写入全局状态图后只留下聚合后的状态，这里按接收顺序另存最近的 `--event-history` 个事件（超出时丢弃最旧的），
+//! 供 `GET /api/v1/events` 按节点、job、类型和时间过滤，重建时间线。每个事件带递增的 `id`，
+//! 按 `after=<id>` 向后翻页。历史只保存在内存中（`--storage` 持久化的事件用于恢复状态图，不在这里查询）。
+//! 判定不可逆故障时也从这里统计同一设备在窗口内的错误次数（见 `k8s_controller::FaultDetector`）。
+
+use ark_core::event::{Event, EventType};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 默认保留的事件数
+pub const DEFAULT_EVENT_HISTORY: usize = 100_000;
+
+/// 查询默认返回的事件数
+const DEFAULT_QUERY_LIMIT: usize = 100;
+
+/// 单次查询最多返回的事件数
+const MAX_QUERY_LIMIT: usize = 1000;
+
+/// 历史中的一个事件
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEvent {
+    /// 接收序号（翻页游标）
+    pub id: u64,
+    /// Hub 收到的时间（毫秒）
+    pub received_at: u64,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+/// 一页查询结果
+#[derive(Debug, Serialize)]
+pub struct EventPage {
+    pub events: Vec<HistoryEvent>,
+    /// 还有更多匹配的事件时，下一页的 `after` 参数
+    pub next: Option<u64>,
+}
+
+struct History {
+    events: VecDeque<HistoryEvent>,
+    next_id: u64,
+}
+
+/// 事件历史存储
+pub struct EventHistory {
+    history: Mutex<History>,
+    capacity: usize,
+}
+
+impl EventHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            history: Mutex::new(History {
+                events: VecDeque::new(),
+                next_id: 1,
+            }),
+            capacity,
+        }
+    }
+
+    /// 保存一个已写入状态图的事件
+    pub fn record(&self, event: &Event) {
+        if self.capacity == 0 {
+            return;
+        }
+        let received_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let id = history.next_id;
+        history.next_id += 1;
+        if history.events.len() == self.capacity {
+            history.events.pop_front();
+        }
+        history.events.push_back(HistoryEvent {
+            id,
+            received_at,
+            event: event.clone(),
+        });
+    }
+
+    /// 最近 window 内收到的、满足条件的事件数
+    pub fn count_recent(&self, window: Duration, matches: impl Fn(&Event) -> bool) -> usize {
+        let since = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .saturating_sub(window)
+            .as_millis() as u64;
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        history
+            .events
+            .iter()
+            .rev()
+            .take_while(|entry| entry.received_at >= since)
+            .filter(|entry| matches(&entry.event))
+            .count()
+    }
+
+    /// 按查询参数（node_id、job_id、type、since、after、limit）过滤，从旧到新返回一页
+    pub fn query(&self, params: &HashMap<String, String>) -> Result<EventPage, String> {
+        let event_type = match params.get("type") {
+            Some(name) => Some(
+                serde_json::from_value::<EventType>(serde_json::Value::String(name.clone()))
+                    .map_err(|_| format!("未知的事件类型: {}", name))?,
+            ),
+            None => None,
+        };
+        let number = |name: &str| -> Result<Option<u64>, String> {
+            match params.get(name) {
+                Some(value) => value.parse::<u64>().map(Some).map_err(|_| format!("无效的 {}: {}", name, value)),
+                None => Ok(None),
+            }
+        };
+        let since = number("since")?;
+        let after = number("after")?.unwrap_or(0);
+        let limit = (number("limit")?.unwrap_or(DEFAULT_QUERY_LIMIT as u64) as usize).clamp(1, MAX_QUERY_LIMIT);
+        let field = |name: &str| params.get(name).map(String::as_str);
+        let matches = |entry: &HistoryEvent| {
+            let event = &entry.event;
+            entry.id > after
+                && field("node_id").is_none_or(|node| event.node_id.as_deref() == Some(node))
+                && field("job_id").is_none_or(|job| event.job_id.as_deref() == Some(job))
+                && event_type.as_ref().is_none_or(|event_type| event.event_type == *event_type)
+                && since.is_none_or(|since| event.ts >= since)
+        };
+
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let mut found = history.events.iter().filter(|e| matches(e));
+        let events: Vec<HistoryEvent> = found.by_ref().take(limit).cloned().collect();
+        let next = match found.next() {
+            Some(_) => events.last().map(|e| e.id),
+            None => None,
+        };
+        Ok(EventPage { events, next })
+    }
+}
+
+impl Default for EventHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_HISTORY)
+    }
+}
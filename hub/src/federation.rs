@@ -0,0 +1,171 @@
+//! Hub 联邦：下级 Hub 把收到的事件转发给上级 Hub，多机房部署时得到全局视图
+//!
+//! 下级 Hub 以 `--upstream-hub <URL> --cluster-id <ID>` 启动，像 Agent 一样连接上级 Hub 的 WebSocket 端口
+//! （同样的注册、心跳和事件消息；令牌取自 `ARK_UPSTREAM_TOKEN`，`--upstream-ca` 指定 wss:// 的 CA）：
+//! - 注册消息的 node_id 为 cluster_id，capabilities 含 `federation`
+//! - 转发的事件 node_id 改写为 `<cluster_id>/<node_id>`，上级 Hub 的状态图、job 索引和告警按此区分各机房的节点
+//!
+//! Agent 只连接本机房的 Hub，跨广域网的只有 Hub 之间的一条连接。转发是尽力而为的：断开期间事件在队列中
+//! 缓冲（最多 `QUEUE_CAPACITY` 条，超出的丢弃），重连后继续发送，发送失败的那一条不会重发。
+//! 上级 Hub 不能向下级机房的节点下发命令（`POST /api/v1/fix` 须发给下级 Hub）。
+
+use ark_core::event::Event;
+use futures_util::{SinkExt, StreamExt};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::{HeaderValue, AUTHORIZATION};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async_tls_with_config, Connector};
+
+/// 下级 Hub 注册时声明的能力
+pub const FEDERATION_CAPABILITY: &str = "federation";
+
+/// cluster_id 与下级节点 node_id 之间的分隔符
+pub const CLUSTER_SEPARATOR: char = '/';
+
+/// 等待发送到上级 Hub 的事件数上限
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// 心跳间隔（上级 Hub 默认 45 秒未收到消息标记为 stale）
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// 重连间隔上限
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// 下级节点在上级 Hub 中的 node_id
+pub fn federated_node_id(cluster_id: &str, node_id: &str) -> String {
+    format!("{}{}{}", cluster_id, CLUSTER_SEPARATOR, node_id)
+}
+
+/// node_id 是否属于该下级 Hub
+pub fn belongs_to(node_id: &str, cluster_id: &str) -> bool {
+    node_id
+        .strip_prefix(cluster_id)
+        .is_some_and(|rest| rest.starts_with(CLUSTER_SEPARATOR))
+}
+
+/// 校验 cluster_id：不能为空，也不能含 `/` 或状态图的命名空间分隔符 `::`
+pub fn validate_cluster_id(cluster_id: &str) -> Result<(), String> {
+    if cluster_id.is_empty() || cluster_id.contains(CLUSTER_SEPARATOR) || cluster_id.contains("::") {
+        return Err(format!("无效的 --cluster-id: {:?}（不能为空，不能含 / 或 ::）", cluster_id));
+    }
+    Ok(())
+}
+
+/// 到上级 Hub 的转发端
+pub struct Upstream {
+    cluster_id: String,
+    tx: mpsc::Sender<String>,
+    dropped: AtomicU64,
+}
+
+impl Upstream {
+    /// 启动后台连接任务（断开后按指数退避重连）
+    pub fn spawn(url: String, cluster_id: String, token: Option<String>, connector: Option<Connector>) -> Arc<Self> {
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        let registration = serde_json::json!({
+            "type": "register",
+            "node_id": cluster_id,
+            "labels": { "cluster_id": cluster_id },
+            "agent_version": format!("ark-hub {}", env!("CARGO_PKG_VERSION")),
+            "capabilities": ["events", "heartbeat", FEDERATION_CAPABILITY],
+            "heartbeat_secs": HEARTBEAT_INTERVAL.as_secs(),
+        })
+        .to_string();
+        let link = UpstreamLink { url, cluster_id: cluster_id.clone(), token, connector, registration };
+        tokio::spawn(link.run(rx));
+        Arc::new(Self { cluster_id, tx, dropped: AtomicU64::new(0) })
+    }
+
+    /// 转发一个已写入本地状态图的事件
+    pub fn forward(&self, event: &Event) {
+        let mut event = event.clone();
+        let node_id = event.node_id.as_deref().unwrap_or("-");
+        event.node_id = Some(federated_node_id(&self.cluster_id, node_id));
+        let message = match serde_json::to_string(&event) {
+            Ok(message) => message,
+            Err(e) => {
+                eprintln!("[federation] 序列化事件失败: {}", e);
+                return;
+            }
+        };
+        if self.tx.try_send(message).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            if dropped % 1000 == 1 {
+                eprintln!("[federation] 上级 Hub 队列已满，已丢弃 {} 个事件", dropped);
+            }
+        }
+    }
+}
+
+struct UpstreamLink {
+    url: String,
+    cluster_id: String,
+    token: Option<String>,
+    connector: Option<Connector>,
+    registration: String,
+}
+
+impl UpstreamLink {
+    async fn run(self, mut rx: mpsc::Receiver<String>) {
+        let mut delay = Duration::from_secs(1);
+        loop {
+            let started = Instant::now();
+            let result = self.session(&mut rx).await;
+            // 连接保持过一段时间后断开的，从最短间隔重新开始退避
+            if started.elapsed() > MAX_RECONNECT_DELAY {
+                delay = Duration::from_secs(1);
+            }
+            match result {
+                Ok(()) => return,
+                Err(e) => eprintln!("[federation] 与上级 Hub {} 的连接断开: {}，{} 秒后重连", self.url, e, delay.as_secs()),
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    }
+
+    /// 一次连接：注册后转发队列中的事件并定期发送心跳，直到连接断开（Hub 退出时返回 Ok）
+    async fn session(&self, rx: &mut mpsc::Receiver<String>) -> Result<(), String> {
+        let mut request = self.url.as_str().into_client_request().map_err(|e| format!("无效的上级 Hub 地址: {}", e))?;
+        if let Some(ref token) = self.token {
+            let value = HeaderValue::from_str(&format!("Bearer {}", token)).map_err(|e| format!("无效的上级 Hub 令牌: {}", e))?;
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+        let (stream, _) = connect_async_tls_with_config(request, None, false, self.connector.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+        let (mut write, mut read) = stream.split();
+        write
+            .send(Message::Text(self.registration.clone()))
+            .await
+            .map_err(|e| format!("发送注册消息失败: {}", e))?;
+        println!("🌐 已连接上级 Hub {}（cluster_id={}）", self.url, self.cluster_id);
+
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        heartbeat.tick().await;
+        loop {
+            tokio::select! {
+                message = rx.recv() => {
+                    let Some(message) = message else {
+                        return Ok(());
+                    };
+                    write.send(Message::Text(message)).await.map_err(|e| e.to_string())?;
+                }
+                _ = heartbeat.tick() => {
+                    let message = serde_json::json!({ "type": "heartbeat", "node_id": self.cluster_id });
+                    write.send(Message::Text(message.to_string())).await.map_err(|e| e.to_string())?;
+                }
+                // 上级 Hub 不向下级发送消息，只需发现连接关闭
+                msg = read.next() => match msg {
+                    Some(Ok(Message::Close(_))) | None => return Err("上级 Hub 关闭连接".to_string()),
+                    Some(Err(e)) => return Err(e.to_string()),
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Hub → Agent 的流控
+//!
+//! Hub 按指数加权平均统计每条消息的处理耗时（状态图写锁争用、存储或告警积压时变长），超过 `--flow-slow-ms`
+//! 时向所有已连接的 Agent 广播 `{"flow": "slow"}`，Agent 随即暂停推送利用率、存储和带宽类事件（错误、进程状态、
+//! 规则命中等照常推送）；降到阈值的四分之一以下，或处于 slow 状态时超过 `IDLE_RECOVERY` 没有消息，广播 `{"flow": "normal"}`。
+//! 新连接注册时若正处于 slow 状态，单独发送一次。
+
+use dashmap::DashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+/// 新样本在平均值中的权重
+const EWMA_ALPHA: f64 = 0.2;
+
+/// slow 状态下超过该时间没有消息时恢复 normal（Agent 减少推送后样本也会变少）
+pub const IDLE_RECOVERY: Duration = Duration::from_secs(10);
+
+struct FlowState {
+    /// 平均处理耗时（毫秒）
+    average_ms: f64,
+    slow: bool,
+    last_sample: Instant,
+}
+
+/// 流控状态
+pub struct FlowControl {
+    slow_ms: f64,
+    state: Mutex<FlowState>,
+}
+
+impl FlowControl {
+    pub fn new(slow_after: Duration) -> Self {
+        Self {
+            slow_ms: slow_after.as_secs_f64() * 1000.0,
+            state: Mutex::new(FlowState { average_ms: 0.0, slow: false, last_sample: Instant::now() }),
+        }
+    }
+
+    /// 记录一条消息的处理耗时，状态变化时返回新状态（true 为 slow）
+    pub fn observe(&self, elapsed: Duration) -> Option<bool> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.average_ms = EWMA_ALPHA * elapsed.as_secs_f64() * 1000.0 + (1.0 - EWMA_ALPHA) * state.average_ms;
+        state.last_sample = Instant::now();
+        let slow = if state.slow {
+            state.average_ms >= self.slow_ms / 4.0
+        } else {
+            state.average_ms > self.slow_ms
+        };
+        if slow == state.slow {
+            return None;
+        }
+        state.slow = slow;
+        Some(slow)
+    }
+
+    /// slow 状态下长时间没有消息时恢复，返回是否发生了恢复
+    pub fn recover_idle(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if !state.slow || state.last_sample.elapsed() < IDLE_RECOVERY {
+            return false;
+        }
+        state.slow = false;
+        state.average_ms = 0.0;
+        true
+    }
+
+    pub fn is_slow(&self) -> bool {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).slow
+    }
+}
+
+/// 流控消息
+pub fn flow_message(slow: bool) -> Message {
+    let flow = if slow { "slow" } else { "normal" };
+    Message::Text(serde_json::json!({ "flow": flow }).to_string())
+}
+
+/// 向所有已连接的节点广播流控状态
+pub fn broadcast(connections: &DashMap<String, mpsc::UnboundedSender<Message>>, slow: bool) {
+    if slow {
+        eprintln!("[hub] 消息处理变慢，通知 {} 个节点暂停推送利用率类事件", connections.len());
+    } else {
+        println!("[hub] 消息处理恢复，通知 {} 个节点恢复推送", connections.len());
+    }
+    for sender in connections.iter() {
+        let _ = sender.send(flow_message(slow));
+    }
+}
@@ -0,0 +1,158 @@
+//! 健康评分：每个节点和 job 一个 0-100 的分数，供看板和调度器做放置决策
+//!
+//! 从 100 分中扣除：
+//! - 每条活动的 BlockedBy 边（进程或资源被错误阻塞）扣 `BLOCKED_PENALTY`
+//! - 错误窗口内每个出错实体扣 `ERROR_PENALTY`
+//! - 每个命中的跨节点场景按严重程度扣 `SCENE_CRITICAL_PENALTY` / `SCENE_WARNING_PENALTY`
+//!
+//! 节点的场景取涉及该节点的场景，job 的错误取其所在节点上的错误。离线节点记 0 分。
+//! 80 分及以上为 healthy，50 分及以上为 degraded，其余为 unhealthy。
+
+use crate::jobs::JobEntry;
+use crate::nodes::{NodeStatus, NodeSummary};
+use crate::scene::{ClusterAnalysis, Severity};
+use ark_core::graph::{EdgeType, NodeKey, StateGraph};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+const BLOCKED_PENALTY: u32 = 15;
+const ERROR_PENALTY: u32 = 5;
+const SCENE_CRITICAL_PENALTY: u32 = 40;
+const SCENE_WARNING_PENALTY: u32 = 20;
+
+/// 健康分数及扣分依据
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthScore {
+    pub score: u32,
+    /// healthy / degraded / unhealthy / offline
+    pub status: &'static str,
+    /// 活动的 BlockedBy 边数
+    pub blocked: usize,
+    /// 错误窗口内的出错实体数
+    pub errors: usize,
+    /// 命中的场景（`<job_id>/<scene>`）
+    pub scenes: Vec<String>,
+}
+
+impl HealthScore {
+    fn new(blocked: usize, errors: usize, scenes: &[(&str, &ClusterAnalysis)]) -> Self {
+        let scene_penalty: u32 = scenes
+            .iter()
+            .map(|(_, analysis)| match analysis.severity {
+                Severity::Critical => SCENE_CRITICAL_PENALTY,
+                Severity::Warning => SCENE_WARNING_PENALTY,
+            })
+            .sum();
+        let penalty = (blocked as u32)
+            .saturating_mul(BLOCKED_PENALTY)
+            .saturating_add((errors as u32).saturating_mul(ERROR_PENALTY))
+            .saturating_add(scene_penalty);
+        let score = 100u32.saturating_sub(penalty);
+        let status = match score {
+            80.. => "healthy",
+            50.. => "degraded",
+            _ => "unhealthy",
+        };
+        Self {
+            score,
+            status,
+            blocked,
+            errors,
+            scenes: scenes
+                .iter()
+                .map(|(job_id, analysis)| format!("{}/{}", job_id, analysis.scene.as_str()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct NodeHealth {
+    pub node_id: String,
+    #[serde(flatten)]
+    pub health: HealthScore,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobHealth {
+    pub job_id: String,
+    pub nodes: Vec<String>,
+    #[serde(flatten)]
+    pub health: HealthScore,
+}
+
+/// `GET /api/v1/health` 的响应
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    /// 集群分数：各节点分数的平均值（没有节点时为 100）
+    pub score: u32,
+    /// 各状态的节点数
+    pub summary: BTreeMap<&'static str, usize>,
+    pub nodes: Vec<NodeHealth>,
+    pub jobs: Vec<JobHealth>,
+}
+
+impl HealthReport {
+    /// 根据节点清单、运行中的 job 及其场景计算评分
+    pub async fn build(graph: &StateGraph, inventory: Vec<NodeSummary>, jobs: Vec<(JobEntry, Vec<ClusterAnalysis>)>) -> Self {
+        // 节点 → 活动的 BlockedBy 边数；进程节点键 → 阻塞它的边数
+        let mut node_blocked: BTreeMap<String, usize> = BTreeMap::new();
+        let mut process_blocked: BTreeMap<String, usize> = BTreeMap::new();
+        for edge in graph.get_all_edges_async().await {
+            if edge.edge_type != EdgeType::BlockedBy {
+                continue;
+            }
+            if let Some(node_id) = NodeKey::parse(&edge.from).node_id() {
+                *node_blocked.entry(node_id.to_string()).or_default() += 1;
+            }
+            *process_blocked.entry(edge.from).or_default() += 1;
+        }
+        let errors: BTreeMap<&str, usize> = inventory
+            .iter()
+            .map(|node| (node.info.registration.node_id.as_str(), node.recent_errors))
+            .collect();
+
+        let job_health: Vec<JobHealth> = jobs
+            .iter()
+            .map(|(job, scenes)| {
+                let keys: HashSet<String> = job.processes.iter().map(|p| p.key().to_string()).collect();
+                let blocked = keys.iter().filter_map(|key| process_blocked.get(key)).sum();
+                let job_errors = job.nodes.iter().filter_map(|node_id| errors.get(node_id.as_str())).sum();
+                let scenes: Vec<(&str, &ClusterAnalysis)> = scenes.iter().map(|s| (job.job_id.as_str(), s)).collect();
+                JobHealth {
+                    job_id: job.job_id.clone(),
+                    nodes: job.nodes.clone(),
+                    health: HealthScore::new(blocked, job_errors, &scenes),
+                }
+            })
+            .collect();
+
+        let mut summary: BTreeMap<&'static str, usize> =
+            ["healthy", "degraded", "unhealthy", "offline"].into_iter().map(|status| (status, 0)).collect();
+        let node_health: Vec<NodeHealth> = inventory
+            .iter()
+            .map(|node| {
+                let node_id = &node.info.registration.node_id;
+                let scenes: Vec<(&str, &ClusterAnalysis)> = jobs
+                    .iter()
+                    .flat_map(|(job, scenes)| scenes.iter().map(move |s| (job.job_id.as_str(), s)))
+                    .filter(|(_, analysis)| analysis.nodes.contains(node_id))
+                    .collect();
+                let blocked = node_blocked.get(node_id).copied().unwrap_or(0);
+                let mut health = HealthScore::new(blocked, node.recent_errors, &scenes);
+                if node.info.status == NodeStatus::Offline {
+                    health.score = 0;
+                    health.status = "offline";
+                }
+                *summary.entry(health.status).or_default() += 1;
+                NodeHealth { node_id: node_id.clone(), health }
+            })
+            .collect();
+
+        let score = match node_health.len() {
+            0 => 100,
+            count => (node_health.iter().map(|node| node.health.score).sum::<u32>() as f64 / count as f64).round() as u32,
+        };
+        Self { score, summary, nodes: node_health, jobs: job_health }
+    }
+}
@@ -0,0 +1,181 @@
+//! Job 索引：job_id → 节点、进程、状态、首次/最近出现时间
+//!
+//! 由 Hub 收到的事件增量维护，`cluster_why` 和 `GET /api/v1/jobs` 据此直接定位 job 的进程，
+//! 不再每次请求都扫描全局状态图。启动时从（可能由存储恢复的）状态图重建一次。
+
+use ark_core::event::{Event, EventType};
+use ark_core::graph::{NodeKey, NodeType, StateGraph};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+/// job 中的一个进程
+#[derive(Debug, Clone, Serialize)]
+pub struct JobProcess {
+    pub node_id: Option<String>,
+    pub pid: u32,
+    /// running / exit / zombie
+    pub state: String,
+    pub last_seen: u64,
+}
+
+impl JobProcess {
+    /// 状态图中的节点键
+    pub fn key(&self) -> NodeKey {
+        NodeKey::process(self.node_id.as_deref(), self.pid)
+    }
+}
+
+/// 一个 job 的索引项
+#[derive(Debug, Clone, Serialize)]
+pub struct JobEntry {
+    pub job_id: String,
+    /// 进程所在的节点
+    pub nodes: Vec<String>,
+    pub processes: Vec<JobProcess>,
+    /// 任一进程在运行时为 running，否则为 exited
+    pub state: &'static str,
+    pub first_seen: u64,
+    pub last_seen: u64,
+}
+
+#[derive(Default)]
+struct IndexState {
+    /// job_id → (进程节点键 → 进程)
+    jobs: HashMap<String, BTreeMap<String, JobProcess>>,
+    /// job_id → (首次出现, 最近出现)
+    seen: HashMap<String, (u64, u64)>,
+    /// 进程节点键 → job_id（不带 job_id 的进程事件据此归属）
+    by_process: HashMap<String, String>,
+}
+
+/// Job 索引
+#[derive(Default)]
+pub struct JobIndex {
+    state: RwLock<IndexState>,
+}
+
+impl JobIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从状态图中带 job_id 的进程节点重建索引
+    pub async fn from_graph(graph: &StateGraph) -> Self {
+        let index = Self::new();
+        {
+            let mut state = index.state.write().unwrap_or_else(|e| e.into_inner());
+            for node in graph.get_nodes_async().await.into_values() {
+                if node.node_type != NodeType::Process {
+                    continue;
+                }
+                let (Some(job_id), Some(pid)) = (node.metadata.get("job_id"), node.key().pid()) else {
+                    continue;
+                };
+                let process = JobProcess {
+                    node_id: node.key().node_id().map(str::to_string),
+                    pid,
+                    state: node.metadata.get("state").cloned().unwrap_or_else(|| "running".to_string()),
+                    last_seen: node.last_update,
+                };
+                state.insert(job_id, process);
+            }
+        }
+        index
+    }
+
+    /// 记录一个已写入状态图的事件
+    pub fn observe(&self, event: &Event) {
+        let Some(pid) = event.pid else {
+            return;
+        };
+        let key = NodeKey::process(event.node_id.as_deref(), pid).to_string();
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        let job_id = match event.job_id {
+            Some(ref job_id) => job_id.clone(),
+            None => match state.by_process.get(&key) {
+                Some(job_id) => job_id.clone(),
+                None => return,
+            },
+        };
+
+        let previous = state.jobs.get(&job_id).and_then(|processes| processes.get(&key)).map(|p| p.state.clone());
+        let process_state = match (&event.event_type, event.value.as_str()) {
+            (EventType::ProcessState, "start") => "running".to_string(),
+            (EventType::ProcessState, value @ ("exit" | "zombie")) => value.to_string(),
+            _ => previous.unwrap_or_else(|| "running".to_string()),
+        };
+        state.insert(
+            &job_id,
+            JobProcess {
+                node_id: event.node_id.clone(),
+                pid,
+                state: process_state,
+                last_seen: event.ts,
+            },
+        );
+    }
+
+    /// 查找 job（不存在时返回 None）
+    pub fn get(&self, job_id: &str) -> Option<JobEntry> {
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+        state.entry(job_id)
+    }
+
+    /// 所有 job，按最近出现时间倒序
+    pub fn list(&self) -> Vec<JobEntry> {
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+        let mut jobs: Vec<JobEntry> = state.jobs.keys().filter_map(|job_id| state.entry(job_id)).collect();
+        jobs.sort_by(|a, b| b.last_seen.cmp(&a.last_seen).then_with(|| a.job_id.cmp(&b.job_id)));
+        jobs
+    }
+
+    /// 清理所有进程都已退出、且最近出现时间早于 cutoff（毫秒）的 job
+    pub fn prune(&self, cutoff: u64) -> usize {
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        let expired: Vec<String> = state
+            .jobs
+            .keys()
+            .filter(|job_id| state.entry(job_id).is_some_and(|job| job.state != "running" && job.last_seen < cutoff))
+            .cloned()
+            .collect();
+        for job_id in &expired {
+            if let Some(processes) = state.jobs.remove(job_id) {
+                for key in processes.keys() {
+                    state.by_process.remove(key);
+                }
+            }
+            state.seen.remove(job_id);
+        }
+        expired.len()
+    }
+}
+
+impl IndexState {
+    fn insert(&mut self, job_id: &str, process: JobProcess) {
+        let key = process.key().to_string();
+        let ts = process.last_seen;
+        let seen = self.seen.entry(job_id.to_string()).or_insert((ts, ts));
+        seen.0 = seen.0.min(ts);
+        seen.1 = seen.1.max(ts);
+        self.by_process.insert(key.clone(), job_id.to_string());
+        self.jobs.entry(job_id.to_string()).or_default().insert(key, process);
+    }
+
+    fn entry(&self, job_id: &str) -> Option<JobEntry> {
+        let processes: Vec<JobProcess> = self.jobs.get(job_id)?.values().cloned().collect();
+        let (first_seen, last_seen) = self.seen.get(job_id).copied().unwrap_or_default();
+        let mut nodes: Vec<String> = processes.iter().filter_map(|p| p.node_id.clone()).collect();
+        nodes.sort();
+        nodes.dedup();
+        let state = if processes.iter().any(|p| p.state == "running") { "running" } else { "exited" };
+        Some(JobEntry {
+            job_id: job_id.to_string(),
+            nodes,
+            processes,
+            state,
+            first_seen,
+            last_seen,
+        })
+    }
+}
@@ -1,20 +1,93 @@
 //! Kubernetes 控制器模块
 //! 
-//! 当 Hub 诊断出不可逆硬件故障时，自动调用 K8s API：
-//! 1. 给 Node 打上 NoSchedule 污点
+//! 当 Hub 诊断出不可逆硬件故障时，按处置策略（`ArkRemediationPolicy`，见 `remediation` 模块）自动调用 K8s API：
+//! 1. 给 Node 打污点、cordon（默认打 `ark.io/hardware-failure:NoSchedule` 污点）
 //! 2. 执行 Pod Eviction（驱逐）
 //! 
+//! 不可逆故障由 `FaultDetector` 判定：致命 XID（默认 48、74、79、95）出现一次即判定；应用错误等可恢复的 XID
+//! （默认 13、31、43、45、63、94）不计入；其余硬件错误需在窗口内（默认 600 秒）于同一设备出现 `threshold`（默认 3）次，
+//! 次数从 Hub 的事件历史中统计。阈值和 XID 列表由 `ark-hub --fault-policy <FILE>`（YAML）配置：
+//! ```yaml
+//! window_secs: 600
+//! threshold: 3
+//! fatal_xids: [48, 74, 79, 95]
+//! recoverable_xids: [13, 31, 43, 45, 63, 94]
+//! ```
+//! 
+//! 控制器监听策略变化：重新校验、回写策略的 `status`，并按新策略调整已隔离的节点（不重新驱逐）。
+//! 
+//! 每次处置同时在 Node 上记录 Warning 事件并把 Condition `ArkHardwareFailure` 置为 True（reason 为故障类型，如 `XidError`），
+//! 解除隔离时置为 False 并记录 Normal 事件，`kubectl describe node` 即可看到。只告警的故障同样记录。
+//! 
+//! 每次改动节点（隔离、按新策略调整、解除隔离）和驱逐 Pod 都写入 Hub 审计，details 带触发的故障。
+//! `--k8s-dry-run` 时所有变更请求带 `dryRun=All` 发送（API Server 照常校验但不落盘），审计的 result 为 `dry_run`，
+//! 不登记等待恢复的节点（不会下发自检）。
+//! 
+//! 多副本部署时通过 Lease 选举 leader（见 `leader` 模块），只有 leader 执行上述 K8s 操作。
+//! 
+//! 节点修复后解除隔离（污点和 Hub 做的 cordon）：运维调用 `POST /api/v1/nodes/<node_id>/repaired`，或以 `--taint-recovery-secs` 启用自动恢复——
+//! 节点在该时长内没有再上报故障事件时，Hub 下发自检命令（`{"intent": "self_test"}`），自检通过后解除隔离。
+//! 解除隔离写入 Hub 审计（action 为 `k8s.untaint`）并发出 info 级恢复告警。
+//! 
 //! 让 Ark 从被动监控工具升维成 AI 集群自动驾驶控制面
 
-use k8s_openapi::api::core::v1::{Node, Pod};
-use kube::{Api, Client, Config};
-use kube::api::{Patch, PatchParams};
+use chrono::Utc;
+use k8s_openapi::api::core::v1::{Event as K8sEvent, EventSource, Node, NodeCondition, ObjectReference, Pod};
+use k8s_openapi::api::policy::v1::Eviction;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{DeleteOptions, ObjectMeta, Time};
+use kube::{Api, Client, Config, ResourceExt};
+use kube::api::{ListParams, Patch, PatchParams, PostParams};
+use kube::runtime::{reflector, watcher, WatchStreamExt};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 use serde_json::json;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 use tokio::time::{Duration, Instant};
 use ark_core::event::{Event, EventType};
+use crate::alerts::{Alert, Alerter};
+use crate::audit::{AuditRecord, AuditStore};
+use crate::commands::{self, CommandStore};
+use crate::events::EventHistory;
+use crate::leader::LeaderElector;
+use crate::remediation::{self, ArkRemediationPolicy, EvictSpec, RemediationRule};
+use dashmap::DashMap;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+/// 故障节点的污点 key
+pub const TAINT_KEY: &str = "ark.io/hardware-failure";
+
+/// 被 PDB 拒绝的驱逐的重试间隔
+const PDB_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 记录故障状态的 Node Condition
+pub const CONDITION_TYPE: &str = "ArkHardwareFailure";
+
+/// Node 事件的 source.component
+const EVENT_COMPONENT: &str = "ark-hub";
+
+/// Hub cordon 节点时打的注解，解除隔离时只 uncordon 带该注解的节点
+const CORDON_ANNOTATION: &str = "ark.io/cordoned";
+
+/// 自动恢复的检查间隔
+pub const RECOVERY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 自检命令超过该时间未回报视为未通过
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// 控制器自动操作在审计和命令记录中的发起人
+const CONTROLLER_USER: &str = "k8s-controller";
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
 
 /// 不可逆故障类型
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -29,17 +102,220 @@ pub enum IrreversibleFault {
     OtherHardwareFailure { node_id: String, reason: String },
 }
 
+impl IrreversibleFault {
+    /// 故障所在的节点
+    pub fn node_id(&self) -> &str {
+        match self {
+            IrreversibleFault::PersistentXidError { node_id, .. } |
+            IrreversibleFault::RdmaLinkDown { node_id, .. } |
+            IrreversibleFault::StorageDeviceFailure { node_id, .. } |
+            IrreversibleFault::OtherHardwareFailure { node_id, .. } => node_id,
+        }
+    }
+
+    /// 故障类型（告警指纹的一部分）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IrreversibleFault::PersistentXidError { .. } => "xid_error",
+            IrreversibleFault::RdmaLinkDown { .. } => "rdma_link_down",
+            IrreversibleFault::StorageDeviceFailure { .. } => "storage_failure",
+            IrreversibleFault::OtherHardwareFailure { .. } => "hardware_failure",
+        }
+    }
+
+    /// 一行描述（用于告警）
+    pub fn describe(&self) -> String {
+        match self {
+            IrreversibleFault::PersistentXidError { node_id, gpu_id, xid_code } => {
+                format!("节点 {} 的 {} 持续出现 XID 错误: {}", node_id, gpu_id, xid_code)
+            }
+            IrreversibleFault::RdmaLinkDown { node_id, interface } => {
+                format!("节点 {} 的 RDMA 链路 {} 断开", node_id, interface)
+            }
+            IrreversibleFault::StorageDeviceFailure { node_id, device } => {
+                format!("节点 {} 的存储设备 {} 故障", node_id, device)
+            }
+            IrreversibleFault::OtherHardwareFailure { node_id, reason } => {
+                format!("节点 {} 硬件故障: {}", node_id, reason)
+            }
+        }
+    }
+}
+
+/// 不可逆故障的判定策略
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FaultPolicy {
+    /// 统计窗口（秒）
+    pub window_secs: u64,
+    /// 窗口内同一设备出现该次数的硬件错误才判定为不可逆
+    pub threshold: usize,
+    /// 出现一次即判定为不可逆的 XID（如 48 双比特 ECC、79 GPU 掉卡）
+    pub fatal_xids: Vec<u32>,
+    /// 应用错误或驱动可自行恢复的 XID（如 13 图形引擎异常、31 显存访问越界），不视为硬件故障
+    pub recoverable_xids: Vec<u32>,
+}
+
+impl Default for FaultPolicy {
+    fn default() -> Self {
+        Self {
+            window_secs: 600,
+            threshold: 3,
+            fatal_xids: vec![48, 74, 79, 95],
+            recoverable_xids: vec![13, 31, 43, 45, 63, 94],
+        }
+    }
+}
+
+impl FaultPolicy {
+    pub fn load_from_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+        let policy: Self = serde_yaml::from_str(&content).map_err(|e| format!("解析 {} 失败: {}", path.display(), e))?;
+        if policy.threshold == 0 {
+            return Err(format!("{} 中的 threshold 不能为 0", path.display()));
+        }
+        if let Some(xid) = policy.fatal_xids.iter().find(|xid| policy.recoverable_xids.contains(xid)) {
+            return Err(format!("{} 中 XID {} 同时出现在 fatal_xids 和 recoverable_xids 中", path.display(), xid));
+        }
+        Ok(policy)
+    }
+
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs)
+    }
+}
+
+/// 从事件载荷中解析 XID 编号（"XID_79"、"Xid 79"、"xid=79"）
+pub fn parse_xid(value: &str) -> Option<u32> {
+    let start = value.to_ascii_lowercase().find("xid")? + 3;
+    let digits: String = value[start..]
+        .trim_start_matches(|c: char| !c.is_ascii_digit())
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+fn is_xid(value: &str) -> bool {
+    value.to_ascii_lowercase().contains("xid")
+}
+
+/// 不可逆故障判定（未启用控制器时告警也据此识别故障）
+pub struct FaultDetector {
+    policy: FaultPolicy,
+    events: Arc<EventHistory>,
+}
+
+impl FaultDetector {
+    /// events 为已记录当前事件的事件历史
+    pub fn new(policy: FaultPolicy, events: Arc<EventHistory>) -> Self {
+        Self { policy, events }
+    }
+    
+    /// 检查事件是否表示不可逆故障
+    pub fn detect(&self, event: &Event) -> Option<IrreversibleFault> {
+        let node_id = event.node_id.clone().unwrap_or_else(|| "unknown".to_string());
+        match event.event_type {
+            EventType::ErrorHw if is_xid(&event.value) => {
+                let xid = parse_xid(&event.value);
+                if xid.is_some_and(|xid| self.policy.recoverable_xids.contains(&xid)) {
+                    return None;
+                }
+                let fatal = xid.is_some_and(|xid| self.policy.fatal_xids.contains(&xid));
+                // 同一 GPU 在窗口内的 XID 错误（可恢复的不计入）
+                let persistent = fatal
+                    || self.recent_errors(event, |value| {
+                        is_xid(value) && !parse_xid(value).is_some_and(|xid| self.policy.recoverable_xids.contains(&xid))
+                    }) >= self.policy.threshold;
+                persistent.then(|| IrreversibleFault::PersistentXidError {
+                    node_id,
+                    gpu_id: event.entity_id.clone(),
+                    xid_code: event.value.clone(),
+                })
+            }
+            EventType::ErrorHw => {
+                // 其他硬件错误（如 ECC）在窗口内反复出现才视为不可逆
+                (self.recent_errors(event, |value| !is_xid(value)) >= self.policy.threshold).then(|| {
+                    IrreversibleFault::OtherHardwareFailure {
+                        node_id,
+                        reason: format!("{}: {}", event.entity_id, event.value),
+                    }
+                })
+            }
+            EventType::ErrorNet => {
+                // 检查是否为 RDMA 链路断开
+                if event.value.contains("link_down") || event.value.contains("LINK_DOWN") {
+                    return Some(IrreversibleFault::RdmaLinkDown {
+                        node_id,
+                        interface: event.entity_id.clone(),
+                    });
+                }
+                None
+            }
+            EventType::TopoLinkDown => {
+                // 拓扑链路断开（可能是 PCIe/NVLink）
+                Some(IrreversibleFault::OtherHardwareFailure {
+                    node_id,
+                    reason: format!("Topology link down: {} - {}", event.entity_id, event.value),
+                })
+            }
+            _ => None,
+        }
+    }
+    
+    /// 窗口内同一节点、同一设备上载荷满足条件的硬件错误数（含当前事件）
+    fn recent_errors(&self, event: &Event, matches: impl Fn(&str) -> bool) -> usize {
+        self.events.count_recent(self.policy.window(), |other| {
+            other.event_type == EventType::ErrorHw
+                && other.node_id == event.node_id
+                && other.entity_id == event.entity_id
+                && matches(&other.value)
+        })
+    }
+}
+
+/// 已隔离（打污点或 cordon）、等待恢复的故障节点
+#[derive(Debug, Clone, Serialize)]
+pub struct TaintedNode {
+    pub node_id: String,
+    /// 故障类型
+    pub fault: String,
+    pub reason: String,
+    /// 打的污点 key，只 cordon 时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taint_key: Option<String>,
+    /// 打污点的时间（毫秒）
+    pub tainted_at: u64,
+    /// 最近一次故障事件的时间（毫秒），自动恢复从这里开始计时
+    pub last_fault: u64,
+    /// 已下发、尚未完成的自检命令
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub self_test: Option<String>,
+}
+
 /// Kubernetes 控制器
 pub struct K8sController {
     client: Client,
     node_api: Api<Node>,
     pod_api: Api<Pod>,
+    /// Node 事件写在 default 命名空间（与 kubelet 一致）
+    event_api: Api<K8sEvent>,
     /// 已处理的故障节点（避免重复操作）
     processed_nodes: Arc<RwLock<HashMap<String, Instant>>>,
+    /// 本 Hub 隔离过的节点（Hub 重启前打的污点不在其中，仍可通过 API 解除）
+    tainted: Arc<RwLock<HashMap<String, TaintedNode>>>,
+    /// 有效处置策略的规则，按策略名称排序后依次排列
+    rules: Arc<RwLock<Vec<RemediationRule>>>,
     /// 故障冷却时间（默认 5 分钟，避免频繁操作）
     cooldown_duration: Duration,
     /// 是否启用自动操作（默认 false，需要显式启用）
     enabled: bool,
+    /// Leader 选举，未启用时本实例总是执行操作
+    leader: Option<Arc<LeaderElector>>,
+    /// 变更请求只做服务端校验（dryRun=All），不改动集群
+    dry_run: bool,
+    /// 记录每次变更的审计存储
+    audit: Arc<AuditStore>,
 }
 
 impl K8sController {
@@ -50,77 +326,97 @@ impl K8sController {
         
         let node_api: Api<Node> = Api::all(client.clone());
         let pod_api: Api<Pod> = Api::all(client.clone());
+        let event_api: Api<K8sEvent> = Api::namespaced(client.clone(), "default");
         
         Ok(Self {
             client,
             node_api,
             pod_api,
+            event_api,
             processed_nodes: Arc::new(RwLock::new(HashMap::new())),
+            tainted: Arc::new(RwLock::new(HashMap::new())),
+            rules: Arc::new(RwLock::new(Vec::new())),
             cooldown_duration: Duration::from_secs(300), // 5 分钟冷却
             enabled,
+            leader: None,
+            dry_run: false,
+            audit: Arc::new(AuditStore::new()),
         })
     }
     
-    /// 检查事件是否表示不可逆故障
-    pub fn detect_irreversible_fault(&self, event: &Event) -> Option<IrreversibleFault> {
-        // 只处理错误事件
-        match event.event_type {
-            EventType::ErrorHw => {
-                // 检查是否为持续 XID 错误
-                if event.value.contains("XID") || event.value.contains("xid") {
-                    // 检查错误频率（简化：如果短时间内多次出现，认为是持续故障）
-                    // TODO: 实际应该查询图引擎，统计该节点的 XID 错误频率
-                    let node_id = event.node_id.clone().unwrap_or_else(|| "unknown".to_string());
-                    return Some(IrreversibleFault::PersistentXidError {
-                        node_id,
-                        gpu_id: event.entity_id.clone(),
-                        xid_code: event.value.clone(),
-                    });
-                }
-                
-                // 其他硬件错误
-                let node_id = event.node_id.clone().unwrap_or_else(|| "unknown".to_string());
-                Some(IrreversibleFault::OtherHardwareFailure {
-                    node_id,
-                    reason: format!("{}: {}", event.entity_id, event.value),
-                })
-            }
-            EventType::ErrorNet => {
-                // 检查是否为 RDMA 链路断开
-                if event.value.contains("link_down") || event.value.contains("LINK_DOWN") {
-                    let node_id = event.node_id.clone().unwrap_or_else(|| "unknown".to_string());
-                    return Some(IrreversibleFault::RdmaLinkDown {
-                        node_id,
-                        interface: event.entity_id.clone(),
-                    });
-                }
-                None
-            }
-            EventType::TopoLinkDown => {
-                // 拓扑链路断开（可能是 PCIe/NVLink）
-                let node_id = event.node_id.clone().unwrap_or_else(|| "unknown".to_string());
-                Some(IrreversibleFault::OtherHardwareFailure {
-                    node_id,
-                    reason: format!("Topology link down: {} - {}", event.entity_id, event.value),
-                })
-            }
-            _ => None,
+    pub fn client(&self) -> Client {
+        self.client.clone()
+    }
+    
+    /// 只在 leader 上执行 K8s 操作
+    pub fn with_leader_election(mut self, leader: Arc<LeaderElector>) -> Self {
+        self.leader = Some(leader);
+        self
+    }
+    
+    /// 变更请求带 dryRun=All，只记录不改动集群
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+    
+    /// 变更写入的审计存储
+    pub fn with_audit(mut self, audit: Arc<AuditStore>) -> Self {
+        self.audit = audit;
+        self
+    }
+    
+    fn patch_params(&self) -> PatchParams {
+        PatchParams {
+            dry_run: self.dry_run,
+            ..PatchParams::default()
+        }
+    }
+    
+    fn post_params(&self) -> PostParams {
+        PostParams {
+            dry_run: self.dry_run,
+            ..PostParams::default()
+        }
+    }
+    
+    /// 写入 Hub 审计，dry-run 时成功的变更记为 dry_run
+    fn audit(&self, node_id: &str, by: &str, action: &str, succeeded: bool, details: String) {
+        let result = match (succeeded, self.dry_run) {
+            (false, _) => "failed",
+            (true, true) => "dry_run",
+            (true, false) => "success",
+        };
+        self.audit.append(AuditRecord::hub(node_id, by, action, result, details));
+    }
+    
+    /// 本实例是否执行 K8s 操作
+    pub fn is_leader(&self) -> bool {
+        self.leader.as_ref().map_or(true, |leader| leader.is_leader())
+    }
+    
+    /// 退出前释放 leader 身份
+    pub async fn release_leadership(&self) {
+        if let Some(ref leader) = self.leader {
+            leader.release().await;
         }
     }
     
-    /// 处理不可逆故障：打污点 + 驱逐 Pod
+    /// 处理不可逆故障：按处置策略打污点、cordon、驱逐 Pod
     pub async fn handle_irreversible_fault(&self, fault: &IrreversibleFault) -> Result<(), Box<dyn std::error::Error>> {
         if !self.enabled {
             eprintln!("[k8s-controller] 控制器未启用，跳过操作");
             return Ok(());
         }
         
-        let node_id = match fault {
-            IrreversibleFault::PersistentXidError { node_id, .. } |
-            IrreversibleFault::RdmaLinkDown { node_id, .. } |
-            IrreversibleFault::StorageDeviceFailure { node_id, .. } |
-            IrreversibleFault::OtherHardwareFailure { node_id, .. } => node_id,
-        };
+        let node_id = fault.node_id();
+        if !self.is_leader() {
+            println!("[k8s-controller] 本实例不是 leader，节点 {} 的故障交由 leader 处理", node_id);
+            return Ok(());
+        }
+        
+        // 已打污点的节点再次出现故障：恢复重新计时
+        self.reset_recovery(node_id).await;
         
         // 检查冷却时间
         {
@@ -137,160 +433,603 @@ impl K8sController {
             }
         }
         
+        let action = self.action_for(fault.kind()).await;
+        
+        // 在 Node 上记录事件和 Condition
+        if let Err(e) = self.report_fault(node_id, fault, &action).await.map_err(|e| e.to_string()) {
+            eprintln!("⚠️  [k8s-controller] 记录节点 {} 的 K8s 事件失败: {}", node_id, e);
+        }
+        
+        if action.notify_only {
+            println!("[k8s-controller] 节点 {} 的故障 {} 按处置策略只告警，不执行 K8s 操作", node_id, fault.kind());
+            self.processed_nodes.write().await.insert(node_id.to_string(), Instant::now());
+            return Ok(());
+        }
+        
         println!("🚨 [k8s-controller] 检测到不可逆故障: {:?}", fault);
-        println!("🔧 [k8s-controller] 开始处理节点: {}", node_id);
+        println!(
+            "🔧 [k8s-controller] 开始处理节点: {}{}",
+            node_id,
+            if self.dry_run { "（dry-run，不改动集群）" } else { "" }
+        );
         
-        // 1. 给 Node 打上 NoSchedule 污点
-        match self.taint_node(node_id, fault).await {
-            Ok(_) => {
-                println!("✅ [k8s-controller] 节点 {} 已打上 NoSchedule 污点", node_id);
-            }
-            Err(e) => {
-                eprintln!("❌ [k8s-controller] 打污点失败: {}", e);
-                return Err(e);
+        let cause = format!("fault={}", fault.describe());
+        
+        // 1. 按策略打污点、cordon
+        if action.changes_node() {
+            let details = format!("{}; action={}", cause, describe_action(&action));
+            match self.sync_node(node_id, &action, fault.kind()).await {
+                Ok(changed) => {
+                    println!("✅ [k8s-controller] 节点 {} 已隔离（{}）", node_id, describe_action(&action));
+                    if changed {
+                        self.audit(node_id, CONTROLLER_USER, "k8s.isolate", true, details);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("❌ [k8s-controller] 隔离节点失败: {}", e);
+                    self.audit(node_id, CONTROLLER_USER, "k8s.isolate", false, format!("{}; error={}", details, e));
+                    return Err(e);
+                }
             }
         }
         
+        // 登记等待恢复的节点（dry-run 时节点未被隔离，不登记）
+        if action.changes_node() && !self.dry_run {
+            let now = now_ms();
+            let mut tainted = self.tainted.write().await;
+            let node = tainted.entry(node_id.to_string()).or_insert_with(|| TaintedNode {
+                node_id: node_id.to_string(),
+                fault: fault.kind().to_string(),
+                reason: fault.describe(),
+                taint_key: None,
+                tainted_at: now,
+                last_fault: now,
+                self_test: None,
+            });
+            node.taint_key = action.taint.as_ref().map(|taint| taint.key.clone());
+        }
+        
         // 2. 驱逐该节点上的所有 Pod
-        match self.evict_pods_on_node(node_id).await {
-            Ok(count) => {
-                println!("✅ [k8s-controller] 已驱逐节点 {} 上的 {} 个 Pod", node_id, count);
-            }
-            Err(e) => {
-                eprintln!("⚠️  [k8s-controller] 驱逐 Pod 时出错: {}", e);
-                // 不返回错误，因为污点已经打上，Pod 调度器会自动处理
+        if let Some(ref evict) = action.evict {
+            match self.evict_pods_on_node(node_id, evict, &cause).await {
+                Ok(count) => {
+                    println!("✅ [k8s-controller] 已驱逐节点 {} 上的 {} 个 Pod", node_id, count);
+                }
+                Err(e) => {
+                    eprintln!("⚠️  [k8s-controller] 驱逐 Pod 时出错: {}", e);
+                    // 不返回错误，因为节点已经隔离，Pod 调度器会自动处理
+                }
             }
         }
         
         // 记录处理时间
         {
             let mut processed = self.processed_nodes.write().await;
-            processed.insert(node_id.clone(), Instant::now());
+            processed.insert(node_id.to_string(), Instant::now());
         }
         
         Ok(())
     }
     
-    /// 给 Node 打上 NoSchedule 污点
-    async fn taint_node(&self, node_id: &str, fault: &IrreversibleFault) -> Result<(), Box<dyn std::error::Error>> {
-        // 查找节点（通过 node_id 匹配 K8s Node 名称或标签）
-        // 注意：node_id 可能是 "node-a" 格式，需要映射到实际的 K8s Node 名称
+    /// 在 Node 上记录故障和处置动作
+    async fn report_fault(
+        &self,
+        node_id: &str,
+        fault: &IrreversibleFault,
+        action: &RemediationRule,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut handling = if action.notify_only { "只告警".to_string() } else { describe_action(action) };
+        if action.evict.is_some() {
+            if !handling.is_empty() {
+                handling.push('，');
+            }
+            handling.push_str("驱逐 Pod");
+        }
+        let message = format!("{}（处置：{}）", fault.describe(), handling);
+        self.report_node(node_id, CONTROLLER_USER, true, &condition_reason(fault.kind()), &message).await?;
+        Ok(())
+    }
+    
+    /// 更新 Node 的 `ArkHardwareFailure` Condition 并记录事件，两者都写入审计（by 为发起人）。
+    /// 恢复（failed 为 false）时节点不处于故障状态则不做任何操作，返回 false
+    async fn report_node(
+        &self,
+        node_id: &str,
+        by: &str,
+        failed: bool,
+        reason: &str,
+        message: &str,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
         let k8s_node_name = self.map_node_id_to_k8s_name(node_id).await?;
-        
-        // 获取当前节点
         let node = self.node_api.get(&k8s_node_name).await?;
         
-        // 构建污点
-        let taint_key = "ark.io/hardware-failure";
-        let taint_value = match fault {
-            IrreversibleFault::PersistentXidError { xid_code, .. } => {
-                format!("xid-error:{}", xid_code)
+        let status = if failed { "True" } else { "False" };
+        let previous = node.status.as_ref()
+            .and_then(|s| s.conditions.as_ref())
+            .and_then(|conditions| conditions.iter().find(|c| c.type_ == CONDITION_TYPE));
+        if !failed && previous.map(|c| c.status.as_str()) != Some("True") {
+            return Ok(false);
+        }
+        
+        let now = Time(Utc::now());
+        let last_transition_time = match previous {
+            Some(c) if c.status == status => c.last_transition_time.clone(),
+            _ => Some(now.clone()),
+        };
+        let condition = NodeCondition {
+            type_: CONDITION_TYPE.to_string(),
+            status: status.to_string(),
+            reason: Some(reason.to_string()),
+            message: Some(message.to_string()),
+            last_heartbeat_time: Some(now.clone()),
+            last_transition_time,
+        };
+        // Strategic Merge Patch 按 type 合并 Condition，不影响 kubelet 维护的其他 Condition
+        let patch = json!({
+            "status": {
+                "conditions": [condition]
             }
-            IrreversibleFault::RdmaLinkDown { interface, .. } => {
-                format!("rdma-link-down:{}", interface)
+        });
+        let details = format!("condition={}={}; reason={}; message={}", CONDITION_TYPE, status, reason, message);
+        if let Err(e) = self.node_api
+            .patch_status(&k8s_node_name, &self.patch_params(), &Patch::Strategic(patch))
+            .await
+        {
+            self.audit(node_id, by, "k8s.condition", false, format!("{}; error={}", details, e));
+            return Err(e.into());
+        }
+        self.audit(node_id, by, "k8s.condition", true, details);
+        
+        let event_type = if failed { "Warning" } else { "Normal" };
+        let event = K8sEvent {
+            metadata: ObjectMeta {
+                generate_name: Some(format!("{}.", k8s_node_name)),
+                ..ObjectMeta::default()
+            },
+            involved_object: ObjectReference {
+                api_version: Some("v1".to_string()),
+                kind: Some("Node".to_string()),
+                name: Some(k8s_node_name.clone()),
+                uid: node.metadata.uid.clone(),
+                ..ObjectReference::default()
+            },
+            reason: Some(reason.to_string()),
+            message: Some(message.to_string()),
+            type_: Some(event_type.to_string()),
+            source: Some(EventSource {
+                component: Some(EVENT_COMPONENT.to_string()),
+                host: None,
+            }),
+            first_timestamp: Some(now.clone()),
+            last_timestamp: Some(now),
+            count: Some(1),
+            ..K8sEvent::default()
+        };
+        let details = format!("event={}; reason={}; message={}", event_type, reason, message);
+        if let Err(e) = self.event_api.create(&self.post_params(), &event).await {
+            self.audit(node_id, by, "k8s.event", false, format!("{}; error={}", details, e));
+            return Err(e.into());
+        }
+        self.audit(node_id, by, "k8s.event", true, details);
+        Ok(true)
+    }
+    
+    /// 故障类型对应的处置动作
+    async fn action_for(&self, fault_kind: &str) -> RemediationRule {
+        remediation::action_for(&self.rules.read().await, fault_kind)
+    }
+    
+    /// 监听 ArkRemediationPolicy，策略变化时重新生成规则并按新策略调整已隔离的节点
+    pub fn spawn_policy_watch(self: &Arc<Self>) {
+        let controller = Arc::clone(self);
+        tokio::spawn(async move {
+            let api: Api<ArkRemediationPolicy> = Api::all(controller.client.clone());
+            let (reader, writer) = reflector::store();
+            let stream = reflector(writer, watcher(api, watcher::Config::default()))
+                .default_backoff()
+                .touched_objects();
+            futures_util::pin_mut!(stream);
+            
+            let mut last_error: Option<String> = None;
+            while let Some(result) = stream.next().await {
+                match result {
+                    Ok(_) => {
+                        last_error = None;
+                        controller.reconcile(reader.state()).await;
+                    }
+                    Err(e) => {
+                        // CRD 未安装时会持续失败，同样的错误只打印一次
+                        let message = e.to_string();
+                        if last_error.as_ref() != Some(&message) {
+                            eprintln!("[k8s-controller] 监听 ArkRemediationPolicy 失败（沿用当前处置动作）: {}", message);
+                            last_error = Some(message);
+                        }
+                    }
+                }
             }
-            IrreversibleFault::StorageDeviceFailure { device, .. } => {
-                format!("storage-failure:{}", device)
+        });
+    }
+    
+    /// 校验全部策略并回写 status，规则有变化时按新策略调整已隔离的节点
+    async fn reconcile(&self, mut policies: Vec<Arc<ArkRemediationPolicy>>) {
+        policies.sort_by_key(|policy| policy.name_any());
+        
+        let mut rules = Vec::new();
+        for policy in &policies {
+            let name = policy.name_any();
+            let result = policy.spec.validate();
+            match result {
+                Ok(()) => rules.extend(policy.spec.rules.iter().cloned()),
+                Err(ref e) => eprintln!("[k8s-controller] 处置策略 {} 无效，已忽略: {}", name, e),
             }
-            IrreversibleFault::OtherHardwareFailure { reason, .. } => {
-                format!("hardware-failure:{}", reason.replace(" ", "-"))
+            
+            let generation = policy.metadata.generation;
+            let observed = policy.status.as_ref().and_then(|status| status.observed_generation);
+            // 状态由 leader 回写，其他实例只更新规则
+            if observed != generation && self.is_leader() {
+                let status = json!({
+                    "status": {
+                        "observedGeneration": generation,
+                        "error": result.err(),
+                    }
+                });
+                let api: Api<ArkRemediationPolicy> = Api::all(self.client.clone());
+                if let Err(e) = api.patch_status(&name, &self.patch_params(), &Patch::Merge(&status)).await {
+                    eprintln!("[k8s-controller] 更新处置策略 {} 的状态失败: {}", name, e);
+                }
             }
-        };
+        }
+        
+        {
+            let mut current = self.rules.write().await;
+            if *current == rules {
+                return;
+            }
+            println!("[k8s-controller] 处置策略已更新：{} 个策略，{} 条规则", policies.len(), rules.len());
+            *current = rules;
+        }
+        
+        // 按新策略调整已隔离的节点，不重新驱逐
+        if !self.is_leader() {
+            return;
+        }
+        for node in self.tainted_nodes().await {
+            let action = self.action_for(&node.fault).await;
+            let result = self.sync_node(&node.node_id, &action, &node.fault).await.map_err(|e| e.to_string());
+            let details = format!("fault={}; action={}; 处置策略已更新", node.reason, describe_action(&action));
+            match result {
+                Ok(true) => self.audit(&node.node_id, CONTROLLER_USER, "k8s.resync", true, details),
+                Ok(_) => {}
+                Err(ref e) => self.audit(&node.node_id, CONTROLLER_USER, "k8s.resync", false, format!("{}; error={}", details, e)),
+            }
+            match result {
+                Ok(_) if action.changes_node() => {
+                    if let Some(tracked) = self.tainted.write().await.get_mut(&node.node_id) {
+                        tracked.taint_key = action.taint.as_ref().map(|taint| taint.key.clone());
+                    }
+                }
+                Ok(_) => {
+                    self.tainted.write().await.remove(&node.node_id);
+                    println!("[k8s-controller] 节点 {} 按新的处置策略解除隔离", node.node_id);
+                }
+                Err(e) => eprintln!("[k8s-controller] 按新的处置策略调整节点 {} 失败: {}", node.node_id, e),
+            }
+        }
+    }
+    
+    /// 让节点的污点和 cordon 状态与处置动作一致：去掉 Hub 管理的其他污点，只 uncordon Hub cordon 过的节点。
+    /// 节点有改动时返回 true
+    async fn sync_node(
+        &self,
+        node_id: &str,
+        action: &RemediationRule,
+        fault_kind: &str,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        // Hub 管理的污点：默认污点、各策略的污点以及该节点当前登记的污点
+        let mut managed: Vec<String> = vec![TAINT_KEY.to_string()];
+        managed.extend(self.rules.read().await.iter().filter_map(|rule| rule.taint.as_ref().map(|taint| taint.key.clone())));
+        if let Some(key) = self.tainted.read().await.get(node_id).and_then(|node| node.taint_key.clone()) {
+            managed.push(key);
+        }
         
-        // 检查污点是否已存在
-        let mut taints = node.spec.as_ref()
+        // 查找节点（通过 node_id 匹配 K8s Node 名称或标签）
+        // 注意：node_id 可能是 "node-a" 格式，需要映射到实际的 K8s Node 名称
+        let k8s_node_name = self.map_node_id_to_k8s_name(node_id).await?;
+        let node = self.node_api.get(&k8s_node_name).await?;
+        
+        let taints = node.spec.as_ref()
             .and_then(|s| s.taints.clone())
             .unwrap_or_default();
+        let wanted = action.taint.as_ref().filter(|_| !action.notify_only);
+        let mut updated: Vec<_> = taints
+            .iter()
+            .filter(|t| {
+                match wanted {
+                    // 已有相同的污点时保留原样
+                    Some(taint) if taint.key == t.key => t.effect == taint.effect,
+                    _ => !managed.contains(&t.key),
+                }
+            })
+            .cloned()
+            .collect();
+        if let Some(taint) = wanted {
+            if !updated.iter().any(|t| t.key == taint.key) {
+                updated.push(k8s_openapi::api::core::v1::Taint {
+                    key: taint.key.clone(),
+                    value: Some(fault_kind.replace('_', "-")),
+                    effect: taint.effect.clone(),
+                    time_added: None,
+                });
+            }
+        }
         
-        // 检查是否已有相同 key 的污点
-        if !taints.iter().any(|t| t.key.as_deref() == Some(taint_key)) {
-            // 添加新污点
-            taints.push(k8s_openapi::api::core::v1::Taint {
-                key: Some(taint_key.to_string()),
-                value: Some(taint_value),
-                effect: Some("NoSchedule".to_string()),
-                time_added: None,
-            });
-            
-            // 使用 JSON Patch 更新节点
-            let patch = json!({
-                "spec": {
-                    "taints": taints
+        let unschedulable = node.spec.as_ref().and_then(|s| s.unschedulable).unwrap_or(false);
+        let cordoned_by_hub = node.annotations().contains_key(CORDON_ANNOTATION);
+        let cordon = action.cordon && !action.notify_only;
+        
+        let mut patch = json!({});
+        if updated != taints {
+            // Merge Patch 整体替换污点列表
+            patch["spec"]["taints"] = json!(updated);
+        }
+        if cordon && !unschedulable {
+            patch["spec"]["unschedulable"] = json!(true);
+            patch["metadata"]["annotations"][CORDON_ANNOTATION] = json!("true");
+        } else if !cordon && cordoned_by_hub {
+            // 运维手动 cordon 的节点不动
+            patch["spec"]["unschedulable"] = json!(false);
+            patch["metadata"]["annotations"][CORDON_ANNOTATION] = serde_json::Value::Null;
+        }
+        if patch == json!({}) {
+            println!("[k8s-controller] 节点 {} 已符合处置策略，跳过", k8s_node_name);
+            return Ok(false);
+        }
+        if self.dry_run {
+            println!("[k8s-controller] [dry-run] 节点 {} 的变更（不生效）: {}", k8s_node_name, patch);
+        }
+        
+        self.node_api
+            .patch(&k8s_node_name, &self.patch_params(), &Patch::Merge(patch))
+            .await?;
+        Ok(true)
+    }
+    
+    /// 已隔离、等待恢复的节点
+    pub async fn tainted_nodes(&self) -> Vec<TaintedNode> {
+        let mut nodes: Vec<TaintedNode> = self.tainted.read().await.values().cloned().collect();
+        nodes.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+        nodes
+    }
+    
+    /// 故障事件已停止超过 quiet 的节点
+    pub async fn recovery_due(&self, quiet: Duration) -> Vec<TaintedNode> {
+        let now = now_ms();
+        let quiet_ms = quiet.as_millis() as u64;
+        self.tainted_nodes()
+            .await
+            .into_iter()
+            .filter(|node| now.saturating_sub(node.last_fault) >= quiet_ms)
+            .collect()
+    }
+    
+    /// 记录下发给节点的自检命令
+    pub async fn self_test_started(&self, node_id: &str, command_id: &str) {
+        if let Some(node) = self.tainted.write().await.get_mut(node_id) {
+            node.self_test = Some(command_id.to_string());
+        }
+    }
+    
+    /// 再次出现故障或自检未通过：恢复重新计时
+    pub async fn reset_recovery(&self, node_id: &str) {
+        if let Some(node) = self.tainted.write().await.get_mut(node_id) {
+            node.last_fault = now_ms();
+            node.self_test = None;
+        }
+    }
+    
+    /// 自动恢复：对故障事件已停止 quiet 的节点下发自检（节点离线时等它重连），自检通过后解除隔离，
+    /// 未通过或超时则重新计时
+    pub async fn check_recovery(
+        &self,
+        quiet: Duration,
+        connections: &DashMap<String, mpsc::UnboundedSender<Message>>,
+        commands: &CommandStore,
+        alerts: &Arc<Alerter>,
+    ) {
+        if !self.is_leader() {
+            return;
+        }
+        for node in self.recovery_due(quiet).await {
+            let Some(command_id) = node.self_test else {
+                let command_id = crate::new_command_id();
+                let command = json!({
+                    "intent": "self_test",
+                    "command_id": command_id,
+                    "target_pid": 0,
+                    "requested_by": CONTROLLER_USER,
+                });
+                let sent = match connections.get(&node.node_id) {
+                    Some(sender) => {
+                        commands.issue(&command_id, &node.node_id, 0, "SelfTest", None, Some(CONTROLLER_USER.to_string()));
+                        sender.send(Message::Text(command.to_string())).is_ok()
+                    }
+                    None => false,
+                };
+                if sent {
+                    println!(
+                        "[k8s-controller] 节点 {} 已 {} 秒未上报故障，下发自检命令 {}",
+                        node.node_id,
+                        quiet.as_secs(),
+                        command_id
+                    );
+                    self.self_test_started(&node.node_id, &command_id).await;
                 }
-            });
+                continue;
+            };
             
-            let params = PatchParams::apply("ark-controller");
-            self.node_api
-                .patch(&k8s_node_name, &params, &Patch::Apply(patch))
-                .await?;
-        } else {
-            println!("[k8s-controller] 节点 {} 已有污点，跳过", k8s_node_name);
+            let failure = match commands.get(&command_id) {
+                Some(record) if record.status == commands::PENDING => {
+                    if now_ms().saturating_sub(record.issued_at) < SELF_TEST_TIMEOUT.as_millis() as u64 {
+                        continue;
+                    }
+                    "自检超时".to_string()
+                }
+                Some(record) if record.status == "success" => {
+                    let reason = format!("{} 秒未再上报故障且自检通过", quiet.as_secs());
+                    match self.recover(&node.node_id, CONTROLLER_USER, &reason, alerts).await.map_err(|e| e.to_string()) {
+                        Ok(_) => continue,
+                        Err(e) => format!("解除隔离失败: {}", e),
+                    }
+                }
+                Some(record) => record.message.unwrap_or(record.status),
+                None => "自检命令记录已丢失".to_string(),
+            };
+            eprintln!("[k8s-controller] 节点 {} 未能自动恢复（{}），重新计时", node.node_id, failure);
+            self.reset_recovery(&node.node_id).await;
         }
-        
-        Ok(())
     }
     
-    /// 驱逐节点上的所有 Pod
-    async fn evict_pods_on_node(&self, node_id: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    /// 节点已修复：解除污点和 Hub 做的 cordon，写入审计并发出恢复告警。节点没有被隔离时返回 false
+    pub async fn recover(
+        &self,
+        node_id: &str,
+        by: &str,
+        reason: &str,
+        alerts: &Arc<Alerter>,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        if !self.enabled {
+            return Err("控制器未启用".into());
+        }
+        if !self.is_leader() {
+            return Err("本实例不是 K8s 控制器的 leader，请向 leader 实例发送请求".into());
+        }
+        let removed = match self.sync_node(node_id, &RemediationRule::default(), "").await {
+            Ok(removed) => removed,
+            Err(e) => {
+                self.audit(node_id, by, "k8s.untaint", false, format!("{}; error={}", reason, e));
+                return Err(e);
+            }
+        };
+        self.tainted.write().await.remove(node_id);
+        self.processed_nodes.write().await.remove(node_id);
+        if let Err(e) = self.report_node(node_id, by, false, "Repaired", reason).await.map_err(|e| e.to_string()) {
+            eprintln!("⚠️  [k8s-controller] 更新节点 {} 的 {} Condition 失败: {}", node_id, CONDITION_TYPE, e);
+        }
+        if removed {
+            println!("✅ [k8s-controller] 节点 {} 已解除隔离（{}）", node_id, reason);
+            self.audit(node_id, by, "k8s.untaint", true, reason.to_string());
+            alerts.fire(Alert::recovered(node_id, format!("{}，已解除隔离", reason)));
+        }
+        Ok(removed)
+    }
+    
+    /// 按驱逐策略驱逐节点上的 Pod：跳过 DaemonSet、静态 Pod 和排除的 Pod，按优先级从低到高分批，
+    /// 每批最多 max_parallel 个并行。每个 Pod 的驱逐带 cause 写入审计，返回驱逐成功的 Pod 数
+    async fn evict_pods_on_node(
+        &self,
+        node_id: &str,
+        evict: &EvictSpec,
+        cause: &str,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
         let k8s_node_name = self.map_node_id_to_k8s_name(node_id).await?;
         
-        // 列出所有 Pod
-        let pods = self.pod_api.list(&Default::default()).await?;
+        // 只列出该节点上的 Pod
+        let params = ListParams::default().fields(&format!("spec.nodeName={}", k8s_node_name));
+        let pods = self.pod_api.list(&params).await?;
         
-        // 筛选出在该节点上的 Pod
-        let pods_on_node: Vec<_> = pods
+        let mut candidates: Vec<&Pod> = pods
             .iter()
             .filter(|pod| {
-                pod.spec.as_ref()
-                    .and_then(|s| s.node_name.as_deref())
-                    == Some(&k8s_node_name)
+                // 跳过 DaemonSet Pod（系统 Pod）和静态 Pod
+                let owned_by_node = pod.metadata.owner_references.as_ref().is_some_and(|owner_refs| {
+                    owner_refs.iter().any(|ref_| ref_.kind == "DaemonSet" || ref_.kind == "Node")
+                });
+                let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+                !owned_by_node && evict.should_evict(namespace, pod.labels())
             })
             .collect();
         
+        // 低优先级的 Pod 先驱逐；不分批时所有 Pod 为同一批
+        candidates.sort_by_key(|pod| pod_priority(pod));
+        let waves: Vec<&[&Pod]> = if evict.priority_order {
+            candidates.chunk_by(|a, b| pod_priority(a) == pod_priority(b)).collect()
+        } else {
+            vec![&candidates[..]]
+        };
+        
         let mut evicted_count = 0;
+        for wave in waves {
+            // 先收集为 Vec：stream 中带借用闭包时 tokio::spawn 无法证明 Future 的生命周期（FnOnce not general enough）
+            let evictions: Vec<_> = wave.iter().map(|pod| self.evict_pod(node_id, pod, evict, cause)).collect();
+            evicted_count += futures_util::stream::iter(evictions)
+                .buffer_unordered(evict.max_parallel.max(1) as usize)
+                .filter(|evicted| std::future::ready(*evicted))
+                .count()
+                .await;
+        }
         
-        for pod in pods_on_node {
-            // 跳过 DaemonSet Pod（系统 Pod）
-            if let Some(owner_refs) = &pod.metadata.owner_references {
-                if owner_refs.iter().any(|ref_| {
-                    ref_.kind == "DaemonSet" || ref_.kind == "Node"
-                }) {
-                    continue;
-                }
+        Ok(evicted_count)
+    }
+    
+    /// 驱逐单个 Pod（Eviction API，尊重 PDB），被 PDB 拒绝时按 pdb_retry_seconds 重试
+    async fn evict_pod(&self, node_id: &str, pod: &Pod, evict: &EvictSpec, cause: &str) -> bool {
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        let Some(pod_name) = pod.metadata.name.as_deref() else {
+            return false;
+        };
+        
+        // 使用 Pod Eviction Subresource API
+        // 这是生产级实现：尊重 PodDisruptionBudget，优雅处理退出信号
+        let pod_api: Api<Pod> = Api::namespaced(self.client.clone(), namespace);
+        
+        // 构建 Eviction 请求体
+        let eviction = Eviction {
+            metadata: ObjectMeta {
+                name: Some(pod_name.to_string()),
+                namespace: Some(namespace.to_string()),
+                ..ObjectMeta::default()
+            },
+            delete_options: evict.grace_period_seconds.map(|secs| DeleteOptions {
+                grace_period_seconds: Some(secs),
+                ..DeleteOptions::default()
+            }),
+        };
+        let details = format!("pod={}/{}; {}", namespace, pod_name, cause);
+        let eviction_body = match serde_json::to_vec(&eviction) {
+            Ok(body) => body,
+            Err(e) => {
+                eprintln!("[k8s-controller] ⚠️  序列化 Pod {}/{} 的 Eviction 失败: {}", namespace, pod_name, e);
+                self.audit(node_id, CONTROLLER_USER, "k8s.evict", false, format!("{}; error={}", details, e));
+                return false;
             }
-            
-            // 执行优雅驱逐（使用 Eviction API，尊重 PDB）
-            let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
-            let pod_name = pod.metadata.name.as_deref().ok_or("Pod name is missing")?;
-            
-            // 使用 Pod Eviction Subresource API
-            // 这是生产级实现：尊重 PodDisruptionBudget，优雅处理退出信号
-            let pod_api: Api<Pod> = Api::namespaced(self.client.clone(), namespace);
-            
-            // 构建 Eviction 请求体
-            let eviction_body = serde_json::json!({
-                "apiVersion": "policy/v1",
-                "kind": "Eviction",
-                "metadata": {
-                    "name": pod_name,
-                    "namespace": namespace
-                }
-            });
-            
-            // 使用 kube 的 create_subresource 调用 Eviction API
+        };
+        
+        let deadline = Instant::now() + Duration::from_secs(evict.pdb_retry_seconds);
+        loop {
+            // 使用 kube 的 create_subresource 调用 Eviction API（API Server 返回的是 Status 而非 Eviction）
             // 这会触发 Pod 的优雅关闭流程，并尊重 PDB 限制
-            match pod_api.create_subresource("eviction", pod_name, &Default::default(), &eviction_body).await {
+            match pod_api
+                .create_subresource::<kube::core::Status>("eviction", pod_name, &self.post_params(), eviction_body.clone())
+                .await
+            {
                 Ok(_) => {
-                    evicted_count += 1;
                     println!(
-                        "[k8s-controller] ✅ 已优雅驱逐 Pod: {}/{} (尊重 PDB)",
+                        "[k8s-controller] ✅ {}已优雅驱逐 Pod: {}/{} (尊重 PDB)",
+                        if self.dry_run { "[dry-run] " } else { "" },
                         namespace,
                         pod_name
                     );
+                    self.audit(node_id, CONTROLLER_USER, "k8s.evict", true, details);
+                    return true;
+                }
+                // PDB 不允许此时驱逐：在重试时长内稍后再试
+                Err(kube::Error::Api(ref e)) if e.code == 429 && Instant::now() + PDB_RETRY_INTERVAL <= deadline => {
+                    println!("[k8s-controller] Pod {}/{} 受 PDB 限制暂不能驱逐，{:?} 后重试", namespace, pod_name, PDB_RETRY_INTERVAL);
+                    tokio::time::sleep(PDB_RETRY_INTERVAL).await;
                 }
                 Err(e) => {
                     // Eviction API 可能因为 PDB 限制而失败，这是正常行为
-                    // 我们记录警告但不中断流程（因为污点已经打上，调度器会处理）
+                    // 我们记录警告但不中断流程（因为节点已经隔离，调度器会处理）
                     eprintln!(
                         "[k8s-controller] ⚠️  驱逐 Pod {}/{} 失败（可能受 PDB 限制）: {}",
                         namespace,
@@ -298,13 +1037,13 @@ impl K8sController {
                         e
                     );
                     eprintln!(
-                        "[k8s-controller]   提示：节点已打上 NoSchedule 污点，调度器将自动处理新 Pod 的调度"
+                        "[k8s-controller]   提示：节点已隔离，调度器将自动处理新 Pod 的调度"
                     );
+                    self.audit(node_id, CONTROLLER_USER, "k8s.evict", false, format!("{}; error={}", details, e));
+                    return false;
                 }
             }
         }
-        
-        Ok(evicted_count)
     }
     
     /// 将 Ark node_id 映射到 K8s Node 名称
@@ -346,3 +1085,34 @@ impl K8sController {
         Ok(node_id.to_string())
     }
 }
+
+/// 处置动作的简短描述，用于日志
+fn describe_action(action: &RemediationRule) -> String {
+    let mut parts = Vec::new();
+    if let Some(ref taint) = action.taint {
+        parts.push(format!("污点 {}:{}", taint.key, taint.effect));
+    }
+    if action.cordon {
+        parts.push("cordon".to_string());
+    }
+    parts.join("，")
+}
+
+/// Condition 和事件的 reason：故障类型转为驼峰（xid_error -> XidError）
+fn condition_reason(fault_kind: &str) -> String {
+    fault_kind
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Pod 的调度优先级（未设置 PriorityClass 时为 0）
+fn pod_priority(pod: &Pod) -> i32 {
+    pod.spec.as_ref().and_then(|spec| spec.priority).unwrap_or(0)
+}
@@ -0,0 +1,222 @@
+//! K8s 控制器的 Leader 选举
+//!
+//! 多个 Hub 副本同时启用 `--enable-k8s-controller` 时，只有持有 Lease（`coordination.k8s.io/v1`，
+//! 默认 `<Hub 所在命名空间>/ark-hub-controller`）的实例执行 K8s 变更：打污点、cordon、驱逐、解除隔离和回写策略状态；
+//! 其余实例照常判定故障、告警。Leader 每 `lease_duration / 3` 续约一次，超过 `lease_duration` 未续约时其他实例接管。
+//! 是否过期按本地单调时钟判断（与 client-go 的 leaderelection 相同）：记录本实例观察到 Lease 的持有者或 renewTime
+//! 变化的时刻，此后超过 `leaseDurationSeconds` 不再变化即视为过期，不比较对方写入的 renewTime 与本地墙钟，不受时钟偏差影响。
+//! 续约出错或超过续约期限（`lease_duration * 2 / 3`）未完成时立即放弃 leader 身份；距上次成功续约超过续约期限时
+//! `is_leader()` 也不再返回 true（API Server 无响应、续约卡住时，不会在 Lease 过期、其他实例接管后继续操作节点）。
+//! 宁可短暂无人处置也不让两个实例同时操作节点。Hub 正常退出时释放 Lease。
+//! 实例标识取 `POD_NAME`（Deployment 中通过 Downward API 注入），其次 `HOSTNAME`。
+
+use chrono::Utc;
+use k8s_openapi::api::coordination::v1::{Lease, LeaseSpec};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{MicroTime, ObjectMeta};
+use kube::api::PostParams;
+use kube::{Api, Client};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::metrics::HubMetricsCollector;
+
+/// 默认的 Lease 名称
+pub const DEFAULT_LEASE_NAME: &str = "ark-hub-controller";
+
+/// 基于 Lease 的 Leader 选举
+pub struct LeaderElector {
+    api: Api<Lease>,
+    lease_name: String,
+    identity: String,
+    lease_duration: Duration,
+    /// 续约期限：单次续约的超时，也是上次成功续约后仍视为 leader 的时长（小于 lease_duration）
+    renew_deadline: Duration,
+    leader: AtomicBool,
+    /// 上次成功写入 Lease 的时间（以发起请求的时间计，不晚于写入 Lease 的 renewTime）
+    last_renew: Mutex<Option<Instant>>,
+    /// 上次观察到的 Lease 持有记录及其变化的本地时刻，用于判断 Lease 是否过期
+    observed: Mutex<Option<ObservedLease>>,
+    metrics: Arc<HubMetricsCollector>,
+}
+
+/// 观察到的 Lease 持有记录
+struct ObservedLease {
+    holder_identity: Option<String>,
+    renew_time: Option<MicroTime>,
+    /// 本实例观察到该记录的时刻（本地单调时钟）
+    observed_at: Instant,
+}
+
+impl LeaderElector {
+    /// 在 client 的默认命名空间（in-cluster 时为 Hub 所在命名空间）中竞选 lease_name
+    pub fn new(client: Client, lease_name: &str, lease_duration: Duration, metrics: Arc<HubMetricsCollector>) -> Self {
+        let identity = std::env::var("POD_NAME")
+            .or_else(|_| std::env::var("HOSTNAME"))
+            .unwrap_or_else(|_| format!("ark-hub-{:08x}", rand::random::<u32>()));
+        metrics.set_k8s_leader(&identity, false);
+        Self {
+            api: Api::default_namespaced(client),
+            lease_name: lease_name.to_string(),
+            identity,
+            lease_duration,
+            renew_deadline: lease_duration * 2 / 3,
+            leader: AtomicBool::new(false),
+            last_renew: Mutex::new(None),
+            observed: Mutex::new(None),
+            metrics,
+        }
+    }
+
+    pub fn identity(&self) -> &str {
+        &self.identity
+    }
+
+    /// 是否为 leader：须在续约期限内成功续约过
+    pub fn is_leader(&self) -> bool {
+        if !self.leader.load(Ordering::Relaxed) {
+            return false;
+        }
+        let last_renew = *self.last_renew.lock().unwrap_or_else(|e| e.into_inner());
+        last_renew.is_some_and(|renewed| renewed.elapsed() < self.renew_deadline)
+    }
+
+    /// 启动竞选 / 续约循环
+    pub fn spawn(self: &Arc<Self>) {
+        let elector = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(elector.lease_duration / 3);
+            loop {
+                interval.tick().await;
+                let started = Instant::now();
+                match tokio::time::timeout(elector.renew_deadline, elector.try_acquire_or_renew()).await {
+                    Ok(Ok(leader)) => {
+                        if leader {
+                            *elector.last_renew.lock().unwrap_or_else(|e| e.into_inner()) = Some(started);
+                        }
+                        elector.set_leader(leader);
+                    }
+                    Ok(Err(e)) => {
+                        eprintln!("[k8s-controller] 续约 Lease {} 失败: {}", elector.lease_name, e);
+                        elector.set_leader(false);
+                    }
+                    Err(_) => {
+                        eprintln!(
+                            "[k8s-controller] 续约 Lease {} 超时（{:.1} 秒）",
+                            elector.lease_name,
+                            elector.renew_deadline.as_secs_f64()
+                        );
+                        elector.set_leader(false);
+                    }
+                }
+            }
+        });
+    }
+
+    /// 退出前释放 Lease，其他实例不必等待过期即可接管
+    pub async fn release(&self) {
+        if !self.leader.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        self.metrics.set_k8s_leader(&self.identity, false);
+        let result = match self.api.get_opt(&self.lease_name).await {
+            Ok(Some(mut lease)) => {
+                let spec = lease.spec.get_or_insert_with(LeaseSpec::default);
+                if spec.holder_identity.as_deref() != Some(self.identity.as_str()) {
+                    return;
+                }
+                spec.holder_identity = None;
+                spec.renew_time = None;
+                self.api.replace(&self.lease_name, &PostParams::default(), &lease).await.map(|_| ())
+            }
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => println!("[k8s-controller] 已释放 Lease {}", self.lease_name),
+            Err(e) => eprintln!("[k8s-controller] 释放 Lease {} 失败: {}", self.lease_name, e),
+        }
+    }
+
+    fn set_leader(&self, leader: bool) {
+        if self.leader.swap(leader, Ordering::Relaxed) == leader {
+            return;
+        }
+        if leader {
+            println!("✅ [k8s-controller] 实例 {} 成为 leader（Lease {}），开始执行 K8s 操作", self.identity, self.lease_name);
+            self.metrics.record_k8s_leader_acquired(&self.identity);
+        } else {
+            eprintln!("⚠️  [k8s-controller] 实例 {} 不再是 leader，停止执行 K8s 操作", self.identity);
+        }
+        self.metrics.set_k8s_leader(&self.identity, leader);
+    }
+
+    /// 记录观察到的持有者和 renewTime，与上次不同时以当前时刻重新计时；返回距最近一次变化的时长
+    fn observe(&self, spec: &LeaseSpec) -> Duration {
+        let mut observed = self.observed.lock().unwrap_or_else(|e| e.into_inner());
+        let unchanged = observed.as_ref().is_some_and(|o| {
+            o.holder_identity == spec.holder_identity && o.renew_time == spec.renew_time
+        });
+        if !unchanged {
+            *observed = Some(ObservedLease {
+                holder_identity: spec.holder_identity.clone(),
+                renew_time: spec.renew_time.clone(),
+                observed_at: Instant::now(),
+            });
+        }
+        observed.as_ref().map_or(Duration::ZERO, |o| o.observed_at.elapsed())
+    }
+
+    /// 取得或续约 Lease，返回本实例是否为 leader。以 resourceVersion 做乐观并发，冲突时视为未取得
+    async fn try_acquire_or_renew(&self) -> Result<bool, kube::Error> {
+        let now = MicroTime(Utc::now());
+        let duration_secs = self.lease_duration.as_secs().max(1) as i32;
+
+        let Some(mut lease) = self.api.get_opt(&self.lease_name).await? else {
+            let lease = Lease {
+                metadata: ObjectMeta {
+                    name: Some(self.lease_name.clone()),
+                    ..ObjectMeta::default()
+                },
+                spec: Some(LeaseSpec {
+                    holder_identity: Some(self.identity.clone()),
+                    lease_duration_seconds: Some(duration_secs),
+                    acquire_time: Some(now.clone()),
+                    renew_time: Some(now),
+                    lease_transitions: Some(0),
+                }),
+            };
+            return conflict_as_false(self.api.create(&PostParams::default(), &lease).await);
+        };
+
+        let spec = lease.spec.get_or_insert_with(LeaseSpec::default);
+        let unchanged_for = self.observe(spec);
+        let held_by_self = spec.holder_identity.as_deref() == Some(self.identity.as_str());
+        if !held_by_self {
+            let held = spec.holder_identity.as_deref().is_some_and(|holder| !holder.is_empty());
+            // 持有者在 leaseDurationSeconds 内（按本地时钟）没有续约即视为过期
+            let expired = match spec.lease_duration_seconds {
+                Some(secs) => unchanged_for > Duration::from_secs(secs.max(0) as u64),
+                None => true,
+            };
+            if held && !expired {
+                return Ok(false);
+            }
+            spec.holder_identity = Some(self.identity.clone());
+            spec.acquire_time = Some(now.clone());
+            spec.lease_transitions = Some(spec.lease_transitions.unwrap_or(0) + 1);
+        }
+        spec.lease_duration_seconds = Some(duration_secs);
+        spec.renew_time = Some(now);
+        conflict_as_false(self.api.replace(&self.lease_name, &PostParams::default(), &lease).await)
+    }
+}
+
+/// 写入 Lease 成功为 true；409（其他实例抢先写入）为 false
+fn conflict_as_false(result: Result<Lease, kube::Error>) -> Result<bool, kube::Error> {
+    match result {
+        Ok(_) => Ok(true),
+        Err(kube::Error::Api(e)) if e.code == 409 => Ok(false),
+        Err(e) => Err(e),
+    }
+}
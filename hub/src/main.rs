@@ -3,21 +3,69 @@
 //! 接收各节点的 WebSocket 连接，维护全局状态图
 //! 提供跨节点的根因分析和集群级修复能力
 
-use ark_core::event::Event;
-use ark_core::graph::{StateGraph, NodeType};
+use ark_core::event::{Event, EventType};
+use ark_core::export::ExportFormat;
+use ark_core::graph::{GraphConfig, Node, NodeKey, StateGraph};
+use ark_core::rules::{rules_key_from_env, RuleBundle};
+use ark_core::straggler::DEFAULT_STRAGGLER_MARGIN;
 use clap::Parser;
 use std::sync::Arc;
 use tokio::sync::{RwLock, mpsc};
-use tokio_tungstenite::{accept_async, tungstenite::Message, WebSocketStream, MaybeTlsStream};
+use tokio_tungstenite::{accept_hdr_async, tungstenite::Message, WebSocketStream, MaybeTlsStream};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
 use futures_util::{SinkExt, StreamExt};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
 use warp::Filter;
 use serde_json::json;
 use dashmap::DashMap;
+use tracing::Instrument;
 mod metrics;
 mod k8s_controller;
+mod alerts;
+mod approvals;
+mod audit;
+mod auth;
+mod commands;
+mod delivery;
+mod events;
+mod federation;
+mod flow;
+mod health;
+mod jobs;
+mod leader;
+mod nodes;
+mod oidc;
+mod remediation;
+mod remote_write;
+mod scene;
+mod shutdown;
+mod storage;
+mod telemetry;
+mod tls;
+use alerts::{Alert, AlertConfig, Alerter, SilenceRequest};
+use approvals::{is_destructive_action, ApprovalRequest, ApprovalStore, VerifyRequest, DEFAULT_APPROVAL_THRESHOLD};
+use audit::{AuditMessage, AuditStore};
+use auth::{agent_may_act_as, HubAuth, Principal, Role};
+use commands::{AgentReport, CommandResult, CommandStore};
+use oidc::{OidcConfig, OidcVerifier};
+use remote_write::{RemoteWriteConfig, RemoteWriter};
+use delivery::{DeliveryTracker, Envelope};
+use events::{EventHistory, DEFAULT_EVENT_HISTORY};
+use federation::Upstream;
+use flow::FlowControl;
+use health::HealthReport;
+use tls::TlsFiles;
+use jobs::JobIndex;
+use nodes::{ControlMessage, NodeRegistry};
+use scene::{ClusterAnalysis, ClusterSceneIdentifier, JobScope, SWITCH_LABEL};
+use storage::StorageHandle;
 use metrics::HubMetricsCollector;
-use k8s_controller::K8sController;
+use k8s_controller::{FaultDetector, FaultPolicy, K8sController};
+
+/// 已结束的 job 在索引中保留的时间（毫秒）
+const JOB_RETENTION_MS: u64 = 60 * 60 * 1000;
 
 #[derive(Parser)]
 #[command(name = "ark-hub")]
@@ -32,6 +80,95 @@ struct Cli {
     /// 启用 Kubernetes 控制器（自动打污点和驱逐 Pod）
     #[arg(long)]
     enable_k8s_controller: bool,
+    /// 故障节点超过该时长（秒）未再上报故障事件时下发自检，通过后自动解除污点（未指定时只能通过 API 解除）
+    #[arg(long)]
+    taint_recovery_secs: Option<u64>,
+    /// 不可逆故障判定策略（YAML：统计窗口、次数阈值、致命和可恢复的 XID）
+    #[arg(long)]
+    fault_policy: Option<std::path::PathBuf>,
+    /// K8s 控制器只演练：打污点、驱逐等变更请求带 dryRun=All 发送，记录到日志和审计但不改动集群
+    #[arg(long, requires = "enable_k8s_controller")]
+    k8s_dry_run: bool,
+    /// 关闭 K8s 控制器的 Leader 选举（仅单副本部署时使用；多副本时各实例会同时操作节点）
+    #[arg(long)]
+    no_leader_election: bool,
+    /// Leader 选举使用的 Lease 名称（位于 Hub 所在命名空间）
+    #[arg(long, default_value = leader::DEFAULT_LEASE_NAME)]
+    leader_lease: String,
+    /// Lease 有效期（秒），leader 每 1/3 有效期续约一次，超过有效期未续约时其他实例接管
+    #[arg(long, default_value_t = 15)]
+    leader_lease_duration_secs: u64,
+    /// 全局状态图配置文件（YAML，可配置错误窗口、清理策略、容量上限）
+    #[arg(long)]
+    graph_config: Option<std::path::PathBuf>,
+    /// 下发给 Agent 的规则目录（GET /api/v1/rules；设置 ARK_RULES_KEY 时签名）
+    #[arg(long)]
+    rules_dir: Option<std::path::PathBuf>,
+    /// 集中保存 Agent 上报的审计记录的 JSONL 文件（GET /api/v1/audit；未设置时只保存在内存中）
+    #[arg(long)]
+    audit_log: Option<std::path::PathBuf>,
+    /// 持久化全局状态图和事件的数据库（如 sqlite:///var/lib/ark/hub.db?mode=rwc 或 postgres://user@host/ark；
+    /// 需以 --features storage 构建），启动时从中恢复状态图
+    #[arg(long)]
+    storage: Option<String>,
+    /// 状态图快照间隔（秒，配置了 --storage 时生效）
+    #[arg(long, default_value_t = 300)]
+    snapshot_interval_secs: u64,
+    /// 超过该时间（秒）未收到心跳的节点标记为 stale
+    #[arg(long, default_value_t = 45)]
+    node_stale_secs: u64,
+    /// TLS 证书（PEM，与 --tls-key 同时指定时 WebSocket 监听 wss://、HTTP API 监听 https://）
+    #[arg(long)]
+    tls_cert: Option<std::path::PathBuf>,
+    /// TLS 私钥（PEM）
+    #[arg(long)]
+    tls_key: Option<std::path::PathBuf>,
+    /// 校验 Agent 客户端证书的 CA（PEM，指定后 WebSocket 连接须出示由它签发的证书）
+    #[arg(long)]
+    tls_client_ca: Option<std::path::PathBuf>,
+    /// Agent 令牌文件（每行 `<node_id> <token>`，node_id 为 * 时不限节点），指定后 WebSocket 握手须携带令牌
+    #[arg(long)]
+    agent_tokens: Option<std::path::PathBuf>,
+    /// API 密钥文件（每行 `<role> <key> [name]`，role 为 viewer / operator / admin，省略时为 admin；
+    /// name 为持有人身份，用作修复发起人和审批人），指定后 /api/v1/* 请求须携带 `Authorization: Bearer <key>`
+    #[arg(long)]
+    api_keys: Option<std::path::PathBuf>,
+    /// OIDC 身份提供方（如 https://sso.example.com/realms/infra），指定后 API 也接受它签发的 JWT
+    #[arg(long)]
+    oidc_issuer: Option<String>,
+    /// JWT 的 aud（未指定时不校验）
+    #[arg(long)]
+    oidc_audience: Option<String>,
+    /// JWT 中携带角色的声明（支持嵌套路径，如 realm_access.roles）
+    #[arg(long, default_value = "roles")]
+    oidc_role_claim: String,
+    /// 集群修复涉及的进程数超过该值时，必须携带第二位运维签发的审批 token
+    #[arg(long, default_value_t = DEFAULT_APPROVAL_THRESHOLD)]
+    approval_threshold: usize,
+    /// 告警配置文件（YAML：Webhook 地址、模板、去重和限流），规则命中、跨节点场景和不可逆故障推送到 Webhook
+    #[arg(long)]
+    alert_config: Option<std::path::PathBuf>,
+    /// 内存中保留的最近事件数（GET /api/v1/events；0 表示不保留）
+    #[arg(long, default_value_t = DEFAULT_EVENT_HISTORY)]
+    event_history: usize,
+    /// 消息平均处理耗时超过该值（毫秒）时通知 Agent 暂停推送利用率类事件
+    #[arg(long, default_value_t = 100)]
+    flow_slow_ms: u64,
+    /// 退出时等待 Agent 断开的最长时间（秒），超时后关闭剩余连接
+    #[arg(long, default_value_t = 15)]
+    shutdown_timeout_secs: u64,
+    /// Prometheus remote_write 配置文件（YAML：地址、认证、附加标签），定期把指标推送到中心 Prometheus / Mimir
+    #[arg(long)]
+    remote_write_config: Option<std::path::PathBuf>,
+    /// 上级 Hub 的 WebSocket 地址（联邦：把本 Hub 收到的事件转发过去，令牌取自 ARK_UPSTREAM_TOKEN）
+    #[arg(long, requires = "cluster_id")]
+    upstream_hub: Option<String>,
+    /// 本 Hub 在上级 Hub 中的集群名（转发事件的 node_id 改写为 <cluster_id>/<node_id>）
+    #[arg(long)]
+    cluster_id: Option<String>,
+    /// 校验上级 Hub 证书的 CA（PEM，wss:// 使用私有 CA 时指定）
+    #[arg(long)]
+    upstream_ca: Option<std::path::PathBuf>,
 }
 
 #[tokio::main]
@@ -39,21 +176,112 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
     
     println!("🚀 ark-hub 启动中...");
-    println!("📡 WebSocket 监听地址: ws://{}", cli.ws_listen);
-    println!("🌐 HTTP API 监听地址: http://{}", cli.http_listen);
+    let _telemetry = telemetry::init("ark-hub")?;
+    
+    // TLS（证书有误时在启动阶段报错，不等到第一个连接）
+    let tls = TlsFiles::from_args(cli.tls_cert.clone(), cli.tls_key.clone(), cli.tls_client_ca.clone())?;
+    let acceptor = tls.as_ref().map(TlsFiles::acceptor).transpose()?;
+    let (ws_scheme, http_scheme) = if tls.is_some() { ("wss", "https") } else { ("ws", "http") };
+    println!("📡 WebSocket 监听地址: {}://{}", ws_scheme, cli.ws_listen);
+    println!("🌐 HTTP API 监听地址: {}://{}", http_scheme, cli.http_listen);
+    if let Some(TlsFiles { client_ca: Some(ref ca), .. }) = tls {
+        println!("🔒 Agent 客户端证书校验: {}", ca.display());
+    }
+    
+    // Agent 令牌和 API 密钥
+    let mut auth = HubAuth::load(cli.agent_tokens.as_deref(), cli.api_keys.as_deref())?;
+    if let Some(ref issuer) = cli.oidc_issuer {
+        let oidc = OidcVerifier::discover(OidcConfig {
+            issuer: issuer.clone(),
+            audience: cli.oidc_audience.clone(),
+            role_claim: cli.oidc_role_claim.clone(),
+        })
+        .await?;
+        println!("🔑 OIDC 令牌校验已启用: {}", oidc.issuer());
+        auth = auth.with_oidc(oidc);
+    }
+    let auth = Arc::new(auth);
+    if auth.agents_enabled() {
+        println!("🔑 Agent 令牌校验已启用");
+    }
+    if auth.api_enabled() {
+        println!("🔑 API 密钥校验已启用");
+    } else {
+        println!("⚠️  未配置 --api-keys 或 --oidc-issuer，HTTP API（含 POST /api/v1/fix）不做鉴权");
+    }
     
     // 创建全局状态图
-    let global_graph = Arc::new(StateGraph::new());
+    let graph_config = match cli.graph_config {
+        Some(ref path) => {
+            let config = GraphConfig::load_from_file(path)?;
+            println!("📄 已加载状态图配置: {}", path.display());
+            config
+        }
+        None => GraphConfig::default(),
+    };
+    let (global_graph, storage, restored_commands) = storage::open_graph(
+        cli.storage.as_deref(),
+        graph_config,
+        std::time::Duration::from_secs(cli.snapshot_interval_secs.max(1)),
+    )
+    .await?;
+    if let Some(ref url) = cli.storage {
+        println!("💾 状态持久化: {}", url);
+    }
+    
+    // 下发命令的执行状态（从存储恢复，之后随下发和 Agent 回报更新）
+    let commands = CommandStore::new().with_storage(storage.clone());
+    commands.restore(restored_commands);
+    let commands = Arc::new(commands);
+    
+    // Job 索引（从恢复的状态图重建，之后随事件增量更新）
+    let jobs = Arc::new(JobIndex::from_graph(&global_graph).await);
     
     // 创建 Metrics 收集器
     let metrics = Arc::new(HubMetricsCollector::new()?);
+    let remote_writer = match cli.remote_write_config {
+        Some(ref path) => {
+            let config = RemoteWriteConfig::load_from_file(path)?;
+            println!("📤 Prometheus remote_write: {}（每 {} 秒）", config.url, config.interval().as_secs());
+            Some(RemoteWriter::new(config)?)
+        }
+        None => None,
+    };
+    
+    // 创建集中审计存储
+    let audit_store = match cli.audit_log {
+        Some(ref path) => AuditStore::new().with_file(path)?,
+        None => AuditStore::new(),
+    };
+    let audit_store = Arc::new(audit_store);
     
     // 创建 K8s 控制器（如果启用）
     let k8s_controller = if cli.enable_k8s_controller {
         match K8sController::new(true).await {
             Ok(controller) => {
                 println!("✅ Kubernetes 控制器已启用");
-                Some(Arc::new(controller))
+                if cli.k8s_dry_run {
+                    println!("   dry-run：变更请求带 dryRun=All，只校验和记录，不改动集群");
+                }
+                let controller = controller
+                    .with_dry_run(cli.k8s_dry_run)
+                    .with_audit(Arc::clone(&audit_store));
+                let controller = if cli.no_leader_election {
+                    controller
+                } else {
+                    let elector = Arc::new(leader::LeaderElector::new(
+                        controller.client(),
+                        &cli.leader_lease,
+                        std::time::Duration::from_secs(cli.leader_lease_duration_secs.max(3)),
+                        Arc::clone(&metrics),
+                    ));
+                    elector.spawn();
+                    println!("   Leader 选举：Lease {}，本实例 {}", cli.leader_lease, elector.identity());
+                    controller.with_leader_election(elector)
+                };
+                let controller = Arc::new(controller);
+                controller.spawn_policy_watch();
+                Some(controller)
             }
             Err(e) => {
                 eprintln!("⚠️  无法初始化 Kubernetes 控制器: {}", e);
@@ -66,25 +294,113 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         None
     };
     
+    // 告警（未配置时不推送，仍输出到 stdout）
+    let alerts = match cli.alert_config {
+        Some(ref path) => {
+            let alerts = Alerter::new(AlertConfig::load_from_file(path)?)?;
+            println!("🔔 告警 Webhook: {} 个（{}）", alerts.webhook_count(), path.display());
+            alerts
+        }
+        None => Alerter::new(AlertConfig::default())?,
+    };
+    let alerts = Arc::new(alerts);
+    
+    // 最近事件历史
+    let events = Arc::new(EventHistory::new(cli.event_history));
+    
+    // 不可逆故障判定：按事件历史统计同一设备的错误次数
+    let fault_policy = match cli.fault_policy {
+        Some(ref path) => FaultPolicy::load_from_file(path)?,
+        None => FaultPolicy::default(),
+    };
+    if cli.event_history == 0 {
+        eprintln!("[hub] 警告：事件历史已关闭（--event-history 0），只有致命 XID 会判定为不可逆故障");
+    }
+    let faults = Arc::new(FaultDetector::new(fault_policy, Arc::clone(&events)));
+    
+    // 流控：处理变慢时通知 Agent 减少推送
+    let flow = Arc::new(FlowControl::new(std::time::Duration::from_millis(cli.flow_slow_ms.max(1))));
+    
+    // 联邦：转发事件到上级 Hub
+    let upstream = match (cli.upstream_hub.clone(), cli.cluster_id.clone()) {
+        (Some(url), Some(cluster_id)) => {
+            federation::validate_cluster_id(&cluster_id)?;
+            let connector = cli.upstream_ca.as_deref().map(tls::upstream_connector).transpose()?;
+            println!("🌐 上级 Hub: {}（cluster_id={}）", url, cluster_id);
+            Some(Upstream::spawn(url, cluster_id, std::env::var("ARK_UPSTREAM_TOKEN").ok(), connector))
+        }
+        _ => None,
+    };
+    
+    // 各节点已接收的消息序号（去重 Agent 重发的消息）
+    let delivery = Arc::new(DeliveryTracker::new());
+    
+    // Agent 节点注册表（注册信息和心跳）
+    let nodes = Arc::new(NodeRegistry::new(std::time::Duration::from_secs(cli.node_stale_secs)));
+    
     // 创建 WebSocket 连接管理器（node_id -> sender）
     let connections: Arc<DashMap<String, mpsc::UnboundedSender<Message>>> = Arc::new(DashMap::new());
     
+    // 退出时停止接受新连接
+    let (shutdown_tx, mut shutdown_rx) = tokio::sync::watch::channel(false);
+    
     // 启动 WebSocket 服务器
     let ws_listen = cli.ws_listen.clone();
     let ws_handle = {
         let graph = Arc::clone(&global_graph);
         let conns = Arc::clone(&connections);
         let k8s_ctrl = k8s_controller.clone();
+        let audit_store = Arc::clone(&audit_store);
+        let storage = storage.clone();
+        let delivery = Arc::clone(&delivery);
+        let nodes = Arc::clone(&nodes);
+        let jobs = Arc::clone(&jobs);
+        let auth = Arc::clone(&auth);
+        let commands = Arc::clone(&commands);
+        let alerts = Arc::clone(&alerts);
+        let events = Arc::clone(&events);
+        let faults = Arc::clone(&faults);
+        let upstream = upstream.clone();
+        let flow = Arc::clone(&flow);
+        let metrics = Arc::clone(&metrics);
         tokio::spawn(async move {
             let listener = TcpListener::bind(&ws_listen).await?;
             println!("✅ WebSocket 服务器已启动，等待节点连接...");
             
-            while let Ok((stream, addr)) = listener.accept().await {
+            loop {
+                let (stream, addr) = tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok(accepted) => accepted,
+                        Err(_) => break,
+                    },
+                    _ = shutdown_rx.changed() => break,
+                };
                 let graph = Arc::clone(&graph);
                 let conns = Arc::clone(&conns);
                 let k8s_ctrl = k8s_ctrl.clone();
+                let audit_store = Arc::clone(&audit_store);
+                let storage = storage.clone();
+                let delivery = Arc::clone(&delivery);
+                let nodes = Arc::clone(&nodes);
+                let jobs = Arc::clone(&jobs);
+                let acceptor = acceptor.clone();
+                let auth = Arc::clone(&auth);
+                let commands = Arc::clone(&commands);
+                let alerts = Arc::clone(&alerts);
+                let events = Arc::clone(&events);
+                let faults = Arc::clone(&faults);
+                let upstream = upstream.clone();
+                let flow = Arc::clone(&flow);
+                let metrics = Arc::clone(&metrics);
                 tokio::spawn(async move {
-                    if let Err(e) = handle_connection(stream, addr, graph, conns, k8s_ctrl).await {
+                    let result = match acceptor {
+                        Some(acceptor) => match acceptor.accept(stream).await {
+                            Ok(stream) => handle_connection(stream, addr, graph, conns, k8s_ctrl, audit_store, storage, delivery, nodes, jobs, auth, commands, alerts, events, faults, upstream, flow, metrics).await,
+                            Err(e) => Err(format!("TLS 握手失败: {}", e).into()),
+                        },
+                        None => handle_connection(stream, addr, graph, conns, k8s_ctrl, audit_store, storage, delivery, nodes, jobs, auth, commands, alerts, events, faults, upstream, flow, metrics).await,
+                    };
+                    if let Err(e) = result {
                         eprintln!("[hub] 处理连接 {} 时出错: {}", addr, e);
                     }
                 });
@@ -99,31 +415,123 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let graph = Arc::clone(&global_graph);
         let metrics = Arc::clone(&metrics);
         let connections = Arc::clone(&connections);
+        let nodes = Arc::clone(&nodes);
+        let jobs = Arc::clone(&jobs);
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
             loop {
                 interval.tick().await;
                 metrics.update_graph_metrics(&graph).await;
-                // 更新 WebSocket 连接数
+                // 清理已结束且长时间没有事件的 job
+                let now_ms = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64;
+                jobs.prune(now_ms.saturating_sub(JOB_RETENTION_MS));
+                // 更新 WebSocket 连接数和节点状态
+                let counts = nodes.counts();
                 let connected = connections.len();
-                metrics.update_websocket_connections(connected, 0);
+                metrics.update_websocket_connections(connected, counts.get("offline").copied().unwrap_or(0));
+                metrics.update_agent_nodes(&counts);
+                // 每个 job 的阻塞边数和每个节点的近期错误数（不含跨节点场景）
+                let running = jobs.list().into_iter().filter(|job| job.state == "running").map(|job| (job, Vec::new())).collect();
+                let report = HealthReport::build(&graph, nodes.inventory(&graph).await, running).await;
+                metrics.update_cluster_metrics(&report);
             }
         })
     };
     
+    // 定期把指标推送到 remote_write 端点
+    if let Some(writer) = remote_writer {
+        let metrics = Arc::clone(&metrics);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(writer.interval());
+            loop {
+                interval.tick().await;
+                if let Err(e) = writer.push(&metrics.families()).await {
+                    eprintln!("[hub] remote_write 推送失败: {}", e);
+                }
+            }
+        });
+    }
+    
+    // 流控处于 slow 状态但长时间没有消息时恢复
+    {
+        let flow = Arc::clone(&flow);
+        let connections = Arc::clone(&connections);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(flow::IDLE_RECOVERY);
+            loop {
+                interval.tick().await;
+                if flow.recover_idle() {
+                    flow::broadcast(&connections, false);
+                }
+            }
+        });
+    }
+    
+    // 故障节点自动恢复：故障事件停止后下发自检，通过后解除污点
+    if let (Some(controller), Some(quiet)) = (k8s_controller.clone(), cli.taint_recovery_secs.filter(|secs| *secs > 0)) {
+        let connections = Arc::clone(&connections);
+        let commands = Arc::clone(&commands);
+        let alerts = Arc::clone(&alerts);
+        let quiet = std::time::Duration::from_secs(quiet);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(k8s_controller::RECOVERY_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                controller.check_recovery(quiet, &connections, &commands, &alerts).await;
+            }
+        });
+    }
+    
+    // 定期对运行中的 job 做跨节点场景分析，命中时告警
+    if alerts.enabled() {
+        let graph = Arc::clone(&global_graph);
+        let nodes = Arc::clone(&nodes);
+        let jobs = Arc::clone(&jobs);
+        let alerts = Arc::clone(&alerts);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(alerts.scene_interval());
+            loop {
+                interval.tick().await;
+                for job in jobs.list().into_iter().filter(|job| job.state == "running") {
+                    let keys: Vec<NodeKey> = job.processes.iter().map(|p| p.key()).collect();
+                    for analysis in job_scenes(&graph, &nodes, &job.job_id, &keys).await {
+                        alerts.fire(Alert::scene(&job.job_id, &analysis));
+                    }
+                }
+            }
+        });
+    }
+    
     // 启动 HTTP API 服务器
     let http_listen = cli.http_listen.clone();
     let http_handle = {
         let graph = Arc::clone(&global_graph);
         let conns = Arc::clone(&connections);
         let metrics = Arc::clone(&metrics);
+        let approvals = Arc::new(ApprovalStore::new());
+        let rules_dir = cli.rules_dir.clone();
+        let audit_store = Arc::clone(&audit_store);
+        let nodes = Arc::clone(&nodes);
+        let jobs = Arc::clone(&jobs);
+        let auth = Arc::clone(&auth);
+        let commands = Arc::clone(&commands);
+        let alerts = Arc::clone(&alerts);
+        let events = Arc::clone(&events);
+        let k8s_ctrl = k8s_controller.clone();
+        let approval_threshold = cli.approval_threshold;
         tokio::spawn(async move {
             // 创建 API 路由（包含 metrics 端点）
-            let api = create_api_routes(graph, conns, metrics);
+            let api = create_api_routes(graph, conns, metrics, approvals, approval_threshold, rules_dir, audit_store, nodes, jobs, auth, commands, alerts, events, k8s_ctrl);
             println!("✅ HTTP API 服务器已启动");
             let port = http_listen.split(':').last().unwrap_or("8081").parse().unwrap_or(8081);
-            println!("📊 Prometheus Metrics 端点: http://0.0.0.0:{}/metrics", port);
-            warp::serve(api).run(([0, 0, 0, 0], port)).await;
+            println!("📊 Prometheus Metrics 端点: {}://0.0.0.0:{}/metrics", http_scheme, port);
+            match tls {
+                Some(tls) => warp::serve(api).tls().cert_path(&tls.cert).key_path(&tls.key).run(([0, 0, 0, 0], port)).await,
+                None => warp::serve(api).run(([0, 0, 0, 0], port)).await,
+            }
         })
     };
     
@@ -137,33 +545,84 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         _ = http_handle => {
             println!("[hub] HTTP 服务器已关闭");
         }
+        _ = shutdown::signal() => {
+            println!("[hub] 收到退出信号，停止接受新连接，通知 {} 个节点", connections.len());
+            let _ = shutdown_tx.send(true);
+            let timeout = std::time::Duration::from_secs(cli.shutdown_timeout_secs);
+            let remaining = shutdown::drain(&connections, timeout).await;
+            if remaining > 0 {
+                eprintln!("[hub] {} 个节点未在 {} 秒内断开，已关闭连接", remaining, cli.shutdown_timeout_secs);
+            }
+            if tokio::time::timeout(timeout, storage.flush()).await.is_err() {
+                eprintln!("[hub] 等待存储写入超时");
+            }
+            if let Some(ref controller) = k8s_controller {
+                controller.release_leadership().await;
+            }
+            println!("[hub] 已退出");
+        }
     }
     
     Ok(())
 }
 
 /// 处理单个 WebSocket 连接
-async fn handle_connection(
-    stream: TcpStream,
+#[allow(clippy::too_many_arguments)]
+async fn handle_connection<S>(
+    stream: S,
     addr: std::net::SocketAddr,
     graph: Arc<StateGraph>,
     connections: Arc<DashMap<String, mpsc::UnboundedSender<Message>>>,
     k8s_controller: Option<Arc<K8sController>>,
-) -> Result<(), Box<dyn std::error::Error>> {
+    audit_store: Arc<AuditStore>,
+    storage: StorageHandle,
+    delivery: Arc<DeliveryTracker>,
+    nodes: Arc<NodeRegistry>,
+    jobs: Arc<JobIndex>,
+    auth: Arc<HubAuth>,
+    commands: Arc<CommandStore>,
+    alerts: Arc<Alerter>,
+    events: Arc<EventHistory>,
+    faults: Arc<FaultDetector>,
+    upstream: Option<Arc<Upstream>>,
+    flow: Arc<FlowControl>,
+    metrics: Arc<HubMetricsCollector>,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
     println!("[hub] 新节点连接: {}", addr);
     
-    let ws_stream = accept_async(stream).await?;
+    // 握手时校验 Agent 令牌，记下令牌绑定的 node_id
+    let mut bound_node: Option<String> = None;
+    let ws_stream = accept_hdr_async(stream, |request: &Request, response: Response| {
+        let header = request.headers().get("authorization").and_then(|v| v.to_str().ok());
+        match auth.agent(header) {
+            Ok(node) => {
+                bound_node = node;
+                Ok(response)
+            }
+            Err(e) => {
+                eprintln!("[hub] 拒绝连接 {}: {}", addr, e);
+                let mut rejection = ErrorResponse::new(Some(e));
+                *rejection.status_mut() = StatusCode::UNAUTHORIZED;
+                Err(rejection)
+            }
+        }
+    })
+    .await?;
     let (mut write, mut read) = ws_stream.split();
     
     // 创建用于发送消息的通道
     let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
     
-    // 从连接地址生成默认 node_id（Agent 会在第一个事件中提供真实的 node_id）
+    // 注册前以连接地址作为临时 node_id（只用于标注未带 node_id 的事件，不进入连接表）
     let mut node_id = format!("node-{}", addr.ip());
-    
-    // 立即注册连接（使用默认 node_id，后续可能被事件中的 node_id 更新）
-    connections.insert(node_id.clone(), tx.clone());
-    println!("[hub] 注册节点连接: {} (临时)", node_id);
+    // 本次连接在节点注册表中的编号（收到注册消息后有效）
+    let mut connection: Option<u64> = None;
+    let mut unregistered_warned = false;
+    // 下级 Hub 的连接（注册时声明 federation 能力），事件的 node_id 为 <cluster_id>/<node_id>
+    let mut federated = false;
     
     // 启动消息转发任务（从通道转发到 WebSocket write 端）
     let write_task = tokio::spawn(async move {
@@ -179,47 +638,157 @@ async fn handle_connection(
     while let Some(msg) = read.next().await {
         match msg? {
             Message::Text(text) => {
-                // 解析事件
-                match serde_json::from_str::<Event>(&text) {
-                    Ok(mut event) => {
-                        // 如果事件中包含 node_id，使用它并更新连接表
-                        if let Some(event_node_id) = &event.node_id {
-                            if *event_node_id != node_id {
-                                // node_id 发生变化，更新连接表
-                                connections.remove(&node_id);
-                                node_id = event_node_id.clone();
-                                connections.insert(node_id.clone(), tx.clone());
-                                println!("[hub] 更新节点连接: {}", node_id);
+                // 注册和心跳：注册后按 node_id 登记连接，下发命令按它寻址
+                let control = serde_json::from_str::<ControlMessage>(&text).ok();
+                if let Some(ControlMessage::Register(registration)) = control.as_ref() {
+                    if !agent_may_act_as(bound_node.as_deref(), &registration.node_id) {
+                        eprintln!("[hub] 拒绝连接 {}: 令牌不能注册节点 {}", addr, registration.node_id);
+                        break;
+                    }
+                    if registration.node_id != node_id {
+                        connections.remove_if(&node_id, |_, sender| sender.same_channel(&tx));
+                        node_id = registration.node_id.clone();
+                    }
+                    connections.insert(node_id.clone(), tx.clone());
+                    storage.record_node(&node_id, addr);
+                    println!(
+                        "[hub] 节点注册: {} (hostname={}, agent={}, capabilities={})",
+                        node_id,
+                        registration.hostname,
+                        registration.agent_version,
+                        registration.capabilities.join(",")
+                    );
+                    connection = Some(nodes.register(registration.clone(), addr));
+                    federated = registration.capabilities.iter().any(|c| c == federation::FEDERATION_CAPABILITY);
+                    if flow.is_slow() {
+                        let _ = tx.send(flow::flow_message(true));
+                    }
+                }
+                match connection {
+                    Some(connection) => nodes.seen(&node_id, connection),
+                    None if !unregistered_warned => {
+                        eprintln!("[hub] 连接 {} 未发送注册消息（旧版 Agent？），无法接收下发命令", addr);
+                        unregistered_warned = true;
+                    }
+                    None => {}
+                }
+                if control.is_some() {
+                    continue;
+                }
+                
+                // 带序号的消息：重复的只确认不处理，处理完成后确认
+                let envelope = serde_json::from_str::<Envelope>(&text).unwrap_or_default();
+                // 令牌绑定节点时，不接受以其他节点名义上报的消息（仍然确认，避免 Agent 反复重发）
+                if let (Some(bound), Some(claimed)) = (bound_node.as_ref(), envelope.node_id.as_ref()) {
+                    if bound != claimed && !(federated && federation::belongs_to(claimed, bound)) {
+                        eprintln!("[hub] 丢弃连接 {} 以节点 {} 名义上报的消息（令牌绑定 {}）", addr, claimed, bound);
+                        if let Some(seq) = envelope.seq {
+                            let _ = tx.send(delivery::ack_message(seq));
+                        }
+                        continue;
+                    }
+                }
+                if let Some(seq) = envelope.seq {
+                    if !delivery.accept(envelope.node_id.as_deref().unwrap_or(&node_id), seq) {
+                        let _ = tx.send(delivery::ack_message(seq));
+                        continue;
+                    }
+                }
+                
+                let started = std::time::Instant::now();
+                'process: {
+                    // 下发命令的执行结果
+                    if let Ok(AgentReport::CommandResult(result)) = serde_json::from_str::<AgentReport>(&text) {
+                        match commands.complete(result) {
+                            Ok(record) => {
+                                println!("[hub] 命令 {} 在节点 {} 执行结果: {}", record.command_id, record.node_id, record.status);
+                                metrics.record_fix_action(&record.action, &record.node_id, &record.status);
                             }
-                        } else {
-                            // 事件中没有 node_id，使用默认值
-                            event.node_id = Some(node_id.clone());
+                            Err(e) => eprintln!("[hub] 忽略命令回报: {}", e),
                         }
-                        
-                        // 更新全局图
-                        if let Err(e) = graph.process_event(&event).await {
-                            eprintln!("[hub] 处理事件失败: {}", e);
-                        } else {
-                            println!("[hub] 收到事件: {:?} from {}", event.event_type, node_id);
+                        break 'process;
+                    }
+                    
+                    // 审计记录（带 audit 字段）单独保存，不进入状态图
+                    if let Ok(message) = serde_json::from_str::<AuditMessage>(&text) {
+                        match message.into_record() {
+                            Ok(record) => {
+                                println!("[hub] 收到审计记录: {} {} from {}", record.action, record.result, record.node_id);
+                                audit_store.append(record);
+                            }
+                            Err(e) => eprintln!("[hub] 解析审计记录失败: {}", e),
+                        }
+                        break 'process;
+                    }
+                    
+                    // 解析事件
+                    match serde_json::from_str::<Event>(&text) {
+                        Ok(mut event) => {
+                            // 未带 node_id 的事件归属到本连接注册的节点
+                            if event.node_id.is_none() {
+                                event.node_id = Some(node_id.clone());
+                            }
+                            // 下级 Hub 只能上报自己集群内的节点
+                            if federated {
+                                if let Some(ref event_node) = event.node_id {
+                                    if !federation::belongs_to(event_node, &node_id) {
+                                        event.node_id = Some(federation::federated_node_id(&node_id, event_node));
+                                    }
+                                }
+                            }
+                            let span = tracing::info_span!("event.ingest", event_type = ?event.event_type, node_id = event.node_id.as_deref().unwrap_or("-"));
+                            telemetry::set_parent(&span, event.traceparent.as_deref());
                             
-                            // 检测不可逆故障并触发 K8s 操作
-                            if let Some(ref controller) = k8s_controller {
-                                if let Some(fault) = controller.detect_irreversible_fault(&event) {
-                                    // 在后台任务中处理故障（避免阻塞事件处理）
-                                    let controller_clone = Arc::clone(controller);
-                                    tokio::spawn(async move {
-                                        if let Err(e) = controller_clone.handle_irreversible_fault(&fault).await {
-                                            eprintln!("[k8s-controller] 处理故障失败: {}", e);
-                                        }
-                                    });
+                            // 更新全局图
+                            if let Err(e) = graph.process_event(&event).instrument(tracing::info_span!(parent: &span, "graph.update")).await {
+                                eprintln!("[hub] 处理事件失败: {}", e);
+                            } else {
+                                storage.record_event(&event);
+                                events.record(&event);
+                                jobs.observe(&event);
+                                if let Ok(serde_json::Value::String(event_type)) = serde_json::to_value(&event.event_type) {
+                                    metrics.record_event_received(&event_type, event.node_id.as_deref().unwrap_or("-"));
+                                }
+                                if let Some(ref upstream) = upstream {
+                                    event.traceparent = telemetry::traceparent(&span);
+                                    upstream.forward(&event);
+                                }
+                                println!("[hub] 收到事件: {:?} from {}", event.event_type, node_id);
+                                
+                                // Agent 规则命中告警
+                                if event.event_type == EventType::RuleMatched {
+                                    alerts.fire(Alert::rule_match(&event));
+                                }
+                                
+                                // 检测不可逆故障：告警，并触发 K8s 操作
+                                if let Some(fault) = faults.detect(&event) {
+                                    alerts.fire(Alert::fault(&fault));
+                                    // 下级机房的节点不在本集群的 Kubernetes 中，由下级 Hub 处理
+                                    if let Some(controller) = k8s_controller.as_ref().filter(|_| !federated) {
+                                        // 在后台任务中处理故障（避免阻塞事件处理）
+                                        let controller_clone = Arc::clone(controller);
+                                        let span = tracing::info_span!(parent: &span, "k8s.action", fault = fault.kind(), node_id = fault.node_id());
+                                        tokio::spawn(async move {
+                                            if let Err(e) = controller_clone.handle_irreversible_fault(&fault).await {
+                                                eprintln!("[k8s-controller] 处理故障失败: {}", e);
+                                            }
+                                        }.instrument(span));
+                                    }
                                 }
                             }
                         }
-                    }
-                    Err(e) => {
-                        eprintln!("[hub] 解析事件失败: {}", e);
+                        Err(e) => {
+                            eprintln!("[hub] 解析事件失败: {}", e);
+                        }
                     }
                 }
+                
+                if let Some(seq) = envelope.seq {
+                    let _ = tx.send(delivery::ack_message(seq));
+                }
+                if let Some(slow) = flow.observe(started.elapsed()) {
+                    flow::broadcast(&connections, slow);
+                }
             }
             Message::Close(_) => {
                 println!("[hub] 节点 {} 断开连接", node_id);
@@ -229,8 +798,11 @@ async fn handle_connection(
         }
     }
     
-    // 从连接表中移除
-    connections.remove(&node_id);
+    // 从连接表中移除（节点已用新连接重新注册时保留新连接）
+    connections.remove_if(&node_id, |_, sender| sender.same_channel(&tx));
+    if let Some(connection) = connection {
+        nodes.disconnected(&node_id, connection);
+    }
     println!("[hub] 节点 {} 已从连接表移除", node_id);
     
     // 等待写任务结束
@@ -253,12 +825,142 @@ fn with_connections(
     warp::any().map(move || connections.clone())
 }
 
+/// 生成下发命令的 ID（节点审计日志按此与 Hub 侧记录对应）
+fn new_command_id() -> String {
+    use rand::Rng;
+    let id: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+    format!("cmd-{}", id)
+}
+
 /// Fix 请求结构
 #[derive(serde::Deserialize)]
 struct FixRequest {
+    #[serde(default)]
     node_id: String,
+    #[serde(default)]
     target_pid: u32,
+    /// 按 Pod（<namespace>/<name>）指定目标，由 Hub 换算为节点和 PID
+    #[serde(default)]
+    pod: Option<String>,
     action: Option<String>, // 可选，默认 "GracefulShutdown"
+    job_id: Option<String>,   // 集群修复时携带，审批按 job 绑定
+    approval: Option<String>, // 破窗审批 token（破坏性动作或集群修复超过审批阈值时必须携带）
+}
+
+impl FixRequest {
+    /// 审批绑定的操作和目标：集群修复按 job，单点修复按节点上的进程
+    fn approval_scope(&self) -> (&'static str, String) {
+        match &self.job_id {
+            Some(job_id) => ("cluster_fix", format!("job-{}", job_id)),
+            None => ("fix", NodeKey::process(Some(&self.node_id), self.target_pid).to_string()),
+        }
+    }
+
+    /// 按 Pod 指定目标时换算为节点和 PID：Pod 中只有一个运行中的进程时直接使用，
+    /// 有多个时须同时指定 target_pid。失败时返回状态码和原因
+    async fn resolve_pod(&mut self, graph: &StateGraph) -> Result<(), (warp::http::StatusCode, String)> {
+        let Some(ref pod) = self.pod else {
+            return Ok(());
+        };
+        let processes: Vec<_> = graph
+            .find_processes_by_pod(pod)
+            .await
+            .into_iter()
+            .filter(|node| node.metadata.get("state").map(String::as_str) == Some("running"))
+            .collect();
+        let target = match processes.as_slice() {
+            [] => return Err((warp::http::StatusCode::NOT_FOUND, format!("Pod {} 中没有运行中的进程", pod))),
+            [only] if self.target_pid == 0 => only,
+            _ => {
+                let pids: Vec<String> = processes.iter().filter_map(|node| node.key().pid()).map(|pid| pid.to_string()).collect();
+                match processes.iter().find(|node| self.target_pid != 0 && node.key().pid() == Some(self.target_pid)) {
+                    Some(node) => node,
+                    None if self.target_pid == 0 => {
+                        return Err((warp::http::StatusCode::CONFLICT, format!("Pod {} 中有多个进程（PID {}），请指定 target_pid", pod, pids.join(", "))));
+                    }
+                    None => {
+                        return Err((warp::http::StatusCode::NOT_FOUND, format!("PID {} 不属于 Pod {}（PID {}）", self.target_pid, pod, pids.join(", "))));
+                    }
+                }
+            }
+        };
+        let key = target.key();
+        let (Some(node_id), Some(pid)) = (key.node_id(), key.pid()) else {
+            return Err((warp::http::StatusCode::NOT_FOUND, format!("Pod {} 的进程缺少节点信息", pod)));
+        };
+        self.node_id = node_id.to_string();
+        self.target_pid = pid;
+        Ok(())
+    }
+}
+
+/// Warp Filter：注入审批存储
+fn with_approvals(
+    approvals: Arc<ApprovalStore>,
+) -> impl Filter<Extract = (Arc<ApprovalStore>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || approvals.clone())
+}
+
+/// Warp Filter：注入审计存储
+fn with_audit_store(
+    audit_store: Arc<AuditStore>,
+) -> impl Filter<Extract = (Arc<AuditStore>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || audit_store.clone())
+}
+
+/// Warp Filter：注入节点注册表
+fn with_nodes(
+    nodes: Arc<NodeRegistry>,
+) -> impl Filter<Extract = (Arc<NodeRegistry>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || nodes.clone())
+}
+
+/// Warp Filter：注入事件历史
+fn with_events(
+    events: Arc<EventHistory>,
+) -> impl Filter<Extract = (Arc<EventHistory>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || events.clone())
+}
+
+/// Warp Filter：注入 job 索引
+fn with_jobs(
+    jobs: Arc<JobIndex>,
+) -> impl Filter<Extract = (Arc<JobIndex>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || jobs.clone())
+}
+
+/// Warp Filter：注入下发命令状态
+fn with_commands(
+    commands: Arc<CommandStore>,
+) -> impl Filter<Extract = (Arc<CommandStore>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || commands.clone())
+}
+
+/// Warp Filter：注入告警器
+fn with_alerts(
+    alerts: Arc<Alerter>,
+) -> impl Filter<Extract = (Arc<Alerter>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || alerts.clone())
+}
+
+/// Warp Filter：注入 K8s 控制器（未启用时为 None）
+fn with_k8s_controller(
+    controller: Option<Arc<K8sController>>,
+) -> impl Filter<Extract = (Option<Arc<K8sController>>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || controller.clone())
+}
+
+/// 节点修复确认
+#[derive(serde::Deserialize)]
+struct RepairRequest {
+    #[serde(default)]
+    requested_by: Option<String>,
+    #[serde(default)]
+    reason: Option<String>,
 }
 
 /// Warp Filter：注入 Metrics 收集器
@@ -269,14 +971,35 @@ fn with_metrics(
 }
 
 /// 创建 HTTP API 路由
+#[allow(clippy::too_many_arguments)]
 fn create_api_routes(
     graph: Arc<StateGraph>,
     connections: Arc<DashMap<String, mpsc::UnboundedSender<Message>>>,
     metrics: Arc<HubMetricsCollector>,
+    approvals: Arc<ApprovalStore>,
+    approval_threshold: usize,
+    rules_dir: Option<std::path::PathBuf>,
+    audit_store: Arc<AuditStore>,
+    nodes: Arc<NodeRegistry>,
+    jobs: Arc<JobIndex>,
+    auth: Arc<HubAuth>,
+    commands: Arc<CommandStore>,
+    alerts: Arc<Alerter>,
+    events: Arc<EventHistory>,
+    k8s_controller: Option<Arc<K8sController>>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     let graph_filter = with_graph(graph.clone());
+    let jobs_filter = with_jobs(jobs);
+    let commands_filter = with_commands(commands);
+    let alerts_filter = with_alerts(alerts);
+    let approvals_filter = with_approvals(approvals);
     let conns_filter = with_connections(connections.clone());
     let metrics_filter = with_metrics(metrics.clone());
+    let viewer = auth::require(Arc::clone(&auth), Role::Viewer);
+    let operator = auth::require(Arc::clone(&auth), Role::Operator);
+    let admin = auth::require(Arc::clone(&auth), Role::Admin);
+    let operator_principal = auth::principal(Arc::clone(&auth), Role::Operator);
+    let agent = auth::require_agent(auth);
     
     // GET /metrics - Prometheus Metrics 端点
     let metrics_route = warp::path("metrics")
@@ -299,18 +1022,26 @@ fn create_api_routes(
             }
         });
     
-    // GET /api/v1/why?job_id=xxx
+    // GET /api/v1/why?job_id=xxx[&straggler_margin=0.2]
     let why_route = warp::path!("api" / "v1" / "why")
+        .and(viewer.clone())
         .and(warp::query::<std::collections::HashMap<String, String>>())
         .and(graph_filter.clone())
+        .and(jobs_filter.clone())
+        .and(with_nodes(Arc::clone(&nodes)))
         .and_then(
-            |params: std::collections::HashMap<String, String>, graph: Arc<StateGraph>| async move {
+            |params: std::collections::HashMap<String, String>, graph: Arc<StateGraph>, jobs: Arc<JobIndex>, nodes: Arc<NodeRegistry>| async move {
                 if let Some(job_id) = params.get("job_id") {
-                    match cluster_why(graph, job_id).await {
-                        Ok((causes, processes)) => Ok(warp::reply::json(&json!({
+                    let straggler_margin = params
+                        .get("straggler_margin")
+                        .and_then(|m| m.parse::<f64>().ok())
+                        .unwrap_or(DEFAULT_STRAGGLER_MARGIN);
+                    match cluster_why(graph, &jobs, &nodes, job_id, straggler_margin).await {
+                        Ok((causes, processes, scenes)) => Ok(warp::reply::json(&json!({
                             "job_id": job_id,
                             "causes": causes,
-                            "processes": processes
+                            "processes": processes,
+                            "scenes": scenes
                         }))),
                         Err(e) => Ok(warp::reply::json(&json!({
                             "error": e.to_string()
@@ -326,15 +1057,21 @@ fn create_api_routes(
     
     // GET /api/v1/ps
     let ps_route = warp::path!("api" / "v1" / "ps")
+        .and(viewer.clone())
         .and(graph_filter.clone())
         .and_then(|graph: Arc<StateGraph>| async move {
             let processes = graph.get_active_processes().await;
             let result: Vec<serde_json::Value> = processes
                 .iter()
                 .map(|node| {
+                    let key = node.key();
                     json!({
                         "id": node.id,
+                        "node_id": key.node_id(),
+                        "pid": key.pid(),
                         "job_id": node.metadata.get("job_id").unwrap_or(&"-".to_string()),
+                        "pod": node.metadata.get("pod"),
+                        "container_id": node.metadata.get("container_id"),
                         "state": node.metadata.get("state").unwrap_or(&"unknown".to_string()),
                     })
                 })
@@ -343,30 +1080,358 @@ fn create_api_routes(
                 "processes": result
             })))
         });
+
+    // GET /api/v1/pods/<namespace>/<name> - Pod 中的进程（由 Agent 通过 cgroup 和 kubelet 关联）
+    let pod_route = warp::path!("api" / "v1" / "pods" / String / String)
+        .and(viewer.clone())
+        .and(warp::get())
+        .and(graph_filter.clone())
+        .and_then(|namespace: String, name: String, graph: Arc<StateGraph>| async move {
+            let pod = format!("{}/{}", namespace, name);
+            let processes = graph.find_processes_by_pod(&pod).await;
+            if processes.is_empty() {
+                return Ok::<_, warp::Rejection>(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": format!("未找到 Pod {} 的进程", pod) })),
+                    warp::http::StatusCode::NOT_FOUND,
+                ));
+            }
+            let result: Vec<serde_json::Value> = processes
+                .iter()
+                .map(|node| {
+                    let key = node.key();
+                    json!({
+                        "node_id": key.node_id(),
+                        "pid": key.pid(),
+                        "job_id": node.metadata.get("job_id"),
+                        "container_id": node.metadata.get("container_id"),
+                        "state": node.metadata.get("state").unwrap_or(&"unknown".to_string()),
+                    })
+                })
+                .collect();
+            Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "pod": pod, "processes": result })),
+                warp::http::StatusCode::OK,
+            ))
+        });
+    
+    // GET /api/v1/graph?format=dot|json|graphml
+    let graph_route = warp::path!("api" / "v1" / "graph")
+        .and(viewer.clone())
+        .and(warp::get())
+        .and(warp::query::<std::collections::HashMap<String, String>>())
+        .and(graph_filter.clone())
+        .and_then(
+            |params: std::collections::HashMap<String, String>, graph: Arc<StateGraph>| async move {
+                let format = params.get("format").map(String::as_str).unwrap_or("json");
+                match ExportFormat::parse(format) {
+                    Some(export_format) => Ok::<_, warp::Rejection>(warp::reply::with_status(
+                        warp::reply::with_header(
+                            graph.export(export_format).await,
+                            "content-type",
+                            export_format.content_type(),
+                        ),
+                        warp::http::StatusCode::OK,
+                    )),
+                    None => Ok(warp::reply::with_status(
+                        warp::reply::with_header(
+                            format!("unsupported format: {} (expected dot, json or graphml)", format),
+                            "content-type",
+                            "text/plain",
+                        ),
+                        warp::http::StatusCode::BAD_REQUEST,
+                    )),
+                }
+            },
+        );
+    
+    // GET /api/v1/nodes - 节点清单：注册信息、状态（online / stale / offline）、GPU / NPU 数和近期错误数
+    let nodes_route = warp::path!("api" / "v1" / "nodes")
+        .and(viewer.clone())
+        .and(warp::get())
+        .and(with_nodes(Arc::clone(&nodes)))
+        .and(graph_filter.clone())
+        .and_then(|nodes: Arc<NodeRegistry>, graph: Arc<StateGraph>| async move {
+            Ok::<_, warp::Rejection>(warp::reply::json(&json!({
+                "nodes": nodes.inventory(&graph).await
+            })))
+        });
+    
+    // DELETE /api/v1/nodes/<node_id> - 从节点清单中移除节点（在线时先断开连接，Agent 重连后会重新注册）
+    let node_remove_route = warp::path!("api" / "v1" / "nodes" / String)
+        .and(admin.clone())
+        .and(warp::delete())
+        .and(with_nodes(Arc::clone(&nodes)))
+        .and(conns_filter.clone())
+        .and_then(|node_id: String, nodes: Arc<NodeRegistry>, conns: Arc<DashMap<String, mpsc::UnboundedSender<Message>>>| async move {
+            if let Some((_, sender)) = conns.remove(&node_id) {
+                let _ = sender.send(Message::Close(None));
+            }
+            match nodes.remove(&node_id) {
+                Some(_) => {
+                    println!("[hub] 节点 {} 已从节点清单移除", node_id);
+                    Ok::<_, warp::Rejection>(warp::reply::with_status(
+                        warp::reply::json(&json!({ "success": true, "node_id": node_id })),
+                        warp::http::StatusCode::OK,
+                    ))
+                }
+                None => Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": format!("节点 {} 未注册", node_id) })),
+                    warp::http::StatusCode::NOT_FOUND,
+                )),
+            }
+        });
+    
+    // POST /api/v1/nodes/<node_id>/repaired - 运维确认节点已修复：解除故障污点，写入审计并发出恢复告警
+    let node_repaired_route = warp::path!("api" / "v1" / "nodes" / String / "repaired")
+        .and(admin.clone())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_k8s_controller(k8s_controller))
+        .and(alerts_filter.clone())
+        .and_then(|node_id: String, req: RepairRequest, controller: Option<Arc<K8sController>>, alerts: Arc<Alerter>| async move {
+            let Some(controller) = controller else {
+                return Ok::<_, warp::Rejection>(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": "Kubernetes 控制器未启用（--enable-k8s-controller）" })),
+                    warp::http::StatusCode::BAD_REQUEST,
+                ));
+            };
+            if !controller.is_leader() {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": "本实例不是 K8s 控制器的 leader，请重试或直接请求 leader 实例" })),
+                    warp::http::StatusCode::SERVICE_UNAVAILABLE,
+                ));
+            }
+            let requested_by = req.requested_by.unwrap_or_else(|| "api".to_string());
+            let reason = req.reason.unwrap_or_else(|| "运维确认已修复".to_string());
+            match controller.recover(&node_id, &requested_by, &reason, &alerts).await.map_err(|e| e.to_string()) {
+                Ok(untainted) => Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "success": true, "node_id": node_id, "untainted": untainted })),
+                    warp::http::StatusCode::OK,
+                )),
+                Err(e) => Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": e })),
+                    warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+                )),
+            }
+        });
+    
+    // GET /api/v1/jobs[?state=running|exited&node_id=xxx&limit=100] - job 索引及各 job 当前的根因
+    let jobs_route = warp::path!("api" / "v1" / "jobs")
+        .and(viewer.clone())
+        .and(warp::get())
+        .and(warp::query::<std::collections::HashMap<String, String>>())
+        .and(graph_filter.clone())
+        .and(jobs_filter.clone())
+        .and_then(
+            |params: std::collections::HashMap<String, String>, graph: Arc<StateGraph>, jobs: Arc<JobIndex>| async move {
+                let limit = params.get("limit").and_then(|l| l.parse::<usize>().ok()).unwrap_or(100);
+                let mut result = Vec::new();
+                for job in jobs
+                    .list()
+                    .into_iter()
+                    .filter(|job| params.get("state").is_none_or(|state| job.state == state))
+                    .filter(|job| params.get("node_id").is_none_or(|node_id| job.nodes.contains(node_id)))
+                    .take(limit)
+                {
+                    let keys: Vec<NodeKey> = job.processes.iter().map(|p| p.key()).collect();
+                    let causes = job_causes(&graph, &keys).await;
+                    let mut value = serde_json::to_value(&job).unwrap_or_default();
+                    value["active_causes"] = json!(causes);
+                    result.push(value);
+                }
+                Ok::<_, warp::Rejection>(warp::reply::json(&json!({ "jobs": result })))
+            },
+        );
+    
+    // GET /api/v1/health[?node_id=xxx&job_id=xxx] - 节点和运行中 job 的健康评分（0-100）
+    let health_route = warp::path!("api" / "v1" / "health")
+        .and(viewer.clone())
+        .and(warp::get())
+        .and(warp::query::<std::collections::HashMap<String, String>>())
+        .and(graph_filter.clone())
+        .and(with_nodes(Arc::clone(&nodes)))
+        .and(jobs_filter.clone())
+        .and_then(
+            |params: std::collections::HashMap<String, String>, graph: Arc<StateGraph>, nodes: Arc<NodeRegistry>, jobs: Arc<JobIndex>| async move {
+                let mut running = Vec::new();
+                for job in jobs.list().into_iter().filter(|job| job.state == "running") {
+                    let keys: Vec<NodeKey> = job.processes.iter().map(|p| p.key()).collect();
+                    let scenes = job_scenes(&graph, &nodes, &job.job_id, &keys).await;
+                    running.push((job, scenes));
+                }
+                let mut report = HealthReport::build(&graph, nodes.inventory(&graph).await, running).await;
+                if let Some(node_id) = params.get("node_id") {
+                    report.nodes.retain(|node| node.node_id == *node_id);
+                    report.jobs.retain(|job| job.nodes.contains(node_id));
+                }
+                if let Some(job_id) = params.get("job_id") {
+                    report.jobs.retain(|job| job.job_id == *job_id);
+                }
+                Ok::<_, warp::Rejection>(warp::reply::json(&report))
+            },
+        );
+    
+    // GET /api/v1/audit?job_id=xxx[&node_id=&pid=&action=&result=&user=&command_id=&limit=100]
+    let audit_route = warp::path!("api" / "v1" / "audit")
+        .and(viewer.clone())
+        .and(warp::get())
+        .and(warp::query::<std::collections::HashMap<String, String>>())
+        .and(with_audit_store(audit_store))
+        .and_then(
+            |params: std::collections::HashMap<String, String>, audit_store: Arc<AuditStore>| async move {
+                match audit_store.query(&params) {
+                    Ok(records) => Ok::<_, warp::Rejection>(warp::reply::with_status(
+                        warp::reply::json(&json!({ "records": records })),
+                        warp::http::StatusCode::OK,
+                    )),
+                    Err(e) => Ok(warp::reply::with_status(
+                        warp::reply::json(&json!({ "error": e })),
+                        warp::http::StatusCode::BAD_REQUEST,
+                    )),
+                }
+            },
+        );
+    
+    // GET /api/v1/events[?node_id=xxx&job_id=xxx&type=error.hw&since=<ms>&after=<id>&limit=100] - 最近事件（从旧到新，按 after 翻页）
+    let events_route = warp::path!("api" / "v1" / "events")
+        .and(viewer.clone())
+        .and(warp::get())
+        .and(warp::query::<std::collections::HashMap<String, String>>())
+        .and(with_events(events))
+        .and_then(
+            |params: std::collections::HashMap<String, String>, events: Arc<EventHistory>| async move {
+                match events.query(&params) {
+                    Ok(page) => Ok::<_, warp::Rejection>(warp::reply::with_status(
+                        warp::reply::json(&page),
+                        warp::http::StatusCode::OK,
+                    )),
+                    Err(e) => Ok(warp::reply::with_status(
+                        warp::reply::json(&json!({ "error": e })),
+                        warp::http::StatusCode::BAD_REQUEST,
+                    )),
+                }
+            },
+        );
     
     // POST /api/v1/fix
     let fix_route = warp::path!("api" / "v1" / "fix")
+        .and(operator_principal.clone())
         .and(warp::post())
         .and(warp::body::json())
-        .and(conns_filter)
-        .and_then(|req: FixRequest, conns: Arc<DashMap<String, mpsc::UnboundedSender<Message>>>| async move {
+        .and(graph_filter.clone())
+        .and(jobs_filter.clone())
+        .and(conns_filter.clone())
+        .and(approvals_filter.clone())
+        .and(commands_filter.clone())
+        .and(metrics_filter.clone())
+        .and_then(move |principal: Principal, mut req: FixRequest, graph: Arc<StateGraph>, jobs: Arc<JobIndex>, conns: Arc<DashMap<String, mpsc::UnboundedSender<Message>>>, approvals: Arc<ApprovalStore>, commands: Arc<CommandStore>, metrics: Arc<HubMetricsCollector>| async move {
+            // 按 Pod 指定时先换算为节点和 PID，审批按换算后的进程绑定
+            if let Err((status, e)) = req.resolve_pod(&graph).await {
+                return Ok::<_, warp::Rejection>(warp::reply::with_status(warp::reply::json(&json!({ "error": e })), status));
+            }
+
+            // 发起人取自认证身份，随命令下发写入节点审计日志
+            let requested_by = principal.name;
+            let action = req.action.clone().unwrap_or_else(|| "GracefulShutdown".to_string());
+
+            // 集群修复的目标必须是该 job 的进程，否则按 job 签发的审批可以用于任意进程
+            let mut fanout = 0;
+            if let Some(ref job_id) = req.job_id {
+                let keys = job_process_keys(&graph, &jobs, job_id).await;
+                let target = NodeKey::process(Some(&req.node_id), req.target_pid);
+                if !keys.contains(&target) {
+                    return Ok(warp::reply::with_status(
+                        warp::reply::json(&json!({ "error": format!("进程 {} 不属于 job {}", target, job_id) })),
+                        warp::http::StatusCode::FORBIDDEN
+                    ));
+                }
+                fanout = keys.len();
+            }
+
+            match req.approval {
+                // 携带审批 token 时先校验（须是为发起人签发的），避免把无效 token 下发到节点
+                Some(ref token) => {
+                    let (scope, target) = req.approval_scope();
+                    if let Err(e) = approvals.verify(token, scope, &target, Some(&requested_by)) {
+                        return Ok(warp::reply::with_status(
+                            warp::reply::json(&json!({ "error": e })),
+                            warp::http::StatusCode::FORBIDDEN
+                        ));
+                    }
+                }
+                // 破坏性动作一律需要审批
+                None if is_destructive_action(&action) => {
+                    let (scope, target) = req.approval_scope();
+                    return Ok(warp::reply::with_status(
+                        warp::reply::json(&json!({
+                            "error": format!(
+                                "{} 是破坏性操作，需要第二位运维签发的审批 token（{} {}）",
+                                action, scope, target
+                            )
+                        })),
+                        warp::http::StatusCode::FORBIDDEN
+                    ));
+                }
+                // 集群修复按 job 逐个进程下发，涉及的进程数超过阈值时必须审批
+                None if fanout > approval_threshold => {
+                    return Ok(warp::reply::with_status(
+                        warp::reply::json(&json!({
+                            "error": format!(
+                                "集群修复涉及 {} 个进程（超过审批阈值 {}），需要第二位运维签发的审批 token",
+                                fanout, approval_threshold
+                            )
+                        })),
+                        warp::http::StatusCode::FORBIDDEN
+                    ));
+                }
+                None => {}
+            }
+            
             // 查找节点连接
             if let Some(sender) = conns.get(&req.node_id) {
-                // 构建命令 JSON
+                // 构建命令 JSON（审批 token 一并下发，由 Agent 在执行前再次校验）
+                let command_id = new_command_id();
+                let span = tracing::info_span!("fix.issue", node_id = %req.node_id, pid = req.target_pid, command_id = %command_id);
                 let command = json!({
                     "intent": "fix",
+                    "command_id": command_id,
+                    "requested_by": requested_by,
                     "target_pid": req.target_pid,
-                    "action": req.action.as_ref().unwrap_or(&"GracefulShutdown".to_string())
+                    "action": action,
+                    "job_id": req.job_id,
+                    "approval": req.approval,
+                    "traceparent": telemetry::traceparent(&span)
                 });
                 
+                // 发送前登记，Agent 的回报不会早于登记到达
+                commands.issue(&command_id, &req.node_id, req.target_pid, &action, req.job_id.clone(), Some(requested_by.clone()));
+                
                 // 发送命令
                 if let Ok(json_str) = serde_json::to_string(&command) {
                     if sender.send(Message::Text(json_str)).is_ok() {
-                        Ok(warp::reply::json(&json!({
-                            "success": true,
-                            "message": format!("命令已发送到节点 {}", req.node_id)
-                        })))
+                        println!(
+                            "[hub] 修复命令 {} 已下发到节点 {} (发起人 {})",
+                            command_id,
+                            req.node_id,
+                            requested_by
+                        );
+                        Ok(warp::reply::with_status(
+                            warp::reply::json(&json!({
+                                "success": true,
+                                "command_id": command_id,
+                                "status": commands::PENDING,
+                                "message": format!("命令已发送到节点 {}，执行结果见 GET /api/v1/fix/{}", req.node_id, command_id)
+                            })),
+                            warp::http::StatusCode::OK
+                        ))
                     } else {
+                        metrics.record_fix_action(&action, &req.node_id, "undelivered");
+                        let _ = commands.complete(CommandResult {
+                            node_id: req.node_id.clone(),
+                            command_id,
+                            status: "undelivered".to_string(),
+                            message: Some("连接已关闭".to_string()),
+                        });
                         Ok(warp::reply::with_status(
                             warp::reply::json(&json!({
                                 "error": "发送命令失败：连接已关闭"
@@ -392,72 +1457,318 @@ fn create_api_routes(
             }
         });
     
-    metrics_route.or(why_route).or(ps_route).or(fix_route)
+    // GET /api/v1/fix/<command_id> - 下发命令的执行状态（pending 表示尚未收到 Agent 回报）
+    let fix_status_route = warp::path!("api" / "v1" / "fix" / String)
+        .and(viewer.clone())
+        .and(warp::get())
+        .and(commands_filter)
+        .and_then(|command_id: String, commands: Arc<CommandStore>| async move {
+            match commands.get(&command_id) {
+                Some(record) => Ok::<_, warp::Rejection>(warp::reply::with_status(
+                    warp::reply::json(&record),
+                    warp::http::StatusCode::OK,
+                )),
+                None => Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": format!("未知的命令: {}", command_id) })),
+                    warp::http::StatusCode::NOT_FOUND,
+                )),
+            }
+        });
+    
+    // POST /api/v1/approvals - 第二位运维签发破窗审批 token（审批人为请求的认证身份）
+    let approvals_route = warp::path!("api" / "v1" / "approvals")
+        .and(operator_principal)
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(approvals_filter.clone())
+        .and_then(|principal: Principal, req: ApprovalRequest, approvals: Arc<ApprovalStore>| async move {
+            if principal.is_anonymous() {
+                return Ok::<_, warp::Rejection>(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": "签发审批需要启用 API 鉴权（--api-keys 或 --oidc-issuer），以识别审批人" })),
+                    warp::http::StatusCode::FORBIDDEN,
+                ));
+            }
+            match approvals.issue(req, &principal.name) {
+                Ok(approval) => {
+                    println!(
+                        "[hub] 审批已签发: {} {} (申请人 {}, 审批人 {})",
+                        approval.action, approval.target, approval.requested_by, approval.approved_by
+                    );
+                    Ok(warp::reply::with_status(
+                        warp::reply::json(&approval),
+                        warp::http::StatusCode::CREATED,
+                    ))
+                }
+                Err(e) => Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": e })),
+                    warp::http::StatusCode::BAD_REQUEST,
+                )),
+            }
+        });
+    
+    // POST /api/v1/approvals/verify - Agent 执行高危操作前校验 token
+    let verify_route = warp::path!("api" / "v1" / "approvals" / "verify")
+        .and(agent.clone())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(approvals_filter)
+        .and_then(|req: VerifyRequest, approvals: Arc<ApprovalStore>| async move {
+            match approvals.verify(&req.token, &req.action, &req.target, req.requested_by.as_deref()) {
+                Ok(approval) => Ok::<_, warp::Rejection>(warp::reply::with_status(
+                    warp::reply::json(&approval),
+                    warp::http::StatusCode::OK,
+                )),
+                Err(e) => Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": e })),
+                    warp::http::StatusCode::FORBIDDEN,
+                )),
+            }
+        });
+    
+    // POST /api/v1/rules/reload - 校验规则目录，通知已连接的 Agent 立即重新拉取（不必等刷新间隔）
+    let rules_reload_route = warp::path!("api" / "v1" / "rules" / "reload")
+        .and(admin.clone())
+        .and(warp::post())
+        .and(warp::any().map({
+            let rules_dir = rules_dir.clone();
+            move || rules_dir.clone()
+        }))
+        .and(conns_filter)
+        .and_then(|rules_dir: Option<std::path::PathBuf>, conns: Arc<DashMap<String, mpsc::UnboundedSender<Message>>>| async move {
+            let bundle = match rules_dir {
+                Some(dir) => RuleBundle::from_dir(&dir).and_then(|bundle| bundle.verify(None).map(|rules| (bundle, rules.len()))),
+                None => Err("Hub 未配置规则目录（--rules-dir）".to_string()),
+            };
+            match bundle {
+                Ok((bundle, rules)) => {
+                    let message = json!({ "rules": bundle.checksum }).to_string();
+                    let notified = conns
+                        .iter()
+                        .filter(|sender| sender.send(Message::Text(message.clone())).is_ok())
+                        .count();
+                    println!("[hub] 规则已重新加载（{} 条），已通知 {} 个节点", rules, notified);
+                    Ok::<_, warp::Rejection>(warp::reply::with_status(
+                        warp::reply::json(&json!({ "success": true, "rules": rules, "checksum": bundle.checksum, "notified": notified })),
+                        warp::http::StatusCode::OK,
+                    ))
+                }
+                Err(e) => Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": e })),
+                    warp::http::StatusCode::UNPROCESSABLE_ENTITY,
+                )),
+            }
+        });
+    
+    // GET /api/v1/rules - 向 Agent 下发规则包（每次请求重新打包，Hub 上修改规则即时生效）
+    let rules_route = warp::path!("api" / "v1" / "rules")
+        .and(agent.clone())
+        .and(warp::get())
+        .and(warp::any().map(move || rules_dir.clone()))
+        .and_then(|rules_dir: Option<std::path::PathBuf>| async move {
+            let bundle = match rules_dir {
+                Some(dir) => RuleBundle::from_dir(&dir),
+                None => Err("Hub 未配置规则目录（--rules-dir）".to_string()),
+            };
+            match bundle {
+                Ok(bundle) => {
+                    let bundle = match rules_key_from_env() {
+                        Some(key) => bundle.signed(&key),
+                        None => bundle,
+                    };
+                    Ok::<_, warp::Rejection>(warp::reply::with_status(
+                        warp::reply::json(&bundle),
+                        warp::http::StatusCode::OK,
+                    ))
+                }
+                Err(e) => Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": e })),
+                    warp::http::StatusCode::NOT_FOUND,
+                )),
+            }
+        });
+    
+    // GET /api/v1/alerts - 活动告警（按指纹合并，含出现次数和静默状态）
+    let alerts_route = warp::path!("api" / "v1" / "alerts")
+        .and(viewer.clone())
+        .and(warp::get())
+        .and(alerts_filter.clone())
+        .map(|alerts: Arc<Alerter>| warp::reply::json(&json!({ "alerts": alerts.active() })));
+    
+    // GET /api/v1/silences - 未到期的静默
+    let silences_route = warp::path!("api" / "v1" / "silences")
+        .and(viewer.clone())
+        .and(warp::get())
+        .and(alerts_filter.clone())
+        .map(|alerts: Arc<Alerter>| warp::reply::json(&json!({ "silences": alerts.silences().list() })));
+    
+    // POST /api/v1/silences - 维护窗口内静默节点或 job 的告警
+    let silence_create_route = warp::path!("api" / "v1" / "silences")
+        .and(operator.clone())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(alerts_filter.clone())
+        .and_then(|req: SilenceRequest, alerts: Arc<Alerter>| async move {
+            match alerts.silences().add(req) {
+                Ok(silence) => {
+                    println!(
+                        "[hub] 静默已创建: {} node={} job={} ({})",
+                        silence.id,
+                        silence.node_id.as_deref().unwrap_or("*"),
+                        silence.job_id.as_deref().unwrap_or("*"),
+                        silence.reason
+                    );
+                    Ok::<_, warp::Rejection>(warp::reply::with_status(
+                        warp::reply::json(&silence),
+                        warp::http::StatusCode::CREATED,
+                    ))
+                }
+                Err(e) => Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": e })),
+                    warp::http::StatusCode::BAD_REQUEST,
+                )),
+            }
+        });
+    
+    // DELETE /api/v1/silences/<id> - 提前结束静默
+    let silence_remove_route = warp::path!("api" / "v1" / "silences" / String)
+        .and(operator.clone())
+        .and(warp::delete())
+        .and(alerts_filter)
+        .and_then(|id: String, alerts: Arc<Alerter>| async move {
+            match alerts.silences().remove(&id) {
+                Some(silence) => {
+                    println!("[hub] 静默 {} 已结束", silence.id);
+                    Ok::<_, warp::Rejection>(warp::reply::with_status(
+                        warp::reply::json(&json!({ "success": true, "id": silence.id })),
+                        warp::http::StatusCode::OK,
+                    ))
+                }
+                None => Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": format!("未知的静默: {}", id) })),
+                    warp::http::StatusCode::NOT_FOUND,
+                )),
+            }
+        });
+    
+    metrics_route
+        .or(why_route)
+        .or(ps_route)
+        .or(pod_route)
+        .or(graph_route)
+        .or(fix_route)
+        .or(fix_status_route)
+        .or(approvals_route)
+        .or(verify_route)
+        .or(rules_route)
+        .or(rules_reload_route)
+        .or(audit_route)
+        .or(events_route)
+        .or(nodes_route)
+        .or(node_remove_route)
+        .or(node_repaired_route)
+        .or(jobs_route)
+        .or(health_route)
+        .or(alerts_route)
+        .or(silences_route)
+        .or(silence_create_route)
+        .or(silence_remove_route)
+        .recover(auth::recover_denied)
 }
 
 /// 集群级根因分析：根据 job_id 查找所有相关进程并分析根因
+/// 除阻塞根因外，还会跨节点对比同 job 各 rank，给出掉队进程
+/// job 各进程在全局图中的根因（带节点前缀，去重排序）
+///
+/// 使用带命名空间的节点键，避免不同主机上的同名 PID 混淆
+async fn job_causes(graph: &StateGraph, keys: &[NodeKey]) -> Vec<String> {
+    let mut causes = Vec::new();
+    for key in keys {
+        for cause in graph.find_root_cause_by_key(key).await {
+            // 添加节点信息到根因描述中
+            causes.push(match key.node_id() {
+                Some(node_name) => format!("{}: {}", node_name, cause),
+                None => cause,
+            });
+        }
+    }
+    causes.sort();
+    causes.dedup();
+    causes
+}
+
+/// job 的所有进程（集群修复的目标）：优先取 job 索引，索引中没有时回退到扫描全局图
+async fn job_process_keys(graph: &StateGraph, jobs: &JobIndex, job_id: &str) -> Vec<NodeKey> {
+    match jobs.get(job_id) {
+        Some(job) => job.processes.iter().map(|p| p.key()).collect(),
+        None => graph
+            .find_processes_by_job(job_id)
+            .await
+            .iter()
+            .map(Node::key)
+            .collect(),
+    }
+}
+
+#[tracing::instrument(name = "diag.cluster", skip(graph, jobs, nodes, straggler_margin))]
 async fn cluster_why(
     graph: Arc<StateGraph>,
+    jobs: &JobIndex,
+    nodes: &NodeRegistry,
     target_job_id: &str,
-) -> Result<(Vec<String>, Vec<serde_json::Value>), Box<dyn std::error::Error>> {
-    let nodes = graph.nodes.read().await;
-    let mut global_causes = Vec::new();
+    straggler_margin: f64,
+) -> Result<(Vec<String>, Vec<serde_json::Value>, Vec<ClusterAnalysis>), Box<dyn std::error::Error>> {
+    // 1. 找出所有属于这个 job_id 的进程节点
+    let job_pids = job_process_keys(&graph, jobs, target_job_id).await;
+    
+    if job_pids.is_empty() {
+        return Ok((vec![format!("未找到 job_id={} 的进程", target_job_id)], Vec::new(), Vec::new()));
+    }
     
-    // 1. 在全局图中找出所有属于这个 job_id 的进程节点
-    let job_pids: Vec<String> = nodes
+    // 2. 构建进程列表（用于 CLI 提取节点和 PID）
+    let process_list: Vec<serde_json::Value> = job_pids
         .iter()
-        .filter(|(_, n)| {
-            n.node_type == NodeType::Process
-                && n.metadata.get("job_id") == Some(&target_job_id.to_string())
+        .filter_map(|key| {
+            let (node_id, pid) = (key.node_id()?, key.pid()?);
+            Some(json!({
+                "node_id": node_id,
+                "pid": pid,
+                "node_id_full": key.to_string()
+            }))
         })
-        .map(|(id, _)| id.clone())
         .collect();
     
-    drop(nodes);
+    // 3. 对每个进程节点，在全局图中发起根因分析
+    let mut global_causes = job_causes(&graph, &job_pids).await;
     
-    if job_pids.is_empty() {
-        return Ok((vec![format!("未找到 job_id={} 的进程", target_job_id)], Vec::new()));
-    }
+    // 4. 跨节点掉队检测（放在阻塞根因之后）
+    let stragglers = graph.find_job_stragglers(target_job_id, straggler_margin).await;
+    global_causes.extend(stragglers.iter().map(|s| s.describe()));
     
-    // 2. 构建进程列表（用于 CLI 提取节点和 PID）
-    let mut process_list = Vec::new();
+    // 5. 跨节点场景分析
+    let scenes = job_scenes(&graph, nodes, target_job_id, &job_pids).await;
     
-    // 3. 对每个进程节点，在全局图中发起根因分析
-    // 直接使用完整的节点 ID（包含命名空间），避免命名空间丢失
-    for pid_id in &job_pids {
-        // 提取节点 ID 和 PID 并添加到进程列表
-        if pid_id.contains("::") {
-            let parts: Vec<&str> = pid_id.split("::").collect();
-            let node_id = parts[0].to_string();
-            if let Some(pid_part) = parts.get(1) {
-                if let Some(pid_str) = pid_part.strip_prefix("pid-") {
-                    if let Ok(pid) = pid_str.parse::<u32>() {
-                        process_list.push(json!({
-                            "node_id": node_id,
-                            "pid": pid,
-                            "node_id_full": pid_id
-                        }));
-                    }
-                }
-            }
-        }
-        
-        let causes = graph.find_root_cause_by_id(pid_id).await;
-        for cause in causes {
-            // 添加节点信息到根因描述中
-            let node_info = if pid_id.contains("::") {
-                let node_name = pid_id.split("::").next().unwrap_or("unknown");
-                format!("{}: {}", node_name, cause)
-            } else {
-                cause
-            };
-            global_causes.push(node_info);
+    Ok((global_causes, process_list, scenes))
+}
+
+/// 对 job 做跨节点场景分析（只看仍在运行的 rank，交换机取自节点注册标签）
+async fn job_scenes(graph: &StateGraph, nodes: &NodeRegistry, job_id: &str, keys: &[NodeKey]) -> Vec<ClusterAnalysis> {
+    let mut ranks = Vec::new();
+    for key in keys {
+        let running = graph.node_by_key(key).await.is_some_and(|node| {
+            !matches!(node.metadata.get("state").map(String::as_str), Some("exit" | "zombie" | "crash" | "failed"))
+        });
+        if running {
+            ranks.push(key.clone());
         }
     }
-    
-    // 4. 去重并返回全局根因和进程列表
-    global_causes.sort();
-    global_causes.dedup();
-    
-    Ok((global_causes, process_list))
+    let switches = nodes
+        .list()
+        .into_iter()
+        .filter_map(|info| {
+            let switch = info.registration.labels.get(SWITCH_LABEL)?.clone();
+            Some((info.registration.node_id, switch))
+        })
+        .collect();
+    let scope = JobScope { job_id: job_id.to_string(), ranks, switches };
+    ClusterSceneIdentifier::new().analyze(graph, &scope).await
 }
@@ -3,24 +3,32 @@
 //! 暴露 Ark Hub 的指标供 Prometheus 抓取
 
 use prometheus::{
-    register_counter_vec, register_gauge_vec, register_histogram_vec,
-    CounterVec, GaugeVec, HistogramVec, Encoder, TextEncoder,
+    register_counter_vec, register_gauge_vec, register_histogram_vec, register_int_counter_vec,
+    CounterVec, GaugeVec, HistogramVec, IntCounterVec, Encoder, TextEncoder,
 };
+use prometheus::proto::MetricFamily;
 use std::sync::Arc;
 use ark_core::graph::StateGraph;
+use crate::health::HealthReport;
 
 /// Hub Metrics 收集器
 pub struct HubMetricsCollector {
     // 基础指标
     global_graph_nodes_total: GaugeVec,
     global_graph_edges_total: GaugeVec,
+    global_graph_reordered_events_total: IntCounterVec,
     events_received_total: CounterVec,
     websocket_connections: GaugeVec,
+    agent_nodes: GaugeVec,
     
     // 详细指标
     cluster_query_duration_seconds: HistogramVec,
     cluster_fix_actions_total: CounterVec,
     agent_events_received_total: CounterVec,
+    job_blocked: GaugeVec,
+    node_recent_errors: GaugeVec,
+    k8s_controller_leader: GaugeVec,
+    k8s_leader_transitions_total: CounterVec,
 }
 
 impl HubMetricsCollector {
@@ -38,6 +46,11 @@ impl HubMetricsCollector {
                 "全局图中边总数",
                 &["edge_type"]
             )?,
+            global_graph_reordered_events_total: register_int_counter_vec!(
+                "ark_hub_graph_reordered_events_total",
+                "全局图中乱序到达的事件数（reordered: 早于水位线；stale_update: 被跳过的节点状态覆盖）",
+                &["kind"]
+            )?,
             events_received_total: register_counter_vec!(
                 "ark_hub_events_received_total",
                 "Hub 接收的事件总数",
@@ -48,6 +61,11 @@ impl HubMetricsCollector {
                 "当前 WebSocket 连接数",
                 &["status"]
             )?,
+            agent_nodes: register_gauge_vec!(
+                "ark_hub_agent_nodes",
+                "已注册的 Agent 节点数（online / stale：超时未收到心跳 / offline：连接断开）",
+                &["status"]
+            )?,
             
             // 详细指标
             cluster_query_duration_seconds: register_histogram_vec!(
@@ -66,6 +84,26 @@ impl HubMetricsCollector {
                 "从各 Agent 接收的事件数",
                 &["node_id", "event_type"]
             )?,
+            job_blocked: register_gauge_vec!(
+                "ark_hub_job_blocked",
+                "运行中 job 的进程上活动的 BlockedBy 边数",
+                &["job_id"]
+            )?,
+            node_recent_errors: register_gauge_vec!(
+                "ark_hub_node_recent_errors",
+                "各节点错误窗口内的出错实体数",
+                &["node_id"]
+            )?,
+            k8s_controller_leader: register_gauge_vec!(
+                "ark_hub_k8s_controller_leader",
+                "本实例是否为 K8s 控制器的 leader（1 / 0）",
+                &["identity"]
+            )?,
+            k8s_leader_transitions_total: register_counter_vec!(
+                "ark_hub_k8s_leader_transitions_total",
+                "本实例成为 K8s 控制器 leader 的次数",
+                &["identity"]
+            )?,
         })
     }
     
@@ -77,12 +115,7 @@ impl HubMetricsCollector {
         // 统计节点类型
         let mut node_counts = std::collections::HashMap::new();
         for node in nodes.values() {
-            let node_type = match node.node_type {
-                ark_core::graph::NodeType::Process => "process",
-                ark_core::graph::NodeType::Resource => "resource",
-                ark_core::graph::NodeType::Error => "error",
-            };
-            *node_counts.entry(node_type).or_insert(0) += 1;
+            *node_counts.entry(node.node_type.as_str()).or_insert(0) += 1;
         }
         
         // 更新节点指标
@@ -95,12 +128,7 @@ impl HubMetricsCollector {
         // 统计边类型
         let mut edge_counts = std::collections::HashMap::new();
         for edge in &edges {
-            let edge_type = match edge.edge_type {
-                ark_core::graph::EdgeType::Consumes => "consumes",
-                ark_core::graph::EdgeType::WaitsOn => "waits_on",
-                ark_core::graph::EdgeType::BlockedBy => "blocked_by",
-            };
-            *edge_counts.entry(edge_type).or_insert(0) += 1;
+            *edge_counts.entry(edge.edge_type.as_str()).or_insert(0) += 1;
         }
         
         // 更新边指标
@@ -109,6 +137,31 @@ impl HubMetricsCollector {
                 .with_label_values(&[edge_type])
                 .set(count as f64);
         }
+
+        // 更新乱序事件指标（图中的计数只增不减，计数器补上差值）
+        for (kind, count) in [
+            ("reordered", graph.reordered_event_count()),
+            ("stale_update", graph.stale_update_count()),
+        ] {
+            let counter = self.global_graph_reordered_events_total.with_label_values(&[kind]);
+            counter.inc_by(count.saturating_sub(counter.get()));
+        }
+    }
+    
+    /// 更新集群级指标（已结束的 job 和移除的节点不再导出）
+    pub fn update_cluster_metrics(&self, report: &HealthReport) {
+        self.job_blocked.reset();
+        for job in &report.jobs {
+            self.job_blocked
+                .with_label_values(&[&job.job_id])
+                .set(job.health.blocked as f64);
+        }
+        self.node_recent_errors.reset();
+        for node in &report.nodes {
+            self.node_recent_errors
+                .with_label_values(&[&node.node_id])
+                .set(node.health.errors as f64);
+        }
     }
     
     /// 记录接收的事件
@@ -132,6 +185,15 @@ impl HubMetricsCollector {
             .set(disconnected as f64);
     }
     
+    /// 更新各状态的 Agent 节点数
+    pub fn update_agent_nodes(&self, counts: &std::collections::BTreeMap<&'static str, usize>) {
+        for (status, count) in counts {
+            self.agent_nodes
+                .with_label_values(&[status])
+                .set(*count as f64);
+        }
+    }
+    
     /// 记录集群查询耗时
     pub fn record_query_duration(&self, query_type: &str, duration_seconds: f64) {
         self.cluster_query_duration_seconds
@@ -146,6 +208,25 @@ impl HubMetricsCollector {
             .inc();
     }
     
+    /// 更新 K8s 控制器的 leader 状态
+    pub fn set_k8s_leader(&self, identity: &str, leader: bool) {
+        self.k8s_controller_leader
+            .with_label_values(&[identity])
+            .set(if leader { 1.0 } else { 0.0 });
+    }
+    
+    /// 记录本实例成为 leader
+    pub fn record_k8s_leader_acquired(&self, identity: &str) {
+        self.k8s_leader_transitions_total
+            .with_label_values(&[identity])
+            .inc();
+    }
+    
+    /// 当前的全部指标（remote_write 推送）
+    pub fn families(&self) -> Vec<MetricFamily> {
+        prometheus::gather()
+    }
+    
     /// 生成 Prometheus 格式的指标输出
     pub fn gather(&self) -> Result<String, prometheus::Error> {
         let encoder = TextEncoder::new();
@@ -0,0 +1,270 @@
+//! Agent 节点注册与心跳
+//!
+//! Agent 每次建立连接后先发送注册消息
+//! `{"type": "register", "node_id", "hostname", "labels", "agent_version", "capabilities", "heartbeat_secs"}`，
+//! 之后定期发送 `{"type": "heartbeat", "node_id"}`。Hub 以注册消息中的 node_id 登记连接（下发命令按它寻址）；
+//! 超过 `--node-stale-secs` 未收到该节点任何消息的标记为 stale，连接断开的标记为 offline。
+//!
+//! `GET /api/v1/nodes` 在注册信息之外，从全局状态图统计每个节点的 GPU / NPU 数和近期错误数；
+//! 只出现在状态图中（如从存储恢复、旧版 Agent）而未注册的节点以 offline 列出；下级 Hub 转发的节点
+//! （见 `federation`）随下级 Hub 的连接状态，并带 `cluster_id` 标签。
+
+use crate::federation::{belongs_to, FEDERATION_CAPABILITY};
+use ark_core::graph::{NodeType, StateGraph};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Agent 发送的控制消息（带 `type` 字段，与事件和审计记录区分）
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlMessage {
+    Register(Registration),
+    Heartbeat,
+}
+
+/// 节点注册信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Registration {
+    pub node_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    #[serde(default)]
+    pub agent_version: String,
+    /// Agent 支持的能力（如 events、ack、audit、fix）
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Agent 的心跳间隔（秒）
+    #[serde(default)]
+    pub heartbeat_secs: Option<u64>,
+}
+
+/// 节点状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeStatus {
+    Online,
+    Stale,
+    Offline,
+}
+
+impl NodeStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeStatus::Online => "online",
+            NodeStatus::Stale => "stale",
+            NodeStatus::Offline => "offline",
+        }
+    }
+}
+
+/// 已注册节点
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeInfo {
+    #[serde(flatten)]
+    pub registration: Registration,
+    pub addr: String,
+    /// 本次连接注册时间（毫秒）
+    pub connected_at: u64,
+    /// 最近一次收到该节点消息的时间（毫秒）
+    pub last_seen: u64,
+    pub status: NodeStatus,
+    /// 当前连接的编号：节点重连后旧连接的断开不影响新连接
+    #[serde(skip)]
+    connection: u64,
+}
+
+/// 节点清单中的一项：注册信息 + 从状态图统计的资源和错误
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeSummary {
+    #[serde(flatten)]
+    pub info: NodeInfo,
+    pub gpus: usize,
+    pub npus: usize,
+    /// 错误窗口（状态图 error_window_ms）内出错的实体数
+    pub recent_errors: usize,
+}
+
+/// 状态图中某个节点的资源和错误计数
+#[derive(Default)]
+struct GraphCounts {
+    gpus: usize,
+    npus: usize,
+    errors: usize,
+    /// 主机节点的最近更新时间（未注册节点的 last_seen）
+    last_update: u64,
+}
+
+/// 资源实体是否为 GPU / NPU（与探针的 entity_id 命名一致，如 gpu-03、npu-0）
+fn accelerator_kind(entity: &str) -> Option<&'static str> {
+    if entity.starts_with("gpu") {
+        Some("gpu")
+    } else if entity.starts_with("npu") || entity.contains("ascend") {
+        Some("npu")
+    } else {
+        None
+    }
+}
+
+/// 节点注册表（只在内存中，Hub 重启后由 Agent 重连时重新注册）
+pub struct NodeRegistry {
+    nodes: DashMap<String, NodeInfo>,
+    stale_after: Duration,
+    next_connection: AtomicU64,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+impl NodeRegistry {
+    pub fn new(stale_after: Duration) -> Self {
+        Self {
+            nodes: DashMap::new(),
+            stale_after,
+            next_connection: AtomicU64::new(1),
+        }
+    }
+
+    /// 登记节点的一次连接，返回连接编号
+    pub fn register(&self, registration: Registration, addr: SocketAddr) -> u64 {
+        let connection = self.next_connection.fetch_add(1, Ordering::Relaxed);
+        let now = now_ms();
+        self.nodes.insert(
+            registration.node_id.clone(),
+            NodeInfo {
+                registration,
+                addr: addr.to_string(),
+                connected_at: now,
+                last_seen: now,
+                status: NodeStatus::Online,
+                connection,
+            },
+        );
+        connection
+    }
+
+    /// 收到节点的心跳或其他消息
+    pub fn seen(&self, node_id: &str, connection: u64) {
+        if let Some(mut node) = self.nodes.get_mut(node_id) {
+            if node.connection == connection {
+                node.last_seen = now_ms();
+                node.status = NodeStatus::Online;
+            }
+        }
+    }
+
+    /// 连接断开（节点已用新连接重新注册时忽略）
+    pub fn disconnected(&self, node_id: &str, connection: u64) {
+        if let Some(mut node) = self.nodes.get_mut(node_id) {
+            if node.connection == connection {
+                node.status = NodeStatus::Offline;
+            }
+        }
+    }
+
+    /// 从注册表中移除节点
+    pub fn remove(&self, node_id: &str) -> Option<NodeInfo> {
+        self.nodes.remove(node_id).map(|(_, node)| node)
+    }
+
+    /// 所有节点（按 node_id 排序），状态按最近消息时间刷新
+    pub fn list(&self) -> Vec<NodeInfo> {
+        let now = now_ms();
+        let stale_after = self.stale_after.as_millis() as u64;
+        let mut nodes: Vec<NodeInfo> = self
+            .nodes
+            .iter_mut()
+            .map(|mut node| {
+                if node.status == NodeStatus::Online && now.saturating_sub(node.last_seen) > stale_after {
+                    node.status = NodeStatus::Stale;
+                }
+                node.clone()
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.registration.node_id.cmp(&b.registration.node_id));
+        nodes
+    }
+
+    /// 节点清单：已注册节点和只出现在状态图中的节点，附带资源和错误计数
+    pub async fn inventory(&self, graph: &StateGraph) -> Vec<NodeSummary> {
+        let mut counts: BTreeMap<String, GraphCounts> = BTreeMap::new();
+        for node in graph.get_nodes_async().await.into_values() {
+            if node.node_type == NodeType::Host {
+                if let Some(host) = node.metadata.get("host") {
+                    counts.entry(host.clone()).or_default().last_update = node.last_update;
+                }
+                continue;
+            }
+            let key = node.key();
+            let Some(node_id) = key.node_id() else {
+                continue;
+            };
+            let entry = counts.entry(node_id.to_string()).or_default();
+            match node.node_type {
+                NodeType::Resource => match accelerator_kind(key.entity()) {
+                    Some("gpu") => entry.gpus += 1,
+                    Some(_) => entry.npus += 1,
+                    None => {}
+                },
+                NodeType::Error => entry.errors += 1,
+                _ => {}
+            }
+        }
+
+        let mut summaries: Vec<NodeSummary> = self
+            .list()
+            .into_iter()
+            .map(|info| {
+                let counts = counts.remove(&info.registration.node_id).unwrap_or_default();
+                NodeSummary { info, gpus: counts.gpus, npus: counts.npus, recent_errors: counts.errors }
+            })
+            .collect();
+        // 下级 Hub 转发的节点（<cluster_id>/<node_id>）随下级 Hub 的连接状态
+        let clusters: Vec<(String, NodeStatus)> = summaries
+            .iter()
+            .filter(|node| node.info.registration.capabilities.iter().any(|c| c == FEDERATION_CAPABILITY))
+            .map(|node| (node.info.registration.node_id.clone(), node.info.status))
+            .collect();
+        summaries.extend(counts.into_iter().map(|(node_id, counts)| {
+            let cluster = clusters.iter().find(|(cluster_id, _)| belongs_to(&node_id, cluster_id));
+            let mut registration = Registration { node_id, ..Default::default() };
+            if let Some((cluster_id, _)) = cluster {
+                registration.labels.insert("cluster_id".to_string(), cluster_id.clone());
+            }
+            NodeSummary {
+                info: NodeInfo {
+                    registration,
+                    addr: String::new(),
+                    connected_at: 0,
+                    last_seen: counts.last_update,
+                    status: cluster.map(|(_, status)| *status).unwrap_or(NodeStatus::Offline),
+                    connection: 0,
+                },
+                gpus: counts.gpus,
+                npus: counts.npus,
+                recent_errors: counts.errors,
+            }
+        }));
+        summaries.sort_by(|a, b| a.info.registration.node_id.cmp(&b.info.registration.node_id));
+        summaries
+    }
+
+    /// 各状态的节点数
+    pub fn counts(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts: BTreeMap<&'static str, usize> =
+            [NodeStatus::Online, NodeStatus::Stale, NodeStatus::Offline].iter().map(|s| (s.as_str(), 0)).collect();
+        for node in self.list() {
+            *counts.entry(node.status.as_str()).or_insert(0) += 1;
+        }
+        counts
+    }
+}
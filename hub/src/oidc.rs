@@ -0,0 +1,136 @@
+//! OIDC 令牌校验：HTTP API 的 Bearer 令牌也可以是身份提供方签发的 JWT
+//!
+//! 启动时从 `<issuer>/.well-known/openid-configuration` 取得 JWKS 地址并拉取公钥；令牌的 kid 不在缓存中时
+//! 重新拉取（至少间隔 `JWKS_REFRESH_MIN`，应对身份提供方轮换密钥）。角色取自 `--oidc-role-claim`
+//! 指向的声明（支持 `realm_access.roles` 这样的嵌套路径，值为字符串或字符串数组），取其中最高的角色；
+//! 身份取自 `sub` 声明。
+
+use crate::auth::{Principal, Role};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// 两次因未知 kid 重新拉取 JWKS 的最小间隔
+const JWKS_REFRESH_MIN: Duration = Duration::from_secs(60);
+
+/// OIDC 配置
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    pub issuer: String,
+    /// 期望的 aud（未指定时不校验）
+    pub audience: Option<String>,
+    /// 携带角色的声明路径
+    pub role_claim: String,
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    jwks_uri: String,
+}
+
+/// OIDC 令牌校验器
+pub struct OidcVerifier {
+    config: OidcConfig,
+    jwks_uri: String,
+    client: reqwest::Client,
+    /// 公钥和拉取时间
+    keys: RwLock<(JwkSet, Instant)>,
+}
+
+impl OidcVerifier {
+    /// 发现 JWKS 地址并拉取公钥（失败时 Hub 启动失败）
+    pub async fn discover(config: OidcConfig) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let url = format!("{}/.well-known/openid-configuration", config.issuer.trim_end_matches('/'));
+        let discovery: Discovery = fetch_json(&client, &url).await?;
+        let keys = fetch_json(&client, &discovery.jwks_uri).await?;
+        Ok(Self {
+            config,
+            jwks_uri: discovery.jwks_uri,
+            client,
+            keys: RwLock::new((keys, Instant::now())),
+        })
+    }
+
+    pub fn issuer(&self) -> &str {
+        &self.config.issuer
+    }
+
+    /// 校验 JWT，返回 sub 和其中最高的角色（没有可识别的角色时为 None）
+    pub async fn identify(&self, token: &str) -> Result<Option<Principal>, String> {
+        let header = decode_header(token).map_err(|e| format!("无效的令牌: {}", e))?;
+        let kid = header.kid.ok_or("令牌缺少 kid")?;
+        let key = match self.key(&kid).await {
+            Some(key) => key,
+            None => {
+                self.refresh().await?;
+                self.key(&kid).await.ok_or_else(|| format!("未知的签名密钥: {}", kid))?
+            }
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.config.issuer]);
+        match self.config.audience {
+            Some(ref audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        let claims = decode::<serde_json::Value>(token, &key, &validation)
+            .map_err(|e| format!("令牌校验失败: {}", e))?
+            .claims;
+        let subject = claims
+            .get("sub")
+            .and_then(|sub| sub.as_str())
+            .filter(|sub| !sub.is_empty())
+            .ok_or("令牌缺少 sub 声明")?
+            .to_string();
+
+        let claim = self
+            .config
+            .role_claim
+            .split('.')
+            .try_fold(&claims, |value, field| value.get(field));
+        let roles = match claim {
+            Some(serde_json::Value::String(role)) => vec![role.as_str()],
+            Some(serde_json::Value::Array(roles)) => roles.iter().filter_map(|r| r.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        Ok(roles
+            .into_iter()
+            .filter_map(Role::parse)
+            .max()
+            .map(|role| Principal { name: subject, role }))
+    }
+
+    async fn key(&self, kid: &str) -> Option<DecodingKey> {
+        let keys = self.keys.read().await;
+        keys.0.find(kid).and_then(|jwk| DecodingKey::from_jwk(jwk).ok())
+    }
+
+    /// 重新拉取 JWKS（距上次拉取不足 JWKS_REFRESH_MIN 时跳过）
+    async fn refresh(&self) -> Result<(), String> {
+        let mut keys = self.keys.write().await;
+        if keys.1.elapsed() < JWKS_REFRESH_MIN {
+            return Ok(());
+        }
+        let fetched = fetch_json(&self.client, &self.jwks_uri).await?;
+        *keys = (fetched, Instant::now());
+        Ok(())
+    }
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(client: &reqwest::Client, url: &str) -> Result<T, String> {
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("请求 {} 失败: {}", url, e))?
+        .json()
+        .await
+        .map_err(|e| format!("解析 {} 失败: {}", url, e))
+}
@@ -0,0 +1,230 @@
+//! 故障处置策略：`ArkRemediationPolicy` CRD
+//!
+//! 各集群对隔离的影响范围要求不同，由集群级 CRD 描述哪类故障触发哪些 K8s 动作：
+//! ```yaml
+//! apiVersion: ark.io/v1alpha1
+//! kind: ArkRemediationPolicy
+//! metadata:
+//!   name: gpu-nodes
+//! spec:
+//!   rules:
+//!     - faults: [xid_error, hardware_failure]   # 故障类型，为空时匹配全部
+//!       cordon: true
+//!       taint: {key: ark.io/hardware-failure, effect: NoSchedule}
+//!       evict:
+//!         gracePeriodSeconds: 300
+//!         excludeNamespaces: [kube-system]    # 不驱逐的命名空间
+//!         excludeLabels: [ark.io/no-evict, app=etcd]   # 带有任一标签（key 或 key=value）的 Pod 不驱逐
+//!         maxParallel: 5                       # 同时进行的驱逐数
+//!         pdbRetrySeconds: 600                 # 被 PDB 拒绝时在该时长内重试
+//!     - faults: [rdma_link_down]
+//!       notifyOnly: true                        # 只告警，不动节点
+//! ```
+//! 故障类型为 `xid_error`、`rdma_link_down`、`storage_failure`、`hardware_failure`。多个策略按名称排序后依次匹配，
+//! 取第一条匹配的规则；没有策略或没有规则匹配时沿用默认动作（打 `ark.io/hardware-failure:NoSchedule` 污点并驱逐 Pod）。
+//! 驱逐默认按 Pod 优先级（`spec.priority`）从低到高分批进行，同一优先级的 Pod 并行驱逐，`priorityOrder: false` 时不分批；
+//! DaemonSet 和静态 Pod 总是跳过。无效的策略被忽略，错误写入其 `status.error`。CRD 定义见 `deploy/crd-remediation-policy.yaml`。
+
+use kube::CustomResource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::k8s_controller::TAINT_KEY;
+
+/// 故障类型（`IrreversibleFault::kind`）
+pub const FAULT_KINDS: [&str; 4] = ["xid_error", "rdma_link_down", "storage_failure", "hardware_failure"];
+
+/// 污点效果
+const TAINT_EFFECTS: [&str; 3] = ["NoSchedule", "PreferNoSchedule", "NoExecute"];
+
+/// 故障处置策略
+#[derive(CustomResource, Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[kube(
+    group = "ark.io",
+    version = "v1alpha1",
+    kind = "ArkRemediationPolicy",
+    shortname = "arp",
+    status = "RemediationPolicyStatus"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct RemediationPolicySpec {
+    #[serde(default)]
+    pub rules: Vec<RemediationRule>,
+}
+
+/// 一条规则：匹配的故障类型和对节点执行的动作
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RemediationRule {
+    /// 匹配的故障类型，为空时匹配全部
+    #[serde(default)]
+    pub faults: Vec<String>,
+    /// 只告警，不执行任何 K8s 动作
+    #[serde(default)]
+    pub notify_only: bool,
+    /// 把节点标记为不可调度（kubectl cordon）
+    #[serde(default)]
+    pub cordon: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taint: Option<TaintSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evict: Option<EvictSpec>,
+}
+
+/// 给节点打的污点
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TaintSpec {
+    pub key: String,
+    /// NoSchedule、PreferNoSchedule 或 NoExecute
+    #[serde(default = "default_effect")]
+    pub effect: String,
+}
+
+fn default_effect() -> String {
+    "NoSchedule".to_string()
+}
+
+/// 驱逐节点上的 Pod（Eviction API，尊重 PDB）
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EvictSpec {
+    /// Pod 的优雅退出时间（秒），未指定时使用 Pod 自己的 terminationGracePeriodSeconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grace_period_seconds: Option<i64>,
+    /// 不驱逐这些命名空间中的 Pod
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_namespaces: Vec<String>,
+    /// 带有任一标签的 Pod 不驱逐（`key` 匹配任意值，`key=value` 匹配指定值）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_labels: Vec<String>,
+    /// 按 Pod 优先级从低到高分批驱逐
+    #[serde(default = "default_priority_order")]
+    pub priority_order: bool,
+    /// 同时进行的驱逐数
+    #[serde(default = "default_max_parallel")]
+    pub max_parallel: u32,
+    /// 被 PDB 拒绝（429）时在该时长（秒）内重试，0 表示不重试
+    #[serde(default)]
+    pub pdb_retry_seconds: u64,
+}
+
+fn default_priority_order() -> bool {
+    true
+}
+
+fn default_max_parallel() -> u32 {
+    5
+}
+
+impl Default for EvictSpec {
+    fn default() -> Self {
+        Self {
+            grace_period_seconds: None,
+            exclude_namespaces: Vec::new(),
+            exclude_labels: Vec::new(),
+            priority_order: default_priority_order(),
+            max_parallel: default_max_parallel(),
+            pdb_retry_seconds: 0,
+        }
+    }
+}
+
+impl EvictSpec {
+    /// Pod 是否不在排除范围内
+    pub fn should_evict(&self, namespace: &str, labels: &BTreeMap<String, String>) -> bool {
+        if self.exclude_namespaces.iter().any(|excluded| excluded == namespace) {
+            return false;
+        }
+        !self.exclude_labels.iter().any(|selector| match selector.split_once('=') {
+            Some((key, value)) => labels.get(key).is_some_and(|v| v == value),
+            None => labels.contains_key(selector.as_str()),
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.grace_period_seconds.is_some_and(|secs| secs < 0) {
+            return Err("evict.gracePeriodSeconds 不能为负数".to_string());
+        }
+        if self.max_parallel == 0 {
+            return Err("evict.maxParallel 至少为 1".to_string());
+        }
+        if let Some(selector) = self.exclude_labels.iter().find(|selector| selector.split('=').next().unwrap_or_default().trim().is_empty()) {
+            return Err(format!("无效的 evict.excludeLabels: {:?}（格式为 key 或 key=value）", selector));
+        }
+        Ok(())
+    }
+}
+
+/// 策略状态
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RemediationPolicyStatus {
+    /// Hub 已处理的 metadata.generation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed_generation: Option<i64>,
+    /// 策略无效时的原因（无效的策略被忽略）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RemediationRule {
+    /// 没有策略匹配时的默认动作：打故障污点并驱逐 Pod
+    pub fn default_action() -> Self {
+        Self {
+            taint: Some(TaintSpec { key: TAINT_KEY.to_string(), effect: default_effect() }),
+            evict: Some(EvictSpec::default()),
+            ..Self::default()
+        }
+    }
+
+    pub fn matches(&self, fault_kind: &str) -> bool {
+        self.faults.is_empty() || self.faults.iter().any(|kind| kind == fault_kind)
+    }
+
+    /// 是否改动节点（污点或 cordon）
+    pub fn changes_node(&self) -> bool {
+        !self.notify_only && (self.cordon || self.taint.is_some())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(kind) = self.faults.iter().find(|kind| !FAULT_KINDS.contains(&kind.as_str())) {
+            return Err(format!("未知的故障类型: {}（可选 {}）", kind, FAULT_KINDS.join(", ")));
+        }
+        if self.notify_only && (self.cordon || self.taint.is_some() || self.evict.is_some()) {
+            return Err("notifyOnly 不能与 cordon、taint、evict 同时指定".to_string());
+        }
+        if let Some(ref taint) = self.taint {
+            if taint.key.trim().is_empty() {
+                return Err("taint.key 不能为空".to_string());
+            }
+            if !TAINT_EFFECTS.contains(&taint.effect.as_str()) {
+                return Err(format!("无效的 taint.effect: {}（可选 {}）", taint.effect, TAINT_EFFECTS.join(", ")));
+            }
+        }
+        if let Some(ref evict) = self.evict {
+            evict.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl RemediationPolicySpec {
+    /// 校验全部规则，错误信息带规则序号
+    pub fn validate(&self) -> Result<(), String> {
+        for (index, rule) in self.rules.iter().enumerate() {
+            rule.validate().map_err(|e| format!("rules[{}]: {}", index, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// 按顺序取第一条匹配的规则，没有时为默认动作
+pub fn action_for(rules: &[RemediationRule], fault_kind: &str) -> RemediationRule {
+    rules
+        .iter()
+        .find(|rule| rule.matches(fault_kind))
+        .cloned()
+        .unwrap_or_else(RemediationRule::default_action)
+}
@@ -0,0 +1,225 @@
+//! Prometheus remote_write：把 Hub 的指标定期推送到中心 Prometheus / Mimir / VictoriaMetrics
+//!
+//! 无法直接抓取 Hub 的 `/metrics` 时（跨网络、只允许出站连接）使用。`ark-hub --remote-write-config <FILE>`（YAML）：
+//! ```yaml
+//! url: https://mimir.example.com/api/v1/push
+//! interval_secs: 30            # 推送间隔
+//! bearer_token: xxx            # 或 basic_auth: {username: ark, password: xxx}
+//! headers:
+//!   X-Scope-OrgID: infra       # Mimir 多租户
+//! external_labels:
+//!   cluster: dc1               # 附加到每条时间序列
+//! retries: 3                   # 网络错误或 5xx 时的重试次数（指数退避），4xx 不重试
+//! ```
+//!
+//! 推送的是 `/metrics` 中的全部指标（含每个 job 的阻塞边数 `ark_hub_job_blocked`、每个节点的近期错误数
+//! `ark_hub_node_recent_errors`、各节点的事件数和修复动作数），编码为 protobuf `WriteRequest` 并用 snappy 压缩。
+
+use prometheus::proto::{MetricFamily, MetricType};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 单次推送的超时
+const PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BasicAuth {
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+}
+
+/// remote_write 配置
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteWriteConfig {
+    pub url: String,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default)]
+    pub bearer_token: Option<String>,
+    #[serde(default)]
+    pub basic_auth: Option<BasicAuth>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub external_labels: BTreeMap<String, String>,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+fn default_interval_secs() -> u64 {
+    30
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+impl RemoteWriteConfig {
+    pub fn load_from_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+        let config: Self = serde_yaml::from_str(&content).map_err(|e| format!("解析 {} 失败: {}", path.display(), e))?;
+        if !config.url.starts_with("http://") && !config.url.starts_with("https://") {
+            return Err(format!("{} 中的无效 remote_write 地址: {}", path.display(), config.url));
+        }
+        if config.bearer_token.is_some() && config.basic_auth.is_some() {
+            return Err(format!("{} 中 bearer_token 和 basic_auth 只能指定一个", path.display()));
+        }
+        Ok(config)
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(1))
+    }
+}
+
+// remote_write 1.0 的 protobuf 消息（prometheus/prompb/types.proto、remote.proto）
+#[derive(Clone, PartialEq, prost::Message)]
+struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    timeseries: Vec<TimeSeries>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct TimeSeries {
+    #[prost(message, repeated, tag = "1")]
+    labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    samples: Vec<Sample>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Label {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, tag = "2")]
+    value: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Sample {
+    #[prost(double, tag = "1")]
+    value: f64,
+    #[prost(int64, tag = "2")]
+    timestamp: i64,
+}
+
+/// remote_write 推送端
+pub struct RemoteWriter {
+    config: RemoteWriteConfig,
+    client: reqwest::Client,
+}
+
+impl RemoteWriter {
+    pub fn new(config: RemoteWriteConfig) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(PUSH_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self { config, client })
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.config.interval()
+    }
+
+    /// 推送当前注册的全部指标
+    pub async fn push(&self, families: &[MetricFamily]) -> Result<usize, String> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+        let request = WriteRequest { timeseries: timeseries(families, &self.config.external_labels, timestamp) };
+        let count = request.timeseries.len();
+        let body = snap::raw::Encoder::new()
+            .compress_vec(&prost::Message::encode_to_vec(&request))
+            .map_err(|e| format!("snappy 压缩失败: {}", e))?;
+
+        let mut attempt = 0;
+        loop {
+            let mut builder = self
+                .client
+                .post(&self.config.url)
+                .header("content-type", "application/x-protobuf")
+                .header("content-encoding", "snappy")
+                .header("x-prometheus-remote-write-version", "0.1.0")
+                .header("user-agent", concat!("ark-hub/", env!("CARGO_PKG_VERSION")))
+                .body(body.clone());
+            if let Some(ref token) = self.config.bearer_token {
+                builder = builder.bearer_auth(token);
+            }
+            if let Some(ref auth) = self.config.basic_auth {
+                builder = builder.basic_auth(&auth.username, Some(&auth.password));
+            }
+            for (name, value) in &self.config.headers {
+                builder = builder.header(name, value);
+            }
+            let error = match builder.send().await {
+                Ok(response) if response.status().is_success() => return Ok(count),
+                Ok(response) if response.status().is_client_error() => {
+                    let status = response.status();
+                    let text = response.text().await.unwrap_or_default();
+                    return Err(format!("HTTP {}: {}", status, text.trim()));
+                }
+                Ok(response) => format!("HTTP {}", response.status()),
+                Err(e) => e.to_string(),
+            };
+            if attempt >= self.config.retries {
+                return Err(format!("{}（已重试 {} 次）", error, attempt));
+            }
+            tokio::time::sleep(Duration::from_secs(1 << attempt.min(6))).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// 指标族转为时间序列：直方图拆为 `_bucket`（带 le）、`_sum`、`_count`，摘要拆为分位数、`_sum`、`_count`
+fn timeseries(families: &[MetricFamily], external_labels: &BTreeMap<String, String>, timestamp: i64) -> Vec<TimeSeries> {
+    let mut series = Vec::new();
+    for family in families {
+        let name = family.get_name();
+        for metric in family.get_metric() {
+            let mut labels: BTreeMap<String, String> = external_labels.clone();
+            for pair in metric.get_label() {
+                labels.insert(pair.get_name().to_string(), pair.get_value().to_string());
+            }
+            let mut push = |suffix: &str, extra: Option<(&str, String)>, value: f64| {
+                let mut labels = labels.clone();
+                labels.insert("__name__".to_string(), format!("{}{}", name, suffix));
+                if let Some((label, label_value)) = extra {
+                    labels.insert(label.to_string(), label_value);
+                }
+                series.push(TimeSeries {
+                    // BTreeMap 按名称排序，remote_write 要求标签有序
+                    labels: labels.into_iter().map(|(name, value)| Label { name, value }).collect(),
+                    samples: vec![Sample { value, timestamp }],
+                });
+            };
+            match family.get_field_type() {
+                MetricType::COUNTER => push("", None, metric.get_counter().get_value()),
+                MetricType::GAUGE => push("", None, metric.get_gauge().get_value()),
+                MetricType::UNTYPED => push("", None, metric.get_untyped().get_value()),
+                MetricType::HISTOGRAM => {
+                    let histogram = metric.get_histogram();
+                    for bucket in histogram.get_bucket() {
+                        push("_bucket", Some(("le", bucket.get_upper_bound().to_string())), bucket.get_cumulative_count() as f64);
+                    }
+                    push("_bucket", Some(("le", "+Inf".to_string())), histogram.get_sample_count() as f64);
+                    push("_sum", None, histogram.get_sample_sum());
+                    push("_count", None, histogram.get_sample_count() as f64);
+                }
+                MetricType::SUMMARY => {
+                    let summary = metric.get_summary();
+                    for quantile in summary.get_quantile() {
+                        push("", Some(("quantile", quantile.get_quantile().to_string())), quantile.get_value());
+                    }
+                    push("_sum", None, summary.get_sample_sum());
+                    push("_count", None, summary.get_sample_count() as f64);
+                }
+            }
+        }
+    }
+    series
+}
@@ -0,0 +1,72 @@
+use ark_core::graph::{Edge, EdgeType, Node, NodeKey, NodeType, StateGraph};
+use crate::scene::types::{ClusterAnalysis, ClusterSceneType};
+use std::collections::HashMap;
+
+/// 分析对象：一个 job 的 rank 及其所在节点的拓扑信息
+#[derive(Debug, Clone, Default)]
+pub struct JobScope {
+    pub job_id: String,
+    /// 仍在运行的 rank（进程节点键）
+    pub ranks: Vec<NodeKey>,
+    /// node_id → 交换机（来自节点注册标签，未配置的节点不在其中）
+    pub switches: HashMap<String, String>,
+}
+
+/// 跨节点场景分析器 trait
+#[async_trait::async_trait]
+pub trait ClusterSceneAnalyzer: Send + Sync {
+    /// 分析 job，未命中该场景时返回 None
+    async fn analyze(&self, graph: &StateGraph, scope: &JobScope) -> Option<ClusterAnalysis>;
+
+    /// 获取场景类型
+    fn scene_type(&self) -> ClusterSceneType;
+}
+
+/// rank 阻塞于的错误（error_type，没有时为错误节点 ID）
+pub fn blocking_errors(edges: &[Edge], nodes: &HashMap<String, Node>, rank: &str) -> Vec<String> {
+    edges
+        .iter()
+        .filter(|e| e.from == rank && e.edge_type == EdgeType::BlockedBy)
+        .filter_map(|e| nodes.get(&e.to))
+        .filter(|node| node.node_type == NodeType::Error)
+        .map(|node| node.metadata.get("error_type").cloned().unwrap_or_else(|| node.id.clone()))
+        .collect()
+}
+
+/// 去重后的 node_id 列表
+pub fn distinct_nodes<'a>(keys: impl IntoIterator<Item = &'a NodeKey>) -> Vec<String> {
+    let mut nodes: Vec<String> = keys
+        .into_iter()
+        .filter_map(|key| key.node_id().map(str::to_string))
+        .collect();
+    nodes.sort();
+    nodes.dedup();
+    nodes
+}
+
+/// 场景注册表
+pub struct ClusterSceneRegistry {
+    analyzers: Vec<Box<dyn ClusterSceneAnalyzer>>,
+}
+
+impl ClusterSceneRegistry {
+    pub fn new() -> Self {
+        Self {
+            analyzers: Vec::new(),
+        }
+    }
+
+    pub fn register<A: ClusterSceneAnalyzer + 'static>(&mut self, analyzer: A) {
+        self.analyzers.push(Box::new(analyzer));
+    }
+
+    pub fn all_analyzers(&self) -> &[Box<dyn ClusterSceneAnalyzer>] {
+        &self.analyzers
+    }
+}
+
+impl Default for ClusterSceneRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,98 @@
+use ark_core::graph::{EdgeType, NodeKey, StateGraph};
+use crate::scene::analyzer::{distinct_nodes, ClusterSceneAnalyzer, JobScope};
+use crate::scene::types::{ClusterAnalysis, ClusterSceneType, Severity};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 存储 IOPS 低于该值视为慢
+const SLOW_IOPS: f64 = 50.0;
+
+/// job 范围的 Checkpoint 卡住场景分析器
+/// Checkpoint 通常由所有 rank 同时写入共享存储，多数 rank 同时等待存储说明瓶颈在存储侧
+pub struct CheckpointStallAnalyzer;
+
+#[async_trait::async_trait]
+impl ClusterSceneAnalyzer for CheckpointStallAnalyzer {
+    fn scene_type(&self) -> ClusterSceneType {
+        ClusterSceneType::CheckpointStall
+    }
+
+    async fn analyze(&self, graph: &StateGraph, scope: &JobScope) -> Option<ClusterAnalysis> {
+        if scope.ranks.len() < 2 {
+            return None;
+        }
+
+        let nodes = graph.get_nodes_async().await;
+        let edges = graph.get_all_edges_async().await;
+
+        let mut stalled: Vec<&NodeKey> = Vec::new();
+        // 存储实体（去掉主机命名空间）→ 等待它的主机
+        let mut storages: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let mut slow = BTreeSet::new();
+        for rank in &scope.ranks {
+            let rank_id = rank.to_string();
+            let mut waiting = nodes
+                .get(&rank_id)
+                .and_then(|node| node.metadata.get("state"))
+                .is_some_and(|state| state.contains("checkpoint") || state.contains("saving"));
+
+            for edge in edges.iter().filter(|e| e.from == rank_id && e.edge_type == EdgeType::WaitsOn) {
+                let key = NodeKey::parse(&edge.to);
+                if !(key.entity().contains("storage") || key.entity().contains("disk")) {
+                    continue;
+                }
+                waiting = true;
+                storages
+                    .entry(key.entity().to_string())
+                    .or_default()
+                    .insert(key.node_id().unwrap_or("-").to_string());
+                let iops = nodes
+                    .get(&edge.to)
+                    .and_then(|node| node.metadata.get("iops")?.parse::<f64>().ok());
+                if let Some(iops) = iops.filter(|iops| *iops < SLOW_IOPS) {
+                    slow.insert(format!("存储 {} IOPS 过低: {:.0}", edge.to, iops));
+                }
+            }
+            if waiting {
+                stalled.push(rank);
+            }
+        }
+
+        // 至少一半的 rank 同时卡住才算 job 范围
+        if stalled.len() < 2 || stalled.len() * 2 < scope.ranks.len() {
+            return None;
+        }
+
+        let mut root_causes = vec![format!(
+            "job {} 的 {}/{} 个 rank 同时卡在 Checkpoint 保存（等待存储）",
+            scope.job_id,
+            stalled.len(),
+            scope.ranks.len()
+        )];
+        for (storage, hosts) in &storages {
+            if hosts.len() > 1 {
+                root_causes.push(format!(
+                    "{} 台主机同时等待 {}，共享存储可能过载",
+                    hosts.len(),
+                    storage
+                ));
+            }
+        }
+        root_causes.extend(slow);
+
+        let recommendations = vec![
+            "检查共享存储（NFS / Lustre / 对象存储网关）的吞吐和延迟".to_string(),
+            "错开各 rank 的 Checkpoint 写入，或只由部分 rank 写入".to_string(),
+            "考虑使用异步 Checkpoint 保存".to_string(),
+            "检查 Checkpoint 目录的磁盘空间".to_string(),
+        ];
+
+        Some(ClusterAnalysis {
+            scene: ClusterSceneType::CheckpointStall,
+            root_causes,
+            confidence: if stalled.len() == scope.ranks.len() { 0.85 } else { 0.6 },
+            recommendations,
+            nodes: distinct_nodes(stalled),
+            severity: Severity::Warning,
+        })
+    }
+}
@@ -0,0 +1,82 @@
+use ark_core::graph::{EdgeType, StateGraph};
+use crate::scene::analyzer::{blocking_errors, distinct_nodes, ClusterSceneAnalyzer, JobScope};
+use crate::scene::types::{ClusterAnalysis, ClusterSceneType, Severity};
+
+/// 低于该利用率（%）的 rank 视为空闲
+const IDLE_UTIL: f64 = 5.0;
+
+/// 集合通信卡住场景分析器
+/// 同步训练中一个 rank 出错后，其余 rank 在 all-reduce 等集合通信中空等：
+/// 单看每台主机只能看到"利用率低"，跨节点才能定位真正出错的那个 rank
+pub struct CollectiveStallAnalyzer;
+
+#[async_trait::async_trait]
+impl ClusterSceneAnalyzer for CollectiveStallAnalyzer {
+    fn scene_type(&self) -> ClusterSceneType {
+        ClusterSceneType::CollectiveStall
+    }
+
+    async fn analyze(&self, graph: &StateGraph, scope: &JobScope) -> Option<ClusterAnalysis> {
+        if scope.ranks.len() < 2 {
+            return None;
+        }
+
+        let nodes = graph.get_nodes_async().await;
+        let edges = graph.get_all_edges_async().await;
+
+        let mut failing = Vec::new();
+        let mut waiting = Vec::new();
+        for rank in &scope.ranks {
+            let rank_id = rank.to_string();
+            let errors = blocking_errors(&edges, &nodes, &rank_id);
+            if !errors.is_empty() {
+                failing.push((rank, errors));
+                continue;
+            }
+
+            // 没有错误的 rank 必须有利用率数据且全部低于 IDLE_UTIL
+            let utils: Vec<f64> = edges
+                .iter()
+                .filter(|e| e.from == rank_id && e.edge_type == EdgeType::Consumes)
+                .filter_map(|e| nodes.get(&e.to))
+                .filter_map(|node| node.metadata.get("util")?.parse::<f64>().ok())
+                .collect();
+            if utils.is_empty() || utils.iter().any(|util| *util >= IDLE_UTIL) {
+                return None;
+            }
+            waiting.push(rank);
+        }
+
+        // 出错的只能是少数 rank，全部出错是各节点各自的故障
+        if failing.is_empty() || waiting.is_empty() || failing.len() * 2 > scope.ranks.len() {
+            return None;
+        }
+
+        let mut root_causes: Vec<String> = failing
+            .iter()
+            .map(|(rank, errors)| format!("rank {} 出现错误: {}", rank, errors.join(", ")))
+            .collect();
+        root_causes.push(format!(
+            "job {} 的其余 {} 个 rank 利用率均低于 {}%，在集合通信中等待出错的 rank",
+            scope.job_id,
+            waiting.len(),
+            IDLE_UTIL
+        ));
+
+        let recommendations = vec![
+            "优先处理出错 rank 所在节点，其余节点无需单独排查".to_string(),
+            "检查出错 rank 的 GPU/NPU 状态和 NCCL/HCCL 日志".to_string(),
+            "设置 NCCL_TIMEOUT / HCCL_EXEC_TIMEOUT，避免集合通信无限期等待".to_string(),
+            "隔离出错节点后从最近的 Checkpoint 重启 job".to_string(),
+        ];
+
+        Some(ClusterAnalysis {
+            scene: ClusterSceneType::CollectiveStall,
+            root_causes,
+            confidence: if failing.len() == 1 { 0.9 } else { 0.7 },
+            recommendations,
+            nodes: distinct_nodes(failing.iter().map(|(rank, _)| *rank)),
+            severity: Severity::Critical,
+        })
+    }
+}
@@ -0,0 +1,63 @@
+//! 跨节点场景分析
+//!
+//! Agent 的 `scene` 只看单台主机上的一个进程；分布式训练中很多故障只有把同一 job 的所有 rank
+//! 放在一起才看得出来。这里沿用同样的分析器 + 注册表结构，但分析对象是 Hub 全局状态图中的一个 job：
+//! - `collective_stall`：job 的所有 rank 都空闲，只有个别 rank 出现错误（其余 rank 在集合通信中等待它）
+//! - `pfc_storm`：同一交换机下多台主机同时出现 PFC 风暴（交换机取自节点注册标签 `switch`）
+//! - `checkpoint_stall`：job 的多数 rank 同时卡在 Checkpoint 保存（等待存储）
+//!
+//! 结果随 `GET /api/v1/why` 的 `scenes` 字段返回，按置信度从高到低排列。
+
+mod types;
+mod analyzer;
+mod collective_stall;
+mod pfc_storm;
+mod checkpoint_stall;
+
+pub use types::{ClusterAnalysis, Severity};
+pub use analyzer::{ClusterSceneAnalyzer, ClusterSceneRegistry, JobScope};
+pub use collective_stall::CollectiveStallAnalyzer;
+pub use pfc_storm::{PfcStormAnalyzer, SWITCH_LABEL};
+pub use checkpoint_stall::CheckpointStallAnalyzer;
+
+use ark_core::graph::StateGraph;
+
+/// 跨节点场景识别器
+pub struct ClusterSceneIdentifier {
+    registry: ClusterSceneRegistry,
+}
+
+impl ClusterSceneIdentifier {
+    pub fn new() -> Self {
+        let mut registry = ClusterSceneRegistry::new();
+
+        // 注册所有跨节点场景分析器
+        registry.register(CollectiveStallAnalyzer);
+        registry.register(PfcStormAnalyzer);
+        registry.register(CheckpointStallAnalyzer);
+
+        Self { registry }
+    }
+
+    /// 对 job 运行所有分析器，返回命中的场景（按置信度从高到低）
+    pub async fn analyze(&self, graph: &StateGraph, scope: &JobScope) -> Vec<ClusterAnalysis> {
+        let mut results = Vec::new();
+        for analyzer in self.registry.all_analyzers() {
+            if let Some(result) = analyzer.analyze(graph, scope).await {
+                results.push(result);
+            }
+        }
+        results.sort_by(|a, b| {
+            b.confidence
+                .partial_cmp(&a.confidence)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        results
+    }
+}
+
+impl Default for ClusterSceneIdentifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,105 @@
+use ark_core::graph::{NodeType, StateGraph};
+use crate::scene::analyzer::{distinct_nodes, ClusterSceneAnalyzer, JobScope};
+use crate::scene::types::{ClusterAnalysis, ClusterSceneType, Severity};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 节点所在交换机的注册标签（`ark run --node-label switch=leaf-3`）
+pub const SWITCH_LABEL: &str = "switch";
+
+/// PFC 风暴场景分析器
+/// 多台主机同时出现 PFC 风暴时，问题通常在它们共同的交换机上，而不是各自的网卡
+pub struct PfcStormAnalyzer;
+
+#[async_trait::async_trait]
+impl ClusterSceneAnalyzer for PfcStormAnalyzer {
+    fn scene_type(&self) -> ClusterSceneType {
+        ClusterSceneType::PfcStorm
+    }
+
+    async fn analyze(&self, graph: &StateGraph, scope: &JobScope) -> Option<ClusterAnalysis> {
+        // 出现 PFC 错误的主机（不限于该 job：同一交换机下其他 job 的主机也是证据）
+        let storm_hosts: BTreeSet<String> = graph
+            .get_nodes_async()
+            .await
+            .into_values()
+            .filter(|node| node.node_type == NodeType::Error)
+            .filter(|node| {
+                node.metadata
+                    .get("error_type")
+                    .is_some_and(|error_type| error_type.to_lowercase().contains("pfc"))
+            })
+            .filter_map(|node| node.key().node_id().map(str::to_string))
+            .collect();
+        let job_hosts = distinct_nodes(&scope.ranks);
+        if !job_hosts.iter().any(|host| storm_hosts.contains(host)) {
+            return None;
+        }
+
+        // 按交换机分组，只看 job 所在的交换机
+        let mut by_switch: BTreeMap<&str, Vec<&String>> = BTreeMap::new();
+        for host in &storm_hosts {
+            if let Some(switch) = scope.switches.get(host) {
+                by_switch.entry(switch.as_str()).or_default().push(host);
+            }
+        }
+        let job_switches: BTreeSet<&str> = job_hosts
+            .iter()
+            .filter_map(|host| scope.switches.get(host).map(String::as_str))
+            .collect();
+
+        let mut root_causes = Vec::new();
+        let mut nodes = Vec::new();
+        for (switch, hosts) in &by_switch {
+            if hosts.len() < 2 || !job_switches.contains(switch) {
+                continue;
+            }
+            let names: Vec<&str> = hosts.iter().map(|host| host.as_str()).collect();
+            root_causes.push(format!(
+                "交换机 {} 下 {} 台主机同时出现 PFC 风暴: {}",
+                switch,
+                hosts.len(),
+                names.join(", ")
+            ));
+            nodes.extend(hosts.iter().map(|host| host.to_string()));
+        }
+
+        // 未配置交换机标签时，只能从 job 自身的主机推断
+        let confidence = if !root_causes.is_empty() {
+            0.85
+        } else {
+            let unlabeled: Vec<&String> = job_hosts
+                .iter()
+                .filter(|host| storm_hosts.contains(*host) && !scope.switches.contains_key(*host))
+                .collect();
+            if unlabeled.len() < 2 {
+                return None;
+            }
+            let names: Vec<&str> = unlabeled.iter().map(|host| host.as_str()).collect();
+            root_causes.push(format!(
+                "job {} 的 {} 台主机同时出现 PFC 风暴: {}（节点未配置 {} 标签，无法确认是否同一交换机）",
+                scope.job_id,
+                unlabeled.len(),
+                names.join(", "),
+                SWITCH_LABEL
+            ));
+            nodes.extend(unlabeled.into_iter().cloned());
+            0.5
+        };
+
+        let recommendations = vec![
+            "检查交换机 PFC 配置和各端口的 pause 帧计数".to_string(),
+            "排查交换机下是否有网卡持续发送 pause 帧（PFC 死锁或慢接收端）".to_string(),
+            "检查 ECN / DCQCN 拥塞控制参数".to_string(),
+            "必要时将 job 调度到其他交换机下的节点".to_string(),
+        ];
+
+        Some(ClusterAnalysis {
+            scene: ClusterSceneType::PfcStorm,
+            root_causes,
+            confidence,
+            recommendations,
+            nodes,
+            severity: Severity::Critical,
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use serde::Serialize;
+
+/// 跨节点场景类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClusterSceneType {
+    CollectiveStall,     // 集合通信卡住：个别 rank 出错，其余 rank 空等
+    PfcStorm,            // 同一交换机下多台主机 PFC 风暴
+    CheckpointStall,     // job 范围的 Checkpoint 卡住
+}
+
+impl ClusterSceneType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClusterSceneType::CollectiveStall => "collective_stall",
+            ClusterSceneType::PfcStorm => "pfc_storm",
+            ClusterSceneType::CheckpointStall => "checkpoint_stall",
+        }
+    }
+}
+
+/// 跨节点分析结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterAnalysis {
+    pub scene: ClusterSceneType,
+    pub root_causes: Vec<String>,
+    pub confidence: f64,
+    pub recommendations: Vec<String>,
+    /// 涉及的节点（node_id）
+    pub nodes: Vec<String>,
+    /// 严重程度
+    pub severity: Severity,
+}
+
+/// 严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Critical,  // 严重：整个 job 停滞
+    Warning,   // 警告：性能下降、可能停滞
+}
@@ -0,0 +1,50 @@
+//! 优雅退出
+//!
+//! 收到 SIGTERM（或 Ctrl-C）后 Hub 停止接受新的 WebSocket 连接，向已连接的 Agent 广播 `{"shutdown": true}`；
+//! Agent 把已发送但未确认的消息排回本地缓冲后断开，按退避重连，新的 Hub 实例起来后补发。所有 Agent 断开或超过
+//! `--shutdown-timeout-secs`（默认 15）后关闭剩余连接，等待存储写完待写入的记录并补写一份快照，然后退出。
+
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+/// 等待 SIGTERM 或 Ctrl-C
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = terminate.recv() => {}
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+                return;
+            }
+            Err(e) => eprintln!("[hub] 注册 SIGTERM 处理失败: {}，仅响应 Ctrl-C", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// 退出通知
+pub fn shutdown_message() -> Message {
+    Message::Text(serde_json::json!({ "shutdown": true }).to_string())
+}
+
+/// 通知所有节点并等待其断开，超时后关闭剩余连接，返回被强制关闭的连接数
+pub async fn drain(connections: &DashMap<String, mpsc::UnboundedSender<Message>>, timeout: Duration) -> usize {
+    for sender in connections.iter() {
+        let _ = sender.send(shutdown_message());
+    }
+    let deadline = Instant::now() + timeout;
+    while !connections.is_empty() && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let remaining = connections.len();
+    for sender in connections.iter() {
+        let _ = sender.send(Message::Close(None));
+    }
+    remaining
+}
@@ -0,0 +1,133 @@
+//! Hub 状态持久化（`--features storage`，sqlx：SQLite 或 PostgreSQL）
+//!
+//! 未启用时 Hub 的全局状态图和收到的事件只在内存中，重启即丢失。启用后（`ark-hub --storage <URL>`）：
+//! - 收到的每个事件（已注入 node_id）由后台任务批量写入 `hub_events`
+//! - 连接过的节点（node_id、地址、首次和最近在线时间）写入 `hub_nodes`
+//! - 每隔 `--snapshot-interval-secs` 把全局状态图的 JSON 导出写入 `hub_snapshots`，保留最近若干份，
+//!   早于最旧快照的事件随之清理
+//!
+//! - 下发命令及其执行状态写入 `hub_commands`（见 `commands`），保留 7 天
+//!
+//! 启动时从最新快照恢复状态图，再按接收顺序重放快照之后收到的事件。Hub 优雅退出时（见 `shutdown`）
+//! 等待待写入的记录写完，并补写一份快照。
+
+#[cfg(feature = "storage")]
+mod sql;
+
+use crate::commands::CommandRecord;
+use ark_core::event::Event;
+use ark_core::graph::{GraphConfig, StateGraph};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, oneshot};
+
+/// 待写入存储的记录
+#[cfg_attr(not(feature = "storage"), allow(dead_code))]
+enum Record {
+    /// 收到的事件（JSON）
+    Event { received_at: u64, node_id: String, event: String },
+    /// 节点连接或更新 node_id
+    Node { node_id: String, addr: String, seen_at: u64 },
+    /// 下发命令的最新状态（JSON）
+    Command { command_id: String, node_id: String, updated_at: u64, record: String },
+    /// 之前的记录写完后写入一份快照，再通知调用方
+    Flush(oneshot::Sender<()>),
+}
+
+/// 写入存储的入口：未配置存储时所有操作都是空操作
+#[derive(Clone, Default)]
+pub struct StorageHandle {
+    tx: Option<mpsc::UnboundedSender<Record>>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+impl StorageHandle {
+    /// 记录一个已写入状态图的事件
+    pub fn record_event(&self, event: &Event) {
+        let Some(ref tx) = self.tx else {
+            return;
+        };
+        match serde_json::to_string(event) {
+            Ok(json) => {
+                let _ = tx.send(Record::Event {
+                    received_at: now_ms(),
+                    node_id: event.node_id.clone().unwrap_or_default(),
+                    event: json,
+                });
+            }
+            Err(e) => eprintln!("[hub-storage] 序列化事件失败: {}", e),
+        }
+    }
+
+    /// 记录下发命令的最新状态
+    pub fn record_command(&self, record: &CommandRecord) {
+        let Some(ref tx) = self.tx else {
+            return;
+        };
+        match serde_json::to_string(record) {
+            Ok(json) => {
+                let _ = tx.send(Record::Command {
+                    command_id: record.command_id.clone(),
+                    node_id: record.node_id.clone(),
+                    updated_at: now_ms(),
+                    record: json,
+                });
+            }
+            Err(e) => eprintln!("[hub-storage] 序列化命令失败: {}", e),
+        }
+    }
+
+    /// 等待已提交的记录全部写入并写入一份最新快照（未配置存储时立即返回）
+    pub async fn flush(&self) {
+        let Some(ref tx) = self.tx else {
+            return;
+        };
+        let (done, wait) = oneshot::channel();
+        if tx.send(Record::Flush(done)).is_ok() {
+            let _ = wait.await;
+        }
+    }
+
+    /// 记录节点连接（或连接上的 node_id 更新）
+    pub fn record_node(&self, node_id: &str, addr: std::net::SocketAddr) {
+        if let Some(ref tx) = self.tx {
+            let _ = tx.send(Record::Node {
+                node_id: node_id.to_string(),
+                addr: addr.to_string(),
+                seen_at: now_ms(),
+            });
+        }
+    }
+}
+
+/// 创建全局状态图：配置了存储时从存储恢复（连同下发命令的状态），并启动事件写入和定期快照任务
+pub async fn open_graph(
+    url: Option<&str>,
+    config: GraphConfig,
+    snapshot_interval: Duration,
+) -> Result<(Arc<StateGraph>, StorageHandle, Vec<CommandRecord>), String> {
+    let Some(url) = url else {
+        return Ok((Arc::new(StateGraph::with_config(config)), StorageHandle::default(), Vec::new()));
+    };
+
+    #[cfg(feature = "storage")]
+    {
+        let storage = sql::HubStorage::connect(url).await?;
+        let graph = Arc::new(storage.restore(config).await?);
+        let commands = storage.load_commands().await?;
+        storage.spawn_snapshots(Arc::clone(&graph), snapshot_interval);
+        Ok((Arc::clone(&graph), storage.spawn_writer(graph), commands))
+    }
+
+    #[cfg(not(feature = "storage"))]
+    {
+        let _ = (url, config, snapshot_interval);
+        Err("当前构建未启用状态持久化，请以 --features storage 重新编译 ark-hub".to_string())
+    }
+}
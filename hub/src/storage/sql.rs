@@ -0,0 +1,280 @@
+//! sqlx 后端：同一套 SQL 同时用于 SQLite 和 PostgreSQL（`sqlx::Any`，按 URL 选择驱动）
+
+use super::{now_ms, Record, StorageHandle};
+use crate::commands::{CommandRecord, MAX_COMMANDS};
+use ark_core::event::Event;
+use ark_core::export::ExportFormat;
+use ark_core::graph::{GraphConfig, StateGraph};
+use futures_util::TryStreamExt;
+use sqlx::any::AnyPoolOptions;
+use sqlx::{AnyPool, Row};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// 保留的快照份数
+const KEEP_SNAPSHOTS: i64 = 10;
+
+/// 单次事务写入的最大记录数
+const WRITE_BATCH: usize = 500;
+
+/// 下发命令的保留时间
+const COMMAND_RETENTION_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS hub_events (
+        received_at BIGINT NOT NULL,
+        node_id TEXT NOT NULL,
+        event TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS hub_events_received_at ON hub_events (received_at)",
+    "CREATE TABLE IF NOT EXISTS hub_nodes (
+        node_id TEXT PRIMARY KEY,
+        addr TEXT NOT NULL,
+        first_seen BIGINT NOT NULL,
+        last_seen BIGINT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS hub_snapshots (
+        taken_at BIGINT PRIMARY KEY,
+        graph TEXT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS hub_commands (
+        command_id TEXT PRIMARY KEY,
+        node_id TEXT NOT NULL,
+        updated_at BIGINT NOT NULL,
+        record TEXT NOT NULL
+    )",
+];
+
+const UPSERT_NODE: &str = "INSERT INTO hub_nodes (node_id, addr, first_seen, last_seen) VALUES ($1, $2, $3, $3)
+    ON CONFLICT (node_id) DO UPDATE SET addr = excluded.addr, last_seen = excluded.last_seen";
+
+const UPSERT_COMMAND: &str = "INSERT INTO hub_commands (command_id, node_id, updated_at, record) VALUES ($1, $2, $3, $4)
+    ON CONFLICT (command_id) DO UPDATE SET updated_at = excluded.updated_at, record = excluded.record";
+
+const TOUCH_NODE: &str = "UPDATE hub_nodes SET last_seen = $2 WHERE node_id = $1 AND last_seen < $2";
+
+pub(super) struct HubStorage {
+    pool: AnyPool,
+}
+
+impl HubStorage {
+    /// 连接数据库并建表（如 `sqlite:///var/lib/ark/hub.db?mode=rwc`、`postgres://user@host/ark`）
+    pub(super) async fn connect(url: &str) -> Result<Self, String> {
+        sqlx::any::install_default_drivers();
+        let pool = AnyPoolOptions::new()
+            .max_connections(4)
+            .connect(url)
+            .await
+            .map_err(|e| format!("连接存储 {} 失败: {}", url, e))?;
+        for statement in SCHEMA {
+            sqlx::query(statement)
+                .execute(&pool)
+                .await
+                .map_err(|e| format!("初始化存储表失败: {}", e))?;
+        }
+        Ok(Self { pool })
+    }
+
+    /// 从最新快照恢复状态图，再重放快照之后收到的事件
+    pub(super) async fn restore(&self, config: GraphConfig) -> Result<StateGraph, String> {
+        let snapshot = sqlx::query("SELECT taken_at, graph FROM hub_snapshots ORDER BY taken_at DESC LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("读取状态图快照失败: {}", e))?;
+        let (graph, since) = match snapshot {
+            Some(row) => {
+                let taken_at: i64 = row.try_get(0).map_err(|e| e.to_string())?;
+                let content: String = row.try_get(1).map_err(|e| e.to_string())?;
+                (StateGraph::import_json_with_config(&content, config)?, taken_at)
+            }
+            None => (StateGraph::with_config(config), -1),
+        };
+
+        let mut replayed = 0usize;
+        let mut events = sqlx::query("SELECT event FROM hub_events WHERE received_at > $1 ORDER BY received_at")
+            .bind(since)
+            .fetch(&self.pool);
+        while let Some(row) = events.try_next().await.map_err(|e| format!("读取事件失败: {}", e))? {
+            let json: String = row.try_get(0).map_err(|e| e.to_string())?;
+            let event = match serde_json::from_str::<Event>(&json) {
+                Ok(event) => event,
+                Err(e) => {
+                    eprintln!("[hub-storage] 跳过无法解析的事件: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = graph.process_event(&event).await {
+                eprintln!("[hub-storage] 重放事件失败: {}", e);
+            }
+            replayed += 1;
+        }
+
+        let nodes: i64 = sqlx::query("SELECT COUNT(*) FROM hub_nodes")
+            .fetch_one(&self.pool)
+            .await
+            .and_then(|row| row.try_get(0))
+            .map_err(|e| format!("读取节点清单失败: {}", e))?;
+        println!(
+            "[hub-storage] 已恢复状态图：{} 个节点、{} 条边（快照{}，重放 {} 个事件；已知 Agent 节点 {} 个）",
+            graph.node_count().await,
+            graph.edge_count().await,
+            if since < 0 { "无" } else { "已载入" },
+            replayed,
+            nodes
+        );
+        Ok(graph)
+    }
+
+    /// 载入最近下发的命令（最多 MAX_COMMANDS 条）
+    pub(super) async fn load_commands(&self) -> Result<Vec<CommandRecord>, String> {
+        let rows = sqlx::query("SELECT record FROM hub_commands ORDER BY updated_at DESC LIMIT $1")
+            .bind(MAX_COMMANDS as i64)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| format!("读取下发命令失败: {}", e))?;
+        let mut commands = Vec::with_capacity(rows.len());
+        for row in rows {
+            let json: String = row.try_get(0).map_err(|e| e.to_string())?;
+            match serde_json::from_str::<CommandRecord>(&json) {
+                Ok(record) => commands.push(record),
+                Err(e) => eprintln!("[hub-storage] 跳过无法解析的命令记录: {}", e),
+            }
+        }
+        Ok(commands)
+    }
+
+    /// 启动后台写入任务，返回写入入口（flush 时为 graph 写入快照）
+    pub(super) fn spawn_writer(&self, graph: Arc<StateGraph>) -> StorageHandle {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let pool = self.pool.clone();
+        tokio::spawn(async move {
+            while let Some(first) = rx.recv().await {
+                // 攒批，遇到 Flush 时先写完之前的记录
+                let mut batch = Vec::new();
+                let mut flush = None;
+                let mut next = Some(first);
+                while let Some(record) = next.take() {
+                    match record {
+                        Record::Flush(done) => {
+                            flush = Some(done);
+                            break;
+                        }
+                        record => batch.push(record),
+                    }
+                    if batch.len() < WRITE_BATCH {
+                        next = rx.try_recv().ok();
+                    }
+                }
+                if !batch.is_empty() {
+                    if let Err(e) = write_batch(&pool, batch).await {
+                        eprintln!("[hub-storage] 写入存储失败: {}", e);
+                    }
+                }
+                if let Some(done) = flush {
+                    if let Err(e) = write_snapshot(&pool, &graph).await {
+                        eprintln!("[hub-storage] 写入状态图快照失败: {}", e);
+                    }
+                    let _ = done.send(());
+                }
+            }
+        });
+        StorageHandle { tx: Some(tx) }
+    }
+
+    /// 启动定期快照任务
+    pub(super) fn spawn_snapshots(&self, graph: Arc<StateGraph>, interval: Duration) {
+        let pool = self.pool.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // 第一次 tick 立即触发，跳过（刚恢复的状态已在存储中）
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = write_snapshot(&pool, &graph).await {
+                    eprintln!("[hub-storage] 写入状态图快照失败: {}", e);
+                }
+            }
+        });
+    }
+}
+
+/// 在一个事务中写入一批记录；事件同时刷新来源节点的最近在线时间
+async fn write_batch(pool: &AnyPool, batch: Vec<Record>) -> Result<(), sqlx::Error> {
+    let mut last_seen: HashMap<String, u64> = HashMap::new();
+    let mut tx = pool.begin().await?;
+    for record in batch {
+        match record {
+            Record::Event { received_at, node_id, event } => {
+                sqlx::query("INSERT INTO hub_events (received_at, node_id, event) VALUES ($1, $2, $3)")
+                    .bind(received_at as i64)
+                    .bind(node_id.as_str())
+                    .bind(event)
+                    .execute(&mut *tx)
+                    .await?;
+                last_seen.insert(node_id, received_at);
+            }
+            Record::Command { command_id, node_id, updated_at, record } => {
+                sqlx::query(UPSERT_COMMAND)
+                    .bind(command_id)
+                    .bind(node_id)
+                    .bind(updated_at as i64)
+                    .bind(record)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            Record::Node { node_id, addr, seen_at } => {
+                sqlx::query(UPSERT_NODE)
+                    .bind(node_id)
+                    .bind(addr)
+                    .bind(seen_at as i64)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            // 攒批时已拆出
+            Record::Flush(_) => {}
+        }
+    }
+    for (node_id, seen_at) in last_seen {
+        sqlx::query(TOUCH_NODE)
+            .bind(node_id)
+            .bind(seen_at as i64)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await
+}
+
+/// 写入一份快照，只保留最近 KEEP_SNAPSHOTS 份，并清理早于最旧快照的事件（恢复时不再需要）和过期的下发命令
+async fn write_snapshot(pool: &AnyPool, graph: &StateGraph) -> Result<(), sqlx::Error> {
+    let content = graph.export(ExportFormat::Json).await;
+    let mut tx = pool.begin().await?;
+    sqlx::query("INSERT INTO hub_snapshots (taken_at, graph) VALUES ($1, $2)")
+        .bind(now_ms() as i64)
+        .bind(content)
+        .execute(&mut *tx)
+        .await?;
+    let oldest: Option<i64> = sqlx::query(
+        "SELECT MIN(taken_at) FROM (SELECT taken_at FROM hub_snapshots ORDER BY taken_at DESC LIMIT $1) AS recent",
+    )
+    .bind(KEEP_SNAPSHOTS)
+    .fetch_one(&mut *tx)
+    .await?
+    .try_get(0)?;
+    if let Some(oldest) = oldest {
+        sqlx::query("DELETE FROM hub_snapshots WHERE taken_at < $1")
+            .bind(oldest)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM hub_events WHERE received_at <= $1")
+            .bind(oldest)
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("DELETE FROM hub_commands WHERE updated_at < $1")
+        .bind(now_ms().saturating_sub(COMMAND_RETENTION_MS) as i64)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
@@ -0,0 +1,96 @@
+//! OpenTelemetry trace 导出
+//!
+//! 以 `--features otel` 编译并设置 `OTEL_EXPORTER_OTLP_ENDPOINT` 时，Hub 把 span 以 OTLP/HTTP 导出：
+//! 每个收到的事件一个 `event.ingest`（挂在 Agent 的同名 span 之下），下含 `graph.update` 和触发的 `k8s.action`；
+//! `GET /api/v1/why` 为 `diag.cluster`，`POST /api/v1/fix` 为 `fix.issue`，下发的命令携带 `traceparent`，
+//! Agent 执行命令的 `hub.fix` span 接在其下。转发到上级 Hub 的事件同样携带本级的 `traceparent`。
+//! 采样率等按 OTel 标准环境变量配置。
+
+use tracing::Span;
+
+/// 导出器句柄，drop 时刷新尚未导出的 span
+pub struct Telemetry {
+    #[cfg(feature = "otel")]
+    provider: opentelemetry_sdk::trace::SdkTracerProvider,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("[otel] 刷新 span 失败: {}", e);
+        }
+    }
+}
+
+/// 设置了 `OTEL_EXPORTER_OTLP_ENDPOINT` 时初始化 trace 导出
+#[cfg(feature = "otel")]
+pub fn init(service: &'static str) -> Result<Option<Telemetry>, String> {
+    use opentelemetry::trace::TracerProvider as _;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    let Ok(endpoint) = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") else {
+        return Ok(None);
+    };
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .build()
+        .map_err(|e| format!("创建 OTLP 导出器失败: {}", e))?;
+    let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(opentelemetry_sdk::Resource::builder().with_service_name(service).build())
+        .build();
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer(service)))
+        .try_init()
+        .map_err(|e| format!("初始化 tracing 失败: {}", e))?;
+    println!("🔭 OpenTelemetry trace 导出到 {}", endpoint);
+    Ok(Some(Telemetry { provider }))
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn init(_service: &'static str) -> Result<Option<Telemetry>, String> {
+    if std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_some() {
+        eprintln!("[hub] 警告：未启用 otel feature（cargo build --features otel），忽略 OTEL_EXPORTER_OTLP_ENDPOINT");
+    }
+    Ok(None)
+}
+
+/// span 的 W3C traceparent（span 未被导出时为 None）
+#[cfg(feature = "otel")]
+pub fn traceparent(span: &Span) -> Option<String> {
+    use opentelemetry::propagation::TextMapPropagator;
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let context = span.context();
+    if !context.span().span_context().is_valid() {
+        return None;
+    }
+    let mut carrier = std::collections::HashMap::new();
+    opentelemetry_sdk::propagation::TraceContextPropagator::new().inject_context(&context, &mut carrier);
+    carrier.remove("traceparent")
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn traceparent(_span: &Span) -> Option<String> {
+    None
+}
+
+/// 把 span 挂到对端传来的 traceparent 之下
+#[cfg(feature = "otel")]
+pub fn set_parent(span: &Span, traceparent: Option<&str>) {
+    use opentelemetry::propagation::TextMapPropagator;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let Some(traceparent) = traceparent else {
+        return;
+    };
+    let carrier = std::collections::HashMap::from([("traceparent".to_string(), traceparent.to_string())]);
+    let context = opentelemetry_sdk::propagation::TraceContextPropagator::new().extract(&carrier);
+    let _ = span.set_parent(context);
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn set_parent(_span: &Span, _traceparent: Option<&str>) {}
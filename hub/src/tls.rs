@@ -0,0 +1,101 @@
+//! Hub 的 TLS：WebSocket 监听（wss://）和 HTTP API（https://）共用同一份证书
+//!
+//! 事件流中含有主机名、PID 和 job 名，跨机房传输时应启用 TLS。配置 `--tls-client-ca` 时
+//! WebSocket 监听要求 Agent 出示由该 CA 签发的客户端证书（Agent 以 `--hub-cert/--hub-key` 配置）；
+//! HTTP API 只做服务端认证，供运维和 CLI 直接访问。
+//!
+//! 作为下级 Hub 连接 wss:// 上级 Hub 时（见 `federation`），默认用系统内置的根证书校验，`--upstream-ca` 指定私有 CA。
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::Connector;
+
+/// 证书文件（PEM）
+#[derive(Debug, Clone)]
+pub struct TlsFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    /// 校验 Agent 客户端证书的 CA
+    pub client_ca: Option<PathBuf>,
+}
+
+impl TlsFiles {
+    /// 按命令行参数组合：证书和私钥须同时指定，客户端 CA 只能在启用 TLS 时指定
+    pub fn from_args(
+        cert: Option<PathBuf>,
+        key: Option<PathBuf>,
+        client_ca: Option<PathBuf>,
+    ) -> Result<Option<Self>, String> {
+        match (cert, key) {
+            (Some(cert), Some(key)) => Ok(Some(Self { cert, key, client_ca })),
+            (None, None) if client_ca.is_some() => Err("--tls-client-ca 需要同时指定 --tls-cert 和 --tls-key".to_string()),
+            (None, None) => Ok(None),
+            _ => Err("--tls-cert 和 --tls-key 须同时指定".to_string()),
+        }
+    }
+
+    /// WebSocket 监听的 TLS 握手器（启动时构建，证书有误时直接报错退出）
+    pub fn acceptor(&self) -> Result<TlsAcceptor, String> {
+        let certs = load_certs(&self.cert)?;
+        let key = load_key(&self.key)?;
+        let builder = match self.client_ca {
+            Some(ref path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in load_certs(path)? {
+                    roots
+                        .add(cert)
+                        .map_err(|e| format!("无效的客户端 CA 证书 {}: {}", path.display(), e))?;
+                }
+                let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+                    .build()
+                    .map_err(|e| format!("无效的客户端 CA 证书 {}: {}", path.display(), e))?;
+                ServerConfig::builder().with_client_cert_verifier(verifier)
+            }
+            None => ServerConfig::builder().with_no_client_auth(),
+        };
+        let config = builder
+            .with_single_cert(certs, key)
+            .map_err(|e| format!("证书 {} 与私钥 {} 不匹配: {}", self.cert.display(), self.key.display(), e))?;
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+/// 用私有 CA 校验上级 Hub 证书的 TLS 连接器
+pub fn upstream_connector(ca: &Path) -> Result<Connector, String> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(ca)? {
+        roots
+            .add(cert)
+            .map_err(|e| format!("无效的上级 Hub CA 证书 {}: {}", ca.display(), e))?;
+    }
+    let config = ClientConfig::builder().with_root_certificates(roots).with_no_client_auth();
+    Ok(Connector::Rustls(Arc::new(config)))
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, String> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| format!("读取 {} 失败: {}", path.display(), e))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("解析证书 {} 失败: {}", path.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("{} 中没有 PEM 证书", path.display()));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>, String> {
+    rustls_pemfile::private_key(&mut open(path)?)
+        .map_err(|e| format!("解析私钥 {} 失败: {}", path.display(), e))?
+        .ok_or_else(|| format!("{} 中没有 PEM 私钥", path.display()))
+}